@@ -0,0 +1,11 @@
+//! ビルドスクリプト
+//!
+//! ビルド対象のターゲットトリプル（`x86_64-unknown-linux-gnu` など）を
+//! `RUST_TOAST_TARGET` としてコンパイル時の環境変数に渡します（`rust-toast targets` で表示）。
+//! クロスコンパイルでは、ビルドしたマシンではなく実行するマシンのトリプルになります。
+
+fn main() {
+    let target = std::env::var("TARGET").unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=RUST_TOAST_TARGET={target}");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -6,11 +6,44 @@
 //! - `clap` の derive API（`#[derive(Parser)]`）
 //! - `ValueEnum` による型安全な選択肢
 //! - CLI 引数からライブラリ型への変換
+//! - `Subcommand` によるサブコマンドの定義
 
-use clap::{Parser, ValueEnum};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
-use crate::notifier::{NotificationBuilder, UrgencyLevel};
-use crate::platform::Platform;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use regex::Regex;
+
+use crate::clock;
+use crate::config::{self, HostnamePrefix};
+use crate::doctor;
+use crate::error::{NotificationError, Result};
+use crate::exec;
+use crate::manager;
+use crate::notifier::{
+    self, Action, Backend, CustomCommandNotifier, Interaction, NotificationBuilder, Notifier,
+    Overflow, RenderedDelivery, Scenario, UrgencyLevel,
+};
+use crate::pipe;
+use crate::platform::{self, Platform};
+use crate::recurring::{self, Cron, Recurrence};
+use crate::redact;
+use crate::rules;
+use crate::schedule::{self, When};
+use crate::sender::SenderInfo;
+use crate::setup;
+use crate::statusbar;
+use crate::store;
+use crate::targets;
+use crate::template;
+use crate::text;
+use crate::trace::TraceContext;
+use crate::watch::Watch;
+
+/// `--sound` も設定ファイルの `sound` も無い時の通知音（macOS の osascript はこれが無いと鳴らない）
+const DEFAULT_SOUND: &str = "default";
 
 // ============================================================
 // CLI 引数の定義
@@ -28,41 +61,536 @@ use crate::platform::Platform;
 #[derive(Parser, Debug)]
 #[command(name = "rust-toast")]
 #[command(author, version, about = "Cross-platform toast notification tool")]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct Args {
-    /// Notification title (通知のタイトル)
-    #[arg(short, long, default_value = "Notification")]
-    pub title: String,
+    /// Subcommand; sends a notification when omitted (サブコマンド、省略時は通知を送信)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Also show debug logs such as the selected backend and command timings (デバッグ用のログも表示)
+    #[arg(long, global = true)]
+    pub verbose: bool,
+
+    // 通知の引数を flatten で取り込むため、サブコマンドを省略した `rust-toast -m ...` は
+    // `rust-toast send -m ...` と同じ意味になる（`--help` に出ないよう `//` コメント）
+    #[command(flatten)]
+    pub send: SendArgs,
+}
+
+/// 通知を送る引数（`rust-toast send` と、サブコマンドを省略した場合）
+///
+/// # 学習ポイント
+/// - `#[derive(clap::Args)]` で引数のまとまりを作り、`flatten` とサブコマンドの両方で使う
+#[derive(clap::Args, Debug)]
+pub struct SendArgs {
+    /// Notification title; defaults to --title-template (通知のタイトル)
+    #[arg(short, long)]
+    pub title: Option<String>,
+
+    /// Title used when --title is omitted; {hostname} is replaced (タイトル未指定時のテンプレート)
+    #[arg(long, value_name = "TEMPLATE")]
+    pub title_template: Option<String>,
+
+    /// Prefix the title with this machine's hostname (タイトルにホスト名を付ける)
+    #[arg(long, value_enum)]
+    pub hostname_prefix: Option<CliHostnamePrefix>,
+
+    /// Notification message, taken as is (use --stdin to read it from stdin) (通知のメッセージ) - Required
+    ///
+    /// 他のサブコマンド使用時は不要なので `Option` にし、`required_unless_present` で必須にする
+    #[arg(short, long, required_unless_present_any = ["stdin", "json"])]
+    pub message: Option<String>,
+
+    /// Read the message from stdin, e.g. `make 2>&1 | rust-toast --stdin -t Done`; colors are stripped and only the last 4000 characters are kept (標準入力から本文を読む)
+    #[arg(long, conflicts_with_all = ["message", "at", "after"])]
+    pub stdin: bool,
+
+    /// Read the notification, or an array of them, as JSON from FILE or stdin (-); keys as in --backend stdout, other options override them (JSON で通知を読む)
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "-", conflicts_with_all = ["stdin", "at", "after"])]
+    pub json: Option<PathBuf>,
 
-    /// Notification message (通知のメッセージ) - Required
+    /// Render the message as basic markdown (メッセージを Markdown として表示)
+    #[arg(long)]
+    pub markdown: bool,
+
+    /// Timeout like 5s, 2m, 500ms or never; a bare number is milliseconds (タイムアウト時間)
+    ///
+    /// 省略時は緊急度ごとのデフォルト（low: 3s, normal: 5s, critical: never）
+    #[arg(short = 'T', long, value_parser = parse_timeout)]
+    pub timeout: Option<Duration>,
+
+    /// Icon name (Linux) or image path (アイコン名またはパス、名前は Linux のみ)
+    ///
+    /// 省略時は Linux では dialog-information、他はアプリのアイコン
     #[arg(short, long)]
-    pub message: String,
+    pub icon: Option<String>,
 
-    /// Timeout in milliseconds, 0 = no timeout (タイムアウト時間)
-    #[arg(short = 'T', long, default_value = "5000")]
-    pub timeout: u32,
+    /// Notification category such as email.arrived (通知の種類、freedesktop の category)
+    #[arg(long, value_name = "CATEGORY")]
+    pub category: Option<String>,
 
-    /// Icon name or path (アイコン名またはパス、Linux only)
-    #[arg(short, long, default_value = "dialog-information")]
-    pub icon: String,
+    /// App name: D-Bus app_name, Windows AppUserModelID or macOS bundle ID (送信元のアプリ名)
+    #[arg(long, value_name = "NAME")]
+    pub app_name: Option<String>,
+
+    /// Topic such as ci/build, matched against configured topic rules (トピック)
+    #[arg(long, value_name = "TOPIC")]
+    pub topic: Option<String>,
+
+    /// Image shown in the notification body (通知に表示する画像のパス)
+    #[arg(long, value_name = "PATH")]
+    pub image: Option<PathBuf>,
+
+    /// Urgency level; normal unless set here, in --json or in the profile (緊急度レベル)
+    ///
+    /// 指定した時だけ `--json` やプロファイルの値を上書きするため、既定値は持たせない
+    #[arg(short, long, value_enum)]
+    pub urgency: Option<CliUrgencyLevel>,
 
-    /// Urgency level (緊急度レベル)
-    #[arg(short, long, default_value = "normal", value_enum)]
-    pub urgency: CliUrgencyLevel,
+    /// Priority 0-10 for routing; defaults from the urgency (優先度)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=10))]
+    pub priority: Option<u8>,
 
-    /// Subtitle (サブタイトル、macOS only)
-    #[arg(short, long, default_value = "")]
-    pub subtitle: String,
+    /// Subtitle; macOS, and the second line on Windows (サブタイトル)
+    #[arg(short, long)]
+    pub subtitle: Option<String>,
 
-    /// Sound name (通知音、macOS only)
+    /// Sound name (macOS) or sound file path (通知音の名前またはファイルパス)
     ///
     /// Available sounds: default, Basso, Blow, Bottle, Frog, Funk,
-    /// Glass, Hero, Morse, Ping, Pop, Purr, Sosumi, Submarine, Tink
-    #[arg(long, default_value = "default")]
-    pub sound: String,
+    /// Glass, Hero, Morse, Ping, Pop, Purr, Sosumi, Submarine, Tink.
+    /// Windows system sounds can be given as ms-winsoundevent:Notification.Mail etc.
+    /// Without it the sound from the config file is used, or default if it has none.
+    #[arg(long, value_parser = SoundParser, hide_possible_values = true)]
+    pub sound: Option<String>,
+
+    /// Force specific backend, a plugin by name, or all to send through every available one (強制的に特定のバックエンド・プラグインを使用、all で全て)
+    #[arg(long, value_parser = BackendParser)]
+    pub backend: Option<BackendChoice>,
+
+    /// Send by running a command line instead, e.g. 'herbe {title} {message}'; also {urgency}, {subtitle}, {icon}, {timeout}, {app_name} (コマンドのテンプレートで送る)
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_backend_command, conflicts_with_all = ["backend", "wait"])]
+    pub backend_command: Option<CustomCommandNotifier>,
+
+    /// Use the defaults and backend of [profile.NAME] in the config file (設定ファイルのプロファイルを使用)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Action button as ID=LABEL, repeatable (アクションボタン、複数指定可)
+    #[arg(long = "action", value_name = "ID=LABEL", value_parser = parse_action)]
+    pub actions: Vec<Action>,
+
+    /// Show an inline reply box; the text is printed as reply:TEXT (返信欄を表示、--wait が必要)
+    #[arg(long, value_name = "PLACEHOLDER", requires = "wait")]
+    pub reply: Option<String>,
+
+    /// Wait for the user and print clicked, action:ID, reply:TEXT, dismissed or expired (操作を待って結果を出力)
+    ///
+    /// Exits with 0 when clicked, an action is chosen or a reply is sent,
+    /// 3 when dismissed and 4 when it expired.
+    #[arg(long)]
+    pub wait: bool,
 
-    /// Force specific backend (強制的に特定のバックエンドを使用)
+    /// Keep the notification until the user acknowledges it (確認されるまで表示し続ける)
+    #[arg(long)]
+    pub require_ack: bool,
+
+    /// Repeat the sound every N seconds until acknowledged; critical + --require-ack only (確認されるまで音を繰り返す)
+    #[arg(long, value_name = "SECS", requires_all = ["require_ack", "wait"])]
+    pub repeat_sound: Option<u64>,
+
+    /// Do not keep the notification in the notification center or history (通知センター・履歴に残さない)
+    #[arg(long, conflicts_with = "resident")]
+    pub transient: bool,
+
+    /// Keep the notification in the notification center until removed (通知センターに残し続ける)
+    #[arg(long)]
+    pub resident: bool,
+
+    /// Open URL when the notification is clicked; Linux needs --wait (クリックで URL を開く)
+    #[arg(long, value_name = "URL")]
+    pub on_click_url: Option<String>,
+
+    /// Run a shell command when the notification is clicked; needs --wait except macOS + terminal-notifier (クリックでコマンドを実行)
+    #[arg(long, value_name = "CMD")]
+    pub on_click_exec: Option<String>,
+
+    /// Accept on_click_exec commands from --json; without it such JSON is rejected (--json のコマンドを許可)
+    #[arg(long, requires = "json")]
+    pub allow_exec: bool,
+
+    /// Keep the notification on screen until dismissed as an alarm, reminder or call (通知の用途、閉じるまで表示)
+    #[arg(long, value_enum)]
+    pub scenario: Option<CliScenario>,
+
+    /// Play no sound (通知音を鳴らさない)
+    #[arg(long, conflicts_with = "loop_sound")]
+    pub silent: bool,
+
+    /// Repeat the sound until the notification is dismissed; Windows only (通知音を繰り返す)
+    #[arg(long)]
+    pub loop_sound: bool,
+
+    /// Do not show the notification during Do Not Disturb / Focus (おやすみモード中は表示しない)
+    #[arg(long)]
+    pub respect_dnd: bool,
+
+    /// Deliver even during Do Not Disturb / Focus as time-sensitive (おやすみモードを越えて届ける)
+    #[arg(long)]
+    pub force: bool,
+
+    /// Where the notification came from, e.g. "via build.sh on ci-01"; Windows only (通知の出どころ)
+    #[arg(long, value_name = "TEXT")]
+    pub attribution: Option<String>,
+
+    /// Hide the body on the lock screen and when mirrored to other devices (ロック画面・転送先では本文を隠す)
+    #[arg(long)]
+    pub private: bool,
+
+    /// Expand {hostname}, {date}, {env:VAR} and --var placeholders in --title and --message (タイトルと本文のプレースホルダーを展開)
+    #[arg(long)]
+    pub template: bool,
+
+    /// Template variable as KEY=VALUE, repeatable, e.g. exit_code=$?; implies --template (テンプレートの変数、複数指定可)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Backend-specific hint as KEY=VALUE, repeatable, e.g. fgcolor=#ff0000 (バックエンドに渡すヒント、複数指定可)
+    #[arg(long = "hint", value_name = "KEY=VALUE", value_parser = parse_hint)]
+    pub hints: Vec<(String, String)>,
+
+    /// Group tag; notifications with the same tag replace each other (グループのタグ、同じタグの通知はまとまる)
+    #[arg(long, value_name = "TAG")]
+    pub group: Option<String>,
+
+    /// Action Center toast collection to file it under, Windows only (トーストコレクション、Windows のみ)
+    #[arg(long, value_name = "ID")]
+    pub collection: Option<String>,
+
+    /// Replace a previously shown notification by its ID (指定 ID の通知を置き換え)
+    #[arg(long, value_name = "ID")]
+    pub replace_id: Option<String>,
+
+    /// Print the backend-assigned notification ID to stdout (通知 ID を出力)
+    #[arg(long)]
+    pub print_id: bool,
+
+    /// Print the chosen backend and the exact command, script or D-Bus call instead of sending (送信せずに配送内容を表示)
+    #[arg(long, conflicts_with_all = ["at", "after", "wait", "print_id"])]
+    pub dry_run: bool,
+
+    /// Show it later instead: HH:MM or +10m; registers a Windows scheduled task (指定した時刻に通知)
+    #[arg(long, value_name = "WHEN")]
+    pub at: Option<When>,
+
+    /// Show it after a delay like 30s, 10m or 2h, waiting in a detached process (指定した時間の後に通知)
+    #[arg(long, value_name = "DELAY", value_parser = parse_after, conflicts_with_all = ["at", "wait", "print_id"])]
+    pub after: Option<Duration>,
+
+    /// Attach the calling process name, PID and working directory (送信元プロセスの情報を添付)
+    #[arg(long)]
+    pub capture_sender: bool,
+
+    /// W3C traceparent of the calling request; defaults to $TRACEPARENT (トレース ID を添付)
+    #[arg(long, value_name = "TRACEPARENT")]
+    pub traceparent: Option<TraceContext>,
+
+    /// Record the notification in the history used by `statusbar` and `history` (通知履歴に記録)
+    #[arg(long)]
+    pub record_history: bool,
+
+    /// If no backend can show it, save it and retry on the next successful send (表示できなければ保存して後で再送)
+    #[arg(long)]
+    pub spool: bool,
+
+    /// Retry transient failures such as a busy notification daemon up to N times, with backoff (一時的な失敗を N 回まで再試行)
+    #[arg(long, value_name = "N")]
+    pub retries: Option<u32>,
+
+    /// What to do when the title or message is longer than the backend shows; unset sends it as is (長さの上限を超えた時の扱い)
     #[arg(long, value_enum)]
-    pub backend: Option<CliBackend>,
+    pub overflow: Option<CliOverflow>,
+
+    /// Mask card numbers, token=... values and AWS keys in the text (機密情報を伏せ字にする)
+    #[arg(long)]
+    pub redact: bool,
+}
+
+// ============================================================
+// サブコマンドの定義
+// ============================================================
+
+/// サブコマンド
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Send a notification; the same as giving the options without a subcommand (通知を送信、サブコマンド省略時と同じ)
+    Send(Box<SendArgs>),
+    /// Close a notification shown with --print-id (通知を閉じる)
+    Dismiss {
+        /// Notification ID printed by --print-id (通知 ID)
+        id: String,
+        /// Backend that showed the notification (通知を表示したバックエンド)
+        #[arg(long, value_enum)]
+        backend: Option<CliBackend>,
+    },
+    /// Show how a notification would be routed without sending it (送信せずに判断の過程を表示)
+    Simulate {
+        /// Notification JSON (same keys as --backend stdout output), - for stdin (通知の JSON ファイル)
+        #[arg(long, value_name = "FILE")]
+        from_file: PathBuf,
+    },
+    /// Register an AppUserModelID for Windows toasts (Windows の AppUserModelID を登録)
+    RegisterApp {
+        /// AppUserModelID to pass as --app-name, e.g. MyCompany.MyTool (登録する ID)
+        id: String,
+        /// Name shown on the toast (トーストに表示する名前)
+        #[arg(long, value_name = "NAME")]
+        display_name: String,
+        /// Icon shown on the toast (トーストに表示するアイコン)
+        #[arg(long, value_name = "PATH")]
+        icon: Option<PathBuf>,
+    },
+    /// Print unread/critical counts for waybar or i3blocks (ステータスバー用に未読数を出力)
+    Statusbar {
+        /// Output format (出力形式)
+        #[arg(long, value_enum, default_value = "waybar")]
+        format: CliStatusbarFormat,
+        /// Reset the unread count before printing, e.g. from on-click (未読数をクリア)
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Show notifications missed while locked or in DND, then mark them seen (未読の通知を表示して既読にする)
+    Unread {
+        /// Print only the number of unread notifications (未読数だけを表示)
+        #[arg(long)]
+        count: bool,
+        /// Do not mark the notifications as seen (既読にしない)
+        #[arg(long)]
+        keep: bool,
+    },
+    /// Search recorded notifications and their delivery results (送信した通知の履歴を検索)
+    History {
+        /// Only notifications recorded within this long ago, e.g. 30m or 2h (この時間内に記録したもの)
+        #[arg(long, value_name = "AGE", value_parser = parse_after)]
+        since: Option<Duration>,
+        /// Topic pattern such as ci/* (トピックのパターン)
+        #[arg(long, value_name = "PATTERN")]
+        topic: Option<String>,
+        /// Urgency level (緊急度)
+        #[arg(long, value_enum)]
+        urgency: Option<CliUrgencyLevel>,
+        /// Text contained in the title or message, case-insensitive (タイトルか本文に含む文字列)
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+        /// Part of the backend name, e.g. linux for "Linux (D-Bus)" (送信したバックエンド)
+        #[arg(long, value_name = "NAME")]
+        backend: Option<String>,
+        /// Only notifications whose delivery failed on some backend (送信に失敗したものだけ)
+        #[arg(long)]
+        failed: bool,
+        /// Show at most this many of the newest matches (表示する最大件数)
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Print one JSON object per line (1 行に 1 つの JSON で出力)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Diagnose why notifications might not show up (通知が届かない原因を診断)
+    Doctor {
+        /// Open the settings page for problems that can be fixed there (設定ページを開いて修正)
+        #[arg(long)]
+        fix: bool,
+        /// AppUserModelID to check on Windows, as passed to --app-name (確認する Windows のアプリ ID)
+        #[arg(long, value_name = "ID")]
+        app_name: Option<String>,
+    },
+    /// Show the target triple and which backends are compiled in (ビルド対象と組み込まれたバックエンドを表示)
+    Targets,
+    /// List the backends and whether they are available (バックエンドの一覧と利用可否を表示)
+    Backends {
+        /// Also show the features each backend supports (対応している機能も表示)
+        #[arg(long)]
+        detailed: bool,
+    },
+    /// Test the backends interactively and write the initial config (対話しながら初期設定を作成)
+    Setup,
+    /// Run a command, then notify its result, run time and last output lines (コマンドを実行して結果を通知)
+    ///
+    /// Exits with the command's exit code (128 + N if killed by signal N, 127 if not found).
+    Exec {
+        /// Output lines to include in the notification (通知に含める出力の行数)
+        #[arg(long, value_name = "LINES", default_value_t = 5)]
+        tail: usize,
+        /// Notification title instead of the command line (通知のタイトル)
+        #[arg(short, long)]
+        title: Option<String>,
+        /// Force specific backend or a plugin by name (強制的に特定のバックエンドを使用)
+        #[arg(long, value_parser = BackendParser)]
+        backend: Option<BackendChoice>,
+        /// Use the defaults and backend of [profile.NAME] in the config file (設定ファイルのプロファイルを使用)
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Command to run and its arguments, e.g. -- cargo build (実行するコマンド)
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Notify when a file or directory changes, or when new lines match a pattern (ファイルの変更を監視して通知)
+    Watch {
+        /// File or directory to watch; directories are watched recursively (監視するファイル・ディレクトリ)
+        path: PathBuf,
+        /// Only notify appended lines matching this regex, e.g. 'ERROR|panicked' (追記された行を正規表現で絞り込む)
+        #[arg(long, value_name = "REGEX", value_parser = parse_regex)]
+        pattern: Option<Regex>,
+        /// Notification title instead of the file name (通知のタイトル)
+        #[arg(short, long)]
+        title: Option<String>,
+        /// Force specific backend or a plugin by name (強制的に特定のバックエンドを使用)
+        #[arg(long, value_parser = BackendParser)]
+        backend: Option<BackendChoice>,
+        /// Use the defaults and backend of [profile.NAME] in the config file (設定ファイルのプロファイルを使用)
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
+    /// Read stdin continuously and notify lines matching a pattern (標準入力の行を監視して通知)
+    Pipe {
+        /// Only notify lines matching this regex; without it every line (通知する行の正規表現)
+        #[arg(long = "match", value_name = "REGEX", value_parser = parse_regex)]
+        pattern: Option<Regex>,
+        /// Send one notification per matching line as it arrives (default) (一致した行ごとに通知)
+        #[arg(long, conflicts_with = "on_eof")]
+        per_line: bool,
+        /// Send the matching lines in one notification when the input ends (入力の終わりにまとめて通知)
+        #[arg(long)]
+        on_eof: bool,
+        /// Notification title (通知のタイトル)
+        #[arg(short, long)]
+        title: Option<String>,
+        /// Force specific backend or a plugin by name (強制的に特定のバックエンドを使用)
+        #[arg(long, value_parser = BackendParser)]
+        backend: Option<BackendChoice>,
+        /// Use the defaults and backend of [profile.NAME] in the config file (設定ファイルのプロファイルを使用)
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
+    /// Print a shell completion script (シェル補完スクリプトを出力)
+    ///
+    /// The script asks rust-toast for candidates on every completion, so backend names
+    /// include plugins installed later; macOS sound names are completed too.
+    Completions {
+        /// Shell to generate the script for (対象のシェル)
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print the manual page in roff format, e.g. `rust-toast man | man -l -` (man ページを出力)
+    Man,
+    /// Show, create or check the config file (設定ファイルの場所・作成・検証)
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Check notification rules against fixtures (ルールのテスト)
+    Rules {
+        #[command(subcommand)]
+        action: RulesCommand,
+    },
+    /// Inspect or repair the persistent store (永続化ストアの検査・修復)
+    Store {
+        #[command(subcommand)]
+        action: StoreCommand,
+    },
+    /// Manage recurring notifications run by `schedule run` (繰り返しの通知の管理)
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleCommand,
+    },
+}
+
+/// `schedule` サブコマンドの操作
+#[derive(Subcommand, Debug)]
+pub enum ScheduleCommand {
+    /// Register a recurring notification; pass its arguments after -- (繰り返しの通知を登録)
+    Add {
+        /// Interval like 30m or 2h, counted from when `schedule run` starts (間隔)
+        #[arg(long, value_name = "INTERVAL", value_parser = parse_after, required_unless_present = "cron", conflicts_with = "cron")]
+        every: Option<Duration>,
+        /// Cron expression: minute hour day month weekday (cron 形式の時刻)
+        #[arg(long, value_name = "EXPR")]
+        cron: Option<Cron>,
+        /// Minutes east of UTC that the cron expression is in, e.g. 540 for JST; defaults to the local time zone (UTC からのずれ、分。省略時はローカルタイム)
+        #[arg(long, value_name = "MINUTES", allow_hyphen_values = true)]
+        utc_offset: Option<i32>,
+        /// rust-toast arguments for the notification, e.g. -- -m "Stand up" (通知の引数)
+        #[arg(last = true, required = true)]
+        args: Vec<String>,
+    },
+    /// List registered recurring notifications (登録した通知の一覧)
+    List,
+    /// Remove a recurring notification by ID (登録した通知を削除)
+    Remove {
+        /// ID shown by `schedule list` (予定の ID)
+        id: u64,
+    },
+    /// Stay in the foreground and send notifications when they are due (常駐して時刻に通知)
+    Run,
+    /// Print a systemd user unit that keeps `schedule run` running (systemd のユーザーユニットを表示)
+    Unit {
+        /// Drop privileges: no capabilities, umask 077, read-only home except the data directory (権限を絞る)
+        #[arg(long)]
+        hardened: bool,
+    },
+}
+
+/// `config` サブコマンドの操作
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the path of the config file (設定ファイルのパスを表示)
+    Path,
+    /// Write a commented template config file (雛形を書き出す)
+    Init {
+        /// Overwrite an existing config file (既存のファイルを上書き)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check that the config file parses and is private (設定ファイルを検証)
+    Validate,
+}
+
+/// `rules` サブコマンドの操作
+#[derive(Subcommand, Debug)]
+pub enum RulesCommand {
+    /// Run the cases in a fixture file and report pass/fail (フィクスチャのケースを実行)
+    Test {
+        /// TOML file with [[case]] entries (テストケースの TOML ファイル)
+        file: PathBuf,
+    },
+}
+
+/// `store` サブコマンドの操作
+#[derive(Subcommand, Debug)]
+pub enum StoreCommand {
+    /// Check journal integrity (ジャーナルの整合性チェック)
+    Check {
+        /// Journal file; defaults to all journals in the data directory (対象のジャーナル)
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+    /// Drop corrupted records from journals (壊れたレコードを取り除いて修復)
+    Repair {
+        /// Journal file; defaults to all journals in the data directory (対象のジャーナル)
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+    /// Send notifications saved by --spool while no backend was available (保存した通知を送り直す)
+    Flush,
+    /// Upgrade journals to the current format (ジャーナルを現在の形式に移行)
+    Migrate {
+        /// Journal file; defaults to all journals in the data directory (対象のジャーナル)
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Show what would change without writing (変更内容の表示のみ)
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 // ============================================================
@@ -83,6 +611,61 @@ pub enum CliUrgencyLevel {
     Critical,
 }
 
+/// CLI 用の通知の用途
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliScenario {
+    /// Alarm, e.g. a timer going off
+    Alarm,
+    /// Reminder, e.g. an upcoming meeting
+    Reminder,
+    /// Incoming call
+    IncomingCall,
+}
+
+/// CliScenario から Scenario への変換
+impl From<CliScenario> for Scenario {
+    fn from(scenario: CliScenario) -> Self {
+        match scenario {
+            CliScenario::Alarm => Scenario::Alarm,
+            CliScenario::Reminder => Scenario::Reminder,
+            CliScenario::IncomingCall => Scenario::IncomingCall,
+        }
+    }
+}
+
+/// CLI 用の長さの上限を超えた時の扱い
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliOverflow {
+    /// Cut to the limit and end with an ellipsis
+    Truncate,
+    /// Send the message as several notifications
+    Split,
+    /// Fail without sending
+    Error,
+}
+
+/// CliOverflow から Overflow への変換
+impl From<CliOverflow> for Overflow {
+    fn from(overflow: CliOverflow) -> Self {
+        match overflow {
+            CliOverflow::Truncate => Overflow::Truncate,
+            CliOverflow::Split => Overflow::Split,
+            CliOverflow::Error => Overflow::Error,
+        }
+    }
+}
+
+/// CLI 用のホスト名付与の設定
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliHostnamePrefix {
+    /// Never prefix
+    Never,
+    /// Prefix only when running over SSH
+    Remote,
+    /// Always prefix
+    Always,
+}
+
 /// CLI 用のバックエンド選択
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum CliBackend {
@@ -92,12 +675,120 @@ pub enum CliBackend {
     Windows,
     /// macOS notification via osascript
     Macos,
+    /// Modal dialog (zenity/kdialog, MessageBox, display dialog)
+    Dialog,
+    /// Boxed banner on stderr
+    Console,
+    /// Print the resolved notification as JSON to stdout
+    Stdout,
+}
+
+/// `--backend` で選んだ送り先
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendChoice {
+    /// 1 つのバックエンド
+    One(Backend),
+    /// 利用できる全てのバックエンド（`notifier::available_backends()`）
+    All,
+}
+
+/// `--backend` の値の解析（組み込みのバックエンド、登録されたプラグイン、`all`）
+///
+/// 候補は解析の時点で `notifier::registered_backends()` から作るので、
+/// `load_plugins()` の後に解析すれば `--help` やエラーの一覧にプラグインも表示されます。
+#[derive(Debug, Clone, Copy)]
+pub struct BackendParser;
+
+impl BackendParser {
+    fn possible_values() -> Vec<clap::builder::PossibleValue> {
+        let builtin: Vec<_> = CliBackend::value_variants()
+            .iter()
+            .filter_map(ValueEnum::to_possible_value)
+            .collect();
+        let plugins: Vec<_> = notifier::registered_backends()
+            .into_iter()
+            .filter(|name| !builtin.iter().any(|value| value.matches(name, false)))
+            .map(|name| clap::builder::PossibleValue::new(name).help("Plugin"))
+            .collect();
+        let all = clap::builder::PossibleValue::new("all").help("Every available backend");
+        builtin.into_iter().chain(plugins).chain([all]).collect()
+    }
+}
+
+impl clap::builder::TypedValueParser for BackendParser {
+    type Value = BackendChoice;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> std::result::Result<BackendChoice, clap::Error> {
+        let name = clap::builder::PossibleValuesParser::new(Self::possible_values())
+            .parse_ref(cmd, arg, value)?;
+        if name == "all" {
+            return Ok(BackendChoice::All);
+        }
+        let backend = CliBackend::from_str(&name, false)
+            .map(Backend::from)
+            .unwrap_or(Backend::Custom(name));
+        Ok(BackendChoice::One(backend))
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        Some(Box::new(Self::possible_values().into_iter()))
+    }
+}
+
+/// `--sound` の値の解析（任意の文字列。macOS のシステムサウンドの名前はシェル補完の候補にする）
+#[derive(Debug, Clone, Copy)]
+pub struct SoundParser;
+
+impl clap::builder::TypedValueParser for SoundParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> std::result::Result<String, clap::Error> {
+        clap::builder::StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        let names = std::iter::once("default").chain(notifier::MACOS_SOUNDS.iter().copied());
+        Some(Box::new(names.map(clap::builder::PossibleValue::new)))
+    }
+}
+
+/// CLI 用のステータスバーの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CliStatusbarFormat {
+    /// waybar custom module JSON (return-type: json)
+    Waybar,
+    /// i3blocks block (full_text, short_text, color)
+    I3blocks,
 }
 
 // ============================================================
 // 型変換の実装
 // ============================================================
 
+/// CliStatusbarFormat から statusbar::Format への変換
+impl From<CliStatusbarFormat> for statusbar::Format {
+    fn from(format: CliStatusbarFormat) -> Self {
+        match format {
+            CliStatusbarFormat::Waybar => statusbar::Format::Waybar,
+            CliStatusbarFormat::I3blocks => statusbar::Format::I3blocks,
+        }
+    }
+}
+
 /// CliUrgencyLevel から UrgencyLevel への変換
 ///
 /// `From` トレイトを実装することで、`.into()` で変換できます。
@@ -111,65 +802,1399 @@ impl From<CliUrgencyLevel> for UrgencyLevel {
     }
 }
 
-/// CliBackend から Platform への変換
-impl From<CliBackend> for Platform {
+/// CliHostnamePrefix から HostnamePrefix への変換
+impl From<CliHostnamePrefix> for HostnamePrefix {
+    fn from(prefix: CliHostnamePrefix) -> Self {
+        match prefix {
+            CliHostnamePrefix::Never => HostnamePrefix::Never,
+            CliHostnamePrefix::Remote => HostnamePrefix::Remote,
+            CliHostnamePrefix::Always => HostnamePrefix::Always,
+        }
+    }
+}
+
+/// CliBackend から Backend への変換
+impl From<CliBackend> for Backend {
     fn from(backend: CliBackend) -> Self {
         match backend {
-            CliBackend::Linux => Platform::Linux,
-            CliBackend::Windows => Platform::Windows,
-            CliBackend::Macos => Platform::MacOs,
+            CliBackend::Linux => Backend::Native(Platform::Linux),
+            CliBackend::Windows => Backend::Native(Platform::Windows),
+            CliBackend::Macos => Backend::Native(Platform::MacOs),
+            CliBackend::Dialog => Backend::Dialog,
+            CliBackend::Console => Backend::Console,
+            CliBackend::Stdout => Backend::Stdout,
+        }
+    }
+}
+
+/// `ID=LABEL` 形式の文字列を Action に変換
+///
+/// `=` が無い場合は、ラベルをそのまま ID としても使います。
+///
+/// # 学習ポイント
+/// `value_parser` に関数を渡すと、独自の型を CLI 引数として受け取れます。
+fn parse_action(s: &str) -> std::result::Result<Action, String> {
+    let (id, label) = s.split_once('=').unwrap_or((s, s));
+    if id.is_empty() || label.is_empty() {
+        return Err(format!("invalid action '{s}': expected ID=LABEL"));
+    }
+    Ok(Action::new(id, label))
+}
+
+/// `--backend-command` のテンプレートを解釈する（引用符やプレースホルダーの誤りはここでエラー）
+fn parse_backend_command(s: &str) -> std::result::Result<CustomCommandNotifier, String> {
+    CustomCommandNotifier::new(s).map_err(|e| e.to_string())
+}
+
+/// `KEY=VALUE` 形式の文字列をヒントのキーと値に分ける（値は空でもよい）
+fn parse_hint(s: &str) -> std::result::Result<(String, String), String> {
+    parse_key_value(s, "hint")
+}
+
+/// `KEY=VALUE` 形式の文字列をテンプレートの変数に分ける（値は空でもよい）
+fn parse_var(s: &str) -> std::result::Result<(String, String), String> {
+    parse_key_value(s, "variable")
+}
+
+fn parse_key_value(s: &str, what: &str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("invalid {what} '{s}': expected KEY=VALUE")),
+    }
+}
+
+/// `5s` / `2m` / `500ms` / `1h` / `never` 形式の文字列を表示時間に変換する
+///
+/// 単位の無い数値はミリ秒として扱います（以前の `--timeout 5000` との互換のため）。
+/// `never` と `0` は「閉じるまで表示」（`Duration::ZERO`）です。
+fn parse_timeout(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("never") {
+        return Ok(Duration::ZERO);
+    }
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid timeout '{s}': expected e.g. 5s, 2m, 500ms or never"))?;
+    let seconds = |factor: u64| {
+        value
+            .checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("timeout '{s}' is too large"))
+    };
+    match unit {
+        "" | "ms" => Ok(Duration::from_millis(value)),
+        "s" => seconds(1),
+        "m" => seconds(60),
+        "h" => seconds(60 * 60),
+        _ => Err(format!("invalid timeout unit '{unit}': use ms, s, m or h")),
+    }
+}
+
+/// `rust-toast exec`: コマンドを実行し、結果を通知して、コマンドの終了コードで終わる
+///
+/// 通知に失敗してもコマンドの終了コードを優先します（ラッパーとして透過に使えるように）。
+/// 終了コードは `Outcome::exit_code()`（シグナルは 128 + 番号）で、起動できなければ
+/// 127（見つからない）/ 126（実行できない）です。
+/// Ctrl-C はコマンドにだけ届き、rust-toast は終わったコマンドを通知してから終わります。
+fn run_exec(
+    command: &[String],
+    tail: usize,
+    title: Option<String>,
+    backend: Option<BackendChoice>,
+    profile: Option<String>,
+) -> Result<ExitCode> {
+    ignore_interrupts();
+    let outcome = match exec::run(command, tail) {
+        Ok(outcome) => outcome,
+        Err(e @ NotificationError::CommandNotFound { .. }) => {
+            eprintln!("error: {e}");
+            return Ok(ExitCode::from(exec::EXIT_NOT_FOUND));
+        }
+        Err(NotificationError::CommandExecution(e)) => {
+            eprintln!("error: cannot run {}: {e}", command[0]);
+            return Ok(ExitCode::from(exec::EXIT_NOT_EXECUTABLE));
+        }
+        Err(e) => return Err(e),
+    };
+    let mut builder = outcome.to_builder();
+    if let Some(title) = title {
+        builder = builder.title(title);
+    }
+    if let Err(e) = SendSettings::load(backend, profile).and_then(|settings| settings.send(builder))
+    {
+        eprintln!("warning: could not send the notification: {e}");
+    }
+    let code = outcome.exit_code();
+    match u8::try_from(code) {
+        Ok(code) => Ok(ExitCode::from(code)),
+        // Windows の終了コードは 255 を超えうる（ExitCode は u8 しか持てない）
+        Err(_) => std::process::exit(code),
+    }
+}
+
+/// このプロセスでは Ctrl-C（SIGINT）で終わらないようにする（Unix のみ）
+///
+/// 端末の Ctrl-C はプロセスグループ全体に届くので、子プロセスは止まり、こちらはその結果を通知できます。
+/// `exec` は通知したら終わるので、元には戻しません。
+#[cfg(unix)]
+fn ignore_interrupts() {
+    let interrupted = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGINT, interrupted) {
+        tracing::debug!("cannot ignore SIGINT: {e}");
+    }
+}
+
+#[cfg(not(unix))]
+fn ignore_interrupts() {}
+
+/// 設定ファイルに通知音が無ければ、CLI の既定の通知音（`DEFAULT_SOUND`）にする
+///
+/// ライブラリの既定は「通知音の指定なし」ですが、CLI はこれまでどおり既定の音を鳴らします。
+/// `--sound` とプロファイルの `sound` は通知の項目なので、こちらより優先されます。
+fn apply_default_sound() {
+    let mut config = config::current();
+    if config.sound.is_none() {
+        config.sound = Some(DEFAULT_SOUND.to_string());
+        config::set(config);
+    }
+}
+
+/// 通知で指定していない項目をプロファイルの項目で埋める（`send` と `exec` などで共通）
+fn with_profile(
+    builder: NotificationBuilder,
+    profile: Option<&setup::Profile>,
+) -> Result<NotificationBuilder> {
+    Ok(match profile {
+        Some(profile) => builder.or_defaults(profile.builder()?),
+        None => builder,
+    })
+}
+
+/// `exec` / `watch` / `pipe` の送信に使う設定ファイル・プロファイル・バックエンド
+///
+/// `watch` と `pipe` は通知のたびに読み直さないよう、監視を始める前に 1 回だけ `load()` します。
+struct SendSettings {
+    /// `--backend`（無ければ通知か設定ファイルのバックエンド）
+    backend: Option<Backend>,
+    /// 設定ファイルのバックエンド
+    default_backend: Option<Backend>,
+    /// `--profile` のプロファイル
+    profile: Option<setup::Profile>,
+}
+
+impl SendSettings {
+    /// 設定ファイルとプロファイルを読み、グローバル設定に反映する
+    fn load(backend: Option<BackendChoice>, profile: Option<String>) -> Result<Self> {
+        let backend = match backend {
+            Some(BackendChoice::All) => {
+                return Err(NotificationError::Other(
+                    "--backend all is only supported by send".to_string(),
+                ))
+            }
+            Some(BackendChoice::One(backend)) => Some(backend),
+            None => None,
+        };
+        let settings = setup::load()?;
+        let profile = match profile {
+            Some(name) => Some(settings.profile(&name)?.clone()),
+            None => None,
+        };
+        settings.apply_config();
+        apply_default_sound();
+        if let Some(profile) = &profile {
+            profile.apply_config();
+        }
+        Ok(Self {
+            backend,
+            default_backend: settings.backend,
+            profile,
+        })
+    }
+
+    /// 組み立てた通知を送る
+    ///
+    /// 通知の項目はプロファイルより優先し、`backend` が無ければ設定ファイルのバックエンドを使います。
+    fn send(&self, builder: NotificationBuilder) -> Result<()> {
+        let mut builder = with_profile(builder, self.profile.as_ref())?;
+        if let Some(backend) = &self.backend {
+            builder = builder.backend(backend.clone());
+        } else if !builder.has_backend() {
+            if let Some(backend) = &self.default_backend {
+                builder = builder.backend(backend.clone());
+            }
+        }
+        let handle = builder.send()?;
+        eprintln!(
+            "✓ Toast notification sent successfully ({})",
+            handle.report()
+        );
+        Ok(())
+    }
+}
+
+/// 終了コード: 送信できた（`--wait` ではクリック・アクション・返信）
+pub const EXIT_SUCCESS: u8 = 0;
+/// 終了コード: `--wait` で通知が閉じられた
+pub const EXIT_DISMISSED: u8 = 3;
+/// 終了コード: `--wait` で操作されないまま表示時間が過ぎた
+pub const EXIT_EXPIRED: u8 = 4;
+
+/// `--wait` の操作結果の終了コード
+///
+/// 1（送信の失敗）と 2（引数の誤り）とは重ならないようにしているので、
+/// `if rust-toast --wait ...; then ...; fi` で「ユーザーが反応した」だけを拾えます。
+pub fn wait_exit_code(interaction: &Interaction) -> u8 {
+    match interaction {
+        Interaction::Clicked | Interaction::ActionInvoked(_) | Interaction::Replied(_) => {
+            EXIT_SUCCESS
         }
+        Interaction::Dismissed => EXIT_DISMISSED,
+        Interaction::Expired => EXIT_EXPIRED,
+    }
+}
+
+/// 補完の候補を求める時にシェルが設定する環境変数（`COMPLETE=bash rust-toast -- ...`）
+const COMPLETE_ENV: &str = "COMPLETE";
+
+/// シェルから候補を求められた場合（`COMPLETE_ENV` がある場合）は、候補を出力して終了する
+///
+/// 候補は補完のたびに `Args::command()` から作るため、`--backend` には `load_plugins()` で
+/// その時に登録されているプラグインも含まれます。標準出力に何か書く前に呼んでください。
+pub fn complete_from_env() {
+    clap_complete::CompleteEnv::with_factory(Args::command)
+        .var(COMPLETE_ENV)
+        .complete();
+}
+
+/// シェル補完スクリプトを書き出す
+///
+/// 候補を並べたスクリプトではなく、補完のたびに `COMPLETE_ENV` を付けて rust-toast を呼ぶ
+/// スクリプトです（候補は `complete_from_env()` が返す）。
+fn print_completions(shell: clap_complete::Shell, out: &mut dyn std::io::Write) -> Result<()> {
+    let name = shell.to_string();
+    let shells = clap_complete::env::Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .ok_or_else(|| NotificationError::Other(format!("no completion support for {name}")))?;
+    completer.write_registration(COMPLETE_ENV, "rust-toast", "rust-toast", "rust-toast", out)?;
+    Ok(())
+}
+
+/// man ページの設定ファイルの節（roff）
+///
+/// 場所は OS ごとに書きます（`setup::path()` と `store::data_dir()` の規則。変えたらこちらも直す）。
+const MAN_FILES: &str = r#".SH FILES
+.TP
+\fICONFIG_DIR/config.toml\fR
+Defaults used when options are omitted: \fBbackend\fR, \fBrecord_history\fR, \fBtitle\fR,
+\fBicon\fR, \fBsound\fR and \fBfallback\fR (a list of backends tried when sending fails).
+\fB[plugins.\fINAME\fB]\fR tables hold environment variables passed to a plugin, and
+\fB[profile.\fINAME\fB]\fR tables hold defaults selected with \fB\-\-profile\fR, using the same keys
+as \fB\-\-json\fR. \fBrust\-toast config init\fR writes a commented template and
+\fBrust\-toast config path\fR prints the path in use. \fICONFIG_DIR\fR is
+\fI$XDG_CONFIG_HOME/rust\-toast\fR (default \fI~/.config/rust\-toast\fR) on Linux and WSL,
+\fI~/Library/Application Support/rust\-toast\fR on macOS and \fI%APPDATA%\\rust\-toast\fR on Windows.
+.TP
+\fIDATA_DIR/plugins/\fR
+Executables registered as backends by file name. A plugin reads one notification as JSON on
+stdin and exits with 0 on success. \fIDATA_DIR\fR also holds the history, the queue and
+downloaded icons. It is \fB$RUST_TOAST_DATA_DIR\fR if set, otherwise
+\fI$XDG_DATA_HOME/rust\-toast\fR (default \fI~/.local/share/rust\-toast\fR) on Linux and WSL,
+\fI~/Library/Application Support/rust\-toast\fR on macOS and \fI%LOCALAPPDATA%\\rust\-toast\fR
+on Windows.
+.SH ENVIRONMENT
+.TP
+\fBRUST_TOAST_CONFIG\fR
+Path of the config file, used instead of \fICONFIG_DIR/config.toml\fR.
+.TP
+\fBRUST_TOAST_DATA_DIR\fR
+Data directory for plugins, history, the queue and cached icons.
+.TP
+\fBXDG_CONFIG_HOME\fR, \fBXDG_DATA_HOME\fR
+Base directories for \fICONFIG_DIR\fR and \fIDATA_DIR\fR on Linux and WSL.
+.TP
+\fBCOMPLETE\fR
+Set by the script from \fBrust\-toast completions\fR to ask for completion candidates.
+.TP
+\fBTRACEPARENT\fR
+W3C trace context attached when \fB\-\-traceparent\fR is omitted.
+.SH EXIT STATUS
+.TP
+\fB0\fR
+The notification was sent (or the subcommand succeeded). With \fB\-\-wait\fR, it was clicked,
+an action was chosen or a reply was sent; the outcome is printed as \fBclicked\fR,
+\fBaction:\fIID\fR or \fBreply:\fITEXT\fR.
+.TP
+\fB1\fR
+Sending failed on every backend, or the input or config file was invalid.
+.TP
+\fB2\fR
+The command line could not be parsed.
+.TP
+\fB3\fR
+With \fB\-\-wait\fR, the notification was dismissed.
+.TP
+\fB4\fR
+With \fB\-\-wait\fR, the notification expired without being touched.
+.PP
+\fBrust\-toast exec\fR exits with the status of the command it ran: 128 + \fIN\fR if it was killed by
+signal \fIN\fR, 127 if the command was not found and 126 if it could not be executed.
+"#;
+
+/// man ページを書き出す
+///
+/// オプションとサブコマンドは `clap_mangen` が `Args::command()` から作り、
+/// バックエンドの一覧（`BackendParser` の候補）と `MAN_FILES` を加えます。
+fn print_man(out: &mut dyn std::io::Write) -> Result<()> {
+    let man = clap_mangen::Man::new(Args::command());
+    man.render_title(out)?;
+    man.render_name_section(out)?;
+    man.render_synopsis_section(out)?;
+    man.render_description_section(out)?;
+    man.render_options_section(out)?;
+    man.render_subcommands_section(out)?;
+
+    writeln!(out, ".SH BACKENDS")?;
+    for value in BackendParser::possible_values() {
+        // プラグインの名前の `-` は roff ではハイフンではなくマイナスとして書く
+        writeln!(out, ".TP\n\\fB{}\\fR", value.get_name().replace('-', "\\-"))?;
+        writeln!(
+            out,
+            "{}",
+            value
+                .get_help()
+                .map(|help| help.to_string())
+                .unwrap_or_default()
+        )?;
+    }
+    out.write_all(MAN_FILES.as_bytes())?;
+
+    man.render_version_section(out)?;
+    Ok(())
+}
+
+/// `--json` のファイル（`-` は標準入力）から通知を読む（`on_click_exec` は `allow_exec` の時だけ）
+fn read_notifications(path: &Path, allow_exec: bool) -> Result<Vec<NotificationBuilder>> {
+    let json = if path.as_os_str() == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    let builders = if allow_exec {
+        NotificationBuilder::from_json_many_with_exec(&json)?
+    } else {
+        NotificationBuilder::from_json_many(&json)?
+    };
+    if builders.is_empty() {
+        return Err(NotificationError::Other(
+            "the JSON array has no notifications".to_string(),
+        ));
+    }
+    Ok(builders)
+}
+
+/// 正規表現の引数（`--pattern`）
+fn parse_regex(s: &str) -> std::result::Result<Regex, String> {
+    Regex::new(s).map_err(|e| e.to_string())
+}
+
+/// `30s` / `10m` / `2h` 形式の文字列を待ち時間に変換する（`--after`、単位の無い数値はミリ秒）
+fn parse_after(s: &str) -> std::result::Result<Duration, String> {
+    match parse_timeout(s) {
+        Ok(delay) if !s.trim().eq_ignore_ascii_case("never") => Ok(delay),
+        _ => Err(format!("invalid delay '{s}': expected e.g. 30s, 10m or 2h")),
     }
 }
 
+/// 履歴の 1 件を `2024-05-01 09:30:00 Linux ✓ Title: message` の形式にする
+///
+/// 時刻は UTC から `utc_offset`（分、`platform::local_utc_offset()`）ずらしたローカルタイムです。
+fn history_line(record: &store::history::HistoryRecord, utc_offset: i32) -> String {
+    let secs = record
+        .timestamp
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+        .saturating_add_signed(i64::from(utc_offset) * 60);
+    let (year, month, day) = template::civil_date(secs / 86_400);
+    let time = secs % 86_400;
+    let backends = match record.deliveries.as_slice() {
+        [] => "-".to_string(),
+        deliveries => deliveries
+            .iter()
+            .map(|delivery| {
+                let mark = if delivery.is_ok() { "✓" } else { "✗" };
+                format!("{} {mark}", delivery.backend)
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} {backends} {}: {}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        record.notification.title,
+        record.notification.message
+    )
+}
+
 // ============================================================
 // Args のメソッド
 // ============================================================
 
 impl Args {
+    /// サブコマンド、または（省略時は）通知の送信を実行する
+    pub fn run(self) -> Result<ExitCode> {
+        match self.command {
+            Some(command) => command.run(),
+            None => self.send.run(),
+        }
+    }
+}
+
+impl SendArgs {
+    /// `--stdin` の場合に、標準入力から本文を読む
+    ///
+    /// 標準入力を読むのは `--stdin` だけで、`-m -` は "-" という本文のままです。
+    /// 予約（`--at` / `--after`）の実行時には標準入力が無いため、組み合わせはエラーにします。
+    pub fn read_stdin(&mut self) -> Result<()> {
+        if !self.stdin {
+            return Ok(());
+        }
+        if self.at.is_some() || self.after.is_some() {
+            return Err(NotificationError::Other(
+                "--stdin cannot be combined with --at or --after".to_string(),
+            ));
+        }
+        let mut input = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut input)?;
+        let message = text::clean_piped_text(&String::from_utf8_lossy(&input));
+        if message.is_empty() {
+            return Err(NotificationError::InvalidInput {
+                field: "message".to_string(),
+                reason: "stdin is empty".to_string(),
+            });
+        }
+        self.message = Some(message);
+        Ok(())
+    }
+
+    /// `--template` / `--var` が指定されていれば、タイトルと本文のプレースホルダーを展開する
+    ///
+    /// 知らないプレースホルダーはエラーになるため、`into_builder()` より前に呼びます。
+    pub fn expand_template(&mut self) -> Result<()> {
+        if !self.template && self.vars.is_empty() {
+            return Ok(());
+        }
+        let vars: BTreeMap<String, String> = self.vars.iter().cloned().collect();
+        for text in [&mut self.title, &mut self.message].into_iter().flatten() {
+            *text = template::render(text, &vars)?;
+        }
+        Ok(())
+    }
+
+    /// 設定に関わる CLI 引数をグローバル設定（`config::set()`）に反映する
+    ///
+    /// `into_builder()` の前に呼ぶと、`build()` 時のデフォルト補完に使われます。
+    pub fn apply_config(&self) {
+        if self.title_template.is_none()
+            && self.hostname_prefix.is_none()
+            && !self.record_history
+            && !self.redact
+            && !self.spool
+        {
+            return;
+        }
+
+        let mut config = config::current();
+        if let Some(template) = &self.title_template {
+            config.title_template = template.clone();
+        }
+        if let Some(prefix) = self.hostname_prefix {
+            config.hostname_prefix = prefix.into();
+        }
+        if self.record_history {
+            config.record_history = true;
+        }
+        if self.redact {
+            config.redactions.extend(redact::builtin());
+        }
+        if self.spool {
+            config.spool = true;
+        }
+        config::set(config);
+    }
+
     /// CLI 引数から NotificationBuilder を構築
     ///
     /// CLI の責務（引数パース）と通知の責務（送信）を分離するため、
     /// Args は直接通知を送信せず、Builder を返します。
+    /// 指定したオプションの項目だけを設定するので、`--json` やプロファイルの Builder に
+    /// `or_defaults()` で重ねられます。
     ///
     /// # 使用例
     /// ```ignore
     /// let args = Args::parse();
-    /// args.into_builder().send()?;
+    /// args.send.into_builder().send()?;
     /// ```
     pub fn into_builder(self) -> NotificationBuilder {
-        let mut builder = NotificationBuilder::new()
-            .title(self.title)
-            .message(self.message)
-            .timeout(self.timeout)
-            .icon(self.icon)
-            .urgency(self.urgency.into())
-            .subtitle(self.subtitle)
-            .sound(self.sound);
+        let mut builder = match self.message {
+            Some(message) if self.markdown => NotificationBuilder::new().body_markdown(message),
+            Some(message) => NotificationBuilder::new().message(message),
+            None => NotificationBuilder::new(),
+        };
+        if let Some(urgency) = self.urgency {
+            builder = builder.urgency(urgency.into());
+        }
 
-        // バックエンドの強制指定があれば設定
-        if let Some(backend) = self.backend {
-            builder = builder.backend(backend.into());
+        // アイコン・サブタイトル・通知音の指定が無ければ、バックエンドの既定に任せる
+        if let Some(icon) = self.icon {
+            builder = builder.icon(icon);
+        }
+        if let Some(subtitle) = self.subtitle {
+            builder = builder.subtitle(subtitle);
+        }
+        if let Some(sound) = self.sound {
+            builder = builder.sound(sound);
         }
 
-        builder
-    }
-}
+        // タイトルの指定が無ければ、テンプレート（Config）に任せる
+        if let Some(title) = self.title {
+            builder = builder.title(title);
+        }
 
-// ============================================================
-// テスト
-// ============================================================
+        // タイムアウトの指定が無ければ、緊急度ごとのデフォルトに任せる
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        if let Some(priority) = self.priority {
+            builder = builder.priority(priority);
+        }
 
-    #[test]
-    fn test_urgency_conversion() {
-        assert_eq!(
-            UrgencyLevel::from(CliUrgencyLevel::Low),
-            UrgencyLevel::Low
-        );
+        if let Some(image) = self.image {
+            builder = builder.image(image);
+        }
+
+        if let Some(category) = self.category {
+            builder = builder.category(category);
+        }
+
+        if let Some(app_name) = self.app_name {
+            builder = builder.app_name(app_name);
+        }
+
+        if let Some(topic) = self.topic {
+            builder = builder.topic(topic);
+        }
+
+        if let Some(retries) = self.retries {
+            builder = builder.retries(retries);
+        }
+
+        if let Some(overflow) = self.overflow {
+            builder = builder.overflow(overflow.into());
+        }
+
+        // バックエンドの強制指定があれば設定（`all` は送信する側で扱う）
+        if let Some(BackendChoice::One(backend)) = self.backend {
+            builder = builder.backend(backend);
+        }
+
+        for action in self.actions {
+            builder = builder.action(action.id, action.label);
+        }
+
+        if let Some(placeholder) = self.reply {
+            builder = builder.reply_action(placeholder);
+        }
+
+        if self.transient {
+            builder = builder.transient(true);
+        }
+        if self.resident {
+            builder = builder.resident(true);
+        }
+        if self.private {
+            builder = builder.private(true);
+        }
+        if let Some(url) = self.on_click_url {
+            builder = builder.on_click_url(url);
+        }
+        if let Some(command) = self.on_click_exec {
+            builder = builder.on_click_exec(command);
+        }
+        if let Some(attribution) = self.attribution {
+            builder = builder.attribution(attribution);
+        }
+        if let Some(scenario) = self.scenario {
+            builder = builder.scenario(scenario.into());
+        }
+        if self.silent {
+            builder = builder.silent(true);
+        }
+        if self.loop_sound {
+            builder = builder.loop_sound(true);
+        }
+        if self.respect_dnd {
+            builder = builder.respect_dnd(true);
+        }
+        if self.force {
+            builder = builder.time_sensitive(true);
+        }
+
+        for (key, value) in self.hints {
+            builder = builder.hint(key, value);
+        }
+
+        if let Some(group) = self.group {
+            builder = builder.group(group);
+        }
+        if let Some(collection) = self.collection {
+            builder = builder.collection(collection);
+        }
+
+        if let Some(id) = self.replace_id {
+            builder = builder.replace_id(id);
+        }
+
+        if self.require_ack {
+            builder = builder.require_ack();
+        }
+        if let Some(secs) = self.repeat_sound {
+            builder = builder.repeat_sound(Duration::from_secs(secs));
+        }
+
+        // CLI の送信元は rust-toast 自身ではなく、起動した親プロセス（スクリプト）
+        if self.capture_sender || config::current().capture_sender {
+            builder = builder.sender(SenderInfo::parent());
+        }
+
+        if let Some(context) = self.traceparent.or_else(TraceContext::from_env) {
+            builder = builder.trace_context(context);
+        }
+
+        builder
+    }
+
+    /// 通知を送り、結果を表示する（`rust-toast send`）
+    ///
+    /// stdout は ID や操作結果、`--backend stdout` の JSON に使うため、成功メッセージは stderr に出します。
+    /// `--wait` の場合は操作結果を終了コード（`wait_exit_code()`）でも返します。
+    pub fn run(mut self) -> Result<ExitCode> {
+        self.read_stdin()?;
+
+        // --at の場合は OS のスケジューラに同じ引数（--at を除く）での実行を登録して終わる
+        if let Some(when) = self.at {
+            let args =
+                schedule::forwarded_args(std::env::args().skip(1), &std::env::current_dir()?)?;
+            let name = schedule::schedule(when, &args)?;
+            println!("{}", name);
+            eprintln!("✓ Scheduled for {} (task {})", when, name);
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        // --var などでタイトルと本文のプレースホルダーを展開
+        self.expand_template()?;
+
+        // 設定ファイル（`rust-toast setup` / `config init`）と、--title-template などの設定を反映
+        let settings = setup::load()?;
+        let profile = match self.profile.take() {
+            Some(name) => Some(settings.profile(&name)?.clone()),
+            None => None,
+        };
+        settings.apply_config();
+        if let Some(profile) = &profile {
+            profile.apply_config();
+        }
+        self.apply_config();
+        apply_default_sound();
+
+        // --backend-command のコマンドは登録せず、その場で send_with() に渡す
+        // （登録すると、同じ名前のプラグインを置き換えてしまうため）
+        let command = self.backend_command.take();
+
+        // --dry-run の場合は、バックエンドが行う呼び出しを表示するだけで何も送らない
+        if self.dry_run {
+            return self
+                .print_dry_run(
+                    &settings,
+                    profile.as_ref(),
+                    command.as_ref(),
+                    &mut std::io::stdout(),
+                )
+                .map(|()| ExitCode::SUCCESS);
+        }
+
+        // --backend all の場合は、利用できる全てのバックエンドに送って結果を 1 行ずつ表示
+        if self.backend == Some(BackendChoice::All) {
+            if self.wait || self.after.is_some() {
+                return Err(NotificationError::Other(
+                    "--wait and --after cannot be combined with --backend all".to_string(),
+                ));
+            }
+            return self
+                .send_to_all(profile.as_ref())
+                .map(|()| ExitCode::SUCCESS);
+        }
+
+        // --after の場合は、端末を閉じても残る子プロセスに同じ引数で待たせる
+        if let Some(delay) = self.after {
+            if schedule::is_detached() {
+                let builders = self.into_builders(Some(&settings), profile.as_ref())?;
+                if let Some(command) = &command {
+                    clock::current().sleep(delay);
+                    for builder in builders {
+                        builder.send_with(command)?;
+                    }
+                    return Ok(ExitCode::SUCCESS);
+                }
+                for builder in builders {
+                    builder.send_after(delay)?;
+                }
+                return manager::global().flush().map(|()| ExitCode::SUCCESS);
+            }
+            let pid = schedule::spawn_detached(&std::env::args().skip(1).collect::<Vec<_>>())?;
+            println!("{}", pid);
+            eprintln!("✓ Scheduled in {}s (pid {})", delay.as_secs(), pid);
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        // --json の配列は順に送り、ID や操作結果を 1 行ずつ出力する
+        let (wait, print_id) = (self.wait, self.print_id);
+        let mut exit_code = EXIT_SUCCESS;
+        for builder in self.into_builders(Some(&settings), profile.as_ref())? {
+            // --wait の場合は操作結果を stdout と終了コードで返す（スクリプトで分岐できるように）
+            // 複数の通知では、閉じられた・期限切れのものがあればその終了コード（大きい方）
+            if wait {
+                let interaction = builder.send_and_wait()?;
+                println!("{}", interaction);
+                exit_code = exit_code.max(wait_exit_code(&interaction));
+                continue;
+            }
+
+            let handle = match &command {
+                Some(command) => builder.send_with(command)?,
+                None => builder.send()?,
+            };
+
+            // --print-id の場合は、後から --replace-id で置き換えられるよう ID を出力
+            if print_id {
+                if let Some(id) = handle.id() {
+                    println!("{}", id);
+                }
+            }
+            eprintln!(
+                "✓ Toast notification sent successfully ({})",
+                handle.report()
+            );
+        }
+        Ok(ExitCode::from(exit_code))
+    }
+
+    /// 送る代わりに、選ばれるバックエンドと実際の呼び出し（`render()`）を `out` に書き出す
+    ///
+    /// `--backend all` では利用できるバックエンドごとに表示します。複数の場合は空行で区切ります。
+    fn print_dry_run(
+        self,
+        settings: &setup::Settings,
+        profile: Option<&setup::Profile>,
+        command: Option<&CustomCommandNotifier>,
+        out: &mut dyn std::io::Write,
+    ) -> Result<()> {
+        let all = self.backend == Some(BackendChoice::All);
+        let mut rendered = Vec::new();
+        for builder in self.into_builders((!all).then_some(settings), profile)? {
+            if let Some(command) = command {
+                rendered.push(RenderedDelivery {
+                    backend: command.backend_name(),
+                    invocation: command.render(&builder.build())?,
+                });
+            } else if all {
+                for backend in notifier::available_backends() {
+                    rendered.push(builder.clone().backend(backend).render()?);
+                }
+            } else {
+                rendered.push(builder.render()?);
+            }
+        }
+        let text: Vec<String> = rendered.iter().map(ToString::to_string).collect();
+        writeln!(out, "{}", text.join("\n\n"))?;
+        Ok(())
+    }
+
+    /// 利用できる全てのバックエンドに送る（1 つでも失敗すればエラー終了）
+    fn send_to_all(self, profile: Option<&setup::Profile>) -> Result<()> {
+        let print_id = self.print_id;
+        let mut results = Vec::new();
+        for builder in self.into_builders(None, profile)? {
+            results.extend(builder.send_to(&notifier::available_backends())?);
+        }
+        let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+        for (backend, result) in results.iter() {
+            match result {
+                Ok(handle) => {
+                    // --print-id の場合は、どのバックエンドの ID かを添えて出力
+                    if let (true, Some(id)) = (print_id, handle.id()) {
+                        println!("{}\t{}", backend.key(), id);
+                    }
+                    eprintln!("✓ {}", backend.key());
+                }
+                Err(err) => eprintln!("✗ {}: {}", backend.key(), err),
+            }
+        }
+        if failed > 0 {
+            return Err(NotificationError::Other(format!(
+                "{failed} of {} backends failed",
+                results.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// 送る通知の Builder（`--json` の場合は読み込んだ通知ごと）
+    ///
+    /// CLI で指定した項目、`--json` の通知、プロファイル（`profile`）の項目の順に優先します
+    /// （`or_defaults()` で下の層から埋めるため、既定値と同じ値を明示した項目も上書きされません）。
+    /// どれにもバックエンドが無ければ、設定ファイル（`settings`）のバックエンドを使います。
+    fn into_builders(
+        mut self,
+        settings: Option<&setup::Settings>,
+        profile: Option<&setup::Profile>,
+    ) -> Result<Vec<NotificationBuilder>> {
+        let notifications = match self.json.take() {
+            Some(path) => Some(read_notifications(&path, self.allow_exec)?),
+            None => None,
+        };
+        let default_backend = settings
+            .filter(|_| self.backend.is_none())
+            .and_then(|settings| settings.backend.clone());
+        let overrides = self.into_builder();
+        let builders = match notifications {
+            Some(notifications) => notifications
+                .into_iter()
+                .map(|builder| overrides.clone().or_defaults(builder))
+                .collect(),
+            None => vec![overrides],
+        };
+        builders
+            .into_iter()
+            .map(|builder| {
+                let builder = with_profile(builder, profile)?;
+                Ok(match &default_backend {
+                    Some(backend) if !builder.has_backend() => builder.backend(backend.clone()),
+                    _ => builder,
+                })
+            })
+            .collect()
+    }
+}
+
+// ============================================================
+// サブコマンドの実行
+// ============================================================
+
+impl Command {
+    /// サブコマンドを実行する（`send` 以外は成功すれば終了コード 0）
+    pub fn run(self) -> Result<ExitCode> {
+        let result = match self {
+            Command::Send(args) => return args.run(),
+            Command::Exec {
+                tail,
+                title,
+                backend,
+                profile,
+                command,
+            } => return run_exec(&command, tail, title, backend, profile),
+            Command::Dismiss { id, backend } => notifier::dismiss(&id, backend.map(Backend::from)),
+            Command::Simulate { from_file } => {
+                let json = if from_file.as_os_str() == "-" {
+                    std::io::read_to_string(std::io::stdin())?
+                } else {
+                    std::fs::read_to_string(&from_file)?
+                };
+                // シミュレーションはコマンドを実行しないので、on_click_exec も読む
+                let simulation =
+                    notifier::simulate(NotificationBuilder::from_json_with_exec(&json)?);
+                for decision in &simulation.decisions {
+                    println!("{decision}");
+                }
+                println!(
+                    "{:>8}: {}",
+                    "shown",
+                    if simulation.shown { "yes" } else { "no" }
+                );
+                println!("{:>8}: {}", "result", simulation.notification.to_json());
+                Ok(())
+            }
+            Command::RegisterApp {
+                id,
+                display_name,
+                icon,
+            } => {
+                let icon = icon.map(|icon| std::path::absolute(&icon).unwrap_or(icon));
+                notifier::register_app_id(&id, &display_name, icon.as_deref())
+            }
+            Command::Statusbar { format, clear } => {
+                // i3blocks はクリックされると BLOCK_BUTTON を設定してコマンドを再実行する
+                let clicked = format == CliStatusbarFormat::I3blocks
+                    && std::env::var_os("BLOCK_BUTTON").is_some_and(|button| !button.is_empty());
+                if clear || clicked {
+                    statusbar::clear()?;
+                }
+                println!("{}", statusbar::render(format.into(), statusbar::counts()?));
+                Ok(())
+            }
+            Command::Unread { count, keep } => {
+                let unread = store::history::unread()?;
+                if count {
+                    println!("{}", unread.len());
+                } else {
+                    for record in &unread {
+                        println!("{}", serde_json::Value::Object(record.clone()));
+                    }
+                    if !keep && !unread.is_empty() {
+                        store::history::mark_all_seen()?;
+                    }
+                }
+                Ok(())
+            }
+            Command::History {
+                since,
+                topic,
+                urgency,
+                grep,
+                backend,
+                failed,
+                limit,
+                json,
+            } => {
+                let filter = store::history::HistoryFilter {
+                    since: since.map(|age| {
+                        std::time::SystemTime::now()
+                            .checked_sub(age)
+                            .unwrap_or(std::time::UNIX_EPOCH)
+                    }),
+                    topic,
+                    urgency: urgency.map(UrgencyLevel::from),
+                    text: grep,
+                    backend,
+                    failed,
+                    limit: Some(limit),
+                    ..Default::default()
+                };
+                let history = store::history::History::load()?;
+                let utc_offset = platform::local_utc_offset().unwrap_or(0);
+                for record in history.query(&filter) {
+                    if json {
+                        println!("{}", record.to_json());
+                    } else {
+                        println!("{}", history_line(record, utc_offset));
+                    }
+                }
+                Ok(())
+            }
+            Command::Doctor { fix, app_name } => {
+                let checks = doctor::run(app_name.as_deref());
+                for check in &checks {
+                    println!("{check}");
+                }
+                if fix {
+                    for uri in doctor::fix(&checks)? {
+                        println!("opened {uri}");
+                    }
+                }
+                // --fix で設定ページを開いたものは、ユーザーが直すので失敗に数えない
+                let failed = checks
+                    .iter()
+                    .filter(|check| check.status == doctor::Status::Fail)
+                    .filter(|check| !fix || check.fix_uri.is_none())
+                    .count();
+                if failed > 0 {
+                    return Err(NotificationError::Other(format!(
+                        "{failed} check(s) failed"
+                    )));
+                }
+                Ok(())
+            }
+            Command::Targets => {
+                println!("target:   {}", targets::TARGET);
+                println!("platform: {}", crate::platform::detect_platform());
+                for support in targets::report() {
+                    println!("{support}");
+                }
+                Ok(())
+            }
+            Command::Backends { detailed } => {
+                // 組み込みと同じ名前で登録されたものは、組み込みの行に表示される
+                let registered = notifier::registered_backends()
+                    .into_iter()
+                    .filter_map(|name| match name.parse::<Backend>() {
+                        Ok(backend @ Backend::Custom(_)) => Some(targets::support(backend)),
+                        _ => None,
+                    });
+                let supports = targets::report().into_iter().chain(registered);
+                for support in supports {
+                    println!("{support}");
+                    if detailed {
+                        let capabilities = notifier::backend_capabilities(support.backend);
+                        println!("         supports: {capabilities}");
+                    }
+                }
+                Ok(())
+            }
+            Command::Setup => {
+                // ウィザードで決めない項目（タイトルやプラグインの環境変数など）は残す
+                let chosen = setup::run()?;
+                let settings = setup::Settings {
+                    backend: chosen.backend,
+                    record_history: chosen.record_history,
+                    ..setup::load()?
+                };
+                let path = setup::save(&settings)?;
+                println!("wrote {}", path.display());
+                Ok(())
+            }
+            Command::Config { action } => action.run(),
+            Command::Watch {
+                path,
+                pattern,
+                title,
+                backend,
+                profile,
+            } => {
+                if !path.exists() {
+                    return Err(NotificationError::InvalidInput {
+                        field: "path".to_string(),
+                        reason: format!("{} does not exist", path.display()),
+                    });
+                }
+                let settings = SendSettings::load(backend, profile)?;
+                eprintln!("Watching {} (Ctrl+C to stop)", path.display());
+                Watch::new(path, pattern, title).run(|builder| settings.send(builder))
+            }
+            Command::Pipe {
+                pattern,
+                per_line: _,
+                on_eof,
+                title,
+                backend,
+                profile,
+            } => {
+                let settings = SendSettings::load(backend, profile)?;
+                let mode = if on_eof {
+                    pipe::Mode::OnEof
+                } else {
+                    pipe::Mode::PerLine
+                };
+                pipe::run(
+                    std::io::stdin().lock(),
+                    pattern.as_ref(),
+                    mode,
+                    title.as_deref(),
+                    |builder| settings.send(builder),
+                )
+                .map(drop)
+            }
+            Command::Completions { shell } => print_completions(shell, &mut std::io::stdout()),
+            Command::Man => print_man(&mut std::io::stdout()),
+            Command::Rules { action } => action.run(),
+            Command::Store { action } => action.run(),
+            Command::Schedule { action } => action.run(),
+        };
+        result.map(|()| ExitCode::SUCCESS)
+    }
+}
+
+impl ConfigCommand {
+    /// 設定ファイルの操作を実行し、結果を stdout に表示する
+    fn run(self) -> Result<()> {
+        match self {
+            ConfigCommand::Path => println!("{}", setup::path().display()),
+            ConfigCommand::Init { force } => {
+                let path = setup::init(force)?;
+                println!("wrote {}", path.display());
+            }
+            ConfigCommand::Validate => {
+                for warning in setup::validate()? {
+                    eprintln!("warning: {warning}");
+                }
+                println!("✓ {} is valid", setup::path().display());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RulesCommand {
+    /// ルールのテストを実行し、結果を stdout に表示する
+    ///
+    /// 失敗したケースがあればエラーを返す（CI で終了コードを見られるように）。
+    fn run(self) -> Result<()> {
+        match self {
+            RulesCommand::Test { file } => {
+                let results: Vec<_> = rules::load(&file)?.iter().map(rules::run).collect();
+                for result in &results {
+                    if result.passed() {
+                        println!("ok   {}", result.name);
+                    } else {
+                        println!("FAIL {}", result.name);
+                        for failure in &result.failures {
+                            println!("       {failure}");
+                        }
+                    }
+                }
+
+                let failed = results.iter().filter(|r| !r.passed()).count();
+                println!("{} passed, {} failed", results.len() - failed, failed);
+                if failed > 0 {
+                    return Err(NotificationError::Other(format!(
+                        "{failed} rule test(s) failed"
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl StoreCommand {
+    /// ストア操作を実行し、結果を stdout に表示する
+    fn run(self) -> Result<()> {
+        match self {
+            StoreCommand::Check { path } => {
+                let mut unhealthy = 0;
+                for path in target_journals(path) {
+                    let report = store::check(&path)?;
+                    println!("{}: {}", path.display(), describe_report(&report));
+                    if !report.is_healthy() {
+                        unhealthy += 1;
+                    }
+                }
+                if unhealthy > 0 {
+                    return Err(NotificationError::Other(format!(
+                        "{unhealthy} journal(s) need repair; run `rust-toast store repair`"
+                    )));
+                }
+                Ok(())
+            }
+            StoreCommand::Repair { path } => {
+                for path in target_journals(path) {
+                    let report = store::repair(&path)?;
+                    if report.is_healthy() {
+                        println!("{}: ok, nothing to repair", path.display());
+                    } else {
+                        println!(
+                            "{}: repaired, kept {} record(s)",
+                            path.display(),
+                            report.valid_records
+                        );
+                    }
+                }
+                Ok(())
+            }
+            StoreCommand::Flush => {
+                let sent = notifier::flush_spool()?;
+                println!("sent {sent} spooled notification(s)");
+                Ok(())
+            }
+            StoreCommand::Migrate { path, dry_run } => {
+                for path in target_journals(path) {
+                    match store::migrate::migrate(&path, dry_run)? {
+                        None => println!(
+                            "{}: already at v{}",
+                            path.display(),
+                            store::journal::FORMAT_VERSION
+                        ),
+                        Some(plan) => println!("{}", describe_plan(&plan, dry_run)),
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ScheduleCommand {
+    /// 予定の操作を実行し、結果を stdout に表示する
+    fn run(self) -> Result<()> {
+        match self {
+            ScheduleCommand::Add {
+                every,
+                cron,
+                utc_offset,
+                args,
+            } => {
+                let recurrence = match (every, cron) {
+                    (Some(interval), _) if interval.is_zero() => {
+                        return Err(NotificationError::Other(
+                            "--every must be longer than zero".to_string(),
+                        ))
+                    }
+                    (Some(interval), _) => Recurrence::Every(interval),
+                    (None, Some(cron)) => Recurrence::Cron(cron.utc_offset(
+                        utc_offset.unwrap_or_else(|| platform::local_utc_offset().unwrap_or(0)),
+                    )),
+                    (None, None) => unreachable!("clap requires --every or --cron"),
+                };
+                // 実行時に失敗しないよう、通知の引数を今のうちに確かめる
+                Args::try_parse_from(std::iter::once("rust-toast".to_string()).chain(args.clone()))
+                    .map_err(|err| {
+                        // clap のエラーは使い方の説明が続くので、最初の行だけにする
+                        let reason = err.to_string();
+                        let reason = reason.lines().next().unwrap_or_default().to_string();
+                        NotificationError::Other(format!("invalid arguments: {reason}"))
+                    })?;
+                let id = store::schedules::add(&recurrence, &args)?;
+                println!("{id}");
+                eprintln!("✓ Scheduled {recurrence}; keep `rust-toast schedule run` running");
+                Ok(())
+            }
+            ScheduleCommand::List => {
+                for schedule in store::schedules::list()? {
+                    println!(
+                        "{}\t{}\t{}",
+                        schedule.id,
+                        schedule.recurrence,
+                        schedule.args.join(" ")
+                    );
+                }
+                Ok(())
+            }
+            ScheduleCommand::Remove { id } => {
+                if !store::schedules::remove(id)? {
+                    return Err(NotificationError::Other(format!(
+                        "no schedule with ID {id}"
+                    )));
+                }
+                Ok(())
+            }
+            ScheduleCommand::Run => recurring::run(),
+            ScheduleCommand::Unit { hardened } => {
+                let data_dir = store::data_dir();
+                let data_dir = std::path::absolute(&data_dir).unwrap_or(data_dir);
+                print!(
+                    "{}",
+                    recurring::systemd_unit(&std::env::current_exe()?, &data_dir, hardened)
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 移行内容を 1 行の説明に変換
+fn describe_plan(plan: &store::MigrationPlan, dry_run: bool) -> String {
+    let mut line = format!(
+        "{}: v{} -> v{}, {} record(s)",
+        plan.path.display(),
+        plan.from_version,
+        plan.to_version,
+        plan.records
+    );
+    if plan.dropped_records > 0 {
+        line.push_str(&format!(
+            ", {} unreadable record(s) dropped",
+            plan.dropped_records
+        ));
+    }
+    if dry_run {
+        line.push_str(" (dry run, nothing written)");
+    } else {
+        line.push_str(&format!(", backup at {}", plan.backup_path.display()));
+    }
+    line
+}
+
+/// 対象のジャーナル一覧（指定が無ければデータディレクトリ内の既存ジャーナル）
+fn target_journals(path: Option<PathBuf>) -> Vec<PathBuf> {
+    match path {
+        Some(path) => vec![path],
+        None => {
+            let paths: Vec<PathBuf> = store::JOURNALS
+                .iter()
+                .map(|name| store::journal_path(name))
+                .filter(|path| path.exists())
+                .collect();
+            if paths.is_empty() {
+                println!("No journals found in {}", store::data_dir().display());
+            }
+            paths
+        }
+    }
+}
+
+/// チェック結果を 1 行の説明に変換
+fn describe_report(report: &store::CheckReport) -> String {
+    if report.is_healthy() {
+        return format!("ok, {} record(s)", report.valid_records);
+    }
+    let mut problems = Vec::new();
+    if report.corrupt_records > 0 {
+        problems.push(format!("{} corrupt record(s)", report.corrupt_records));
+    }
+    if report.torn_tail {
+        problems.push("torn tail".to_string());
+    }
+    format!(
+        "damaged, {} valid record(s), {}",
+        report.valid_records,
+        problems.join(", ")
+    )
+}
+
+// ============================================================
+// ログの表示
+// ============================================================
+
+/// ライブラリのログ（`tracing` から `log` に流れたもの）を stderr に `(メッセージ)` の形で出す
+///
+/// 依存クレート（zbus など）のログと、span の出入りは表示しません。
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.target().starts_with("rust_toast") && metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if self.enabled(record.metadata()) {
+            eprintln!("({})", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// CLI のログの表示を始める（`verbose` なら debug も、そうでなければ info 以上）
+///
+/// ライブラリはログを `tracing` で出すだけなので、組み込んだアプリケーションの
+/// stderr には何も書きません。CLI だけがここで表示先を用意します。
+/// 2 回目以降は表示する水準だけを変えます（引数の解析の前後で呼ぶため）。
+pub fn init_logging(verbose: bool) {
+    let level = if verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    // 表示先は最初の 1 回だけ設定できる（2 回目以降の失敗は無視する）
+    let _ = log::set_logger(&StderrLogger);
+    log::set_max_level(level);
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urgency_conversion() {
+        assert_eq!(UrgencyLevel::from(CliUrgencyLevel::Low), UrgencyLevel::Low);
         assert_eq!(
             UrgencyLevel::from(CliUrgencyLevel::Normal),
             UrgencyLevel::Normal
@@ -182,31 +2207,797 @@ mod tests {
 
     #[test]
     fn test_backend_conversion() {
-        assert_eq!(Platform::from(CliBackend::Linux), Platform::Linux);
-        assert_eq!(Platform::from(CliBackend::Windows), Platform::Windows);
-        assert_eq!(Platform::from(CliBackend::Macos), Platform::MacOs);
+        assert_eq!(
+            Backend::from(CliBackend::Linux),
+            Backend::Native(Platform::Linux)
+        );
+        assert_eq!(
+            Backend::from(CliBackend::Windows),
+            Backend::Native(Platform::Windows)
+        );
+        assert_eq!(
+            Backend::from(CliBackend::Macos),
+            Backend::Native(Platform::MacOs)
+        );
+        assert_eq!(Backend::from(CliBackend::Dialog), Backend::Dialog);
+        assert_eq!(Backend::from(CliBackend::Console), Backend::Console);
+        assert_eq!(Backend::from(CliBackend::Stdout), Backend::Stdout);
     }
 
     #[test]
     fn test_args_into_builder() {
         // Args を手動で構築（通常は clap::Parser::parse() で取得）
-        let args = Args {
-            title: "Test".to_string(),
-            message: "Hello".to_string(),
-            timeout: 1000,
-            icon: "icon.png".to_string(),
-            urgency: CliUrgencyLevel::Critical,
-            subtitle: "Sub".to_string(),
-            sound: "Ping".to_string(),
-            backend: Some(CliBackend::Macos),
+        let args = SendArgs {
+            title: Some("Test".to_string()),
+            title_template: None,
+            hostname_prefix: None,
+            message: Some("Hello".to_string()),
+            stdin: false,
+            json: None,
+            markdown: false,
+            timeout: Some(Duration::from_secs(1)),
+            icon: Some("icon.png".to_string()),
+            image: Some(PathBuf::from("/tmp/chart.png")),
+            category: Some("email.arrived".to_string()),
+            app_name: Some("my-tool".to_string()),
+            topic: Some("ci/build".to_string()),
+            urgency: Some(CliUrgencyLevel::Critical),
+            priority: Some(7),
+            subtitle: Some("Sub".to_string()),
+            sound: Some("Ping".to_string()),
+            backend: Some(BackendChoice::One(CliBackend::Macos.into())),
+            backend_command: None,
+            profile: None,
+            actions: vec![Action::new("open", "Open")],
+            reply: Some("Reply".to_string()),
+            wait: false,
+            require_ack: false,
+            repeat_sound: None,
+            transient: false,
+            resident: true,
+            private: true,
+            on_click_url: Some("https://example.com".to_string()),
+            on_click_exec: Some("less build.log".to_string()),
+            allow_exec: false,
+            attribution: Some("via make".to_string()),
+            scenario: Some(CliScenario::IncomingCall),
+            silent: false,
+            loop_sound: true,
+            respect_dnd: true,
+            force: true,
+            template: false,
+            vars: Vec::new(),
+            hints: vec![("fgcolor".to_string(), "#ff0000".to_string())],
+            group: Some("build".to_string()),
+            collection: Some("ci".to_string()),
+            replace_id: Some("42".to_string()),
+            print_id: false,
+            dry_run: false,
+            at: None,
+            after: None,
+            capture_sender: false,
+            traceparent: "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                .parse()
+                .ok(),
+            record_history: false,
+            redact: false,
+            spool: false,
+            retries: Some(3),
+            overflow: Some(CliOverflow::Split),
         };
 
         let notification = args.into_builder().build();
+        assert_eq!(notification.retry.retries, 3);
+        assert_eq!(notification.overflow, Some(Overflow::Split));
 
         assert_eq!(notification.title, "Test");
         assert_eq!(notification.message, "Hello");
         assert_eq!(notification.timeout, 1000);
         assert_eq!(notification.urgency, UrgencyLevel::Critical);
-        assert_eq!(notification.backend_override, Some(Platform::MacOs));
+        assert_eq!(notification.priority, 7);
+        assert_eq!(
+            notification.backend_override,
+            Some(Backend::Native(Platform::MacOs))
+        );
+        assert_eq!(notification.actions, vec![Action::new("open", "Open")]);
+        assert_eq!(notification.reply_placeholder.as_deref(), Some("Reply"));
+        assert_eq!(notification.replace_id.as_deref(), Some("42"));
+        assert_eq!(notification.group.as_deref(), Some("build"));
+        assert_eq!(notification.collection.as_deref(), Some("ci"));
+        assert_eq!(
+            notification.trace_context.map(|c| c.span_id),
+            Some(0x00f067aa0ba902b7)
+        );
+        assert_eq!(notification.image, Some(PathBuf::from("/tmp/chart.png")));
+        assert_eq!(notification.category.as_deref(), Some("email.arrived"));
+        assert_eq!(notification.app_name.as_deref(), Some("my-tool"));
+        assert_eq!(notification.topic.as_deref(), Some("ci/build"));
+        assert_eq!(notification.hints["fgcolor"], "#ff0000");
+        assert!(notification.resident);
+        assert!(notification.private);
+        assert_eq!(
+            notification.on_click_url.as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            notification.on_click_exec.as_deref(),
+            Some("less build.log")
+        );
+        assert_eq!(notification.attribution.as_deref(), Some("via make"));
+        assert_eq!(notification.scenario, Some(Scenario::IncomingCall));
+        assert!(notification.loop_sound);
+        assert!(notification.respect_dnd);
+        assert!(notification.time_sensitive);
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(parse_action("yes=Deploy"), Ok(Action::new("yes", "Deploy")));
+        assert_eq!(parse_action("OK"), Ok(Action::new("OK", "OK")));
+        assert!(parse_action("=Deploy").is_err());
+    }
+
+    #[test]
+    fn test_parse_hint() {
+        assert_eq!(
+            parse_hint("x-dunst-stack-tag=build"),
+            Ok(("x-dunst-stack-tag".to_string(), "build".to_string()))
+        );
+        assert_eq!(parse_hint("key="), Ok(("key".to_string(), String::new())));
+        assert!(parse_hint("novalue").is_err());
+        assert!(parse_hint("=x").is_err());
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let mut args = Args::try_parse_from([
+            "rust-toast",
+            "-t",
+            "{job}",
+            "-m",
+            "exit {exit_code} {{ok}}",
+            "--var",
+            "job=backup",
+            "--var=exit_code=3",
+        ])
+        .unwrap();
+        args.send.expand_template().unwrap();
+        assert_eq!(args.send.title.as_deref(), Some("backup"));
+        assert_eq!(args.send.message.as_deref(), Some("exit 3 {ok}"));
+
+        // --template も --var も無ければ展開しない
+        let mut args = Args::try_parse_from(["rust-toast", "-m", "{json: true}"]).unwrap();
+        args.send.expand_template().unwrap();
+        assert_eq!(args.send.message.as_deref(), Some("{json: true}"));
+        let mut args = Args::try_parse_from(["rust-toast", "--template", "-m", "{nope}"]).unwrap();
+        assert!(args.send.expand_template().is_err());
+    }
+
+    #[test]
+    fn test_stdin_flag_replaces_message() {
+        let args = Args::try_parse_from(["rust-toast", "--stdin", "-t", "Done"]).unwrap();
+        assert!(args.send.stdin);
+        assert!(Args::try_parse_from(["rust-toast", "--stdin", "-m", "x"]).is_err());
+        assert!(Args::try_parse_from(["rust-toast", "--stdin", "--after", "5m"]).is_err());
+
+        // `-m -` は標準入力を読まず、"-" をそのまま送る
+        let mut args = Args::try_parse_from(["rust-toast", "-m", "-"]).unwrap();
+        args.send.read_stdin().unwrap();
+        assert_eq!(args.send.message.as_deref(), Some("-"));
+    }
+
+    #[test]
+    fn test_json_notifications_take_cli_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.json");
+        std::fs::write(
+            &path,
+            r#"[{"title":"Build","message":"ok","urgency":"low"},{"message":"deploy","backend":"console"}]"#,
+        )
+        .unwrap();
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "send",
+            "--json",
+            path.to_str().unwrap(),
+            "--topic",
+            "ci",
+        ])
+        .unwrap();
+        let Some(Command::Send(send)) = args.command else {
+            panic!("expected send");
+        };
+        let notifications: Vec<_> = send
+            .into_builders(None, None)
+            .unwrap()
+            .into_iter()
+            .map(|builder| builder.build())
+            .collect();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].title, "Build");
+        assert_eq!(notifications[0].urgency, UrgencyLevel::Low);
+        assert_eq!(notifications[1].message, "deploy");
+        assert_eq!(notifications[1].backend_override, Some(Backend::Console));
+        assert!(notifications
+            .iter()
+            .all(|n| n.topic.as_deref() == Some("ci")));
+
+        // 既定値と同じ値でも、指定したオプションは JSON の値より優先する
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "send",
+            "--json",
+            path.to_str().unwrap(),
+            "-u",
+            "normal",
+        ])
+        .unwrap();
+        let Some(Command::Send(send)) = args.command else {
+            panic!("expected send");
+        };
+        let first = send.into_builders(None, None).unwrap().remove(0).build();
+        assert_eq!(first.urgency, UrgencyLevel::Normal);
+        assert_eq!(first.title, "Build");
+
+        assert!(Args::try_parse_from(["rust-toast", "--json", "--stdin"]).is_err());
+        assert!(Args::try_parse_from(["rust-toast", "-m", "hi", "--allow-exec"]).is_err());
+    }
+
+    #[test]
+    fn test_profile_defaults_yield_to_cli() {
+        let settings = setup::Settings::from_toml(
+            r#"
+            [profile.builds]
+            title = "CI"
+            urgency = "critical"
+            backend = "console"
+            "#,
+        )
+        .unwrap();
+        let profile = settings.profile("builds").unwrap();
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "-m",
+            "done",
+            "--profile",
+            "builds",
+            "--title",
+            "Nightly",
+        ])
+        .unwrap();
+        assert_eq!(args.send.profile.as_deref(), Some("builds"));
+        let notifications: Vec<_> = args
+            .send
+            .into_builders(Some(&settings), Some(profile))
+            .unwrap()
+            .into_iter()
+            .map(|builder| builder.build())
+            .collect();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].title, "Nightly");
+        assert_eq!(notifications[0].message, "done");
+        assert_eq!(notifications[0].urgency, UrgencyLevel::Critical);
+        assert_eq!(notifications[0].backend_override, Some(Backend::Console));
+
+        // 既定値と同じ緊急度でも、明示すればプロファイルより優先する
+        let args = Args::try_parse_from(["rust-toast", "-m", "done", "-u", "normal"]).unwrap();
+        let notification = args
+            .send
+            .into_builders(Some(&settings), Some(profile))
+            .unwrap()
+            .remove(0)
+            .build();
+        assert_eq!(notification.urgency, UrgencyLevel::Normal);
+        assert_eq!(notification.title, "CI");
+
+        // exec などが組み立てた通知も同じ規則で重ねる
+        let builder = NotificationBuilder::new()
+            .message("✓ make")
+            .urgency(UrgencyLevel::Normal);
+        let notification = with_profile(builder, Some(profile)).unwrap().build();
+        assert_eq!(notification.urgency, UrgencyLevel::Normal);
+        assert_eq!(notification.backend_override, Some(Backend::Console));
+    }
+
+    #[test]
+    fn test_wait_exit_codes() {
+        assert_eq!(wait_exit_code(&Interaction::Clicked), 0);
+        assert_eq!(
+            wait_exit_code(&Interaction::ActionInvoked("open".to_string())),
+            0
+        );
+        assert_eq!(wait_exit_code(&Interaction::Replied("ok".to_string())), 0);
+        assert_eq!(wait_exit_code(&Interaction::Dismissed), EXIT_DISMISSED);
+        assert_eq!(wait_exit_code(&Interaction::Expired), EXIT_EXPIRED);
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_timeout("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_timeout("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_timeout("5000"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_timeout("never"), Ok(Duration::ZERO));
+        assert!(parse_timeout("5 minutes").is_err());
+        assert!(parse_timeout("s").is_err());
+        assert!(parse_timeout("18446744073709551615h").is_err());
+    }
+
+    #[test]
+    fn test_schedule_add_arguments() {
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "schedule",
+            "add",
+            "--cron",
+            "0 9 * * 1-5",
+            "--utc-offset",
+            "-300",
+            "--",
+            "-m",
+            "Stand up",
+        ])
+        .unwrap();
+        let Some(Command::Schedule {
+            action:
+                ScheduleCommand::Add {
+                    every,
+                    cron,
+                    utc_offset,
+                    args,
+                },
+        }) = args.command
+        else {
+            panic!("expected schedule add");
+        };
+        assert_eq!(every, None);
+        assert_eq!(cron.unwrap().source(), "0 9 * * 1-5");
+        assert_eq!(utc_offset, Some(-300));
+        assert_eq!(args, ["-m", "Stand up"]);
+
+        // --every と --cron のどちらか 1 つが必要
+        assert!(Args::try_parse_from(["rust-toast", "schedule", "add", "--", "-m", "x"]).is_err());
+
+        let args = Args::try_parse_from(["rust-toast", "schedule", "unit", "--hardened"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Schedule {
+                action: ScheduleCommand::Unit { hardened: true }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_after_flag() {
+        let args = Args::try_parse_from(["rust-toast", "-m", "Tea", "--after", "3m"]).unwrap();
+        assert_eq!(args.send.after, Some(Duration::from_secs(180)));
+        assert!(Args::try_parse_from(["rust-toast", "-m", "Tea", "--after", "never"]).is_err());
+        assert!(
+            Args::try_parse_from(["rust-toast", "-m", "Tea", "--after", "3m", "--wait"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_store_subcommand_does_not_require_message() {
+        let args = Args::try_parse_from(["rust-toast", "store", "check"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Store {
+                action: StoreCommand::Check { path: None }
+            })
+        ));
+        assert!(Args::try_parse_from(["rust-toast"]).is_err());
+    }
+
+    #[test]
+    fn test_backend_accepts_registered_plugins() {
+        let args = Args::try_parse_from(["rust-toast", "-m", "x", "--backend", "macos"]).unwrap();
+        assert_eq!(
+            args.send.backend,
+            Some(BackendChoice::One(Backend::Native(Platform::MacOs)))
+        );
+        let parse = || Args::try_parse_from(["rust-toast", "-m", "x", "--backend", "cli-pager"]);
+        assert!(parse().is_err());
+
+        notifier::register_backend("cli-pager", Box::new(notifier::ConsoleNotifier));
+        let args = parse().unwrap();
+        assert_eq!(
+            args.send.backend,
+            Some(BackendChoice::One(Backend::Custom("cli-pager".to_string())))
+        );
+        notifier::unregister_backend("cli-pager");
+
+        let args = Args::try_parse_from(["rust-toast", "-m", "x", "--backend", "all"]).unwrap();
+        assert_eq!(args.send.backend, Some(BackendChoice::All));
+    }
+
+    #[test]
+    fn test_send_subcommand_matches_bare_form() {
+        let args = Args::try_parse_from(["rust-toast", "send", "-m", "Done", "-u", "low"]).unwrap();
+        let Some(Command::Send(send)) = args.command else {
+            panic!("expected send");
+        };
+        assert_eq!(send.message.as_deref(), Some("Done"));
+        assert!(matches!(send.urgency, Some(CliUrgencyLevel::Low)));
+
+        assert!(Args::try_parse_from(["rust-toast", "send"]).is_err());
+        assert!(Args::try_parse_from(["rust-toast", "-m", "Done", "backends"]).is_err());
+    }
+
+    #[test]
+    fn test_dismiss_subcommand() {
+        let args =
+            Args::try_parse_from(["rust-toast", "dismiss", "42", "--backend", "linux"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Dismiss { ref id, backend: Some(CliBackend::Linux) }) if id == "42"
+        ));
+    }
+
+    #[test]
+    fn test_register_app_subcommand() {
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "register-app",
+            "MyCompany.MyTool",
+            "--display-name",
+            "My Tool",
+        ])
+        .unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::RegisterApp { ref id, ref display_name, icon: None })
+                if id == "MyCompany.MyTool" && display_name == "My Tool"
+        ));
+    }
+
+    #[test]
+    fn test_statusbar_subcommand() {
+        let args =
+            Args::try_parse_from(["rust-toast", "statusbar", "--format", "i3blocks"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Statusbar {
+                format: CliStatusbarFormat::I3blocks,
+                clear: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unread_subcommand() {
+        let args = Args::try_parse_from(["rust-toast", "unread", "--keep"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Unread {
+                count: false,
+                keep: true
+            })
+        ));
+    }
+
+    #[test]
+    fn test_history_subcommand() {
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "history",
+            "--since",
+            "2h",
+            "--topic",
+            "ci/*",
+            "--failed",
+        ])
+        .unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::History {
+                since: Some(since),
+                ref topic,
+                failed: true,
+                limit: 20,
+                json: false,
+                ..
+            }) if since == Duration::from_secs(2 * 60 * 60) && topic.as_deref() == Some("ci/*")
+        ));
+
+        // 記録時刻（UNIX 時刻 1 秒）をローカルタイムで表示する
+        let entries = store::journal::entries_from(&[
+            r#"{"title":"Build","message":"passed"}"#,
+            r#"{"event":"result","seq":1,"backend":"Console","id":null,"error":null}"#,
+        ]);
+        let history = store::history::History::from_entries(&entries);
+        let record = &history.records()[0];
+        assert_eq!(
+            history_line(record, 540),
+            "1970-01-01 09:00:01 Console ✓ Build: passed"
+        );
+        assert_eq!(
+            history_line(record, 0),
+            "1970-01-01 00:00:01 Console ✓ Build: passed"
+        );
+    }
+
+    #[test]
+    fn test_verbose_applies_to_subcommands() {
+        let args = Args::try_parse_from(["rust-toast", "backends", "--verbose"]).unwrap();
+        assert!(args.verbose);
+        assert!(
+            !Args::try_parse_from(["rust-toast", "-m", "x"])
+                .unwrap()
+                .verbose
+        );
+    }
+
+    #[test]
+    fn test_completions_include_backends_and_sounds() {
+        let args = Args::try_parse_from(["rust-toast", "completions", "fish"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Completions {
+                shell: clap_complete::Shell::Fish
+            })
+        ));
+        let mut script = Vec::new();
+        print_completions(clap_complete::Shell::Bash, &mut script).unwrap();
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("COMPLETE=\"bash\""), "{script}");
+
+        // 候補はスクリプトではなく、補完のたびに Args::command() から作る
+        let candidates = |args: &[&str]| {
+            let args: Vec<_> = args.iter().map(std::ffi::OsString::from).collect();
+            let index = args.len() - 1;
+            clap_complete::engine::complete(&mut Args::command(), args, index, None)
+                .unwrap()
+                .iter()
+                .map(|candidate| candidate.get_value().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert!(candidates(&["rust-toast", "--backend", ""]).contains(&"dialog".to_string()));
+        assert!(candidates(&["rust-toast", "--sound", "So"]).contains(&"Sosumi".to_string()));
+
+        // 補完の候補にしても、任意の通知音を受け付ける
+        let args = Args::try_parse_from(["rust-toast", "-m", "x", "--sound", "/tmp/ding.wav"]);
+        assert_eq!(args.unwrap().send.sound.as_deref(), Some("/tmp/ding.wav"));
+    }
+
+    #[test]
+    fn test_man_page_sections() {
+        let mut page = Vec::new();
+        print_man(&mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        for section in [
+            ".TH rust-toast",
+            ".SH OPTIONS",
+            ".SH BACKENDS",
+            ".SH EXIT STATUS",
+        ] {
+            assert!(page.contains(section), "missing {section}");
+        }
+        assert!(page.contains("\\fBconsole\\fR"), "{page}");
+        for variable in [setup::CONFIG_ENV, store::DATA_DIR_ENV, COMPLETE_ENV] {
+            assert!(page.contains(variable), "missing {variable}");
+        }
+    }
+
+    #[test]
+    fn test_exec_subcommand_takes_command_after_separator() {
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "exec",
+            "--tail",
+            "3",
+            "--",
+            "cargo",
+            "build",
+            "--release",
+        ])
+        .unwrap();
+        let Some(Command::Exec { tail, command, .. }) = args.command else {
+            panic!("expected exec");
+        };
+        assert_eq!(tail, 3);
+        assert_eq!(command, ["cargo", "build", "--release"]);
+        assert!(Args::try_parse_from(["rust-toast", "exec"]).is_err());
+    }
+
+    #[test]
+    fn test_watch_subcommand() {
+        let args = Args::try_parse_from([
+            "rust-toast",
+            "watch",
+            "app.log",
+            "--pattern",
+            "ERROR|panicked",
+        ])
+        .unwrap();
+        let Some(Command::Watch { path, pattern, .. }) = args.command else {
+            panic!("expected watch");
+        };
+        assert_eq!(path, PathBuf::from("app.log"));
+        assert!(pattern.unwrap().is_match("thread 'main' panicked"));
+        assert!(Args::try_parse_from(["rust-toast", "watch", ".", "--pattern", "("]).is_err());
+    }
+
+    #[test]
+    fn test_backend_command_conflicts_with_wait() {
+        let parse = |extra: &str| {
+            Args::try_parse_from([
+                "rust-toast",
+                "-m",
+                "x",
+                "--backend-command",
+                "herbe {message}",
+                extra,
+            ])
+        };
+        assert!(parse("--wait").is_err());
+        assert!(parse("--print-id").is_ok());
+    }
+
+    #[test]
+    fn test_dry_run_conflicts_with_delivery_flags() {
+        let args = Args::try_parse_from(["rust-toast", "-m", "hi", "--dry-run"]).unwrap();
+        assert!(args.send.dry_run);
+        for flag in ["--wait", "--print-id"] {
+            assert!(Args::try_parse_from(["rust-toast", "-m", "hi", "--dry-run", flag]).is_err());
+        }
+    }
+
+    fn dry_run(args: &[&str]) -> String {
+        let args = Args::try_parse_from(args).unwrap();
+        let mut out = Vec::new();
+        args.send
+            .print_dry_run(&setup::Settings::default(), None, None, &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_prints_backend_and_invocation() {
+        let out = dry_run(&[
+            "rust-toast",
+            "-t",
+            "Build",
+            "-m",
+            "done",
+            "--backend",
+            "console",
+            "--dry-run",
+        ]);
+        let (backend, invocation) = out.split_once('\n').unwrap();
+        assert_eq!(backend, "# backend: Console");
+        assert!(
+            invocation.contains("Build") && invocation.contains("done"),
+            "{out}"
+        );
+        // 同じ入力なら同じ出力になる（プロセスの起動や乱数に依らない）
+        assert_eq!(
+            out,
+            dry_run(&[
+                "rust-toast",
+                "-t",
+                "Build",
+                "-m",
+                "done",
+                "--backend",
+                "console",
+                "--dry-run",
+            ])
+        );
+
+        let out = dry_run(&[
+            "rust-toast",
+            "-t",
+            "Build",
+            "-m",
+            "done",
+            "-u",
+            "critical",
+            "--backend",
+            "stdout",
+            "--dry-run",
+        ]);
+        let (backend, invocation) = out.split_once('\n').unwrap();
+        assert_eq!(backend, "# backend: Stdout (JSON)");
+        let json: serde_json::Value = serde_json::from_str(invocation).unwrap();
+        assert_eq!(json["title"], "Build");
+        assert_eq!(json["message"], "done");
+        assert_eq!(json["urgency"], "critical");
+    }
+
+    #[test]
+    fn test_pipe_subcommand() {
+        let args =
+            Args::try_parse_from(["rust-toast", "pipe", "--match", "ERROR", "--on-eof"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Pipe {
+                pattern: Some(_),
+                on_eof: true,
+                ..
+            })
+        ));
+        assert!(Args::try_parse_from(["rust-toast", "pipe", "--per-line", "--on-eof"]).is_err());
+    }
+
+    #[test]
+    fn test_config_subcommand() {
+        let args = Args::try_parse_from(["rust-toast", "config", "init", "--force"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Config {
+                action: ConfigCommand::Init { force: true }
+            })
+        ));
+        assert!(Args::try_parse_from(["rust-toast", "config"]).is_err());
+    }
+
+    #[test]
+    fn test_backends_subcommand() {
+        let args = Args::try_parse_from(["rust-toast", "backends", "--detailed"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Backends { detailed: true })
+        ));
+    }
+
+    #[test]
+    fn test_simulate_subcommand() {
+        let args =
+            Args::try_parse_from(["rust-toast", "simulate", "--from-file", "notif.json"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Simulate { ref from_file }) if from_file == &PathBuf::from("notif.json")
+        ));
+    }
+
+    #[test]
+    fn test_rules_test_subcommand() {
+        let args =
+            Args::try_parse_from(["rust-toast", "rules", "test", "rules_test.toml"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Rules {
+                action: RulesCommand::Test { ref file }
+            }) if file == &PathBuf::from("rules_test.toml")
+        ));
+    }
+
+    #[test]
+    fn test_describe_plan_dry_run() {
+        let plan = store::MigrationPlan {
+            path: PathBuf::from("queue.wal"),
+            from_version: 1,
+            to_version: 2,
+            records: 4,
+            dropped_records: 0,
+            backup_path: PathBuf::from("queue.wal.v1.bak"),
+        };
+        assert_eq!(
+            describe_plan(&plan, true),
+            "queue.wal: v1 -> v2, 4 record(s) (dry run, nothing written)"
+        );
+
+        let plan = store::MigrationPlan {
+            dropped_records: 2,
+            ..plan
+        };
+        assert_eq!(
+            describe_plan(&plan, false),
+            "queue.wal: v1 -> v2, 4 record(s), 2 unreadable record(s) dropped, backup at queue.wal.v1.bak"
+        );
+    }
+
+    #[test]
+    fn test_describe_report() {
+        let report = store::CheckReport {
+            valid_records: 3,
+            corrupt_records: 1,
+            torn_tail: true,
+        };
+        assert_eq!(
+            describe_report(&report),
+            "damaged, 3 valid record(s), 1 corrupt record(s), torn tail"
+        );
     }
 }
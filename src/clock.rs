@@ -0,0 +1,179 @@
+//! 時計と待機の抽象化
+//!
+//! 現在時刻の取得と一定時間の待機を `Clock` トレイト経由で行います。
+//! 時刻に依存する処理（ジャーナルの記録時刻など）は全て `clock::current()` を使うため、
+//! テストでは `ManualClock` に差し替えて、時間の経過を決定的に再現できます。
+//!
+//! # 例
+//! ```
+//! use std::sync::Arc;
+//! use std::time::{Duration, UNIX_EPOCH};
+//! use rust_toast::clock::{self, Clock, ManualClock};
+//!
+//! let manual = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_000)));
+//! clock::set(manual.clone());
+//!
+//! manual.advance(Duration::from_secs(60));
+//! assert_eq!(clock::current().now(), UNIX_EPOCH + Duration::from_secs(1_060));
+//! # clock::set(Arc::new(clock::SystemClock));
+//! ```
+//!
+//! # 学習ポイント
+//! - `Arc<dyn Trait>` によるトレイトオブジェクトの共有
+//! - `Send + Sync` 境界（スレッド間で共有できる型の条件）
+//! - テストのための依存性の注入
+
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 時刻の取得と待機を提供するトレイト
+///
+/// `Journal` のように `Debug` を derive する型にも持たせられるよう、`Debug` を要求します。
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// 現在時刻
+    fn now(&self) -> SystemTime;
+
+    /// `duration` だけ待つ
+    fn sleep(&self, duration: Duration);
+
+    /// 現在時刻（UNIX エポックからのミリ秒）
+    fn now_ms(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64)
+    }
+}
+
+// ============================================================
+// 実装
+// ============================================================
+
+/// OS の時計を使う実装（デフォルト）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// 手動で進める時計（テスト用）
+///
+/// `sleep()` は実際には待たず、その分だけ時刻を進めます。
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<SystemTime>,
+}
+
+impl ManualClock {
+    /// 指定した時刻から始まる時計を作成
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            now: Mutex::new(start),
+        }
+    }
+
+    /// 時刻を `duration` だけ進める
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+
+    /// 時刻を設定する
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// 呼ばれるたびに `clock::current()` に任せる実装
+///
+/// プロセス全体で共有する記録（送信数の上限・重複など）は最初の利用時に作るため、
+/// 時計を直接持つと、その後の `clock::set()` による差し替えに従いません。
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use rust_toast::clock::{self, Clock, CurrentClock, ManualClock};
+///
+/// let manual = Arc::new(ManualClock::new(UNIX_EPOCH));
+/// clock::set(manual.clone());
+///
+/// CurrentClock.sleep(Duration::from_secs(5));
+/// assert_eq!(manual.now(), UNIX_EPOCH + Duration::from_secs(5));
+/// assert_eq!(CurrentClock.now(), manual.now());
+/// # clock::set(Arc::new(clock::SystemClock));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CurrentClock;
+
+impl Clock for CurrentClock {
+    fn now(&self) -> SystemTime {
+        current().now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        current().sleep(duration);
+    }
+}
+
+// ============================================================
+// グローバルな時計
+// ============================================================
+
+/// プロセス全体で共有する時計
+fn global() -> &'static RwLock<Arc<dyn Clock>> {
+    static CLOCK: OnceLock<RwLock<Arc<dyn Clock>>> = OnceLock::new();
+    CLOCK.get_or_init(|| RwLock::new(Arc::new(SystemClock)))
+}
+
+/// 時計を置き換える
+pub fn set(clock: Arc<dyn Clock>) {
+    *global().write().unwrap_or_else(|e| e.into_inner()) = clock;
+}
+
+/// 現在の時計
+pub fn current() -> Arc<dyn Clock> {
+    global().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_sleep_advances_time() {
+        let start = UNIX_EPOCH + Duration::from_secs(100);
+        let clock = ManualClock::new(start);
+
+        clock.sleep(Duration::from_millis(1500));
+        assert_eq!(clock.now(), start + Duration::from_millis(1500));
+        assert_eq!(clock.now_ms(), 101_500);
+
+        clock.set(UNIX_EPOCH);
+        assert_eq!(clock.now_ms(), 0);
+    }
+
+    #[test]
+    fn test_system_clock_is_after_epoch() {
+        assert!(SystemClock.now_ms() > 0);
+    }
+}
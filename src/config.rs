@@ -0,0 +1,311 @@
+//! 設定モジュール
+//!
+//! 呼び出し側が値を指定しなかった場合に使うデフォルト値をまとめて管理します。
+//! アプリケーションの起動時に `config::set()` で一度設定すると、
+//! 以降の全ての `NotificationBuilder::build()` に反映されます。
+//!
+//! # 例
+//! ```
+//! use rust_toast::config::{self, Config};
+//!
+//! let mut config = Config::default();
+//! config.low.timeout = 2000; // 低緊急度の通知は 2 秒で消す
+//! config::set(config);
+//! ```
+//!
+//! # 学習ポイント
+//! - `OnceLock` + `RwLock` による「読み込みが多いグローバル設定」
+//! - `Default` トレイトによるデフォルト値の定義
+
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::notifier::{
+    Backend, Icon, Overflow, RetryPolicy, ServerQuirks, UrgencyLevel, DEFAULT_FALLBACK_CHAIN,
+};
+use crate::platform;
+use crate::preset::Preset;
+use crate::priority::{PriorityRoute, QuietHours};
+use crate::process::HelperPolicy;
+use crate::redact::Redaction;
+use crate::throttle::GlobalRateLimit;
+use crate::topic::TopicRule;
+
+/// ホスト名が取得できなかった場合に使う名前
+pub(crate) const UNKNOWN_HOSTNAME: &str = "localhost";
+
+// ============================================================
+// 緊急度ごとのデフォルト
+// ============================================================
+
+/// 緊急度ごとのデフォルト値
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrgencyDefaults {
+    /// 表示時間（ミリ秒）、0 = ユーザーが閉じるまで表示（sticky）
+    pub timeout: u32,
+    /// 通知センター / アクションセンターに残る時間（`None` = OS に任せる）
+    pub expiration: Option<Duration>,
+}
+
+impl UrgencyDefaults {
+    /// 表示時間だけを指定して作成
+    pub const fn with_timeout(timeout: u32) -> Self {
+        Self {
+            timeout,
+            expiration: None,
+        }
+    }
+}
+
+// ============================================================
+// ホスト名の付与
+// ============================================================
+
+/// タイトルの先頭にホスト名を付けるかどうか
+///
+/// 複数のマシンから通知が届く場合に、どのマシンからの通知かを区別するために使います。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HostnamePrefix {
+    /// 付けない
+    #[default]
+    Never,
+    /// SSH 経由で実行された場合のみ付ける
+    Remote,
+    /// 常に付ける
+    Always,
+}
+
+impl HostnamePrefix {
+    /// ホスト名を付けるべきかどうか
+    pub fn applies(self, remote: bool) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Remote => remote,
+            Self::Always => true,
+        }
+    }
+}
+
+// ============================================================
+// Config 本体
+// ============================================================
+
+/// rust-toast 全体の設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// 低緊急度のデフォルト（3 秒）
+    pub low: UrgencyDefaults,
+    /// 通常のデフォルト（5 秒）
+    pub normal: UrgencyDefaults,
+    /// 重要のデフォルト（閉じるまで表示）
+    pub critical: UrgencyDefaults,
+    /// タイトル未指定時のテンプレート（`{hostname}` はホスト名に置換）
+    pub title_template: String,
+    /// タイトルの先頭にホスト名を付けるかどうか
+    pub hostname_prefix: HostnamePrefix,
+    /// アイコンを指定しなかった通知のアイコン（`None` はバックエンドの既定）
+    pub icon: Option<Icon>,
+    /// 通知音を指定しなかった通知の通知音（`None` はバックエンドの既定）
+    pub sound: Option<String>,
+    /// 送信元プロセスの情報（名前・PID・作業ディレクトリ）を通知に添付するかどうか
+    pub capture_sender: bool,
+    /// 送信した通知を通知履歴（`store::history`）に記録するかどうか
+    pub record_history: bool,
+    /// 名前付きのプリセット（`NotificationBuilder::preset()` で使う）
+    pub presets: BTreeMap<String, Preset>,
+    /// トピックのパターン（`ci/*` など）ごとのルール
+    pub topics: BTreeMap<String, TopicRule>,
+    /// 通知の本文をデフォルトで非公開にするかどうか（`NotificationBuilder::private()`）
+    pub private: bool,
+    /// 通知の構築時に適用する伏せ字のルール（`redact::builtin()` で組み込みのルール）
+    pub redactions: Vec<Redaction>,
+    /// 外部コマンド（PowerShell, osascript など）の実行ポリシー
+    pub helpers: HelperPolicy,
+    /// Linux の通知デーモンの名前ごとの癖（組み込みの表より優先）
+    pub server_quirks: BTreeMap<String, ServerQuirks>,
+    /// 優先度の範囲ごとの振り分け（追加のバックエンド・静かな時間帯に捨てる）
+    pub priority_routes: Vec<PriorityRoute>,
+    /// 静かな時間帯（`PriorityRoute::drop_in_quiet_hours` の判定に使う）
+    pub quiet_hours: Option<QuietHours>,
+    /// 自動選択されたバックエンドの送信時間の予算（超えている間は重要でない通知をコンソールに）
+    pub latency_budget: Option<Duration>,
+    /// 外部コマンド（PowerShell, osascript など）の終了を待つ上限（10 秒）
+    pub command_timeout: Duration,
+    /// 一時的な失敗を再試行する方針（デフォルトは再試行しない）
+    pub retry: RetryPolicy,
+    /// タイトルや本文がバックエンドの長さの上限を超えた時の扱い（`None` ならそのまま送る）
+    pub overflow: Option<Overflow>,
+    /// 送信に失敗した時に試すバックエンド（試す順、デフォルトは Dialog → Console）
+    pub fallback_chain: Vec<Backend>,
+    /// プラグインの名前ごとに、実行時に渡す環境変数（認証情報など）
+    pub plugin_env: BTreeMap<String, BTreeMap<String, String>>,
+    /// 全ての通知に掛ける送信数の上限と、超えた通知の扱い（`None` なら制限しない。数えるのはプロセス内だけ）
+    pub rate_limit: Option<GlobalRateLimit>,
+    /// 同じタイトルと本文の通知を、この時間内なら 1 つにまとめる（`None` ならまとめない。記録はプロセス内だけ）
+    pub dedupe_window: Option<Duration>,
+    /// 表示できなかった通知をフォールバックせずに送信待ちキューに残し、後で送り直すかどうか
+    pub spool: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            low: UrgencyDefaults::with_timeout(3000),
+            normal: UrgencyDefaults::with_timeout(5000),
+            critical: UrgencyDefaults::with_timeout(0),
+            title_template: "Notification".to_string(),
+            hostname_prefix: HostnamePrefix::Never,
+            icon: None,
+            sound: None,
+            capture_sender: false,
+            record_history: false,
+            presets: BTreeMap::new(),
+            topics: BTreeMap::new(),
+            private: false,
+            redactions: Vec::new(),
+            helpers: HelperPolicy::default(),
+            server_quirks: BTreeMap::new(),
+            priority_routes: Vec::new(),
+            quiet_hours: None,
+            latency_budget: None,
+            command_timeout: Duration::from_secs(10),
+            retry: RetryPolicy::default(),
+            overflow: None,
+            fallback_chain: DEFAULT_FALLBACK_CHAIN.to_vec(),
+            plugin_env: BTreeMap::new(),
+            rate_limit: None,
+            dedupe_window: None,
+            spool: false,
+        }
+    }
+}
+
+impl Config {
+    /// 緊急度に対応するデフォルト値を返す
+    pub fn urgency_defaults(&self, urgency: UrgencyLevel) -> &UrgencyDefaults {
+        match urgency {
+            UrgencyLevel::Low => &self.low,
+            UrgencyLevel::Normal => &self.normal,
+            UrgencyLevel::Critical => &self.critical,
+        }
+    }
+
+    /// 最終的なタイトルを決める
+    ///
+    /// 1. タイトルが無ければ `title_template` から作る
+    /// 2. `hostname_prefix` が当てはまれば `ホスト名: タイトル` にする
+    pub fn resolve_title(&self, title: Option<String>) -> String {
+        self.resolve_title_with(title, platform::is_remote_session(), platform::hostname)
+    }
+
+    /// 実行環境を引数で受け取る `resolve_title()`（テスト用に分離）
+    ///
+    /// ホスト名の取得はファイル読み込みやコマンド実行を伴うため、必要な時だけ呼びます。
+    fn resolve_title_with(
+        &self,
+        title: Option<String>,
+        remote: bool,
+        hostname: impl Fn() -> Option<String>,
+    ) -> String {
+        let host = || hostname().unwrap_or_else(|| UNKNOWN_HOSTNAME.to_string());
+
+        let title = title.unwrap_or_else(|| {
+            if self.title_template.contains("{hostname}") {
+                self.title_template.replace("{hostname}", &host())
+            } else {
+                self.title_template.clone()
+            }
+        });
+
+        if !self.hostname_prefix.applies(remote) {
+            return title;
+        }
+        let host = host();
+        // テンプレートなどで既にホスト名が付いている場合は重ねない
+        if title.starts_with(&host) {
+            title
+        } else {
+            format!("{host}: {title}")
+        }
+    }
+}
+
+// ============================================================
+// グローバル設定
+// ============================================================
+
+/// プロセス全体で共有する設定
+fn global() -> &'static RwLock<Config> {
+    static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(Config::default()))
+}
+
+/// 設定を置き換える
+pub fn set(config: Config) {
+    *global().write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// 現在の設定のコピーを返す
+pub fn current() -> Config {
+    global().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_urgency_timeouts() {
+        let config = Config::default();
+        assert_eq!(config.urgency_defaults(UrgencyLevel::Low).timeout, 3000);
+        assert_eq!(config.urgency_defaults(UrgencyLevel::Normal).timeout, 5000);
+        assert_eq!(config.urgency_defaults(UrgencyLevel::Critical).timeout, 0);
+    }
+
+    #[test]
+    fn test_resolve_title_template() {
+        let config = Config {
+            title_template: "Message from {hostname}".to_string(),
+            ..Config::default()
+        };
+        let host = || Some("build-01".to_string());
+
+        assert_eq!(
+            config.resolve_title_with(None, false, host),
+            "Message from build-01"
+        );
+        assert_eq!(
+            config.resolve_title_with(Some("Done".to_string()), false, host),
+            "Done"
+        );
+    }
+
+    #[test]
+    fn test_resolve_title_hostname_prefix() {
+        let config = Config {
+            hostname_prefix: HostnamePrefix::Remote,
+            ..Config::default()
+        };
+        let host = || Some("build-01".to_string());
+
+        assert_eq!(
+            config.resolve_title_with(Some("Done".to_string()), true, host),
+            "build-01: Done"
+        );
+        // ローカル実行では付けない
+        assert_eq!(
+            config.resolve_title_with(Some("Done".to_string()), false, host),
+            "Done"
+        );
+        // 既にホスト名で始まっていれば重ねない
+        assert_eq!(
+            config.resolve_title_with(Some("build-01 finished".to_string()), true, host),
+            "build-01 finished"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! 環境の診断（通知が届かない原因の確認）
+//!
+//! `rust-toast doctor` で、通知が表示されない時によくある原因を順に確認します。
+//!
+//! | 確認 | 内容 |
+//! |------|------|
+//! | platform | 検出したプラットフォーム |
+//! | helper | バックエンドが使う外部コマンド（実行ポリシーを含む） |
+//! | toasts | Windows / WSL で通知がオフになっていないか |
+//! | server | Linux で通知デーモンが動いているか（名前と補正する癖） |
+//! | focus | 画面ロック・おやすみモード（未読として履歴に残る） |
+//!
+//! 直し方が設定ページを開くことなら `Check::fix_uri` に URI が入り、
+//! `doctor --fix` で開けます。
+//!
+//! # 学習ポイント
+//! - 確認の結果をデータ（`Check`）として集め、表示と修正を分離する
+//! - エラーの種類（`NotificationsDisabled`）から修正方法を取り出す
+
+use std::fmt;
+
+use crate::config;
+use crate::error::NotificationError;
+use crate::notifier::{self, quirks, ServerQuirks};
+use crate::platform::{self, Platform};
+use crate::process;
+
+/// 確認の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// 問題なし
+    Ok,
+    /// 通知は届くが、表示されないことがある
+    Warn,
+    /// 通知が届かない
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        f.pad(label)
+    }
+}
+
+/// 1 つの確認
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// 確認の名前（`helper powershell.exe` など）
+    pub name: String,
+    /// 結果
+    pub status: Status,
+    /// 結果の説明
+    pub detail: String,
+    /// 問題を直すために開く設定ページの URI（`doctor --fix` で開く）
+    pub fix_uri: Option<String>,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix_uri: None,
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<4} {}: {}", self.status, self.name, self.detail)
+    }
+}
+
+/// 全ての確認を実行する
+///
+/// `app_id` は Windows / WSL で通知の設定を確認する AppUserModelID です（`--app-name`）。
+pub fn run(app_id: Option<&str>) -> Vec<Check> {
+    let platform = platform::detect_platform();
+    let mut checks = vec![Check::new(
+        "platform",
+        if platform == Platform::Unknown {
+            Status::Fail
+        } else {
+            Status::Ok
+        },
+        platform.to_string(),
+    )];
+
+    for program in helpers(platform) {
+        checks.push(match process::check(program) {
+            Ok(path) => Check::new(
+                format!("helper {program}"),
+                Status::Ok,
+                path.display().to_string(),
+            ),
+            Err(e) => Check::new(format!("helper {program}"), Status::Fail, e.to_string()),
+        });
+    }
+
+    if matches!(platform, Platform::Windows | Platform::Wsl) {
+        checks.push(toasts_check(notifier::check_toasts_enabled(app_id)));
+    }
+
+    if platform == Platform::Linux {
+        checks.push(server_check(notifier::server_name().as_deref()));
+    }
+
+    checks.push(if platform::is_session_locked() {
+        Check::new("focus", Status::Warn, "session is locked")
+    } else if platform::is_do_not_disturb() {
+        Check::new("focus", Status::Warn, "do not disturb is on")
+    } else {
+        Check::new("focus", Status::Ok, "notifications can be shown")
+    });
+
+    checks
+}
+
+/// プラットフォームのバックエンドが使う外部コマンド
+fn helpers(platform: Platform) -> &'static [&'static str] {
+    match platform {
+        Platform::Wsl | Platform::Windows => &["powershell.exe"],
+        Platform::MacOs => &["osascript"],
+        Platform::Linux | Platform::Unknown => &[],
+    }
+}
+
+/// 通知デーモンの名前を確認の結果に変換
+fn server_check(name: Option<&str>) -> Check {
+    let Some(name) = name else {
+        return Check::new("server", Status::Fail, "no notification daemon on D-Bus");
+    };
+    let quirks = quirks::for_server(&config::current(), name);
+    let detail = if quirks == ServerQuirks::default() {
+        name.to_string()
+    } else {
+        format!("{name} (applying quirks: {quirks:?})")
+    };
+    Check::new("server", Status::Ok, detail)
+}
+
+/// `check_toasts_enabled()` の結果を確認の結果に変換
+fn toasts_check(result: crate::error::Result<()>) -> Check {
+    match result {
+        Ok(()) => Check::new("toasts", Status::Ok, "enabled"),
+        Err(NotificationError::NotificationsDisabled {
+            reason,
+            settings_uri,
+        }) => Check {
+            fix_uri: Some(settings_uri.clone()),
+            ..Check::new(
+                "toasts",
+                Status::Fail,
+                format!("{reason}; enable them at {settings_uri}"),
+            )
+        },
+        Err(e) => Check::new("toasts", Status::Warn, format!("could not check: {e}")),
+    }
+}
+
+/// 確認で見つかった設定ページを開く
+///
+/// 開いた URI を返します（同じページは 1 度だけ開く）。
+pub fn fix(checks: &[Check]) -> crate::error::Result<Vec<String>> {
+    let mut opened: Vec<String> = Vec::new();
+    for uri in checks.iter().filter_map(|check| check.fix_uri.as_ref()) {
+        if !opened.contains(uri) {
+            notifier::open_url(uri)?;
+            opened.push(uri.clone());
+        }
+    }
+    Ok(opened)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_check_carries_settings_uri() {
+        let check = toasts_check(Err(NotificationError::NotificationsDisabled {
+            reason: "turned off for this app".to_string(),
+            settings_uri: notifier::NOTIFICATION_SETTINGS_URI.to_string(),
+        }));
+        assert_eq!(check.status, Status::Fail);
+        assert_eq!(check.fix_uri.as_deref(), Some("ms-settings:notifications"));
+        assert_eq!(
+            check.to_string(),
+            "FAIL toasts: turned off for this app; enable them at ms-settings:notifications"
+        );
+
+        let check = toasts_check(Err(NotificationError::Other("boom".to_string())));
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(check.fix_uri, None);
+        assert_eq!(fix(&[check]).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_server_check() {
+        assert_eq!(server_check(None).status, Status::Fail);
+        assert_eq!(server_check(Some("dunst")).detail, "dunst");
+        assert!(server_check(Some("mako"))
+            .detail
+            .contains("markup_links: false"));
+    }
+}
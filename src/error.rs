@@ -13,8 +13,26 @@ use std::fmt;
 /// 通知処理のエラー型
 ///
 /// 各バリアントは異なるエラーケースを表します。
+/// 呼び出し側はメッセージの文字列ではなく、バリアントで原因を見分けられます。
 /// `#[derive(Debug)]` でデバッグ出力を自動実装。
+///
+/// # 例
+/// ```no_run
+/// use rust_toast::{NotificationBuilder, NotificationError};
+///
+/// match NotificationBuilder::new().message("Done").send() {
+///     Ok(_) => {}
+///     Err(NotificationError::BackendUnavailable { hint, .. }) => eprintln!("hint: {hint}"),
+///     Err(NotificationError::Timeout { .. }) => { /* 後で送り直す */ }
+///     Err(err) => eprintln!("{err}"),
+/// }
+/// ```
+///
+/// # 学習ポイント
+/// - `#[non_exhaustive]` で、バリアントを追加しても利用側のコンパイルが壊れないようにする
+///   （クレートの外の `match` には `_` の腕が必要になる）
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum NotificationError {
     /// 通知送信失敗
     /// - `backend`: 使用したバックエンド名（Linux, Windows, macOS）
@@ -27,6 +45,55 @@ pub enum NotificationError {
     /// 外部コマンド実行エラー（PowerShell, osascript等）
     CommandExecution(std::io::Error),
 
+    /// 外部コマンドが見つからない（`PATH` に無い）
+    /// - `program`: プログラム名
+    CommandNotFound { program: String },
+
+    /// バックエンドがこの環境では使えない
+    /// - `backend`: バックエンド名
+    /// - `hint`: 使えるようにする方法（インストールするツール、有効にするフィーチャーなど）
+    BackendUnavailable { backend: String, hint: String },
+
+    /// 永続化ストレージ（ジャーナルなど）の読み書きエラー
+    /// - `path`: 対象のファイル
+    /// - `reason`: 失敗の理由
+    Storage {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    /// トピックの送信数の上限を超えた
+    /// - `topic`: 通知のトピック
+    RateLimited { topic: String },
+
+    /// OS の設定で通知が無効になっている
+    /// - `reason`: 無効な理由（アプリだけ / 全体 / グループポリシーなど）
+    /// - `settings_uri`: 設定ページを開く URI（`ms-settings:notifications` など）
+    NotificationsDisabled {
+        reason: String,
+        settings_uri: String,
+    },
+
+    /// 実行ポリシー（`Config::helpers`）で許可されない外部コマンド
+    /// - `program`: プログラム名
+    /// - `reason`: 拒否した理由
+    UntrustedHelper { program: String, reason: String },
+
+    /// 外部コマンドが時間内に終わらなかった（強制終了済み）
+    /// - `backend`: コマンドを起動したバックエンド名（バックエンド以外から起動した場合は `None`）
+    /// - `program`: プログラム名
+    /// - `elapsed`: 待った時間（`Config::command_timeout` など）
+    Timeout {
+        backend: Option<String>,
+        program: String,
+        elapsed: std::time::Duration,
+    },
+
+    /// 通知の内容が不正（`NotificationBuilder::try_build()`）
+    /// - `field`: 不正な項目（`message`, `timeout`, `sound`, `icon` など）
+    /// - `reason`: 不正な理由
+    InvalidInput { field: String, reason: String },
+
     /// その他のエラー
     Other(String),
 }
@@ -60,11 +127,71 @@ impl fmt::Display for NotificationError {
             Self::CommandExecution(err) => {
                 write!(f, "Command execution error: {}", err)
             }
+            Self::CommandNotFound { program } => {
+                write!(f, "{} not found in PATH", program)
+            }
+            Self::BackendUnavailable { backend, hint } => {
+                write!(f, "{} backend is not available: {}", backend, hint)
+            }
+            Self::Storage { path, reason } => {
+                write!(f, "Storage error ({}): {}", path.display(), reason)
+            }
+            Self::RateLimited { topic } => {
+                write!(f, "Rate limit exceeded for topic '{}'", topic)
+            }
+            Self::NotificationsDisabled {
+                reason,
+                settings_uri,
+            } => {
+                write!(
+                    f,
+                    "Notifications are {}; enable them at {} (or run `rust-toast doctor --fix`)",
+                    reason, settings_uri
+                )
+            }
+            Self::UntrustedHelper { program, reason } => {
+                write!(f, "Refusing to run '{}': {}", program, reason)
+            }
+            Self::Timeout {
+                backend,
+                program,
+                elapsed,
+            } => {
+                if let Some(backend) = backend {
+                    write!(f, "{}: ", backend)?;
+                }
+                write!(
+                    f,
+                    "'{}' did not finish within {:?} and was killed",
+                    program, elapsed
+                )
+            }
+            Self::InvalidInput { field, reason } => {
+                write!(f, "Invalid {}: {}", field, reason)
+            }
             Self::Other(msg) => write!(f, "{}", msg),
         }
     }
 }
 
+impl NotificationError {
+    /// バックエンドの中で起きたエラーに、バックエンド名を付ける（`Timeout` のみ、付いていなければ）
+    pub(crate) fn in_backend(self, name: &str) -> Self {
+        match self {
+            Self::Timeout {
+                backend: None,
+                program,
+                elapsed,
+            } => Self::Timeout {
+                backend: Some(name.to_string()),
+                program,
+                elapsed,
+            },
+            err => err,
+        }
+    }
+}
+
 /// `std::error::Error` トレイトの実装
 ///
 /// これを実装することで、`Box<dyn Error>` として扱えるようになり、
@@ -118,8 +245,8 @@ impl From<Box<dyn std::error::Error>> for NotificationError {
     }
 }
 
-/// notify-rust のエラーからの変換（Linux のみ）
-#[cfg(target_os = "linux")]
+/// notify-rust のエラーからの変換（Linux の `linux-dbus` フィーチャーのみ）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
 impl From<notify_rust::error::Error> for NotificationError {
     fn from(err: notify_rust::error::Error) -> Self {
         Self::SendFailed {
@@ -155,6 +282,59 @@ mod tests {
         assert_eq!(err.to_string(), "Unsupported platform: FreeBSD");
     }
 
+    #[test]
+    fn test_display_storage() {
+        let err = NotificationError::Storage {
+            path: std::path::PathBuf::from("/tmp/queue.wal"),
+            reason: "checksum mismatch".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Storage error (/tmp/queue.wal): checksum mismatch"
+        );
+    }
+
+    #[test]
+    fn test_display_invalid() {
+        let err = NotificationError::InvalidInput {
+            field: "icon".to_string(),
+            reason: "/tmp/missing.png does not exist".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid icon: /tmp/missing.png does not exist"
+        );
+    }
+
+    #[test]
+    fn test_display_timeout() {
+        let err = NotificationError::Timeout {
+            backend: None,
+            program: "powershell.exe".to_string(),
+            elapsed: std::time::Duration::from_secs(10),
+        };
+        assert_eq!(
+            err.to_string(),
+            "'powershell.exe' did not finish within 10s and was killed"
+        );
+        assert_eq!(
+            err.in_backend("Windows (PowerShell)").to_string(),
+            "Windows (PowerShell): 'powershell.exe' did not finish within 10s and was killed"
+        );
+    }
+
+    #[test]
+    fn test_display_backend_unavailable() {
+        let err = NotificationError::BackendUnavailable {
+            backend: "Dialog".to_string(),
+            hint: "install zenity or kdialog".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Dialog backend is not available: install zenity or kdialog"
+        );
+    }
+
     #[test]
     fn test_from_string() {
         let err: NotificationError = "Something went wrong".into();
@@ -0,0 +1,312 @@
+//! コマンドを実行して結果を通知する（`rust-toast exec -- cargo build`）
+//!
+//! コマンドの出力はそのまま端末に流しながら、最後の数行を覚えておき、
+//! 終了したら成功・失敗、所要時間、出力の末尾を 1 つの通知にまとめます。
+//!
+//! | 結果 | タイトル | 緊急度 |
+//! |------|----------|--------|
+//! | 成功 | `✓ cargo build` | normal |
+//! | 失敗 | `✗ cargo build (exit 101)` | critical |
+//!
+//! 本文は `Finished in 1m 05s` と、出力の末尾（`tail` 行、ANSI エスケープを除く）です。
+//! 出力は届いた分をすぐに流すので、改行の無いプロンプト（`Continue? [y/N]`）もそのまま見えます。
+//!
+//! CLI の終了コードはシェルと同じく、コマンドの終了コード・シグナルなら 128 + 番号（Unix）・
+//! 見つからなければ 127・実行できなければ 126 です（`Outcome::exit_code()`）。
+//!
+//! # 学習ポイント
+//! - 子プロセスの stdout / stderr をスレッドで読み、端末への転送と記録を同時に行う
+//! - 転送はバイト列のまま、行に分けるのは記録する側だけ
+//! - `Arc<Mutex<VecDeque>>` による、スレッド間で共有する固定長のバッファ
+
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{NotificationBuilder, UrgencyLevel};
+use crate::text;
+
+/// コマンドが見つからない場合の終了コード（シェルと同じ）
+pub const EXIT_NOT_FOUND: u8 = 127;
+
+/// コマンドを実行できない（実行権限が無いなど）場合の終了コード（シェルと同じ）
+pub const EXIT_NOT_EXECUTABLE: u8 = 126;
+
+/// 改行の来ない出力を記録用に覚えておく長さの上限（バイト、超えた分は先頭から捨てる）
+const MAX_PENDING_LINE: usize = 4096;
+
+/// 実行したコマンドの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// 実行したコマンドライン（表示用に空白で繋いだもの）
+    pub command: String,
+    /// 終了コード（シグナルで終了した場合は `None`）
+    pub code: Option<i32>,
+    /// 終了させたシグナルの番号（Unix のみ。終了コードで終わった場合は `None`）
+    pub signal: Option<i32>,
+    /// 実行にかかった時間
+    pub elapsed: Duration,
+    /// 出力（stdout と stderr を届いた順に）の末尾の行
+    pub tail: Vec<String>,
+}
+
+impl Outcome {
+    /// 終了コード 0 で終わったかどうか
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// `rust-toast exec` の終了コード（シグナルは 128 + 番号、どちらも無ければ 1）
+    pub fn exit_code(&self) -> i32 {
+        match (self.code, self.signal) {
+            (Some(code), _) => code,
+            (None, Some(signal)) => 128 + signal,
+            (None, None) => 1,
+        }
+    }
+
+    /// 結果の通知（タイトル・本文・緊急度）
+    pub fn to_builder(&self) -> NotificationBuilder {
+        let (title, urgency) = match self.code {
+            Some(0) => (format!("✓ {}", self.command), UrgencyLevel::Normal),
+            Some(code) => (
+                format!("✗ {} (exit {code})", self.command),
+                UrgencyLevel::Critical,
+            ),
+            None => (
+                match self.signal {
+                    Some(signal) => format!("✗ {} (killed by signal {signal})", self.command),
+                    None => format!("✗ {} (killed by a signal)", self.command),
+                },
+                UrgencyLevel::Critical,
+            ),
+        };
+        let mut message = format!("Finished in {}", format_elapsed(self.elapsed));
+        for line in &self.tail {
+            message.push('\n');
+            message.push_str(line);
+        }
+        NotificationBuilder::new()
+            .title(title)
+            .message(message)
+            .urgency(urgency)
+    }
+}
+
+/// コマンドを実行し、終わるまで待つ
+///
+/// 標準入力はそのまま渡し、stdout / stderr は端末に流しながら末尾の `tail` 行を記録します。
+/// 起動できない場合、見つからなければ `CommandNotFound`、それ以外は `CommandExecution` です。
+pub fn run(command: &[String], tail: usize) -> Result<Outcome> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| NotificationError::InvalidInput {
+            field: "command".to_string(),
+            reason: "no command given after --".to_string(),
+        })?;
+    let started = Instant::now();
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => NotificationError::CommandNotFound {
+                program: program.clone(),
+            },
+            _ => NotificationError::CommandExecution(e),
+        })?;
+
+    let lines = Arc::new(Mutex::new(VecDeque::with_capacity(tail)));
+    let relays = [
+        child
+            .stdout
+            .take()
+            .map(|out| relay(out, io::stdout(), Arc::clone(&lines), tail)),
+        child
+            .stderr
+            .take()
+            .map(|err| relay(err, io::stderr(), Arc::clone(&lines), tail)),
+    ];
+    let status = child
+        .wait()
+        .map_err(|e| NotificationError::Other(format!("cannot wait for {program}: {e}")))?;
+    for relay in relays.into_iter().flatten() {
+        // 転送のスレッドは panic しない（書き込みの失敗は無視する）
+        let _ = relay.join();
+    }
+
+    let tail = lines
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain(..)
+        .collect();
+    Ok(Outcome {
+        command: command.join(" "),
+        code: status.code(),
+        signal: signal_of(status),
+        elapsed: started.elapsed(),
+        tail,
+    })
+}
+
+#[cfg(unix)]
+fn signal_of(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal_of(_status: ExitStatus) -> Option<i32> {
+    None
+}
+
+/// 出力を届いたまま `writer` に流し、整えた行を `lines` の末尾に `limit` 行まで残す
+///
+/// 最後の改行の後の部分（改行で終わらない出力）も、読み終えた時に 1 行として残します。
+fn relay(
+    mut reader: impl Read + Send + 'static,
+    mut writer: impl Write + Send + 'static,
+    lines: Arc<Mutex<VecDeque<String>>>,
+    limit: usize,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut chunk = [0; 8192];
+        let mut pending = Vec::new();
+        loop {
+            let n = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            let _ = writer.write_all(&chunk[..n]).and_then(|()| writer.flush());
+            for piece in chunk[..n].split_inclusive(|&b| b == b'\n') {
+                pending.extend_from_slice(piece);
+                if piece.ends_with(b"\n") {
+                    remember(&lines, limit, &pending);
+                    pending.clear();
+                } else if pending.len() > MAX_PENDING_LINE {
+                    pending.drain(..pending.len() - MAX_PENDING_LINE);
+                }
+            }
+        }
+        remember(&lines, limit, &pending);
+    })
+}
+
+/// 1 行を整えて `lines` の末尾に加える（空の行は残さない）
+fn remember(lines: &Mutex<VecDeque<String>>, limit: usize, line: &[u8]) {
+    let line = clean_line(&String::from_utf8_lossy(line));
+    if limit == 0 || line.is_empty() {
+        return;
+    }
+    let mut lines = lines.lock().unwrap_or_else(|e| e.into_inner());
+    if lines.len() == limit {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// 出力の 1 行を通知の本文向けに整える（ANSI エスケープを除き、`\r` の書き換えは最後の部分だけ）
+fn clean_line(line: &str) -> String {
+    let plain = text::strip_ansi(line.trim_end_matches(['\n', '\r']));
+    let last = plain.rsplit('\r').find(|part| !part.is_empty());
+    last.unwrap_or("").trim_end().to_string()
+}
+
+/// 所要時間の表示（`3.2s` / `1m 05s` / `2h 03m`）
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => format!("{:.1}s", elapsed.as_secs_f64()),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(3200)), "3.2s");
+        assert_eq!(format_elapsed(Duration::from_secs(65)), "1m 05s");
+        assert_eq!(format_elapsed(Duration::from_secs(7380)), "2h 03m");
+    }
+
+    #[test]
+    fn test_outcome_notification() {
+        let outcome = Outcome {
+            command: "cargo build".to_string(),
+            code: Some(101),
+            signal: None,
+            elapsed: Duration::from_secs(65),
+            tail: vec!["error: could not compile `demo`".to_string()],
+        };
+        let notification = outcome.to_builder().build();
+        assert_eq!(notification.title, "✗ cargo build (exit 101)");
+        assert_eq!(
+            notification.message,
+            "Finished in 1m 05s\nerror: could not compile `demo`"
+        );
+        assert_eq!(notification.urgency, UrgencyLevel::Critical);
+        assert!(!outcome.success());
+        assert_eq!(outcome.exit_code(), 101);
+
+        let killed = Outcome {
+            code: None,
+            signal: Some(2),
+            ..outcome
+        };
+        assert_eq!(
+            killed.to_builder().build().title,
+            "✗ cargo build (killed by signal 2)"
+        );
+        assert_eq!(killed.exit_code(), 130);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_keeps_tail_and_exit_code() {
+        let command: Vec<String> = ["sh", "-c", "printf 'a\\nb\\n\\033[31mc\\033[0m\\n'; exit 3"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let outcome = run(&command, 2).unwrap();
+        assert_eq!(outcome.code, Some(3));
+        assert_eq!(outcome.tail, ["b", "c"]);
+
+        assert!(run(&[], 2).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_keeps_unterminated_output_and_signal() {
+        let command: Vec<String> = ["sh", "-c", "printf 'done\\nContinue? '; kill -TERM $$"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let outcome = run(&command, 5).unwrap();
+        assert_eq!(outcome.tail, ["done", "Continue?"]);
+        assert_eq!(outcome.code, None);
+        assert_eq!(outcome.signal, Some(15));
+        assert_eq!(outcome.exit_code(), 143);
+
+        let missing = run(&["rust-toast-no-such-command".to_string()], 5);
+        assert!(matches!(
+            missing,
+            Err(NotificationError::CommandNotFound { .. })
+        ));
+    }
+}
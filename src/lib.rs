@@ -9,20 +9,21 @@
 //! | Linux | D-Bus (notify-rust) | デスクトップ通知 |
 //! | WSL | PowerShell | Windows 通知を送信 |
 //! | macOS | osascript | 通知センターに送信 |
-//! | Windows | PowerShell | バルーン通知 |
+//! | Windows | PowerShell | トースト通知 |
 //!
 //! ## 使用例
 //!
 //! ### ライブラリとして使用
 //!
 //! ```ignore
+//! use std::time::Duration;
 //! use rust_toast::notifier::NotificationBuilder;
 //!
 //! // Builder パターンで通知を構築して送信
 //! NotificationBuilder::new()
 //!     .title("Hello")
 //!     .message("World!")
-//!     .timeout(5000)
+//!     .timeout(Duration::from_secs(5))
 //!     .send()?;
 //! ```
 //!
@@ -43,13 +44,63 @@
 //! ```text
 //! rust_toast
 //! ├── cli        # CLI 引数定義（clap）
+//! ├── clock      # 時計と待機の抽象化
+//! ├── config     # 設定（緊急度ごとのデフォルトなど）
+//! ├── doctor     # 環境の診断（通知が届かない原因の確認）
 //! ├── error      # エラー型定義
+//! ├── exec       # コマンドを実行して結果を通知（exec）
+//! ├── manager    # バックグラウンドで送信する通知マネージャー
 //! ├── notifier   # 通知システムのコア
 //! │   ├── mod    # トレイト定義、Builder、ディスパッチ
+//! │   ├── capabilities# バックエンドが対応している機能
+//! │   ├── click  # クリック時の動作（URL を開く・コマンドを実行）
+//! │   ├── command# コマンドのテンプレートで送るバックエンド
+//! │   ├── console# コンソール（最終フォールバック）バックエンド
+//! │   ├── dedupe # 重複する通知のまとめ
+//! │   ├── dialog # ダイアログ（フォールバック）バックエンド
+//! │   ├── handle # 送信済み通知のハンドル（置き換え）
+//! │   ├── icon   # アイコンの種類と取得（URL・データ URI・テーマ）
+//! │   ├── length # タイトルと本文の長さの上限
 //! │   ├── linux  # Linux バックエンド
 //! │   ├── macos  # macOS バックエンド
+//! │   ├── markdown# Markdown 本文の変換
+//! │   ├── nonblocking# 非同期の送信（async フィーチャー）
+//! │   ├── plugin # 外部プログラムのプラグイン
+//! │   ├── quirks # Linux の通知デーモンごとの癖
+//! │   ├── registry# 独自のバックエンドの登録
+//! │   ├── render # 配送内容の書き出し（ドライラン）
+//! │   ├── retry  # 一時的な失敗の再試行
+//! │   ├── simulate# 送信のシミュレーション
+//! │   ├── stdout # JSON 出力バックエンド
+//! │   ├── subscribe# 送信される通知の購読
 //! │   └── windows# Windows バックエンド
-//! └── platform   # プラットフォーム検出
+//! ├── pipe       # 標準入力の行の監視（pipe）
+//! ├── platform   # プラットフォーム検出
+//! ├── preset     # 翻訳付きプリセット
+//! ├── priority   # 数値の優先度による振り分け
+//! ├── process    # 外部コマンド実行ユーティリティ
+//! ├── progress   # 長い処理の進捗通知
+//! ├── recurring  # 繰り返しの通知（cron 形式）
+//! ├── redact     # 機密情報の伏せ字
+//! ├── rules      # 判断ルールのテスト（フィクスチャ）
+//! ├── schedule   # 指定した時刻の通知（OS のスケジューラに登録）
+//! ├── sender     # 送信元プロセスの情報
+//! ├── setup      # 初回設定のウィザードと設定ファイル（プロファイル）
+//! ├── statusbar  # ステータスバー（waybar / i3blocks）向けの出力
+//! ├── store      # 永続化ストア
+//! │   ├── mod    # データディレクトリの決定
+//! │   ├── history# 通知履歴
+//! │   ├── journal# 追記専用ログ（WAL）
+//! │   ├── migrate# ジャーナル形式の移行
+//! │   ├── queue  # 送信待ちキュー（スプール）
+//! │   └── schedules# 繰り返しの通知の登録
+//! ├── targets    # ビルド対象とバックエンドの対応状況
+//! ├── template   # プレースホルダー付きの文面
+//! ├── text       # 端末向けの出力の整形（ANSI エスケープの除去）
+//! ├── throttle   # 全体の送信数の制限
+//! ├── topic      # トピックごとのルール
+//! ├── trace      # 分散トレースとの関連付け
+//! └── watch      # ファイルの変更の監視（watch）
 //! ```
 //!
 //! ## 学習できる Rust の概念
@@ -68,15 +119,90 @@
 /// CLI 引数定義モジュール
 pub mod cli;
 
+/// 時計と待機の抽象化（テストで差し替え可能）
+pub mod clock;
+
+/// 設定モジュール（デフォルト値の管理）
+pub mod config;
+
+/// 環境の診断（通知が届かない原因の確認）
+pub mod doctor;
+
 /// エラー型定義モジュール
 pub mod error;
 
+/// コマンドを実行して結果を通知する（`rust-toast exec`）
+pub mod exec;
+
+/// バックグラウンドで送信する通知マネージャー（ワーカースレッドとキュー）
+pub mod manager;
+
 /// 通知システムのコアモジュール
 pub mod notifier;
 
+/// 標準入力の行を監視して通知する（`rust-toast pipe`）
+pub mod pipe;
+
 /// プラットフォーム検出モジュール
 pub mod platform;
 
+/// 翻訳付きプリセット
+pub mod preset;
+
+/// 数値の優先度（0〜10）による振り分け
+pub mod priority;
+
+/// 外部コマンド実行ユーティリティ
+pub mod process;
+
+/// 長い処理の進捗通知（イテレータのアダプタ）
+pub mod progress;
+
+/// 繰り返しの通知（間隔・cron 形式と、登録した予定の実行）
+pub mod recurring;
+
+/// 機密情報の伏せ字（正規表現のルール）
+pub mod redact;
+
+/// 送信元プロセスの情報
+pub mod sender;
+
+/// 判断ルールのテスト（宣言的なフィクスチャ）
+pub mod rules;
+
+/// 指定した時刻の通知（OS のスケジューラに登録）
+pub mod schedule;
+
+/// 初回設定のウィザード（`rust-toast setup`）と設定ファイル（`rust-toast config`）
+pub mod setup;
+
+/// ステータスバー（waybar / i3blocks）向けの出力
+pub mod statusbar;
+
+/// 永続化ストアモジュール
+pub mod store;
+
+/// ビルド対象とバックエンドの対応状況
+pub mod targets;
+
+/// プレースホルダー付きの文面（`{hostname}`, `{env:VAR}` など）
+pub mod template;
+
+/// 端末向けの出力を通知の本文にする（ANSI エスケープの除去など）
+pub mod text;
+
+/// 全体の送信数の制限（上限を超えた通知を捨てる・待つ・まとめる）
+pub mod throttle;
+
+/// トピック（名前空間）ごとのルール
+pub mod topic;
+
+/// 分散トレースとの関連付け（W3C Trace Context）
+pub mod trace;
+
+/// ファイルの変更を監視して通知する（`rust-toast watch`）
+pub mod watch;
+
 // ============================================================
 // 便利な再エクスポート
 // ============================================================
@@ -88,7 +214,11 @@ pub mod platform;
 pub use error::{NotificationError, Result};
 
 /// 通知関連の型の再エクスポート
-pub use notifier::{Notification, NotificationBuilder, Notifier, UrgencyLevel};
+pub use notifier::{
+    Action, Backend, BackendResult, Capabilities, Icon, Interaction, Invocation, Notification,
+    NotificationBuilder, NotificationHandle, Notifier, Overflow, RenderedDelivery, RetryPolicy,
+    Scenario, SendReport, Timeout, UrgencyLevel,
+};
 
 /// プラットフォーム関連の再エクスポート
 pub use platform::{detect_platform, Platform};
@@ -2,45 +2,50 @@
 //!
 //! このファイルは CLI アプリケーションのエントリーポイントです。
 //! ロジックは `lib.rs` と各モジュールに委譲し、ここでは
-//! 引数のパースと結果の表示のみを行います。
+//! 引数のパースと実行の呼び出しのみを行います。
 //!
 //! # 学習ポイント
 //! - `main.rs` と `lib.rs` の分離
 //! - 薄いエントリーポイントの設計
 //! - エラーハンドリングの統合
 
+use std::process::ExitCode;
+
 use clap::Parser;
 
 // ライブラリからインポート
 // クレート名は Cargo.toml の [package] name から決まる
 // ハイフンはアンダースコアに変換される（rust-toast → rust_toast）
-use rust_toast::cli::Args;
-use rust_toast::Result;
+use rust_toast::cli::{self, Args};
+use rust_toast::{notifier, Result};
 
 /// メイン関数
 ///
 /// # 処理の流れ
 /// 1. CLI 引数をパース（`Args::parse()`）
-/// 2. 引数から `NotificationBuilder` を構築
-/// 3. 通知を送信
-/// 4. 結果を表示
+/// 2. サブコマンドを実行（省略時と `send` は `SendArgs::run()` で通知を送信）
 ///
 /// # 戻り値
-/// - `Ok(())`: 正常終了
-/// - `Err(NotificationError)`: エラー終了（Rust が自動的にメッセージを表示）
-fn main() -> Result<()> {
+/// - `Ok(ExitCode)`: 正常終了（`--wait` では操作結果の終了コード）
+/// - `Err(NotificationError)`: エラー終了（Rust が自動的にメッセージを表示、終了コード 1）
+fn main() -> Result<ExitCode> {
+    // プラグインの読み込みの警告も表示するため、最初に info 以上で始める
+    cli::init_logging(false);
+
+    // プラグインディレクトリの実行ファイルを登録（--backend の候補にするため、パースより前）
+    notifier::load_plugins();
+
+    // シェルの補完から呼ばれた場合は候補を出力して終わる（登録したプラグインも候補になる）
+    cli::complete_from_env();
+
     // Step 1: CLI 引数をパース
     // clap が自動的に --help, --version を処理し、
     // 必須引数が不足している場合はエラーメッセージを表示して終了
     let args = Args::parse();
 
-    // Step 2-3: NotificationBuilder を構築して送信
-    // into_builder() で Args → NotificationBuilder に変換
-    // send() で通知を送信
-    args.into_builder().send()?;
-
-    // Step 4: 成功メッセージを表示
-    println!("✓ Toast notification sent successfully");
+    // --verbose なら debug のログも表示する
+    cli::init_logging(args.verbose);
 
-    Ok(())
+    // Step 2: サブコマンド（省略時は通知の送信）を実行
+    args.run()
 }
@@ -0,0 +1,397 @@
+//! バックグラウンドで送信する通知マネージャー
+//!
+//! PowerShell の起動などで `send()` は 1 秒近く止まることがあり、GUI アプリの
+//! メインスレッドからは呼べません。`NotificationManager` は専用のワーカースレッドと
+//! キューを持ち、`enqueue()` はすぐに戻ります。送信は渡した順に 1 つずつ行い、
+//! 失敗はエラーハンドラに渡します（デフォルトはログに出すだけ）。
+//!
+//! `schedule_after()` / `schedule_at()` は指定した時刻まで送信を遅らせます
+//! （`NotificationBuilder::send_after()` / `send_at()` はプロセス全体で共有する `global()` を使う）。
+//! 予約した通知はプロセスの中で待つだけなので、プロセスが終了すると送られません。
+//! 時刻は `clock::current()` から読み、予約を待つ間は `Clock::sleep()` で少しずつ待ちます
+//! （その間に追加された通知は `POLL_INTERVAL` 以内に受け取る）。
+//!
+//! ```no_run
+//! use rust_toast::manager::NotificationManager;
+//! use rust_toast::NotificationBuilder;
+//!
+//! let manager = NotificationManager::with_error_handler(|notification, err| {
+//!     eprintln!("'{}' was not shown: {err}", notification.title);
+//! });
+//! manager.enqueue(NotificationBuilder::new().message("Export finished"))?;
+//! // drop（または shutdown()）すると、残りを送ってからワーカーを止める
+//! # Ok::<(), rust_toast::NotificationError>(())
+//! ```
+//!
+//! # 学習ポイント
+//! - `std::sync::mpsc` のチャネルをキューにしたワーカースレッド
+//! - `Drop` で送信側を閉じ、ワーカーの終了を待つ（グレースフルシャットダウン）
+//! - `Box<dyn Fn + Send>` によるコールバック
+//! - `BinaryHeap` と `recv_timeout()` による時刻順の待ち合わせ
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{Clock, CurrentClock};
+use crate::config;
+use crate::error::{NotificationError, Result};
+use crate::notifier::{self, Notification, NotificationBuilder};
+
+/// 予約を待っている間に、新しい指示を確かめる間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 送信に失敗した時に呼ばれるハンドラ（ワーカースレッドで呼ばれる）
+pub type ErrorHandler = Box<dyn Fn(&Notification, &NotificationError) + Send>;
+
+/// ワーカーへの指示
+enum Job {
+    /// 通知を送信する
+    Send(Box<NotificationBuilder>),
+    /// 指定した時刻に通知を送信する
+    SendAt(SystemTime, Box<NotificationBuilder>),
+    /// ここまでの通知（予約したものを含む）を送り終えたら知らせる
+    Flush(Sender<()>),
+}
+
+/// 時刻を待っている指示（`due` が同じなら追加した順）
+struct Pending {
+    due: SystemTime,
+    seq: u64,
+    job: Job,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        (self.due, self.seq) == (other.due, other.seq)
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.due, self.seq).cmp(&(other.due, other.seq))
+    }
+}
+
+/// 専用のワーカースレッドで通知を送信するマネージャー
+#[derive(Debug)]
+pub struct NotificationManager {
+    clock: Arc<dyn Clock>,
+    queue: Option<Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl NotificationManager {
+    /// 失敗をログ（`tracing` の warn）に出すマネージャーを作成（ワーカースレッドを起動する）
+    pub fn new() -> Self {
+        Self::with_error_handler(|notification, err| {
+            tracing::warn!("notification '{}' failed: {err}", notification.title);
+        })
+    }
+
+    /// 失敗をハンドラに渡すマネージャーを作成（ワーカースレッドを起動する）
+    pub fn with_error_handler(
+        handler: impl Fn(&Notification, &NotificationError) + Send + 'static,
+    ) -> Self {
+        Self::spawn(Arc::new(CurrentClock), Box::new(handler))
+    }
+
+    /// 時計を指定してワーカースレッドを起動する（テストでは `ManualClock` を渡す）
+    fn spawn(clock: Arc<dyn Clock>, handler: ErrorHandler) -> Self {
+        let (queue, jobs) = mpsc::channel();
+        let worker_clock = Arc::clone(&clock);
+        let worker = thread::Builder::new()
+            .name("rust-toast-manager".to_string())
+            .spawn(move || work(jobs, handler, worker_clock.as_ref()))
+            .expect("failed to spawn the notification manager thread");
+        Self {
+            clock,
+            queue: Some(queue),
+            worker: Some(worker),
+        }
+    }
+
+    /// 通知をキューに追加する（送信を待たずにすぐ戻る）
+    ///
+    /// ワーカーが止まっている（ハンドラがパニックした）場合はエラーを返します。
+    pub fn enqueue(&self, notification: impl Into<NotificationBuilder>) -> Result<()> {
+        self.push(Job::Send(Box::new(notification.into())))
+    }
+
+    /// `delay` の後に通知を送信するよう予約する（すぐに戻る）
+    pub fn schedule_after(
+        &self,
+        notification: impl Into<NotificationBuilder>,
+        delay: Duration,
+    ) -> Result<()> {
+        self.push(Job::SendAt(
+            self.clock.now() + delay,
+            Box::new(notification.into()),
+        ))
+    }
+
+    /// `at` の時刻に通知を送信するよう予約する（過ぎた時刻ならすぐに送る）
+    pub fn schedule_at(
+        &self,
+        notification: impl Into<NotificationBuilder>,
+        at: SystemTime,
+    ) -> Result<()> {
+        self.push(Job::SendAt(at, Box::new(notification.into())))
+    }
+
+    /// ここまでにキューに追加した通知を送り終えるまで待つ
+    ///
+    /// 予約した通知も含むため、その時刻まで戻りません。
+    /// `Config::spool` が有効なら、送信待ちキュー（スプール）に残った通知も古い順に数件送り直します
+    /// （送れなかった通知は残り、ログに出します）。
+    pub fn flush(&self) -> Result<()> {
+        let (done, finished) = mpsc::channel();
+        self.push(Job::Flush(done))?;
+        finished.recv().map_err(|_| stopped())
+    }
+
+    /// 残りの通知を送ってから、ワーカースレッドを止める（drop と同じ）
+    ///
+    /// 予約した通知があれば、その時刻まで待ってから送ります。
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn push(&self, job: Job) -> Result<()> {
+        self.queue
+            .as_ref()
+            .ok_or_else(stopped)?
+            .send(job)
+            .map_err(|_| stopped())
+    }
+
+    fn stop(&mut self) {
+        // 送信側を閉じると、ワーカーはキューを空にしてから終わる
+        drop(self.queue.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Default for NotificationManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for NotificationManager {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// プロセス全体で共有するマネージャー（`send_after()` / `send_at()` が使う）
+///
+/// `static` は drop されないため、予約した通知を送る前にプロセスを終えないよう、
+/// 必要なら `flush()` で待ってください。
+pub fn global() -> &'static NotificationManager {
+    static MANAGER: OnceLock<NotificationManager> = OnceLock::new();
+    MANAGER.get_or_init(NotificationManager::new)
+}
+
+/// ワーカースレッドの本体
+///
+/// 送信側が全て閉じられ（マネージャーが drop され）、予約した通知も送り終えるまで続けます。
+fn work(jobs: Receiver<Job>, handler: ErrorHandler, clock: &dyn Clock) {
+    let mut pending: BinaryHeap<Reverse<Pending>> = BinaryHeap::new();
+    let mut seq = 0;
+    let mut open = true;
+    loop {
+        // 時刻が来た指示を実行する
+        while pending
+            .peek()
+            .is_some_and(|Reverse(next)| next.due <= clock.now())
+        {
+            let Some(Reverse(next)) = pending.pop() else {
+                break;
+            };
+            run(next.job, &handler);
+        }
+
+        // 次の指示か、次の予約の時刻を待つ（待つのは時計の sleep() なので、偽の時計では待たない）
+        let job = match (pending.peek(), open) {
+            (Some(Reverse(next)), true) => match jobs.try_recv() {
+                Ok(job) => Some(job),
+                Err(TryRecvError::Empty) => {
+                    clock.sleep(until(clock, next.due).min(POLL_INTERVAL));
+                    None
+                }
+                Err(TryRecvError::Disconnected) => {
+                    open = false;
+                    None
+                }
+            },
+            (Some(Reverse(next)), false) => {
+                clock.sleep(until(clock, next.due));
+                None
+            }
+            (None, true) => {
+                let job = jobs.recv().ok();
+                open = job.is_some();
+                job
+            }
+            (None, false) => break,
+        };
+
+        seq += 1;
+        match job {
+            None => {}
+            Some(Job::SendAt(due, builder)) => pending.push(Reverse(Pending {
+                due,
+                seq,
+                job: Job::Send(builder),
+            })),
+            // 予約が残っていれば、最後の予約の後に知らせる
+            Some(Job::Flush(done)) if !pending.is_empty() => {
+                let due = pending
+                    .iter()
+                    .map(|Reverse(next)| next.due)
+                    .max()
+                    .unwrap_or_else(|| clock.now());
+                pending.push(Reverse(Pending {
+                    due,
+                    seq,
+                    job: Job::Flush(done),
+                }));
+            }
+            Some(job) => run(job, &handler),
+        }
+    }
+}
+
+/// `due` までの残り時間（過ぎていれば 0）
+fn until(clock: &dyn Clock, due: SystemTime) -> Duration {
+    due.duration_since(clock.now()).unwrap_or_default()
+}
+
+/// 指示を 1 つ実行する
+fn run(job: Job, handler: &ErrorHandler) {
+    match job {
+        Job::Send(builder) | Job::SendAt(_, builder) => {
+            let notification = builder.clone().build();
+            if let Err(err) = builder.send() {
+                handler(&notification, &err);
+            }
+        }
+        Job::Flush(done) => {
+            notifier::retry_spool(&config::current());
+            let _ = done.send(());
+        }
+    }
+}
+
+fn stopped() -> NotificationError {
+    NotificationError::Other("notification manager has stopped".to_string())
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::notifier::{register_backend, unregister_backend, Backend, Notifier};
+    use std::sync::Mutex;
+    use std::time::{Instant, UNIX_EPOCH};
+
+    /// 本文が "fail" なら失敗し、それ以外は記録するバックエンド
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Recorder {
+        fn send(&self, notification: &Notification) -> Result<()> {
+            if notification.message == "fail" {
+                return Err(NotificationError::Other("rejected".to_string()));
+            }
+            self.0.lock().unwrap().push(notification.message.clone());
+            Ok(())
+        }
+        fn is_available(&self) -> bool {
+            true
+        }
+        fn backend_name(&self) -> &'static str {
+            "Recorder"
+        }
+    }
+
+    #[test]
+    fn test_manager_delivers_in_order_and_reports_errors() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        register_backend("manager-recorder", Box::new(Recorder(Arc::clone(&sent))));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&errors);
+        let manager = NotificationManager::with_error_handler(move |notification, err| {
+            reported
+                .lock()
+                .unwrap()
+                .push(format!("{}: {err}", notification.message));
+        });
+
+        let builder = |message: &str| {
+            NotificationBuilder::new()
+                .message(message)
+                .backend(Backend::Custom("manager-recorder".to_string()))
+                .fallback_chain(Vec::<Backend>::new())
+        };
+        for message in ["1", "fail", "2"] {
+            manager.enqueue(builder(message)).unwrap();
+        }
+        manager.flush().unwrap();
+        assert_eq!(*sent.lock().unwrap(), ["1", "2"]);
+        assert_eq!(*errors.lock().unwrap(), ["fail: rejected"]);
+
+        // shutdown() は残りを送ってから止まる
+        manager.enqueue(builder("3").build()).unwrap();
+        manager.shutdown();
+        assert_eq!(*sent.lock().unwrap(), ["1", "2", "3"]);
+        unregister_backend("manager-recorder");
+    }
+
+    #[test]
+    fn test_scheduled_notifications_wait_for_their_time() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        register_backend("schedule-recorder", Box::new(Recorder(Arc::clone(&sent))));
+        let builder = |message: &str| {
+            NotificationBuilder::new()
+                .message(message)
+                .backend(Backend::Custom("schedule-recorder".to_string()))
+        };
+        // 偽の時計では sleep() が時刻を進めるだけなので、実際には待たない
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let clock = Arc::new(ManualClock::new(start));
+        let manager = NotificationManager::spawn(clock.clone(), Box::new(|_, _| {}));
+        let started = Instant::now();
+        manager
+            .schedule_after(builder("later"), Duration::from_secs(3_600))
+            .unwrap();
+        manager
+            .schedule_at(builder("soon"), start + Duration::from_secs(60))
+            .unwrap();
+        manager.enqueue(builder("now")).unwrap();
+
+        // flush() は予約した通知を送り終えるまで待つ
+        manager.flush().unwrap();
+        assert!(clock.now() >= start + Duration::from_secs(3_600));
+        assert!(started.elapsed() < Duration::from_secs(60));
+        assert_eq!(*sent.lock().unwrap(), ["now", "soon", "later"]);
+        unregister_backend("schedule-recorder");
+    }
+}
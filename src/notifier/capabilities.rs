@@ -0,0 +1,143 @@
+//! バックエンドが対応している機能
+//!
+//! `Notifier::capabilities()` は、そのバックエンドで使える機能（アクションボタン・画像・
+//! 置き換えなど）を返します。呼び出し側は送る前に確認して、対応していない機能を
+//! 使わない通知に切り替えられます（`rust-toast backends --detailed` で一覧できます）。
+//!
+//! | バックエンド | 判定方法 |
+//! |-------------|---------|
+//! | Linux | 通知デーモンの `GetCapabilities` の結果 |
+//! | Windows / WSL | トーストの仕様（アクションは 5 個まで） |
+//! | macOS | `alerter` / `terminal-notifier` がインストールされているか |
+//! | その他 | 何も対応していない（`Capabilities::default()`） |
+//!
+//! # 例
+//! ```
+//! use rust_toast::notifier::{backend_capabilities, Backend};
+//!
+//! let capabilities = backend_capabilities(Backend::Console);
+//! if !capabilities.supports_actions {
+//!     // アクションボタンの代わりに、本文に操作方法を書く
+//! }
+//! ```
+//!
+//! # 学習ポイント
+//! - 推測ではなく、問い合わせた結果で振る舞いを変える（機能検出）
+//! - `Default` を「何もできない」にして、分からない時は安全な側に倒す
+
+use std::fmt;
+
+/// バックエンドが対応している機能
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// アクションボタン（`NotificationBuilder::action()`）
+    pub supports_actions: bool,
+    /// 返信の入力欄（`NotificationBuilder::reply_action()`）
+    pub supports_reply: bool,
+    /// 画像（`NotificationBuilder::image()`）
+    pub supports_images: bool,
+    /// 本文のマークアップ（Markdown の本文を書式付きで表示できる）
+    pub supports_markup: bool,
+    /// 通知音
+    pub supports_sound: bool,
+    /// 表示中の通知の置き換え（`Notification::replace_id`）
+    pub supports_replace: bool,
+    /// 表示中の通知を閉じる（`Notifier::close()`）
+    pub supports_close: bool,
+    /// ユーザーの操作を待つ（`Notifier::send_and_wait()`）
+    pub supports_wait: bool,
+    /// アクションボタンの最大数（`None` は上限が分からない）
+    pub max_actions: Option<usize>,
+    /// タイトルの最大文字数（`None` は上限が分からない）
+    pub max_title_len: Option<usize>,
+    /// 本文の最大文字数（`None` は上限が分からない）
+    pub max_message_len: Option<usize>,
+}
+
+impl Capabilities {
+    /// freedesktop の通知デーモンが `GetCapabilities` で返した一覧から作る
+    ///
+    /// 置き換え・閉じる・操作の待ち受け（`NotificationClosed` シグナル）は仕様で必須です。
+    pub fn from_server(capabilities: &[String]) -> Self {
+        let has = |name: &str| capabilities.iter().any(|c| c == name);
+        Self {
+            supports_actions: has("actions"),
+            supports_reply: has("inline-reply"),
+            supports_images: has("body-images") || has("icon-static"),
+            supports_markup: has("body-markup"),
+            supports_sound: has("sound"),
+            supports_replace: true,
+            supports_close: true,
+            supports_wait: true,
+            ..Self::default()
+        }
+    }
+
+    /// 対応している機能の名前（`Display` の順）
+    fn supported(&self) -> Vec<String> {
+        let flags = [
+            (self.supports_actions, "actions"),
+            (self.supports_reply, "reply"),
+            (self.supports_images, "images"),
+            (self.supports_markup, "markup"),
+            (self.supports_sound, "sound"),
+            (self.supports_replace, "replace"),
+            (self.supports_close, "close"),
+            (self.supports_wait, "wait"),
+        ];
+        let mut names: Vec<String> = flags
+            .iter()
+            .filter(|(supported, _)| *supported)
+            .map(|(_, name)| name.to_string())
+            .collect();
+        let limits = [
+            (self.max_actions, "actions"),
+            (self.max_title_len, "title"),
+            (self.max_message_len, "message"),
+        ];
+        for (limit, name) in limits {
+            if let Some(limit) = limit {
+                names.push(format!("{name}<={limit}"));
+            }
+        }
+        names
+    }
+}
+
+/// 対応している機能を空白区切りで並べる（例: `actions images replace actions<=5`）
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let supported = self.supported();
+        if supported.is_empty() {
+            write!(f, "(none)")
+        } else {
+            write!(f, "{}", supported.join(" "))
+        }
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_server_capabilities() {
+        let server: Vec<String> = ["body", "body-markup", "actions", "icon-static"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let capabilities = Capabilities::from_server(&server);
+        assert!(capabilities.supports_actions);
+        assert!(capabilities.supports_images);
+        assert!(!capabilities.supports_sound);
+        assert_eq!(
+            capabilities.to_string(),
+            "actions images markup replace close wait"
+        );
+        assert_eq!(Capabilities::default().to_string(), "(none)");
+    }
+}
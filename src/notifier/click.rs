@@ -0,0 +1,163 @@
+//! クリック時の動作
+//!
+//! `NotificationBuilder::on_click_url()` で指定した URL を開き、
+//! `on_click_exec()` で指定したコマンドを実行します。
+//! OS の通知が自分で URL を開ける場合（Windows のプロトコル起動、macOS の terminal-notifier）は
+//! バックエンドに任せ、それ以外では `send_and_wait()` がクリックを受け取った後にここで開きます。
+//! コマンドは macOS の terminal-notifier（`-execute`）以外では常にここで実行します。
+//!
+//! | 環境 | URL を開くコマンド | コマンドを実行するシェル |
+//! |------|--------------------|--------------------------|
+//! | Linux / WSL | `xdg-open`（WSL は `rundll32.exe`） | `sh -c` |
+//! | macOS | `open` | `sh -c` |
+//! | Windows | `rundll32.exe url.dll,FileProtocolHandler` | `cmd.exe /C` |
+//!
+//! # 学習ポイント
+//! - URL はシェルを経由せずに引数で渡す（URL 内の `&` などを解釈させない）
+//! - 開く URL のスキームを許可リストで絞る（`file:` やアプリのスキームを開かせない）
+//! - コマンドは利用者がシェルの構文で書いたものとして、シェルに渡す
+
+use std::process::Stdio;
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{Interaction, Notification, Notifier};
+use crate::platform::{detect_platform, Platform};
+use crate::process;
+
+/// 操作の結果に応じてクリック時の動作を行う
+///
+/// 本体がクリックされた場合のみ動作します。
+/// URL はバックエンドが自分で開かない場合のみ開きます。
+pub(crate) fn handle(
+    notifier: &dyn Notifier,
+    notification: &Notification,
+    interaction: &Interaction,
+) -> Result<()> {
+    if *interaction != Interaction::Clicked {
+        return Ok(());
+    }
+    if let Some(url) = &notification.on_click_url {
+        if !notifier.opens_urls_natively() {
+            open_url(url)?;
+        }
+    }
+    if let Some(command) = &notification.on_click_exec {
+        run_command(command)?;
+    }
+    Ok(())
+}
+
+/// クリックで開いてよい URL のスキーム
+const URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// クリックで開く URL が `http` / `https` / `mailto` かどうか確かめる
+///
+/// 他のスキーム（`file:`、`smb:`、アプリ独自のスキームなど）は、
+/// 通知の送り手がローカルのファイルやアプリを開かせることになるため受け付けません。
+pub(crate) fn check_url(url: &str) -> Result<()> {
+    match url::Url::parse(url) {
+        Ok(parsed) if URL_SCHEMES.contains(&parsed.scheme()) => Ok(()),
+        _ => Err(NotificationError::InvalidInput {
+            field: "on_click_url".to_string(),
+            reason: format!("'{url}' is not an http, https or mailto URL"),
+        }),
+    }
+}
+
+/// URL を既定のアプリ（ブラウザなど）で開く（終了は待たない）
+pub(crate) fn open_url(url: &str) -> Result<()> {
+    check_url(url)?;
+    let (program, args) = opener(detect_platform(), url);
+    process::command(program)?
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// コマンドをシェルで実行する（終了は待たない）
+pub(crate) fn run_command(command: &str) -> Result<()> {
+    let (shell, args) = shell(detect_platform(), command);
+    process::command(shell)?
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// プラットフォームごとのシェルと引数
+///
+/// WSL では rust-toast 自身が Linux 側で動いているため、Linux のシェルを使います。
+fn shell(platform: Platform, command: &str) -> (&'static str, Vec<String>) {
+    match platform {
+        Platform::Windows => ("cmd.exe", vec!["/C".to_string(), command.to_string()]),
+        _ => ("sh", vec!["-c".to_string(), command.to_string()]),
+    }
+}
+
+/// プラットフォームごとの URL を開くコマンドと引数
+fn opener(platform: Platform, url: &str) -> (&'static str, Vec<String>) {
+    match platform {
+        Platform::MacOs => ("open", vec![url.to_string()]),
+        // cmd.exe の start は & などを解釈してしまうため、rundll32 で直接開く
+        Platform::Windows | Platform::Wsl => (
+            "rundll32.exe",
+            vec!["url.dll,FileProtocolHandler".to_string(), url.to_string()],
+        ),
+        Platform::Linux | Platform::Unknown => ("xdg-open", vec![url.to_string()]),
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opener_per_platform() {
+        let url = "https://example.com/?a=1&b=2";
+        assert_eq!(
+            opener(Platform::Linux, url),
+            ("xdg-open", vec![url.to_string()])
+        );
+        assert_eq!(opener(Platform::MacOs, url).0, "open");
+        assert_eq!(
+            opener(Platform::Wsl, url).1,
+            vec!["url.dll,FileProtocolHandler".to_string(), url.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_check_url_allows_web_and_mail_only() {
+        for url in [
+            "https://example.com/?a=1",
+            "HTTP://example.com",
+            "mailto:ops@example.com",
+        ] {
+            assert!(check_url(url).is_ok(), "{url}");
+        }
+        for url in [
+            "file:///etc/passwd",
+            "smb://host/share",
+            "ms-settings:",
+            "example.com",
+        ] {
+            assert!(check_url(url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_shell_per_platform() {
+        let command = "code /var/log/build.log";
+        assert_eq!(
+            shell(Platform::Wsl, command),
+            ("sh", vec!["-c".to_string(), command.to_string()])
+        );
+        assert_eq!(shell(Platform::Windows, command).0, "cmd.exe");
+    }
+}
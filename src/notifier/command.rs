@@ -0,0 +1,251 @@
+//! コマンドのテンプレートで送るバックエンド
+//!
+//! 対応していない環境（dwm + herbe、自作のスクリプトなど）でも、Rust を書かずに
+//! 通知を送れるように、コマンドラインのテンプレートをバックエンドとして使います。
+//! CLI では `--backend-command 'herbe {title} {message}'` で指定します。
+//!
+//! テンプレートは先に空白で引数に分け（`'...'` / `"..."` / `\` でまとめられる）、
+//! その後で引数ごとにプレースホルダーを置き換えます。シェルを通さないので、
+//! タイトルや本文に空白や記号があっても 1 つの引数のまま渡ります。
+//!
+//! | プレースホルダー | 値 |
+//! |------------------|----|
+//! | `{title}` | タイトル |
+//! | `{message}` | 本文 |
+//! | `{urgency}` | `low` / `normal` / `critical` |
+//! | `{subtitle}` | サブタイトル（無ければ空） |
+//! | `{icon}` | アイコン（無ければ空） |
+//! | `{timeout}` | 表示時間（ミリ秒、0 は無制限） |
+//! | `{app_name}` | 送信元のアプリ名（無ければ空） |
+//!
+//! `{hostname}` / `{date}` / `{env:VAR}` など `template` モジュールのものも使えます。
+//! 終了コードが 0 なら成功で、標準出力の最初の空でない行を通知 ID として扱います（プラグインと同じ）。
+//!
+//! # 例
+//! ```no_run
+//! use rust_toast::notifier::{register_backend, CustomCommandNotifier};
+//! use rust_toast::{Backend, NotificationBuilder};
+//!
+//! let herbe = CustomCommandNotifier::new("herbe {title} {message}")?;
+//! register_backend("herbe", Box::new(herbe));
+//! NotificationBuilder::new()
+//!     .message("Build finished")
+//!     .backend(Backend::Custom("herbe".to_string()))
+//!     .send()?;
+//! # Ok::<(), rust_toast::NotificationError>(())
+//! ```
+//!
+//! # 学習ポイント
+//! - シェルを通さない引数の組み立て（分けてから置き換える順序でインジェクションを防ぐ）
+//! - 文字ごとの状態遷移による引用符の解釈
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{invalid_field, Icon, Invocation, Notification, Notifier};
+use crate::process;
+use crate::template;
+
+/// コマンドのテンプレートで通知を送るバックエンド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomCommandNotifier {
+    template: String,
+    argv: Vec<String>,
+}
+
+impl CustomCommandNotifier {
+    /// コマンドラインのテンプレートから作成
+    ///
+    /// 引用符が閉じていない、コマンドが空、知らないプレースホルダーがある場合は
+    /// `NotificationError::InvalidInput` を返します。
+    pub fn new(template: impl Into<String>) -> Result<Self> {
+        let template = template.into();
+        let argv = split_words(&template)?;
+        if argv.is_empty() {
+            return Err(invalid_field("command", "the template is empty"));
+        }
+        // 送る前に、プレースホルダーの名前を確かめておく
+        let vars = variables(&Notification::default());
+        for arg in &argv {
+            template::render(arg, &vars)?;
+        }
+        Ok(Self { template, argv })
+    }
+
+    /// 元のテンプレート
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// 通知のプレースホルダーを置き換えたコマンドライン（プログラムと引数）
+    fn command_line(&self, notification: &Notification) -> Result<(String, Vec<String>)> {
+        let vars = variables(notification);
+        let mut argv = self
+            .argv
+            .iter()
+            .map(|arg| template::render(arg, &vars))
+            .collect::<Result<Vec<_>>>()?;
+        let program = argv.remove(0);
+        Ok((program, argv))
+    }
+}
+
+impl Notifier for CustomCommandNotifier {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        self.deliver(notification).map(drop)
+    }
+
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        let (program, args) = self.command_line(notification)?;
+        let mut command = if program.contains(std::path::MAIN_SEPARATOR) {
+            process::check_path(Path::new(&program))?;
+            Command::new(&program)
+        } else {
+            process::command(&program)?
+        };
+        command.args(&args);
+        let output = process::output_within(command, notification.command_timeout)?;
+        if !output.status.success() {
+            return Err(NotificationError::SendFailed {
+                backend: self.backend_name().to_string(),
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string))
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        let (program, args) = self.command_line(notification)?;
+        Ok(Invocation::command(program, args))
+    }
+
+    fn is_available(&self) -> bool {
+        let program = &self.argv[0];
+        if program.contains(std::path::MAIN_SEPARATOR) {
+            Path::new(program).is_file()
+        } else {
+            process::resolve(program).is_some()
+        }
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "Command"
+    }
+}
+
+/// プレースホルダーの値
+fn variables(notification: &Notification) -> BTreeMap<String, String> {
+    let optional = |value: Option<&str>| value.unwrap_or_default().to_string();
+    BTreeMap::from([
+        ("title".to_string(), notification.title.clone()),
+        ("message".to_string(), notification.message.clone()),
+        (
+            "urgency".to_string(),
+            notification.urgency.as_str().to_string(),
+        ),
+        ("subtitle".to_string(), optional(notification.subtitle())),
+        (
+            "icon".to_string(),
+            notification.icon().map(Icon::to_string).unwrap_or_default(),
+        ),
+        ("timeout".to_string(), notification.timeout.to_string()),
+        (
+            "app_name".to_string(),
+            optional(notification.app_name.as_deref()),
+        ),
+    ])
+}
+
+/// コマンドラインを POSIX シェルと同じ規則で引数に分ける（展開やリダイレクトは行わない）
+///
+/// - `'...'` の中はそのまま
+/// - `"..."` の中は `\"` と `\\` だけをエスケープとして扱う
+/// - 引用符の外の `\` は次の 1 文字をそのまま使う
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(invalid_field("command", "unclosed ' quote")),
+                    }
+                }
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(invalid_field("command", "unclosed \" quote")),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(invalid_field("command", "unclosed \" quote")),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| invalid_field("command", "trailing backslash"))?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotificationBuilder;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words(r#"herbe  '{title}: x' "say \"{message}\"" a\ b ''"#).unwrap(),
+            ["herbe", "{title}: x", r#"say "{message}""#, "a b", ""]
+        );
+        assert!(split_words("notify-send 'oops").is_err());
+    }
+
+    #[test]
+    fn test_command_line_keeps_each_placeholder_one_argument() {
+        let notifier = CustomCommandNotifier::new("herbe {title} '[{urgency}] {message}'").unwrap();
+        let notification = NotificationBuilder::new()
+            .title("Build; rm -rf /")
+            .message("done in 3 min")
+            .build();
+        let (program, args) = notifier.command_line(&notification).unwrap();
+        assert_eq!(program, "herbe");
+        assert_eq!(args, ["Build; rm -rf /", "[normal] done in 3 min"]);
+
+        assert!(CustomCommandNotifier::new("herbe {nope}").is_err());
+        assert!(CustomCommandNotifier::new("  ").is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! コンソール通知バックエンド（最終フォールバック）
+//!
+//! 通知を標準エラー出力に枠付きのバナーとして表示します。
+//! GUI が無い環境（SSH 先、未知のプラットフォーム）や、
+//! 他の全てのバックエンドが失敗した場合に使われます。
+//!
+//! ```text
+//! ╭──────────────────╮
+//! │ Build finished   │
+//! │ All tests passed │
+//! ╰──────────────────╯
+//! ```
+//!
+//! # 学習ポイント
+//! - ANSI エスケープシーケンスによる色付け
+//! - `NO_COLOR` 環境変数（<https://no-color.org>）への対応
+//! - `std::io::IsTerminal` による出力先の判定
+//! - 全角文字を考慮した表示幅の計算
+
+use crate::error::Result;
+use crate::notifier::{Invocation, Notification, Notifier, UrgencyLevel};
+use std::io::{IsTerminal, Write};
+
+/// コンソール通知バックエンド
+pub struct ConsoleNotifier;
+
+impl Notifier for ConsoleNotifier {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let banner = render_banner(notification, use_color());
+
+        // stderr をロックして一度に書き込む（他スレッドの出力と混ざらないように）
+        let mut stderr = std::io::stderr().lock();
+        stderr.write_all(banner.as_bytes())?;
+        stderr.flush()?;
+        Ok(())
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        Ok(Invocation::Output(render_banner(notification, false)))
+    }
+
+    fn is_available(&self) -> bool {
+        // 標準エラー出力はどの環境にもある
+        true
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "Console"
+    }
+}
+
+/// 色付き出力を使うかどうか
+///
+/// `NO_COLOR` が空でない値で設定されているか、
+/// stderr が端末でない（ファイルやパイプへのリダイレクト）場合は色を付けません。
+fn use_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && std::io::stderr().is_terminal()
+}
+
+/// 緊急度に応じた ANSI カラーコード
+fn urgency_color(urgency: UrgencyLevel) -> &'static str {
+    match urgency {
+        UrgencyLevel::Low => "2;34",      // 薄い青
+        UrgencyLevel::Normal => "36",     // シアン
+        UrgencyLevel::Critical => "1;31", // 太字の赤
+    }
+}
+
+/// 通知を枠付きのバナー文字列に変換する
+fn render_banner(notification: &Notification, color: bool) -> String {
+    // 表示する行（タイトル → サブタイトル → 本文 → 出どころ）
+    let mut lines: Vec<&str> = vec![notification.title.as_str()];
+    if let Some(subtitle) = notification.visible_subtitle() {
+        lines.push(subtitle);
+    }
+    lines.extend(notification.message.lines());
+    if let Some(attribution) = &notification.attribution {
+        lines.push(attribution.as_str());
+    }
+
+    let width = lines
+        .iter()
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or(0);
+
+    // 色付きの場合は枠線を緊急度の色に、タイトルを太字にする
+    let (border_start, title_start, reset) = if color {
+        (
+            format!("\x1b[{}m", urgency_color(notification.urgency)),
+            "\x1b[1m".to_string(),
+            "\x1b[0m",
+        )
+    } else {
+        (String::new(), String::new(), "")
+    };
+
+    let horizontal = "─".repeat(width + 2);
+    let mut banner = format!("{border_start}╭{horizontal}╮{reset}\n");
+
+    for (index, line) in lines.iter().enumerate() {
+        let padding = " ".repeat(width - display_width(line));
+        let (start, end) = if index == 0 {
+            (title_start.as_str(), reset)
+        } else {
+            ("", "")
+        };
+        banner.push_str(&format!(
+            "{border_start}│{reset} {start}{line}{end}{padding} {border_start}│{reset}\n"
+        ));
+    }
+
+    banner.push_str(&format!("{border_start}╰{horizontal}╯{reset}\n"));
+    banner
+}
+
+/// 端末上での表示幅を計算する
+///
+/// 日本語などの全角文字は 2 桁分の幅を取るため、
+/// `chars().count()` だけでは枠がずれてしまいます。
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1FAFF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotificationBuilder;
+
+    #[test]
+    fn test_backend_name() {
+        assert_eq!(ConsoleNotifier.backend_name(), "Console");
+        assert!(ConsoleNotifier.is_available());
+    }
+
+    #[test]
+    fn test_render_banner_plain() {
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .message("All tests passed")
+            .build();
+
+        assert_eq!(
+            render_banner(&notification, false),
+            "╭──────────────────╮\n\
+             │ Build            │\n\
+             │ All tests passed │\n\
+             ╰──────────────────╯\n"
+        );
+    }
+
+    #[test]
+    fn test_render_banner_colored_critical() {
+        let notification = NotificationBuilder::new()
+            .title("Disk full")
+            .urgency(UrgencyLevel::Critical)
+            .build();
+
+        let banner = render_banner(&notification, true);
+        assert!(banner.starts_with("\x1b[1;31m╭"));
+        assert!(banner.contains("\x1b[1mDisk full\x1b[0m"));
+    }
+
+    #[test]
+    fn test_display_width_wide_chars() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("通知"), 4);
+    }
+}
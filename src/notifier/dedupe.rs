@@ -0,0 +1,193 @@
+//! 同じ通知の重複をまとめる
+//!
+//! `Config::dedupe_window`（例: 30 秒）を設定すると、同じタイトルと本文の通知が
+//! 前回から窓の時間内に届いた場合、新しい通知を出さずに前回の通知を
+//! 本文に「(×3)」のような回数を付けて置き換えます（`replace_id` の仕組みを使う）。
+//! 前回の通知に ID が無い（置き換えに対応していないバックエンド）場合は、表示せずに数えるだけです。
+//! 記録するのは表示できた通知だけなので、送信に失敗した通知の送り直しはまとめられません。
+//!
+//! ログの監視のように同じ行が続けて出る場合に、通知で画面が埋まるのを防ぎます。
+//! 記録はプロセス内だけです（`latency` と同じ）。
+//!
+//! # 学習ポイント
+//! - `HashMap` のキーにタプルを使う
+//! - 依存性の注入（`Clock`）で時間に依存する判定をテスト可能にする
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{Clock, CurrentClock};
+use crate::notifier::{Backend, Notification};
+
+/// 重複とみなす通知の組（バックエンド、タイトル、本文）
+type Key = (Option<Backend>, String, String);
+
+/// 1 つの通知の記録
+#[derive(Debug)]
+struct Seen {
+    /// 最後に届いた時刻
+    last: SystemTime,
+    /// 届いた回数
+    count: u32,
+    /// 表示中の通知の ID（置き換えに使う）
+    id: Option<String>,
+}
+
+/// 重複の判定結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Dedupe {
+    /// 初めての通知（そのまま送る）
+    New,
+    /// 窓の時間内の重複（`count` 回目）。ID があればその通知を置き換える
+    Repeat {
+        count: u32,
+        replace_id: Option<String>,
+    },
+}
+
+/// 最近送った通知を記録し、重複を判定する
+#[derive(Debug)]
+pub(crate) struct Deduper {
+    clock: Arc<dyn Clock>,
+    seen: Mutex<HashMap<Key, Seen>>,
+}
+
+impl Deduper {
+    /// 時計を指定して作成
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 通知が前回から `window` 以内の重複かどうか（重複なら届いたことを数える）
+    ///
+    /// 初めての通知は記録しません。表示できた後で `remember()` が記録します。
+    pub(crate) fn check(&self, notification: &Notification, window: Duration) -> Dedupe {
+        let now = self.clock.now();
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        // 窓を過ぎた記録を捨てる（記録が溜まり続けないように）
+        seen.retain(|_, entry| now.duration_since(entry.last).unwrap_or_default() < window);
+
+        match seen.get_mut(&key(notification)) {
+            Some(entry) => {
+                entry.last = now;
+                entry.count += 1;
+                Dedupe::Repeat {
+                    count: entry.count,
+                    replace_id: entry.id.clone(),
+                }
+            }
+            None => Dedupe::New,
+        }
+    }
+
+    /// `check()` と同じ判定をするが、記録しない（シミュレーション用）
+    pub(crate) fn peek(&self, notification: &Notification, window: Duration) -> Dedupe {
+        let now = self.clock.now();
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        match seen
+            .get(&key(notification))
+            .filter(|entry| now.duration_since(entry.last).unwrap_or_default() < window)
+        {
+            Some(entry) => Dedupe::Repeat {
+                count: entry.count + 1,
+                replace_id: entry.id.clone(),
+            },
+            None => Dedupe::New,
+        }
+    }
+
+    /// 表示できた通知とその ID を記録する（ID が無ければ前回の ID を残す）
+    pub(crate) fn remember(&self, notification: &Notification, id: Option<&str>) {
+        let now = self.clock.now();
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = seen.entry(key(notification)).or_insert(Seen {
+            last: now,
+            count: 1,
+            id: None,
+        });
+        if let Some(id) = id {
+            entry.id = Some(id.to_string());
+        }
+    }
+}
+
+fn key(notification: &Notification) -> Key {
+    (
+        notification.backend_override.clone(),
+        notification.title.clone(),
+        notification.message.clone(),
+    )
+}
+
+/// 重複の回数を本文に付ける（例: `Disk full (×3)`）
+pub(crate) fn with_counter(notification: &Notification, count: u32) -> Notification {
+    let mut repeated = notification.clone();
+    repeated.message = format!("{} (×{count})", notification.message);
+    if let Some(markdown) = &notification.markdown {
+        repeated.markdown = Some(format!("{markdown} (×{count})"));
+    }
+    repeated
+}
+
+/// プロセス全体で共有する `Deduper`（時計は呼ばれるたびに `clock::current()` を読む）
+pub(crate) fn global() -> &'static Deduper {
+    static DEDUPER: OnceLock<Deduper> = OnceLock::new();
+    DEDUPER.get_or_init(|| Deduper::new(Arc::new(CurrentClock)))
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::notifier::NotificationBuilder;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_dedupe_within_window() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+        let deduper = Deduper::new(clock.clone());
+        let window = Duration::from_secs(30);
+        let disk = NotificationBuilder::new()
+            .title("Disk")
+            .message("full")
+            .build();
+        let other = NotificationBuilder::new()
+            .title("Disk")
+            .message("ok")
+            .build();
+
+        assert_eq!(deduper.peek(&disk, window), Dedupe::New);
+        // 表示できなかった通知は記録しないので、送り直しもそのまま送る
+        assert_eq!(deduper.check(&disk, window), Dedupe::New);
+        assert_eq!(deduper.check(&disk, window), Dedupe::New);
+        deduper.remember(&disk, Some("7"));
+        assert_eq!(deduper.check(&other, window), Dedupe::New);
+
+        clock.advance(Duration::from_secs(20));
+        let repeat = |count| Dedupe::Repeat {
+            count,
+            replace_id: Some("7".to_string()),
+        };
+        // peek() は数えない
+        assert_eq!(deduper.peek(&disk, window), repeat(2));
+        assert_eq!(deduper.check(&disk, window), repeat(2));
+        // 窓は最後に届いた時刻から数える
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(deduper.check(&disk, window), repeat(3));
+
+        clock.advance(window);
+        assert_eq!(deduper.check(&disk, window), Dedupe::New);
+
+        let counted = with_counter(&disk, 3);
+        assert_eq!(counted.message, "full (×3)");
+        assert_eq!(counted.title, "Disk");
+    }
+}
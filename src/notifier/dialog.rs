@@ -0,0 +1,237 @@
+//! ダイアログ通知バックエンド（フォールバック用）
+//!
+//! 通知デーモンが動いていない環境でもメッセージが失われないよう、
+//! モーダルダイアログとして表示します。
+//!
+//! | Platform | 使用するコマンド |
+//! |----------|-----------------|
+//! | Linux | `zenity --info` または `kdialog --msgbox` |
+//! | WSL / Windows | PowerShell の `MessageBox` |
+//! | macOS | `osascript` の `display dialog` |
+//!
+//! # 学習ポイント
+//! - 実行時のコマンド存在チェックによるバックエンド切り替え
+//! - 他モジュールのヘルパー関数（エスケープ処理）の再利用
+//! - コマンドと引数をデータとして組み立て、テストしやすくする設計
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::macos::escape_applescript;
+use crate::notifier::windows::escape_powershell;
+use crate::notifier::{Invocation, Notification, Notifier};
+use crate::platform::{detect_platform, find_executable, Platform};
+use crate::process;
+
+/// ダイアログ通知バックエンド
+///
+/// 通常の通知と違いユーザーが閉じるまで表示され続けるため、
+/// 主に他のバックエンドが失敗した時のフォールバックとして使います。
+pub struct DialogNotifier;
+
+/// 実行するコマンドとその引数
+///
+/// `Command` を直接組み立てず、一度この構造体に落とし込むことで
+/// 「どんなコマンドが実行されるか」をテストで検証できます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DialogCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Notifier for DialogNotifier {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let command = dialog_command(detect_platform(), notification).ok_or_else(no_dialog_tool)?;
+
+        let output = process::command(&command.program)?
+            .args(&command.args)
+            .output()?;
+
+        // zenity は --timeout で閉じられた場合に終了コード 5 を返すが、
+        // メッセージは表示済みなので成功として扱う
+        if output.status.success() || output.status.code() == Some(5) {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(NotificationError::SendFailed {
+                backend: "Dialog".to_string(),
+                reason: stderr.to_string(),
+            })
+        }
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        let command = dialog_command(detect_platform(), notification).ok_or_else(no_dialog_tool)?;
+        Ok(Invocation::command(command.program, command.args))
+    }
+
+    fn is_available(&self) -> bool {
+        match detect_platform() {
+            Platform::Linux => linux_dialog_tool().is_some(),
+            Platform::Unknown => false,
+            _ => true,
+        }
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "Dialog"
+    }
+}
+
+/// ダイアログを表示できるツールが無い場合のエラー
+fn no_dialog_tool() -> NotificationError {
+    NotificationError::BackendUnavailable {
+        backend: DialogNotifier.backend_name().to_string(),
+        hint: "no dialog tool found (install zenity or kdialog)".to_string(),
+    }
+}
+
+/// プラットフォームに応じたダイアログ表示コマンドを組み立てる
+///
+/// 利用できるツールが無い場合は `None` を返します。
+fn dialog_command(platform: Platform, notification: &Notification) -> Option<DialogCommand> {
+    match platform {
+        Platform::Linux => linux_dialog_command(notification),
+        Platform::Wsl | Platform::Windows => Some(windows_dialog_command(notification)),
+        Platform::MacOs => Some(macos_dialog_command(notification)),
+        Platform::Unknown => None,
+    }
+}
+
+/// Linux で利用可能なダイアログツールを zenity → kdialog の順に探す
+fn linux_dialog_tool() -> Option<&'static str> {
+    // GUI セッションが無ければダイアログは出せない
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return None;
+    }
+
+    ["zenity", "kdialog"]
+        .into_iter()
+        .find(|tool| find_executable(tool).is_some())
+}
+
+/// Linux: zenity または kdialog のコマンドを組み立てる
+///
+/// `zenity --notification` は通知デーモンに依存するため、
+/// デーモン不在時でも確実に表示される `--info`（モーダル）を使います。
+fn linux_dialog_command(notification: &Notification) -> Option<DialogCommand> {
+    let tool = linux_dialog_tool()?;
+
+    if tool == "zenity" {
+        let mut args = vec![
+            "--info".to_string(),
+            format!("--title={}", notification.title),
+            format!("--text={}", notification.message),
+        ];
+        // zenity のタイムアウトは秒単位
+        if notification.timeout > 0 {
+            args.push(format!("--timeout={}", notification.timeout.div_ceil(1000)));
+        }
+        return Some(DialogCommand {
+            program: "zenity".to_string(),
+            args,
+        });
+    }
+
+    Some(DialogCommand {
+        program: "kdialog".to_string(),
+        args: vec![
+            "--title".to_string(),
+            notification.title.clone(),
+            "--msgbox".to_string(),
+            notification.message.clone(),
+        ],
+    })
+}
+
+/// Windows / WSL: WinForms の MessageBox を PowerShell から表示
+fn windows_dialog_command(notification: &Notification) -> DialogCommand {
+    let script = format!(
+        r#"
+        Add-Type -AssemblyName System.Windows.Forms
+        [System.Windows.Forms.MessageBox]::Show('{}', '{}') | Out-Null
+        "#,
+        escape_powershell(&notification.message),
+        escape_powershell(&notification.title)
+    );
+
+    DialogCommand {
+        program: "powershell.exe".to_string(),
+        args: vec![
+            "-NoProfile".to_string(),
+            "-NonInteractive".to_string(),
+            "-Command".to_string(),
+            script,
+        ],
+    }
+}
+
+/// macOS: AppleScript の `display dialog` を使用
+fn macos_dialog_command(notification: &Notification) -> DialogCommand {
+    let mut script = format!(
+        r#"display dialog "{}" with title "{}" buttons {{"OK"}} default button "OK""#,
+        escape_applescript(&notification.message),
+        escape_applescript(&notification.title)
+    );
+
+    // giving up after: 指定秒数で自動的に閉じる
+    if notification.timeout > 0 {
+        script.push_str(&format!(
+            " giving up after {}",
+            notification.timeout.div_ceil(1000)
+        ));
+    }
+
+    DialogCommand {
+        program: "osascript".to_string(),
+        args: vec!["-e".to_string(), script],
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotificationBuilder;
+    use std::time::Duration;
+
+    #[test]
+    fn test_backend_name() {
+        assert_eq!(DialogNotifier.backend_name(), "Dialog");
+    }
+
+    #[test]
+    fn test_unknown_platform_has_no_dialog() {
+        let notification = NotificationBuilder::new().build();
+        assert!(dialog_command(Platform::Unknown, &notification).is_none());
+    }
+
+    #[test]
+    fn test_macos_dialog_command() {
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .message(r#"Say "done""#)
+            .timeout(Duration::from_millis(2500))
+            .build();
+
+        let command = macos_dialog_command(&notification);
+        assert_eq!(command.program, "osascript");
+        assert_eq!(
+            command.args[1],
+            r#"display dialog "Say \"done\"" with title "Build" buttons {"OK"} default button "OK" giving up after 3"#
+        );
+    }
+
+    #[test]
+    fn test_windows_dialog_command_escapes_quotes() {
+        let notification = NotificationBuilder::new()
+            .title("It's")
+            .message("done")
+            .build();
+
+        let command = windows_dialog_command(&notification);
+        assert_eq!(command.program, "powershell.exe");
+        assert!(command.args[3].contains("MessageBox]::Show('done', 'It''s')"));
+    }
+}
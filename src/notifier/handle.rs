@@ -0,0 +1,261 @@
+//! 送信済み通知のハンドル
+//!
+//! `send()` は表示した通知を指す `NotificationHandle` を返します。
+//! ハンドルの `update()` を使うと、新しい通知を増やさずに
+//! 表示中の通知をその場で書き換えられます（進捗表示など）。
+//!
+//! | バックエンド | ID の正体 |
+//! |-------------|-----------|
+//! | Linux | D-Bus の通知 ID（`replaces_id`） |
+//! | Windows | トーストの Tag（Group は `rust-toast` 固定、`app_name` 指定時は `Tag@AppUserModelID`） |
+//! | macOS | alerter の `-group`（alerter 使用時のみ） |
+//!
+//! `group()` を指定した場合、Windows と macOS ではグループのタグが ID になります。
+//!
+//! ハンドルの `report()` には、どのバックエンドが表示したか・かかった時間・
+//! フォールバックした場合は失敗したバックエンドのエラー出力の抜粋（`SendReport`）が入ります。
+//!
+//! # 学習ポイント
+//! - `&mut self` のメソッドで自身の状態を更新する
+//! - `AtomicU32` によるロック不要のカウンタ
+
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::clock;
+use crate::error::{NotificationError, Result};
+use crate::notifier::{dismiss, Notification, NotificationBuilder};
+
+/// エラー出力の抜粋の最大文字数
+const STDERR_EXCERPT_CHARS: usize = 200;
+
+/// 送信の結果の詳細（`NotificationHandle::report()`）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SendReport {
+    /// 通知を表示したバックエンドの名前（表示しなかった・送信待ちキューに保存した場合は `None`）
+    pub backend: Option<&'static str>,
+    /// 最初に試して失敗したバックエンドの名前（フォールバックした・キューに保存した場合）
+    pub failed_backend: Option<&'static str>,
+    /// `send()` を呼んでから配送が終わるまでの時間
+    pub elapsed: Duration,
+    /// バックエンドが割り当てた ID（`NotificationHandle::id()` と同じ）
+    pub id: Option<String>,
+    /// 失敗したバックエンドのエラー出力（PowerShell / osascript の stderr など）の抜粋
+    pub stderr: Option<String>,
+}
+
+impl SendReport {
+    /// 配送に失敗したバックエンドのエラーを記録する（抜粋は先頭の `STDERR_EXCERPT_CHARS` 文字）
+    pub(crate) fn failed(mut self, backend: &'static str, err: &NotificationError) -> Self {
+        let output = match err {
+            NotificationError::SendFailed { reason, .. } => reason.trim().to_string(),
+            err => err.to_string(),
+        };
+        let mut excerpt: String = output.chars().take(STDERR_EXCERPT_CHARS).collect();
+        if excerpt.len() < output.len() {
+            excerpt.push('…');
+        }
+        self.failed_backend = Some(backend);
+        self.stderr = Some(excerpt);
+        self
+    }
+}
+
+/// `via Linux in 12ms` / `via Dialog after Linux failed in 3.2s` の形式で表示
+impl fmt::Display for SendReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.backend {
+            Some(backend) => write!(f, "via {backend}")?,
+            None => write!(f, "not shown")?,
+        }
+        if let Some(failed) = self.failed_backend {
+            write!(f, " after {failed} failed")?;
+        }
+        write!(f, " in {:.1?}", self.elapsed)
+    }
+}
+
+/// 送信済みの通知を指すハンドル
+#[derive(Debug, Clone)]
+pub struct NotificationHandle {
+    /// バックエンドが割り当てた ID（置き換えに対応していない場合は `None`）
+    id: Option<String>,
+    /// 最後に送信した通知の内容
+    notification: Notification,
+    /// 最後の送信の結果
+    report: SendReport,
+}
+
+impl NotificationHandle {
+    /// 新しいハンドルを作成
+    pub(crate) fn new(id: Option<String>, notification: Notification) -> Self {
+        let report = SendReport {
+            id: id.clone(),
+            ..SendReport::default()
+        };
+        Self {
+            id,
+            notification,
+            report,
+        }
+    }
+
+    /// 送信の結果を付ける（`id` は報告の ID に揃える）
+    pub(crate) fn with_report(mut self, report: SendReport) -> Self {
+        self.id = report.id.clone();
+        self.report = report;
+        self
+    }
+
+    /// 最後の送信の結果（表示したバックエンド・かかった時間など）
+    ///
+    /// # 例
+    /// ```no_run
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let handle = NotificationBuilder::new().message("Done").send()?;
+    /// if let Some(backend) = handle.report().backend {
+    ///     println!("shown by {backend} in {:?}", handle.report().elapsed);
+    /// }
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn report(&self) -> &SendReport {
+        &self.report
+    }
+
+    /// バックエンドが割り当てた ID
+    ///
+    /// ID を返さないバックエンド（osascript, コンソール等）や、
+    /// フォールバック先で表示された場合は `None` です。
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// 最後に送信した通知の内容
+    pub fn notification(&self) -> &Notification {
+        &self.notification
+    }
+
+    /// 表示中の通知を新しい内容で置き換える
+    ///
+    /// バックエンドの指定が無い場合は、元の通知と同じバックエンドを使います。
+    /// ID が無い場合は、新しい通知として表示されます。
+    ///
+    /// # 例
+    /// ```no_run
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let mut handle = NotificationBuilder::new().message("0%").send()?;
+    /// for percent in [25, 50, 75, 100] {
+    ///     handle.update(NotificationBuilder::new().message(format!("{percent}%")))?;
+    /// }
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn update(&mut self, builder: NotificationBuilder) -> Result<()> {
+        let mut builder = builder;
+        if builder.backend.is_none() {
+            builder.backend = self.notification.backend_override.clone();
+        }
+        // Windows ではアプリ（AppUserModelID）が異なると置き換えられない
+        if builder.app_name.is_none() {
+            builder.app_name = self.notification.app_name.clone();
+        }
+        if let Some(id) = &self.id {
+            builder = builder.replace_id(id.clone());
+        }
+
+        *self = builder.send()?;
+        Ok(())
+    }
+
+    /// 表示中の通知を閉じる
+    ///
+    /// ID が無い（置き換えに対応していないバックエンドで表示された）場合はエラーになります。
+    pub fn close(self) -> Result<()> {
+        let id = self.id.ok_or_else(|| {
+            NotificationError::Other("notification has no id and cannot be closed".to_string())
+        })?;
+        dismiss(&id, self.notification.backend_override)
+    }
+}
+
+/// 置き換え用の ID を新しく生成する
+///
+/// 現在時刻とプロセス内の連番を組み合わせた 12 桁の 16 進数です
+/// （古い Windows ではトーストの Tag が 16 文字までのため短くしています）。
+pub(crate) fn generate_id() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let millis = clock::current().now_ms();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:08x}{:04x}", millis & 0xFFFF_FFFF, count & 0xFFFF)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::Backend;
+
+    #[test]
+    fn test_generate_id_is_unique_and_short() {
+        let first = generate_id();
+        let second = generate_id();
+        assert_ne!(first, second);
+        assert_eq!(first.len(), 12);
+    }
+
+    #[test]
+    fn test_report_keeps_stderr_excerpt() {
+        let err = NotificationError::SendFailed {
+            backend: "Windows".to_string(),
+            reason: format!("{}\n", "x".repeat(300)),
+        };
+        let report = SendReport {
+            backend: Some("Dialog"),
+            elapsed: Duration::from_millis(1500),
+            ..SendReport::default()
+        }
+        .failed("Windows", &err);
+
+        assert_eq!(report.failed_backend, Some("Windows"));
+        let stderr = report.stderr.as_deref().unwrap();
+        assert_eq!(stderr.chars().count(), STDERR_EXCERPT_CHARS + 1);
+        assert!(stderr.ends_with('…'));
+        assert_eq!(
+            report.to_string(),
+            "via Dialog after Windows failed in 1.5s"
+        );
+    }
+
+    #[test]
+    fn test_close_without_id_fails() {
+        let handle = NotificationHandle::new(None, NotificationBuilder::new().build());
+        assert!(handle.close().is_err());
+    }
+
+    #[test]
+    fn test_update_keeps_backend_and_replaces_id() {
+        let notification = NotificationBuilder::new()
+            .backend(Backend::Stdout)
+            .app_name("my-tool")
+            .build();
+        let mut handle = NotificationHandle::new(Some("abc".to_string()), notification);
+
+        handle
+            .update(NotificationBuilder::new().message("50%"))
+            .unwrap();
+
+        let updated = handle.notification();
+        assert_eq!(updated.message, "50%");
+        assert_eq!(updated.backend_override, Some(Backend::Stdout));
+        assert_eq!(updated.replace_id.as_deref(), Some("abc"));
+        assert_eq!(updated.app_name.as_deref(), Some("my-tool"));
+        assert_eq!(handle.report().backend, Some("Stdout (JSON)"));
+        assert_eq!(handle.report().failed_backend, None);
+    }
+}
@@ -0,0 +1,554 @@
+//! 通知のアイコン
+//!
+//! アイコンは 1 つの文字列ではなく、種類ごとのバリアントで指定します。
+//! 文字列から作る場合（`NotificationBuilder::icon("...")`、CLI の `--icon`）は `Icon::parse()` の
+//! 規則で種類を決めます。
+//!
+//! | バリアント | 文字列の例 | Linux | Windows / WSL | macOS |
+//! |-----------|-----------|-------|---------------|-------|
+//! | `Named` | `dialog-warning` | テーマの名前のまま渡す | テーマ（hicolor）のファイルを探す | 同左 |
+//! | `Path` | `./logo.png` / `file:///opt/logo.png` | パス | パス（WSL は `wslpath -w`） | パス |
+//! | `Url` | `https://example.com/logo.png` | ダウンロードしたファイル | 同左 | 同左 |
+//! | `DataUri` | `data:image/png;base64,...` | デコードしたファイル | 同左 | 同左 |
+//!
+//! ダウンロードとデコードの結果は `<データディレクトリ>/icons/` に URL（データ URI）の
+//! SHA-256 の名前で保存し、次からはそのファイルを使います（ダウンロードには `curl` を使います）。
+//! ダウンロードは `MAX_ICON_BYTES`（1 MiB）と `MAX_DOWNLOAD_TIME`（10 秒）までで、保存したファイルは
+//! `URL_CACHE_TTL`（1 日）を過ぎるとダウンロードし直します（データ URI は中身が変わらないので期限無し）。
+//! 取得できなかった場合は警告をログに出し、アイコン無し（Linux は既定のアイコン）で送ります。
+//! 取得するのは送る時（各バックエンドの `deliver()`）だけで、`render()` やバックエンドの選択では行いません。
+//!
+//! # 例
+//! ```
+//! use rust_toast::Icon;
+//! use std::path::PathBuf;
+//!
+//! assert_eq!(Icon::parse("dialog-warning"), Icon::Named("dialog-warning".to_string()));
+//! assert_eq!(Icon::parse("/opt/app/logo.png"), Icon::Path(PathBuf::from("/opt/app/logo.png")));
+//! assert!(matches!(Icon::parse("https://example.com/logo.png"), Icon::Url(_)));
+//! ```
+//!
+//! # 学習ポイント
+//! - 「名前かパスか」を文字列の形で毎回推測せず、作る時に 1 度だけ判定して型にする
+//! - 取得に時間のかかるもの（ダウンロード）は、内容のハッシュを名前にしてキャッシュする
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{invalid_field, is_path_like};
+use crate::process;
+use crate::store;
+
+/// テーマのアイコンを探すサイズ（大きい順、`scalable` は SVG）
+const THEME_SIZES: [&str; 7] = [
+    "scalable", "256x256", "128x128", "64x64", "48x48", "32x32", "16x16",
+];
+
+/// テーマのアイコンとして探す拡張子
+const THEME_EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+/// ダウンロード・デコードするアイコンの大きさの上限
+const MAX_ICON_BYTES: u64 = 1024 * 1024;
+
+/// ダウンロードを待つ時間の上限（`command_timeout` の方が短ければそちら）
+const MAX_DOWNLOAD_TIME: Duration = Duration::from_secs(10);
+
+/// ダウンロードしたアイコンを使い回す期間
+const URL_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 通知のアイコン（`NotificationBuilder::icon()`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Icon {
+    /// アイコンテーマの名前（`dialog-information` など）
+    Named(String),
+    /// 画像ファイルのパス（`build()` で絶対パスになる）
+    Path(PathBuf),
+    /// 画像の URL（`http` / `https`、送る時にダウンロードする）
+    Url(Url),
+    /// データ URI（`data:image/png;base64,...`、送る時にファイルに書き出す）
+    DataUri(String),
+}
+
+impl Icon {
+    /// 文字列からアイコンを作る
+    ///
+    /// 1. `data:` で始まればデータ URI
+    /// 2. `http://` / `https://` の URL ならダウンロードする URL
+    /// 3. `file://` の URL、またはパス区切り（`/` `\`）を含めばファイルパス
+    /// 4. それ以外はアイコンテーマの名前
+    pub fn parse(value: &str) -> Self {
+        if value.starts_with("data:") {
+            return Self::DataUri(value.to_string());
+        }
+        if let Ok(url) = Url::parse(value) {
+            match url.scheme() {
+                "http" | "https" => return Self::Url(url),
+                "file" => {
+                    if let Ok(path) = url.to_file_path() {
+                        return Self::Path(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if is_path_like(value) {
+            Self::Path(PathBuf::from(value))
+        } else {
+            Self::Named(value.to_string())
+        }
+    }
+
+    /// アイコンテーマの名前の場合はその名前
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Named(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// 手元にある表示用のファイル（何も起動しない）
+    ///
+    /// パスはそのまま返し、テーマの名前は hicolor テーマと `pixmaps` から探します。
+    /// URL とデータ URI は `resolve()` でファイルにするまで `None` です。
+    pub(crate) fn local_file(&self) -> Option<PathBuf> {
+        match self {
+            Self::Named(name) => find_in_theme(name, &icon_dirs()),
+            Self::Path(path) => Some(path.clone()),
+            Self::Url(_) | Self::DataUri(_) => None,
+        }
+    }
+
+    /// 表示に使うローカルのファイル
+    ///
+    /// URL はダウンロードし、データ URI は書き出して、キャッシュしたファイルを返します。
+    /// それ以外は `local_file()` と同じです。`timeout` はダウンロードを待つ時間です
+    /// （`MAX_DOWNLOAD_TIME` より長い場合は `MAX_DOWNLOAD_TIME`）。
+    pub(crate) fn resolve(&self, timeout: Duration) -> Result<Option<PathBuf>> {
+        match self {
+            Self::Named(_) | Self::Path(_) => Ok(self.local_file()),
+            Self::Url(url) => {
+                let path = cache_path(&cache_dir(), url.as_str(), extension_of(url.path()));
+                let timeout = timeout.min(MAX_DOWNLOAD_TIME);
+                cached(&path, Some(URL_CACHE_TTL), |tmp| {
+                    download(url, tmp, timeout)
+                })
+                .map(Some)
+            }
+            Self::DataUri(uri) => {
+                let (extension, data) = decode_data_uri(uri)?;
+                if data.len() as u64 > MAX_ICON_BYTES {
+                    return Err(too_large(uri.get(..32).unwrap_or(uri)));
+                }
+                let path = cache_path(&cache_dir(), uri, extension);
+                cached(&path, None, |tmp| {
+                    std::fs::write(tmp, &data).map_err(|e| storage_error(tmp, e))
+                })
+                .map(Some)
+            }
+        }
+    }
+}
+
+/// 文字列の形（`parse()` で元に戻せる）
+impl fmt::Display for Icon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, "{name}"),
+            Self::Path(path) => write!(f, "{}", path.display()),
+            Self::Url(url) => write!(f, "{url}"),
+            Self::DataUri(uri) => write!(f, "{uri}"),
+        }
+    }
+}
+
+impl From<&str> for Icon {
+    fn from(value: &str) -> Self {
+        Self::parse(value)
+    }
+}
+
+impl From<String> for Icon {
+    fn from(value: String) -> Self {
+        Self::parse(&value)
+    }
+}
+
+impl From<PathBuf> for Icon {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl From<&Path> for Icon {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl From<Url> for Icon {
+    fn from(url: Url) -> Self {
+        Self::Url(url)
+    }
+}
+
+/// 文字列（`Display` の形）として保存する（以前の `icon: String` と互換）
+#[cfg(feature = "serde")]
+impl serde::Serialize for Icon {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Icon {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// データ URI をデコードして、拡張子（MIME タイプから）と中身を返す
+///
+/// `;base64` が付いていれば Base64、無ければパーセントエンコーディングとして読みます。
+pub(crate) fn decode_data_uri(uri: &str) -> Result<(&'static str, Vec<u8>)> {
+    let invalid = |reason: &str| invalid_field("icon", format!("invalid data URI: {reason}"));
+    let rest = uri
+        .strip_prefix("data:")
+        .ok_or_else(|| invalid("missing 'data:'"))?;
+    let (header, payload) = rest.split_once(',').ok_or_else(|| invalid("missing ','"))?;
+    let mut params = header.split(';');
+    let mime = params.next().unwrap_or_default().to_ascii_lowercase();
+    let base64 = params.any(|param| param.eq_ignore_ascii_case("base64"));
+
+    let extension = match mime.as_str() {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "image/bmp" => "bmp",
+        "image/webp" => "webp",
+        other => return Err(invalid(&format!("'{other}' is not an image type"))),
+    };
+    let data = if base64 {
+        let compact: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD
+            .decode(compact)
+            .map_err(|e| invalid(&e.to_string()))?
+    } else {
+        percent_decode(payload)
+    };
+    Ok((extension, data))
+}
+
+/// `%XX` をバイトに戻す（不正な `%` はそのまま）
+fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// ダウンロードしたアイコンを保存するディレクトリ
+fn cache_dir() -> PathBuf {
+    store::data_dir().join("icons")
+}
+
+/// `key`（URL やデータ URI）に対応するキャッシュのファイル
+fn cache_path(dir: &Path, key: &str, extension: &str) -> PathBuf {
+    let digest = Sha256::digest(key.as_bytes());
+    let name: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    dir.join(format!("{name}.{extension}"))
+}
+
+/// URL のパスの拡張子（画像らしくなければ `png`）
+fn extension_of(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("jpg" | "jpeg") => "jpg",
+        Some("gif") => "gif",
+        Some("svg") => "svg",
+        Some("ico") => "ico",
+        Some("bmp") => "bmp",
+        Some("webp") => "webp",
+        _ => "png",
+    }
+}
+
+/// `path` が無いか `ttl` より古ければ `fill` で一時ファイルに書いてから置く
+///
+/// 途中で失敗しても壊れたファイルを残しません。`ttl` が `None` なら期限はありません。
+fn cached(
+    path: &Path,
+    ttl: Option<Duration>,
+    fill: impl FnOnce(&Path) -> Result<()>,
+) -> Result<PathBuf> {
+    if is_fresh(path, ttl) {
+        return Ok(path.to_path_buf());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| storage_error(dir, e))?;
+    }
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    let result =
+        fill(&tmp).and_then(|()| std::fs::rename(&tmp, path).map_err(|e| storage_error(path, e)));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result.map(|()| path.to_path_buf())
+}
+
+/// キャッシュのファイルがあり、更新から `ttl` を過ぎていないか
+fn is_fresh(path: &Path, ttl: Option<Duration>) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+    let Some(ttl) = ttl else {
+        return true;
+    };
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < ttl)
+}
+
+/// `curl` で `url` を `dest` にダウンロードする（`MAX_ICON_BYTES` を超えたら失敗）
+fn download(url: &Url, dest: &Path, timeout: Duration) -> Result<()> {
+    tracing::debug!("downloading icon {url}");
+    let mut command = process::command("curl")?;
+    command
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .arg("--max-time")
+        .arg(timeout.as_secs().max(1).to_string())
+        .arg("--max-filesize")
+        .arg(MAX_ICON_BYTES.to_string())
+        .arg("--output")
+        .arg(dest)
+        .arg(url.as_str())
+        .stdin(Stdio::null());
+    let output = process::output_within(command, timeout)?;
+    if !output.status.success() {
+        return Err(NotificationError::Other(format!(
+            "failed to download icon {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    // Content-Length の無い応答では curl が上限を確かめられないため、書いたファイルでも確かめる
+    let size = std::fs::metadata(dest)
+        .map_err(|e| storage_error(dest, e))?
+        .len();
+    if size > MAX_ICON_BYTES {
+        return Err(too_large(url.as_str()));
+    }
+    Ok(())
+}
+
+fn too_large(source: &str) -> NotificationError {
+    invalid_field(
+        "icon",
+        format!("{source} is larger than {MAX_ICON_BYTES} bytes"),
+    )
+}
+
+fn storage_error(path: &Path, err: std::io::Error) -> NotificationError {
+    NotificationError::Storage {
+        path: path.to_path_buf(),
+        reason: err.to_string(),
+    }
+}
+
+/// アイコンテーマを探すディレクトリ（`$XDG_DATA_HOME` と `$XDG_DATA_DIRS` の `icons` / `pixmaps`）
+fn icon_dirs() -> Vec<PathBuf> {
+    let env = |key: &str| std::env::var(key).ok().filter(|value| !value.is_empty());
+    let data_home = env("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| Path::new(&home).join(".local").join("share")));
+    let data_dirs =
+        env("XDG_DATA_DIRS").unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    data_home
+        .into_iter()
+        .chain(std::env::split_paths(&data_dirs))
+        .collect()
+}
+
+/// `data_dirs` の hicolor テーマ（大きいサイズから）と `pixmaps` から名前のアイコンを探す
+fn find_in_theme(name: &str, data_dirs: &[PathBuf]) -> Option<PathBuf> {
+    if name.is_empty() {
+        return None;
+    }
+    let file_names = || {
+        THEME_EXTENSIONS
+            .iter()
+            .map(move |ext| format!("{name}.{ext}"))
+    };
+    for data_dir in data_dirs {
+        let theme = data_dir.join("icons").join("hicolor");
+        for size in THEME_SIZES {
+            // apps / status / devices などの分類ごとのディレクトリ
+            let Ok(contexts) = std::fs::read_dir(theme.join(size)) else {
+                continue;
+            };
+            let mut contexts: Vec<PathBuf> = contexts.flatten().map(|e| e.path()).collect();
+            contexts.sort();
+            for context in contexts {
+                if let Some(found) = file_names().map(|f| context.join(f)).find(|p| p.is_file()) {
+                    return Some(found);
+                }
+            }
+        }
+        let pixmaps = data_dir.join("pixmaps");
+        if let Some(found) = file_names().map(|f| pixmaps.join(f)).find(|p| p.is_file()) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_icon_kinds() {
+        assert_eq!(
+            Icon::parse("emblem-ok"),
+            Icon::Named("emblem-ok".to_string())
+        );
+        assert_eq!(
+            Icon::parse("./logo.png"),
+            Icon::Path(PathBuf::from("./logo.png"))
+        );
+        assert_eq!(
+            Icon::parse("file:///opt/app/logo.png"),
+            Icon::Path(PathBuf::from("/opt/app/logo.png"))
+        );
+        let url = Icon::parse("https://example.com/logo.png?v=2");
+        assert!(matches!(url, Icon::Url(_)));
+        assert_eq!(url.to_string(), "https://example.com/logo.png?v=2");
+        assert!(matches!(
+            Icon::parse("data:image/png;base64,iVBO"),
+            Icon::DataUri(_)
+        ));
+    }
+
+    #[test]
+    fn test_decode_data_uri() {
+        let (extension, data) = decode_data_uri("data:image/png;base64,aGVs bG8=").unwrap();
+        assert_eq!(extension, "png");
+        assert_eq!(data, b"hello");
+
+        let (extension, data) = decode_data_uri("data:image/svg+xml,%3Csvg%2F%3E").unwrap();
+        assert_eq!(extension, "svg");
+        assert_eq!(data, b"<svg/>");
+
+        assert!(decode_data_uri("data:text/plain;base64,aGVsbG8=").is_err());
+        assert!(decode_data_uri("data:image/png;base64,@@@").is_err());
+    }
+
+    #[test]
+    fn test_cached_writes_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = cache_path(dir.path(), "https://example.com/a.svg", "svg");
+        let mut calls = 0;
+        for _ in 0..2 {
+            cached(&path, None, |tmp| {
+                calls += 1;
+                std::fs::write(tmp, b"<svg/>").map_err(|e| storage_error(tmp, e))
+            })
+            .unwrap();
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(std::fs::read(&path).unwrap(), b"<svg/>");
+
+        let failed = cache_path(dir.path(), "https://example.com/b.png", "png");
+        assert!(cached(&failed, None, |_| Err(NotificationError::Other(
+            "offline".into()
+        )))
+        .is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_cached_refetches_after_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = cache_path(dir.path(), "https://example.com/a.png", "png");
+        let write = |body: &'static [u8]| {
+            move |tmp: &Path| std::fs::write(tmp, body).map_err(|e| storage_error(tmp, e))
+        };
+        cached(&path, Some(URL_CACHE_TTL), write(b"old")).unwrap();
+        cached(&path, Some(URL_CACHE_TTL), write(b"new")).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+
+        // 期限を過ぎたファイルは取得し直す
+        cached(&path, Some(Duration::ZERO), write(b"new")).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_data_uri_size_limit() {
+        let payload = "A".repeat(MAX_ICON_BYTES as usize * 2);
+        let icon = Icon::parse(&format!("data:image/png;base64,{payload}"));
+        assert!(matches!(
+            icon.resolve(Duration::from_secs(1)),
+            Err(NotificationError::InvalidInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_find_in_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = dir.path().join("icons/hicolor/48x48/apps");
+        let large = dir.path().join("icons/hicolor/256x256/status");
+        std::fs::create_dir_all(&small).unwrap();
+        std::fs::create_dir_all(&large).unwrap();
+        std::fs::write(small.join("tool.png"), b"").unwrap();
+        std::fs::write(large.join("tool.png"), b"").unwrap();
+        std::fs::create_dir_all(dir.path().join("pixmaps")).unwrap();
+        std::fs::write(dir.path().join("pixmaps/legacy.xpm"), b"").unwrap();
+
+        let dirs = [PathBuf::from("/nonexistent"), dir.path().to_path_buf()];
+        assert_eq!(find_in_theme("tool", &dirs), Some(large.join("tool.png")));
+        assert_eq!(
+            find_in_theme("legacy", &dirs),
+            Some(dir.path().join("pixmaps/legacy.xpm"))
+        );
+        assert_eq!(find_in_theme("missing", &dirs), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! バックエンドの応答時間の記録と、遅い場合の切り替え
+//!
+//! `Config::latency_budget`（例: 300 ms）を設定すると、自動選択されたバックエンドの
+//! 最近の送信時間の平均が予算を超えている間は、重要でない通知を
+//! 速いコンソール表示に切り替えます。`REPROBE_INTERVAL` ごとに 1 回だけ
+//! 元のバックエンドで送り直して測り直し、速くなっていれば元に戻します。
+//!
+//! 記録はプロセス内だけです（CLI のように 1 回で終わるプロセスでは切り替わりません）。
+//!
+//! # 学習ポイント
+//! - `VecDeque` による直近 N 件の記録（移動平均）
+//! - 依存性の注入（`Clock`）で時間に依存する判定をテスト可能にする
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{Clock, CurrentClock};
+
+/// 平均を取る直近の送信の数
+const WINDOW: usize = 5;
+
+/// 切り替え中に、元のバックエンドを測り直す間隔
+pub const REPROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 1 つのバックエンドの記録
+#[derive(Debug, Default)]
+struct BackendLatency {
+    /// 直近の送信時間（古い順、最大 `WINDOW` 件）
+    samples: VecDeque<Duration>,
+    /// 切り替えを始めた時刻、または最後に測り直した時刻
+    downgraded_at: Option<SystemTime>,
+}
+
+impl BackendLatency {
+    fn average(&self) -> Option<Duration> {
+        let count = u32::try_from(self.samples.len()).ok().filter(|&n| n > 0)?;
+        Some(self.samples.iter().sum::<Duration>() / count)
+    }
+}
+
+/// バックエンド名ごとの送信時間を記録する
+#[derive(Debug)]
+pub struct LatencyTracker {
+    clock: Arc<dyn Clock>,
+    backends: Mutex<HashMap<String, BackendLatency>>,
+}
+
+impl LatencyTracker {
+    /// 時計を指定して作成
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            backends: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 送信にかかった時間を記録する
+    pub fn record(&self, backend: &str, elapsed: Duration) {
+        let mut backends = self.backends.lock().unwrap_or_else(|e| e.into_inner());
+        let latency = backends.entry(backend.to_string()).or_default();
+        if latency.samples.len() == WINDOW {
+            latency.samples.pop_front();
+        }
+        latency.samples.push_back(elapsed);
+    }
+
+    /// 直近の送信時間の平均（記録が無ければ `None`）
+    pub fn average(&self, backend: &str) -> Option<Duration> {
+        let backends = self.backends.lock().unwrap_or_else(|e| e.into_inner());
+        backends.get(backend).and_then(BackendLatency::average)
+    }
+
+    /// 次の送信でバックエンドを避けるべきかどうか
+    ///
+    /// 平均が `budget` を超えていれば `true` です。ただし切り替えてから
+    /// `REPROBE_INTERVAL` 経つごとに 1 回は `false` を返し（測り直し）、
+    /// その時は古い記録を捨てて、次の送信時間だけで判定し直します。
+    pub fn should_avoid(&self, backend: &str, budget: Duration) -> bool {
+        let now = self.clock.now();
+        let mut backends = self.backends.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(latency) = backends.get_mut(backend) else {
+            return false;
+        };
+        if latency.average().is_none_or(|average| average <= budget) {
+            latency.downgraded_at = None;
+            return false;
+        }
+        match latency.downgraded_at {
+            Some(since) if now.duration_since(since).unwrap_or_default() >= REPROBE_INTERVAL => {
+                latency.downgraded_at = Some(now);
+                latency.samples.clear();
+                false
+            }
+            Some(_) => true,
+            None => {
+                latency.downgraded_at = Some(now);
+                true
+            }
+        }
+    }
+}
+
+/// プロセス全体で共有する `LatencyTracker`（時計は呼ばれるたびに `clock::current()` を読む）
+pub(crate) fn global_tracker() -> &'static LatencyTracker {
+    static TRACKER: OnceLock<LatencyTracker> = OnceLock::new();
+    TRACKER.get_or_init(|| LatencyTracker::new(Arc::new(CurrentClock)))
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    const BUDGET: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn test_average_of_recent_sends() {
+        let tracker = LatencyTracker::new(Arc::new(ManualClock::new(UNIX_EPOCH)));
+        assert_eq!(tracker.average("Windows"), None);
+        for ms in [1000, 100, 200, 300, 400, 500] {
+            tracker.record("Windows", Duration::from_millis(ms));
+        }
+        // 最初の 1000 ms は WINDOW から外れる
+        assert_eq!(tracker.average("Windows"), Some(Duration::from_millis(300)));
+    }
+
+    #[test]
+    fn test_downgrade_and_reprobe() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+        let tracker = LatencyTracker::new(clock.clone());
+        assert!(!tracker.should_avoid("Windows", BUDGET));
+
+        tracker.record("Windows", Duration::from_millis(900));
+        assert!(tracker.should_avoid("Windows", BUDGET));
+        clock.advance(Duration::from_secs(30));
+        assert!(tracker.should_avoid("Windows", BUDGET));
+
+        // 間隔が経ったら 1 回だけ測り直す
+        clock.advance(Duration::from_secs(30));
+        assert!(!tracker.should_avoid("Windows", BUDGET));
+        tracker.record("Windows", Duration::from_millis(100));
+        assert!(!tracker.should_avoid("Windows", BUDGET));
+    }
+}
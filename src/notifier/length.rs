@@ -0,0 +1,217 @@
+//! タイトルと本文の長さの上限
+//!
+//! バックエンドには、実用上表示できる長さの目安があります
+//! （`Capabilities::max_title_len` / `max_message_len`、文字数）。
+//! `Notification::overflow` を指定すると、送る前に上限と比べて次のように扱います。
+//!
+//! | `Overflow` | 上限を超えた場合 |
+//! |-----------|-----------------|
+//! | `Truncate` | 上限に収まるように切り詰め、末尾を `…` にする |
+//! | `Split` | 本文を上限ごとに分けて複数の通知にする（タイトルに `(1/3)` を付ける） |
+//! | `Error` | 送らずに `NotificationError::InvalidInput` を返す |
+//!
+//! | バックエンド | タイトル | 本文 | 根拠 |
+//! |-------------|---------|------|------|
+//! | Windows / WSL | 80 | 160 | トーストはタイトル 2 行・本文 4 行で切れる |
+//! | macOS | 60 | 200 | バナーはタイトル 1 行・本文 2 行（通知センターで全文） |
+//! | Linux | 100 | 1000 | 通知デーモンの多くが長い本文を省略する |
+//!
+//! 指定しなければ（デフォルト）、これまでどおりそのまま送り、切り方は OS に任せます。
+//! 切り詰め・分割した通知は、Markdown の本文（`markdown`）を使わずプレーンテキストで送ります。
+//!
+//! # 学習ポイント
+//! - バイト数ではなく文字数（`chars()`）で数える（マルチバイト文字の途中で切らない）
+//! - 分割は単語の途中を避け、空白で切れなければ文字数で切る
+
+use crate::error::Result;
+use crate::notifier::{invalid_field, Capabilities, Notification};
+
+/// 切り詰めた時に末尾に付ける記号
+const ELLIPSIS: char = '…';
+
+/// タイトルや本文がバックエンドの上限を超えた時の扱い（`NotificationBuilder::overflow()`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Overflow {
+    /// 上限に収まるように切り詰め、末尾を `…` にする
+    Truncate,
+    /// 本文を分けて複数の通知にする（タイトルは切り詰める）
+    Split,
+    /// 送らずにエラーにする
+    Error,
+}
+
+/// 通知を `capabilities` の上限に合わせる（送る順に並べた通知を返す）
+///
+/// 上限を超えていなければ、元の通知 1 つだけを返します。
+pub(crate) fn fit(
+    notification: &Notification,
+    capabilities: &Capabilities,
+    overflow: Overflow,
+) -> Result<Vec<Notification>> {
+    let title_len = notification.title.chars().count();
+    let message_len = notification.message.chars().count();
+    let title_over = capabilities.max_title_len.filter(|&max| title_len > max);
+    let message_over = capabilities
+        .max_message_len
+        .filter(|&max| message_len > max);
+    if title_over.is_none() && message_over.is_none() {
+        return Ok(vec![notification.clone()]);
+    }
+
+    match overflow {
+        Overflow::Error => {
+            let (field, len, max) = match (title_over, message_over) {
+                (Some(max), _) => ("title", title_len, max),
+                (None, Some(max)) => ("message", message_len, max),
+                (None, None) => unreachable!("checked above"),
+            };
+            Err(invalid_field(
+                field,
+                format!("{len} characters, over the backend limit of {max}"),
+            ))
+        }
+        Overflow::Truncate => {
+            let mut fitted = notification.clone();
+            if let Some(max) = title_over {
+                fitted.title = truncate(&notification.title, max);
+            }
+            if let Some(max) = message_over {
+                fitted.message = truncate(&notification.message, max);
+            }
+            fitted.markdown = None;
+            Ok(vec![fitted])
+        }
+        Overflow::Split => {
+            let chunks = match message_over {
+                Some(max) => split(&notification.message, max),
+                None => vec![notification.message.clone()],
+            };
+            let count = chunks.len();
+            let parts = chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let mut part = notification.clone();
+                    let title = if count > 1 {
+                        format!("{} ({}/{count})", notification.title, i + 1)
+                    } else {
+                        notification.title.clone()
+                    };
+                    part.title = match capabilities.max_title_len {
+                        Some(max) => truncate(&title, max),
+                        None => title,
+                    };
+                    part.message = chunk;
+                    part.markdown = None;
+                    // 置き換えると前の部分が消えてしまうので、置き換えるのは最初の部分だけ
+                    if i > 0 {
+                        part.replace_id = None;
+                    }
+                    part
+                })
+                .collect();
+            Ok(parts)
+        }
+    }
+}
+
+/// `max` 文字に収まるように切り詰め、末尾を `…` にする（収まっていればそのまま）
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max.saturating_sub(1)).collect();
+    format!("{}{ELLIPSIS}", kept.trim_end())
+}
+
+/// `max` 文字ごとに分ける（できるだけ空白で区切り、区切りの空白は捨てる）
+fn split(text: &str, max: usize) -> Vec<String> {
+    let max = max.max(1);
+    let mut chunks = Vec::new();
+    let mut rest: Vec<char> = text.chars().collect();
+    while rest.len() > max {
+        let cut = rest[..=max]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .filter(|&i| i > 0)
+            .unwrap_or(max);
+        let chunk: String = rest[..cut].iter().collect();
+        chunks.push(chunk.trim_end().to_string());
+        rest = rest[cut..]
+            .iter()
+            .copied()
+            .skip_while(|c| c.is_whitespace())
+            .collect();
+    }
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest.into_iter().collect());
+    }
+    chunks
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotificationBuilder;
+
+    fn limits() -> Capabilities {
+        Capabilities {
+            max_title_len: Some(10),
+            max_message_len: Some(12),
+            ..Capabilities::default()
+        }
+    }
+
+    #[test]
+    fn test_fit_truncates_with_ellipsis() {
+        let notification = NotificationBuilder::new()
+            .title("Nightly build finished")
+            .message("ビルドが正常に終了しました。成果物を確認してください")
+            .build();
+        let fitted = fit(&notification, &limits(), Overflow::Truncate).unwrap();
+        assert_eq!(fitted.len(), 1);
+        assert_eq!(fitted[0].title, "Nightly b…");
+        assert_eq!(fitted[0].message, "ビルドが正常に終了しま…");
+
+        let short = NotificationBuilder::new().title("ok").message("ok").build();
+        let fitted = fit(&short, &limits(), Overflow::Error).unwrap();
+        assert_eq!(fitted[0].message, "ok");
+    }
+
+    #[test]
+    fn test_fit_splits_message_on_whitespace() {
+        let notification = NotificationBuilder::new()
+            .title("Log")
+            .message("one two three four five")
+            .replace_id("7")
+            .build();
+        let parts = fit(&notification, &limits(), Overflow::Split).unwrap();
+        let messages: Vec<&str> = parts.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(messages, ["one two", "three four", "five"]);
+        assert_eq!(parts[0].title, "Log (1/3)");
+        assert_eq!(parts[0].replace_id.as_deref(), Some("7"));
+        assert_eq!(parts[1].replace_id, None);
+    }
+
+    #[test]
+    fn test_fit_error_names_the_field() {
+        let notification = NotificationBuilder::new()
+            .title("Disk")
+            .message("this message is too long")
+            .build();
+        let err = fit(&notification, &limits(), Overflow::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid message: 24 characters, over the backend limit of 12"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! バックエンドごとの同時実行数制限
+//!
+//! 複数スレッドから一斉に通知を送ると、PowerShell などの外部プロセスが
+//! 大量に起動してしまいます（WSL では特に重い）。セマフォで同時実行数を
+//! 制限し、プロセスの嵐を防ぎます。
+//!
+//! # 学習ポイント
+//! - `Mutex` と `Condvar` によるセマフォの実装
+//! - `Drop` トレイトによる RAII（スコープを抜けると自動的に解放）
+//! - `OnceLock` によるグローバル状態の遅延初期化
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+
+/// PowerShell の同時起動数のデフォルト上限
+const DEFAULT_POWERSHELL_LIMIT: usize = 4;
+
+/// カウンティングセマフォ
+///
+/// 同時に `acquire()` できる数を `permits` 個に制限します。
+#[derive(Debug)]
+pub struct Semaphore {
+    /// 残りの許可数
+    available: Mutex<usize>,
+    /// 許可が返却されたことを待機中のスレッドに知らせる
+    released: Condvar,
+}
+
+/// セマフォの許可証
+///
+/// この値が drop されると、許可がセマフォに返却されます。
+#[derive(Debug)]
+pub struct Permit {
+    semaphore: Arc<Semaphore>,
+}
+
+impl Semaphore {
+    /// `permits` 個の許可を持つセマフォを作成
+    pub fn new(permits: usize) -> Self {
+        Self {
+            available: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    /// 許可を取得する（空きが出るまでブロック）
+    pub fn acquire(self: &Arc<Self>) -> Permit {
+        let mut available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        // spurious wakeup に備えて、条件を満たすまでループで待つ
+        while *available == 0 {
+            available = self
+                .released
+                .wait(available)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *available -= 1;
+
+        Permit {
+            semaphore: Arc::clone(self),
+        }
+    }
+
+    /// 許可を取得する（空きが無ければ即座に `None`）
+    pub fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        if *available == 0 {
+            return None;
+        }
+        *available -= 1;
+
+        Some(Permit {
+            semaphore: Arc::clone(self),
+        })
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut available = self
+            .semaphore
+            .available
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *available += 1;
+        self.semaphore.released.notify_one();
+    }
+}
+
+// ============================================================
+// バックエンドごとの制限テーブル
+// ============================================================
+
+/// バックエンド名 → セマフォ の対応表
+///
+/// `OnceLock` で初回アクセス時に初期化します。
+fn limits() -> &'static Mutex<HashMap<String, Arc<Semaphore>>> {
+    static LIMITS: OnceLock<Mutex<HashMap<String, Arc<Semaphore>>>> = OnceLock::new();
+    LIMITS.get_or_init(|| {
+        let mut map = HashMap::new();
+        map.insert(
+            "Windows (PowerShell)".to_string(),
+            Arc::new(Semaphore::new(DEFAULT_POWERSHELL_LIMIT)),
+        );
+        Mutex::new(map)
+    })
+}
+
+/// バックエンドの同時実行数の上限を設定する
+///
+/// `backend` には `Notifier::backend_name()` の値を指定します。
+/// `None` を指定すると制限を解除します。
+///
+/// # 例
+/// ```
+/// use rust_toast::notifier::{set_concurrency_limit, Notifier, WindowsNotifier};
+///
+/// // PowerShell の同時起動を 2 プロセスまでに制限
+/// set_concurrency_limit(WindowsNotifier.backend_name(), Some(2));
+/// ```
+pub fn set_concurrency_limit(backend: &str, max: Option<usize>) {
+    let mut limits = limits().lock().unwrap_or_else(|e| e.into_inner());
+    match max {
+        // 0 を許すと永遠に送信できなくなるため、最低 1 にする
+        Some(max) => {
+            limits.insert(backend.to_string(), Arc::new(Semaphore::new(max.max(1))));
+        }
+        None => {
+            limits.remove(backend);
+        }
+    }
+}
+
+/// バックエンドの許可を取得する
+///
+/// 制限が設定されていないバックエンドでは `None` を返し、待機しません。
+pub(crate) fn acquire(backend: &str) -> Option<Permit> {
+    // テーブルのロックは待機前に解放する（他のバックエンドを巻き込まないため）
+    let semaphore = limits()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(backend)
+        .cloned()?;
+    Some(semaphore.acquire())
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_semaphore_limits_permits() {
+        let semaphore = Arc::new(Semaphore::new(2));
+        let first = semaphore.try_acquire();
+        let second = semaphore.try_acquire();
+
+        assert!(first.is_some());
+        assert!(second.is_some());
+        assert!(semaphore.try_acquire().is_none());
+
+        // drop すると許可が返却される
+        drop(first);
+        assert!(semaphore.try_acquire().is_some());
+    }
+
+    #[test]
+    fn test_semaphore_blocks_until_released() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore.acquire();
+
+        let cloned = Arc::clone(&semaphore);
+        let handle = thread::spawn(move || {
+            let _permit = cloned.acquire();
+        });
+
+        drop(permit);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_concurrency_limit() {
+        set_concurrency_limit("test-backend", Some(1));
+        let permit = acquire("test-backend");
+        assert!(permit.is_some());
+
+        set_concurrency_limit("test-backend", None);
+        assert!(acquire("test-backend").is_none());
+    }
+}
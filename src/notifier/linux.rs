@@ -2,19 +2,72 @@
 //!
 //! `notify-rust` クレートを使用して D-Bus 経由で通知を送信します。
 //!
+//! 通知の組み立ては notify-rust、D-Bus の呼び出し（`Notify` など）は zbus で直接行います。
+//!
+//! D-Bus への接続に失敗した場合は、`systemctl --user show-environment` から
+//! セッションバスのアドレス（`DBUS_SESSION_BUS_ADDRESS`）を読み直し、
+//! 変わっていればそのアドレスに接続して 1 度だけ送り直します。ログインし直した後や
+//! `systemctl --user import-environment` の後でも、長く動くプロセスが
+//! 古いバスのアドレスを使い続けないようにするためです。
+//! プロセスの環境変数は書き換えません（他のスレッドが読んでいる間の `set_var` は安全ではないため）。
+//!
+//! 実装は Linux 向けで `linux-dbus` フィーチャー（デフォルトで有効）がある時だけ組み込まれ、
+//! それ以外では notify-rust / zbus に依存しないスタブになります。
+//!
 //! # 学習ポイント
-//! - 条件付きコンパイル `#[cfg(target_os = "linux")]`
+//! - 条件付きコンパイル `#[cfg(all(target_os = "linux", feature = "linux-dbus"))]`（ターゲットとフィーチャーの組み合わせ）
 //! - 外部クレートのラッピング
 //! - 同一関数の異なるプラットフォーム向け実装
+//! - 失敗時に環境を読み直して 1 度だけ再試行する
+//! - アドレスを指定した D-Bus の接続（`zbus::blocking::connection::Builder::address()`）
 
-use crate::error::Result;
-#[cfg(not(target_os = "linux"))]
-use crate::error::NotificationError;
-use crate::notifier::{Notification, Notifier, UrgencyLevel};
+use crate::error::{NotificationError, Result};
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+use crate::notifier::{
+    markdown, quirks, Capabilities, DBusNotify, Icon, Interaction, Invocation, ServerQuirks,
+    UrgencyLevel,
+};
+#[cfg(all(feature = "async", target_os = "linux", feature = "linux-dbus"))]
+use crate::notifier::{nonblocking, AsyncNotifier, BoxFuture};
+use crate::notifier::{Notification, Notifier};
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+use std::borrow::Cow;
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+use std::collections::{BTreeMap, HashMap};
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+use std::sync::Mutex;
 
 // notify-rust は Linux でのみ使用
-#[cfg(target_os = "linux")]
-use notify_rust::{Notification as RustNotification, Timeout, Urgency};
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+use notify_rust::{Hint, Notification as RustNotification, Timeout, Urgency};
+
+/// 通知本体のクリックを表す D-Bus のアクション ID（仕様で予約されている）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const DEFAULT_ACTION: &str = "default";
+
+/// アイコンの指定が無い通知に使うアイコンテーマの名前
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const DEFAULT_ICON: &str = "dialog-information";
+
+/// 同じ値の通知を 1 つにまとめるヒント（dunst 由来、他の通知デーモンも一部対応）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const DUNST_STACK_TAG: &str = "x-dunst-stack-tag";
+
+/// 通知デーモンのバス名（インターフェース名も同じ）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const NOTIFICATIONS_BUS: &str = "org.freedesktop.Notifications";
+
+/// 通知デーモンのオブジェクトパス
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+/// セッションバスのアドレスの環境変数
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const BUS_ADDRESS_VAR: &str = "DBUS_SESSION_BUS_ADDRESS";
+
+/// `NotificationClosed` の理由のうち「時間切れ」（1）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+const CLOSED_EXPIRED: u32 = 1;
 
 /// Linux 通知バックエンド
 ///
@@ -26,35 +79,118 @@ pub struct LinuxNotifier;
 // Linux 向け実装
 // ============================================================
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
 impl Notifier for LinuxNotifier {
     fn send(&self, notification: &Notification) -> Result<()> {
-        // タイムアウトの変換
-        let timeout = if notification.timeout == 0 {
-            Timeout::Never
-        } else {
-            Timeout::Milliseconds(notification.timeout)
-        };
+        self.deliver(notification).map(|_| ())
+    }
 
-        // 緊急度の変換
-        let urgency = match notification.urgency {
-            UrgencyLevel::Low => Urgency::Low,
-            UrgencyLevel::Normal => Urgency::Normal,
-            UrgencyLevel::Critical => Urgency::Critical,
-        };
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        let notification = &for_delivery(notification);
+        let id = with_session_retry(|| {
+            let built = build_notification(notification, &current_server());
+            call_notify(&session_connection()?, notification, &built)
+        })?;
+
+        // D-Bus の通知 ID（次回 replaces_id に指定すると置き換わる）
+        Ok(Some(id.to_string()))
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        // 通知デーモンに問い合わせず、仕様どおりのデーモンに送る場合の引数にする
+        let built = build_notification(notification, &Server::default());
+        Ok(Invocation::DBus(notify_arguments(notification, &built)))
+    }
+
+    fn close(&self, id: &str) -> Result<()> {
+        let id: u32 = id.parse().map_err(|_| NotificationError::SendFailed {
+            backend: "Linux".to_string(),
+            reason: format!("invalid notification id: {id}"),
+        })?;
+
+        // 別プロセスからも閉じられるよう、ID だけで CloseNotification を呼ぶ
+        with_session_retry(|| {
+            session_connection()?
+                .call_method(
+                    Some(NOTIFICATIONS_BUS),
+                    NOTIFICATIONS_PATH,
+                    Some(NOTIFICATIONS_BUS),
+                    "CloseNotification",
+                    &(id,),
+                )
+                .map_err(dbus_error)?;
+            Ok(())
+        })
+    }
 
-        // notify-rust の API を使用して通知を送信
-        RustNotification::new()
-            .summary(&notification.title)
-            .body(&notification.message)
-            .icon(&notification.icon)
-            .timeout(timeout)
-            .urgency(urgency)
-            .show()?; // エラーは From トレイトで自動変換
+    fn send_and_wait(&self, notification: &Notification) -> Result<Interaction> {
+        let notification = &for_delivery(notification);
+        let (signals, id) = with_session_retry(|| {
+            let mut rust_notification = build_notification(notification, &current_server());
+            // 本体のクリックを ActionInvoked("default") として受け取るために登録する
+            rust_notification.action(DEFAULT_ACTION, "");
+            let connection = session_connection()?;
+            // 表示の直後に閉じられても受け取れるよう、送る前にシグナルを購読する
+            let signals = zbus::blocking::Proxy::new(
+                &connection,
+                NOTIFICATIONS_BUS,
+                NOTIFICATIONS_PATH,
+                NOTIFICATIONS_BUS,
+            )
+            .and_then(|proxy| proxy.receive_all_signals())
+            .map_err(dbus_error)?;
+            let id = call_notify(&connection, notification, &rust_notification)?;
+            Ok((signals, id))
+        })?;
 
+        // 閉じた理由（時間切れ / ユーザー操作）も区別する
+        let interaction = signals
+            .filter_map(|message| {
+                let header = message.header();
+                let body = message.body();
+                match header.member()?.as_str() {
+                    "ActionInvoked" => {
+                        let (closed_id, action): (u32, String) = body.deserialize().ok()?;
+                        (closed_id == id).then_some(match action.as_str() {
+                            DEFAULT_ACTION => Interaction::Clicked,
+                            _ => Interaction::ActionInvoked(action),
+                        })
+                    }
+                    "NotificationClosed" => {
+                        let (closed_id, reason): (u32, u32) = body.deserialize().ok()?;
+                        (closed_id == id).then_some(if reason == CLOSED_EXPIRED {
+                            Interaction::Expired
+                        } else {
+                            Interaction::Dismissed
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(Interaction::Dismissed);
+
+        Ok(interaction)
+    }
+
+    fn warm_up(&self) -> Result<()> {
+        // サーバー情報を問い合わせることで、D-Bus セッションバスへの接続と
+        // 通知デーモンの起動（D-Bus アクティベーション）を先に済ませておく
+        with_session_retry(call_daemon::<ServerInformation>("GetServerInformation"))?;
         Ok(())
     }
 
+    fn capabilities(&self) -> Capabilities {
+        // 通知デーモンごとに違うので問い合わせる（接続できなければ仕様で必須のものだけ）
+        let server = with_session_retry(call_daemon::<Vec<String>>("GetCapabilities"));
+        Capabilities {
+            // 仕様に上限は無いが、多くの通知デーモンは長い本文を省略する（文字数は目安）
+            max_title_len: Some(100),
+            max_message_len: Some(1000),
+            ..Capabilities::from_server(&server.unwrap_or_default())
+        }
+    }
+
     fn is_available(&self) -> bool {
         // Linux 向けにコンパイルされていれば利用可能
         true
@@ -65,22 +201,520 @@ impl Notifier for LinuxNotifier {
     }
 }
 
+/// `show_async()`（zbus の非同期 API）で送る
+///
+/// 失敗してセッションの環境変数が変わっていれば、`with_session_retry()` と同じく 1 度だけ再試行します。
+#[cfg(all(feature = "async", target_os = "linux", feature = "linux-dbus"))]
+impl AsyncNotifier for LinuxNotifier {
+    fn deliver_async<'a>(
+        &'a self,
+        notification: &'a Notification,
+    ) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            let notification = for_delivery(notification);
+            let rust_notification = build_notification(&notification, &current_server());
+            let id = match call_notify_async(&notification, &rust_notification).await {
+                Ok(id) => id,
+                Err(err) if nonblocking::blocking(refresh_session_bus).await => {
+                    tracing::info!("{err}; session bus changed, reconnecting");
+                    *SERVER.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                    call_notify_async(&notification, &rust_notification).await?
+                }
+                Err(err) => return Err(err),
+            };
+            Ok(Some(id.to_string()))
+        })
+    }
+}
+
+/// 送る内容（URL などのアイコンはファイルにし、非公開の通知は画面のロック中なら本文を隠す）
+///
+/// freedesktop の仕様にはロック画面用の表示が無いため、送る内容そのものを変えます。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn for_delivery(notification: &Notification) -> Cow<'_, Notification> {
+    let notification = notification.with_fetched_icon();
+    if notification.private && crate::platform::is_session_locked() {
+        Cow::Owned(notification.public_view())
+    } else {
+        notification
+    }
+}
+
+/// 送信先の通知デーモンについて、通知の組み立てに使う情報
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Server {
+    /// 既知の癖（`quirks::for_server()`）
+    quirks: ServerQuirks,
+    /// 本文のマークアップに対応しているか（`GetCapabilities` の `body-markup`）
+    body_markup: bool,
+}
+
+/// 仕様どおりに動き、マークアップに対応した通知デーモン
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            quirks: ServerQuirks::default(),
+            body_markup: true,
+        }
+    }
+}
+
+/// 問い合わせ済みの通知デーモン（`None` はまだ問い合わせていない）
+///
+/// 名前（`GetServerInformation`、接続できなければ `None`）と、`body-markup` に対応しているか。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+static SERVER: Mutex<Option<(Option<String>, bool)>> = Mutex::new(None);
+
+/// 実行中の通知デーモンの名前と `body-markup` への対応（結果はキャッシュする）
+///
+/// セッションの環境が変わった時（`with_session_retry()`）にキャッシュを捨てます。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn server_info() -> (Option<String>, bool) {
+    SERVER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| {
+            let name = call_daemon::<ServerInformation>("GetServerInformation")()
+                .ok()
+                .map(|(name, ..)| name);
+            let markup = call_daemon::<Vec<String>>("GetCapabilities")()
+                .is_ok_and(|capabilities| Capabilities::from_server(&capabilities).supports_markup);
+            (name, markup)
+        })
+        .clone()
+}
+
+/// 実行中の通知デーモンの名前（`GetServerInformation`）
+///
+/// 通知デーモンに接続できない場合は `None` です。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+pub(crate) fn server_name() -> Option<String> {
+    server_info().0
+}
+
+/// Linux 以外では通知デーモンは無い
+#[cfg(not(all(target_os = "linux", feature = "linux-dbus")))]
+pub(crate) fn server_name() -> Option<String> {
+    None
+}
+
+/// 実行中の通知デーモン（癖は `Config::server_quirks` と組み込みの表から）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn current_server() -> Server {
+    let (name, body_markup) = server_info();
+    Server {
+        quirks: quirks::for_server(&crate::config::current(), &name.unwrap_or_default()),
+        body_markup,
+    }
+}
+
+// ============================================================
+// D-Bus の呼び出し
+// ============================================================
+
+/// `GetServerInformation` の戻り値（名前, ベンダー, バージョン, 仕様のバージョン）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+type ServerInformation = (String, String, String, String);
+
+/// 読み直したセッションバスのアドレス（`None` は `DBUS_SESSION_BUS_ADDRESS` のまま）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+static SESSION_BUS: Mutex<Option<String>> = Mutex::new(None);
+
+/// 読み直したアドレスがあれば、それを返す
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn session_bus() -> Option<String> {
+    SESSION_BUS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// セッションバスに接続する（読み直したアドレスがあればそちら）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn session_connection() -> Result<zbus::blocking::Connection> {
+    match session_bus() {
+        Some(address) => zbus::blocking::connection::Builder::address(address.as_str())
+            .and_then(|builder| builder.build()),
+        None => zbus::blocking::Connection::session(),
+    }
+    .map_err(dbus_error)
+}
+
+/// 引数の無いメソッドを通知デーモンに呼ぶ関数（`with_session_retry()` に渡す）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn call_daemon<R>(method: &'static str) -> impl Fn() -> Result<R>
+where
+    R: for<'d> zbus::zvariant::DynamicDeserialize<'d>,
+{
+    move || {
+        let reply = session_connection()?
+            .call_method(
+                Some(NOTIFICATIONS_BUS),
+                NOTIFICATIONS_PATH,
+                Some(NOTIFICATIONS_BUS),
+                method,
+                &(),
+            )
+            .map_err(dbus_error)?;
+        reply.body().deserialize().map_err(dbus_error)
+    }
+}
+
+/// `Notify` を呼び、通知デーモンが割り当てた ID を返す
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn call_notify(
+    connection: &zbus::blocking::Connection,
+    notification: &Notification,
+    built: &RustNotification,
+) -> Result<u32> {
+    let hints = notify_hints(notification, built);
+    let reply = connection
+        .call_method(
+            Some(NOTIFICATIONS_BUS),
+            NOTIFICATIONS_PATH,
+            Some(NOTIFICATIONS_BUS),
+            "Notify",
+            &notify_body(notification, built, &hints),
+        )
+        .map_err(dbus_error)?;
+    reply.body().deserialize().map_err(dbus_error)
+}
+
+/// `call_notify()` の非同期版（接続も非同期）
+#[cfg(all(feature = "async", target_os = "linux", feature = "linux-dbus"))]
+async fn call_notify_async(notification: &Notification, built: &RustNotification) -> Result<u32> {
+    let connection = match session_bus() {
+        Some(address) => {
+            zbus::connection::Builder::address(address.as_str())
+                .map_err(dbus_error)?
+                .build()
+                .await
+        }
+        None => zbus::Connection::session().await,
+    }
+    .map_err(dbus_error)?;
+    let hints = notify_hints(notification, built);
+    let reply = connection
+        .call_method(
+            Some(NOTIFICATIONS_BUS),
+            NOTIFICATIONS_PATH,
+            Some(NOTIFICATIONS_BUS),
+            "Notify",
+            &notify_body(notification, built, &hints),
+        )
+        .await
+        .map_err(dbus_error)?;
+    reply.body().deserialize().map_err(dbus_error)
+}
+
+/// `Notify` の引数（`notify_hints()` の結果を借りる）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+type NotifyBody<'a> = (
+    &'a str,
+    u32,
+    &'a str,
+    &'a str,
+    &'a str,
+    &'a Vec<String>,
+    HashMap<&'a str, zbus::zvariant::Value<'a>>,
+    i32,
+);
+
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn notify_body<'a>(
+    notification: &Notification,
+    built: &'a RustNotification,
+    hints: &'a [Hint],
+) -> NotifyBody<'a> {
+    (
+        &built.appname,
+        replaces_id(notification),
+        &built.icon,
+        &built.summary,
+        &built.body,
+        &built.actions,
+        hints.iter().map(Into::into).collect(),
+        built.timeout.into(),
+    )
+}
+
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn dbus_error(err: zbus::Error) -> NotificationError {
+    NotificationError::SendFailed {
+        backend: "Linux".to_string(),
+        reason: err.to_string(),
+    }
+}
+
+// ============================================================
+// セッションバスの読み直し
+// ============================================================
+
+/// `action` が失敗し、セッションバスのアドレスが変わっていれば 1 度だけ再試行する
+///
+/// アドレスが変わっていなければ（通知デーモン側の問題なら）元のエラーを返します。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn with_session_retry<T>(action: impl Fn() -> Result<T>) -> Result<T> {
+    match action() {
+        Err(err) if refresh_session_bus() => {
+            tracing::info!("{err}; session bus changed, reconnecting");
+            *SERVER.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            action()
+        }
+        result => result,
+    }
+}
+
+/// systemd のユーザー環境からセッションバスのアドレスを読み直す（変わった場合は `true`）
+///
+/// `systemctl --user` が使えない場合は、`$XDG_RUNTIME_DIR/bus` があればそのアドレスにします。
+/// 変わったアドレスは `SESSION_BUS` に入れ、以降の `session_connection()` が使います。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn refresh_session_bus() -> bool {
+    let fresh = crate::process::command("systemctl")
+        .ok()
+        .and_then(|mut command| command.args(["--user", "show-environment"]).output().ok())
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_bus_address(&String::from_utf8_lossy(&output.stdout)))
+        .or_else(runtime_dir_bus);
+    let Some(fresh) = fresh else {
+        return false;
+    };
+
+    let mut current = SESSION_BUS.lock().unwrap_or_else(|e| e.into_inner());
+    let in_use = current
+        .clone()
+        .or_else(|| std::env::var(BUS_ADDRESS_VAR).ok());
+    if in_use.as_deref() == Some(fresh.as_str()) {
+        return false;
+    }
+    *current = Some(fresh);
+    true
+}
+
+/// `$XDG_RUNTIME_DIR/bus`（systemd が作るセッションバスのソケット）のアドレス
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn runtime_dir_bus() -> Option<String> {
+    let socket = std::path::Path::new(&std::env::var_os("XDG_RUNTIME_DIR")?).join("bus");
+    socket
+        .exists()
+        .then(|| format!("unix:path={}", socket.display()))
+}
+
+/// `systemctl --user show-environment` の出力からセッションバスのアドレスを取り出す
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn parse_bus_address(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| *key == BUS_ADDRESS_VAR)
+        .map(|(_, value)| value.to_string())
+}
+
+/// `Notification` を notify-rust の通知に変換
+///
+/// `server` は送信先の通知デーモンの癖と対応状況で、仕様どおりに表示されない部分を補います。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn build_notification(notification: &Notification, server: &Server) -> RustNotification {
+    let quirks = &server.quirks;
+    // タイムアウトの変換
+    let timeout = if notification.timeout == 0 {
+        Timeout::Never
+    } else {
+        Timeout::Milliseconds(notification.timeout)
+    };
+
+    // 緊急度の変換（重要でないと閉じるまで表示しないデーモンでは引き上げる）
+    // おやすみモードを越えて届ける通知も重要にする（GNOME などは重要な通知だけを表示する）
+    let urgency = match notification.urgency {
+        _ if notification.time_sensitive => Urgency::Critical,
+        _ if notification.timeout == 0 && quirks.sticky_needs_critical => Urgency::Critical,
+        UrgencyLevel::Low => Urgency::Low,
+        UrgencyLevel::Normal => Urgency::Normal,
+        UrgencyLevel::Critical => Urgency::Critical,
+    };
+
+    // notify-rust の API を使用して通知を構築
+    // Markdown の本文は Pango マークアップで渡す（body-markup に対応していなければプレーンテキスト）
+    let body = match &notification.markdown {
+        Some(_) if !server.body_markup => notification.message.clone(),
+        Some(source) if quirks.markup_links => markdown::to_pango(source),
+        Some(source) => markdown::to_pango_without_links(source),
+        None => notification.message.clone(),
+    };
+
+    // テーマの名前はそのまま、それ以外はファイルにして渡す（URL などは for_delivery() で取得済み）
+    let icon_file = match notification.icon() {
+        Some(Icon::Named(_)) | None => None,
+        Some(_) => notification.icon_file(),
+    };
+    let icon = match notification.icon() {
+        Some(Icon::Named(name)) => Some(name.clone()),
+        _ => icon_file
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string()),
+    };
+
+    let mut rust_notification = RustNotification::new();
+    rust_notification
+        .summary(&notification.title)
+        .body(&body)
+        .icon(icon.as_deref().unwrap_or(DEFAULT_ICON))
+        .timeout(timeout)
+        .urgency(urgency);
+
+    // 未指定の場合は notify-rust のデフォルト（実行ファイル名）
+    if let Some(app_name) = &notification.app_name {
+        rust_notification.appname(app_name);
+    }
+
+    // ファイルパスのアイコンを app_icon で表示しないデーモンには画像として渡す
+    match (&notification.image, icon_file) {
+        (Some(image), _) => {
+            rust_notification.image_path(&image.to_string_lossy());
+        }
+        (None, Some(icon)) if quirks.icon_as_image => {
+            rust_notification.image_path(&icon.to_string_lossy());
+        }
+        _ => {}
+    }
+
+    // 同じタグの通知を 1 つにまとめる（dunst などが対応）
+    if let Some(group) = &notification.group {
+        rust_notification.hint(Hint::Custom(DUNST_STACK_TAG.to_string(), group.clone()));
+    }
+
+    // 通知デーモン（dunst など）はカテゴリごとのルールを持てる
+    if let Some(category) = &notification.category {
+        rust_notification.hint(Hint::Category(category.clone()));
+    }
+
+    // 一時的な通知は履歴に残さず、常駐する通知はアクション後も消さない
+    if notification.transient {
+        rust_notification.hint(Hint::Transient(true));
+    }
+    if notification.resident {
+        rust_notification.hint(Hint::Resident(true));
+    }
+
+    // 名前付きの通知音（macOS 用）は無視し、ファイルパスの場合のみ鳴らす
+    if let Some(sound) = notification.sound_path() {
+        rust_notification.hint(Hint::SoundFile(sound.to_string_lossy().to_string()));
+    }
+    if notification.silent {
+        rust_notification.hint(Hint::SuppressSound(true));
+    }
+
+    // 利用者が指定したヒント（既定のヒントより後に追加し、同じ名前なら上書きする）
+    for (key, value) in &notification.hints {
+        rust_notification.hint(custom_hint(key, value));
+    }
+
+    // アクションボタンを追加（表示できるかは通知デーモン次第）
+    for action in &notification.actions {
+        rust_notification.action(&action.id, &action.label);
+    }
+
+    // 置き換え対象の ID（数値でなければ新しい通知として表示）
+    if let Some(id) = notification
+        .replace_id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+    {
+        rust_notification.id(id);
+    }
+
+    rust_notification
+}
+
+/// notify-rust の通知から、`Notify` に渡る引数を取り出す（`Notifier::render()`）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn notify_arguments(notification: &Notification, built: &RustNotification) -> DBusNotify {
+    let hints: BTreeMap<String, String> = notify_hints(notification, built)
+        .iter()
+        .map(|hint| {
+            let (name, value): (&str, zbus::zvariant::Value) = hint.into();
+            (name.to_string(), value.to_string())
+        })
+        .collect();
+
+    DBusNotify {
+        app_name: built.appname.clone(),
+        replaces_id: replaces_id(notification),
+        app_icon: built.icon.clone(),
+        summary: built.summary.clone(),
+        body: built.body.clone(),
+        actions: built.actions.clone(),
+        hints: hints.into_iter().collect(),
+        expire_timeout: built.timeout.into(),
+    }
+}
+
+/// `Notify` に渡すヒント（同じ名前は後のものが優先）
+///
+/// 独自のヒント（`Hint::Custom`）は notify-rust の非公開フィールドに入るため、
+/// `build_notification()` と同じ規則で元の通知から作り直します。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn notify_hints(notification: &Notification, built: &RustNotification) -> Vec<Hint> {
+    let stack_tag = notification
+        .group
+        .iter()
+        .map(|group| Hint::Custom(DUNST_STACK_TAG.to_string(), group.clone()));
+    let custom = notification
+        .hints
+        .iter()
+        .map(|(key, value)| custom_hint(key, value))
+        .filter(|hint| matches!(hint, Hint::Custom(..) | Hint::CustomInt(..)));
+    built
+        .hints
+        .iter()
+        .cloned()
+        .chain(stack_tag)
+        .chain(custom)
+        .collect()
+}
+
+/// 置き換え対象の ID（数値でなければ 0 = 新しい通知）
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn replaces_id(notification: &Notification) -> u32 {
+    notification
+        .replace_id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+        .unwrap_or(0)
+}
+
+/// キーと値の文字列から notify-rust の `Hint` を作る
+///
+/// 既知の名前（`transient`, `x` など）は仕様どおりの型に、
+/// それ以外は整数に見えれば整数、そうでなければ文字列にします。
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+fn custom_hint(key: &str, value: &str) -> Hint {
+    Hint::from_key_val(key, value).unwrap_or_else(|_| match value.parse() {
+        Ok(number) => Hint::CustomInt(key.to_string(), number),
+        Err(_) => Hint::Custom(key.to_string(), value.to_string()),
+    })
+}
+
 // ============================================================
 // Linux 以外のプラットフォーム向けスタブ実装
 // ============================================================
 
-/// Linux 以外では、エラーを返すスタブ実装を提供
+/// Linux 以外（または `linux-dbus` フィーチャーが無い時）は、エラーを返すスタブ実装を提供
 ///
 /// # 学習ポイント
 /// `#[cfg(not(...))]` で「〜以外」を指定できます。
 /// これにより、Linux でコンパイルされた場合とそれ以外で
 /// 異なる実装を提供できます。
-#[cfg(not(target_os = "linux"))]
+#[cfg(not(all(target_os = "linux", feature = "linux-dbus")))]
 impl Notifier for LinuxNotifier {
     fn send(&self, _notification: &Notification) -> Result<()> {
-        Err(NotificationError::UnsupportedPlatform(
-            "Linux notification requires a binary compiled for Linux".to_string(),
-        ))
+        Err(NotificationError::BackendUnavailable {
+            backend: "Linux".to_string(),
+            hint: "requires a binary compiled for Linux with the `linux-dbus` feature".to_string(),
+        })
     }
 
     fn is_available(&self) -> bool {
@@ -99,6 +733,8 @@ impl Notifier for LinuxNotifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+    use std::time::Duration;
 
     #[test]
     fn test_backend_name() {
@@ -107,14 +743,125 @@ mod tests {
         let _ = notifier.backend_name();
     }
 
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+    #[test]
+    fn test_parse_bus_address() {
+        let output =
+            "HOME=/home/me\nDBUS_SESSION_BUS_ADDRESS=unix:path=/run/user/1000/bus\nDISPLAY=:0\n";
+        assert_eq!(
+            parse_bus_address(output).as_deref(),
+            Some("unix:path=/run/user/1000/bus")
+        );
+        assert_eq!(parse_bus_address("HOME=/home/me\nDISPLAY=:0\n"), None);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+    #[test]
+    fn test_build_notification_hints() {
+        use crate::notifier::NotificationBuilder;
+
+        let notification = NotificationBuilder::new()
+            .message("Step 3/50")
+            .category("transfer")
+            .app_name("my-tool")
+            .transient(true)
+            .silent(true)
+            .build();
+        let built = build_notification(&notification, &Server::default());
+
+        assert_eq!(built.appname, "my-tool");
+        // Hint::Custom（x-dunst-stack-tag）は notify-rust の非公開フィールドに入るため確認できない
+        assert!(built
+            .hints
+            .contains(&Hint::Category("transfer".to_string())));
+        assert!(built.hints.contains(&Hint::Transient(true)));
+        assert!(!built.hints.contains(&Hint::Resident(true)));
+        assert!(built.hints.contains(&Hint::SuppressSound(true)));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+    #[test]
+    fn test_notify_arguments() {
+        use crate::notifier::NotificationBuilder;
+
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .message("a < b")
+            .group("ci")
+            .hint("x-canonical-private-synchronous", "volume")
+            .action("open", "Open")
+            .replace_id("42")
+            .timeout(Duration::from_secs(3))
+            .build();
+        let built = build_notification(&notification, &Server::default());
+        let notify = notify_arguments(&notification, &built);
+
+        assert_eq!(notify.summary, "Build");
+        assert_eq!(notify.body, "a < b");
+        assert_eq!(notify.replaces_id, 42);
+        assert_eq!(notify.actions, ["open", "Open"]);
+        assert_eq!(notify.expire_timeout, 3000);
+        let hint = |name: &str| {
+            notify
+                .hints
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(hint(DUNST_STACK_TAG), Some("\"ci\""));
+        assert_eq!(hint("x-canonical-private-synchronous"), Some("\"volume\""));
+        assert_eq!(hint("urgency"), Some("byte 0x01"));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+    #[test]
+    fn test_build_notification_applies_quirks() {
+        use crate::notifier::NotificationBuilder;
+
+        let notification = NotificationBuilder::new()
+            .body_markdown("see [logs](https://ci/1)")
+            .icon("/tmp/tool.png")
+            .timeout(Duration::ZERO)
+            .urgency(UrgencyLevel::Normal)
+            .build();
+        let quirks = ServerQuirks {
+            markup_links: false,
+            icon_as_image: true,
+            sticky_needs_critical: true,
+        };
+
+        let server = Server {
+            quirks,
+            ..Server::default()
+        };
+        let built = build_notification(&notification, &server);
+        assert_eq!(built.body, "see logs (https://ci/1)");
+        assert!(built
+            .hints
+            .contains(&Hint::ImagePath("/tmp/tool.png".to_string())));
+        assert!(built.hints.contains(&Hint::Urgency(Urgency::Critical)));
+
+        let built = build_notification(&notification, &Server::default());
+        assert!(built.body.contains("<a href="));
+        assert!(built.hints.contains(&Hint::Urgency(Urgency::Normal)));
+
+        // body-markup に対応していない通知デーモンにはプレーンテキストで送る
+        let server = Server {
+            body_markup: false,
+            ..Server::default()
+        };
+        let built = build_notification(&notification, &server);
+        assert_eq!(built.body, "see logs (https://ci/1)");
+    }
+
+    #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
     #[test]
     fn test_linux_notifier_available() {
         let notifier = LinuxNotifier;
         assert!(notifier.is_available());
     }
 
-    #[cfg(not(target_os = "linux"))]
+    #[cfg(not(all(target_os = "linux", feature = "linux-dbus")))]
     #[test]
     fn test_linux_notifier_unavailable() {
         let notifier = LinuxNotifier;
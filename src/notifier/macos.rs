@@ -1,63 +1,179 @@
 //! macOS 通知バックエンド
 //!
 //! `osascript` コマンドを使用して AppleScript 経由で通知センターに通知を送信します。
+//! アクションボタンが指定され、[alerter](https://github.com/vjeantet/alerter)
+//! がインストールされている場合は alerter を使います。
 //!
 //! # 学習ポイント
 //! - `std::process::Command` による外部コマンド実行
 //! - AppleScript の構文
 //! - 文字列のエスケープ処理
 
+use std::process::{Command, Output, Stdio};
+
+use crate::config;
 use crate::error::{NotificationError, Result};
-use crate::notifier::{Notification, Notifier};
-use std::process::Command;
+use crate::notifier::click;
+use crate::notifier::handle::generate_id;
+use crate::notifier::render::RENDER_ID;
+#[cfg(feature = "async")]
+use crate::notifier::{nonblocking, AsyncNotifier, BoxFuture};
+use crate::notifier::{Capabilities, Interaction, Invocation, Notification, Notifier};
+use crate::process;
+
+/// macOS のシステムサウンドの名前（`/System/Library/Sounds` のファイル名。`--sound` の補完候補）
+pub const SYSTEM_SOUNDS: &[&str] = &[
+    "Basso",
+    "Blow",
+    "Bottle",
+    "Frog",
+    "Funk",
+    "Glass",
+    "Hero",
+    "Morse",
+    "Ping",
+    "Pop",
+    "Purr",
+    "Sosumi",
+    "Submarine",
+    "Tink",
+];
 
 /// macOS 通知バックエンド
 pub struct MacOsNotifier;
 
 impl Notifier for MacOsNotifier {
     fn send(&self, notification: &Notification) -> Result<()> {
-        // AppleScript 用にエスケープ
-        let title = escape_applescript(&notification.title);
-        let message = escape_applescript(&notification.message);
-        let subtitle = escape_applescript(&notification.subtitle);
-
-        // AppleScript を構築
-        // 構文: display notification "メッセージ" with title "タイトル" subtitle "サブ" sound name "音"
-        let mut script = format!(
-            r#"display notification "{}" with title "{}""#,
-            message, title
-        );
+        self.deliver(notification).map(|_| ())
+    }
 
-        // サブタイトルがあれば追加
-        if !subtitle.is_empty() {
-            script.push_str(&format!(r#" subtitle "{}""#, subtitle));
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        let notification = &notification.with_fetched_icon();
+        if uses_terminal_notifier(notification) {
+            let mut command = process::command("terminal-notifier")?;
+            command.args(build_terminal_notifier_args(notification)?);
+            let output = process::output_within(command, notification.command_timeout)?;
+            if !output.status.success() {
+                return Err(NotificationError::SendFailed {
+                    backend: "macOS".to_string(),
+                    reason: String::from_utf8_lossy(&output.stderr).to_string(),
+                });
+            }
+            play_sound_file(notification)?;
+            // -group の通知は同じ group で置き換えられる
+            return Ok(terminal_notifier_group(notification).cloned());
         }
 
-        // 通知音を追加
-        script.push_str(&format!(r#" sound name "{}""#, notification.sound));
+        if uses_alerter(notification) {
+            let (args, group) = build_alerter_delivery(notification, generate_id);
+
+            // alerter はユーザーが操作するまで終了しないので、起動だけして待たない
+            process::command("alerter")?
+                .args(args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            play_sound_file(notification)?;
+            return Ok(Some(group));
+        }
 
         // osascript を実行
-        // osascript は macOS の AppleScript インタープリタ
-        let output = Command::new("osascript")
-            .arg("-e") // -e: スクリプトを引数として実行
-            .arg(&script)
-            .output()?; // io::Error は NotificationError に自動変換
+        let command = osascript_command(notification)?;
+        let output = process::output_within(command, notification.command_timeout)?;
+        osascript_result(notification, output)
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        // deliver() と同じ順にコマンドを選ぶ（通知音のファイルを鳴らす afplay は含めない）
+        if uses_terminal_notifier(notification) {
+            let args = build_terminal_notifier_args(notification)?;
+            return Ok(Invocation::command("terminal-notifier", args));
+        }
+        if uses_alerter(notification) {
+            let (args, _) = build_alerter_delivery(notification, || RENDER_ID.to_string());
+            return Ok(Invocation::command("alerter", args));
+        }
+        let args = vec!["-e".to_string(), build_applescript(notification)];
+        Ok(Invocation::command("osascript", args))
+    }
 
+    fn close(&self, id: &str) -> Result<()> {
+        // ID は alerter の group なので、alerter で削除する
+        if process::resolve("alerter").is_none() {
+            return Err(alerter_required("closing notifications"));
+        }
+
+        let mut command = process::command("alerter")?;
+        command.arg("-remove").arg(id);
+        let output = process::output_within(command, config::current().command_timeout)?;
         if output.status.success() {
             Ok(())
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
             Err(NotificationError::SendFailed {
                 backend: "macOS".to_string(),
-                reason: stderr.to_string(),
+                reason: String::from_utf8_lossy(&output.stderr).to_string(),
             })
         }
     }
 
+    fn send_and_wait(&self, notification: &Notification) -> Result<Interaction> {
+        // osascript の通知は操作結果を返さないため、alerter が必須
+        if process::resolve("alerter").is_none() {
+            return Err(alerter_required("waiting for interaction"));
+        }
+
+        // alerter はユーザーが操作するか時間切れになるまで終了せず、結果を stdout に出力する
+        let notification = &notification.with_fetched_icon();
+        let mut args = build_alerter_args(notification);
+        if let Some(group) = &notification.group {
+            args.extend(["-group".to_string(), group.clone()]);
+        }
+        let alerter = process::command("alerter")?
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        play_sound_file(notification)?;
+        let output = alerter.wait_with_output()?;
+
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(parse_alerter_output(notification, &stdout))
+        } else {
+            Err(NotificationError::SendFailed {
+                backend: "macOS".to_string(),
+                reason: String::from_utf8_lossy(&output.stderr).to_string(),
+            })
+        }
+    }
+
+    fn warm_up(&self) -> Result<()> {
+        // osascript のパスを解決してキャッシュしておく
+        process::check("osascript").map(|_| ())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        // osascript の通知は文字と通知音だけ。アクション・置き換えなどは alerter を使う
+        let alerter = process::resolve("alerter").is_some();
+        Capabilities {
+            supports_actions: alerter,
+            supports_reply: alerter,
+            supports_images: alerter,
+            supports_sound: true,
+            supports_replace: alerter,
+            supports_close: alerter,
+            supports_wait: alerter,
+            // バナーはタイトル 1 行・本文 2 行まで（通知センターでは全文、文字数は目安）
+            max_title_len: Some(60),
+            max_message_len: Some(200),
+            ..Capabilities::default()
+        }
+    }
+
     fn is_available(&self) -> bool {
         // macOS でのみ利用可能
         // cfg! マクロは bool を返す（#[cfg] とは異なる）
-        cfg!(target_os = "macos")
+        cfg!(all(target_os = "macos", feature = "macos-osascript"))
     }
 
     fn backend_name(&self) -> &'static str {
@@ -65,6 +181,308 @@ impl Notifier for MacOsNotifier {
     }
 }
 
+/// osascript の起動は `tokio::process::Command` で待ち、alerter / terminal-notifier を
+/// 使う通知は `Notifier::deliver()` を `spawn_blocking` で実行する
+#[cfg(feature = "async")]
+impl AsyncNotifier for MacOsNotifier {
+    fn deliver_async<'a>(
+        &'a self,
+        notification: &'a Notification,
+    ) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            if uses_terminal_notifier(notification) || uses_alerter(notification) {
+                let notification = notification.clone();
+                return nonblocking::blocking(move || MacOsNotifier.deliver(&notification)).await;
+            }
+            let command = osascript_command(notification)?;
+            let output = nonblocking::output_within(command, notification.command_timeout).await?;
+            osascript_result(notification, output)
+        })
+    }
+}
+
+/// クリック時の動作（-open / -execute）があり、terminal-notifier で送るかどうか
+///
+/// クリック時の動作は terminal-notifier だけができます。
+fn uses_terminal_notifier(notification: &Notification) -> bool {
+    let has_click_action =
+        notification.on_click_url.is_some() || notification.on_click_exec.is_some();
+    has_click_action && process::resolve("terminal-notifier").is_some()
+}
+
+/// alerter で送るかどうか
+///
+/// アクションボタン・通知の置き換え・画像・アイコン・送信元アプリ・
+/// 閉じるまで残るアラート（scenario）は osascript ではできないため、alerter を使います。
+fn uses_alerter(notification: &Notification) -> bool {
+    let needs_alerter = !notification.actions.is_empty()
+        || notification.scenario.is_some()
+        || notification.reply_placeholder.is_some()
+        || notification.replace_id.is_some()
+        || notification.group.is_some()
+        || notification.app_name.is_some()
+        || notification.image.is_some()
+        || notification.icon().is_some();
+    needs_alerter && process::resolve("alerter").is_some()
+}
+
+/// alerter が無いとできない操作のエラー
+fn alerter_required(operation: &str) -> NotificationError {
+    NotificationError::BackendUnavailable {
+        backend: MacOsNotifier.backend_name().to_string(),
+        hint: format!("{operation} requires alerter (brew install vjeantet/tap/alerter)"),
+    }
+}
+
+/// 通知を表示する alerter の引数と、ハンドルの ID（alerter の `-group`）
+///
+/// group が無い通知は `new_id` で ID を作ります。
+fn build_alerter_delivery(
+    notification: &Notification,
+    new_id: impl FnOnce() -> String,
+) -> (Vec<String>, String) {
+    // 同じ group の通知は置き換えられるので、group を ID として返す
+    let group = notification
+        .replace_id
+        .clone()
+        .or_else(|| notification.group.clone())
+        .unwrap_or_else(new_id);
+    let mut args = build_alerter_args(notification);
+    args.extend(["-group".to_string(), group.clone()]);
+    (args, group)
+}
+
+/// 通知を表示する osascript のコマンド（まだ起動しない）
+///
+/// osascript は macOS の AppleScript インタープリタ
+fn osascript_command(notification: &Notification) -> Result<Command> {
+    let mut command = process::command("osascript")?;
+    command
+        .arg("-e") // -e: スクリプトを引数として実行
+        .arg(build_applescript(notification));
+    Ok(command)
+}
+
+/// osascript の実行結果を `deliver()` の戻り値に変換
+///
+/// osascript の通知は置き換えられないため ID は無い
+fn osascript_result(notification: &Notification, output: Output) -> Result<Option<String>> {
+    if output.status.success() {
+        play_sound_file(notification)?;
+        Ok(None)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(NotificationError::SendFailed {
+            backend: "macOS".to_string(),
+            reason: stderr.to_string(),
+        })
+    }
+}
+
+/// 通知音がファイルパスの場合、`afplay` で再生する
+///
+/// 通知センターはシステムサウンドの名前しか受け付けないため、
+/// 通知とは別に鳴らします。再生の終了は待ちません。
+fn play_sound_file(notification: &Notification) -> Result<()> {
+    if let Some(sound) = notification.sound_path() {
+        process::command("afplay")?
+            .arg(sound)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+    }
+    Ok(())
+}
+
+/// `display notification` の AppleScript を構築
+///
+/// 構文: display notification "メッセージ" with title "タイトル" subtitle "サブ" sound name "音"
+fn build_applescript(notification: &Notification) -> String {
+    // AppleScript 用にエスケープ
+    let title = escape_applescript(&notification.title);
+    let message = escape_applescript(&notification.message);
+
+    let mut script = format!(
+        r#"display notification "{}" with title "{}""#,
+        message, title
+    );
+
+    // サブタイトルがあれば追加
+    if let Some(subtitle) = notification.visible_subtitle() {
+        script.push_str(&format!(r#" subtitle "{}""#, escape_applescript(subtitle)));
+    }
+
+    // 通知音を追加（ファイルは play_sound_file() で鳴らす、無音なら付けない）
+    if let Some(sound) = notification.sound_name() {
+        script.push_str(&format!(r#" sound name "{}""#, sound));
+    }
+
+    script
+}
+
+/// terminal-notifier に渡す引数（クリックで URL を開く・コマンドを実行する通知）
+///
+/// terminal-notifier で表示できない項目（`terminal_notifier_unsupported()`）がある場合と、
+/// URL のスキームが許可されていない場合はエラーにします（黙って落とさない）。
+fn build_terminal_notifier_args(notification: &Notification) -> Result<Vec<String>> {
+    if let Some(feature) = terminal_notifier_unsupported(notification) {
+        let field = if notification.on_click_url.is_some() {
+            "on_click_url"
+        } else {
+            "on_click_exec"
+        };
+        return Err(NotificationError::InvalidInput {
+            field: field.to_string(),
+            reason: format!(
+                "terminal-notifier cannot show {feature}; send the click action and {feature} separately"
+            ),
+        });
+    }
+    let mut args = vec![
+        "-title".to_string(),
+        notification.title.clone(),
+        "-message".to_string(),
+        notification.message.clone(),
+    ];
+    if let Some(url) = &notification.on_click_url {
+        click::check_url(url)?;
+        args.extend(["-open".to_string(), url.clone()]);
+    }
+    if let Some(command) = &notification.on_click_exec {
+        args.extend(["-execute".to_string(), command.clone()]);
+    }
+    if let Some(subtitle) = notification.visible_subtitle() {
+        args.extend(["-subtitle".to_string(), subtitle.to_string()]);
+    }
+    if let Some(group) = terminal_notifier_group(notification) {
+        args.extend(["-group".to_string(), group.clone()]);
+    }
+    // ファイルパスの通知音は afplay で別に鳴らす
+    if let Some(sound) = notification.sound_name() {
+        args.extend(["-sound".to_string(), sound.to_string()]);
+    }
+    Ok(args)
+}
+
+/// terminal-notifier の `-group`（同じ group の前の通知を置き換える。`replace_id` を優先）
+fn terminal_notifier_group(notification: &Notification) -> Option<&String> {
+    notification
+        .replace_id
+        .as_ref()
+        .or(notification.group.as_ref())
+}
+
+/// terminal-notifier では表示できない項目（無ければ `None`）
+fn terminal_notifier_unsupported(notification: &Notification) -> Option<&'static str> {
+    if !notification.actions.is_empty() {
+        Some("action buttons")
+    } else if notification.reply_placeholder.is_some() {
+        Some("a reply field")
+    } else if notification.image.is_some() {
+        Some("an image")
+    } else if notification.icon.is_some() {
+        Some("an icon")
+    } else if notification.app_name.is_some() {
+        Some("a custom app name")
+    } else if notification.scenario.is_some() {
+        Some("a scenario")
+    } else {
+        None
+    }
+}
+
+/// alerter のコマンドライン引数を構築
+///
+/// alerter は引数をそのまま受け取るため、AppleScript のエスケープは不要です。
+/// `-actions` はカンマ区切りなので、ラベル中のカンマは取り除きます。
+fn build_alerter_args(notification: &Notification) -> Vec<String> {
+    let mut args = vec![
+        "-title".to_string(),
+        notification.title.clone(),
+        "-message".to_string(),
+        notification.message.clone(),
+    ];
+
+    if let Some(subtitle) = notification.visible_subtitle() {
+        args.push("-subtitle".to_string());
+        args.push(subtitle.to_string());
+    }
+
+    // alerter は返信欄とボタンを同時に表示できないため、返信欄を優先する
+    if let Some(placeholder) = &notification.reply_placeholder {
+        args.push("-reply".to_string());
+        args.push(placeholder.clone());
+    } else if !notification.actions.is_empty() {
+        let labels: Vec<String> = notification
+            .actions
+            .iter()
+            .map(|action| action.label.replace(',', ""))
+            .collect();
+        args.push("-actions".to_string());
+        args.push(labels.join(","));
+    }
+
+    if let Some(image) = &notification.image {
+        args.push("-contentImage".to_string());
+        args.push(image.to_string_lossy().to_string());
+    }
+
+    // 送信元のアプリ（バンドル ID）。通知のアイコンとクリック時に開くアプリが変わる
+    if let Some(app_name) = &notification.app_name {
+        args.push("-sender".to_string());
+        args.push(app_name.clone());
+    }
+
+    // URL などはファイルにし、テーマの名前はファイルが見つかった時だけ渡す
+    if let Some(icon) = notification.icon_file() {
+        args.push("-appIcon".to_string());
+        args.push(icon.to_string_lossy().to_string());
+    }
+
+    // alerter の -sound はシステムサウンドの名前のみ（ファイルは play_sound_file() で鳴らす）
+    if let Some(sound) = notification.sound_name() {
+        args.push("-sound".to_string());
+        args.push(sound.to_string());
+    }
+
+    // alerter のタイムアウトは秒単位（0 = 操作されるまで表示し続ける）
+    if notification.timeout > 0 {
+        args.push("-timeout".to_string());
+        args.push(notification.timeout.div_ceil(1000).to_string());
+    }
+
+    args
+}
+
+/// alerter の出力を `Interaction` に変換
+///
+/// alerter は操作の種類を `@` で始まる値で、押されたボタンをラベルで出力します。
+/// ラベルは `build_alerter_args()` と同じ変換（カンマ除去）をしてアクション ID に戻します。
+fn parse_alerter_output(notification: &Notification, output: &str) -> Interaction {
+    match output.trim() {
+        "@CONTENTCLICKED" => Interaction::Clicked,
+        "@TIMEOUT" => Interaction::Expired,
+        "@CLOSED" | "" => Interaction::Dismissed,
+        // 返信欄がある場合、alerter は入力されたテキストを出力する
+        text if notification.reply_placeholder.is_some() => Interaction::Replied(text.to_string()),
+        // ボタンが 1 つだけの場合、alerter はラベルではなくこの値を出力する
+        "@ACTIONCLICKED" => notification
+            .actions
+            .first()
+            .map_or(Interaction::Clicked, |action| {
+                Interaction::ActionInvoked(action.id.clone())
+            }),
+        label => notification
+            .actions
+            .iter()
+            .find(|action| action.label.replace(',', "") == label)
+            .map_or_else(
+                || Interaction::ActionInvoked(label.to_string()),
+                |action| Interaction::ActionInvoked(action.id.clone()),
+            ),
+    }
+}
+
 /// AppleScript 用の文字列エスケープ
 ///
 /// AppleScript では以下の文字をエスケープする必要があります:
@@ -75,7 +493,7 @@ impl Notifier for MacOsNotifier {
 /// ```ignore
 /// escape_applescript(r#"Hello "World""#) // => r#"Hello \"World\""#
 /// ```
-fn escape_applescript(s: &str) -> String {
+pub(super) fn escape_applescript(s: &str) -> String {
     s.replace('\\', "\\\\").replace('"', "\\\"")
 }
 
@@ -86,6 +504,8 @@ fn escape_applescript(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::notifier::NotificationBuilder;
+    use std::time::Duration;
 
     #[test]
     fn test_escape_applescript_basic() {
@@ -110,6 +530,210 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_applescript() {
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .message("Done")
+            .subtitle("CI")
+            .sound("Glass")
+            .build();
+
+        assert_eq!(
+            build_applescript(&notification),
+            r#"display notification "Done" with title "Build" subtitle "CI" sound name "Glass""#
+        );
+
+        // 通知音もサブタイトルも指定しなければ付けない
+        let plain = NotificationBuilder::new()
+            .title("Build")
+            .message("Done")
+            .build();
+        assert_eq!(
+            build_applescript(&plain),
+            r#"display notification "Done" with title "Build""#
+        );
+    }
+
+    #[test]
+    fn test_build_terminal_notifier_args() {
+        let notification = NotificationBuilder::new()
+            .title("Report")
+            .message("Ready")
+            .sound("Glass")
+            .on_click_url("https://example.com")
+            .on_click_exec("open /tmp/report.log")
+            .build();
+
+        assert_eq!(
+            build_terminal_notifier_args(&notification).unwrap(),
+            vec![
+                "-title",
+                "Report",
+                "-message",
+                "Ready",
+                "-open",
+                "https://example.com",
+                "-execute",
+                "open /tmp/report.log",
+                "-sound",
+                "Glass"
+            ]
+        );
+
+        // replace_id は -group で置き換える
+        let replacing = NotificationBuilder::new()
+            .on_click_url("https://example.com")
+            .group("build")
+            .replace_id("42")
+            .build();
+        let args = build_terminal_notifier_args(&replacing).unwrap();
+        assert!(args.ends_with(&["-group".to_string(), "42".to_string()]));
+
+        // 表示できない項目・許可していないスキームは黙って落とさずにエラーにする
+        let with_action = NotificationBuilder::new()
+            .on_click_exec("open /tmp")
+            .action("retry", "Retry")
+            .build();
+        let err = build_terminal_notifier_args(&with_action).unwrap_err();
+        assert!(err.to_string().contains("action buttons"), "{err}");
+        let file_url = NotificationBuilder::new()
+            .on_click_url("file:///etc/passwd")
+            .build();
+        assert!(build_terminal_notifier_args(&file_url).is_err());
+    }
+
+    #[test]
+    fn test_build_alerter_args_with_actions() {
+        let notification = NotificationBuilder::new()
+            .title("Deploy")
+            .message("Ship it?")
+            .timeout(Duration::from_millis(1500))
+            .action("yes", "Yes, ship")
+            .action("no", "No")
+            .build();
+
+        assert_eq!(
+            build_alerter_args(&notification),
+            vec![
+                "-title",
+                "Deploy",
+                "-message",
+                "Ship it?",
+                "-actions",
+                "Yes ship,No",
+                "-timeout",
+                "2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sound_file_is_not_passed_as_name() {
+        let notification = NotificationBuilder::new()
+            .message("Done")
+            .sound("/System/Library/Sounds/Glass.aiff")
+            .build();
+
+        assert!(!build_applescript(&notification).contains("sound name"));
+        assert!(!build_alerter_args(&notification).contains(&"-sound".to_string()));
+
+        let silent = NotificationBuilder::new()
+            .sound("Glass")
+            .silent(true)
+            .build();
+        assert!(!build_applescript(&silent).contains("sound name"));
+        assert!(!build_terminal_notifier_args(&silent)
+            .unwrap()
+            .contains(&"-sound".to_string()));
+    }
+
+    #[test]
+    fn test_build_alerter_args_with_image() {
+        let notification = NotificationBuilder::new()
+            .message("Chart")
+            .image("/tmp/chart.png")
+            .build();
+
+        let args = build_alerter_args(&notification);
+        let index = args.iter().position(|arg| arg == "-contentImage").unwrap();
+        assert_eq!(args[index + 1], "/tmp/chart.png");
+        // デフォルトのアイコン名は渡さない
+        assert!(!args.contains(&"-appIcon".to_string()));
+    }
+
+    #[test]
+    fn test_build_alerter_args_with_icon() {
+        let notification = NotificationBuilder::new()
+            .message("Built")
+            .icon("/opt/app/logo.png")
+            .build();
+
+        let args = build_alerter_args(&notification);
+        let index = args.iter().position(|arg| arg == "-appIcon").unwrap();
+        assert_eq!(args[index + 1], "/opt/app/logo.png");
+    }
+
+    #[test]
+    fn test_build_alerter_args_with_app_name() {
+        let notification = NotificationBuilder::new()
+            .message("Done")
+            .app_name("com.apple.Terminal")
+            .build();
+
+        let args = build_alerter_args(&notification);
+        let index = args.iter().position(|arg| arg == "-sender").unwrap();
+        assert_eq!(args[index + 1], "com.apple.Terminal");
+    }
+
+    #[test]
+    fn test_reply_action() {
+        let notification = NotificationBuilder::new()
+            .message("Alice: lunch?")
+            .reply_action("Reply to Alice")
+            .action("ignored", "Ignored")
+            .build();
+
+        let args = build_alerter_args(&notification);
+        let index = args.iter().position(|arg| arg == "-reply").unwrap();
+        assert_eq!(args[index + 1], "Reply to Alice");
+        assert!(!args.contains(&"-actions".to_string()));
+
+        assert_eq!(
+            parse_alerter_output(&notification, "sure, 12:30\n"),
+            Interaction::Replied("sure, 12:30".to_string())
+        );
+        assert_eq!(
+            parse_alerter_output(&notification, "@CLOSED"),
+            Interaction::Dismissed
+        );
+    }
+
+    #[test]
+    fn test_parse_alerter_output() {
+        let notification = NotificationBuilder::new()
+            .action("yes", "Yes, ship")
+            .action("no", "No")
+            .build();
+
+        assert_eq!(
+            parse_alerter_output(&notification, "Yes ship\n"),
+            Interaction::ActionInvoked("yes".to_string())
+        );
+        assert_eq!(
+            parse_alerter_output(&notification, "@CONTENTCLICKED"),
+            Interaction::Clicked
+        );
+        assert_eq!(
+            parse_alerter_output(&notification, "@TIMEOUT"),
+            Interaction::Expired
+        );
+        assert_eq!(
+            parse_alerter_output(&notification, "@CLOSED"),
+            Interaction::Dismissed
+        );
+    }
+
     #[test]
     fn test_backend_name() {
         let notifier = MacOsNotifier;
@@ -0,0 +1,229 @@
+//! Markdown 本文の変換
+//!
+//! `NotificationBuilder::body_markdown()` で渡された本文を、
+//! バックエンドが表示できる形式に変換します。
+//!
+//! | 記法 | Pango（Linux） | プレーンテキスト |
+//! |------|----------------|------------------|
+//! | `**太字**` | `<b>太字</b>` | 太字 |
+//! | `*斜体*` | `<i>斜体</i>` | 斜体 |
+//! | `` `コード` `` | `<tt>コード</tt>` | コード |
+//! | `[文字](URL)` | `<a href="URL">文字</a>` | 文字 (URL) |
+//! | `# 見出し` | `<b>見出し</b>` | 見出し |
+//! | `- 項目` | `• 項目` | `• 項目` |
+//!
+//! 入れ子や表などには対応しない、通知向けの最小限のサブセットです。
+//!
+//! # 学習ポイント
+//! - `enum` によるトークン表現と、出力形式ごとの `match`
+//! - `str::find()` / スライスによる簡単な字句解析
+
+/// 1 行の中の要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inline<'a> {
+    Text(&'a str),
+    Bold(&'a str),
+    Italic(&'a str),
+    Code(&'a str),
+    Link { text: &'a str, url: &'a str },
+}
+
+/// Markdown を Pango マークアップに変換（Linux の通知デーモン向け）
+pub fn to_pango(markdown: &str) -> String {
+    pango(markdown, true)
+}
+
+/// `<a>` を使わずに Pango マークアップに変換（リンクは「文字 (URL)」）
+///
+/// `<a>` は freedesktop の仕様の拡張で Pango 自体には無いため、
+/// 一部の通知デーモン（mako など）ではマークアップ全体が解釈されなくなります。
+#[cfg_attr(
+    not(all(target_os = "linux", feature = "linux-dbus")),
+    allow(dead_code)
+)]
+pub fn to_pango_without_links(markdown: &str) -> String {
+    pango(markdown, false)
+}
+
+fn pango(markdown: &str, links: bool) -> String {
+    render(markdown, |inline, out| match inline {
+        Inline::Text(text) => out.push_str(&escape_pango(text)),
+        Inline::Bold(text) => out.push_str(&format!("<b>{}</b>", escape_pango(text))),
+        Inline::Italic(text) => out.push_str(&format!("<i>{}</i>", escape_pango(text))),
+        Inline::Code(text) => out.push_str(&format!("<tt>{}</tt>", escape_pango(text))),
+        Inline::Link { text, url } if links => out.push_str(&format!(
+            r#"<a href="{}">{}</a>"#,
+            escape_pango(url),
+            escape_pango(text)
+        )),
+        Inline::Link { text, url } => out.push_str(&escape_pango(&link_text(text, url))),
+    })
+}
+
+/// リンクを表示する文字列（URL はクリックできないので、文字の後ろに残す）
+fn link_text(text: &str, url: &str) -> String {
+    if text == url {
+        url.to_string()
+    } else {
+        format!("{text} ({url})")
+    }
+}
+
+/// Markdown の記号を取り除いたプレーンテキストに変換
+pub fn to_plain(markdown: &str) -> String {
+    render(markdown, |inline, out| match inline {
+        Inline::Text(text) | Inline::Bold(text) | Inline::Italic(text) | Inline::Code(text) => {
+            out.push_str(text)
+        }
+        Inline::Link { text, url } => out.push_str(&link_text(text, url)),
+    })
+}
+
+/// 行ごとに見出し・箇条書きを処理し、行内の要素を `emit` で出力する
+fn render(markdown: &str, emit: impl Fn(Inline, &mut String)) -> String {
+    let mut lines = Vec::new();
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let mut out = String::new();
+
+        if let Some(heading) = strip_heading(trimmed) {
+            // 見出しは行全体を太字にする（行内の記法は解釈しない）
+            emit(Inline::Bold(heading), &mut out);
+        } else {
+            let body = match trimmed.strip_prefix("- ").or(trimmed.strip_prefix("* ")) {
+                Some(item) => {
+                    out.push_str("• ");
+                    item
+                }
+                None => line,
+            };
+            for inline in parse_inline(body) {
+                emit(inline, &mut out);
+            }
+        }
+        lines.push(out);
+    }
+    lines.join("\n")
+}
+
+/// `# ` 〜 `###### ` で始まる行なら見出しの文字列を返す
+fn strip_heading(line: &str) -> Option<&str> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&level) {
+        line[level..].strip_prefix(' ').map(str::trim)
+    } else {
+        None
+    }
+}
+
+/// 1 行を行内の要素に分解する
+///
+/// 閉じ記号が見つからない場合は、記号をそのまま文字として扱います。
+fn parse_inline(line: &str) -> Vec<Inline<'_>> {
+    let mut result = Vec::new();
+    let mut rest = line;
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    // 直前までの文字列を Text として確定させる
+    fn flush<'a>(result: &mut Vec<Inline<'a>>, line: &'a str, start: usize, end: usize) {
+        if start < end {
+            result.push(Inline::Text(&line[start..end]));
+        }
+    }
+
+    while let Some(c) = rest.chars().next() {
+        let parsed = match c {
+            '*' if rest.starts_with("**") => {
+                delimited(rest, "**").map(|(t, n)| (Inline::Bold(t), n))
+            }
+            '*' => delimited(rest, "*").map(|(t, n)| (Inline::Italic(t), n)),
+            '`' => delimited(rest, "`").map(|(t, n)| (Inline::Code(t), n)),
+            '[' => link(rest),
+            _ => None,
+        };
+
+        match parsed {
+            Some((inline, consumed)) => {
+                flush(&mut result, line, text_start, pos);
+                result.push(inline);
+                pos += consumed;
+                text_start = pos;
+            }
+            None => pos += c.len_utf8(),
+        }
+        rest = &line[pos..];
+    }
+
+    flush(&mut result, line, text_start, pos);
+    result
+}
+
+/// `marker` で囲まれた部分を取り出し、(中身, 消費したバイト数) を返す
+fn delimited<'a>(input: &'a str, marker: &str) -> Option<(&'a str, usize)> {
+    let inner = &input[marker.len()..];
+    let end = inner.find(marker)?;
+    let content = &inner[..end];
+    // `2 * 3 * 4` のような記号の前後に空白がある場合は強調とみなさない
+    let emphasized = !content.is_empty()
+        && !content.starts_with(char::is_whitespace)
+        && !content.ends_with(char::is_whitespace);
+    emphasized.then(|| (content, marker.len() * 2 + end))
+}
+
+/// `[文字](URL)` を取り出す
+fn link(input: &str) -> Option<(Inline<'_>, usize)> {
+    let text_end = input.find("](")?;
+    let url_end = input[text_end + 2..].find(')')? + text_end + 2;
+    let text = &input[1..text_end];
+    let url = &input[text_end + 2..url_end];
+    (!text.is_empty() && !url.is_empty()).then_some((Inline::Link { text, url }, url_end + 1))
+}
+
+/// Pango マークアップ用のエスケープ
+fn escape_pango(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "# Build\n**3** tests *failed* in `core`\n- see [logs](https://ci/1)";
+
+    #[test]
+    fn test_to_pango() {
+        assert_eq!(
+            to_pango(SOURCE),
+            "<b>Build</b>\n<b>3</b> tests <i>failed</i> in <tt>core</tt>\n• see <a href=\"https://ci/1\">logs</a>"
+        );
+        assert_eq!(to_pango("a < b & c"), "a &lt; b &amp; c");
+        assert_eq!(
+            to_pango_without_links("see [a&b](https://ci/1)"),
+            "see a&amp;b (https://ci/1)"
+        );
+    }
+
+    #[test]
+    fn test_to_plain() {
+        assert_eq!(
+            to_plain(SOURCE),
+            "Build\n3 tests failed in core\n• see logs (https://ci/1)"
+        );
+    }
+
+    #[test]
+    fn test_unclosed_markers_are_kept() {
+        assert_eq!(to_plain("2 * 3 = 6"), "2 * 3 = 6");
+        assert_eq!(to_plain("2 * 3 * 4"), "2 * 3 * 4");
+        assert_eq!(to_plain("[not a link]"), "[not a link]");
+        assert_eq!(to_plain("#hashtag"), "#hashtag");
+    }
+}
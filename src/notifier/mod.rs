@@ -22,20 +22,138 @@
 //!   ┌────────┼────────┐
 //!   ▼        ▼        ▼
 //! Linux   Windows   macOS   ← 各バックエンドの実装
+//!   │        │        │
+//!   └────────┼────────┘ 送信失敗時
+//!            ▼
+//!   Dialog → Console        ← フォールバック（fallback_chain で変更可）
 //! ```
 
 // サブモジュールの宣言
+mod capabilities;
+mod click;
+mod command;
+mod console;
+mod dedupe;
+mod dialog;
+mod handle;
+mod icon;
+mod latency;
+mod length;
+mod limit;
 mod linux;
 mod macos;
+mod markdown;
+#[cfg(feature = "async")]
+mod nonblocking;
+mod plugin;
+pub mod quirks;
+mod registry;
+mod render;
+mod retry;
+mod runspace;
+mod simulate;
+mod stdout;
+mod subscribe;
 mod windows;
 
 // 各バックエンドの Notifier 実装を公開
+pub use capabilities::Capabilities;
+pub use command::CustomCommandNotifier;
+pub use console::ConsoleNotifier;
+pub use dialog::DialogNotifier;
+pub use handle::{NotificationHandle, SendReport};
+pub use icon::Icon;
+pub use length::Overflow;
+pub use limit::{set_concurrency_limit, Permit, Semaphore};
 pub use linux::LinuxNotifier;
-pub use macos::MacOsNotifier;
-pub use windows::WindowsNotifier;
+pub use macos::{MacOsNotifier, SYSTEM_SOUNDS as MACOS_SOUNDS};
+#[cfg(feature = "async")]
+pub use nonblocking::{async_notifier_for, AsyncNotifier, BoxFuture};
+pub use plugin::{discover, load_plugins, plugin_dir, PluginNotifier};
+pub use quirks::ServerQuirks;
+pub use registry::{register_backend, registered_backends, unregister_backend};
+pub use render::{DBusNotify, Invocation, RenderedDelivery};
+pub use retry::{RetryOn, RetryPolicy};
+pub use simulate::{simulate, Decision, Simulation};
+pub use stdout::StdoutNotifier;
+pub use subscribe::{subscribe, subscribe_topic, Subscription};
+pub use windows::{
+    check_toasts_enabled, register_app_id, schedule_task, WindowsNotifier,
+    NOTIFICATION_SETTINGS_URI,
+};
+
+pub(crate) use click::open_url;
+pub(crate) use linux::server_name;
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::clock;
+use crate::config::{self, Config};
 use crate::error::{NotificationError, Result};
-use crate::platform::{detect_platform, Platform};
+use crate::platform::{self, detect_platform, Platform};
+use crate::preset::{normalize_locale, FALLBACK_LOCALE};
+use crate::priority;
+use crate::redact;
+use crate::rules;
+use crate::sender::SenderInfo;
+use crate::store::history::{self, Delivery};
+use crate::store::queue;
+use crate::targets;
+use crate::template::{self, NotificationTemplate};
+use crate::throttle::{self, Admission};
+use crate::topic;
+use crate::trace::TraceContext;
+
+/// 非公開の通知で、本文の代わりに表示する文言
+pub const PRIVATE_BODY: &str = "New notification";
+
+/// `NotificationBuilder::from_json()` / `from_toml()` が読むキー（`config validate` で知らないキーを探す）
+pub(crate) const JSON_KEYS: [&str; 37] = [
+    "title",
+    "message",
+    "markdown",
+    "timeout",
+    "expiration_ms",
+    "icon",
+    "image",
+    "urgency",
+    "priority",
+    "subtitle",
+    "sound",
+    "backend",
+    "actions",
+    "reply_placeholder",
+    "group",
+    "collection",
+    "replace_id",
+    "require_ack",
+    "repeat_sound_ms",
+    "preset",
+    "locale",
+    "category",
+    "app_name",
+    "topic",
+    "on_click_url",
+    "on_click_exec",
+    "attribution",
+    "scenario",
+    "silent",
+    "loop_sound",
+    "respect_dnd",
+    "time_sensitive",
+    "trace_context",
+    "transient",
+    "resident",
+    "private",
+    "hints",
+];
 
 // ============================================================
 // Notifier トレイト
@@ -61,6 +179,14 @@ pub trait Notifier {
     /// - `Err(NotificationError)`: 送信失敗
     fn send(&self, notification: &Notification) -> Result<()>;
 
+    /// 通知を送信し、バックエンドが割り当てた ID を返す
+    ///
+    /// ID は `Notification::replace_id` に指定すると、その通知を置き換えられます。
+    /// 置き換えに対応していないバックエンドは、デフォルト実装で `None` を返します。
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        self.send(notification).map(|()| None)
+    }
+
     /// この Notifier が現在のプラットフォームで利用可能か
     ///
     /// 例: `LinuxNotifier` は Linux でのみ利用可能
@@ -68,6 +194,220 @@ pub trait Notifier {
 
     /// バックエンド名を返す（ログ/デバッグ用）
     fn backend_name(&self) -> &'static str;
+
+    /// 初回送信を速くするための事前初期化
+    ///
+    /// D-Bus 接続の確立や外部コマンドのパス解決など、時間のかかる準備を
+    /// 先に済ませます。デフォルト実装は何もしません。
+    ///
+    /// # 学習ポイント
+    /// トレイトのメソッドにはデフォルト実装を持たせることができ、
+    /// 実装側は必要なものだけを上書きできます。
+    fn warm_up(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 表示中の通知を ID（`deliver()` の戻り値）で閉じる
+    ///
+    /// 通知を閉じられないバックエンドでは、デフォルト実装がエラーを返します。
+    fn close(&self, _id: &str) -> Result<()> {
+        Err(NotificationError::UnsupportedPlatform(format!(
+            "{} backend cannot close notifications",
+            self.backend_name()
+        )))
+    }
+
+    /// 通知を送らずに、表示するために行う呼び出し（コマンドライン・D-Bus の引数）を返す
+    ///
+    /// `NotificationBuilder::render()` から使います。書き出せないバックエンドでは、
+    /// デフォルト実装がエラーを返します。
+    fn render(&self, _notification: &Notification) -> Result<Invocation> {
+        Err(NotificationError::UnsupportedPlatform(format!(
+            "{} backend cannot render notifications",
+            self.backend_name()
+        )))
+    }
+
+    /// 複数の通知をまとめて送信し、それぞれの結果（`deliver()` の戻り値）を順に返す
+    ///
+    /// デフォルト実装は `deliver()` を 1 件ずつ呼びます（失敗しても残りを送ります）。
+    /// 外部コマンドを 1 回の起動でまとめて実行できるバックエンド（Windows）は上書きします。
+    /// 戻り値は渡した通知と同じ数・同じ順にします。
+    fn send_batch(&self, notifications: &[Notification]) -> Vec<Result<Option<String>>> {
+        notifications
+            .iter()
+            .map(|notification| self.deliver(notification))
+            .collect()
+    }
+
+    /// 通知を送信し、ユーザーが操作するまで待つ
+    ///
+    /// クリック・アクションボタン・閉じる操作・時間切れのどれで
+    /// 通知が消えたかを返します。操作を受け取れないバックエンドでは
+    /// デフォルト実装がエラーを返します。
+    fn send_and_wait(&self, _notification: &Notification) -> Result<Interaction> {
+        Err(NotificationError::UnsupportedPlatform(format!(
+            "{} backend cannot wait for user interaction",
+            self.backend_name()
+        )))
+    }
+
+    /// このバックエンドが対応している機能
+    ///
+    /// 呼び出し側は送る前に確認して、対応していない機能（アクションボタンなど）を
+    /// 使わない通知に切り替えられます。デフォルト実装は何も対応していないと答えます。
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// 通知音の繰り返し（`Notification::sound_escalation()`）を OS 側で行えるかどうか
+    ///
+    /// `false`（デフォルト）の場合、`NotificationBuilder::send_and_wait()` が
+    /// 一定間隔で通知を送り直して音を鳴らし直します。
+    fn repeats_sound_natively(&self) -> bool {
+        false
+    }
+
+    /// クリック時に開く URL（`Notification::on_click_url`）を OS 側で開けるかどうか
+    ///
+    /// `false`（デフォルト）の場合、`NotificationBuilder::send_and_wait()` が
+    /// クリックを受け取った後に URL を開きます。
+    fn opens_urls_natively(&self) -> bool {
+        false
+    }
+}
+
+// ============================================================
+// バックエンド指定
+// ============================================================
+
+/// 通知の送信に使うバックエンド
+///
+/// `Platform` は「どの環境で動いているか」を表すのに対し、
+/// `Backend` は「どうやって通知を届けるか」を表します。
+/// プラットフォーム標準の通知以外（ダイアログなど）も選べるように分けています。
+///
+/// # 学習ポイント
+/// - データを持つバリアント（`Native(Platform)`）
+/// - `From<Platform>` を実装しているので `.backend(Platform::MacOs)` とも書ける
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// プラットフォーム標準の通知（D-Bus, PowerShell, osascript）
+    Native(Platform),
+    /// モーダルダイアログ（zenity/kdialog, MessageBox, display dialog）
+    Dialog,
+    /// 標準エラー出力への枠付き表示
+    Console,
+    /// 標準出力への JSON 出力（スクリプト連携用、何も表示しない）
+    Stdout,
+    /// `register_backend()` で登録したバックエンド（登録した名前）
+    Custom(String),
+}
+
+impl Backend {
+    /// ネイティブバックエンドの場合、対象プラットフォームを返す
+    pub fn platform(&self) -> Option<Platform> {
+        match self {
+            Self::Native(platform) => Some(*platform),
+            Self::Dialog | Self::Console | Self::Stdout | Self::Custom(_) => None,
+        }
+    }
+
+    /// 機械可読な短い名前（CLI の `--backend` の値と同じ、登録したバックエンドは `"custom"`）
+    ///
+    /// 登録したバックエンドの名前は `custom_name()` で取得します。
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Native(Platform::Linux) => "linux",
+            Self::Native(Platform::Wsl) => "wsl",
+            Self::Native(Platform::Windows) => "windows",
+            Self::Native(Platform::MacOs) => "macos",
+            Self::Native(Platform::Unknown) => "unknown",
+            Self::Dialog => "dialog",
+            Self::Console => "console",
+            Self::Stdout => "stdout",
+            Self::Custom(_) => "custom",
+        }
+    }
+
+    /// `register_backend()` で登録した名前（`Custom` 以外は `None`）
+    pub fn custom_name(&self) -> Option<&str> {
+        match self {
+            Self::Custom(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// 設定ファイル・JSON・登録簿で使う名前（`Custom` は登録した名前、それ以外は `name()`）
+    pub(crate) fn key(&self) -> &str {
+        self.custom_name().unwrap_or(self.name())
+    }
+}
+
+/// `Platform` から `Backend` への変換
+///
+/// プラットフォームを指定した場合は、そのネイティブ通知を使います。
+impl From<Platform> for Backend {
+    fn from(platform: Platform) -> Self {
+        Self::Native(platform)
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Native(platform) => write!(f, "{}", platform),
+            Self::Dialog => write!(f, "Dialog"),
+            Self::Console => write!(f, "Console"),
+            Self::Stdout => write!(f, "Stdout (JSON)"),
+            Self::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// `Backend::key()` の名前（登録したバックエンドはその名前）から `Backend` への変換
+///
+/// 組み込み以外の名前は `Backend::Custom` にします。登録されているかは送信時に確かめるので、
+/// 同じ文字列はプラグインの読み込みの前後に関わらず同じバックエンドになります。
+impl FromStr for Backend {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "linux" => Ok(Self::Native(Platform::Linux)),
+            "wsl" => Ok(Self::Native(Platform::Wsl)),
+            "windows" => Ok(Self::Native(Platform::Windows)),
+            "macos" => Ok(Self::Native(Platform::MacOs)),
+            "unknown" => Ok(Self::Native(Platform::Unknown)),
+            "dialog" => Ok(Self::Dialog),
+            "console" => Ok(Self::Console),
+            "stdout" => Ok(Self::Stdout),
+            "" => Err(NotificationError::Other("empty backend name".to_string())),
+            other => Ok(Self::Custom(other.to_string())),
+        }
+    }
+}
+
+/// `Backend::key()` の名前（`"dialog"` など、登録したバックエンドはその名前）として書き出す
+#[cfg(feature = "serde")]
+impl serde::Serialize for Backend {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+/// `Backend::key()` の名前から読み込む
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Backend {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 // ============================================================
@@ -78,6 +418,11 @@ pub trait Notifier {
 ///
 /// CLI引数としても使用するため、`clap::ValueEnum` を derive しています。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum UrgencyLevel {
     /// 低: 緊急性の低い通知（バックグラウンド処理完了など）
     Low,
@@ -88,6 +433,235 @@ pub enum UrgencyLevel {
     Critical,
 }
 
+impl UrgencyLevel {
+    /// 小文字の名前（CLI の `--urgency` の値と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// `UrgencyLevel::as_str()` の名前から `UrgencyLevel` への変換
+impl FromStr for UrgencyLevel {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "critical" => Ok(Self::Critical),
+            other => Err(NotificationError::Other(format!(
+                "unknown urgency: {other}"
+            ))),
+        }
+    }
+}
+
+// ============================================================
+// シナリオ
+// ============================================================
+
+/// 通知の用途（アラーム・リマインダー・着信）
+///
+/// 指定すると、表示時間を指定しない限りユーザーが閉じるまで表示し続けます。
+/// - Windows: トーストの `scenario`（閉じるボタン付きで、画面に残り続ける）
+/// - macOS: alerter のアラート（操作されるまで表示、alerter が必要）
+/// - その他: 閉じるまで表示（タイムアウト 0）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Scenario {
+    /// アラーム（タイマーの終了など）
+    Alarm,
+    /// リマインダー（予定の通知など）
+    Reminder,
+    /// 着信
+    IncomingCall,
+}
+
+impl Scenario {
+    /// ケバブケースの名前（CLI の `--scenario` の値と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alarm => "alarm",
+            Self::Reminder => "reminder",
+            Self::IncomingCall => "incoming-call",
+        }
+    }
+}
+
+/// `Scenario::as_str()` の名前から `Scenario` への変換
+impl FromStr for Scenario {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "alarm" => Ok(Self::Alarm),
+            "reminder" => Ok(Self::Reminder),
+            "incoming-call" => Ok(Self::IncomingCall),
+            other => Err(NotificationError::Other(format!(
+                "unknown scenario: {other}"
+            ))),
+        }
+    }
+}
+
+// ============================================================
+// プラットフォームごとの上書き
+// ============================================================
+
+/// 特定のプラットフォームで送る時だけ Builder に適用する変更
+///
+/// `NotificationBuilder::platform()` で追加します。
+/// 関数を `Arc` で持つため、Builder ごと `clone()` できます。
+#[derive(Clone)]
+struct PlatformOverride {
+    platform: Platform,
+    apply: Arc<dyn Fn(NotificationBuilder) -> NotificationBuilder + Send + Sync>,
+}
+
+impl PlatformOverride {
+    /// 送信先のプラットフォームに適用するかどうか
+    ///
+    /// WSL は Windows の通知を使うため、`Platform::Windows` の上書きも適用します。
+    fn applies_to(&self, target: Platform) -> bool {
+        self.platform == target || (self.platform == Platform::Windows && target == Platform::Wsl)
+    }
+}
+
+/// 関数は表示できないので、対象のプラットフォームだけを表示する
+impl fmt::Debug for PlatformOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlatformOverride")
+            .field("platform", &self.platform)
+            .finish_non_exhaustive()
+    }
+}
+
+// ============================================================
+// 表示時間
+// ============================================================
+
+/// 通知の表示時間（`NotificationBuilder::timeout()` の引数、ミリ秒で持つ）
+///
+/// `Duration` から作ります（`Duration::ZERO` = ユーザーが閉じるまで表示）。
+/// ミリ秒の `u32` からも作れますが、`timeout(5000)` と書いていた既存のコードのためだけに残しています。
+/// トレイトの実装には `#[deprecated]` を付けられない（警告が出ない）ため、非推奨はこの説明で示します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout(u32);
+
+impl Timeout {
+    /// ミリ秒
+    pub fn as_millis(self) -> u32 {
+        self.0
+    }
+}
+
+/// ミリ秒未満は切り捨て、約 49 日を超える値は上限に丸めます。
+impl From<Duration> for Timeout {
+    fn from(timeout: Duration) -> Self {
+        Self(u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX))
+    }
+}
+
+/// ミリ秒（非推奨: `Duration` を使ってください）
+impl From<u32> for Timeout {
+    fn from(millis: u32) -> Self {
+        Self(millis)
+    }
+}
+
+// ============================================================
+// アクションボタン
+// ============================================================
+
+/// 通知に表示するアクションボタン
+///
+/// ボタンを表示できないバックエンド（osascript, コンソール等）では無視されます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Action {
+    /// アクションの識別子（ボタンが押された時にアプリ側で判別するための値）
+    pub id: String,
+    /// ボタンに表示するラベル
+    pub label: String,
+}
+
+impl Action {
+    /// 新しいアクションを作成
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+        }
+    }
+}
+
+// ============================================================
+// ユーザーの操作
+// ============================================================
+
+/// 通知に対するユーザーの操作（`send_and_wait()` の結果）
+///
+/// `Display` / `FromStr` で `clicked` / `action:<ID>` / `reply:<テキスト>` /
+/// `dismissed` / `expired` の文字列と相互変換できます（CLI の `--wait` の出力と同じ形式）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Interaction {
+    /// 通知本体がクリックされた
+    Clicked,
+    /// アクションボタンが押された（アクション ID）
+    ActionInvoked(String),
+    /// 返信欄に入力して送信された（入力されたテキスト）
+    Replied(String),
+    /// ユーザーが閉じた
+    Dismissed,
+    /// 操作されないまま表示時間が過ぎた
+    Expired,
+}
+
+impl fmt::Display for Interaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clicked => write!(f, "clicked"),
+            Self::ActionInvoked(id) => write!(f, "action:{}", id),
+            Self::Replied(text) => write!(f, "reply:{}", text),
+            Self::Dismissed => write!(f, "dismissed"),
+            Self::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+/// 文字列から `Interaction` への変換
+///
+/// # 学習ポイント
+/// `FromStr` を実装すると `"clicked".parse::<Interaction>()` と書けます。
+impl FromStr for Interaction {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "clicked" => Ok(Self::Clicked),
+            "dismissed" => Ok(Self::Dismissed),
+            "expired" => Ok(Self::Expired),
+            other => other
+                .strip_prefix("action:")
+                .map(|id| Self::ActionInvoked(id.to_string()))
+                .or_else(|| {
+                    other
+                        .strip_prefix("reply:")
+                        .map(|text| Self::Replied(text.to_string()))
+                })
+                .ok_or_else(|| NotificationError::Other(format!("unknown interaction: {other}"))),
+        }
+    }
+}
+
 // ============================================================
 // Notification 構造体
 // ============================================================
@@ -95,7 +669,26 @@ pub enum UrgencyLevel {
 /// 通知の内容を表す構造体
 ///
 /// Builder パターンで構築され、各 `Notifier` 実装に渡されます。
+///
+/// `serde` フィーチャーを有効にすると `Serialize` / `Deserialize` を実装します
+/// （保存やソケット越しの受け渡し用）。読み込み時に無いフィールドは
+/// `Notification::default()` の値になります。
+/// Builder は関数（`platform()` の上書き）を持つため、`build()` してから書き出してください。
+///
+/// 項目は今後も増えるため `#[non_exhaustive]` にしています。クレートの外では構造体リテラルで
+/// 作れないので、`NotificationBuilder` で構築し、`set_subtitle()` などのセッターで変更してください。
+/// 省略できる項目（サブタイトル・通知音・アイコン）は `Option` で、`None` は「指定なし」
+/// （バックエンドの既定）を表します。空文字列の指定とは区別されます。
+///
+/// `Duration` のフィールドは serde の標準の形（`{"secs": 5, "nanos": 0}`）です。
+/// CLI の `--json` と同じ形式が必要な場合は `to_json()` / `NotificationBuilder::from_json()` を使います。
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[non_exhaustive]
 pub struct Notification {
     /// 通知のタイトル
     pub title: String,
@@ -103,187 +696,2883 @@ pub struct Notification {
     pub message: String,
     /// 表示時間（ミリ秒）、0 = 無制限
     pub timeout: u32,
-    /// アイコン名またはパス（Linux のみ）
-    pub icon: String,
+    /// `timeout` を Builder で指定したかどうか（`false` なら緊急度の既定値）
+    pub(crate) timeout_explicit: bool,
+    /// 通知センター / アクションセンターに残る時間（`None` = OS に任せる、Windows のみ）
+    pub expiration: Option<Duration>,
+    /// アイコン（テーマの名前・パス・URL・データ URI、`None` はバックエンドの既定）
+    pub icon: Option<Icon>,
+    /// 通知に表示する画像（絶対パス）
+    pub image: Option<PathBuf>,
     /// 緊急度レベル
     pub urgency: UrgencyLevel,
-    /// サブタイトル（macOS のみ）
-    pub subtitle: String,
-    /// 通知音（macOS のみ）
-    pub sound: String,
+    /// 優先度（0〜10、`Config::priority_routes` での振り分けに使う）
+    pub priority: u8,
+    /// サブタイトル（macOS、Windows ではタイトルと本文の間の 2 行目）
+    pub subtitle: Option<String>,
+    /// 通知音の名前（macOS のシステムサウンド、Windows の `ms-winsoundevent:`）またはファイルパス
+    /// （`None` はバックエンドの既定。macOS の osascript では音を付けない）
+    pub sound: Option<String>,
     /// 強制的に使用するバックエンド（None = 自動検出）
-    pub backend_override: Option<Platform>,
+    pub backend_override: Option<Backend>,
+    /// アクションボタン（表示順）
+    pub actions: Vec<Action>,
+    /// グループのタグ（同じタグの通知は 1 つにまとまる）
+    pub group: Option<String>,
+    /// アクションセンターのトーストコレクション ID（Windows のみ）
+    pub collection: Option<String>,
+    /// 返信欄のプレースホルダー（`Some` なら返信欄を表示、Windows / macOS のみ）
+    pub reply_placeholder: Option<String>,
+    /// 置き換える通知の ID（`NotificationHandle::id()` の値）
+    pub replace_id: Option<String>,
+    /// 送信元プロセスの情報（`Config::capture_sender` が有効な場合など）
+    pub sender: Option<SenderInfo>,
+    /// ユーザーが確認するまで表示し続けるかどうか
+    pub require_ack: bool,
+    /// 確認されるまで通知音を鳴らし直す間隔（重要 + `require_ack` の場合のみ有効）
+    pub repeat_sound: Option<Duration>,
+    /// 文面の言語（`ja_JP` 形式、プリセットの翻訳選択に使ったもの）
+    pub locale: String,
+    /// Markdown で指定された本文の原文（`message` は記号を取り除いたもの）
+    pub markdown: Option<String>,
+    /// 通知の種類（freedesktop の `category`、例: `email.arrived`）
+    pub category: Option<String>,
+    /// 送信元のアプリ名（Linux の app_name、Windows の AppUserModelID、macOS のバンドル ID）
+    pub app_name: Option<String>,
+    /// トピック（`ci/build` など、`Config::topics` のルールに使う）
+    pub topic: Option<String>,
+    /// バックエンドにそのまま渡すヒント（キー → 値）
+    pub hints: BTreeMap<String, String>,
+    /// 表示が終わったら履歴（通知センター）に残さない
+    pub transient: bool,
+    /// 操作されても通知センターから消さない
+    pub resident: bool,
+    /// 本文を見せてよいのは本人が操作している時だけ（ロック画面・他の端末への転送ではタイトルのみ）
+    pub private: bool,
+    /// クリックされた時に開く URL
+    pub on_click_url: Option<String>,
+    /// クリックされた時に実行するコマンド（シェルの構文）
+    pub on_click_exec: Option<String>,
+    /// 通知の出どころ（`via build.sh on ci-01` など、Windows の attribution テキスト）
+    pub attribution: Option<String>,
+    /// 通知の用途（アラーム・リマインダー・着信）
+    pub scenario: Option<Scenario>,
+    /// 通知音を鳴らさない
+    pub silent: bool,
+    /// 通知音を閉じるまで繰り返す（Windows のみ）
+    pub loop_sound: bool,
+    /// おやすみモード・集中モード中は表示しない（履歴には未読として残る）
+    pub respect_dnd: bool,
+    /// おやすみモード・集中モードを越えて届ける（`respect_dnd` より優先）
+    pub time_sensitive: bool,
+    /// 通知を送った処理のトレース ID / スパン ID（監視基盤との関連付け）
+    pub trace_context: Option<TraceContext>,
+    /// 外部コマンド（PowerShell, osascript など）の終了を待つ上限（超えたら強制終了する）
+    pub command_timeout: Duration,
+    /// 一時的な失敗（デーモンが応答しないなど）を再試行する方針（フォールバックの前に使う）
+    pub retry: RetryPolicy,
+    /// タイトルや本文がバックエンドの長さの上限を超えた時の扱い（`None` ならそのまま送る）
+    pub overflow: Option<Overflow>,
+    /// 送信に失敗した時に試すバックエンド（試す順、空ならフォールバックしない）
+    pub fallback_chain: Vec<Backend>,
 }
 
-// ============================================================
-// NotificationBuilder（Builder パターン）
-// ============================================================
-
-/// 通知を構築するための Builder
-///
-/// # Builder パターンとは
-/// 複雑なオブジェクトを段階的に構築するデザインパターンです。
-/// 各メソッドが `self` を返すことで、メソッドチェーンが可能になります。
+/// 何も指定しない Builder を、デフォルトの設定（`Config::default()`）で構築した通知
 ///
-/// # 使用例
-/// ```ignore
-/// NotificationBuilder::new()
-///     .title("Hello".to_string())
-///     .message("World".to_string())
-///     .timeout(5000)
-///     .send()?;
-/// ```
-#[derive(Debug, Clone, Default)]
-pub struct NotificationBuilder {
-    title: Option<String>,
-    message: Option<String>,
-    timeout: Option<u32>,
-    icon: Option<String>,
-    urgency: Option<UrgencyLevel>,
-    subtitle: Option<String>,
-    sound: Option<String>,
-    backend: Option<Platform>,
+/// `config::set()` の設定には影響されません。
+impl Default for Notification {
+    fn default() -> Self {
+        NotificationBuilder::new().build_with_config(&Config::default())
+    }
 }
 
-impl NotificationBuilder {
-    /// 新しい Builder を作成
-    pub fn new() -> Self {
-        Self::default()
+/// 構築済みの通知を、全ての項目を指定した Builder に戻す（`Notification::to_builder()`）
+///
+/// 受け取った・読み込んだ通知を一部だけ変えて送り直す場合に使います。
+/// 補完済みの値もそのまま指定されます。ただし表示時間は Builder で指定した場合だけ引き継ぎ、
+/// 緊急度から決まった値は構築し直す時の緊急度で決め直します。
+/// タイトルのホスト名と伏せ字は、構築し直しても重ねて付きません。
+impl From<Notification> for NotificationBuilder {
+    fn from(notification: Notification) -> Self {
+        Self {
+            title: Some(notification.title),
+            message: Some(notification.message),
+            timeout: notification
+                .timeout_explicit
+                .then_some(notification.timeout),
+            expiration: notification.expiration,
+            icon: notification.icon,
+            image: notification.image,
+            urgency: Some(notification.urgency),
+            priority: Some(notification.priority),
+            subtitle: notification.subtitle,
+            sound: notification.sound,
+            backend: notification.backend_override,
+            actions: notification.actions,
+            reply_placeholder: notification.reply_placeholder,
+            group: notification.group,
+            collection: notification.collection,
+            replace_id: notification.replace_id,
+            sender: notification.sender,
+            require_ack: notification.require_ack,
+            repeat_sound: notification.repeat_sound,
+            preset: None,
+            locale: Some(notification.locale),
+            // message はプレーンテキスト版として残す（markdown があれば本文は markdown から表示する）
+            markdown: notification.markdown,
+            category: notification.category,
+            app_name: notification.app_name,
+            topic: notification.topic,
+            hints: notification.hints,
+            transient: notification.transient,
+            resident: notification.resident,
+            private: Some(notification.private),
+            on_click_url: notification.on_click_url,
+            on_click_exec: notification.on_click_exec,
+            attribution: notification.attribution,
+            scenario: notification.scenario,
+            silent: notification.silent,
+            loop_sound: notification.loop_sound,
+            respect_dnd: notification.respect_dnd,
+            time_sensitive: notification.time_sensitive,
+            trace_context: notification.trace_context,
+            command_timeout: Some(notification.command_timeout),
+            retry: Some(notification.retry),
+            overflow: notification.overflow,
+            fallback_chain: Some(notification.fallback_chain),
+            platform_overrides: Vec::new(),
+            state: PhantomData,
+        }
     }
+}
 
-    /// タイトルを設定
+impl Notification {
+    /// Markdown の本文を Pango マークアップに変換したもの（Linux 用）
     ///
-    /// # 学習ポイント
-    /// `mut self` を受け取り `Self` を返すことで、メソッドチェーンを実現
-    pub fn title(mut self, title: impl Into<String>) -> Self {
-        self.title = Some(title.into());
-        self
+    /// `body_markdown()` で指定されていない場合は `None` です。
+    pub fn pango_body(&self) -> Option<String> {
+        self.markdown.as_deref().map(markdown::to_pango)
     }
 
-    /// メッセージを設定
-    pub fn message(mut self, message: impl Into<String>) -> Self {
-        self.message = Some(message.into());
-        self
+    /// タイトル
+    pub fn title(&self) -> &str {
+        &self.title
     }
 
-    /// タイムアウトを設定（ミリ秒）
-    pub fn timeout(mut self, timeout: u32) -> Self {
-        self.timeout = Some(timeout);
-        self
+    /// タイトルを変更
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = title.into();
     }
 
-    /// アイコンを設定（Linux のみ）
-    pub fn icon(mut self, icon: impl Into<String>) -> Self {
-        self.icon = Some(icon.into());
-        self
+    /// 本文（プレーンテキスト）
+    pub fn message(&self) -> &str {
+        &self.message
     }
 
-    /// 緊急度を設定
-    pub fn urgency(mut self, urgency: UrgencyLevel) -> Self {
-        self.urgency = Some(urgency);
-        self
+    /// 本文を変更（Markdown の本文は取り除く）
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = message.into();
+        self.markdown = None;
     }
 
-    /// サブタイトルを設定（macOS のみ）
-    pub fn subtitle(mut self, subtitle: impl Into<String>) -> Self {
-        self.subtitle = Some(subtitle.into());
-        self
+    /// サブタイトル（指定が無ければ `None`）
+    pub fn subtitle(&self) -> Option<&str> {
+        self.subtitle.as_deref()
     }
 
-    /// 通知音を設定（macOS のみ）
-    pub fn sound(mut self, sound: impl Into<String>) -> Self {
-        self.sound = Some(sound.into());
-        self
+    /// サブタイトルを変更（`None` で取り除く）
+    pub fn set_subtitle(&mut self, subtitle: Option<String>) {
+        self.subtitle = subtitle;
     }
 
-    /// 使用するバックエンドを強制指定
-    pub fn backend(mut self, backend: Platform) -> Self {
-        self.backend = Some(backend);
-        self
+    /// 通知音の名前またはファイルパス（指定が無ければ `None`）
+    pub fn sound(&self) -> Option<&str> {
+        self.sound.as_deref()
     }
 
-    /// Notification を構築（送信はしない）
-    pub fn build(self) -> Notification {
-        Notification {
-            title: self.title.unwrap_or_else(|| "Notification".to_string()),
-            message: self.message.unwrap_or_default(),
-            timeout: self.timeout.unwrap_or(5000),
-            icon: self.icon.unwrap_or_else(|| "dialog-information".to_string()),
-            urgency: self.urgency.unwrap_or_default(),
-            subtitle: self.subtitle.unwrap_or_default(),
-            sound: self.sound.unwrap_or_else(|| "default".to_string()),
-            backend_override: self.backend,
-        }
+    /// 通知音を変更（`None` でバックエンドの既定に戻す）
+    pub fn set_sound(&mut self, sound: Option<String>) {
+        self.sound = sound;
     }
 
-    /// Notification を構築して送信
-    ///
-    /// # 処理の流れ
-    /// 1. `build()` で `Notification` を構築
-    /// 2. `select_notifier()` で適切なバックエンドを選択
-    /// 3. `notifier.send()` で送信
-    pub fn send(self) -> Result<()> {
-        let notification = self.build();
-        let notifier = select_notifier(&notification)?;
-
-        // デバッグ情報を出力
-        eprintln!(
-            "(Platform: {}, using {} backend)",
-            notification
-                .backend_override
-                .unwrap_or_else(detect_platform),
-            notifier.backend_name()
-        );
-
-        notifier.send(&notification)
+    /// アイコン（指定が無ければ `None`）
+    pub fn icon(&self) -> Option<&Icon> {
+        self.icon.as_ref()
     }
-}
 
-// ============================================================
-// バックエンド選択ロジック
-// ============================================================
+    /// アイコンを変更（`None` でバックエンドの既定に戻す）
+    pub fn set_icon(&mut self, icon: Option<Icon>) {
+        self.icon = icon;
+    }
 
-/// 適切な Notifier を選択
-///
-/// # 学習ポイント
-/// - `Box<dyn Notifier>`: トレイトオブジェクト（動的ディスパッチ）
-/// - 実行時に具体的な型が決まる場合に使用
-/// - `dyn` は "dynamic" の略
-fn select_notifier(notification: &Notification) -> Result<Box<dyn Notifier>> {
-    // バックエンドの強制指定があればそれを使用、なければ自動検出
-    let platform = notification.backend_override.unwrap_or_else(detect_platform);
+    /// 表示するサブタイトル（指定が無いか空なら `None`）
+    pub(crate) fn visible_subtitle(&self) -> Option<&str> {
+        self.subtitle().filter(|subtitle| !subtitle.is_empty())
+    }
 
-    // プラットフォームに応じた Notifier を作成
-    // Box::new() でヒープに配置し、Box<dyn Notifier> として返す
-    let notifier: Box<dyn Notifier> = match platform {
-        Platform::Linux => Box::new(LinuxNotifier),
-        Platform::Wsl | Platform::Windows => Box::new(WindowsNotifier),
-        Platform::MacOs => Box::new(MacOsNotifier),
-        Platform::Unknown => {
-            return Err(NotificationError::UnsupportedPlatform(
-                "Unknown platform. Use --backend to specify manually.".to_string(),
-            ));
+    /// アイコンがファイルパス（`Icon::Path`）の場合はそのパス
+    ///
+    /// それ以外（テーマの名前・URL・データ URI）と、指定が無い場合は `None` です。
+    pub fn icon_path(&self) -> Option<PathBuf> {
+        match self.icon()? {
+            Icon::Path(path) => Some(path.clone()),
+            _ => None,
         }
-    };
+    }
 
-    // 選択された Notifier が利用可能かチェック
-    if !notifier.is_available() {
-        return Err(NotificationError::UnsupportedPlatform(format!(
-            "{} notifier is not available on this platform",
-            notifier.backend_name()
-        )));
+    /// アイコンを表示するローカルのファイル（テーマの名前は探す）
+    ///
+    /// URL とデータ URI は `with_fetched_icon()` でファイルにするまで `None` です。
+    pub(crate) fn icon_file(&self) -> Option<PathBuf> {
+        self.icon()?.local_file()
     }
 
-    Ok(notifier)
-}
+    /// URL・データ URI のアイコンをファイル（`Icon::Path`）にした通知
+    ///
+    /// 送る直前（各バックエンドの `deliver()` など）にだけ呼び、`render()` では呼びません。
+    /// 取得できなければ警告をログに出し、アイコン無しにします。
+    pub(crate) fn with_fetched_icon(&self) -> Cow<'_, Notification> {
+        let Some(icon @ (Icon::Url(_) | Icon::DataUri(_))) = self.icon() else {
+            return Cow::Borrowed(self);
+        };
+        let fetched = icon.resolve(self.command_timeout).unwrap_or_else(|err| {
+            tracing::warn!("icon {icon} is not available: {err}");
+            None
+        });
+        let mut notification = self.clone();
+        notification.icon = fetched.map(Icon::Path);
+        Cow::Owned(notification)
+    }
 
-// ============================================================
-// テスト
-// ============================================================
+    /// 通知音がファイルパスの場合はそのパス（無音の通知では `None`）
+    pub fn sound_path(&self) -> Option<PathBuf> {
+        self.sound()
+            .filter(|sound| !self.silent && is_path_like(sound))
+            .map(PathBuf::from)
+    }
 
-#[cfg(test)]
+    /// 通知音が名前（システムサウンドなど）の場合はその名前（無音の通知と、指定が無い場合は `None`）
+    pub fn sound_name(&self) -> Option<&str> {
+        self.sound()
+            .filter(|sound| !self.silent && !is_path_like(sound))
+    }
+
+    /// 本文を隠した通知（ロック画面や転送先で表示する内容）
+    ///
+    /// タイトル・アイコン・通知音などはそのままで、本文を `PRIVATE_BODY` に置き換え、
+    /// 本文に関わる画像・サブタイトル・Markdown・返信欄を取り除きます。
+    pub fn public_view(&self) -> Notification {
+        Notification {
+            message: PRIVATE_BODY.to_string(),
+            markdown: None,
+            subtitle: None,
+            image: None,
+            reply_placeholder: None,
+            ..self.clone()
+        }
+    }
+
+    /// `try_build()` の検証（タイトルと本文以外）
+    fn validate(&self) -> Result<()> {
+        if Duration::from_millis(u64::from(self.timeout)) > MAX_TIMEOUT {
+            return Err(invalid_field(
+                "timeout",
+                format!(
+                    "{} ms is longer than {} hours; use timeout 0 to keep it until dismissed",
+                    self.timeout,
+                    MAX_TIMEOUT.as_secs() / 3600
+                ),
+            ));
+        }
+        if let Some(name) = self.sound_name() {
+            if !is_sound_name(name) {
+                return Err(invalid_field(
+                    "sound",
+                    format!("'{name}' is not a sound name or a file path"),
+                ));
+            }
+        }
+        if let Some(path) = self.sound_path().filter(|path| !path.is_file()) {
+            return Err(invalid_field(
+                "sound",
+                format!("{} does not exist", path.display()),
+            ));
+        }
+        if let Some(path) = self.icon_path().filter(|path| !path.is_file()) {
+            return Err(invalid_field(
+                "icon",
+                format!("{} does not exist", path.display()),
+            ));
+        }
+        if let Some(Icon::DataUri(uri)) = self.icon() {
+            icon::decode_data_uri(uri)?;
+        }
+        self.check_click_url()
+    }
+
+    /// おやすみモードのため表示しない通知かどうか（`respect_dnd` かつ `time_sensitive` でない）
+    fn is_held_for_dnd(&self) -> bool {
+        self.respect_dnd && !self.time_sensitive && crate::platform::is_do_not_disturb()
+    }
+
+    /// 通知音のファイルが存在するか確認する
+    ///
+    /// 通知は表示できても音だけ鳴らない、という分かりにくい失敗を避けるため、
+    /// 送信前に確認します。
+    fn check_sound_file(&self) -> Result<()> {
+        match self.sound_path() {
+            Some(path) if !path.is_file() => Err(NotificationError::Other(format!(
+                "sound file not found: {}",
+                path.display()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// クリックで開く URL のスキームを確認する（`http` / `https` / `mailto` のみ）
+    fn check_click_url(&self) -> Result<()> {
+        match &self.on_click_url {
+            Some(url) => click::check_url(url),
+            None => Ok(()),
+        }
+    }
+
+    /// 通知音を繰り返す間隔
+    ///
+    /// ディスクフルのような本当に緊急の通知のみを対象にするため、
+    /// 緊急度が重要で、かつ `require_ack` が有効な場合のみ `Some` を返します。
+    pub fn sound_escalation(&self) -> Option<Duration> {
+        if self.urgency == UrgencyLevel::Critical && self.require_ack {
+            self.repeat_sound
+        } else {
+            None
+        }
+    }
+
+    /// 全ての項目を指定した Builder に戻す（`NotificationBuilder::from(notification)` と同じ）
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{Notification, UrgencyLevel};
+    ///
+    /// let received = Notification::from_json(r#"{"title":"Disk","message":"95% used"}"#)?;
+    /// let escalated = received.to_builder().urgency(UrgencyLevel::Critical).build();
+    /// assert_eq!((escalated.title.as_str(), escalated.urgency), ("Disk", UrgencyLevel::Critical));
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn to_builder(&self) -> NotificationBuilder {
+        self.clone().into()
+    }
+
+    /// JSON から通知を構築する（`NotificationBuilder::from_json()` の `build()`）
+    pub fn from_json(json: &str) -> Result<Self> {
+        NotificationBuilder::from_json(json).map(|builder| builder.build())
+    }
+
+    /// TOML から通知を構築する（`NotificationBuilder::from_toml()` の `build()`）
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        NotificationBuilder::from_toml(toml).map(|builder| builder.build())
+    }
+
+    /// 全フィールドを JSON 文字列（1 行）に変換する
+    ///
+    /// デフォルト値の補完が済んだ「最終的な通知内容」を出力するため、
+    /// 他のツールから rust-toast のデフォルト解決ロジックを利用できます。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let json = NotificationBuilder::new().message("Hi").build().to_json();
+    /// assert!(json.contains(r#""message":"Hi""#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        // json! マクロで serde_json::Value を組み立てる
+        // （エスケープ処理は serde_json に任せる）
+        serde_json::json!({
+            "title": self.title,
+            "message": self.message,
+            "timeout": self.timeout,
+            "expiration_ms": self.expiration.map(|d| d.as_millis() as u64),
+            "icon": self.icon.as_ref().map(Icon::to_string),
+            "image": self.image.as_ref().map(|path| path.display().to_string()),
+            "urgency": self.urgency.as_str(),
+            "priority": self.priority,
+            "subtitle": self.subtitle,
+            "sound": self.sound,
+            "backend": self.backend_override.as_ref().map(Backend::key),
+            "actions": self
+                .actions
+                .iter()
+                .map(|action| serde_json::json!({ "id": action.id, "label": action.label }))
+                .collect::<Vec<_>>(),
+            "reply_placeholder": self.reply_placeholder,
+            "group": self.group,
+            "collection": self.collection,
+            "replace_id": self.replace_id,
+            "sender": self.sender.as_ref().map(SenderInfo::to_json_value),
+            "require_ack": self.require_ack,
+            "repeat_sound_ms": self.repeat_sound.map(|d| d.as_millis() as u64),
+            "locale": self.locale,
+            "markdown": self.markdown,
+            "category": self.category,
+            "app_name": self.app_name,
+            "topic": self.topic,
+            "hints": self.hints,
+            "transient": self.transient,
+            "resident": self.resident,
+            "private": self.private,
+            "on_click_url": self.on_click_url,
+            "on_click_exec": self.on_click_exec,
+            "attribution": self.attribution,
+            "scenario": self.scenario.map(|scenario| scenario.as_str()),
+            "silent": self.silent,
+            "loop_sound": self.loop_sound,
+            "respect_dnd": self.respect_dnd,
+            "time_sensitive": self.time_sensitive,
+            "trace_context": self.trace_context.as_ref().map(TraceContext::to_json_value),
+        })
+        .to_string()
+    }
+}
+
+// ============================================================
+// NotificationBuilder（Builder パターン）
+// ============================================================
+
+/// 通知を構築するための Builder
+///
+/// # Builder パターンとは
+/// 複雑なオブジェクトを段階的に構築するデザインパターンです。
+/// 各メソッドが `self` を返すことで、メソッドチェーンが可能になります。
+///
+/// # 使用例
+/// ```ignore
+/// NotificationBuilder::new()
+///     .title("Hello".to_string())
+///     .message("World".to_string())
+///     .timeout(Duration::from_secs(5))
+///     .send()?;
+/// ```
+///
+/// # 本文の指定をコンパイル時に確認する（型状態）
+/// `NotificationBuilder::typed()` は `NotificationBuilder<Missing>` を返し、
+/// `message()` / `body_markdown()` / `preset()` を呼ぶと `NotificationBuilder<Ready>` になります。
+/// `send()` / `build()` は `Ready` にしか無いため、本文を指定し忘れるとコンパイルエラーになります。
+/// `new()` が返す `NotificationBuilder`（`Dynamic`）は従来どおり実行時に補完します（CLI など）。
+///
+/// ```
+/// use rust_toast::NotificationBuilder;
+///
+/// let notification = NotificationBuilder::typed().title("Build").message("Passed").build();
+/// assert_eq!(notification.message, "Passed");
+/// ```
+///
+/// ```compile_fail
+/// use rust_toast::NotificationBuilder;
+///
+/// // 本文が無いので send() が無い
+/// NotificationBuilder::typed().title("Build").send();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NotificationBuilder<S = Dynamic> {
+    title: Option<String>,
+    message: Option<String>,
+    timeout: Option<u32>,
+    expiration: Option<Duration>,
+    icon: Option<Icon>,
+    image: Option<PathBuf>,
+    urgency: Option<UrgencyLevel>,
+    priority: Option<u8>,
+    subtitle: Option<String>,
+    sound: Option<String>,
+    backend: Option<Backend>,
+    actions: Vec<Action>,
+    reply_placeholder: Option<String>,
+    group: Option<String>,
+    collection: Option<String>,
+    replace_id: Option<String>,
+    sender: Option<SenderInfo>,
+    require_ack: bool,
+    repeat_sound: Option<Duration>,
+    preset: Option<String>,
+    locale: Option<String>,
+    markdown: Option<String>,
+    category: Option<String>,
+    app_name: Option<String>,
+    topic: Option<String>,
+    hints: BTreeMap<String, String>,
+    transient: bool,
+    resident: bool,
+    private: Option<bool>,
+    on_click_url: Option<String>,
+    on_click_exec: Option<String>,
+    attribution: Option<String>,
+    scenario: Option<Scenario>,
+    silent: bool,
+    loop_sound: bool,
+    respect_dnd: bool,
+    time_sensitive: bool,
+    trace_context: Option<TraceContext>,
+    command_timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    overflow: Option<Overflow>,
+    fallback_chain: Option<Vec<Backend>>,
+    platform_overrides: Vec<PlatformOverride>,
+    state: PhantomData<S>,
+}
+
+// ============================================================
+// Builder の型状態
+// ============================================================
+
+/// 本文の有無を実行時に扱う Builder の状態（`NotificationBuilder::new()`、デフォルト）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dynamic;
+
+/// 本文がまだ指定されていない状態（`NotificationBuilder::typed()`）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Missing;
+
+/// 本文が指定され、送信できる状態
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ready;
+
+/// Builder の型状態（`Dynamic` / `Missing` / `Ready`）
+///
+/// 外部で実装されないよう、非公開モジュールのトレイトで封印しています。
+pub trait BuilderState: sealed::Sealed {
+    /// 本文を指定した後の状態
+    type WithMessage;
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Dynamic {}
+    impl Sealed for super::Missing {}
+    impl Sealed for super::Ready {}
+}
+
+impl BuilderState for Dynamic {
+    type WithMessage = Dynamic;
+}
+
+impl BuilderState for Missing {
+    type WithMessage = Ready;
+}
+
+impl BuilderState for Ready {
+    type WithMessage = Ready;
+}
+
+impl NotificationBuilder {
+    /// 新しい Builder を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NotificationBuilder<Missing> {
+    /// 本文の指定をコンパイル時に確認する Builder を作成
+    ///
+    /// `message()` などで本文を指定するまで `send()` / `build()` を呼べません。
+    pub fn typed() -> Self {
+        Self::default()
+    }
+}
+
+impl NotificationBuilder<Ready> {
+    /// Notification を構築（`NotificationBuilder::build()` と同じ）
+    pub fn build(self) -> Notification {
+        self.with_state::<Dynamic>().build()
+    }
+
+    /// Notification を構築し、内容を検証する（`NotificationBuilder::try_build()` と同じ）
+    pub fn try_build(self) -> Result<Notification> {
+        self.with_state::<Dynamic>().try_build()
+    }
+
+    /// Notification を構築して送信（`NotificationBuilder::send()` と同じ）
+    pub fn send(self) -> Result<NotificationHandle> {
+        self.with_state::<Dynamic>().send()
+    }
+
+    /// 送信してユーザーの操作を待つ（`NotificationBuilder::send_and_wait()` と同じ）
+    pub fn send_and_wait(self) -> Result<Interaction> {
+        self.with_state::<Dynamic>().send_and_wait()
+    }
+
+    /// 渡したバックエンドで送信（`NotificationBuilder::send_with()` と同じ）
+    pub fn send_with(self, notifier: &dyn Notifier) -> Result<NotificationHandle> {
+        self.with_state::<Dynamic>().send_with(notifier)
+    }
+
+    /// 複数のバックエンドに送信（`NotificationBuilder::send_to()` と同じ）
+    pub fn send_to<B: Into<Backend> + Clone>(self, backends: &[B]) -> Result<Vec<BackendResult>> {
+        self.with_state::<Dynamic>().send_to(backends)
+    }
+
+    /// 指定した時間の後に送信（`NotificationBuilder::send_after()` と同じ）
+    pub fn send_after(self, delay: Duration) -> Result<()> {
+        self.with_state::<Dynamic>().send_after(delay)
+    }
+
+    /// 指定した時刻に送信（`NotificationBuilder::send_at()` と同じ）
+    pub fn send_at(self, at: SystemTime) -> Result<()> {
+        self.with_state::<Dynamic>().send_at(at)
+    }
+
+    /// 送らずに配送内容を書き出す（`NotificationBuilder::render()` と同じ）
+    pub fn render(self) -> Result<RenderedDelivery> {
+        self.with_state::<Dynamic>().render()
+    }
+
+    /// Notification を構築して非同期に送信（`NotificationBuilder::send_async()` と同じ）
+    #[cfg(feature = "async")]
+    pub async fn send_async(self) -> Result<NotificationHandle> {
+        self.with_state::<Dynamic>().send_async().await
+    }
+
+    /// 実行時に補完する Builder に戻す（`Dynamic` を受け取る API に渡す場合など）
+    pub fn into_dynamic(self) -> NotificationBuilder {
+        self.with_state()
+    }
+}
+
+impl<S: BuilderState> NotificationBuilder<S> {
+    /// 型状態だけを変える（フィールドはそのまま）
+    fn with_state<T>(self) -> NotificationBuilder<T> {
+        NotificationBuilder {
+            title: self.title,
+            message: self.message,
+            timeout: self.timeout,
+            expiration: self.expiration,
+            icon: self.icon,
+            image: self.image,
+            urgency: self.urgency,
+            priority: self.priority,
+            subtitle: self.subtitle,
+            sound: self.sound,
+            backend: self.backend,
+            actions: self.actions,
+            reply_placeholder: self.reply_placeholder,
+            group: self.group,
+            collection: self.collection,
+            replace_id: self.replace_id,
+            sender: self.sender,
+            require_ack: self.require_ack,
+            repeat_sound: self.repeat_sound,
+            preset: self.preset,
+            locale: self.locale,
+            markdown: self.markdown,
+            category: self.category,
+            app_name: self.app_name,
+            topic: self.topic,
+            hints: self.hints,
+            transient: self.transient,
+            resident: self.resident,
+            private: self.private,
+            on_click_url: self.on_click_url,
+            on_click_exec: self.on_click_exec,
+            attribution: self.attribution,
+            scenario: self.scenario,
+            silent: self.silent,
+            loop_sound: self.loop_sound,
+            respect_dnd: self.respect_dnd,
+            time_sensitive: self.time_sensitive,
+            trace_context: self.trace_context,
+            command_timeout: self.command_timeout,
+            retry: self.retry,
+            overflow: self.overflow,
+            fallback_chain: self.fallback_chain,
+            platform_overrides: self.platform_overrides,
+            state: PhantomData,
+        }
+    }
+
+    /// タイトルを設定
+    ///
+    /// # 学習ポイント
+    /// `mut self` を受け取り `Self` を返すことで、メソッドチェーンを実現
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// メッセージを設定
+    pub fn message(mut self, message: impl Into<String>) -> NotificationBuilder<S::WithMessage> {
+        self.message = Some(message.into());
+        self.markdown = None;
+        self.with_state()
+    }
+
+    /// Markdown で書いたメッセージを設定
+    ///
+    /// `**太字**` / `*斜体*` / `` `コード` `` / `[リンク](URL)` / 見出し / 箇条書きに対応し、
+    /// Linux では Pango マークアップ、その他では記号を取り除いたテキストで表示します。
+    /// `message()` と同時に使った場合は、後に呼んだ方が使われます。
+    pub fn body_markdown(
+        mut self,
+        markdown: impl Into<String>,
+    ) -> NotificationBuilder<S::WithMessage> {
+        self.markdown = Some(markdown.into());
+        self.message = None;
+        self.with_state()
+    }
+
+    /// 表示時間を設定（`Duration::ZERO` = ユーザーが閉じるまで表示）
+    ///
+    /// 指定しない場合は、緊急度ごとのデフォルト（`Config`）が使われます。
+    /// 従来のミリ秒の `u32`（`.timeout(5000)`）も受け付けます（非推奨、`Timeout` を参照）。
+    pub fn timeout(mut self, timeout: impl Into<Timeout>) -> Self {
+        self.timeout = Some(timeout.into().as_millis());
+        self
+    }
+
+    /// 通知センター / アクションセンターに残る時間を設定（Windows のみ）
+    pub fn expiration(mut self, expiration: Duration) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// アイコンを設定（文字列は `Icon::parse()` の規則で種類を決める）
+    ///
+    /// - Linux: テーマの名前はそのまま、それ以外はファイルにして渡す
+    /// - Windows: ファイルにして `appLogoOverride` に（WSL では Windows のパスに変換）
+    /// - macOS: ファイルにして `alerter` の `-appIcon` に（alerter がインストールされている場合のみ）
+    ///
+    /// Windows と macOS では、テーマの名前は hicolor テーマのファイルが見つかった時だけ表示します。
+    pub fn icon(mut self, icon: impl Into<Icon>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    /// 通知に表示する画像を設定
+    ///
+    /// - Linux: `image-path` ヒント
+    /// - Windows: トースト上部の大きな画像（hero image）
+    /// - macOS: `alerter` の `-contentImage`（alerter がインストールされている場合のみ）
+    pub fn image(mut self, path: impl Into<PathBuf>) -> Self {
+        self.image = Some(path.into());
+        self
+    }
+
+    /// 緊急度を設定
+    pub fn urgency(mut self, urgency: UrgencyLevel) -> Self {
+        self.urgency = Some(urgency);
+        self
+    }
+
+    /// 優先度（0〜10、10 を超える値は 10）を設定
+    ///
+    /// 緊急度とは独立に、`Config::priority_routes` の振り分け（追加のバックエンド、
+    /// 静かな時間帯に捨てるかどうか）に使います。省略時は緊急度から決まります。
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = Some(priority.min(priority::MAX_PRIORITY));
+        self
+    }
+
+    /// サブタイトルを設定（macOS、Windows ではタイトルと本文の間の 2 行目）
+    pub fn subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// 通知音を設定
+    ///
+    /// 名前（`Glass` など）は macOS のシステムサウンド、`ms-winsoundevent:` で始まるものは
+    /// Windows のシステムサウンドです。ファイルパスは全プラットフォームで使えます。
+    /// - Linux: `sound-file` ヒント
+    /// - Windows: トーストの音を消して `SoundPlayer` で再生（WAV のみ）
+    /// - macOS: `afplay` で再生
+    ///
+    /// ファイルが存在しない場合、`send()` はエラーを返します。
+    pub fn sound(mut self, sound: impl Into<String>) -> Self {
+        self.sound = Some(sound.into());
+        self
+    }
+
+    /// 通知音を鳴らさない（`sound()` より優先）
+    ///
+    /// - Linux: `suppress-sound` ヒント
+    /// - Windows: `<audio silent="true"/>`
+    /// - macOS: `sound name` を付けない
+    pub fn silent(mut self, silent: bool) -> Self {
+        self.silent = silent;
+        self
+    }
+
+    /// 通知音を閉じるまで繰り返す（アラーム向け、`silent()` が優先）
+    ///
+    /// - Windows: `<audio loop="true"/>`（`ms-winsoundevent:Notification.Looping.*` の音、
+    ///   それ以外の音はアラーム音になる）と長い表示
+    /// - その他: 繰り返しはできません（`repeat_sound()` で鳴らし直せます）
+    pub fn loop_sound(mut self, loop_sound: bool) -> Self {
+        self.loop_sound = loop_sound;
+        self
+    }
+
+    /// 特定のプラットフォームで送る時だけ適用する変更を追加
+    ///
+    /// 送信先のプラットフォーム（`backend()` やトピックのルールで指定したもの、
+    /// 無ければ `detect_platform()`）が一致した時に、構築の直前に `apply` を呼びます。
+    /// `Platform::Windows` の変更は WSL でも適用されます。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{NotificationBuilder, Platform};
+    ///
+    /// let notification = NotificationBuilder::new()
+    ///     .message("Build finished")
+    ///     .platform(Platform::MacOs, |b| b.sound("Glass"))
+    ///     .platform(Platform::Windows, |b| b.sound("ms-winsoundevent:Notification.Mail"))
+    ///     .backend(Platform::MacOs)
+    ///     .build();
+    /// assert_eq!(notification.sound(), Some("Glass"));
+    /// ```
+    pub fn platform(
+        mut self,
+        platform: Platform,
+        apply: impl Fn(NotificationBuilder) -> NotificationBuilder + Send + Sync + 'static,
+    ) -> Self {
+        self.platform_overrides.push(PlatformOverride {
+            platform,
+            apply: Arc::new(apply),
+        });
+        self
+    }
+
+    /// 使用するバックエンドを強制指定
+    ///
+    /// `Platform` を渡すとそのプラットフォームのネイティブ通知、
+    /// `Backend::Dialog` を渡すとダイアログ表示になります。
+    pub fn backend(mut self, backend: impl Into<Backend>) -> Self {
+        self.backend = Some(backend.into());
+        self
+    }
+
+    /// アクションボタンを追加
+    ///
+    /// 複数回呼ぶと、呼んだ順にボタンが並びます。
+    /// - Linux: D-Bus のアクション
+    /// - Windows: トーストの `<action>` 要素
+    /// - macOS: `alerter` がインストールされている場合のみ
+    pub fn action(mut self, id: impl Into<String>, label: impl Into<String>) -> Self {
+        self.actions.push(Action::new(id, label));
+        self
+    }
+
+    /// 返信欄（テキスト入力）を追加
+    ///
+    /// 入力されたテキストは `send_and_wait()` の `Interaction::Replied` で受け取れます。
+    /// - Windows: トーストの `<input type="text">` と送信ボタン
+    /// - macOS: `alerter` の `-reply`（アクションボタンとは併用できず、ボタンは表示されません）
+    /// - Linux: 標準の仕様が無いため表示されません
+    pub fn reply_action(mut self, placeholder: impl Into<String>) -> Self {
+        self.reply_placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// グループのタグを設定
+    ///
+    /// 同じタグの通知は積み重ならず、新しいものに置き換わります
+    /// （ビルドスクリプトが 50 回通知しても 1 つにまとまる）。
+    /// - Linux: `x-dunst-stack-tag` ヒント（dunst など対応する通知デーモンのみ）
+    /// - Windows: トーストの Tag（Group は `rust-toast`）
+    /// - macOS: alerter の `-group`（alerter がインストールされている場合のみ）
+    ///
+    /// `replace_id()` が指定されている場合はそちらを優先します。
+    pub fn group(mut self, tag: impl Into<String>) -> Self {
+        self.group = Some(tag.into());
+        self
+    }
+
+    /// アクションセンターのトーストコレクションを設定（Windows のみ）
+    ///
+    /// 同じアプリの通知を、アクションセンターで名前付きの集まり（`ci`、`chat` など）に分けます。
+    /// `group()` と違い、通知は置き換わらずにコレクションの中に積み重なります。
+    /// コレクションを作れない環境（パッケージ ID の無いアプリなど）では通常どおり送ります。
+    /// 他のバックエンドでは無視されます。
+    pub fn collection(mut self, id: impl Into<String>) -> Self {
+        self.collection = Some(id.into());
+        self
+    }
+
+    /// 置き換える通知の ID を設定
+    ///
+    /// 通常は `NotificationHandle::update()` が自動で設定します。
+    /// CLI のように別プロセスから置き換える場合に使います。
+    pub fn replace_id(mut self, id: impl Into<String>) -> Self {
+        self.replace_id = Some(id.into());
+        self
+    }
+
+    /// 送信元プロセスの情報を設定
+    ///
+    /// 指定しない場合、`Config::capture_sender` が有効なら現在のプロセスの情報を使います。
+    pub fn sender(mut self, sender: SenderInfo) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// ユーザーが確認するまで表示し続ける
+    ///
+    /// 表示時間は 0（無制限）になります。
+    pub fn require_ack(mut self) -> Self {
+        self.require_ack = true;
+        self
+    }
+
+    /// 確認されるまで通知音を `interval` ごとに鳴らし直す
+    ///
+    /// 緊急度が重要で `require_ack()` も指定した場合のみ有効です。
+    /// - Windows: ループするアラーム音（`scenario="alarm"`）
+    /// - その他: `send_and_wait()` が時間切れのたびに通知を送り直す
+    pub fn repeat_sound(mut self, interval: Duration) -> Self {
+        self.repeat_sound = Some(interval);
+        self
+    }
+
+    /// 登録済みのプリセット（`Config::presets`）の文面を使う
+    ///
+    /// `title()` / `message()` を指定した場合はそちらが優先されます。
+    /// 登録されていない名前は無視されます。
+    pub fn preset(mut self, name: impl Into<String>) -> NotificationBuilder<S::WithMessage> {
+        self.preset = Some(name.into());
+        self.with_state()
+    }
+
+    /// 文面の言語を指定（`ja`, `pt-BR` など）
+    ///
+    /// 指定しない場合は、送信時に実行ユーザーのロケール（`LANG` など）を使います。
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// 通知の種類を設定（例: `email.arrived`, `im.received`, `transfer.complete`）
+    ///
+    /// - Linux: `category` ヒント（dunst などのカテゴリ別ルールに使われる）
+    /// - Windows: 着信（`call.*`）やリマインダー（`reminder.*`, `calendar.*`）は
+    ///   対応する scenario で表示
+    ///
+    /// 名前は [freedesktop の仕様](https://specifications.freedesktop.org/notification-spec/latest/categories.html)
+    /// に合わせることを推奨します。
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// 送信元のアプリ名を設定
+    ///
+    /// 通知が「PowerShell」や「Script Editor」からのものとして表示されないようにします。
+    ///
+    /// - Linux: D-Bus の `app_name`（通知デーモンのアプリ別ルールにも使われる）
+    /// - Windows: トーストの AppUserModelID（事前に `register_app_id()` で登録が必要）
+    /// - macOS: alerter の `-sender` に渡すバンドル ID（例: `com.apple.Terminal`）
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// トピックを設定（例: `ci/build`, `home/door`）
+    ///
+    /// `Config::topics` に一致するルールがあれば、バックエンドと緊急度の
+    /// デフォルトや送信数の上限が適用されます（`topic` モジュールを参照）。
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// バックエンドにそのまま渡すヒントを追加
+    ///
+    /// 通知デーモン独自の機能（dunst の `fgcolor` など）を、このクレートの対応を待たずに使えます。
+    /// 同じキーを複数回指定した場合は最後の値が使われます。
+    /// - Linux: D-Bus のヒント（`transient` などの既知の名前は正しい型で、
+    ///   整数に見える値は整数で、それ以外は文字列で渡す）
+    /// - Windows: `<toast>` 要素の属性（`launch`, `displayTimestamp` など。
+    ///   XML の名前として使えないキーは無視）
+    /// - macOS: 無視
+    pub fn hint(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.hints.insert(key.into(), value.into());
+        self
+    }
+
+    /// 一時的な通知にする（表示が終わったら通知センターに残さない）
+    ///
+    /// 進捗のような短い状態表示で、履歴が埋まるのを防ぎます。
+    /// `Config::record_history` が有効でも、rust-toast の通知履歴にも記録しません。
+    /// - Linux: `transient` ヒント
+    /// - Windows: ポップアップが消える時刻でアクションセンターから消す
+    /// - macOS: 無視
+    pub fn transient(mut self, transient: bool) -> Self {
+        self.transient = transient;
+        self
+    }
+
+    /// 常駐する通知にする（操作されても、明示的に閉じるまで通知センターに残す）
+    ///
+    /// - Linux: `resident` ヒント
+    /// - Windows: アクションセンターから消える時刻（`expiration()`）を付けない
+    /// - macOS: 無視
+    ///
+    /// `transient(true)` と同時に指定した場合は `transient` が優先されます。
+    pub fn resident(mut self, resident: bool) -> Self {
+        self.resident = resident;
+        self
+    }
+
+    /// 本文を非公開にする（指定しない場合は `Config::private`）
+    ///
+    /// ロック画面や他の端末への転送では、タイトルだけを表示し本文を
+    /// 「New notification」に置き換えます（`Notification::public_view()`）。
+    /// - Linux: 画面がロックされている間は本文を隠して送る
+    /// - Windows: 他の端末へのミラーリング（`NotificationMirroring`）を無効にする
+    ///   （ロック画面での表示は Windows の設定に従う）
+    /// - macOS: 無視（「プレビューを表示」の設定に従う）
+    pub fn private(mut self, private: bool) -> Self {
+        self.private = Some(private);
+        self
+    }
+
+    /// クリックされた時に開く URL を設定（`http` / `https` / `mailto` のみ。他のスキームは送信時にエラー）
+    ///
+    /// - Windows: トーストのプロトコル起動（`activationType="protocol"`）
+    /// - macOS: terminal-notifier の `-open`（インストールされている場合）、
+    ///   無ければ `send_and_wait()` でクリックを受け取った時に `open` で開く
+    /// - Linux: `send_and_wait()` でクリックを受け取った時に `xdg-open` で開く
+    ///   （`send()` では開けません）
+    pub fn on_click_url(mut self, url: impl Into<String>) -> Self {
+        self.on_click_url = Some(url.into());
+        self
+    }
+
+    /// クリックされた時に実行するコマンドを設定（例: `code /var/log/build.log`）
+    ///
+    /// コマンドはシェル（`sh -c`、Windows では `cmd.exe /C`）で実行され、終了は待ちません。
+    /// - macOS: terminal-notifier の `-execute`（インストールされている場合）
+    /// - その他: `send_and_wait()` でクリックを受け取った時に実行する（`send()` では実行されません）
+    pub fn on_click_exec(mut self, command: impl Into<String>) -> Self {
+        self.on_click_exec = Some(command.into());
+        self
+    }
+
+    /// 通知の用途を設定（アラーム・リマインダー・着信）
+    ///
+    /// `timeout()` を指定しない限り、ユーザーが閉じるまで表示し続けます。
+    /// Windows では `category()` から決まる scenario より優先します。
+    pub fn scenario(mut self, scenario: Scenario) -> Self {
+        self.scenario = Some(scenario);
+        self
+    }
+
+    /// おやすみモード・集中モード中は表示しない
+    ///
+    /// 判定は `platform::is_do_not_disturb()`（Linux: dunst / GNOME、macOS: 集中モード）です。
+    /// 表示しなかった通知も、履歴を記録していれば未読として残ります。
+    pub fn respect_dnd(mut self, respect: bool) -> Self {
+        self.respect_dnd = respect;
+        self
+    }
+
+    /// おやすみモード・集中モードを越えて届ける（「時間指定通知」）
+    ///
+    /// `respect_dnd()` を無視し、OS にも割り込みを求めます。
+    /// - Windows: `scenario="urgent"`（重要な通知として集中モードを越える、用途の指定が無い場合）
+    /// - Linux: 緊急度を重要にして送る（GNOME などは重要な通知をおやすみモード中も表示する）
+    /// - macOS: OS への要求はできません（署名済みアプリの権限が必要なため）
+    pub fn time_sensitive(mut self, time_sensitive: bool) -> Self {
+        self.time_sensitive = time_sensitive;
+        self
+    }
+
+    /// 通知を送った処理のトレース ID / スパン ID を添付
+    ///
+    /// 表示には使わず、`to_json()` の `"trace_context"`（履歴・stdout バックエンド）に残ります。
+    /// 障害の通知から監視基盤のトレースを辿るために使います。
+    pub fn trace_context(mut self, context: TraceContext) -> Self {
+        self.trace_context = Some(context);
+        self
+    }
+
+    /// 外部コマンド（PowerShell, osascript など）の終了を待つ上限を設定（デフォルトは `Config::command_timeout`）
+    ///
+    /// 上限を過ぎたコマンドは強制終了し、送信は `NotificationError::Timeout` になります。
+    /// D-Bus（Linux）と、ユーザーの操作を待つ `send_and_wait()` には適用されません。
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = Some(timeout);
+        self
+    }
+
+    /// 一時的な失敗を、間隔を倍にしながら `retries` 回まで再試行する（デフォルトは `Config::retry`）
+    ///
+    /// 再試行するのはタイムアウト・送信の失敗・外部コマンドの起動の失敗だけで、
+    /// 間隔などを変える場合は `retry_policy()` を使います。
+    /// 再試行しても届かなければ、フォールバック（`fallback_chain()`）に進みます。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let notification = NotificationBuilder::new()
+    ///     .message("Deploy finished")
+    ///     .retries(3)
+    ///     .build();
+    /// assert_eq!(notification.retry.retries, 3);
+    /// ```
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retry = Some(RetryPolicy::retries(retries));
+        self
+    }
+
+    /// 再試行の方針（回数・間隔・再試行するエラーの種類）を設定
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// タイトルや本文がバックエンドの長さの上限を超えた時の扱いを設定（デフォルトは `Config::overflow`）
+    ///
+    /// 上限は `Notifier::capabilities()` の `max_title_len` / `max_message_len` です。
+    /// `Overflow::Split` で分けた通知は続けて送り、ハンドルは最初の通知を指します。
+    /// `Overflow::Error` の通知は、購読者への配信・履歴への記録・送信数の数え上げより前に止まります。
+    /// `send()` / `send_to()` / `send_batch()` / `send_and_wait()` / `send_async()` の全てに効きます。
+    ///
+    /// # 例
+    /// ```no_run
+    /// use rust_toast::{NotificationBuilder, Overflow};
+    ///
+    /// NotificationBuilder::new()
+    ///     .title("Test log")
+    ///     .message(std::fs::read_to_string("failures.txt")?)
+    ///     .overflow(Overflow::Split)
+    ///     .send()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    /// 送信に失敗した時に試すバックエンドを、試す順に設定（デフォルトは `Config::fallback_chain`）
+    ///
+    /// 空にするとフォールバックせず、失敗をそのまま返します。
+    /// 利用できないバックエンドと、失敗したバックエンド自身は飛ばします。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{Backend, NotificationBuilder};
+    ///
+    /// let notification = NotificationBuilder::new()
+    ///     .message("Disk almost full")
+    ///     .fallback_chain([Backend::Console])
+    ///     .build();
+    /// assert_eq!(notification.fallback_chain, [Backend::Console]);
+    /// ```
+    pub fn fallback_chain<B: Into<Backend>>(mut self, chain: impl IntoIterator<Item = B>) -> Self {
+        self.fallback_chain = Some(chain.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 通知の出どころを設定（例: `via build.sh on ci-01`）
+    ///
+    /// どのツール・ホストからの通知かを本文とは別に示します。
+    /// - Windows: 本文の下に小さく表示される attribution テキスト
+    /// - その他: 表示されません（コンソールのバナーでは最後の行）
+    pub fn attribution(mut self, text: impl Into<String>) -> Self {
+        self.attribution = Some(text.into());
+        self
+    }
+}
+
+impl NotificationBuilder {
+    /// JSON から Builder を作成
+    ///
+    /// `Notification::to_json()` と同じキーを受け付けます（`null` や未知のキーは無視）。
+    /// Markdown の原文（`"markdown"`）がある場合は `"message"` より優先します。
+    ///
+    /// `"on_click_exec"` はクリックでシェルのコマンドを実行するため、受け付けずに
+    /// `NotificationError::InvalidInput` を返します（他のプログラムやファイルから読んだ JSON に
+    /// コマンドを仕込めないように）。コマンドは `on_click_exec()` で明示して設定してください。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let builder = NotificationBuilder::from_json(r#"{"message":"Hi","urgency":"low"}"#)?;
+    /// assert_eq!(builder.build().timeout, 3000);
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::from_value(&parse_json(json)?, false)
+    }
+
+    /// `on_click_exec` も読む `from_json()`（利用者自身が書いた設定ファイルや、表示するだけの履歴など）
+    pub(crate) fn from_json_with_exec(json: &str) -> Result<Self> {
+        Self::from_value(&parse_json(json)?, true)
+    }
+
+    /// バックエンドが指定されているかどうか
+    pub(crate) fn has_backend(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// JSON のオブジェクト 1 つ、またはオブジェクトの配列から Builder を作成（配列の順）
+    ///
+    /// キーは `from_json()` と同じです（`"on_click_exec"` はエラー）。他のプログラムから複数の通知を
+    /// まとめて渡す場合に使います（CLI の `rust-toast send --json`）。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let builders = NotificationBuilder::from_json_many(r#"[{"message":"a"},{"message":"b"}]"#)?;
+    /// assert_eq!(builders.len(), 2);
+    /// assert_eq!(NotificationBuilder::from_json_many(r#"{"message":"a"}"#)?.len(), 1);
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn from_json_many(json: &str) -> Result<Vec<Self>> {
+        Self::from_json_many_inner(json, false)
+    }
+
+    /// `on_click_exec` も読む `from_json_many()`（CLI の `--json` と `--allow-exec`）
+    pub(crate) fn from_json_many_with_exec(json: &str) -> Result<Vec<Self>> {
+        Self::from_json_many_inner(json, true)
+    }
+
+    fn from_json_many_inner(json: &str, allow_exec: bool) -> Result<Vec<Self>> {
+        match parse_json(json)? {
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(|item| Self::from_value(item, allow_exec))
+                .collect(),
+            value => Ok(vec![Self::from_value(&value, allow_exec)?]),
+        }
+    }
+
+    /// TOML から Builder を作成
+    ///
+    /// キーは `from_json()` と同じです（TOML の表を JSON のオブジェクトとして読みます。`"on_click_exec"` はエラー）。
+    /// ファイルに書いた通知の定義を読み込み、一部をプログラムで上書きする場合に使います。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{NotificationBuilder, UrgencyLevel};
+    ///
+    /// let builder = NotificationBuilder::from_toml(
+    ///     r#"
+    ///     title = "Nightly build"
+    ///     urgency = "critical"
+    ///     actions = [{ id = "open", label = "Open log" }]
+    ///     "#,
+    /// )?;
+    /// let notification = builder.message("Failed at step 3").build();
+    /// assert_eq!(notification.urgency, UrgencyLevel::Critical);
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let document: toml_edit::DocumentMut = toml
+            .parse()
+            .map_err(|e| NotificationError::Other(format!("invalid notification TOML: {e}")))?;
+        Self::from_value(&rules::item_to_json(document.as_item()), false)
+    }
+
+    /// 通知の内容を Builder に重ねる
+    ///
+    /// `other` のうち `Notification::default()` と異なる項目だけで上書きします
+    /// （アクションは置き換え、ヒントは追加、フラグは有効なものだけ）。
+    /// `other` は構築済みのため、緊急度から決まった表示時間のような補完済みの値も重ねます。
+    /// ファイルから読んだ定義を、別の定義やプログラムの値と組み合わせる場合に使います。
+    /// `merge()` の後に呼んだセッターはさらに上書きします。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{Notification, NotificationBuilder, UrgencyLevel};
+    ///
+    /// let alert = Notification::from_toml(r#"urgency = "critical""#)?;
+    /// let notification = NotificationBuilder::new()
+    ///     .title("Disk")
+    ///     .merge(&alert)
+    ///     .message("95% used")
+    ///     .build();
+    /// assert_eq!((notification.title.as_str(), notification.urgency), ("Disk", UrgencyLevel::Critical));
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn merge(mut self, other: &Notification) -> Self {
+        fn changed<T: PartialEq + Clone>(value: &T, base: &T) -> Option<T> {
+            (value != base).then(|| value.clone())
+        }
+        let base = Notification::default();
+
+        self.title = changed(&other.title, &base.title).or(self.title);
+        self.message = changed(&other.message, &base.message).or(self.message);
+        self.timeout = changed(&other.timeout, &base.timeout).or(self.timeout);
+        self.expiration = other.expiration.or(self.expiration);
+        self.icon = other.icon.clone().or(self.icon);
+        self.image = other.image.clone().or(self.image);
+        self.urgency = changed(&other.urgency, &base.urgency).or(self.urgency);
+        self.priority = changed(&other.priority, &base.priority).or(self.priority);
+        self.subtitle = other.subtitle.clone().or(self.subtitle);
+        self.sound = other.sound.clone().or(self.sound);
+        self.backend = other.backend_override.clone().or(self.backend);
+        if !other.actions.is_empty() {
+            self.actions = other.actions.clone();
+        }
+        self.reply_placeholder = other.reply_placeholder.clone().or(self.reply_placeholder);
+        self.group = other.group.clone().or(self.group);
+        self.collection = other.collection.clone().or(self.collection);
+        self.replace_id = other.replace_id.clone().or(self.replace_id);
+        self.sender = other.sender.clone().or(self.sender);
+        self.require_ack |= other.require_ack;
+        self.repeat_sound = other.repeat_sound.or(self.repeat_sound);
+        self.locale = changed(&other.locale, &base.locale).or(self.locale);
+        self.markdown = other.markdown.clone().or(self.markdown);
+        self.category = other.category.clone().or(self.category);
+        self.app_name = other.app_name.clone().or(self.app_name);
+        self.topic = other.topic.clone().or(self.topic);
+        self.hints
+            .extend(other.hints.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.transient |= other.transient;
+        self.resident |= other.resident;
+        self.private = changed(&other.private, &base.private).or(self.private);
+        self.on_click_url = other.on_click_url.clone().or(self.on_click_url);
+        self.on_click_exec = other.on_click_exec.clone().or(self.on_click_exec);
+        self.attribution = other.attribution.clone().or(self.attribution);
+        self.scenario = other.scenario.or(self.scenario);
+        self.silent |= other.silent;
+        self.loop_sound |= other.loop_sound;
+        self.respect_dnd |= other.respect_dnd;
+        self.time_sensitive |= other.time_sensitive;
+        self.trace_context = other.trace_context.or(self.trace_context);
+        self.command_timeout =
+            changed(&other.command_timeout, &base.command_timeout).or(self.command_timeout);
+        self.retry = changed(&other.retry, &base.retry).or(self.retry);
+        self.overflow = other.overflow.or(self.overflow);
+        self.fallback_chain =
+            changed(&other.fallback_chain, &base.fallback_chain).or(self.fallback_chain);
+        self
+    }
+
+    /// 指定していない項目を `defaults` の項目で埋める
+    ///
+    /// `merge()` と違って構築前の Builder どうしを重ねるため、既定値と同じ値を明示した項目
+    /// （`urgency(UrgencyLevel::Normal)` など）も `self` のものが残ります。
+    /// 本文は `message` と `markdown` をまとめて扱い、アクションは `self` に無ければ `defaults` のもの、
+    /// ヒントは両方（同じキーは `self`）、フラグはどちらかで有効なら有効です。
+    /// プラットフォームごとの変更は `defaults` のものを先に適用します。
+    pub(crate) fn or_defaults(mut self, defaults: NotificationBuilder) -> Self {
+        self.title = self.title.or(defaults.title);
+        if self.message.is_none() && self.markdown.is_none() {
+            self.message = defaults.message;
+            self.markdown = defaults.markdown;
+        }
+        self.timeout = self.timeout.or(defaults.timeout);
+        self.expiration = self.expiration.or(defaults.expiration);
+        self.icon = self.icon.or(defaults.icon);
+        self.image = self.image.or(defaults.image);
+        self.urgency = self.urgency.or(defaults.urgency);
+        self.priority = self.priority.or(defaults.priority);
+        self.subtitle = self.subtitle.or(defaults.subtitle);
+        self.sound = self.sound.or(defaults.sound);
+        self.backend = self.backend.or(defaults.backend);
+        if self.actions.is_empty() {
+            self.actions = defaults.actions;
+        }
+        self.reply_placeholder = self.reply_placeholder.or(defaults.reply_placeholder);
+        self.group = self.group.or(defaults.group);
+        self.collection = self.collection.or(defaults.collection);
+        self.replace_id = self.replace_id.or(defaults.replace_id);
+        self.sender = self.sender.or(defaults.sender);
+        self.require_ack |= defaults.require_ack;
+        self.repeat_sound = self.repeat_sound.or(defaults.repeat_sound);
+        self.preset = self.preset.or(defaults.preset);
+        self.locale = self.locale.or(defaults.locale);
+        self.category = self.category.or(defaults.category);
+        self.app_name = self.app_name.or(defaults.app_name);
+        self.topic = self.topic.or(defaults.topic);
+        let mut hints = defaults.hints;
+        hints.append(&mut self.hints);
+        self.hints = hints;
+        self.transient |= defaults.transient;
+        self.resident |= defaults.resident;
+        self.private = self.private.or(defaults.private);
+        self.on_click_url = self.on_click_url.or(defaults.on_click_url);
+        self.on_click_exec = self.on_click_exec.or(defaults.on_click_exec);
+        self.attribution = self.attribution.or(defaults.attribution);
+        self.scenario = self.scenario.or(defaults.scenario);
+        self.silent |= defaults.silent;
+        self.loop_sound |= defaults.loop_sound;
+        self.respect_dnd |= defaults.respect_dnd;
+        self.time_sensitive |= defaults.time_sensitive;
+        self.trace_context = self.trace_context.or(defaults.trace_context);
+        self.command_timeout = self.command_timeout.or(defaults.command_timeout);
+        self.retry = self.retry.or(defaults.retry);
+        self.overflow = self.overflow.or(defaults.overflow);
+        self.fallback_chain = self.fallback_chain.or(defaults.fallback_chain);
+        let mut platform_overrides = defaults.platform_overrides;
+        platform_overrides.append(&mut self.platform_overrides);
+        self.platform_overrides = platform_overrides;
+        self
+    }
+
+    /// テンプレートのプレースホルダーを `vars` などで置き換えた Builder を作成
+    ///
+    /// 置き換えの規則は `template` モジュールを参照してください。
+    /// 知らないプレースホルダーがあれば `NotificationError::InvalidInput` を返します。
+    pub fn from_template(
+        template: &NotificationTemplate,
+        vars: &BTreeMap<String, String>,
+    ) -> Result<Self> {
+        let mut builder = Self::new().message(template::render(&template.message, vars)?);
+        if let Some(title) = &template.title {
+            builder = builder.title(template::render(title, vars)?);
+        }
+        Ok(builder)
+    }
+
+    /// JSON の値から Builder を作成（`from_json()` / `from_toml()` の共通部分）
+    ///
+    /// 読むキーは `JSON_KEYS` で、それ以外のキーは無視します。
+    /// `allow_exec` が `false` なら、`"on_click_exec"` があればエラーにします。
+    fn from_value(value: &serde_json::Value, allow_exec: bool) -> Result<Self> {
+        let invalid = |reason: String| {
+            NotificationError::Other(format!("invalid notification JSON: {reason}"))
+        };
+        let object = value
+            .as_object()
+            .ok_or_else(|| invalid("expected an object".to_string()))?;
+        let text = |key: &str| object.get(key).and_then(serde_json::Value::as_str);
+        let number = |key: &str| object.get(key).and_then(serde_json::Value::as_u64);
+
+        let mut builder = Self::new();
+        if let Some(title) = text("title") {
+            builder = builder.title(title);
+        }
+        if let Some(message) = text("message") {
+            builder = builder.message(message);
+        }
+        if let Some(markdown) = text("markdown") {
+            builder = builder.body_markdown(markdown);
+        }
+        if let Some(timeout) = number("timeout") {
+            let timeout = u32::try_from(timeout).map_err(|e| invalid(format!("timeout: {e}")))?;
+            builder.timeout = Some(timeout);
+        }
+        if let Some(ms) = number("expiration_ms") {
+            builder = builder.expiration(Duration::from_millis(ms));
+        }
+        if let Some(icon) = text("icon") {
+            builder = builder.icon(icon);
+        }
+        if let Some(image) = text("image") {
+            builder = builder.image(image);
+        }
+        if let Some(urgency) = text("urgency") {
+            builder = builder.urgency(urgency.parse()?);
+        }
+        if let Some(priority) = number("priority") {
+            builder = builder.priority(priority.min(u64::from(priority::MAX_PRIORITY)) as u8);
+        }
+        if let Some(subtitle) = text("subtitle") {
+            builder = builder.subtitle(subtitle);
+        }
+        if let Some(sound) = text("sound") {
+            builder = builder.sound(sound);
+        }
+        if let Some(backend) = text("backend") {
+            builder = builder.backend(backend.parse::<Backend>()?);
+        }
+        for action in object
+            .get("actions")
+            .and_then(serde_json::Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let id = action.get("id").and_then(serde_json::Value::as_str);
+            let label = action.get("label").and_then(serde_json::Value::as_str);
+            match (id, label) {
+                (Some(id), Some(label)) => builder = builder.action(id, label),
+                (Some(id), None) => builder = builder.action(id, id),
+                _ => return Err(invalid("action without id".to_string())),
+            }
+        }
+        if let Some(placeholder) = text("reply_placeholder") {
+            builder = builder.reply_action(placeholder);
+        }
+        if let Some(group) = text("group") {
+            builder = builder.group(group);
+        }
+        if let Some(collection) = text("collection") {
+            builder = builder.collection(collection);
+        }
+        if let Some(id) = text("replace_id") {
+            builder = builder.replace_id(id);
+        }
+        if object
+            .get("require_ack")
+            .and_then(serde_json::Value::as_bool)
+            == Some(true)
+        {
+            builder = builder.require_ack();
+        }
+        if let Some(ms) = number("repeat_sound_ms") {
+            builder = builder.repeat_sound(Duration::from_millis(ms));
+        }
+        if let Some(preset) = text("preset") {
+            builder = builder.preset(preset);
+        }
+        if let Some(locale) = text("locale") {
+            builder = builder.locale(locale);
+        }
+        if let Some(category) = text("category") {
+            builder = builder.category(category);
+        }
+        if let Some(app_name) = text("app_name") {
+            builder = builder.app_name(app_name);
+        }
+        if let Some(topic) = text("topic") {
+            builder = builder.topic(topic);
+        }
+        if let Some(url) = text("on_click_url") {
+            builder = builder.on_click_url(url);
+        }
+        if let Some(command) = text("on_click_exec") {
+            if !allow_exec {
+                return Err(invalid_field(
+                    "on_click_exec",
+                    "commands are not read from JSON or TOML; set them with on_click_exec() (CLI: --allow-exec)",
+                ));
+            }
+            builder = builder.on_click_exec(command);
+        }
+        if let Some(attribution) = text("attribution") {
+            builder = builder.attribution(attribution);
+        }
+        if let Some(scenario) = text("scenario") {
+            builder = builder.scenario(scenario.parse()?);
+        }
+        let flag = |key: &str| object.get(key).and_then(serde_json::Value::as_bool);
+        if let Some(silent) = flag("silent") {
+            builder = builder.silent(silent);
+        }
+        if let Some(loop_sound) = flag("loop_sound") {
+            builder = builder.loop_sound(loop_sound);
+        }
+        if let Some(respect) = flag("respect_dnd") {
+            builder = builder.respect_dnd(respect);
+        }
+        if let Some(time_sensitive) = flag("time_sensitive") {
+            builder = builder.time_sensitive(time_sensitive);
+        }
+        if let Some(context) = object.get("trace_context").filter(|v| !v.is_null()) {
+            let id = |key: &str| context.get(key).and_then(serde_json::Value::as_str);
+            let context = TraceContext::from_hex(
+                id("trace_id").unwrap_or_default(),
+                id("span_id").unwrap_or_default(),
+            )?;
+            builder = builder.trace_context(context);
+        }
+        if let Some(transient) = flag("transient") {
+            builder = builder.transient(transient);
+        }
+        if let Some(resident) = flag("resident") {
+            builder = builder.resident(resident);
+        }
+        if let Some(private) = flag("private") {
+            builder = builder.private(private);
+        }
+        for (key, value) in object
+            .get("hints")
+            .and_then(serde_json::Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            // 数値や真偽値も文字列として受け付ける
+            let value = match value {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            builder = builder.hint(key, value);
+        }
+        Ok(builder)
+    }
+
+    /// Notification を構築（送信はしない）
+    ///
+    /// 未指定の項目は `config::current()` の設定で補完します。
+    pub fn build(self) -> Notification {
+        self.build_with_config(&config::current())
+    }
+
+    /// Notification を構築し、内容を検証する（送信はしない）
+    ///
+    /// `build()` は不正な値も黙ってデフォルトや OS に任せますが、こちらは
+    /// 次の場合に `NotificationError::InvalidInput` を返します。
+    /// - タイトルと本文が両方とも空（テンプレートのタイトルだけの通知）
+    /// - 表示時間が `MAX_TIMEOUT` を超える
+    /// - 通知音の名前に使えない文字がある、または通知音のファイルが無い
+    /// - アイコンのファイルが無い
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{NotificationBuilder, NotificationError};
+    ///
+    /// let err = NotificationBuilder::new().try_build().unwrap_err();
+    /// assert!(matches!(err, NotificationError::InvalidInput { .. }));
+    /// ```
+    pub fn try_build(self) -> Result<Notification> {
+        let has_title = self
+            .title
+            .as_deref()
+            .is_some_and(|title| !title.trim().is_empty());
+        let notification = self.build();
+        if !has_title && notification.message.trim().is_empty() {
+            return Err(invalid_field("message", "title and message are both empty"));
+        }
+        notification.validate()?;
+        Ok(notification)
+    }
+
+    /// 指定した設定で補完して Notification を構築
+    ///
+    /// 表示時間と有効期限は、指定が無ければ緊急度ごとのデフォルトを使います。
+    pub(crate) fn build_with_config(self, config: &Config) -> Notification {
+        self.apply_platform_overrides(config).build_resolved(config)
+    }
+
+    /// 送信先のプラットフォームに一致する `platform()` の変更を、追加した順に適用する
+    fn apply_platform_overrides(mut self, config: &Config) -> Self {
+        let overrides = std::mem::take(&mut self.platform_overrides);
+        if overrides.is_empty() {
+            return self;
+        }
+        let target = self.target_platform(config);
+        for platform_override in overrides {
+            if platform_override.applies_to(target) {
+                self = (platform_override.apply)(self);
+            }
+        }
+        // 変更の中で追加された上書きは使わない
+        self.platform_overrides.clear();
+        self
+    }
+
+    /// 送信先のプラットフォーム（バックエンドの指定 → トピックのルール → 自動検出）
+    ///
+    /// ダイアログなどプラットフォームに依存しないバックエンドでは、実行中のプラットフォームです。
+    fn target_platform(&self, config: &Config) -> Platform {
+        let backend = self.backend.clone().or_else(|| {
+            self.topic
+                .as_deref()
+                .and_then(|name| topic::find(&config.topics, name))
+                .and_then(|(_, rule)| rule.backend.clone())
+        });
+        backend
+            .as_ref()
+            .and_then(Backend::platform)
+            .unwrap_or_else(detect_platform)
+    }
+
+    /// `platform()` の変更を適用した後の Builder から Notification を構築
+    fn build_resolved(self, config: &Config) -> Notification {
+        // トピックのルールは、明示されていない項目のデフォルトになる
+        let rule = self
+            .topic
+            .as_deref()
+            .and_then(|name| topic::find(&config.topics, name))
+            .map(|(_, rule)| rule);
+        let urgency = self
+            .urgency
+            .or_else(|| rule.and_then(|rule| rule.urgency))
+            .unwrap_or_default();
+        let defaults = config.urgency_defaults(urgency);
+
+        // ロケールは送信時に決める（共有スクリプトでも実行ユーザーの言語になる）
+        let locale = self
+            .locale
+            .map(|locale| normalize_locale(&locale))
+            .or_else(platform::locale)
+            .unwrap_or_else(|| FALLBACK_LOCALE.to_string());
+        let preset = self
+            .preset
+            .as_ref()
+            .and_then(|name| config.presets.get(name))
+            .and_then(|preset| preset.resolve(&locale));
+        let title = self.title.or_else(|| preset.map(|text| text.title.clone()));
+        let message = self
+            .message
+            .or_else(|| self.markdown.as_deref().map(markdown::to_plain))
+            .or_else(|| preset.map(|text| text.message.clone()));
+
+        // 送信・ログ出力・履歴への記録より前に、機密情報を伏せる
+        let redact = |text: String| redact::apply(&config.redactions, &text).into_owned();
+        let title = title.map(redact);
+        let message = message.map(redact);
+        let markdown = self.markdown.map(redact);
+        let subtitle = self.subtitle.map(redact);
+
+        Notification {
+            title: config.resolve_title(title),
+            message: message.unwrap_or_default(),
+            // 確認を求める通知は、確認されるまで消さない
+            // 確認が必要な通知と、用途（アラームなど）を指定した通知は閉じるまで表示する
+            timeout: if self.require_ack || (self.scenario.is_some() && self.timeout.is_none()) {
+                0
+            } else {
+                self.timeout.unwrap_or(defaults.timeout)
+            },
+            timeout_explicit: self.timeout.is_some(),
+            expiration: self.expiration.or(defaults.expiration),
+            icon: self
+                .icon
+                .or_else(|| config.icon.clone())
+                .map(|icon| match icon {
+                    Icon::Path(path) => Icon::Path(std::path::absolute(&path).unwrap_or(path)),
+                    icon => icon,
+                }),
+            // 通知デーモンや PowerShell は作業ディレクトリが異なるため、絶対パスにしておく
+            image: self
+                .image
+                .map(|path| std::path::absolute(&path).unwrap_or(path)),
+            urgency,
+            priority: self
+                .priority
+                .unwrap_or_else(|| priority::from_urgency(urgency)),
+            subtitle,
+            sound: self
+                .sound
+                .or_else(|| config.sound.clone())
+                .map(absolute_if_path),
+            backend_override: self
+                .backend
+                .or_else(|| rule.and_then(|rule| rule.backend.clone())),
+            actions: self.actions,
+            reply_placeholder: self.reply_placeholder,
+            group: self.group,
+            collection: self.collection,
+            replace_id: self.replace_id,
+            sender: self
+                .sender
+                .or_else(|| config.capture_sender.then(SenderInfo::current)),
+            require_ack: self.require_ack,
+            repeat_sound: self.repeat_sound,
+            locale,
+            markdown,
+            category: self.category,
+            app_name: self.app_name,
+            topic: self.topic,
+            hints: self.hints,
+            transient: self.transient,
+            resident: self.resident && !self.transient,
+            private: self.private.unwrap_or(config.private),
+            on_click_url: self.on_click_url,
+            on_click_exec: self.on_click_exec,
+            attribution: self.attribution,
+            scenario: self.scenario,
+            silent: self.silent,
+            loop_sound: self.loop_sound && !self.silent,
+            respect_dnd: self.respect_dnd,
+            time_sensitive: self.time_sensitive,
+            trace_context: self.trace_context,
+            command_timeout: self.command_timeout.unwrap_or(config.command_timeout),
+            retry: self.retry.unwrap_or_else(|| config.retry.clone()),
+            overflow: self.overflow.or(config.overflow),
+            fallback_chain: self
+                .fallback_chain
+                .unwrap_or_else(|| config.fallback_chain.clone()),
+        }
+    }
+
+    /// Notification を構築して送信
+    ///
+    /// # 処理の流れ
+    /// 1. `build()` で `Notification` を構築
+    /// 2. `select_notifier()` で適切なバックエンドを選択
+    /// 3. `notifier.send()` で送信
+    /// 4. 失敗した場合は `fallback_chain`（デフォルトは Dialog → Console）の順に再送（フォールバック）
+    ///
+    /// 購読者（`subscribe()`）には、送信数の上限などの確認を通過した時点で配信します。
+    ///
+    /// バックエンドごとの同時実行数制限（`set_concurrency_limit`）がある場合は、
+    /// 空きが出るまで送信を待ちます。
+    ///
+    /// 戻り値の `NotificationHandle` で、表示した通知を後から置き換えられます。
+    /// トピックの送信数の上限（`Config::topics`）を超えた場合は
+    /// `NotificationError::RateLimited` を返し、送信しません。
+    ///
+    /// 優先度の振り分け（`Config::priority_routes`）で捨てられた通知は送らず、
+    /// ID の無いハンドルを返します。追加のバックエンドがあれば、そちらにも送ります。
+    ///
+    /// `Config::spool` が有効なら、失敗した通知はフォールバックせずに送信待ちキューに記録し、
+    /// 次に送信できた時に送り直します（`flush_spool()`）。
+    ///
+    /// どのバックエンドが表示したか・かかった時間は `NotificationHandle::report()` で分かります。
+    pub fn send(self) -> Result<NotificationHandle> {
+        self.send_through(None)
+    }
+
+    /// バックエンドを選ばずに、渡した `notifier` で送信する
+    ///
+    /// `register_backend()` で名前を付けずに、その場で作ったバックエンド
+    /// （CLI の `--backend-command` の `CustomCommandNotifier` など）で送る場合に使います。
+    /// 振り分け・履歴・再試行・フォールバックは `send()` と同じです。
+    ///
+    /// # 例
+    /// ```no_run
+    /// use rust_toast::notifier::CustomCommandNotifier;
+    /// use rust_toast::NotificationBuilder;
+    ///
+    /// let herbe = CustomCommandNotifier::new("herbe {title} {message}")?;
+    /// NotificationBuilder::new().message("Build finished").send_with(&herbe)?;
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn send_with(self, notifier: &dyn Notifier) -> Result<NotificationHandle> {
+        self.send_through(Some(notifier))
+    }
+
+    /// `send()` と `send_with()` の本体（`chosen` が無ければバックエンドを選ぶ）
+    fn send_through(self, chosen: Option<&dyn Notifier>) -> Result<NotificationHandle> {
+        let started = clock::current().now();
+        let original = self.build();
+        let config = config::current();
+        let Some(notification) = coalesce(&config, &original) else {
+            return Ok(NotificationHandle::new(None, original));
+        };
+        let selected;
+        let notifier = match chosen {
+            Some(notifier) => notifier,
+            None => {
+                let backend =
+                    avoid_slow_backend(&config, &notification, selected_backend(&notification));
+                selected = notifier_for(backend)?;
+                selected.as_ref()
+            }
+        };
+        let name = notifier.backend_name();
+        // 長さが合わない通知（Overflow::Error）は、購読者・履歴・送信数に残す前に止める
+        let (fitted, rest) = fit_to_backend(&notification, notifier)?;
+        let Some(prepared) = prepare_send(&config, &notification)? else {
+            return Ok(NotificationHandle::new(None, notification));
+        };
+        log_backend(&notification, notifier);
+        send_also(&notification, name, &prepared.route.also);
+
+        let mut report = match deliver_with_retry(notifier, &fitted) {
+            Ok(id) => {
+                record_delivery(prepared.history, Delivery::sent(name, id.clone()));
+                remember_id(&config, &original, id.as_deref());
+                deliver_rest(notifier, &rest);
+                retry_spool(&config);
+                SendReport {
+                    backend: Some(name),
+                    id,
+                    ..SendReport::default()
+                }
+            }
+            // フォールバック先・キューには、長さを合わせる前の通知を渡す
+            Err(err) => {
+                record_delivery(prepared.history, Delivery::failed(name, &err));
+                // JSON 出力はパイプライン用なので、画面表示へのフォールバックはしない
+                if notification.backend_override == Some(Backend::Stdout) {
+                    return Err(err);
+                }
+                let mut report = SendReport::default().failed(name, &err);
+                if spools(&config, &notification) {
+                    spool(&notification, err)?;
+                } else {
+                    // フォールバック先の通知は置き換えられないため、ID は持たない
+                    let fallback = send_fallback(&notification, name, err)?;
+                    record_delivery(prepared.history, Delivery::sent(fallback, None));
+                    remember_id(&config, &original, None);
+                    report.backend = Some(fallback);
+                }
+                report
+            }
+        };
+        report.elapsed = clock::current()
+            .now()
+            .duration_since(started)
+            .unwrap_or_default();
+        Ok(NotificationHandle::new(None, fitted).with_report(report))
+    }
+
+    /// Notification を構築し、指定した全てのバックエンドに送信する（ファンアウト）
+    ///
+    /// 振り分け・送信数の制限・履歴への記録は 1 回だけ行い、各バックエンドに同じ通知を配送します。
+    /// 1 つを選ぶ `send()` と違い、失敗してもフォールバックせず、
+    /// バックエンドごとの結果を渡した順に返します（`Builder::backend()` の指定は無視します）。
+    /// 表示しない通知（静かな時間帯など）は、どのバックエンドにも送らず空を返します。
+    ///
+    /// 利用できる全てのバックエンドに送る場合は `available_backends()` を渡します（CLI の `--backend all`）。
+    ///
+    /// # 例
+    /// ```no_run
+    /// use rust_toast::{NotificationBuilder, Platform};
+    ///
+    /// let results = NotificationBuilder::new()
+    ///     .message("Backup finished")
+    ///     .send_to(&[Platform::Linux, Platform::Windows])?;
+    /// for (backend, result) in &results {
+    ///     if let Err(err) = result {
+    ///         eprintln!("{backend}: {err}");
+    ///     }
+    /// }
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn send_to<B: Into<Backend> + Clone>(self, backends: &[B]) -> Result<Vec<BackendResult>> {
+        let started = clock::current().now();
+        let notification = self.build();
+        let config = config::current();
+        let Some(prepared) = prepare_send(&config, &notification)? else {
+            return Ok(Vec::new());
+        };
+        Ok(backends
+            .iter()
+            .map(|backend| {
+                let backend: Backend = backend.clone().into();
+                // ハンドルの置き換え（update）が同じバックエンドに届くように、送り先を固定する
+                let mut sent = notification.clone();
+                sent.backend_override = Some(backend.clone());
+                let result = notifier_for(backend.clone()).and_then(|notifier| {
+                    log_backend(&sent, notifier.as_ref());
+                    let name = notifier.backend_name();
+                    let result =
+                        fit_to_backend(&sent, notifier.as_ref()).and_then(|(fitted, rest)| {
+                            let id = deliver_with_retry(notifier.as_ref(), &fitted)?;
+                            deliver_rest(notifier.as_ref(), &rest);
+                            sent = fitted;
+                            Ok(id)
+                        });
+                    let delivery = match &result {
+                        Ok(id) => Delivery::sent(name, id.clone()),
+                        Err(err) => Delivery::failed(name, err),
+                    };
+                    record_delivery(prepared.history, delivery);
+                    // 時間は send_to() を呼んでから、このバックエンドの配送が終わるまで
+                    result.map(|id| SendReport {
+                        backend: Some(name),
+                        elapsed: clock::current()
+                            .now()
+                            .duration_since(started)
+                            .unwrap_or_default(),
+                        id,
+                        ..SendReport::default()
+                    })
+                });
+                let handle = |report| NotificationHandle::new(None, sent).with_report(report);
+                (backend, result.map(handle))
+            })
+            .collect())
+    }
+
+    /// 通知を送らずに、バックエンドが使うスクリプト・コマンドライン・D-Bus の引数を返す（ドライラン）
+    ///
+    /// `send()` と同じ `build()` とバックエンドの選択（`backend()` の指定か、プラットフォームのネイティブ）を行いますが、
+    /// 外部コマンドの起動・履歴への記録・送信数の制限・購読者への通知はしません。
+    /// バックエンドが利用できない環境でも書き出せるので、エスケープの確認やゴールデンテストに使えます。
+    ///
+    /// # 例
+    /// ```
+    /// use rust_toast::{Invocation, NotificationBuilder, Platform};
+    ///
+    /// let rendered = NotificationBuilder::new()
+    ///     .title("It's done")
+    ///     .backend(Platform::Windows)
+    ///     .render()?;
+    /// let Invocation::Command { program, args, .. } = &rendered.invocation else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(program, "powershell.exe");
+    /// assert!(args.last().unwrap().contains("<text>It&apos;s done</text>"));
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn render(self) -> Result<RenderedDelivery> {
+        let notification = self.build();
+        let notifier = create_notifier(selected_backend(&notification));
+        Ok(RenderedDelivery {
+            backend: notifier.backend_name(),
+            invocation: notifier.render(&notification)?,
+        })
+    }
+
+    /// `delay` の後に送信するよう予約する（すぐに戻る）
+    ///
+    /// プロセス全体で共有する `manager::global()` のワーカースレッドが、時刻が来たら `send()` します。
+    /// 通知の構築（タイムスタンプなど）も送信の時点で行い、失敗はログに出します。
+    /// 予約はプロセスの中にしか無いため、送る前にプロセスが終わる場合は
+    /// `manager::global().flush()` で待ってください（CLI の `--after` は別プロセスで待ちます）。
+    ///
+    /// # 例
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use rust_toast::{manager, NotificationBuilder};
+    ///
+    /// NotificationBuilder::new()
+    ///     .message("Tea is ready")
+    ///     .send_after(Duration::from_secs(3 * 60))?;
+    /// manager::global().flush()?;
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn send_after(self, delay: Duration) -> Result<()> {
+        crate::manager::global().schedule_after(self.with_state::<Dynamic>(), delay)
+    }
+
+    /// `at` の時刻に送信するよう予約する（過ぎた時刻ならすぐに送る、詳しくは `send_after()`）
+    pub fn send_at(self, at: SystemTime) -> Result<()> {
+        crate::manager::global().schedule_at(self.with_state::<Dynamic>(), at)
+    }
+
+    /// Notification を構築して非同期に送信（`async` フィーチャー）
+    ///
+    /// 手順と戻り値は `send()` と同じですが、PowerShell / osascript の起動と
+    /// D-Bus の呼び出しを待つ間、tokio のランタイムを止めません
+    /// （それ以外のブロッキング処理は `spawn_blocking` で実行します）。
+    /// tokio のランタイム（`rt`）の中で呼んでください。
+    ///
+    /// # 例
+    /// ```ignore
+    /// let handle = NotificationBuilder::new()
+    ///     .message("Build finished")
+    ///     .send_async()
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn send_async(self) -> Result<NotificationHandle> {
+        nonblocking::send(self.build()).await
+    }
+
+    /// Notification を構築して送信し、ユーザーの操作を待つ
+    ///
+    /// 操作結果を受け取れるのはネイティブ通知（Linux, Windows, macOS + alerter）のみです。
+    /// フォールバック先では操作を報告できないため、失敗時はそのままエラーを返します。
+    ///
+    /// # 例
+    /// ```no_run
+    /// use rust_toast::{Interaction, NotificationBuilder};
+    ///
+    /// let interaction = NotificationBuilder::new()
+    ///     .message("Deploy to production?")
+    ///     .action("yes", "Deploy")
+    ///     .send_and_wait()?;
+    /// if interaction == Interaction::ActionInvoked("yes".to_string()) {
+    ///     // デプロイ処理
+    /// }
+    /// # Ok::<(), rust_toast::NotificationError>(())
+    /// ```
+    pub fn send_and_wait(self) -> Result<Interaction> {
+        let notification = self.build();
+        notification.check_sound_file()?;
+        notification.check_click_url()?;
+        let notifier = select_notifier(&notification)?;
+        // 長さが合わない通知（Overflow::Error）は、購読者・履歴・送信数に残す前に止める
+        let (fitted, rest) = fit_to_backend(&notification, notifier.as_ref())?;
+        let config = config::current();
+        let route = priority::route(&config, notification.priority, clock::current().now());
+        if route.dropped {
+            return Err(NotificationError::Other(format!(
+                "not shown: priority {} during quiet hours",
+                notification.priority
+            )));
+        }
+        topic::check_rate_limit(&config, notification.topic.as_deref())?;
+        if !admit(&config, &notification) {
+            return Err(NotificationError::Other(
+                "not shown: global rate limit exceeded".to_string(),
+            ));
+        }
+        subscribe::publish(&notification);
+        record_history(&notification);
+        if notification.is_held_for_dnd() {
+            return Err(NotificationError::Other(
+                "not shown: do not disturb is on".to_string(),
+            ));
+        }
+        log_backend(&notification, notifier.as_ref());
+        send_also(&notification, notifier.backend_name(), &route.also);
+        // 分けた残りの部分は先に送り、操作はアクションの付いた最初の部分で待つ
+        deliver_rest(notifier.as_ref(), &rest);
+
+        // 待機中も外部プロセスは動き続けるため、許可は待ち終わるまで保持する
+        let _permit = limit::acquire(notifier.backend_name());
+        let interaction = match fitted.sound_escalation() {
+            Some(interval) if !notifier.repeats_sound_natively() => {
+                wait_with_escalation(notifier.as_ref(), fitted.clone(), interval)?
+            }
+            _ => notifier.send_and_wait(&fitted)?,
+        };
+        click::handle(notifier.as_ref(), &fitted, &interaction)?;
+        Ok(interaction)
+    }
+}
+
+/// `try_build()` が受け付ける表示時間の上限
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 通知の JSON を読む（`from_json()` / `from_json_many()` の共通部分）
+fn parse_json(json: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(json)
+        .map_err(|e| NotificationError::Other(format!("invalid notification JSON: {e}")))
+}
+
+/// `NotificationError::InvalidInput` を作る
+fn invalid_field(field: &str, reason: impl Into<String>) -> NotificationError {
+    NotificationError::InvalidInput {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// 通知音の名前として使える文字列かどうか
+///
+/// macOS のシステムサウンド（`Ping`）、freedesktop のサウンド名（`message-new-instant`）、
+/// Windows の `ms-winsoundevent:Notification.Mail` などを想定し、英数字と `-` `_` `.` `:` だけを許可します。
+fn is_sound_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// 名前ではなくファイルパスとして扱う文字列かどうか
+///
+/// アイコンテーマやシステムサウンドの名前にはパス区切り文字が含まれないことを利用します。
+fn is_path_like(value: &str) -> bool {
+    value.contains('/') || value.contains('\\')
+}
+
+/// ファイルパスなら絶対パスにする（名前はそのまま）
+///
+/// 通知デーモンや PowerShell は作業ディレクトリが異なるためです。
+fn absolute_if_path(value: String) -> String {
+    if !is_path_like(&value) {
+        return value;
+    }
+    std::path::absolute(&value).map_or(value, |path| path.to_string_lossy().to_string())
+}
+
+/// 確認されるまで `interval` ごとに通知を送り直して待つ
+///
+/// 各回の表示時間を `interval` にし、時間切れ（`Expired`）なら送り直します。
+/// それ以外の操作（クリック・アクション・閉じる）を確認とみなして返します。
+/// 重要な通知の表示時間を無視する通知デーモンでは、送り直しは起きません。
+fn wait_with_escalation(
+    notifier: &dyn Notifier,
+    notification: Notification,
+    interval: Duration,
+) -> Result<Interaction> {
+    let mut round = notification;
+    round.timeout = interval.as_millis().clamp(1000, u32::MAX as u128) as u32;
+
+    loop {
+        match notifier.send_and_wait(&round)? {
+            Interaction::Expired => continue,
+            interaction => return Ok(interaction),
+        }
+    }
+}
+
+// ============================================================
+// バックエンド選択ロジック
+// ============================================================
+
+/// 適切な Notifier を選択
+///
+/// # 学習ポイント
+/// - `Box<dyn Notifier>`: トレイトオブジェクト（動的ディスパッチ）
+/// - 実行時に具体的な型が決まる場合に使用
+/// - `dyn` は "dynamic" の略
+fn select_notifier(notification: &Notification) -> Result<Box<dyn Notifier>> {
+    notifier_for(selected_backend(notification))
+}
+
+/// 通知の送信に使うバックエンド（強制指定があればそれ、なければ自動検出）
+fn selected_backend(notification: &Notification) -> Backend {
+    notification.backend_override.clone().unwrap_or_else(|| {
+        // 組み込まれていないネイティブ通知は選ばず、未知のプラットフォームと同じコンソール表示にする
+        let native = Backend::Native(detect_platform());
+        if targets::is_compiled(&native) {
+            native
+        } else {
+            Backend::Native(Platform::Unknown)
+        }
+    })
+}
+
+/// バックエンドに対応する Notifier を作成し、利用可能かチェックする
+fn notifier_for(backend: Backend) -> Result<Box<dyn Notifier>> {
+    if let Backend::Custom(name) = &backend {
+        if !registry::is_registered(name) {
+            return Err(NotificationError::Other(format!(
+                "backend '{name}' is not registered"
+            )));
+        }
+    }
+    if !targets::is_compiled(&backend) {
+        let hint = match targets::feature(&backend) {
+            Some(feature) => format!(
+                "not compiled into this build (needs the `{feature}` feature on a supported target)"
+            ),
+            None => "not compiled into this build".to_string(),
+        };
+        return Err(NotificationError::BackendUnavailable {
+            backend: backend.to_string(),
+            hint,
+        });
+    }
+    let notifier = create_notifier(backend);
+
+    // 選択された Notifier が利用可能かチェック
+    if !notifier.is_available() {
+        return Err(NotificationError::BackendUnavailable {
+            backend: notifier.backend_name().to_string(),
+            hint: "not supported on this platform".to_string(),
+        });
+    }
+
+    Ok(notifier)
+}
+
+/// バックエンドに対応する Notifier を作成（利用可能かはチェックしない）
+///
+/// 同じ名前で登録されたバックエンドがあれば、組み込みのものより優先します。
+fn create_notifier(backend: Backend) -> Box<dyn Notifier> {
+    if let Some(notifier) = registry::registered(backend.key()) {
+        return notifier;
+    }
+    // Box::new() でヒープに配置し、Box<dyn Notifier> として返す
+    match backend {
+        Backend::Native(Platform::Linux) => Box::new(LinuxNotifier),
+        Backend::Native(Platform::Wsl | Platform::Windows) => Box::new(WindowsNotifier),
+        Backend::Native(Platform::MacOs) => Box::new(MacOsNotifier),
+        // 未知のプラットフォームでは、どこでも使えるコンソール表示にする
+        Backend::Native(Platform::Unknown) => Box::new(ConsoleNotifier),
+        Backend::Dialog => Box::new(DialogNotifier),
+        Backend::Console => Box::new(ConsoleNotifier),
+        Backend::Stdout => Box::new(StdoutNotifier),
+        Backend::Custom(name) => Box::new(registry::Unregistered(name)),
+    }
+}
+
+/// バックエンドの表示名と、実行中の環境で使えるかどうか
+pub fn backend_status(backend: Backend) -> (&'static str, bool) {
+    let notifier = create_notifier(backend);
+    (notifier.backend_name(), notifier.is_available())
+}
+
+/// バックエンドが対応している機能（利用できるかはチェックしない）
+///
+/// 登録されたバックエンドがあれば、`create_notifier()` と同じくそちらを優先します。
+pub fn backend_capabilities(backend: Backend) -> Capabilities {
+    create_notifier(backend).capabilities()
+}
+
+/// `send_to()` のバックエンドごとの結果
+pub type BackendResult = (Backend, Result<NotificationHandle>);
+
+/// ファンアウト（`send_to()`、CLI の `--backend all`）で送る、利用できる全てのバックエンド
+///
+/// 実行中のプラットフォームのネイティブ通知、コンソール、登録されたバックエンド（プラグイン）の順です。
+/// ユーザーが閉じるまで待つダイアログと、パイプライン用の JSON 出力は含めません。
+pub fn available_backends() -> Vec<Backend> {
+    // 未知のプラットフォームのネイティブ通知はコンソール表示なので、重ねて送らない
+    let native = Some(detect_platform())
+        .filter(|platform| *platform != Platform::Unknown)
+        .map(Backend::Native);
+    let builtin = native.into_iter().chain([Backend::Console]);
+    let registered = registry::registered_backends()
+        .into_iter()
+        .filter(|name| name.parse::<Backend>().ok() == Some(Backend::Custom(name.clone())))
+        .map(Backend::Custom);
+    let mut backends: Vec<Backend> = Vec::new();
+    for backend in builtin.chain(registered) {
+        if !backends.contains(&backend) && create_notifier(backend.clone()).is_available() {
+            backends.push(backend);
+        }
+    }
+    backends
+}
+
+/// 表示中の通知を ID で閉じる
+///
+/// `backend` が `None` の場合は、自動検出したバックエンドを使います。
+/// 別プロセスが表示した通知も、ID が分かれば閉じられます（CLI の `dismiss`）。
+///
+/// # 例
+/// ```no_run
+/// // `rust-toast --print-id` で受け取った ID の通知を閉じる
+/// rust_toast::notifier::dismiss("42", None)?;
+/// # Ok::<(), rust_toast::NotificationError>(())
+/// ```
+pub fn dismiss(id: &str, backend: Option<Backend>) -> Result<()> {
+    notifier_for(backend.unwrap_or_else(|| Backend::Native(detect_platform())))?.close(id)
+}
+
+/// 複数の通知をまとめて送信する
+///
+/// 各通知は `send()` と同じ手順（振り分け・送信数の制限・履歴への記録）を通り、
+/// 同じバックエンドに送る通知は `Notifier::send_batch()` でまとめて配送します
+/// （Windows では PowerShell の起動が 1 回で済みます）。
+/// 配送に失敗した通知だけをフォールバック先で送り直します（表示できた通知は送り直しません）。
+///
+/// 戻り値は通知ごとの結果で、渡した通知と同じ順です（`report()` の時間は、まとめた配送全体の時間）。
+/// 1 件の失敗で他の通知は止まりません。表示しない通知（静かな時間帯など）は ID 無しのハンドルです。
+///
+/// # 例
+/// ```no_run
+/// use rust_toast::NotificationBuilder;
+///
+/// let results = rust_toast::notifier::send_batch(
+///     ["api", "web", "worker"].map(|service| {
+///         NotificationBuilder::new().title("Deployed").message(service)
+///     }),
+/// );
+/// for result in &results {
+///     if let Err(err) = result {
+///         eprintln!("{err}");
+///     }
+/// }
+/// ```
+pub fn send_batch(
+    builders: impl IntoIterator<Item = NotificationBuilder>,
+) -> Vec<Result<NotificationHandle>> {
+    let started = clock::current().now();
+    let config = config::current();
+    let notifications: Vec<Notification> = builders
+        .into_iter()
+        .map(NotificationBuilder::<Dynamic>::build)
+        .collect();
+
+    // バックエンドごとに、送る通知の位置をまとめる（最初に現れた順）
+    let mut groups: Vec<(Backend, Box<dyn Notifier>, Vec<usize>)> = Vec::new();
+    let mut outcomes: Vec<Result<SendReport>> = notifications
+        .iter()
+        .map(|_| Ok(SendReport::default()))
+        .collect();
+    let mut records = vec![None; notifications.len()];
+    // 長さの上限に合わせた通知と、分けた残り（`fit_to_backend()`）
+    let mut fitted: Vec<Option<(Notification, Vec<Notification>)>> =
+        vec![None; notifications.len()];
+    for (index, notification) in notifications.iter().enumerate() {
+        let backend = avoid_slow_backend(&config, notification, selected_backend(notification));
+        let position = match groups.iter().position(|(grouped, ..)| *grouped == backend) {
+            Some(position) => position,
+            None => match notifier_for(backend.clone()) {
+                Ok(notifier) => {
+                    groups.push((backend, notifier, Vec::new()));
+                    groups.len() - 1
+                }
+                Err(err) => {
+                    outcomes[index] = Err(err);
+                    continue;
+                }
+            },
+        };
+        let (_, notifier, indices) = &mut groups[position];
+        // 長さが合わない通知（Overflow::Error）は、購読者・履歴・送信数に残す前に止める
+        let parts = match fit_to_backend(notification, notifier.as_ref()) {
+            Ok(parts) => parts,
+            Err(err) => {
+                outcomes[index] = Err(err);
+                continue;
+            }
+        };
+        let prepared = match prepare_send(&config, notification) {
+            Ok(Some(prepared)) => prepared,
+            Ok(None) => continue,
+            Err(err) => {
+                outcomes[index] = Err(err);
+                continue;
+            }
+        };
+        records[index] = prepared.history;
+        send_also(notification, notifier.backend_name(), &prepared.route.also);
+        indices.push(index);
+        fitted[index] = Some(parts);
+    }
+
+    for (backend, notifier, indices) in groups {
+        if indices.is_empty() {
+            continue;
+        }
+        let name = notifier.backend_name();
+        let batch: Vec<Notification> = indices
+            .iter()
+            .filter_map(|&i| fitted[i].as_ref().map(|(first, _)| first.clone()))
+            .collect();
+        log_backend(&batch[0], notifier.as_ref());
+        let delivered = send_batch_with_limit(notifier.as_ref(), &batch);
+        for (index, result) in indices.into_iter().zip(delivered) {
+            // フォールバック先には、長さを合わせる前の通知を渡す（`send()` と同じ）
+            let notification = &notifications[index];
+            outcomes[index] = match result {
+                Ok(id) => {
+                    record_delivery(records[index], Delivery::sent(name, id.clone()));
+                    if let Some((_, rest)) = &fitted[index] {
+                        deliver_rest(notifier.as_ref(), rest);
+                    }
+                    Ok(SendReport {
+                        backend: Some(name),
+                        id,
+                        ..SendReport::default()
+                    })
+                }
+                Err(err) => {
+                    record_delivery(records[index], Delivery::failed(name, &err));
+                    if backend == Backend::Stdout {
+                        Err(err)
+                    } else {
+                        tracing::warn!("{name} failed in a batch: {err}");
+                        send_fallback(notification, name, err.to_string().into()).map(|fallback| {
+                            record_delivery(records[index], Delivery::sent(fallback, None));
+                            SendReport {
+                                backend: Some(fallback),
+                                ..SendReport::default()
+                            }
+                            .failed(name, &err)
+                        })
+                    }
+                }
+            };
+        }
+    }
+
+    let elapsed = clock::current()
+        .now()
+        .duration_since(started)
+        .unwrap_or_default();
+    notifications
+        .into_iter()
+        .zip(fitted)
+        .zip(outcomes)
+        .map(|((notification, fitted), outcome)| {
+            outcome.map(|report| {
+                let report = SendReport { elapsed, ..report };
+                let shown = fitted.map_or(notification, |(first, _)| first);
+                NotificationHandle::new(None, shown).with_report(report)
+            })
+        })
+        .collect()
+}
+
+/// 自動選択されるバックエンドを事前に初期化する
+///
+/// アプリケーションの起動時に呼んでおくと、最初の通知が
+/// 遅くなる（D-Bus 接続や PowerShell の初回起動待ち）のを避けられます。
+///
+/// # 例
+/// ```no_run
+/// // 起動時にバックグラウンドで温めておく
+/// std::thread::spawn(|| rust_toast::notifier::warm_up());
+/// ```
+pub fn warm_up() -> Result<()> {
+    let notification = NotificationBuilder::new().build();
+    select_notifier(&notification)?.warm_up()
+}
+
+/// 使用するプラットフォームとバックエンドをログ（debug）に出す
+fn log_backend(notification: &Notification, notifier: &dyn Notifier) {
+    tracing::debug!(
+        "platform: {}, using {} backend",
+        notification
+            .backend_override
+            .as_ref()
+            .and_then(Backend::platform)
+            .unwrap_or_else(detect_platform),
+        notifier.backend_name()
+    );
+}
+
+/// 優先度の振り分けで捨てた通知をログに出す
+fn log_dropped(notification: &Notification) {
+    tracing::info!(
+        "not shown: priority {} during quiet hours",
+        notification.priority
+    );
+}
+
+/// 優先度の振り分けで指定された追加のバックエンドにも送る
+///
+/// メインのバックエンドと同じもの・利用できないものは飛ばします。
+/// 追加の送信に失敗してもメインの送信は止めず、ログに出すだけにします。
+fn send_also(notification: &Notification, primary: &str, also: &[Backend]) {
+    for backend in also {
+        let notifier = create_notifier(backend.clone());
+        if notifier.backend_name() == primary || !notifier.is_available() {
+            continue;
+        }
+        if let Err(err) = deliver_with_limit(notifier.as_ref(), notification) {
+            tracing::warn!("also via {} failed: {err}", notifier.backend_name());
+        }
+    }
+}
+
+/// `Config::record_history` が有効なら、通知を履歴に記録し、連番を返す
+///
+/// 画面ロック中・おやすみモード中に届いた通知は未読として記録します
+/// （`history::unread_count()` で数えられる）。
+/// 記録に失敗しても通知の送信は止めず、ログに出すだけにします。
+fn record_history(notification: &Notification) -> Option<u64> {
+    if notification.transient || !config::current().record_history {
+        return None;
+    }
+    let seen = !crate::platform::is_user_away();
+    history::record(&notification.to_json(), seen)
+        .inspect_err(|err| tracing::warn!("history not recorded: {err}"))
+        .ok()
+}
+
+/// 履歴に記録した通知（連番 `seq`）の送信の結果を記録する（記録していなければ何もしない）
+fn record_delivery(seq: Option<u64>, delivery: Delivery) {
+    let Some(seq) = seq else {
+        return;
+    };
+    if let Err(err) = history::record_result(seq, &delivery) {
+        tracing::warn!("history not recorded: {err}");
+    }
+}
+
+/// `Notification::overflow` が指定されていれば、通知をバックエンドの長さの上限に合わせる
+///
+/// 最初に送る通知と、続けて送る残り（`Overflow::Split` で分けた場合）を返します。
+/// 指定が無ければ `capabilities()` を問い合わせません（Linux では D-Bus の呼び出しになるため）。
+fn fit_to_backend(
+    notification: &Notification,
+    notifier: &dyn Notifier,
+) -> Result<(Notification, Vec<Notification>)> {
+    let Some(overflow) = notification.overflow else {
+        return Ok((notification.clone(), Vec::new()));
+    };
+    let mut parts = length::fit(notification, &notifier.capabilities(), overflow)?;
+    let first = parts.remove(0);
+    Ok((first, parts))
+}
+
+/// 分けた通知の残りを続けて送る（失敗してもログに出すだけ）
+fn deliver_rest(notifier: &dyn Notifier, rest: &[Notification]) {
+    for part in rest {
+        if let Err(err) = deliver_with_limit(notifier, part) {
+            tracing::warn!("part of a split notification not sent: {err}");
+        }
+    }
+}
+
+/// 選んだバックエンドへの配送（一時的な失敗は `Notification::retry` に従って再試行する）
+///
+/// 再試行するのはこの配送だけで、フォールバック・`also`・まとめた通知は `deliver_with_limit()` で 1 回だけ送ります。
+/// 1 回ごとに許可を取り直します（待つ間は許可を返却しておく）。
+fn deliver_with_retry(
+    notifier: &dyn Notifier,
+    notification: &Notification,
+) -> Result<Option<String>> {
+    retry::run(&notification.retry, || {
+        deliver_with_limit(notifier, notification)
+    })
+}
+
+/// 同時実行数の制限を守りながら送信する
+///
+/// 制限があれば空きが出るまで待ち、送信が終わると許可を返却します。
+fn deliver_with_limit(
+    notifier: &dyn Notifier,
+    notification: &Notification,
+) -> Result<Option<String>> {
+    let _span = tracing::debug_span!("deliver", backend = notifier.backend_name()).entered();
+    let _permit = limit::acquire(notifier.backend_name());
+    let started = clock::current().now();
+    let result = notifier
+        .deliver(notification)
+        .map_err(|err| err.in_backend(notifier.backend_name()));
+    if let Ok(elapsed) = clock::current().now().duration_since(started) {
+        latency::global_tracker().record(notifier.backend_name(), elapsed);
+        tracing::debug!(?elapsed, ok = result.is_ok(), "delivered");
+    }
+    result
+}
+
+/// `deliver_with_limit()` のまとめて送る版（許可は 1 つ、応答時間は 1 件あたりの平均を記録）
+fn send_batch_with_limit(
+    notifier: &dyn Notifier,
+    notifications: &[Notification],
+) -> Vec<Result<Option<String>>> {
+    let _permit = limit::acquire(notifier.backend_name());
+    let started = clock::current().now();
+    let result = notifier.send_batch(notifications);
+    if let Ok(elapsed) = clock::current().now().duration_since(started) {
+        let count = u32::try_from(notifications.len())
+            .unwrap_or(u32::MAX)
+            .max(1);
+        latency::global_tracker().record(notifier.backend_name(), elapsed / count);
+    }
+    result
+}
+
+/// 配送の準備ができた通知（`prepare_send()` の結果）
+struct Prepared {
+    /// 優先度の振り分けの結果
+    route: priority::Route,
+    /// 履歴に記録した連番（送信の結果の記録に使う）
+    history: Option<u64>,
+}
+
+/// 配送より前の共通の処理（`send()` / `send_async()`）
+///
+/// 通知音の確認・優先度の振り分け・送信数の制限・購読者への通知・履歴への記録を行い、
+/// 表示しない通知（静かな時間帯・おやすみモード）なら `None` を返します。
+fn prepare_send(config: &Config, notification: &Notification) -> Result<Option<Prepared>> {
+    notification.check_sound_file()?;
+    notification.check_click_url()?;
+    let route = priority::route(config, notification.priority, clock::current().now());
+    if route.dropped {
+        log_dropped(notification);
+        return Ok(None);
+    }
+    topic::check_rate_limit(config, notification.topic.as_deref())?;
+    if !admit(config, notification) {
+        return Ok(None);
+    }
+    subscribe::publish(notification);
+    let history = record_history(notification);
+    if notification.is_held_for_dnd() {
+        return Ok(None);
+    }
+    Ok(Some(Prepared { route, history }))
+}
+
+/// 同じ通知の重複をまとめる（`Config::dedupe_window`、表示しない重複なら `None`）
+///
+/// 前回の通知に ID があれば、回数を付けた本文でその通知を置き換えます。
+fn coalesce(config: &Config, notification: &Notification) -> Option<Notification> {
+    let Some(window) = config.dedupe_window else {
+        return Some(notification.clone());
+    };
+    match dedupe::global().check(notification, window) {
+        dedupe::Dedupe::New => Some(notification.clone()),
+        dedupe::Dedupe::Repeat {
+            count,
+            replace_id: Some(id),
+        } => {
+            let mut repeated = dedupe::with_counter(notification, count);
+            repeated.replace_id = Some(id);
+            Some(repeated)
+        }
+        dedupe::Dedupe::Repeat {
+            count,
+            replace_id: None,
+        } => {
+            tracing::info!("duplicate ×{count} not shown: the first one cannot be replaced");
+            None
+        }
+    }
+}
+
+/// 表示できた通知と ID を、重複の判定と置き換え用に記録する（`original` は回数を付ける前の通知）
+fn remember_id(config: &Config, original: &Notification, id: Option<&str>) {
+    if config.dedupe_window.is_some() {
+        dedupe::global().remember(original, id);
+    }
+}
+
+/// 全体の送信数の上限（`Config::rate_limit`）を確認する（表示しない通知なら `false`）
+///
+/// `Overflow::Queue` なら空きが出るまで待ち、`Summarize` で省略した通知があれば
+/// この通知の前に件数を知らせる通知を送ります。
+fn admit(config: &Config, notification: &Notification) -> bool {
+    let Some(rule) = config.rate_limit else {
+        return true;
+    };
+    match throttle::global().admit(rule) {
+        Admission::Send => true,
+        Admission::SendWithSummary(count) => {
+            send_summary(notification, count);
+            true
+        }
+        Admission::Dropped => {
+            tracing::info!(
+                "not shown: more than {} notifications in {}s",
+                rule.limit.max,
+                rule.limit.per.as_secs()
+            );
+            false
+        }
+    }
+}
+
+/// `Overflow::Summarize` で省略した件数を、同じバックエンドに知らせる（失敗はログに出すだけ）
+fn send_summary(notification: &Notification, count: u32) {
+    let mut builder = NotificationBuilder::new()
+        .title("rust-toast")
+        .message(format!(
+            "{count} notification(s) suppressed by the rate limit"
+        ))
+        .urgency(UrgencyLevel::Low)
+        .transient(true);
+    if let Some(backend) = &notification.backend_override {
+        builder = builder.backend(backend.clone());
+    }
+    let summary = builder.build();
+    let result = notifier_for(selected_backend(&summary))
+        .and_then(|notifier| deliver_with_limit(notifier.as_ref(), &summary));
+    if let Err(err) = result {
+        tracing::warn!("rate limit summary not shown: {err}");
+    }
+}
+
+/// 応答時間の予算（`Config::latency_budget`）を超えたバックエンドの代わりに使うバックエンド
+const LATENCY_FALLBACK: Backend = Backend::Console;
+
+/// 自動選択されたバックエンドが最近遅ければ、重要でない通知は `LATENCY_FALLBACK` で送る
+///
+/// バックエンドを明示した通知（トピックのルールを含む）と、重要な通知は切り替えません。
+fn avoid_slow_backend(config: &Config, notification: &Notification, backend: Backend) -> Backend {
+    let Some(budget) = config.latency_budget else {
+        return backend;
+    };
+    let name = create_notifier(backend.clone()).backend_name();
+    if notification.backend_override.is_some()
+        || notification.urgency == UrgencyLevel::Critical
+        || !latency::global_tracker().should_avoid(name, budget)
+    {
+        return backend;
+    }
+    tracing::info!(
+        "{} is slower than {} ms; using {} backend",
+        name,
+        budget.as_millis(),
+        LATENCY_FALLBACK.name()
+    );
+    LATENCY_FALLBACK
+}
+
+/// 表示できなかった通知を送信待ちキュー（`store::queue`）に記録する（`Config::spool`）
+pub(crate) fn spool(notification: &Notification, err: NotificationError) -> Result<()> {
+    queue::push(&notification.to_json())?;
+    tracing::info!("{err}; spooled, will retry when a backend is available");
+    Ok(())
+}
+
+/// 表示できなかった通知を送信待ちキューに記録するかどうか
+///
+/// クリックで実行するコマンド（`on_click_exec`）のある通知は、ファイルから読み直した
+/// コマンドを実行しないよう記録せず、フォールバック先に送ります。
+pub(crate) fn spools(config: &Config, notification: &Notification) -> bool {
+    config.spool && notification.on_click_exec.is_none()
+}
+
+/// `send()` の後に送り直す送信待ちの通知の数の上限（1 回の送信を長く止めないように）
+const SPOOL_RETRY_BATCH: usize = 3;
+
+/// 送信できた直後に、送信待ちキューに残っている通知を古い順に `SPOOL_RETRY_BATCH` 件まで送り直す
+/// （失敗はログに出すだけ）
+pub(crate) fn retry_spool(config: &Config) {
+    if !config.spool {
+        return;
+    }
+    match flush_spool_up_to(SPOOL_RETRY_BATCH) {
+        Ok(0) => {}
+        Ok(sent) => tracing::info!("sent {sent} spooled notification(s)"),
+        Err(err) => tracing::warn!("spooled notifications not sent: {err}"),
+    }
+}
+
+/// 送信待ちキュー（`Config::spool`）の通知を古い順に送り直し、送れた数を返す
+///
+/// 送り直しは選ばれたバックエンドだけに行います（フォールバックしない）。
+/// 失敗した通知は回数を記録して次の通知に進み、次回に送り直します。
+/// 読めない通知と `queue::MAX_ATTEMPTS` 回失敗した通知は諦めて、警告をログに出します。
+/// 最後に送った通知と諦めた通知をキューから取り除きます（`queue::compact()`）。
+/// 振り分けや履歴への記録は最初の送信で済んでいるので、もう一度は行いません。
+pub fn flush_spool() -> Result<usize> {
+    flush_spool_up_to(usize::MAX)
+}
+
+fn flush_spool_up_to(limit: usize) -> Result<usize> {
+    let pending = queue::pending()?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    let mut sent = 0;
+    for spooled in pending.into_iter().take(limit) {
+        let seq = spooled.entry.seq;
+        // on_click_exec は読まない（spool() はコマンドのある通知を記録しない）
+        let notification = match Notification::from_json(&spooled.entry.payload) {
+            Ok(notification) => notification,
+            Err(err) => {
+                tracing::warn!("spooled notification #{seq} is dropped: {err}");
+                queue::mark_poisoned(seq, &err.to_string())?;
+                continue;
+            }
+        };
+        let result = notifier_for(selected_backend(&notification))
+            .and_then(|notifier| deliver_with_limit(notifier.as_ref(), &notification));
+        match result {
+            Ok(_) => {
+                queue::mark_sent(seq)?;
+                sent += 1;
+            }
+            Err(err) if spooled.failures + 1 >= queue::MAX_ATTEMPTS => {
+                tracing::warn!(
+                    "spooled notification '{}' is dropped after {} attempts: {err}",
+                    notification.title,
+                    queue::MAX_ATTEMPTS
+                );
+                queue::mark_poisoned(seq, &err.to_string())?;
+            }
+            Err(err) => {
+                tracing::info!(
+                    "spooled notification '{}' not sent: {err}",
+                    notification.title
+                );
+                queue::mark_failed(seq, &err.to_string())?;
+            }
+        }
+    }
+    queue::compact()?;
+    Ok(sent)
+}
+
+/// デフォルトのフォールバック先（`Config::fallback_chain` の初期値、試す順）
+pub const DEFAULT_FALLBACK_CHAIN: [Backend; 2] = [Backend::Dialog, Backend::Console];
+
+/// メインのバックエンドが失敗した時に、フォールバック先で順に再送する
+///
+/// 通知デーモンが動いていない場合などでも、メッセージが黙って
+/// 失われないようにするための仕組みです。
+/// `Notification::fallback_chain` の順に試し、送れたバックエンドの名前を返します。
+/// 全て失敗した場合は元のエラーを返します。
+fn send_fallback(
+    notification: &Notification,
+    failed_backend: &str,
+    original: NotificationError,
+) -> Result<&'static str> {
+    let chain = notification.fallback_chain.iter().cloned();
+    for fallback in chain.map(create_notifier) {
+        // 失敗したバックエンド自身と、利用できないものはスキップ
+        if fallback.backend_name() == failed_backend || !fallback.is_available() {
+            continue;
+        }
+
+        tracing::warn!(
+            "{}; falling back to {} backend",
+            original,
+            fallback.backend_name()
+        );
+        if deliver_with_limit(fallback.as_ref(), notification).is_ok() {
+            return Ok(fallback.backend_name());
+        }
+    }
+
+    Err(original)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::preset::Preset;
+    use std::sync::Mutex;
 
     #[test]
     fn test_notification_builder_defaults() {
@@ -300,7 +3589,7 @@ mod tests {
         let notification = NotificationBuilder::new()
             .title("Test Title")
             .message("Test Message")
-            .timeout(1000)
+            .timeout(Duration::from_secs(1))
             .urgency(UrgencyLevel::Critical)
             .build();
 
@@ -310,6 +3599,996 @@ mod tests {
         assert_eq!(notification.urgency, UrgencyLevel::Critical);
     }
 
+    #[test]
+    fn test_timeout_defaults_follow_urgency() {
+        let mut config = Config::default();
+        config.low.expiration = Some(Duration::from_secs(60));
+
+        let low = NotificationBuilder::new()
+            .urgency(UrgencyLevel::Low)
+            .build_with_config(&config);
+        assert_eq!(low.timeout, 3000);
+        assert_eq!(low.expiration, Some(Duration::from_secs(60)));
+
+        let critical = NotificationBuilder::new()
+            .urgency(UrgencyLevel::Critical)
+            .build_with_config(&config);
+        assert_eq!(critical.timeout, 0);
+
+        // 明示的な指定はデフォルトより優先される
+        let explicit = NotificationBuilder::new()
+            .urgency(UrgencyLevel::Critical)
+            .timeout(Duration::from_millis(1234))
+            .build_with_config(&config);
+        assert_eq!(explicit.timeout, 1234);
+
+        // 従来のミリ秒の指定も使える
+        let legacy = NotificationBuilder::new()
+            .timeout(1234)
+            .build_with_config(&config);
+        assert_eq!(legacy.timeout, 1234);
+    }
+
+    #[test]
+    fn test_command_timeout_from_config() {
+        let mut config = Config::default();
+        assert_eq!(
+            NotificationBuilder::new()
+                .build_with_config(&config)
+                .command_timeout,
+            Duration::from_secs(10)
+        );
+
+        config.command_timeout = Duration::from_secs(3);
+        let notification = NotificationBuilder::new().build_with_config(&config);
+        assert_eq!(notification.command_timeout, Duration::from_secs(3));
+        let notification = NotificationBuilder::new()
+            .command_timeout(Duration::from_secs(30))
+            .build_with_config(&config);
+        assert_eq!(notification.command_timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_capture_sender_from_config() {
+        let config = Config {
+            capture_sender: true,
+            ..Config::default()
+        };
+
+        let captured = NotificationBuilder::new().build_with_config(&config);
+        let sender = captured.sender.as_ref().expect("sender should be captured");
+        assert_eq!(sender.pid, std::process::id());
+        assert!(captured.to_json().contains(r#""sender":{"#));
+
+        // 無効（デフォルト）なら添付しない
+        let plain = NotificationBuilder::new().build_with_config(&Config::default());
+        assert_eq!(plain.sender, None);
+    }
+
+    /// 決まった順に操作結果を返すテスト用の Notifier
+    struct ScriptedNotifier {
+        results: std::sync::Mutex<Vec<Interaction>>,
+        timeouts: std::sync::Mutex<Vec<u32>>,
+    }
+
+    impl Notifier for ScriptedNotifier {
+        fn send(&self, _notification: &Notification) -> Result<()> {
+            Ok(())
+        }
+
+        fn send_and_wait(&self, notification: &Notification) -> Result<Interaction> {
+            self.timeouts.lock().unwrap().push(notification.timeout);
+            Ok(self.results.lock().unwrap().remove(0))
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "scripted"
+        }
+    }
+
+    #[test]
+    fn test_sound_escalation_resends_until_ack() {
+        let notification = NotificationBuilder::new()
+            .urgency(UrgencyLevel::Critical)
+            .require_ack()
+            .repeat_sound(Duration::from_secs(10))
+            .build();
+        assert_eq!(notification.timeout, 0);
+        assert_eq!(
+            notification.sound_escalation(),
+            Some(Duration::from_secs(10))
+        );
+
+        let notifier = ScriptedNotifier {
+            results: std::sync::Mutex::new(vec![
+                Interaction::Expired,
+                Interaction::Expired,
+                Interaction::Clicked,
+            ]),
+            timeouts: std::sync::Mutex::new(Vec::new()),
+        };
+        let interaction =
+            wait_with_escalation(&notifier, notification, Duration::from_secs(10)).unwrap();
+
+        assert_eq!(interaction, Interaction::Clicked);
+        assert_eq!(*notifier.timeouts.lock().unwrap(), vec![10_000; 3]);
+    }
+
+    #[test]
+    fn test_icon_path() {
+        let named = NotificationBuilder::new().icon("dialog-warning").build();
+        assert_eq!(
+            named.icon(),
+            Some(&Icon::Named("dialog-warning".to_string()))
+        );
+        assert_eq!(named.icon_path(), None);
+
+        let file = NotificationBuilder::new().icon("/opt/app/logo.png").build();
+        assert_eq!(file.icon_path(), Some(PathBuf::from("/opt/app/logo.png")));
+
+        // 相対パスは絶対パスにする
+        let relative = NotificationBuilder::new().icon("assets/logo.png").build();
+        assert!(relative.icon_path().unwrap().is_absolute());
+    }
+
+    #[test]
+    fn test_preset_translation_by_locale() {
+        let mut config = Config::default();
+        config.presets.insert(
+            "disk-full".to_string(),
+            Preset::new()
+                .translation("en", "Disk full", "Free up some space")
+                .translation("ja", "ディスクがいっぱいです", "空き容量を確保してください"),
+        );
+
+        let ja = NotificationBuilder::new()
+            .preset("disk-full")
+            .locale("ja-JP")
+            .build_with_config(&config);
+        assert_eq!(ja.title, "ディスクがいっぱいです");
+        assert_eq!(ja.message, "空き容量を確保してください");
+        assert_eq!(ja.locale, "ja_JP");
+
+        // 明示的な指定はプリセットより優先される
+        let custom = NotificationBuilder::new()
+            .preset("disk-full")
+            .locale("fr")
+            .message("/var is full")
+            .build_with_config(&config);
+        assert_eq!(custom.title, "Disk full");
+        assert_eq!(custom.message, "/var is full");
+    }
+
+    #[test]
+    fn test_sound_file_must_exist() {
+        let named = NotificationBuilder::new().sound("Glass").build();
+        assert_eq!(named.sound_path(), None);
+        assert_eq!(named.sound_name(), Some("Glass"));
+        let silent = NotificationBuilder::new()
+            .sound("/missing.wav")
+            .silent(true)
+            .build();
+        assert_eq!((silent.sound_path(), silent.sound_name()), (None, None));
+        assert!(silent.check_sound_file().is_ok());
+        assert!(named.check_sound_file().is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("chime.wav");
+        std::fs::write(&file, b"RIFF").unwrap();
+        let existing = NotificationBuilder::new()
+            .sound(file.to_string_lossy())
+            .build();
+        assert_eq!(existing.sound_path(), Some(file));
+        assert!(existing.check_sound_file().is_ok());
+
+        let missing = NotificationBuilder::new()
+            .sound(dir.path().join("missing.wav").to_string_lossy())
+            .backend(Backend::Stdout)
+            .send();
+        let err = missing.unwrap_err().to_string();
+        assert!(err.contains("sound file not found"), "{err}");
+    }
+
+    #[test]
+    fn test_global_rate_limit_drops_overflow() {
+        let notification = NotificationBuilder::new().message("flood").build();
+        let mut config = Config::default();
+        assert!(admit(&config, &notification));
+
+        // 上限 0 なら全て捨てる（Drop は待たない）
+        config.rate_limit = Some(throttle::GlobalRateLimit::new(
+            0,
+            Duration::from_secs(60),
+            throttle::Overflow::Drop,
+        ));
+        assert!(!admit(&config, &notification));
+        assert!(prepare_send(&config, &notification).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_coalesce_replaces_repeated_notification() {
+        let notification = NotificationBuilder::new()
+            .title("coalesce-test")
+            .message("disk full")
+            .build();
+        let mut config = Config::default();
+        // 窓が無ければそのまま
+        assert_eq!(
+            coalesce(&config, &notification).unwrap().message,
+            "disk full"
+        );
+
+        config.dedupe_window = Some(Duration::from_secs(60));
+        assert_eq!(
+            coalesce(&config, &notification).unwrap().message,
+            "disk full"
+        );
+        remember_id(&config, &notification, Some("7"));
+        let repeated = coalesce(&config, &notification).unwrap();
+        assert_eq!(repeated.message, "disk full (×2)");
+        assert_eq!(repeated.replace_id.as_deref(), Some("7"));
+
+        // ID が無い（置き換えられない）重複は表示しない
+        let silent = NotificationBuilder::new()
+            .title("coalesce-test")
+            .message("no id")
+            .build();
+        assert!(coalesce(&config, &silent).is_some());
+        // 表示できるまでは記録しない（失敗した通知の送り直しを止めない）
+        assert!(coalesce(&config, &silent).is_some());
+        remember_id(&config, &silent, None);
+        assert!(coalesce(&config, &silent).is_none());
+    }
+
+    #[test]
+    fn test_fallback_chain() {
+        let mut config = Config::default();
+        assert_eq!(
+            NotificationBuilder::new()
+                .build_with_config(&config)
+                .fallback_chain,
+            DEFAULT_FALLBACK_CHAIN
+        );
+        config.fallback_chain = vec![Backend::Console];
+        let notification = NotificationBuilder::new().build_with_config(&config);
+        assert_eq!(notification.fallback_chain, [Backend::Console]);
+
+        // 失敗したら、チェーンのバックエンドで送り直す
+        struct Failing;
+        impl Notifier for Failing {
+            fn send(&self, _: &Notification) -> Result<()> {
+                Err(NotificationError::Other("daemon not running".to_string()))
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Failing"
+            }
+        }
+        register_backend("fallback-failing", Box::new(Failing));
+        let send = |chain: Vec<Backend>| {
+            NotificationBuilder::new()
+                .message("fallback")
+                .backend(Backend::Custom("fallback-failing".to_string()))
+                .fallback_chain(chain)
+                .send()
+        };
+        let handle = send(vec![
+            Backend::Custom("fallback-failing".into()),
+            Backend::Stdout,
+        ]);
+        assert_eq!(handle.unwrap().id(), None);
+        let err = send(Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("daemon not running"), "{err}");
+        unregister_backend("fallback-failing");
+    }
+
+    #[test]
+    fn test_retries_transient_failures() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // 最初の 2 回だけ失敗する（デーモンが一瞬応答しない）
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+        struct Flaky;
+        impl Notifier for Flaky {
+            fn send(&self, _: &Notification) -> Result<()> {
+                if CALLS.fetch_add(1, Ordering::SeqCst) < 2 {
+                    return Err(NotificationError::SendFailed {
+                        backend: "Flaky".to_string(),
+                        reason: "daemon busy".to_string(),
+                    });
+                }
+                Ok(())
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Flaky"
+            }
+        }
+        register_backend("retry-flaky", Box::new(Flaky));
+        let send = |retries| {
+            CALLS.store(0, Ordering::SeqCst);
+            NotificationBuilder::new()
+                .message("retry")
+                .backend(Backend::Custom("retry-flaky".to_string()))
+                .fallback_chain(Vec::<Backend>::new())
+                .retry_policy(RetryPolicy {
+                    initial_delay: Duration::ZERO,
+                    ..RetryPolicy::retries(retries)
+                })
+                .send()
+        };
+        assert!(send(1).is_err());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+        assert_eq!(send(2).unwrap().report().backend, Some("Flaky"));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+        unregister_backend("retry-flaky");
+    }
+
+    #[test]
+    fn test_send_splits_long_messages() {
+        static SENT: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+        struct Short;
+        impl Notifier for Short {
+            fn send(&self, notification: &Notification) -> Result<()> {
+                SENT.lock().unwrap().push(notification.message.clone());
+                Ok(())
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Short"
+            }
+            fn capabilities(&self) -> Capabilities {
+                Capabilities {
+                    max_message_len: Some(8),
+                    ..Capabilities::default()
+                }
+            }
+        }
+        register_backend("overflow-short", Box::new(Short));
+        let handle = NotificationBuilder::new()
+            .message("first second third")
+            .backend(Backend::Custom("overflow-short".to_string()))
+            .overflow(Overflow::Split)
+            .send()
+            .unwrap();
+        assert_eq!(*SENT.lock().unwrap(), ["first", "second", "third"]);
+        assert_eq!(handle.notification().title, "Notification (1/3)");
+
+        // まとめて送る場合も分ける
+        SENT.lock().unwrap().clear();
+        let results = send_batch([NotificationBuilder::new()
+            .message("first second")
+            .backend(Backend::Custom("overflow-short".to_string()))
+            .overflow(Overflow::Split)]);
+        assert!(results[0].is_ok());
+        assert_eq!(*SENT.lock().unwrap(), ["first", "second"]);
+
+        // Overflow::Error は購読者にも配信せず、何も送らない
+        SENT.lock().unwrap().clear();
+        let subscription = subscribe_topic("test-overflow/*");
+        let result = NotificationBuilder::new()
+            .message("first second third")
+            .topic("test-overflow/long")
+            .backend(Backend::Custom("overflow-short".to_string()))
+            .overflow(Overflow::Error)
+            .send();
+        assert!(matches!(
+            result,
+            Err(NotificationError::InvalidInput { .. })
+        ));
+        assert!(subscription.try_recv().is_none());
+        assert!(SENT.lock().unwrap().is_empty());
+        unregister_backend("overflow-short");
+    }
+
+    #[test]
+    fn test_send_with_uses_the_given_notifier() {
+        struct Direct(Mutex<Vec<String>>);
+        impl Notifier for Direct {
+            fn send(&self, notification: &Notification) -> Result<()> {
+                self.0.lock().unwrap().push(notification.message.clone());
+                Ok(())
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Direct"
+            }
+        }
+        let direct = Direct(Mutex::new(Vec::new()));
+        let handle = NotificationBuilder::new()
+            .message("direct")
+            .backend(Backend::Custom("send-with-missing".to_string()))
+            .send_with(&direct)
+            .unwrap();
+        assert_eq!(handle.report().backend, Some("Direct"));
+        assert_eq!(*direct.0.lock().unwrap(), ["direct"]);
+    }
+
+    #[test]
+    fn test_send_to_reports_each_backend() {
+        let results = NotificationBuilder::new()
+            .message("fan-out")
+            .backend(Backend::Dialog)
+            .send_to(&[
+                Backend::Stdout,
+                Backend::Custom("fan-out-missing".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        let (backend, result) = &results[0];
+        assert_eq!(*backend, Backend::Stdout);
+        // ハンドルは送ったバックエンドを覚えている
+        let handle = result.as_ref().unwrap();
+        assert_eq!(
+            handle.notification().backend_override,
+            Some(Backend::Stdout)
+        );
+        assert!(results[1].1.is_err());
+
+        let available = available_backends();
+        assert!(available.contains(&Backend::Console));
+        assert!(!available.contains(&Backend::Stdout));
+    }
+
+    #[test]
+    fn test_send_batch() {
+        // デフォルト実装は deliver() を順に呼ぶ
+        struct Numbered;
+        impl Notifier for Numbered {
+            fn send(&self, _: &Notification) -> Result<()> {
+                Ok(())
+            }
+            fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+                Ok(Some(notification.message.clone()))
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Numbered"
+            }
+        }
+        let batch = ["1", "2"].map(|message| NotificationBuilder::new().message(message).build());
+        let ids: Vec<_> = Numbered
+            .send_batch(&batch)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(ids, [Some("1".to_string()), Some("2".to_string())]);
+
+        let handles = send_batch(["a", "b", "c"].map(|message| {
+            NotificationBuilder::new()
+                .message(message)
+                .backend(Backend::Stdout)
+        }));
+        let messages: Vec<&str> = handles
+            .iter()
+            .map(|handle| handle.as_ref().unwrap().notification().message.as_str())
+            .collect();
+        assert_eq!(messages, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_send_batch_falls_back_per_notification() {
+        // "bad" だけ失敗するバックエンドと、受け取った本文を記録するフォールバック先
+        struct Picky;
+        impl Notifier for Picky {
+            fn send(&self, notification: &Notification) -> Result<()> {
+                match notification.message.as_str() {
+                    "bad" => Err(NotificationError::Other("rejected".to_string())),
+                    _ => Ok(()),
+                }
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Picky"
+            }
+        }
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+        impl Notifier for Recorder {
+            fn send(&self, notification: &Notification) -> Result<()> {
+                self.0.lock().unwrap().push(notification.message.clone());
+                Ok(())
+            }
+            fn is_available(&self) -> bool {
+                true
+            }
+            fn backend_name(&self) -> &'static str {
+                "Recorder"
+            }
+        }
+        let received = Arc::new(Mutex::new(Vec::new()));
+        register_backend("batch-picky", Box::new(Picky));
+        register_backend("batch-recorder", Box::new(Recorder(Arc::clone(&received))));
+
+        let builder = |message: &str, backend: &str| {
+            NotificationBuilder::new()
+                .message(message)
+                .backend(Backend::Custom(backend.to_string()))
+                .fallback_chain([Backend::Custom("batch-recorder".to_string())])
+        };
+        let results = send_batch([
+            builder("ok", "batch-picky"),
+            builder("bad", "batch-picky"),
+            builder("lost", "batch-missing"),
+            builder("fine", "batch-picky"),
+        ]);
+        unregister_backend("batch-picky");
+        unregister_backend("batch-recorder");
+
+        assert_eq!(results.len(), 4);
+        let backends: Vec<_> = results
+            .iter()
+            .map(|result| {
+                result
+                    .as_ref()
+                    .ok()
+                    .and_then(|handle| handle.report().backend)
+            })
+            .collect();
+        assert_eq!(
+            backends,
+            [Some("Picky"), Some("Recorder"), None, Some("Picky")]
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap().report().failed_backend,
+            Some("Picky")
+        );
+        // 表示できた通知は送り直さない
+        assert_eq!(*received.lock().unwrap(), ["bad"]);
+    }
+
+    #[test]
+    fn test_to_builder_round_trip() {
+        let original = NotificationBuilder::new()
+            .title("Deploy")
+            .body_markdown("**ready**")
+            .urgency(UrgencyLevel::Low)
+            .timeout(Duration::from_secs(9))
+            .action("yes", "Ship it")
+            .hint("fgcolor", "#ff0000")
+            .private(true)
+            .command_timeout(Duration::from_secs(1))
+            .build_with_config(&Config::default());
+
+        let rebuilt = original.to_builder().build_with_config(&Config::default());
+        assert_eq!(rebuilt.to_json(), original.to_json());
+        assert_eq!(rebuilt.pango_body(), original.pango_body());
+        assert_eq!(rebuilt.command_timeout, original.command_timeout);
+
+        // 一部だけ変えて送り直せる
+        let changed = NotificationBuilder::from(original)
+            .message("shipped")
+            .build_with_config(&Config::default());
+        assert_eq!(
+            (changed.title.as_str(), changed.message.as_str()),
+            ("Deploy", "shipped")
+        );
+        assert_eq!(changed.timeout, 9000);
+
+        // 緊急度から決まった表示時間は、緊急度を変えると決め直す
+        let received = NotificationBuilder::new()
+            .title("Disk")
+            .build_with_config(&Config::default());
+        let escalated = received
+            .to_builder()
+            .urgency(UrgencyLevel::Critical)
+            .build_with_config(&Config::default());
+        assert_eq!(escalated.timeout, Config::default().critical.timeout);
+    }
+
+    #[test]
+    fn test_body_markdown() {
+        let notification = NotificationBuilder::new()
+            .body_markdown("**3** tests failed")
+            .build();
+        assert_eq!(notification.message, "3 tests failed");
+        assert_eq!(
+            notification.pango_body().as_deref(),
+            Some("<b>3</b> tests failed")
+        );
+
+        // 後から message() を呼ぶとプレーンテキストに戻る
+        let plain = NotificationBuilder::new()
+            .body_markdown("**3**")
+            .message("plain")
+            .build();
+        assert_eq!(plain.message, "plain");
+        assert_eq!(plain.pango_body(), None);
+    }
+
+    #[test]
+    fn test_from_json_round_trip() {
+        let original = NotificationBuilder::new()
+            .title("Deploy")
+            .body_markdown("**ready**")
+            .urgency(UrgencyLevel::Critical)
+            .backend(Backend::Stdout)
+            .action("yes", "Ship it")
+            .locale("ja")
+            .category("deploy.ready")
+            .hint("fgcolor", "#ff0000")
+            .transient(true)
+            .attribution("via deploy.sh")
+            .scenario(Scenario::IncomingCall)
+            .respect_dnd(true)
+            .trace_context(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                    .parse()
+                    .unwrap(),
+            )
+            .build_with_config(&Config::default());
+
+        let parsed = NotificationBuilder::from_json(&original.to_json())
+            .unwrap()
+            .build_with_config(&Config::default());
+        assert_eq!(parsed.to_json(), original.to_json());
+
+        assert!(NotificationBuilder::from_json("[]").is_err());
+        assert!(NotificationBuilder::from_json(r#"{"urgency":"urgent"}"#).is_err());
+        assert!(NotificationBuilder::from_json(r#"{"scenario":"meeting"}"#).is_err());
+
+        // クリックで実行するコマンドは、明示して許可した場合だけ読む
+        let exec = r#"{"message":"Build","on_click_exec":"rm -rf ~"}"#;
+        let err = NotificationBuilder::from_json(exec).unwrap_err();
+        assert!(
+            matches!(err, NotificationError::InvalidInput { ref field, .. } if field == "on_click_exec")
+        );
+        assert!(NotificationBuilder::from_json_many(&format!("[{exec}]")).is_err());
+        assert!(NotificationBuilder::from_toml("on_click_exec = 'make'").is_err());
+        let allowed = NotificationBuilder::from_json_many_with_exec(exec).unwrap();
+        assert_eq!(
+            allowed[0].clone().build().on_click_exec.as_deref(),
+            Some("rm -rf ~")
+        );
+    }
+
+    #[test]
+    fn test_or_defaults_keeps_explicit_values() {
+        let defaults = NotificationBuilder::new()
+            .title("CI")
+            .message("from profile")
+            .urgency(UrgencyLevel::Critical)
+            .hint("a", "profile")
+            .hint("b", "profile");
+        let notification = NotificationBuilder::new()
+            .body_markdown("**done**")
+            .urgency(UrgencyLevel::Normal)
+            .hint("a", "cli")
+            .or_defaults(defaults)
+            .build();
+        assert_eq!(notification.title, "CI");
+        assert_eq!(notification.message, "done");
+        assert_eq!(notification.urgency, UrgencyLevel::Normal);
+        assert_eq!(notification.hints["a"], "cli");
+        assert_eq!(notification.hints["b"], "profile");
+    }
+
+    #[test]
+    fn test_platform_overrides_apply_to_target() {
+        let builder = NotificationBuilder::new()
+            .sound("default")
+            .platform(Platform::MacOs, |b| b.sound("Glass"))
+            .platform(Platform::Windows, |b| b.subtitle("win"));
+
+        let mac = builder
+            .clone()
+            .backend(Platform::MacOs)
+            .build_with_config(&Config::default());
+        assert_eq!((mac.sound(), mac.subtitle()), (Some("Glass"), None));
+
+        // WSL には Windows の変更を適用する
+        let wsl = builder
+            .clone()
+            .backend(Platform::Wsl)
+            .build_with_config(&Config::default());
+        assert_eq!(
+            (wsl.sound(), wsl.subtitle()),
+            (Some("default"), Some("win"))
+        );
+    }
+
+    #[test]
+    fn test_from_toml_and_merge() {
+        let definition = Notification::from_toml(
+            r##"
+            title = "Nightly build"
+            urgency = "low"
+            group = "ci"
+            actions = [{ id = "log", label = "Open log" }]
+
+            [hints]
+            fgcolor = "#00ff00"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(definition.title, "Nightly build");
+        assert_eq!(definition.actions, vec![Action::new("log", "Open log")]);
+
+        let merged = NotificationBuilder::new()
+            .title("Overridden")
+            .message("Kept")
+            .hint("x-extra", "1")
+            .merge(&definition)
+            .urgency(UrgencyLevel::Critical)
+            .build_with_config(&Config::default());
+        // 定義にある項目は上書き、無い項目は Builder の値のまま
+        assert_eq!(merged.title, "Nightly build");
+        assert_eq!(merged.message, "Kept");
+        assert_eq!(merged.group.as_deref(), Some("ci"));
+        assert_eq!(merged.hints.len(), 2);
+        // merge() の後のセッターが優先
+        assert_eq!(merged.urgency, UrgencyLevel::Critical);
+
+        assert!(Notification::from_toml("title = ").is_err());
+    }
+
+    #[test]
+    fn test_scenario_stays_until_dismissed() {
+        let alarm = NotificationBuilder::new()
+            .scenario(Scenario::Alarm)
+            .build_with_config(&Config::default());
+        assert_eq!(alarm.timeout, 0);
+
+        // 表示時間を指定した場合はそちらを使う
+        let reminder = NotificationBuilder::new()
+            .scenario(Scenario::Reminder)
+            .timeout(Duration::from_secs(10))
+            .build_with_config(&Config::default());
+        assert_eq!(reminder.timeout, 10_000);
+        assert_eq!(
+            "incoming-call".parse::<Scenario>().unwrap(),
+            Scenario::IncomingCall
+        );
+    }
+
+    #[test]
+    fn test_private_uses_config_default() {
+        let config = Config {
+            private: true,
+            ..Config::default()
+        };
+        let notification = NotificationBuilder::new()
+            .title("Bank")
+            .message("Your code is 123456")
+            .image("/tmp/qr.png")
+            .build_with_config(&config);
+        assert!(notification.private);
+
+        let public = notification.public_view();
+        assert_eq!(public.title, notification.title);
+        assert_eq!(public.message, PRIVATE_BODY);
+        assert_eq!(public.image, None);
+
+        let opted_out = NotificationBuilder::new()
+            .private(false)
+            .build_with_config(&config);
+        assert!(!opted_out.private);
+    }
+
+    #[test]
+    fn test_build_applies_redactions() {
+        let config = Config {
+            redactions: redact::builtin(),
+            ..Config::default()
+        };
+        let notification = NotificationBuilder::new()
+            .title("token=abc")
+            .body_markdown("**card** 4111-1111-1111-1111")
+            .build_with_config(&config);
+        assert_eq!(notification.title, "token=[REDACTED]");
+        assert_eq!(notification.message, "card [REDACTED]");
+        assert_eq!(
+            notification.markdown.as_deref(),
+            Some("**card** [REDACTED]")
+        );
+    }
+
+    #[test]
+    fn test_backend_from_platform() {
+        let notification = NotificationBuilder::new().backend(Platform::MacOs).build();
+        assert_eq!(
+            notification.backend_override,
+            Some(Backend::Native(Platform::MacOs))
+        );
+        assert_eq!(Backend::Dialog.platform(), None);
+
+        let custom: Backend = "pager".parse().unwrap();
+        assert_eq!(
+            (custom.name(), custom.custom_name()),
+            ("custom", Some("pager"))
+        );
+        assert_eq!(
+            (Backend::Console.name(), Backend::Console.custom_name()),
+            ("console", None)
+        );
+        assert!(" ".parse::<Backend>().is_err());
+    }
+
+    #[test]
+    fn test_custom_backend_json_round_trip() {
+        register_backend("json-pager", Box::new(ConsoleNotifier));
+        let original = NotificationBuilder::new()
+            .message("queued")
+            .backend(Backend::Custom("json-pager".to_string()))
+            .build_with_config(&Config::default());
+        let json = original.to_json();
+        assert!(json.contains(r#""backend":"json-pager""#), "{json}");
+
+        let parsed = Notification::from_json(&json).unwrap();
+        assert_eq!(parsed.backend_override, original.backend_override);
+        unregister_backend("json-pager");
+    }
+
+    #[test]
+    fn test_typed_builder_keeps_fields_across_states() {
+        let ready: NotificationBuilder<Ready> = NotificationBuilder::typed()
+            .title("Deploy")
+            .urgency(UrgencyLevel::Low)
+            .body_markdown("**done**");
+        let notification = ready.clone().build();
+        assert_eq!(notification.title, "Deploy");
+        assert_eq!(notification.urgency, UrgencyLevel::Low);
+        assert_eq!(notification.message, "done");
+
+        // Dynamic を受け取る API にも渡せる
+        let dynamic = ready.into_dynamic().message("changed");
+        assert_eq!(dynamic.build().message, "changed");
+    }
+
+    #[test]
+    fn test_try_build_rejects_invalid_fields() {
+        let field = |builder: NotificationBuilder| match builder.try_build() {
+            Err(NotificationError::InvalidInput { field, .. }) => field,
+            other => panic!("expected InvalidInput, got {other:?}"),
+        };
+
+        assert_eq!(field(NotificationBuilder::new().message("  ")), "message");
+        assert_eq!(
+            field(
+                NotificationBuilder::new()
+                    .message("Hi")
+                    .timeout(Duration::from_secs(2 * 24 * 60 * 60))
+            ),
+            "timeout"
+        );
+        assert_eq!(
+            field(NotificationBuilder::new().message("Hi").sound("Glass ping")),
+            "sound"
+        );
+        assert_eq!(
+            field(
+                NotificationBuilder::new()
+                    .message("Hi")
+                    .icon("/nonexistent/rust-toast/icon.png")
+            ),
+            "icon"
+        );
+    }
+
+    #[test]
+    fn test_try_build_accepts_valid_notification() {
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .sound("ms-winsoundevent:Notification.Mail")
+            .timeout(Duration::ZERO)
+            .try_build()
+            .unwrap();
+        assert_eq!(notification.title, "Build");
+        // 無音なら通知音の名前は確認しない
+        assert!(NotificationBuilder::new()
+            .message("Hi")
+            .sound("not a name")
+            .silent(true)
+            .try_build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_notification_to_json() {
+        let json = NotificationBuilder::new()
+            .title("Say \"hi\"")
+            .message("Line1\nLine2")
+            .urgency(UrgencyLevel::Low)
+            .backend(Backend::Stdout)
+            .build()
+            .to_json();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["title"], "Say \"hi\"");
+        assert_eq!(value["message"], "Line1\nLine2");
+        // 低緊急度のデフォルトの表示時間
+        assert_eq!(value["timeout"], 3000);
+        assert_eq!(value["urgency"], "low");
+        assert_eq!(value["backend"], "stdout");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let original = NotificationBuilder::new()
+            .title("Deploy")
+            .message("Finished")
+            .urgency(UrgencyLevel::Critical)
+            .backend(Platform::MacOs)
+            .action("open", "Open")
+            .scenario(Scenario::IncomingCall)
+            .expiration(Duration::from_secs(60))
+            .trace_context(TraceContext::new(1, 2))
+            .build();
+        let json = serde_json::to_string(&original).unwrap();
+        assert!(json.contains(r#""urgency":"critical""#));
+        assert!(json.contains(r#""backend_override":"macos""#));
+        assert!(json.contains(r#""scenario":"incoming-call""#));
+
+        let parsed: Notification = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.trace_context, original.trace_context);
+        assert_eq!(parsed.to_json(), original.to_json());
+
+        // 無いフィールドはデフォルト値
+        let partial: Notification = serde_json::from_str(r#"{"message":"Hi"}"#).unwrap();
+        assert_eq!(partial.message, "Hi");
+        assert_eq!(partial.urgency, UrgencyLevel::Normal);
+        assert_eq!(
+            serde_json::from_str::<Platform>(r#""wsl""#).unwrap(),
+            Platform::Wsl
+        );
+    }
+
+    #[test]
+    fn test_builder_actions_keep_order() {
+        let notification = NotificationBuilder::new()
+            .action("open", "Open")
+            .action("dismiss", "Dismiss")
+            .build();
+
+        assert_eq!(
+            notification.actions,
+            vec![
+                Action::new("open", "Open"),
+                Action::new("dismiss", "Dismiss")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interaction_round_trip() {
+        let interactions = [
+            Interaction::Clicked,
+            Interaction::ActionInvoked("yes".to_string()),
+            Interaction::Replied("on my way: 5 min".to_string()),
+            Interaction::Dismissed,
+            Interaction::Expired,
+        ];
+        for interaction in interactions {
+            assert_eq!(
+                interaction.to_string().parse::<Interaction>().unwrap(),
+                interaction
+            );
+        }
+        assert!("unknown".parse::<Interaction>().is_err());
+    }
+
+    #[test]
+    fn test_send_and_wait_unsupported_backend() {
+        let notification = NotificationBuilder::new().build();
+        assert!(StdoutNotifier.send_and_wait(&notification).is_err());
+    }
+
     #[test]
     fn test_urgency_level_default() {
         let urgency = UrgencyLevel::default();
@@ -0,0 +1,302 @@
+//! 非同期の送信（`async` フィーチャー）
+//!
+//! PowerShell の起動には 1 秒近くかかることがあり、`send()` をそのまま
+//! 非同期のアプリケーションから呼ぶと tokio のワーカースレッドが止まります。
+//! `NotificationBuilder::send_async()` は外部コマンドを `tokio::process::Command` で、
+//! Linux の D-Bus 呼び出しを zbus の非同期 API（notify-rust の `show_async()`）で待ちます。
+//!
+//! | 処理 | 実行のしかた |
+//! |------|-------------|
+//! | Windows / WSL（PowerShell） | `tokio::process::Command` |
+//! | macOS（osascript） | `tokio::process::Command`（alerter / terminal-notifier はブロッキング） |
+//! | Linux（D-Bus） | `show_async()` |
+//! | 上記以外のバックエンド、履歴の記録、フォールバック | `spawn_blocking` |
+//!
+//! # 学習ポイント
+//! - `Pin<Box<dyn Future>>` による、トレイトオブジェクトにできる非同期メソッド
+//! - `tokio::task::spawn_blocking` でブロッキング処理を専用スレッドに逃がす
+//! - `std::process::Command` から `tokio::process::Command` への変換（`From`）
+
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Output;
+use std::time::Duration;
+
+use tracing::Instrument;
+
+use crate::clock;
+use crate::config;
+use crate::error::{NotificationError, Result};
+use crate::notifier::{
+    avoid_slow_backend, coalesce, create_notifier, deliver_rest, deliver_with_retry,
+    fit_to_backend, latency, limit, log_backend, notifier_for, prepare_send, record_delivery,
+    registry, remember_id, retry, retry_spool, selected_backend, send_also, send_fallback, spool,
+    spools, Backend, LinuxNotifier, MacOsNotifier, Notification, NotificationHandle, Notifier,
+    SendReport, WindowsNotifier,
+};
+use crate::platform::Platform;
+use crate::process;
+use crate::store::history::Delivery;
+
+/// `AsyncNotifier::deliver_async()` が返す Future
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 非同期に送信できるバックエンド
+///
+/// `Notifier::deliver()` の非同期版です。`Box<dyn AsyncNotifier>` として扱えるように、
+/// `async fn` ではなく `BoxFuture` を返します。
+pub trait AsyncNotifier: Notifier + Send + Sync {
+    /// 通知を送信し、バックエンドが割り当てた ID を返す（`Notifier::deliver()` と同じ）
+    fn deliver_async<'a>(
+        &'a self,
+        notification: &'a Notification,
+    ) -> BoxFuture<'a, Result<Option<String>>>;
+}
+
+/// バックエンドに対応する `AsyncNotifier`（非同期に送れないバックエンドは `None`）
+///
+/// 同じ名前で登録されたバックエンドがあれば、それを `spawn_blocking` で使うので `None` です。
+pub fn async_notifier_for(backend: &Backend) -> Option<Box<dyn AsyncNotifier>> {
+    if registry::is_registered(backend.key()) {
+        return None;
+    }
+    match backend {
+        #[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+        Backend::Native(Platform::Linux) => Some(Box::new(LinuxNotifier)),
+        Backend::Native(Platform::Wsl | Platform::Windows) => Some(Box::new(WindowsNotifier)),
+        Backend::Native(Platform::MacOs) => Some(Box::new(MacOsNotifier)),
+        _ => None,
+    }
+}
+
+/// ブロッキング処理を tokio の専用スレッドで実行して待つ（パニックはそのまま伝える）
+pub(crate) async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// `process::output_within()` の非同期版（時間切れの場合は子プロセスを強制終了する）
+///
+/// tokio のタイマーを使うため、ランタイムは `enable_time()`（`enable_all()`）で作る必要があります。
+pub(crate) async fn output_within(
+    command: std::process::Command,
+    timeout: Duration,
+) -> Result<Output> {
+    let program = process::program_name(&command);
+    let span = tracing::debug_span!("command", %program);
+    let mut command = tokio::process::Command::from(command);
+    // 時間切れで Future が破棄された時に、子プロセスも終了させる
+    command.kill_on_drop(true);
+    let started = std::time::Instant::now();
+    let output = tokio::time::timeout(timeout, command.output())
+        .instrument(span.clone())
+        .await;
+    let _entered = span.enter();
+    match output {
+        Ok(output) => {
+            let output = output?;
+            tracing::debug!(status = %output.status, elapsed = ?started.elapsed(), "finished");
+            Ok(output)
+        }
+        Err(_) => {
+            tracing::debug!(?timeout, "timed out, killed");
+            Err(NotificationError::Timeout {
+                backend: None,
+                program,
+                elapsed: timeout,
+            })
+        }
+    }
+}
+
+/// `NotificationBuilder::send_async()` の本体（`send()` と同じ手順）
+pub(super) async fn send(original: Notification) -> Result<NotificationHandle> {
+    let started = clock::current().now();
+    let config = config::current();
+    let Some(notification) = coalesce(&config, &original) else {
+        return Ok(NotificationHandle::new(None, original));
+    };
+    // 履歴の記録や画面ロックの確認はブロッキングなので、配送の準備までを専用スレッドで行う
+    let prepared = notification.clone();
+    let Some(selected) = blocking(move || prepare(&prepared)).await? else {
+        return Ok(NotificationHandle::new(None, notification));
+    };
+    let Selected {
+        backend,
+        name: primary,
+        history,
+        fitted,
+        rest,
+    } = selected;
+
+    let result = match async_notifier_for(&backend) {
+        Some(notifier) => deliver_async_with_retry(notifier.as_ref(), &fitted).await,
+        None => {
+            let sent = fitted.clone();
+            let backend = backend.clone();
+            blocking(move || deliver_with_retry(create_notifier(backend).as_ref(), &sent)).await
+        }
+    };
+
+    let delivery = match &result {
+        Ok(id) => Delivery::sent(primary, id.clone()),
+        Err(err) => Delivery::failed(primary, err),
+    };
+    blocking(move || record_delivery(history, delivery)).await;
+
+    let mut report = match result {
+        Ok(id) => {
+            remember_id(&config, &original, id.as_deref());
+            if !rest.is_empty() {
+                blocking(move || deliver_rest(create_notifier(backend).as_ref(), &rest)).await;
+            }
+            if config.spool {
+                blocking(move || retry_spool(&config)).await;
+            }
+            SendReport {
+                backend: Some(primary),
+                id,
+                ..SendReport::default()
+            }
+        }
+        Err(err) if notification.backend_override == Some(Backend::Stdout) => return Err(err),
+        Err(err) if spools(&config, &notification) => {
+            let report = SendReport::default().failed(primary, &err);
+            let sent = notification.clone();
+            blocking(move || spool(&sent, err)).await?;
+            report
+        }
+        Err(err) => {
+            let mut report = SendReport::default().failed(primary, &err);
+            let sent = notification.clone();
+            let fallback = blocking(move || {
+                let fallback = send_fallback(&sent, primary, err)?;
+                record_delivery(history, Delivery::sent(fallback, None));
+                Ok::<_, NotificationError>(fallback)
+            })
+            .await?;
+            remember_id(&config, &original, None);
+            report.backend = Some(fallback);
+            report
+        }
+    };
+    report.elapsed = clock::current()
+        .now()
+        .duration_since(started)
+        .unwrap_or_default();
+    Ok(NotificationHandle::new(None, fitted).with_report(report))
+}
+
+/// `prepare()` で選んだバックエンドと、送る通知
+struct Selected {
+    backend: Backend,
+    /// バックエンドの名前（`Notifier::backend_name()`）
+    name: &'static str,
+    /// 履歴に記録した連番
+    history: Option<u64>,
+    /// 長さの上限に合わせた通知（`fit_to_backend()`）
+    fitted: Notification,
+    /// 分けた通知の残り
+    rest: Vec<Notification>,
+}
+
+/// 配送より前の処理と、バックエンドの選択（表示しない通知は `None`）
+fn prepare(notification: &Notification) -> Result<Option<Selected>> {
+    let config = config::current();
+    let backend = avoid_slow_backend(&config, notification, selected_backend(notification));
+    let notifier = notifier_for(backend.clone())?;
+    let name = notifier.backend_name();
+    // 長さが合わない通知（Overflow::Error）は、購読者・履歴・送信数に残す前に止める（`send()` と同じ）
+    let (fitted, rest) = fit_to_backend(notification, notifier.as_ref())?;
+    let Some(prepared) = prepare_send(&config, notification)? else {
+        return Ok(None);
+    };
+    log_backend(notification, notifier.as_ref());
+    send_also(notification, name, &prepared.route.also);
+    Ok(Some(Selected {
+        backend,
+        name,
+        history: prepared.history,
+        fitted,
+        rest,
+    }))
+}
+
+/// `deliver_with_retry()` の非同期版（待つ時間と回数は `retry::next_delay()` で決める）
+async fn deliver_async_with_retry(
+    notifier: &dyn AsyncNotifier,
+    notification: &Notification,
+) -> Result<Option<String>> {
+    let mut attempt = 0;
+    loop {
+        match deliver_async_with_limit(notifier, notification).await {
+            Err(err) => match retry::next_delay(&notification.retry, &err, &mut attempt) {
+                Some(delay) => blocking(move || clock::current().sleep(delay)).await,
+                None => return Err(err),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// `deliver_with_limit()` の非同期版（同時実行数の制限と、応答時間の記録）
+async fn deliver_async_with_limit(
+    notifier: &dyn AsyncNotifier,
+    notification: &Notification,
+) -> Result<Option<String>> {
+    let name = notifier.backend_name();
+    let span = tracing::debug_span!("deliver", backend = name);
+    // 許可を待つ間もランタイムを止めない
+    let _permit = blocking(move || limit::acquire(name)).await;
+    let started = clock::current().now();
+    let result = notifier
+        .deliver_async(notification)
+        .instrument(span.clone())
+        .await
+        .map_err(|err| err.in_backend(name));
+    if let Ok(elapsed) = clock::current().now().duration_since(started) {
+        latency::global_tracker().record(name, elapsed);
+        span.in_scope(|| tracing::debug!(?elapsed, ok = result.is_ok(), "delivered"));
+    }
+    result
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotificationBuilder;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_send_async_with_blocking_backend() {
+        let future = NotificationBuilder::new()
+            .message("async")
+            .backend(Backend::Stdout)
+            .send_async();
+        // tokio::spawn() に渡せる（Send な Future）
+        fn assert_send<T: Send>(_: &T) {}
+        assert_send(&future);
+
+        let handle = runtime().block_on(future).unwrap();
+        assert_eq!(handle.id(), None);
+        assert_eq!(handle.notification().message, "async");
+    }
+
+    #[test]
+    fn test_async_notifier_for_native_backends() {
+        assert!(async_notifier_for(&Backend::Native(Platform::Windows)).is_some());
+        assert!(async_notifier_for(&Backend::Dialog).is_none());
+        assert!(async_notifier_for(&Backend::Stdout).is_none());
+    }
+}
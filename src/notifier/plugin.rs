@@ -0,0 +1,252 @@
+//! 外部プログラムのプラグイン
+//!
+//! 特定のサービス（社内チャット、ポケベルなど）への配送を本体に入れずに済むように、
+//! プラグインディレクトリ（`<data_dir>/plugins`）にある実行ファイルをバックエンドとして登録します。
+//! ファイル名（拡張子を除く）がバックエンドの名前になり、`--backend <名前>` で選べます。
+//!
+//! ## 約束事
+//!
+//! | 方向 | 内容 |
+//! |------|------|
+//! | 標準入力 | 通知の JSON 1 行（`Notification::to_json()`、`--backend stdout` と同じ） |
+//! | 終了コード | 0 なら成功、それ以外は失敗（標準エラー出力が理由になる） |
+//! | 標準出力 | 最初の空でない行を通知 ID として扱う（省略可） |
+//!
+//! ```sh
+//! #!/bin/sh
+//! # <data_dir>/plugins/chat
+//! jq -r .message | curl -s -d @- https://chat.example.com/hooks/builds
+//! ```
+//!
+//! 実行には `Config::helpers` の実行ポリシー（ファイル名で探す）と、
+//! 通知ごとの `command_timeout` が適用されます。
+//!
+//! # 学習ポイント
+//! - 実行時の発見（`read_dir`）と、登録によるトレイトオブジェクトの追加
+//! - 標準入出力を使ったプロセス間の簡単なプロトコル
+
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::error::{NotificationError, Result};
+use crate::notifier::{register_backend, Backend, Invocation, Notification, Notifier};
+use crate::process;
+use crate::store;
+
+/// プラグインディレクトリの名前（データディレクトリの直下）
+pub const PLUGIN_DIR_NAME: &str = "plugins";
+
+/// プラグインディレクトリのパス
+pub fn plugin_dir() -> PathBuf {
+    store::data_dir().join(PLUGIN_DIR_NAME)
+}
+
+/// 実行ファイル 1 つのプラグイン
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginNotifier {
+    name: String,
+    path: PathBuf,
+}
+
+impl PluginNotifier {
+    /// 実行ファイルのパスから作成（名前はファイル名から拡張子を除いたもの）
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self { name, path }
+    }
+
+    /// バックエンドの名前
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 実行ファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Notifier for PluginNotifier {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        self.deliver(notification).map(drop)
+    }
+
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        process::check_path(&self.path)?;
+        let input = format!("{}\n", notification.to_json());
+        // 設定ファイルの [plugins.<名前>] の環境変数（認証情報など）を渡す
+        let mut command = std::process::Command::new(&self.path);
+        if let Some(env) = config::current().plugin_env.get(&self.name) {
+            command.envs(env);
+        }
+        let output =
+            process::output_with_input(command, input.as_bytes(), notification.command_timeout)?;
+        if !output.status.success() {
+            return Err(NotificationError::SendFailed {
+                backend: self.name.clone(),
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string))
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        Ok(Invocation::Command {
+            program: self.path.to_string_lossy().to_string(),
+            args: Vec::new(),
+            stdin: Some(notification.to_json()),
+        })
+    }
+
+    fn is_available(&self) -> bool {
+        is_executable(&self.path)
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "Plugin"
+    }
+}
+
+/// ディレクトリにあるプラグイン（名前順、隠しファイルと実行できないファイルは除く）
+///
+/// ディレクトリが無い場合は空です。
+pub fn discover(dir: &Path) -> Vec<PluginNotifier> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<PluginNotifier> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| is_executable(path))
+        .map(PluginNotifier::new)
+        .filter(|plugin| !plugin.name.is_empty() && !plugin.name.starts_with('.'))
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+/// プラグインディレクトリのプラグインを登録し、登録した名前を返す
+///
+/// 組み込みのバックエンドと同じ名前（`linux` など）のファイルは、置き換えずに警告して読み飛ばします。
+/// CLI は引数を解析する前に呼びます。ライブラリとして使う場合は必要な時に呼んでください。
+pub fn load_plugins() -> Vec<String> {
+    load_from(&plugin_dir())
+}
+
+fn load_from(dir: &Path) -> Vec<String> {
+    discover(dir)
+        .into_iter()
+        .filter_map(|plugin| {
+            if !matches!(plugin.name.parse::<Backend>(), Ok(Backend::Custom(_))) {
+                tracing::warn!(
+                    "plugin {} is skipped: it has the name of a built-in backend",
+                    plugin.path.display()
+                );
+                return None;
+            }
+            let name = plugin.name.clone();
+            register_backend(name.clone(), Box::new(plugin));
+            Some(name)
+        })
+        .collect()
+}
+
+/// 実行できるファイルか（Unix では実行権限、それ以外では拡張子を見る）
+fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        const EXTENSIONS: [&str; 3] = ["exe", "bat", "cmd"];
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        metadata.is_file()
+            && extension.is_some_and(|extension| {
+                EXTENSIONS
+                    .iter()
+                    .any(|known| extension.eq_ignore_ascii_case(known))
+            })
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::notifier::{unregister_backend, NotificationBuilder};
+    use std::os::unix::fs::PermissionsExt;
+
+    fn write_script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_discover_executables() {
+        let dir = tempfile::tempdir().unwrap();
+        write_script(dir.path(), "pager.sh", "exit 0");
+        write_script(dir.path(), "chat", "exit 0");
+        write_script(dir.path(), ".hidden", "exit 0");
+        std::fs::write(dir.path().join("README"), "not a plugin").unwrap();
+
+        let names: Vec<_> = discover(dir.path())
+            .iter()
+            .map(|plugin| plugin.name().to_string())
+            .collect();
+        assert_eq!(names, ["chat", "pager"]);
+        assert!(discover(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_load_skips_builtin_names() {
+        let dir = tempfile::tempdir().unwrap();
+        write_script(dir.path(), "console", "exit 0");
+        write_script(dir.path(), "linux.sh", "exit 0");
+        write_script(dir.path(), "plugin-test-pager", "exit 0");
+
+        assert_eq!(load_from(dir.path()), ["plugin-test-pager"]);
+        assert!(unregister_backend("plugin-test-pager"));
+        assert!(!unregister_backend("console"));
+    }
+
+    #[test]
+    fn test_plugin_receives_json_and_returns_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let received = dir.path().join("received.json");
+        let plugin = PluginNotifier::new(write_script(
+            dir.path(),
+            "record",
+            &format!("cat > '{}'; echo; echo id-42", received.display()),
+        ));
+        let notification = NotificationBuilder::new().message("plugged").build();
+        assert_eq!(
+            plugin.deliver(&notification).unwrap().as_deref(),
+            Some("id-42")
+        );
+        let json = std::fs::read_to_string(&received).unwrap();
+        assert_eq!(json.trim(), notification.to_json());
+
+        let failing =
+            PluginNotifier::new(write_script(dir.path(), "fail", "echo down >&2; exit 3"));
+        let err = failing.deliver(&notification).unwrap_err();
+        assert!(err.to_string().contains("down"), "{err}");
+    }
+}
@@ -0,0 +1,130 @@
+//! Linux の通知デーモンごとの癖（quirks）
+//!
+//! freedesktop の通知仕様は解釈の幅が広く、通知デーモンによって表示が変わります。
+//! `GetServerInformation` で返る名前から実行中のデーモンを判定し、
+//! 既知の癖を送信時に自動で補います。
+//!
+//! | デーモン | 名前 | 癖 |
+//! |----------|------|----|
+//! | mako | `mako` | `<a>` を含むとマークアップ全体が文字のまま表示される |
+//! | GNOME Shell | `gnome-shell` | `app_icon` のファイルパスを表示せず、`image-path` ヒントなら表示する |
+//! | KDE Plasma | `Plasma` | 緊急度が重要でないとタイムアウト 0 でもポップアップを閉じる |
+//! | dunst | `dunst` | なし |
+//!
+//! 組み込みの表は `Config::server_quirks` で名前ごとに上書き・追加できます。
+//!
+//! # 例
+//! ```
+//! use rust_toast::config::{self, Config};
+//! use rust_toast::notifier::ServerQuirks;
+//!
+//! let mut config = Config::default();
+//! config.server_quirks.insert(
+//!     "my-daemon".to_string(),
+//!     ServerQuirks {
+//!         markup_links: false,
+//!         ..ServerQuirks::default()
+//!     },
+//! );
+//! config::set(config);
+//! ```
+//!
+//! # 学習ポイント
+//! - 振る舞いの違いをコードの分岐ではなくデータ（表）で表す
+//! - 組み込みの表と利用者の設定のマージ（後から入れた方が優先）
+
+use std::collections::BTreeMap;
+
+use crate::config::Config;
+
+/// 通知デーモンの癖と、それを補うための送信方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerQuirks {
+    /// Markdown の本文のリンクを `<a>` で送る（`false` なら「文字 (URL)」）
+    pub markup_links: bool,
+    /// ファイルパスのアイコンを `image-path` ヒントでも渡す（画像が無い場合）
+    pub icon_as_image: bool,
+    /// 閉じるまで表示（タイムアウト 0）の通知を、緊急度を重要にして送る
+    pub sticky_needs_critical: bool,
+}
+
+impl Default for ServerQuirks {
+    /// 仕様どおりに動く通知デーモン（補正なし）
+    fn default() -> Self {
+        Self {
+            markup_links: true,
+            icon_as_image: false,
+            sticky_needs_critical: false,
+        }
+    }
+}
+
+/// 既知の通知デーモンの癖（キーは `GetServerInformation` の名前を小文字にしたもの）
+pub fn builtin() -> BTreeMap<String, ServerQuirks> {
+    let default = ServerQuirks::default();
+    [
+        (
+            "mako",
+            ServerQuirks {
+                markup_links: false,
+                ..default
+            },
+        ),
+        (
+            "gnome-shell",
+            ServerQuirks {
+                icon_as_image: true,
+                ..default
+            },
+        ),
+        (
+            "plasma",
+            ServerQuirks {
+                sticky_needs_critical: true,
+                ..default
+            },
+        ),
+        ("dunst", default),
+    ]
+    .into_iter()
+    .map(|(name, quirks)| (name.to_string(), quirks))
+    .collect()
+}
+
+/// 通知デーモンの名前に対応する癖
+///
+/// 名前は大文字・小文字を区別しません。`Config::server_quirks` は組み込みの表より優先し、
+/// どちらにも無ければ補正なし（`ServerQuirks::default()`）です。
+pub fn for_server(config: &Config, name: &str) -> ServerQuirks {
+    let name = name.to_lowercase();
+    config
+        .server_quirks
+        .iter()
+        .find(|(key, _)| key.to_lowercase() == name)
+        .map(|(_, quirks)| *quirks)
+        .or_else(|| builtin().get(&name).copied())
+        .unwrap_or_default()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_server() {
+        let mut config = Config::default();
+        assert!(!for_server(&config, "mako").markup_links);
+        assert!(for_server(&config, "Plasma").sticky_needs_critical);
+        assert_eq!(for_server(&config, "unknown"), ServerQuirks::default());
+
+        // 設定は組み込みの表より優先する
+        config
+            .server_quirks
+            .insert("Mako".to_string(), ServerQuirks::default());
+        assert!(for_server(&config, "mako").markup_links);
+    }
+}
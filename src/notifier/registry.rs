@@ -0,0 +1,230 @@
+//! 独自のバックエンドの登録
+//!
+//! rust-toast を変更せずに、別のクレートで作った配送方法（社内のチャットなど）を
+//! 使えるようにします。登録したバックエンドは `Backend::Custom(名前)` で指定でき、
+//! 組み込みのバックエンドと同じ名前（`"linux"` など）で登録すると、そちらを置き換えます。
+//!
+//! # 例
+//! ```
+//! use rust_toast::notifier::{register_backend, unregister_backend};
+//! use rust_toast::{Backend, Notification, NotificationBuilder, Notifier, Result};
+//!
+//! struct Chat;
+//!
+//! impl Notifier for Chat {
+//!     fn send(&self, notification: &Notification) -> Result<()> {
+//!         println!("[chat] {}", notification.message);
+//!         Ok(())
+//!     }
+//!     fn is_available(&self) -> bool {
+//!         true
+//!     }
+//!     fn backend_name(&self) -> &'static str {
+//!         "Chat"
+//!     }
+//! }
+//!
+//! register_backend("chat", Box::new(Chat));
+//! NotificationBuilder::new()
+//!     .message("Deployed")
+//!     .backend(Backend::Custom("chat".to_string()))
+//!     .send()?;
+//! assert!(unregister_backend("chat"));
+//! # Ok::<(), rust_toast::NotificationError>(())
+//! ```
+//!
+//! # 学習ポイント
+//! - `Arc<dyn Trait + Send + Sync>` でトレイトオブジェクトをスレッド間で共有する
+//! - 委譲（delegation）による、共有したオブジェクトへのトレイトの実装
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{Capabilities, Interaction, Invocation, Notification, Notifier};
+
+/// 登録されたバックエンド（プロセス全体で共有する）
+type Shared = Arc<dyn Notifier + Send + Sync>;
+
+fn registry() -> &'static RwLock<BTreeMap<String, Shared>> {
+    static REGISTRY: OnceLock<RwLock<BTreeMap<String, Shared>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+/// バックエンドを名前で登録する（同じ名前の登録は置き換える）
+///
+/// 組み込みのバックエンドの名前（`Backend::name()`）で登録すると、組み込みの実装より優先されます。
+pub fn register_backend(name: impl Into<String>, notifier: Box<dyn Notifier + Send + Sync>) {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.into(), Arc::from(notifier));
+}
+
+/// 登録を取り消す（登録されていれば `true`）
+pub fn unregister_backend(name: &str) -> bool {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name)
+        .is_some()
+}
+
+/// 登録されているバックエンドの名前（名前順）
+pub fn registered_backends() -> Vec<String> {
+    registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// その名前のバックエンドが登録されているか
+pub(crate) fn is_registered(name: &str) -> bool {
+    registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains_key(name)
+}
+
+/// 名前で登録されたバックエンド
+pub(crate) fn registered(name: &str) -> Option<Box<dyn Notifier>> {
+    let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+    let notifier = registry.get(name)?;
+    Some(Box::new(Registered(Arc::clone(notifier))))
+}
+
+/// 登録されたバックエンドを `Box<dyn Notifier>` として扱うための委譲
+struct Registered(Shared);
+
+impl Notifier for Registered {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        self.0.send(notification)
+    }
+
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        self.0.deliver(notification)
+    }
+
+    fn send_batch(&self, notifications: &[Notification]) -> Vec<Result<Option<String>>> {
+        self.0.send_batch(notifications)
+    }
+
+    fn is_available(&self) -> bool {
+        self.0.is_available()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.0.backend_name()
+    }
+
+    fn warm_up(&self) -> Result<()> {
+        self.0.warm_up()
+    }
+
+    fn close(&self, id: &str) -> Result<()> {
+        self.0.close(id)
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        self.0.render(notification)
+    }
+
+    fn send_and_wait(&self, notification: &Notification) -> Result<Interaction> {
+        self.0.send_and_wait(notification)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
+    }
+
+    fn repeats_sound_natively(&self) -> bool {
+        self.0.repeats_sound_natively()
+    }
+
+    fn opens_urls_natively(&self) -> bool {
+        self.0.opens_urls_natively()
+    }
+}
+
+/// 登録されていない `Backend::Custom`（常に利用不可）
+pub(crate) struct Unregistered(pub(crate) String);
+
+impl Unregistered {
+    /// 登録されていないことを表すエラー
+    fn error(&self) -> NotificationError {
+        NotificationError::Other(format!("backend '{}' is not registered", self.0))
+    }
+}
+
+impl Notifier for Unregistered {
+    fn send(&self, _notification: &Notification) -> Result<()> {
+        Err(self.error())
+    }
+
+    fn render(&self, _notification: &Notification) -> Result<Invocation> {
+        Err(self.error())
+    }
+
+    fn is_available(&self) -> bool {
+        false
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "Unregistered"
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::{Backend, NotificationBuilder};
+    use std::sync::Mutex;
+
+    /// 受け取った本文を記録するバックエンド
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Recorder {
+        fn send(&self, notification: &Notification) -> Result<()> {
+            self.0.lock().unwrap().push(notification.message.clone());
+            Ok(())
+        }
+        fn is_available(&self) -> bool {
+            true
+        }
+        fn backend_name(&self) -> &'static str {
+            "Recorder"
+        }
+    }
+
+    #[test]
+    fn test_registered_backend_receives_notifications() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        register_backend("test-recorder", Box::new(Recorder(Arc::clone(&received))));
+        assert!(registered_backends().contains(&"test-recorder".to_string()));
+
+        let backend: Backend = "test-recorder".parse().unwrap();
+        assert_eq!(backend, Backend::Custom("test-recorder".to_string()));
+        NotificationBuilder::new()
+            .message("hello")
+            .backend(backend.clone())
+            .send()
+            .unwrap();
+        assert_eq!(*received.lock().unwrap(), ["hello"]);
+
+        assert!(unregister_backend("test-recorder"));
+        // 名前の解析は登録に左右されない
+        assert_eq!("test-recorder".parse::<Backend>().unwrap(), backend);
+        // 登録が無くなったバックエンドには送れない
+        assert!(NotificationBuilder::new()
+            .message("gone")
+            .backend(backend)
+            .send()
+            .is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! 配送内容の書き出し（ドライラン）
+//!
+//! `NotificationBuilder::render()` は通知を送らずに、バックエンドが実際に使う
+//! PowerShell のスクリプト・osascript の式・D-Bus の `Notify` の引数を返します。
+//! エスケープの問題の調査や、ゴールデンテスト（期待する出力との比較）に使います。
+//!
+//! | バックエンド | 書き出す内容 |
+//! |-------------|-------------|
+//! | Linux | D-Bus の `Notify` の引数（`DBusNotify`） |
+//! | Windows / WSL | `powershell.exe` のコマンドライン |
+//! | macOS | `osascript` / `alerter` / `terminal-notifier` のコマンドライン |
+//! | Dialog | `zenity` / `kdialog` / PowerShell / osascript のコマンドライン |
+//! | Plugin | プラグインのパスと、標準入力に渡す JSON |
+//! | Console / Stdout | 出力する文字列 |
+//!
+//! 書き出すだけで、コマンドの起動・D-Bus の呼び出し・ダウンロードは何もしません。
+//! そのため、同じ通知からはいつも同じ内容になります（ゴールデンテストに使えるように）。
+//!
+//! - トーストの Tag・alerter の group など送るたびに作る ID は、固定の `RENDER_ID` にします
+//! - Linux は通知デーモンに問い合わせず、仕様どおりのデーモン（癖なし、マークアップ対応）の場合の引数です
+//! - 画面のロック中に本文を隠す処理、URL・データ URI のアイコンの取得、WSL のパスの変換（`wslpath`）は行いません
+//! - 通知音のファイルを鳴らすコマンド（`afplay`）など、通知とは別に起動するものは含みません
+//!
+//! # 学習ポイント
+//! - コマンドと引数をデータとして扱う（`DialogCommand` と同じ考え方）
+//! - `Display` で人が読む形式、フィールドでテストから使う形式の両方を提供する
+
+use std::fmt;
+
+/// 書き出す時に、送るたびに作る ID（トーストの Tag など）の代わりに使う値
+pub(crate) const RENDER_ID: &str = "rust-toast-render";
+
+/// バックエンドが通知を表示するために行う呼び出し
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invocation {
+    /// 外部コマンドの起動
+    Command {
+        /// 実行するプログラム
+        program: String,
+        /// 引数
+        args: Vec<String>,
+        /// 標準入力に渡す内容
+        stdin: Option<String>,
+    },
+    /// D-Bus の `org.freedesktop.Notifications.Notify` の呼び出し
+    DBus(DBusNotify),
+    /// 端末への出力（Console は stderr、Stdout は stdout）
+    Output(String),
+}
+
+impl Invocation {
+    /// 引数だけのコマンド
+    pub(crate) fn command(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self::Command {
+            program: program.into(),
+            args,
+            stdin: None,
+        }
+    }
+}
+
+/// `Notify` の引数（freedesktop の通知仕様の順）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DBusNotify {
+    /// `app_name`
+    pub app_name: String,
+    /// `replaces_id`（0 は新しい通知）
+    pub replaces_id: u32,
+    /// `app_icon`
+    pub app_icon: String,
+    /// `summary`
+    pub summary: String,
+    /// `body`（Pango マークアップの場合がある）
+    pub body: String,
+    /// `actions`（ID とラベルを交互に並べたもの）
+    pub actions: Vec<String>,
+    /// `hints`（名前と、GVariant のテキスト形式の値。名前順）
+    pub hints: Vec<(String, String)>,
+    /// `expire_timeout`（ミリ秒。-1 はデーモンの既定、0 は閉じるまで）
+    pub expire_timeout: i32,
+}
+
+/// 通知を送らずに書き出した配送内容（`NotificationBuilder::render()` の戻り値）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedDelivery {
+    /// 使われるバックエンドの名前（`Notifier::backend_name()`）
+    pub backend: &'static str,
+    /// バックエンドが行う呼び出し
+    pub invocation: Invocation,
+}
+
+impl fmt::Display for RenderedDelivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# backend: {}", self.backend)?;
+        write!(f, "{}", self.invocation)
+    }
+}
+
+/// コマンドはシェルに貼り付けられる形（引数を `'...'` で囲む）、D-Bus は 1 行に 1 つの引数
+impl fmt::Display for Invocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command {
+                program,
+                args,
+                stdin,
+            } => {
+                write!(f, "{}", shell_quote(program))?;
+                for arg in args {
+                    write!(f, " {}", shell_quote(arg))?;
+                }
+                if let Some(stdin) = stdin {
+                    write!(f, " <<'EOF'\n{stdin}\nEOF")?;
+                }
+                Ok(())
+            }
+            Self::DBus(notify) => {
+                writeln!(f, "app_name:       {:?}", notify.app_name)?;
+                writeln!(f, "replaces_id:    {}", notify.replaces_id)?;
+                writeln!(f, "app_icon:       {:?}", notify.app_icon)?;
+                writeln!(f, "summary:        {:?}", notify.summary)?;
+                writeln!(f, "body:           {:?}", notify.body)?;
+                writeln!(f, "actions:        {:?}", notify.actions)?;
+                writeln!(f, "hints:")?;
+                for (name, value) in &notify.hints {
+                    writeln!(f, "  {name}: {value}")?;
+                }
+                write!(f, "expire_timeout: {}", notify.expire_timeout)
+            }
+            Self::Output(text) => write!(f, "{}", text.trim_end_matches('\n')),
+        }
+    }
+}
+
+/// POSIX シェル向けに引数を囲む（記号を含まない引数はそのまま）
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_quotes_arguments() {
+        let rendered = RenderedDelivery {
+            backend: "macOS (osascript)",
+            invocation: Invocation::command(
+                "osascript",
+                vec![
+                    "-e".to_string(),
+                    r#"display notification "it's done""#.to_string(),
+                ],
+            ),
+        };
+        assert_eq!(
+            rendered.to_string(),
+            "# backend: macOS (osascript)\nosascript -e 'display notification \"it'\\''s done\"'"
+        );
+    }
+
+    #[test]
+    fn test_display_dbus_arguments() {
+        let notify = DBusNotify {
+            summary: "Build".to_string(),
+            hints: vec![("urgency".to_string(), "byte 0x02".to_string())],
+            expire_timeout: -1,
+            ..DBusNotify::default()
+        };
+        let text = Invocation::DBus(notify).to_string();
+        assert!(text.contains("summary:        \"Build\"\n"));
+        assert!(text.contains("hints:\n  urgency: byte 0x02\n"));
+        assert!(text.ends_with("expire_timeout: -1"));
+    }
+}
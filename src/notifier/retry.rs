@@ -0,0 +1,236 @@
+//! 一時的な失敗の再試行
+//!
+//! 通知デーモンが一瞬応答しない、ネットワーク越しのバックエンド（プラグイン）が
+//! 不安定など、送り直せば届く失敗のために、バックエンドへの配送を間隔を空けて再試行します。
+//! 間隔は 1 回ごとに倍にし（200ms → 400ms → 800ms …）、`max_delay` で止めます。
+//! 再試行しても届かなければ、これまでどおりフォールバック（またはスプール）します。
+//!
+//! 再試行するのは最初に選んだバックエンドへの配送だけで、フォールバック・`also` の転送・
+//! まとめた通知（`send_summary`）は 1 回だけ送ります。
+//!
+//! | 失敗の種類（`RetryOn`） | 該当するエラー | 既定 |
+//! |------------------------|---------------|------|
+//! | `Timeout` | `NotificationError::Timeout`（外部コマンドが終わらない） | しない |
+//! | `SendFailed` | `NotificationError::SendFailed`（D-Bus のエラー、PowerShell・プラグインの失敗） | する |
+//! | `CommandExecution` | `NotificationError::CommandExecution`（起動時の I/O エラー） | する |
+//!
+//! 時間切れは既定では再試行しません。PowerShell や osascript が時間内に終わらなくても
+//! 通知はすでに表示されていることがあり、送り直すと同じ通知が 2 回出るためです
+//! （待つ時間も `command_timeout` × 回数になります）。
+//! 設定や環境の問題（`InvalidInput` / `CommandNotFound` / `BackendUnavailable` など）は
+//! 送り直しても変わらないため、再試行しません。
+//!
+//! # 例
+//! ```
+//! use rust_toast::notifier::{RetryOn, RetryPolicy};
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy {
+//!     retries: 3,
+//!     initial_delay: Duration::from_millis(100),
+//!     retry_on: vec![RetryOn::Timeout],
+//!     ..RetryPolicy::default()
+//! };
+//! assert_eq!(policy.delay(2), Duration::from_millis(400));
+//! ```
+//!
+//! # 学習ポイント
+//! - 指数バックオフ（`checked_mul` と `min` で上限を付ける）
+//! - 再試行する処理をクロージャ（`FnMut`）で受け取る
+
+use std::time::Duration;
+
+use crate::clock;
+use crate::error::{NotificationError, Result};
+
+/// 再試行する失敗の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum RetryOn {
+    /// 外部コマンドが時間内に終わらなかった
+    Timeout,
+    /// バックエンドが送信に失敗した
+    SendFailed,
+    /// 外部コマンドの起動・入出力に失敗した
+    CommandExecution,
+}
+
+impl RetryOn {
+    /// エラーがこの種類かどうか
+    pub fn matches(self, err: &NotificationError) -> bool {
+        matches!(
+            (self, err),
+            (Self::Timeout, NotificationError::Timeout { .. })
+                | (Self::SendFailed, NotificationError::SendFailed { .. })
+                | (
+                    Self::CommandExecution,
+                    NotificationError::CommandExecution(_)
+                )
+        )
+    }
+}
+
+/// 再試行の方針（`NotificationBuilder::retries()` / `retry_policy()`、`Config::retry`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct RetryPolicy {
+    /// 最初の送信の後に再試行する回数（0 なら再試行しない）
+    pub retries: u32,
+    /// 1 回目の再試行までの間隔（以降は倍にしていく）
+    pub initial_delay: Duration,
+    /// 間隔の上限
+    pub max_delay: Duration,
+    /// 再試行する失敗の種類
+    pub retry_on: Vec<RetryOn>,
+}
+
+impl Default for RetryPolicy {
+    /// 再試行しない（回数以外は `retries()` で使う既定値。時間切れは再試行しない）
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            initial_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            retry_on: vec![RetryOn::SendFailed, RetryOn::CommandExecution],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 既定の間隔と種類で、`retries` 回まで再試行する方針
+    pub fn retries(retries: u32) -> Self {
+        Self {
+            retries,
+            ..Self::default()
+        }
+    }
+
+    /// `attempt` 回目（0 から）の再試行の前に待つ時間
+    pub fn delay(&self, attempt: u32) -> Duration {
+        2u32.checked_pow(attempt)
+            .and_then(|factor| self.initial_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// `attempt` 回再試行した後の失敗 `err` を、もう一度試すかどうか
+    pub fn should_retry(&self, err: &NotificationError, attempt: u32) -> bool {
+        attempt < self.retries && self.retry_on.iter().any(|kind| kind.matches(err))
+    }
+}
+
+/// `action` を方針に従って再試行する（待つ時間は `clock::current()` で）
+pub(crate) fn run<T>(policy: &RetryPolicy, mut action: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        match action() {
+            Err(err) => match next_delay(policy, &err, &mut attempt) {
+                Some(delay) => clock::current().sleep(delay),
+                None => return Err(err),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// 失敗 `err` の後にもう一度試すなら、その前に待つ時間（`attempt` を進めてログに出す）
+///
+/// `run()` と、非同期の配送（`nonblocking`）の再試行で共有します。
+pub(crate) fn next_delay(
+    policy: &RetryPolicy,
+    err: &NotificationError,
+    attempt: &mut u32,
+) -> Option<Duration> {
+    if !policy.should_retry(err, *attempt) {
+        return None;
+    }
+    let delay = policy.delay(*attempt);
+    *attempt += 1;
+    tracing::info!(
+        "{err}; retrying in {delay:?} ({attempt}/{})",
+        policy.retries
+    );
+    Some(delay)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send_failed() -> NotificationError {
+        NotificationError::SendFailed {
+            backend: "Linux".to_string(),
+            reason: "daemon busy".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_max() {
+        let policy = RetryPolicy::retries(10);
+        assert_eq!(policy.delay(0), Duration::from_millis(200));
+        assert_eq!(policy.delay(1), Duration::from_millis(400));
+        assert_eq!(policy.delay(5), Duration::from_secs(5));
+        assert_eq!(policy.delay(40), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_run_retries_transient_errors_only() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::ZERO,
+            ..RetryPolicy::retries(2)
+        };
+
+        let mut calls = 0;
+        let result = run(&policy, || {
+            calls += 1;
+            if calls < 3 {
+                Err(send_failed())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut calls = 0;
+        let result: Result<()> = run(&policy, || {
+            calls += 1;
+            Err(NotificationError::CommandNotFound {
+                program: "alerter".to_string(),
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result: Result<()> = run(&policy, || {
+            calls += 1;
+            Err(send_failed())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        // 時間切れは既定では再試行しない（表示済みの通知が重なるため）
+        let mut calls = 0;
+        let result: Result<()> = run(&policy, || {
+            calls += 1;
+            Err(NotificationError::Timeout {
+                backend: None,
+                program: "osascript".to_string(),
+                elapsed: Duration::from_secs(10),
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
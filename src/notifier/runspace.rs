@@ -0,0 +1,216 @@
+//! 常駐する PowerShell（Windows / WSL）
+//!
+//! `warm_up()` で起動した `powershell.exe -Command -` をプロセスの中で使い回し、
+//! PowerShell の起動と WinRT の型の読み込み（合わせて 1〜2 秒）を 2 回目以降の通知から無くします。
+//! スクリプトは Base64 にした 1 行として標準入力に書き、結果は印の付いた 1 行で標準出力から読みます。
+//!
+//! | 行 | 内容 |
+//! |----|------|
+//! | 要求 | `& { ... [ScriptBlock]::Create(<Base64 のスクリプト>) ... }` |
+//! | 応答 | `rust-toast-done:<0 か 1>:<Base64 の stdout>:<Base64 の stderr>` |
+//!
+//! 常駐するのは呼び出したプロセスが終わるまでです。通知のたびに起動する CLI には効かず、
+//! ライブラリを使う常駐プロセス（`NotificationManager` を持つアプリなど）向けです。
+//! 応答が時間内に来ない・プロセスが終わった場合は、止めて次の `warm_up()` まで使いません。
+//!
+//! # 学習ポイント
+//! - 子プロセスとの行単位の要求と応答
+//! - 読み取りスレッドと `mpsc::Receiver::recv_timeout()` による待ち時間の上限
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::error::{NotificationError, Result};
+use crate::process;
+
+/// 応答の行の接頭辞
+const DONE_MARKER: &str = "rust-toast-done:";
+
+/// 実行中の PowerShell（`start()` の前と、止めた後は `None`）
+static RUNSPACE: Mutex<Option<Runspace>> = Mutex::new(None);
+
+/// スクリプトの実行結果（`std::process::Output` の代わり）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Reply {
+    /// 例外で止まらずに終わったかどうか
+    pub success: bool,
+    /// 標準出力
+    pub stdout: String,
+    /// 標準エラー出力（`[Console]::Error` への書き込みと例外のメッセージ）
+    pub stderr: String,
+}
+
+struct Runspace {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Drop for Runspace {
+    fn drop(&mut self) {
+        // すでに終わっていた場合の失敗は無視する
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Runspace {
+    fn spawn(program: &str) -> Result<Self> {
+        let mut child = process::command(program)?
+            .args(["-NoProfile", "-NonInteractive", "-NoLogo", "-Command", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or_else(|| closed("stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| closed("stdout"))?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn run(&mut self, script: &str, timeout: Duration) -> Result<Reply> {
+        writeln!(self.stdin, "{}", request_line(script))?;
+        self.stdin.flush()?;
+        loop {
+            match self.lines.recv_timeout(timeout) {
+                Ok(line) => {
+                    if let Some(reply) = parse_reply(&line) {
+                        return Ok(reply);
+                    }
+                    // 印の無い行（PowerShell 自身の出力）は読み飛ばす
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(NotificationError::Timeout {
+                        backend: None,
+                        program: "powershell.exe (resident)".to_string(),
+                        elapsed: timeout,
+                    })
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(closed("stdout")),
+            }
+        }
+    }
+}
+
+/// PowerShell を起動して常駐させ、`init` を実行しておく（起動済みなら何もしない）
+pub(crate) fn start(program: &str, init: &str, timeout: Duration) -> Result<()> {
+    let mut slot = RUNSPACE.lock().unwrap_or_else(|e| e.into_inner());
+    if slot.is_some() {
+        return Ok(());
+    }
+    let mut runspace = Runspace::spawn(program)?;
+    let reply = runspace.run(init, timeout)?;
+    if !reply.success {
+        return Err(NotificationError::SendFailed {
+            backend: "Windows".to_string(),
+            reason: reply.stderr,
+        });
+    }
+    *slot = Some(runspace);
+    Ok(())
+}
+
+/// 常駐している PowerShell でスクリプトを実行する（常駐していなければ `None`）
+///
+/// やり取りに失敗した場合は PowerShell を止めてエラーを返します。
+/// 他のスレッドの実行が終わるまで待ちます（1 つの PowerShell は 1 つずつしか実行できないため）。
+pub(crate) fn run(script: &str, timeout: Duration) -> Option<Result<Reply>> {
+    let mut slot = RUNSPACE.lock().unwrap_or_else(|e| e.into_inner());
+    let result = slot.as_mut()?.run(script, timeout);
+    if result.is_err() {
+        *slot = None;
+    }
+    Some(result)
+}
+
+/// スクリプトを 1 行の要求にする
+///
+/// 子スコープで実行し、`[Console]::Error` への書き込みも集めて応答の行にまとめます。
+fn request_line(script: &str) -> String {
+    let encoded = STANDARD.encode(script);
+    format!(
+        "& {{ $rtErr = New-Object IO.StringWriter; $rtOld = [Console]::Error; [Console]::SetError($rtErr); $rtCode = 1; $rtOut = ''; \
+try {{ $rtOut = & ([ScriptBlock]::Create([Text.Encoding]::UTF8.GetString([Convert]::FromBase64String('{encoded}')))) | Out-String; $rtCode = 0 }} \
+catch {{ $rtErr.WriteLine($_) }} finally {{ [Console]::SetError($rtOld) }}; \
+[Console]::Out.WriteLine('{DONE_MARKER}' + $rtCode + ':' + [Convert]::ToBase64String([Text.Encoding]::UTF8.GetBytes($rtOut)) + ':' + [Convert]::ToBase64String([Text.Encoding]::UTF8.GetBytes($rtErr.ToString()))); \
+[Console]::Out.Flush() }}"
+    )
+}
+
+/// 応答の行を読む（印が無い・形式が違う場合は `None`）
+fn parse_reply(line: &str) -> Option<Reply> {
+    let mut fields = line.trim().strip_prefix(DONE_MARKER)?.splitn(3, ':');
+    let success = fields.next()? == "0";
+    let mut decode = || -> Option<String> {
+        let bytes = STANDARD.decode(fields.next()?).ok()?;
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    };
+    let stdout = decode()?;
+    let stderr = decode()?;
+    Some(Reply {
+        success,
+        stdout,
+        stderr,
+    })
+}
+
+fn closed(pipe: &str) -> NotificationError {
+    NotificationError::SendFailed {
+        backend: "Windows".to_string(),
+        reason: format!("resident PowerShell closed its {pipe}"),
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_is_one_line_with_encoded_script() {
+        let line = request_line("$toast.Tag = 'a'\n$notifier.Show($toast)");
+        assert!(!line.contains('\n'));
+        assert!(line.contains(&STANDARD.encode("$toast.Tag = 'a'\n$notifier.Show($toast)")));
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let line = format!(
+            "{DONE_MARKER}1:{}:{}",
+            STANDARD.encode("out\n"),
+            STANDARD.encode("rust-toast-disabled:DisabledForUser\n")
+        );
+        assert_eq!(
+            parse_reply(&line),
+            Some(Reply {
+                success: false,
+                stdout: "out\n".to_string(),
+                stderr: "rust-toast-disabled:DisabledForUser\n".to_string(),
+            })
+        );
+        assert_eq!(parse_reply("PS C:\\> "), None);
+        assert_eq!(parse_reply(&format!("{DONE_MARKER}0:!!")), None);
+    }
+}
@@ -0,0 +1,493 @@
+//! 送信のシミュレーション（ドライラン）
+//!
+//! 通知を実際には送らずに、デフォルト補完からバックエンド選択までの
+//! 各段階で「何が選ばれ、なぜそうなったか」を記録します。
+//! CLI の `rust-toast simulate --from-file notif.json` から使います。
+//!
+//! # 学習ポイント
+//! - 本番と同じ関数（`build_with_config()`, `create_notifier()`）を使い、
+//!   シミュレーションと実際の動作がずれないようにする
+//! - 子モジュールから親モジュールの非公開フィールドへのアクセス
+
+use std::fmt;
+
+use crate::clock;
+use crate::config::{self, Config};
+use crate::notifier::dedupe::{self, Dedupe};
+use crate::notifier::{
+    create_notifier, latency, Backend, Notification, NotificationBuilder, LATENCY_FALLBACK,
+};
+use crate::platform::{self, detect_platform};
+use crate::preset::{normalize_locale, FALLBACK_LOCALE};
+use crate::priority;
+use crate::throttle::{self, Overflow};
+use crate::topic;
+
+/// 1 つの段階での判断
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    /// 段階の名前（`topic`, `preset`, `title`, `timeout`, `dedupe`, `priority`, `throttle`, `routing`, `latency`, `fallback`）
+    pub stage: &'static str,
+    /// 判断の内容
+    pub outcome: String,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>8}: {}", self.stage, self.outcome)
+    }
+}
+
+/// シミュレーションの結果
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// 段階ごとの判断（評価した順）
+    pub decisions: Vec<Decision>,
+    /// 送信に使われるはずのバックエンド（利用できない場合はフォールバック先）
+    pub backend: Backend,
+    /// 表示されるはずかどうか（重複・送信数の上限・静かな時間で捨てられる場合は `false`）
+    pub shown: bool,
+    /// 最終的に送られるはずの通知
+    pub notification: Notification,
+}
+
+/// 現在の設定（`config::current()`）で送信をシミュレーションする
+pub fn simulate(builder: NotificationBuilder) -> Simulation {
+    simulate_with(builder, &config::current(), platform::is_remote_session())
+}
+
+/// 設定と実行環境を引数で受け取る `simulate()`（テスト用に分離）
+fn simulate_with(builder: NotificationBuilder, config: &Config, remote: bool) -> Simulation {
+    let mut decisions = Vec::new();
+    let mut decide = |stage: &'static str, outcome: String| {
+        decisions.push(Decision { stage, outcome });
+    };
+    let mut shown = true;
+
+    // トピックのルール（送信数の上限は send() と同じ記録で判定するが、数えない）
+    if let Some(name) = &builder.topic {
+        let outcome = match topic::find(&config.topics, name) {
+            Some((pattern, rule)) => {
+                let mut applied = Vec::new();
+                if let Some(backend) = rule.backend.as_ref().filter(|_| builder.backend.is_none()) {
+                    applied.push(format!("backend {}", backend.key()));
+                }
+                if let Some(urgency) = rule.urgency.filter(|_| builder.urgency.is_none()) {
+                    applied.push(format!("urgency {}", urgency.as_str()));
+                }
+                if let Some(limit) = rule.rate_limit {
+                    let reached = topic::rate_limit_reached(pattern, limit);
+                    shown &= !reached;
+                    applied.push(format!(
+                        "limit {}/{}s{}",
+                        limit.max,
+                        limit.per.as_secs(),
+                        if reached { " reached, rejected" } else { "" }
+                    ));
+                }
+                if applied.is_empty() {
+                    format!("'{name}' matched '{pattern}'")
+                } else {
+                    format!("'{name}' matched '{pattern}' ({})", applied.join(", "))
+                }
+            }
+            None => format!("'{name}' (no matching rule)"),
+        };
+        decide("topic", outcome);
+    }
+
+    // プリセット（翻訳の選択）
+    let mut preset_text = None;
+    if let Some(name) = &builder.preset {
+        let locale = builder
+            .locale
+            .as_deref()
+            .map(normalize_locale)
+            .or_else(platform::locale)
+            .unwrap_or_else(|| FALLBACK_LOCALE.to_string());
+        match config.presets.get(name) {
+            Some(preset) => {
+                preset_text = preset.resolve(&locale);
+                decide("preset", format!("'{name}' resolved for locale {locale}"));
+            }
+            None => decide("preset", format!("unknown preset '{name}' (ignored)")),
+        }
+    }
+
+    // タイトル（明示 / プリセット / テンプレート + ホスト名）
+    let source = if builder.title.is_some() {
+        "explicit".to_string()
+    } else if preset_text.is_some() {
+        "from preset".to_string()
+    } else {
+        format!("from template \"{}\"", config.title_template)
+    };
+    let prefix = if config.hostname_prefix.applies(remote) {
+        " + hostname prefix"
+    } else {
+        ""
+    };
+    decide("title", format!("{source}{prefix}"));
+
+    // 表示時間（確認待ち / 明示 / 緊急度ごとのデフォルト）
+    let urgency = builder.urgency.unwrap_or_default();
+    let timeout = if builder.require_ack {
+        "0 ms (require_ack)".to_string()
+    } else if let Some(timeout) = builder.timeout {
+        format!("{timeout} ms (explicit)")
+    } else {
+        format!(
+            "{} ms ({} default)",
+            config.urgency_defaults(urgency).timeout,
+            urgency.as_str()
+        )
+    };
+    decide("timeout", timeout);
+
+    // 重複（send() と同じ記録で判定するが、記録しない）
+    let mut notification = builder.build_with_config(config);
+    if let Some(window) = config.dedupe_window {
+        let outcome = match dedupe::global().peek(&notification, window) {
+            Dedupe::New => format!("first within {}s", window.as_secs()),
+            Dedupe::Repeat {
+                count,
+                replace_id: Some(id),
+            } => {
+                notification = dedupe::with_counter(&notification, count);
+                notification.replace_id = Some(id.clone());
+                format!("repeat ×{count}, replaces {id}")
+            }
+            Dedupe::Repeat {
+                count,
+                replace_id: None,
+            } => {
+                shown = false;
+                format!("repeat ×{count}, not shown (the first one cannot be replaced)")
+            }
+        };
+        decide("dedupe", outcome);
+    }
+
+    // 優先度の振り分け（表がある場合のみ、send() と同じ route()）
+    if !config.priority_routes.is_empty() {
+        let route = priority::route(config, notification.priority, clock::current().now());
+        let outcome = if route.dropped {
+            shown = false;
+            "dropped (quiet hours)".to_string()
+        } else if route.also.is_empty() {
+            "no extra backends".to_string()
+        } else {
+            let names: Vec<_> = route.also.iter().map(Backend::key).collect();
+            format!("also {}", names.join(", "))
+        };
+        decide("priority", format!("{} {outcome}", notification.priority));
+    }
+
+    // 全体の送信数の上限（send() と同じ記録で判定するが、数えない・待たない）
+    if let Some(rule) = config.rate_limit {
+        let throttle = throttle::global();
+        let limit = format!("limit {}/{}s", rule.limit.max, rule.limit.per.as_secs());
+        let wait = throttle.time_until_available(rule);
+        let outcome = if wait.is_zero() {
+            match throttle.suppressed() {
+                0 => format!("{limit}, sent"),
+                count => format!("{limit}, sent after a summary of {count} suppressed"),
+            }
+        } else {
+            match rule.overflow {
+                Overflow::Queue => {
+                    format!("{limit} reached, queued for {} ms", wait.as_millis())
+                }
+                Overflow::Drop | Overflow::Summarize => {
+                    shown = false;
+                    format!(
+                        "{limit} reached, dropped (overflow {})",
+                        rule.overflow.as_str()
+                    )
+                }
+            }
+        };
+        decide("throttle", outcome);
+    }
+
+    // バックエンドの選択
+    let (backend, reason) = match &notification.backend_override {
+        Some(backend) => (backend.clone(), "forced".to_string()),
+        None => {
+            let platform = detect_platform();
+            (Backend::Native(platform), format!("detected {platform}"))
+        }
+    };
+    let notifier = create_notifier(backend.clone());
+    let available = notifier.is_available();
+    decide(
+        "routing",
+        format!(
+            "{} ({reason}, {})",
+            notifier.backend_name(),
+            if available {
+                "available"
+            } else {
+                "unavailable"
+            }
+        ),
+    );
+
+    // 応答時間の予算（記録は状態を持つため、平均だけを表示して切り替えの判定はしない）
+    if let Some(budget) = config.latency_budget {
+        let outcome = match latency::global_tracker().average(notifier.backend_name()) {
+            Some(average) if average > budget => format!(
+                "{} avg {} ms over {} ms budget (non-critical may use {})",
+                notifier.backend_name(),
+                average.as_millis(),
+                budget.as_millis(),
+                LATENCY_FALLBACK.name()
+            ),
+            Some(average) => format!(
+                "{} avg {} ms within {} ms budget",
+                notifier.backend_name(),
+                average.as_millis(),
+                budget.as_millis()
+            ),
+            None => format!("{} not measured yet", notifier.backend_name()),
+        };
+        decide("latency", outcome);
+    }
+
+    // フォールバック（send() と同じ条件）
+    let mut routed = backend;
+    let fallback = if notification.backend_override == Some(Backend::Stdout) {
+        "disabled for stdout backend".to_string()
+    } else {
+        let next = notification
+            .fallback_chain
+            .iter()
+            .cloned()
+            .map(|backend| (backend.clone(), create_notifier(backend)))
+            .find(|(_, f)| f.backend_name() != notifier.backend_name() && f.is_available());
+        match next {
+            Some((_, next)) if available => {
+                format!(
+                    "{} if {} fails",
+                    next.backend_name(),
+                    notifier.backend_name()
+                )
+            }
+            Some((next_backend, next)) => {
+                routed = next_backend;
+                format!("{} (primary unavailable)", next.backend_name())
+            }
+            None => "none available".to_string(),
+        }
+    };
+    decide("fallback", fallback);
+
+    Simulation {
+        decisions,
+        backend: routed,
+        shown,
+        notification,
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HostnamePrefix;
+    use crate::notifier::UrgencyLevel;
+    use crate::preset::Preset;
+    use crate::priority::PriorityRoute;
+    use crate::throttle::GlobalRateLimit;
+    use crate::topic::{RateLimit, TopicRule};
+    use std::time::Duration;
+
+    fn stages(simulation: &Simulation) -> Vec<String> {
+        simulation
+            .decisions
+            .iter()
+            .map(|d| format!("{}: {}", d.stage, d.outcome))
+            .collect()
+    }
+
+    #[test]
+    fn test_simulate_defaults_and_forced_backend() {
+        let builder = NotificationBuilder::new()
+            .message("Hi")
+            .urgency(UrgencyLevel::Low)
+            .backend(Backend::Stdout);
+        let simulation = simulate_with(builder, &Config::default(), false);
+
+        assert_eq!(
+            stages(&simulation),
+            vec![
+                "title: from template \"Notification\"",
+                "timeout: 3000 ms (low default)",
+                "routing: Stdout (JSON) (forced, available)",
+                "fallback: disabled for stdout backend",
+            ]
+        );
+        assert_eq!(simulation.notification.timeout, 3000);
+        assert_eq!(simulation.backend, Backend::Stdout);
+        assert!(simulation.shown);
+    }
+
+    #[test]
+    fn test_simulate_dedupe_and_throttle() {
+        let config = Config {
+            dedupe_window: Some(Duration::from_secs(60)),
+            rate_limit: Some(GlobalRateLimit::new(
+                0,
+                Duration::from_secs(60),
+                Overflow::Drop,
+            )),
+            ..Config::default()
+        };
+        // 他のテストと記録が混ざらないように、この通知だけの本文にする
+        let builder = || {
+            NotificationBuilder::new()
+                .message("test_simulate_dedupe_and_throttle")
+                .backend(Backend::Stdout)
+        };
+        let simulation = simulate_with(builder(), &config, false);
+        assert_eq!(stages(&simulation)[2], "dedupe: first within 60s");
+        assert_eq!(
+            stages(&simulation)[3],
+            "throttle: limit 0/60s reached, dropped (overflow drop)"
+        );
+        assert!(!simulation.shown);
+
+        // send() と同じ記録を読むが、シミュレーションでは記録しない
+        let sent = builder().build_with_config(&config);
+        dedupe::global().remember(&sent, None);
+        let config = Config {
+            rate_limit: None,
+            ..config
+        };
+        for _ in 0..2 {
+            let simulation = simulate_with(builder(), &config, false);
+            assert_eq!(
+                stages(&simulation)[2],
+                "dedupe: repeat ×2, not shown (the first one cannot be replaced)"
+            );
+            assert!(!simulation.shown);
+        }
+
+        dedupe::global().remember(&sent, Some("7"));
+        let simulation = simulate_with(builder(), &config, false);
+        assert_eq!(stages(&simulation)[2], "dedupe: repeat ×2, replaces 7");
+        assert_eq!(
+            simulation.notification.message,
+            "test_simulate_dedupe_and_throttle (×2)"
+        );
+        assert!(simulation.shown);
+    }
+
+    #[test]
+    fn test_simulate_topic_defaults() {
+        let mut config = Config::default();
+        config.topics.insert(
+            "ci/*".to_string(),
+            TopicRule {
+                backend: Some(Backend::Stdout),
+                urgency: Some(UrgencyLevel::Low),
+                rate_limit: Some(RateLimit::new(10, Duration::from_secs(60))),
+            },
+        );
+
+        let builder = NotificationBuilder::new()
+            .message("Built")
+            .topic("ci/build");
+        let simulation = simulate_with(builder, &config, false);
+        assert_eq!(
+            stages(&simulation)[0],
+            "topic: 'ci/build' matched 'ci/*' (backend stdout, urgency low, limit 10/60s)"
+        );
+        assert_eq!(simulation.backend, Backend::Stdout);
+        assert_eq!(simulation.notification.urgency, UrgencyLevel::Low);
+
+        // 明示した値はトピックのルールより優先
+        let builder = NotificationBuilder::new()
+            .topic("ci/build")
+            .urgency(UrgencyLevel::Critical)
+            .backend(Backend::Console);
+        let simulation = simulate_with(builder, &config, false);
+        assert_eq!(
+            simulation.decisions[0].outcome,
+            "'ci/build' matched 'ci/*' (limit 10/60s)"
+        );
+        assert_eq!(simulation.notification.urgency, UrgencyLevel::Critical);
+
+        let other = simulate_with(
+            NotificationBuilder::new().topic("home/door"),
+            &config,
+            false,
+        );
+        assert_eq!(other.decisions[0].outcome, "'home/door' (no matching rule)");
+    }
+
+    #[test]
+    fn test_simulate_priority_route() {
+        let mut config = Config::default();
+        config
+            .priority_routes
+            .push(PriorityRoute::new(8, 10).also(Backend::Console));
+
+        let builder = NotificationBuilder::new()
+            .priority(9)
+            .backend(Backend::Stdout);
+        let simulation = simulate_with(builder, &config, false);
+        assert_eq!(stages(&simulation)[2], "priority: 9 also console");
+
+        let builder = NotificationBuilder::new().backend(Backend::Stdout);
+        let simulation = simulate_with(builder, &config, false);
+        assert_eq!(stages(&simulation)[2], "priority: 5 no extra backends");
+    }
+
+    #[test]
+    fn test_simulate_latency_budget() {
+        let config = Config {
+            latency_budget: Some(Duration::from_millis(300)),
+            ..Config::default()
+        };
+        // stdout バックエンドは他のテストでも使うため、記録の有無には依存しない
+        let simulation = simulate_with(
+            NotificationBuilder::new().backend(Backend::Stdout),
+            &config,
+            false,
+        );
+        assert_eq!(simulation.decisions[3].stage, "latency");
+        assert!(simulation.decisions[3].outcome.starts_with("Stdout (JSON)"));
+    }
+
+    #[test]
+    fn test_simulate_preset_and_hostname_prefix() {
+        let mut config = Config {
+            hostname_prefix: HostnamePrefix::Remote,
+            ..Config::default()
+        };
+        config.presets.insert(
+            "disk-full".to_string(),
+            Preset::new().translation("en", "Disk full", "Free up space"),
+        );
+
+        let builder = NotificationBuilder::new()
+            .preset("disk-full")
+            .locale("en")
+            .backend(Backend::Console);
+        let simulation = simulate_with(builder, &config, true);
+        let stages = stages(&simulation);
+
+        assert_eq!(stages[0], "preset: 'disk-full' resolved for locale en");
+        assert_eq!(stages[1], "title: from preset + hostname prefix");
+
+        let unknown = simulate_with(NotificationBuilder::new().preset("missing"), &config, false);
+        assert_eq!(
+            unknown.decisions[0].outcome,
+            "unknown preset 'missing' (ignored)"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! 標準出力 JSON バックエンド（スクリプト連携用）
+//!
+//! 通知を画面に表示する代わりに、デフォルト値の補完が済んだ
+//! `Notification` を JSON として標準出力に書き出します。
+//!
+//! ```bash
+//! rust-toast -m "Done" --backend stdout | jq .title
+//! ```
+//!
+//! # 学習ポイント
+//! - `serde_json` による JSON 生成
+//! - 標準出力をデータの受け渡しに使う（パイプライン設計）
+
+use crate::error::Result;
+use crate::notifier::{Invocation, Notification, Notifier};
+use std::io::Write;
+
+/// 標準出力 JSON バックエンド
+pub struct StdoutNotifier;
+
+impl Notifier for StdoutNotifier {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        // 1 通知 = 1 行（JSON Lines 形式）で出力する
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", notification.to_json())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        Ok(Invocation::Output(notification.to_json()))
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "Stdout (JSON)"
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_name() {
+        assert_eq!(StdoutNotifier.backend_name(), "Stdout (JSON)");
+        assert!(StdoutNotifier.is_available());
+    }
+}
@@ -0,0 +1,170 @@
+//! 送信される通知の購読
+//!
+//! `send()` / `send_and_wait()` を通過した通知（トピックの送信数の上限などで
+//! 拒否されたものを除く）を、同じプロセス内の購読者にチャネルで配信します。
+//! ステータスバーのウィジェットのような独自の表示先を、
+//! 通常の送信とは別に作るためのものです。
+//!
+//! # 例
+//! ```
+//! use rust_toast::notifier;
+//!
+//! let subscription = notifier::subscribe_topic("ci/*");
+//! std::thread::spawn(move || {
+//!     for notification in subscription {
+//!         println!("[{}] {}", notification.title, notification.message);
+//!     }
+//! });
+//! ```
+//!
+//! # 学習ポイント
+//! - `std::sync::mpsc` によるスレッド間の配信
+//! - `Iterator` の実装で `for` ループに渡せるようにする
+//! - 受信側が drop されたチャネルの掃除（`send()` のエラーで検出）
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::notifier::Notification;
+use crate::topic;
+
+/// 購読者（トピックのパターンと送信側）
+struct Subscriber {
+    /// `None` なら全ての通知
+    pattern: Option<String>,
+    sender: Sender<Notification>,
+}
+
+impl Subscriber {
+    fn wants(&self, notification: &Notification) -> bool {
+        match (&self.pattern, &notification.topic) {
+            (None, _) => true,
+            (Some(pattern), Some(topic)) => topic::matches(pattern, topic),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// 通知の購読（受信側）
+///
+/// drop すると購読が解除されます。
+#[derive(Debug)]
+pub struct Subscription {
+    receiver: Receiver<Notification>,
+}
+
+impl Subscription {
+    /// 次の通知が届くまで待つ
+    pub fn recv(&self) -> Option<Notification> {
+        self.receiver.recv().ok()
+    }
+
+    /// 届いている通知があれば返す（待たない）
+    pub fn try_recv(&self) -> Option<Notification> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// 最大 `timeout` だけ次の通知を待つ
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Notification> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = Notification;
+
+    fn next(&mut self) -> Option<Notification> {
+        self.recv()
+    }
+}
+
+/// プロセス全体の購読者
+fn subscribers() -> &'static Mutex<Vec<Subscriber>> {
+    static SUBSCRIBERS: OnceLock<Mutex<Vec<Subscriber>>> = OnceLock::new();
+    SUBSCRIBERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// 全ての通知を購読する
+pub fn subscribe() -> Subscription {
+    add(None)
+}
+
+/// トピックがパターン（`ci/*` など、`topic::matches()` の形式）に一致する通知を購読する
+///
+/// トピックの無い通知は届きません。
+pub fn subscribe_topic(pattern: impl Into<String>) -> Subscription {
+    add(Some(pattern.into()))
+}
+
+fn add(pattern: Option<String>) -> Subscription {
+    let (sender, receiver) = mpsc::channel();
+    subscribers()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Subscriber { pattern, sender });
+    Subscription { receiver }
+}
+
+/// 通知を購読者に配信する（送信前のフィルタを通過した後に呼ぶ）
+///
+/// 受信側が drop された購読者はここで取り除きます。
+pub(crate) fn publish(notification: &Notification) {
+    let mut subscribers = subscribers().lock().unwrap_or_else(|e| e.into_inner());
+    subscribers.retain(|subscriber| {
+        !subscriber.wants(notification) || subscriber.sender.send(notification.clone()).is_ok()
+    });
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotificationBuilder;
+
+    // 購読者はプロセス全体で共有されるため、他のテストと重ならないトピックを使う
+
+    #[test]
+    fn test_subscribe_topic_filters_notifications() {
+        let subscription = subscribe_topic("test-subscribe/*");
+
+        publish(
+            &NotificationBuilder::new()
+                .message("a")
+                .topic("test-subscribe/a")
+                .build(),
+        );
+        publish(
+            &NotificationBuilder::new()
+                .message("b")
+                .topic("other/b")
+                .build(),
+        );
+        publish(&NotificationBuilder::new().message("c").build());
+
+        assert_eq!(subscription.try_recv().unwrap().message, "a");
+        assert!(subscription.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_dropped_subscription_is_removed() {
+        let count = || {
+            subscribers()
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| s.pattern.as_deref() == Some("test-drop/*"))
+                .count()
+        };
+
+        let subscription = subscribe_topic("test-drop/*");
+        assert_eq!(count(), 1);
+        drop(subscription);
+
+        publish(&NotificationBuilder::new().topic("test-drop/x").build());
+        assert_eq!(count(), 0);
+    }
+}
@@ -1,75 +1,1008 @@
 //! Windows 通知バックエンド
 //!
-//! PowerShell を使用して Windows のバルーン通知を送信します。
+//! PowerShell から WinRT の `ToastNotificationManager` を呼び出し、
+//! Windows 10 以降のトースト通知を送信します。
 //! WSL（Windows Subsystem for Linux）からも使用可能です。
 //!
 //! # 学習ポイント
 //! - PowerShell スクリプトの生成
+//! - トースト通知の XML（`<toast>` 要素）の組み立て
 //! - `std::process::Command` による外部プロセス実行
 //! - raw 文字列リテラル `r#"..."#`
 
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::config;
 use crate::error::{NotificationError, Result};
-use crate::notifier::{Notification, Notifier};
-use std::process::Command;
+use crate::notifier::click;
+use crate::notifier::handle::generate_id;
+use crate::notifier::render::RENDER_ID;
+use crate::notifier::runspace;
+#[cfg(feature = "async")]
+use crate::notifier::{nonblocking, AsyncNotifier, BoxFuture};
+use crate::notifier::{
+    Capabilities, Icon, Interaction, Invocation, Notification, Notifier, Scenario,
+};
+use crate::platform::{detect_platform, Platform};
+use crate::process;
+use crate::schedule::When;
+
+/// トースト通知の送信元として使う AppUserModelID（`app_name` が無い場合）
+///
+/// 未登録のアプリからはトーストを出せないため、
+/// Windows に必ず登録されている PowerShell の ID を借ります。
+/// 独自の ID を使う場合は `register_app_id()` で登録しておきます。
+const POWERSHELL_APP_ID: &str =
+    r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// `duration="long"` にする表示時間の閾値（ミリ秒）
+///
+/// トーストの表示時間は short（約 7 秒）/ long（約 25 秒）の 2 択のため、
+/// これより長い指定は long に丸めます。
+const LONG_DURATION_THRESHOLD: u32 = 10_000;
+
+/// トーストが画面に表示される秒数（short / long）
+const SHORT_POPUP_SECS: u64 = 7;
+const LONG_POPUP_SECS: u64 = 25;
+
+/// 操作待ちで、トーストが消えた後も追加で待つ秒数
+///
+/// 通常は表示時間が過ぎると `Dismissed`（TimedOut）イベントが届きますが、
+/// 届かなかった場合に PowerShell が終了しなくなるのを防ぎます。
+const WAIT_GRACE_SECS: u32 = 30;
+
+/// 返信欄の入力 ID と送信ボタンの arguments（アクション ID と衝突しない名前）
+const REPLY_ID: &str = "rust-toast.reply";
+
+/// 返信欄の送信ボタンのラベル
+const REPLY_LABEL: &str = "Reply";
+
+/// トーストコレクションの launchArgs の接頭辞（後にコレクション ID が続く）
+const COLLECTION_LAUNCH_ARGS: &str = "rust-toast-collection=";
+
+/// トーストの Group（Tag と組み合わせて置き換え対象を特定する）
+const TOAST_GROUP: &str = "rust-toast";
+
+/// Windows の「通知」設定ページを開く URI
+pub const NOTIFICATION_SETTINGS_URI: &str = "ms-settings:notifications";
+
+/// 通知が無効な時にスクリプトが stderr に出力する行の接頭辞（後に `NotificationSetting` の値が続く）
+const DISABLED_MARKER: &str = "rust-toast-disabled:";
+
+/// まとめたスクリプトが通知ごとに stdout に出力する結果の行の接頭辞
+///
+/// `rust-toast-batch:<番号>:ok` か `rust-toast-batch:<番号>:failed:<Base64 のエラー>` です。
+const BATCH_MARKER: &str = "rust-toast-batch:";
+
+/// 繰り返し再生できるトーストの音の接頭辞
+const LOOPING_SOUND_PREFIX: &str = "ms-winsoundevent:Notification.Looping.";
+
+/// 繰り返し再生するデフォルトの音
+const LOOPING_ALARM_SOUND: &str = "ms-winsoundevent:Notification.Looping.Alarm";
 
 /// Windows 通知バックエンド
 pub struct WindowsNotifier;
 
 impl Notifier for WindowsNotifier {
     fn send(&self, notification: &Notification) -> Result<()> {
-        // PowerShell 用にエスケープ
-        let title = escape_powershell(&notification.title);
-        let message = escape_powershell(&notification.message);
-
-        // PowerShell スクリプトを構築
-        // System.Windows.Forms.NotifyIcon を使用してバルーン通知を表示
-        //
-        // # 学習ポイント: raw 文字列リテラル
-        // r#"..."# を使うと、エスケープなしで文字列を書けます。
-        // 特に PowerShell のような特殊文字が多いスクリプトで便利です。
-        let ps_script = format!(
-            r#"
-            Add-Type -AssemblyName System.Windows.Forms
-            $balloon = New-Object System.Windows.Forms.NotifyIcon
-            $balloon.Icon = [System.Drawing.SystemIcons]::Information
-            $balloon.BalloonTipTitle = '{}'
-            $balloon.BalloonTipText = '{}'
-            $balloon.Visible = $true
-            $balloon.ShowBalloonTip({})
-            Start-Sleep -Milliseconds 100
-            $balloon.Dispose()
-            "#,
-            title, message, notification.timeout
-        );
-
-        // PowerShell を実行
-        // WSL からは powershell.exe として呼び出せる（Windows 側のパスが自動解決）
-        let output = Command::new("powershell.exe")
-            .arg("-NoProfile") // プロファイルを読み込まない（高速化）
-            .arg("-NonInteractive") // 対話モードを無効化
-            .arg("-Command") // 後続の引数をコマンドとして実行
-            .arg(&ps_script)
-            .output()?;
-
-        if output.status.success() {
-            Ok(())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(NotificationError::SendFailed {
-                backend: "Windows".to_string(),
-                reason: stderr.to_string(),
-            })
+        self.deliver(notification).map(|_| ())
+    }
+
+    fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
+        let (script, id) = build_delivery(notification);
+        run_script(&script, notification.command_timeout)?;
+        Ok(Some(id))
+    }
+
+    fn send_batch(&self, notifications: &[Notification]) -> Vec<Result<Option<String>>> {
+        let (scripts, ids): (Vec<String>, Vec<String>) =
+            notifications.iter().map(build_delivery).unzip();
+        // 1 回の起動の待ち時間は、まとめた通知の中で最も長いもの
+        let timeout = notifications
+            .iter()
+            .map(|notification| notification.command_timeout)
+            .max()
+            .unwrap_or_default();
+        let mut results = Vec::with_capacity(ids.len());
+        for (script, range) in build_batch_scripts(&scripts) {
+            match run_script(&script, timeout) {
+                Ok(stdout) => results.extend(range.map(|index| batch_result(&stdout, index))),
+                // 起動自体の失敗（時間切れなど）は、まとめた全ての通知の失敗
+                Err(err) => results.extend(range.map(|_| {
+                    Err(NotificationError::SendFailed {
+                        backend: "Windows".to_string(),
+                        reason: err.to_string(),
+                    })
+                })),
+            }
         }
+        results
+            .into_iter()
+            .zip(ids)
+            .map(|(result, id)| result.map(|()| Some(id)))
+            .collect()
+    }
+
+    fn render(&self, notification: &Notification) -> Result<Invocation> {
+        // アイコンの取得と WSL のパスの変換はせず、Tag は固定の値にする
+        let (script, _) = delivery_script(notification, || RENDER_ID.to_string());
+        Ok(Invocation::command(POWERSHELL, powershell_args(&script)))
+    }
+
+    fn close(&self, id: &str) -> Result<()> {
+        // アクションセンターの履歴から Tag + Group（+ AppUserModelID）で削除すると、表示中のトーストも消える
+        run_powershell(&build_remove_script(id))?;
+        Ok(())
+    }
+
+    fn send_and_wait(&self, notification: &Notification) -> Result<Interaction> {
+        // スクリプトは操作結果を `clicked` / `action:<ID>` などの 1 行で出力する
+        // ユーザーの操作を待つので、時間切れにはしない（トーストの表示時間で終わる）
+        let script = build_wait_script(&with_windows_paths(notification));
+        let stdout = powershell_result(powershell_command(&script)?.output()?)?;
+        stdout
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("expired")
+            .parse()
+    }
+
+    fn warm_up(&self) -> Result<()> {
+        // PowerShell を常駐させて WinRT の型を読み込んでおき、以後の deliver() はそこで実行する
+        // （このプロセスが終わるまで。CLI のように 1 通知で終わるプロセスには効かない）
+        runspace::start(
+            POWERSHELL,
+            LOAD_WINRT_TYPES,
+            config::current().command_timeout,
+        )
     }
 
     fn is_available(&self) -> bool {
         // Windows または WSL（Linux からも Windows 通知を送信可能）
-        cfg!(target_os = "windows") || cfg!(target_os = "linux")
+        // `windows-powershell` フィーチャーが無ければ組み込まれていない扱い
+        cfg!(feature = "windows-powershell")
+            && (cfg!(target_os = "windows") || cfg!(target_os = "linux"))
     }
 
     fn backend_name(&self) -> &'static str {
         "Windows (PowerShell)"
     }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            supports_actions: true,
+            supports_reply: true,
+            supports_images: true,
+            supports_sound: true,
+            supports_replace: true,
+            supports_close: true,
+            supports_wait: true,
+            // トーストの <actions> に置けるボタンは 5 個まで
+            max_actions: Some(5),
+            // タイトルは 2 行、本文は 4 行で切れる（文字数は目安）
+            max_title_len: Some(80),
+            max_message_len: Some(160),
+            ..Capabilities::default()
+        }
+    }
+
+    fn repeats_sound_natively(&self) -> bool {
+        // scenario="alarm" のトーストは、操作されるまでアラーム音をループ再生する
+        true
+    }
+
+    fn opens_urls_natively(&self) -> bool {
+        // activationType="protocol" のトーストは、クリックされると OS が URL を開く
+        true
+    }
+}
+
+/// WinRT のトースト関連の型を PowerShell に読み込むスクリプト
+const LOAD_WINRT_TYPES: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
+"#;
+
+/// PowerShell を `tokio::process::Command` で起動して待つ
+#[cfg(feature = "async")]
+impl AsyncNotifier for WindowsNotifier {
+    fn deliver_async<'a>(
+        &'a self,
+        notification: &'a Notification,
+    ) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            let (script, id) = build_delivery(notification);
+            let command = powershell_command(&script)?;
+            powershell_result(
+                nonblocking::output_within(command, notification.command_timeout).await?,
+            )?;
+            Ok(Some(id))
+        })
+    }
+}
+
+/// トーストを表示するスクリプトと、ハンドルの ID（`join_id()` の形式）
+fn build_delivery(notification: &Notification) -> (String, String) {
+    delivery_script(&with_windows_paths(notification), generate_id)
+}
+
+/// `build_delivery()` のうち、パスを変換した後の組み立て（何も起動しない）
+///
+/// 置き換える ID もグループも無い通知は、`new_tag` で Tag を作ります。
+fn delivery_script(
+    notification: &Notification,
+    new_tag: impl FnOnce() -> String,
+) -> (String, String) {
+    // 同じ Tag + Group のトーストは置き換えられるので、Tag を ID として返す
+    // グループのタグがあれば Tag に使い、同じグループのトーストをまとめる
+    let (tag, replaced_app_id) = match notification.replace_id.as_deref() {
+        Some(id) => {
+            let (tag, app_id) = split_id(id);
+            (tag.to_string(), Some(app_id))
+        }
+        None => (notification.group.clone().unwrap_or_else(new_tag), None),
+    };
+    let app_id = notification
+        .app_name
+        .as_deref()
+        .or(replaced_app_id)
+        .unwrap_or(POWERSHELL_APP_ID);
+    let script = build_script(notification, &tag, app_id);
+    (script, join_id(&tag, app_id))
+}
+
+/// まとめたスクリプト 1 つあたりの長さの上限
+///
+/// Windows のコマンドラインは 32,767 文字までなので、余裕を持って分けます。
+const BATCH_SCRIPT_LIMIT: usize = 24_000;
+
+/// 複数のトーストのスクリプトを、PowerShell の起動回数が少なくなるようにまとめる
+///
+/// 各スクリプトはスクリプトブロック（`& { ... }`）で囲み、変数が混ざらないようにします。
+/// `try` / `catch` で 1 件の失敗（通知が無効な場合の `throw` など）が残りを止めないようにし、
+/// 結果を `BATCH_MARKER` の行で出力します（`batch_result()` で読む）。
+/// まとめたスクリプトと、それに含まれる通知の位置の範囲を返します。
+fn build_batch_scripts(scripts: &[String]) -> Vec<(String, Range<usize>)> {
+    let mut batches: Vec<(String, Range<usize>)> = Vec::new();
+    for (index, script) in scripts.iter().enumerate() {
+        let block = format!(
+            "try {{ & {{\n{script}\n}} | Out-Null; [Console]::Out.WriteLine('{BATCH_MARKER}{index}:ok') }} \
+catch {{ [Console]::Out.WriteLine('{BATCH_MARKER}{index}:failed:' + [Convert]::ToBase64String([Text.Encoding]::UTF8.GetBytes(\"$_\"))) }}\n"
+        );
+        match batches.last_mut() {
+            Some((batch, range)) if batch.len() + block.len() <= BATCH_SCRIPT_LIMIT => {
+                batch.push_str(&block);
+                range.end = index + 1;
+            }
+            _ => batches.push((block, index..index + 1)),
+        }
+    }
+    batches
+}
+
+/// まとめたスクリプトの stdout から、`index` 番目の通知の結果を読む
+///
+/// 失敗の理由に `DISABLED_MARKER` があれば `NotificationsDisabled` にします（`script_result()` と同じ）。
+fn batch_result(stdout: &str, index: usize) -> Result<()> {
+    let prefix = format!("{BATCH_MARKER}{index}:");
+    let status = stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(&prefix));
+    match status {
+        Some("ok") => Ok(()),
+        Some(status) => {
+            let reason = status
+                .strip_prefix("failed:")
+                .and_then(|encoded| STANDARD.decode(encoded).ok())
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_default();
+            script_result(false, String::new(), &reason).map(|_| ())
+        }
+        None => Err(NotificationError::SendFailed {
+            backend: "Windows".to_string(),
+            reason: "PowerShell stopped before showing the notification".to_string(),
+        }),
+    }
+}
+
+/// ハンドルの ID を作る
+///
+/// 履歴からの削除には送信元の AppUserModelID も必要なため、
+/// PowerShell 以外の ID で送った場合は `Tag@AppUserModelID` にします。
+fn join_id(tag: &str, app_id: &str) -> String {
+    if app_id == POWERSHELL_APP_ID {
+        tag.to_string()
+    } else {
+        format!("{tag}@{app_id}")
+    }
+}
+
+/// ハンドルの ID を (Tag, AppUserModelID) に分解する（`join_id()` の逆）
+fn split_id(id: &str) -> (&str, &str) {
+    id.split_once('@').unwrap_or((id, POWERSHELL_APP_ID))
+}
+
+/// AppUserModelID を現在のユーザーに登録する
+///
+/// 未登録の ID ではトーストが表示されないため、`app_name()` で独自の ID を使う前に
+/// 一度だけ実行します。`HKCU\Software\Classes\AppUserModelId\<ID>` に
+/// 表示名（と任意でアイコン）を書き込むだけなので、管理者権限は不要です。
+pub fn register_app_id(app_id: &str, display_name: &str, icon: Option<&Path>) -> Result<()> {
+    if !WindowsNotifier.is_available() {
+        return Err(NotificationError::UnsupportedPlatform(
+            "registering an AppUserModelID requires Windows or WSL".to_string(),
+        ));
+    }
+    let icon = icon.map(|icon| match detect_platform() {
+        Platform::Wsl => wslpath(icon).unwrap_or_else(|| icon.to_path_buf()),
+        _ => icon.to_path_buf(),
+    });
+    run_powershell(&build_register_script(
+        app_id,
+        display_name,
+        icon.as_deref(),
+    ))?;
+    Ok(())
+}
+
+/// AppUserModelID をレジストリに登録する PowerShell スクリプトを構築
+fn build_register_script(app_id: &str, display_name: &str, icon: Option<&Path>) -> String {
+    let mut script = format!(
+        r#"$key = 'HKCU:\Software\Classes\AppUserModelId\{}'
+New-Item -Path $key -Force | Out-Null
+New-ItemProperty -Path $key -Name DisplayName -Value '{}' -PropertyType String -Force | Out-Null"#,
+        escape_powershell(app_id),
+        escape_powershell(display_name)
+    );
+    if let Some(icon) = icon {
+        script.push_str(&format!(
+            "\nNew-ItemProperty -Path $key -Name IconUri -Value '{}' -PropertyType String -Force | Out-Null",
+            escape_powershell(&icon.to_string_lossy())
+        ));
+    }
+    script
+}
+
+/// 指定した時刻に rust-toast を `args` で実行する 1 回限りのタスクを登録する
+///
+/// タスクは実行後（期限切れ後）に自動で削除されます。
+/// WSL では `wsl.exe -e` 経由で Linux 側の実行ファイルを起動します。
+/// 登録したタスクの名前（`schtasks /Delete /TN <名前>` で取り消せる）を返します。
+pub fn schedule_task(when: When, args: &[String]) -> Result<String> {
+    let exe = std::env::current_exe()?;
+    let (program, arguments) = match detect_platform() {
+        Platform::Wsl => {
+            let mut wsl_args = Vec::new();
+            if let Ok(distro) = std::env::var("WSL_DISTRO_NAME") {
+                wsl_args.extend(["-d".to_string(), distro]);
+            }
+            wsl_args.extend(["-e".to_string(), exe.to_string_lossy().to_string()]);
+            wsl_args.extend(args.iter().cloned());
+            ("wsl.exe".to_string(), wsl_args)
+        }
+        _ => (exe.to_string_lossy().to_string(), args.to_vec()),
+    };
+
+    let name = format!("rust-toast-{}", generate_id());
+    run_powershell(&build_schedule_script(&name, when, &program, &arguments))?;
+    Ok(name)
+}
+
+/// 1 回限りのタスクを登録する PowerShell スクリプトを構築
+fn build_schedule_script(name: &str, when: When, program: &str, args: &[String]) -> String {
+    let at = match when {
+        When::At { hour, minute } => format!(
+            "$at = [datetime]::Today.AddHours({hour}).AddMinutes({minute})\nif ($at -le (Get-Date)) {{ $at = $at.AddDays(1) }}"
+        ),
+        When::In(duration) => format!("$at = (Get-Date).AddSeconds({})", duration.as_secs()),
+    };
+    let command_line: Vec<String> = args.iter().map(|arg| quote_argument(arg)).collect();
+    format!(
+        r#"{at}
+$action = New-ScheduledTaskAction -Execute '{}' -Argument '{}'
+$trigger = New-ScheduledTaskTrigger -Once -At $at
+$trigger.EndBoundary = $at.AddMinutes(10).ToString('s')
+$settings = New-ScheduledTaskSettingsSet -DeleteExpiredTaskAfter (New-TimeSpan -Seconds 0) -StartWhenAvailable -AllowStartIfOnBatteries -DontStopIfGoingOnBatteries
+Register-ScheduledTask -TaskName '{}' -Action $action -Trigger $trigger -Settings $settings | Out-Null"#,
+        escape_powershell(program),
+        escape_powershell(&command_line.join(" ")),
+        escape_powershell(name),
+    )
+}
+
+/// Windows のコマンドライン（`CommandLineToArgvW` の規則）用に引数をクォートする
+///
+/// 空白や `"` を含む引数だけを `"..."` で囲み、`"` の直前のバックスラッシュを倍にします。
+fn quote_argument(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// PowerShell でスクリプトを実行し、標準出力を返す
+///
+/// WSL からは powershell.exe として呼び出せる（Windows 側のパスが自動解決）。
+/// `Config::command_timeout` を過ぎたら強制終了する。
+fn run_powershell(script: &str) -> Result<String> {
+    run_script(script, config::current().command_timeout)
+}
+
+/// スクリプトを実行し、標準出力を返す（`warm_up()` で常駐させた PowerShell があればそれを使う）
+///
+/// 常駐している PowerShell とのやり取りが時間切れ以外で失敗した場合は、新しく起動して実行します
+/// （時間切れの場合はトーストが表示済みかもしれないので、送り直さずにエラーを返す）。
+fn run_script(script: &str, timeout: Duration) -> Result<String> {
+    match runspace::run(script, timeout) {
+        Some(Ok(reply)) => script_result(reply.success, reply.stdout, &reply.stderr),
+        Some(Err(err @ NotificationError::Timeout { .. })) => Err(err),
+        Some(Err(err)) => {
+            tracing::debug!("resident PowerShell failed, starting a new one: {err}");
+            powershell_result(process::output_within(
+                powershell_command(script)?,
+                timeout,
+            )?)
+        }
+        None => powershell_result(process::output_within(
+            powershell_command(script)?,
+            timeout,
+        )?),
+    }
+}
+
+/// PowerShell のプログラム名（WSL からは Windows 側のものが見つかる）
+const POWERSHELL: &str = "powershell.exe";
+
+/// スクリプトを実行する `powershell.exe` の引数
+fn powershell_args(script: &str) -> Vec<String> {
+    vec![
+        "-NoProfile".to_string(),      // プロファイルを読み込まない（高速化）
+        "-NonInteractive".to_string(), // 対話モードを無効化
+        "-Command".to_string(),        // 後続の引数をコマンドとして実行
+        script.to_string(),
+    ]
+}
+
+/// スクリプトを実行する `powershell.exe` のコマンド（まだ起動しない）
+fn powershell_command(script: &str) -> Result<Command> {
+    let mut command = process::command(POWERSHELL)?;
+    command.args(powershell_args(script));
+    Ok(command)
+}
+
+/// `powershell.exe` の実行結果を、標準出力またはエラーに変換
+fn powershell_result(output: Output) -> Result<String> {
+    script_result(
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        &String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+/// スクリプトの実行結果（成功したか・標準出力・標準エラー出力）を、標準出力またはエラーに変換
+fn script_result(success: bool, stdout: String, stderr: &str) -> Result<String> {
+    if success {
+        Ok(stdout)
+    } else {
+        if let Some(setting) = stderr
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(DISABLED_MARKER))
+        {
+            return Err(NotificationError::NotificationsDisabled {
+                reason: disabled_reason(setting),
+                settings_uri: NOTIFICATION_SETTINGS_URI.to_string(),
+            });
+        }
+        Err(NotificationError::SendFailed {
+            backend: "Windows".to_string(),
+            reason: stderr.to_string(),
+        })
+    }
+}
+
+/// トーストを表示する PowerShell スクリプトを構築
+///
+/// # 学習ポイント: raw 文字列リテラル
+/// r#"..."# を使うと、エスケープなしで文字列を書けます。
+/// 特に PowerShell のような特殊文字が多いスクリプトで便利です。
+fn build_script(notification: &Notification, tag: &str, app_id: &str) -> String {
+    format!(
+        r#"{}
+$toast.Tag = '{}'
+$toast.Group = '{TOAST_GROUP}'
+{}
+$notifier.Show($toast)
+{}"#,
+        build_toast_object(notification),
+        escape_powershell(tag),
+        build_notifier(app_id, notification.collection.as_deref()),
+        build_play_sound(notification)
+    )
+}
+
+/// `$notifier`（ToastNotifier）を作り、通知が無効なら理由を出力して終了するスクリプト
+///
+/// 無効な場合も `Show()` はエラーにならず何も表示されないため、先に確認します。
+///
+/// `collection` を指定した場合は、アクションセンターのトーストコレクションを登録して
+/// そのコレクションの ToastNotifier を使います（Windows 10 1903 以降）。
+/// コレクションはアプリのパッケージ ID が無いと作れない場合があるため、
+/// 失敗したら通常の ToastNotifier で送ります。
+fn build_notifier(app_id: &str, collection: Option<&str>) -> String {
+    let app_id = escape_powershell(app_id);
+    let create = format!(
+        "$notifier = [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{app_id}')"
+    );
+    let create = match collection {
+        None => create,
+        Some(id) => {
+            let id = escape_powershell(id);
+            format!(
+                r#"$notifier = $null
+try {{
+    Add-Type -AssemblyName System.Runtime.WindowsRuntime
+    $manager = [Windows.UI.Notifications.ToastNotificationManager]::GetDefault()
+    $collection = [Windows.UI.Notifications.ToastCollection]::new('{id}', '{id}', '{COLLECTION_LAUNCH_ARGS}{id}', [Uri]'ms-appx:///')
+    $asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {{ $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 }}
+    $saveAction = $asTask | Where-Object {{ $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncAction' }}
+    $saveAction.Invoke($null, @($manager.GetToastCollectionManager('{app_id}').SaveToastCollectionAsync($collection))).Wait()
+    $getNotifier = ($asTask | Where-Object {{ $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' }}).MakeGenericMethod([Windows.UI.Notifications.ToastNotifier])
+    $notifier = $getNotifier.Invoke($null, @($manager.GetToastNotifierForToastCollectionIdAsync('{id}'))).Result
+}} catch {{ }}
+if (-not $notifier) {{ {create} }}"#
+            )
+        }
+    };
+    format!(
+        r#"{create}
+if ($notifier.Setting -ne 'Enabled') {{
+    [Console]::Error.WriteLine('{DISABLED_MARKER}' + $notifier.Setting)
+    throw ('{DISABLED_MARKER}' + $notifier.Setting)
+}}"#
+    )
+}
+
+/// 通知が有効か確認する（無効なら設定ページの URI を含むエラー）
+///
+/// `app_id` が `None` の場合は、デフォルトで使う PowerShell の ID を確認します。
+pub fn check_toasts_enabled(app_id: Option<&str>) -> Result<()> {
+    let script = format!(
+        "{LOAD_WINRT_TYPES}\n{}",
+        build_notifier(app_id.unwrap_or(POWERSHELL_APP_ID), None)
+    );
+    run_powershell(&script).map(|_| ())
+}
+
+/// `NotificationSetting` の値を、通知が無効な理由の説明に変換
+fn disabled_reason(setting: &str) -> String {
+    match setting {
+        "DisabledForApplication" => "turned off for this app".to_string(),
+        "DisabledForUser" => "turned off for all apps".to_string(),
+        "DisabledByGroupPolicy" => "disabled by group policy".to_string(),
+        "DisabledByManifest" => "disabled by the app manifest".to_string(),
+        other => format!("disabled ({other})"),
+    }
+}
+
+/// トーストを表示し、ユーザーの操作を待つ PowerShell スクリプトを構築
+///
+/// `Activated` / `Dismissed` イベントを購読してから表示し、
+/// 最初に届いたイベントを `Interaction` の文字列形式で出力します。
+fn build_wait_script(notification: &Notification) -> String {
+    let play_sound = build_play_sound(notification);
+    let notifier = build_notifier(
+        notification
+            .app_name
+            .as_deref()
+            .unwrap_or(POWERSHELL_APP_ID),
+        notification.collection.as_deref(),
+    );
+    let tag = match &notification.group {
+        Some(group) => format!(
+            "$toast.Tag = '{}'\n$toast.Group = '{TOAST_GROUP}'\n",
+            escape_powershell(group)
+        ),
+        None => String::new(),
+    };
+    // 表示時間 0（無制限）の場合は、操作されるまで待ち続ける
+    let timeout = if notification.timeout == 0 {
+        String::new()
+    } else {
+        format!(
+            " -Timeout {}",
+            notification.timeout.div_ceil(1000) + WAIT_GRACE_SECS
+        )
+    };
+
+    format!(
+        r#"{}
+{tag}Register-ObjectEvent -InputObject $toast -EventName Activated -SourceIdentifier ToastActivated | Out-Null
+Register-ObjectEvent -InputObject $toast -EventName Dismissed -SourceIdentifier ToastDismissed | Out-Null
+{notifier}
+$notifier.Show($toast)
+{play_sound}$raised = Wait-Event{timeout}
+switch ($raised.SourceIdentifier) {{
+    'ToastActivated' {{
+        $activated = [Windows.UI.Notifications.ToastActivatedEventArgs]$raised.SourceEventArgs
+        $arguments = $activated.Arguments
+        if ($arguments -eq '{REPLY_ID}') {{ 'reply:' + ($activated.UserInput['{REPLY_ID}'] -replace '\r?\n', ' ') }}
+        elseif ($arguments) {{ "action:$arguments" }} else {{ 'clicked' }}
+    }}
+    'ToastDismissed' {{
+        if ($raised.SourceEventArgs.Reason -eq 'TimedOut') {{ 'expired' }} else {{ 'dismissed' }}
+    }}
+    default {{ 'expired' }}
+}}
+"#,
+        build_toast_object(notification)
+    )
+}
+
+/// ID（`join_id()` の形式）のトーストを削除する PowerShell スクリプトを構築
+fn build_remove_script(id: &str) -> String {
+    let (tag, app_id) = split_id(id);
+    format!(
+        r#"{LOAD_WINRT_TYPES}
+[Windows.UI.Notifications.ToastNotificationManager]::History.Remove('{}', '{TOAST_GROUP}', '{}')
+"#,
+        escape_powershell(tag),
+        escape_powershell(app_id)
+    )
+}
+
+/// XML から `$toast`（ToastNotification）を作るところまでのスクリプト
+fn build_toast_object(notification: &Notification) -> String {
+    // XML はシングルクォート文字列に埋め込むため、PowerShell 用にもエスケープする
+    let xml = escape_powershell(&build_toast_xml(notification));
+
+    let mut script = format!(
+        r#"{LOAD_WINRT_TYPES}
+$xml = New-Object Windows.Data.Xml.Dom.XmlDocument
+$xml.LoadXml('{xml}')
+$toast = New-Object Windows.UI.Notifications.ToastNotification $xml"#
+    );
+
+    // 非公開の通知は、他の端末（スマートフォン連携など）へ転送しない
+    if notification.private {
+        script.push_str(
+            "\n$toast.NotificationMirroring = [Windows.UI.Notifications.NotificationMirroring]::Disabled",
+        );
+    }
+
+    // アクションセンターから自動で消える時刻
+    // 一時的な通知はポップアップが消えると同時に消し、常駐する通知は期限を付けない
+    let expiration_secs = if notification.transient {
+        Some(popup_secs(notification))
+    } else if notification.resident {
+        None
+    } else {
+        notification
+            .expiration
+            .map(|expiration| expiration.as_secs())
+    };
+    if let Some(secs) = expiration_secs {
+        script.push_str(&format!(
+            "\n$toast.ExpirationTime = [DateTimeOffset]::Now.AddSeconds({})",
+            secs.max(1)
+        ));
+    }
+
+    script
+}
+
+/// トースト通知の XML を構築
+///
+/// ```xml
+/// <toast duration="short">
+///   <visual><binding template="ToastGeneric">
+///     <text>タイトル</text><text>サブタイトル</text><text>メッセージ</text>
+///     <image placement="hero" src="画像"/>
+///     <text placement="attribution">出どころ</text>
+///   </binding></visual>
+///   <actions><action content="ラベル" arguments="ID"/></actions>
+/// </toast>
+/// ```
+fn build_toast_xml(notification: &Notification) -> String {
+    let duration = if is_long_duration(notification) {
+        "long"
+    } else {
+        "short"
+    };
+
+    // 確認されるまで音を鳴らし続ける通知は、アラームとして表示する
+    let alarm = notification.sound_escalation().is_some();
+    let scenario = if alarm {
+        Some("alarm")
+    } else if let Some(scenario) = notification.scenario {
+        Some(toast_scenario(scenario))
+    } else if notification.time_sensitive {
+        // 重要な通知（集中モードでも表示される）
+        Some("urgent")
+    } else {
+        notification.category.as_deref().and_then(category_scenario)
+    };
+    let mut attributes = vec![("duration", duration.to_string())];
+    if let Some(scenario) = scenario {
+        attributes.push(("scenario", scenario.to_string()));
+    }
+    // クリックされたら URL を開く（ブラウザなど、URL のスキームに対応するアプリが起動する）。
+    // 許可していないスキームは send() の前の確認（`check_click_url()`）でエラーになる
+    if let Some(url) = notification
+        .on_click_url
+        .as_ref()
+        .filter(|url| click::check_url(url).is_ok())
+    {
+        attributes.push(("launch", url.clone()));
+        attributes.push(("activationType", "protocol".to_string()));
+    }
+    // 利用者が指定したヒントは <toast> の属性にする（同じ名前なら上書き）
+    for (key, value) in &notification.hints {
+        if !is_xml_name(key) {
+            continue;
+        }
+        attributes.retain(|(name, _)| name != key);
+        attributes.push((key.as_str(), value.clone()));
+    }
+    let mut xml = String::from("<toast");
+    for (name, value) in &attributes {
+        xml.push_str(&format!(r#" {name}="{}""#, escape_xml(value)));
+    }
+    xml.push('>');
+    xml.push_str(r#"<visual><binding template="ToastGeneric">"#);
+    // テキストは 3 行まで（タイトル → サブタイトル → 本文）
+    xml.push_str(&format!("<text>{}</text>", escape_xml(&notification.title)));
+    if let Some(subtitle) = notification.visible_subtitle() {
+        xml.push_str(&format!("<text>{}</text>", escape_xml(subtitle)));
+    }
+    xml.push_str(&format!(
+        "<text>{}</text>",
+        escape_xml(&notification.message)
+    ));
+    if let Some(image) = &notification.image {
+        xml.push_str(&format!(
+            r#"<image placement="hero" src="{}"/>"#,
+            escape_xml(&file_uri(image))
+        ));
+    }
+    // ファイルのアイコン（with_windows_paths() で URL なども変換済み）の時だけアプリのロゴを差し替える
+    if let Some(icon) = notification.icon_path() {
+        xml.push_str(&format!(
+            r#"<image placement="appLogoOverride" src="{}"/>"#,
+            escape_xml(&file_uri(&icon))
+        ));
+    }
+    // 出どころは本文の下に小さく表示される（3 行の制限には数えない）
+    if let Some(attribution) = &notification.attribution {
+        xml.push_str(&format!(
+            r#"<text placement="attribution">{}</text>"#,
+            escape_xml(attribution)
+        ));
+    }
+    xml.push_str("</binding></visual>");
+
+    // 返信欄とアクションボタン（arguments にはアクション ID を入れる）
+    // <input> は <action> より前に置く必要がある
+    if !notification.actions.is_empty() || notification.reply_placeholder.is_some() {
+        xml.push_str("<actions>");
+        if let Some(placeholder) = &notification.reply_placeholder {
+            xml.push_str(&format!(
+                r#"<input id="{REPLY_ID}" type="text" placeHolderContent="{}"/><action content="{REPLY_LABEL}" arguments="{REPLY_ID}" hint-inputId="{REPLY_ID}" activationType="foreground"/>"#,
+                escape_xml(placeholder)
+            ));
+        }
+        for action in &notification.actions {
+            xml.push_str(&format!(
+                r#"<action content="{}" arguments="{}" activationType="foreground"/>"#,
+                escape_xml(&action.label),
+                escape_xml(&action.id)
+            ));
+        }
+        xml.push_str("</actions>");
+    } else if scenario.is_some() {
+        // scenario のあるトーストはボタンが 1 つも無いと通常のトーストとして扱われるため、閉じるボタンを付ける
+        xml.push_str(
+            r#"<actions><action content="" arguments="dismiss" activationType="system"/></actions>"#,
+        );
+    }
+
+    if alarm {
+        xml.push_str(&format!(
+            r#"<audio src="{LOOPING_ALARM_SOUND}" loop="true"/>"#
+        ));
+    } else if notification.silent || notification.sound_path().is_some() {
+        // ファイルは build_play_sound() で鳴らすので、トーストの音は消す
+        xml.push_str(r#"<audio silent="true"/>"#);
+    } else if notification.loop_sound {
+        // 繰り返せるのは Looping の音だけなので、それ以外はアラーム音にする
+        let sound = match notification.sound_name() {
+            Some(sound) if sound.starts_with(LOOPING_SOUND_PREFIX) => sound,
+            _ => LOOPING_ALARM_SOUND,
+        };
+        xml.push_str(&format!(
+            r#"<audio src="{}" loop="true"/>"#,
+            escape_xml(sound)
+        ));
+    } else if let Some(sound) = notification
+        .sound_name()
+        .filter(|sound| sound.starts_with("ms-winsoundevent:"))
+    {
+        xml.push_str(&format!(r#"<audio src="{}"/>"#, escape_xml(sound)));
+    }
+
+    xml.push_str("</toast>");
+    xml
+}
+
+/// `Scenario` に対応するトーストの scenario
+fn toast_scenario(scenario: Scenario) -> &'static str {
+    match scenario {
+        Scenario::Alarm => "alarm",
+        Scenario::Reminder => "reminder",
+        Scenario::IncomingCall => "incomingCall",
+    }
+}
+
+/// カテゴリに対応するトーストの scenario
+///
+/// 表示の仕方が変わる（画面に残り続ける）ため、意味が明らかに一致するものだけ対応させます。
+/// - `call`, `call.*` → `incomingCall`（着信）
+/// - `reminder`, `reminder.*`, `calendar.*` → `reminder`（リマインダー）
+fn category_scenario(category: &str) -> Option<&'static str> {
+    let family = category.split('.').next().unwrap_or(category);
+    match family {
+        "call" => Some("incomingCall"),
+        "reminder" | "calendar" => Some("reminder"),
+        _ => None,
+    }
+}
+
+/// 通知音のファイルを再生する PowerShell スクリプト（1 行、無ければ空）
+///
+/// 未パッケージのアプリのトーストはファイルの音を鳴らせないため、
+/// 表示した後に `System.Media.SoundPlayer`（WAV のみ）で再生します。
+/// `PlaySync()` は再生が終わるまで待つので、PowerShell の終了で音が途切れません。
+fn build_play_sound(notification: &Notification) -> String {
+    match notification.sound_path() {
+        Some(sound) if notification.sound_escalation().is_none() => format!(
+            "(New-Object System.Media.SoundPlayer '{}').PlaySync()\n",
+            escape_powershell(&sound.to_string_lossy())
+        ),
+        _ => String::new(),
+    }
+}
+
+/// ファイルパスを `file:///C:/...` 形式の URI に変換
+///
+/// トーストの `<image src>` はパスではなく URI を受け取ります。
+/// UNC パス（`\\wsl.localhost\...`）は `file://wsl.localhost/...` になります。
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    match path.strip_prefix("//") {
+        Some(unc) => format!("file://{unc}"),
+        None => format!("file:///{}", path.trim_start_matches('/')),
+    }
+}
+
+/// アイコンをファイル（`Icon::Path`）にし、WSL から実行する場合は画像・アイコン・通知音の
+/// パスを Windows のパスに変換する
+///
+/// トーストはファイルのアイコンしか表示できないため、URL・データ URI は
+/// `Notification::with_fetched_icon()` で、テーマの名前は `Notification::icon_file()` でファイルにします。
+/// PowerShell（Windows 側）からは Linux のパスが見えないため、
+/// `wslpath -w` で `C:\...` や `\\wsl.localhost\...` に変換します。
+/// 変換が不要な場合はコピーしません。
+fn with_windows_paths(notification: &Notification) -> Cow<'_, Notification> {
+    let notification = notification.with_fetched_icon();
+    let resolved_icon = match notification.icon() {
+        Some(Icon::Path(_)) | None => None,
+        Some(_) => notification.icon_file(),
+    };
+    let has_paths = notification.image.is_some()
+        || notification.icon_path().is_some()
+        || resolved_icon.is_some()
+        || notification.sound_path().is_some();
+    let wsl = has_paths && detect_platform() == Platform::Wsl;
+    if !wsl && resolved_icon.is_none() {
+        return notification;
+    }
+
+    let mut converted = notification.into_owned();
+    if let Some(icon) = resolved_icon {
+        converted.icon = Some(Icon::Path(icon));
+    }
+    if !wsl {
+        return Cow::Owned(converted);
+    }
+    converted.image = converted
+        .image
+        .map(|image| wslpath(&image).unwrap_or(image));
+    if let Some(icon) = converted.icon_path().and_then(|icon| wslpath(&icon)) {
+        converted.icon = Some(Icon::Path(icon));
+    }
+    if let Some(sound) = converted.sound_path().and_then(|sound| wslpath(&sound)) {
+        converted.sound = Some(sound.to_string_lossy().to_string());
+    }
+    Cow::Owned(converted)
+}
+
+/// `wslpath -w` で Linux のパスを Windows のパスに変換
+fn wslpath(path: &Path) -> Option<PathBuf> {
+    let output = process::command("wslpath")
+        .ok()?
+        .arg("-w")
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let converted = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!converted.is_empty()).then(|| PathBuf::from(converted))
+}
+
+/// `duration="long"` で表示するかどうか
+fn is_long_duration(notification: &Notification) -> bool {
+    // 音の繰り返しは duration="long" でないと無視される
+    notification.timeout == 0
+        || notification.timeout > LONG_DURATION_THRESHOLD
+        || notification.loop_sound
+}
+
+/// トーストが画面に表示される秒数
+fn popup_secs(notification: &Notification) -> u64 {
+    if is_long_duration(notification) {
+        LONG_POPUP_SECS
+    } else {
+        SHORT_POPUP_SECS
+    }
+}
+
+/// XML の属性名として安全に使えるかどうか
+///
+/// 英字か `_` で始まり、英数字と `_` `-` `.` `:` だけを含む名前のみを許可します
+/// （任意の文字列を属性名に埋め込むと XML を壊せてしまうため）。
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+}
+
+/// XML 用の文字列エスケープ
+///
+/// テキストと属性値の両方で安全に使えるよう、5 つの特殊文字を全て置換します。
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
 
 /// PowerShell 用の文字列エスケープ
@@ -81,7 +1014,7 @@ impl Notifier for WindowsNotifier {
 /// ```ignore
 /// escape_powershell("It's working") // => "It''s working"
 /// ```
-fn escape_powershell(s: &str) -> String {
+pub(super) fn escape_powershell(s: &str) -> String {
     s.replace('\'', "''")
 }
 
@@ -92,6 +1025,8 @@ fn escape_powershell(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::notifier::{NotificationBuilder, UrgencyLevel};
+    use std::time::Duration;
 
     #[test]
     fn test_escape_powershell_basic() {
@@ -108,6 +1043,471 @@ mod tests {
         assert_eq!(escape_powershell("'Hello' 'World'"), "''Hello'' ''World''");
     }
 
+    #[test]
+    fn test_quote_argument() {
+        assert_eq!(quote_argument("-m"), "-m");
+        assert_eq!(quote_argument("Stand up"), r#""Stand up""#);
+        assert_eq!(quote_argument(""), r#""""#);
+        assert_eq!(quote_argument(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_argument(r"C:\My Dir\"), r#""C:\My Dir\\""#);
+    }
+
+    #[test]
+    fn test_build_schedule_script() {
+        let args = ["-m".to_string(), "It's time".to_string()];
+        let script = build_schedule_script(
+            "rust-toast-1",
+            When::At {
+                hour: 18,
+                minute: 5,
+            },
+            r"C:\bin\rust-toast.exe",
+            &args,
+        );
+        assert!(script.contains("AddHours(18).AddMinutes(5)"));
+        assert!(script.contains(r#"-Execute 'C:\bin\rust-toast.exe' -Argument '-m "It''s time"'"#));
+        assert!(script.contains("-TaskName 'rust-toast-1'"));
+        assert!(script.contains("-DeleteExpiredTaskAfter"));
+
+        let script = build_schedule_script(
+            "t",
+            When::In(std::time::Duration::from_secs(600)),
+            "wsl.exe",
+            &[],
+        );
+        assert!(script.contains("(Get-Date).AddSeconds(600)"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_build_toast_xml_basic() {
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .message("1 < 2")
+            .build();
+
+        assert_eq!(
+            build_toast_xml(&notification),
+            r#"<toast duration="short"><visual><binding template="ToastGeneric"><text>Build</text><text>1 &lt; 2</text></binding></visual></toast>"#
+        );
+    }
+
+    #[test]
+    fn test_build_toast_xml_layout() {
+        let notification = NotificationBuilder::new()
+            .title("Build")
+            .subtitle("ci-01")
+            .message("Passed")
+            .image("/tmp/chart.png")
+            .attribution("via make & ssh")
+            .build();
+
+        assert!(build_toast_xml(&notification).contains(
+            r#"<text>Build</text><text>ci-01</text><text>Passed</text><image placement="hero" src="file:///tmp/chart.png"/><text placement="attribution">via make &amp; ssh</text></binding>"#
+        ));
+    }
+
+    #[test]
+    fn test_build_toast_xml_with_image() {
+        let notification = NotificationBuilder::new()
+            .message("Chart")
+            .image("/tmp/chart.png")
+            .build();
+
+        assert!(build_toast_xml(&notification)
+            .contains(r#"<image placement="hero" src="file:///tmp/chart.png"/>"#));
+        assert_eq!(
+            file_uri(Path::new(r"C:\Users\me\chart.png")),
+            "file:///C:/Users/me/chart.png"
+        );
+        assert_eq!(
+            file_uri(Path::new(r"\\wsl.localhost\Ubuntu\tmp\chart.png")),
+            "file://wsl.localhost/Ubuntu/tmp/chart.png"
+        );
+    }
+
+    #[test]
+    fn test_build_toast_xml_with_icon() {
+        let named = NotificationBuilder::new().icon("dialog-warning").build();
+        assert!(!build_toast_xml(&named).contains("appLogoOverride"));
+
+        let file = NotificationBuilder::new().icon("/opt/app/logo.png").build();
+        assert!(build_toast_xml(&file)
+            .contains(r#"<image placement="appLogoOverride" src="file:///opt/app/logo.png"/>"#));
+    }
+
+    #[test]
+    fn test_build_toast_xml_alarm_loops_sound() {
+        let notification = NotificationBuilder::new()
+            .message("Disk full")
+            .urgency(UrgencyLevel::Critical)
+            .require_ack()
+            .repeat_sound(Duration::from_secs(10))
+            .build();
+
+        let xml = build_toast_xml(&notification);
+        assert!(xml.starts_with(r#"<toast duration="long" scenario="alarm">"#));
+        assert!(xml.contains(r#"arguments="dismiss" activationType="system""#));
+        assert!(xml.contains(r#"loop="true""#));
+
+        // 重要でなければアラームにしない
+        let normal = NotificationBuilder::new()
+            .require_ack()
+            .repeat_sound(Duration::from_secs(10))
+            .build();
+        assert!(!build_toast_xml(&normal).contains("alarm"));
+    }
+
+    #[test]
+    fn test_sound_file_plays_after_show() {
+        let notification = NotificationBuilder::new()
+            .message("Done")
+            .sound("/media/chime.wav")
+            .build();
+
+        assert!(build_toast_xml(&notification).contains(r#"<audio silent="true"/>"#));
+        let script = build_script(&notification, "abc", POWERSHELL_APP_ID);
+        let show = script.find(".Show($toast)").unwrap();
+        let play = script
+            .find("(New-Object System.Media.SoundPlayer '/media/chime.wav').PlaySync()")
+            .unwrap();
+        assert!(show < play);
+    }
+
+    #[test]
+    fn test_build_toast_xml_silent_and_loop() {
+        let silent = NotificationBuilder::new()
+            .sound("ms-winsoundevent:Notification.Mail")
+            .silent(true)
+            .build();
+        assert!(build_toast_xml(&silent).ends_with(r#"<audio silent="true"/></toast>"#));
+
+        let looping = NotificationBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .sound("ms-winsoundevent:Notification.Looping.Call2")
+            .loop_sound(true)
+            .build();
+        let xml = build_toast_xml(&looping);
+        assert!(xml.starts_with(r#"<toast duration="long">"#));
+        assert!(xml
+            .contains(r#"<audio src="ms-winsoundevent:Notification.Looping.Call2" loop="true"/>"#));
+
+        let default_sound = NotificationBuilder::new().loop_sound(true).build();
+        assert!(build_toast_xml(&default_sound).contains(LOOPING_ALARM_SOUND));
+    }
+
+    #[test]
+    fn test_build_toast_xml_with_system_sound() {
+        let notification = NotificationBuilder::new()
+            .sound("ms-winsoundevent:Notification.Mail")
+            .build();
+        assert!(build_toast_xml(&notification)
+            .contains(r#"<audio src="ms-winsoundevent:Notification.Mail"/>"#));
+        assert!(build_play_sound(&notification).is_empty());
+    }
+
+    #[test]
+    fn test_build_toast_xml_with_hints() {
+        let notification = NotificationBuilder::new()
+            .hint("launch", "app://open?id=1&x=2")
+            .hint("duration", "long")
+            .hint("bad\" onload=\"x", "1")
+            .timeout(Duration::from_secs(1))
+            .build();
+        let xml = build_toast_xml(&notification);
+        assert!(xml.starts_with(r#"<toast duration="long" launch="app://open?id=1&amp;x=2">"#));
+        assert!(!xml.contains("onload"));
+    }
+
+    #[test]
+    fn test_build_toast_xml_on_click_url() {
+        let notification = NotificationBuilder::new()
+            .on_click_url("https://example.com/?a=1&b=2")
+            .build();
+        assert!(build_toast_xml(&notification)
+            .contains(r#"launch="https://example.com/?a=1&amp;b=2" activationType="protocol""#));
+    }
+
+    #[test]
+    fn test_scripts_check_notification_setting() {
+        let notification = NotificationBuilder::new()
+            .app_name("MyCompany.MyTool")
+            .build();
+        for script in [
+            build_script(&notification, "abc", "MyCompany.MyTool"),
+            build_wait_script(&notification),
+        ] {
+            let check = script.find("$notifier.Setting -ne 'Enabled'").unwrap();
+            let show = script.find("$notifier.Show($toast)").unwrap();
+            assert!(check < show);
+            assert!(script.contains("CreateToastNotifier('MyCompany.MyTool')"));
+        }
+        assert_eq!(
+            disabled_reason("DisabledForApplication"),
+            "turned off for this app"
+        );
+    }
+
+    #[test]
+    fn test_category_scenario() {
+        let call = NotificationBuilder::new().category("call.incoming").build();
+        assert!(build_toast_xml(&call)
+            .starts_with(r#"<toast duration="short" scenario="incomingCall">"#));
+
+        // 対応する scenario が無いカテゴリは通常のトースト
+        let email = NotificationBuilder::new().category("email.arrived").build();
+        assert!(!build_toast_xml(&email).contains("scenario"));
+        assert_eq!(category_scenario("calendar.event"), Some("reminder"));
+    }
+
+    #[test]
+    fn test_time_sensitive_uses_urgent_scenario() {
+        let urgent = NotificationBuilder::new().time_sensitive(true).build();
+        assert!(build_toast_xml(&urgent).contains(r#"scenario="urgent""#));
+    }
+
+    #[test]
+    fn test_scenario_overrides_category() {
+        let reminder = NotificationBuilder::new()
+            .category("call.incoming")
+            .scenario(Scenario::Reminder)
+            .build();
+        let xml = build_toast_xml(&reminder);
+        assert!(xml.starts_with(r#"<toast duration="long" scenario="reminder">"#));
+        // ボタンが無いと通常のトーストになるため、閉じるボタンを付ける
+        assert!(xml.contains(r#"activationType="system""#));
+    }
+
+    #[test]
+    fn test_build_toast_xml_with_actions() {
+        let notification = NotificationBuilder::new()
+            .message("Deploy?")
+            .timeout(Duration::ZERO)
+            .action("yes", "Deploy")
+            .action("no", "Cancel")
+            .build();
+
+        let xml = build_toast_xml(&notification);
+        assert!(xml.starts_with(r#"<toast duration="long">"#));
+        assert!(xml.contains(
+            r#"<actions><action content="Deploy" arguments="yes" activationType="foreground"/><action content="Cancel" arguments="no" activationType="foreground"/></actions>"#
+        ));
+    }
+
+    #[test]
+    fn test_build_toast_xml_with_reply() {
+        let notification = NotificationBuilder::new()
+            .message("Alice: lunch?")
+            .reply_action("Reply to \"Alice\"")
+            .action("mute", "Mute")
+            .build();
+
+        assert!(build_toast_xml(&notification).contains(
+            r#"<actions><input id="rust-toast.reply" type="text" placeHolderContent="Reply to &quot;Alice&quot;"/><action content="Reply" arguments="rust-toast.reply" hint-inputId="rust-toast.reply" activationType="foreground"/><action content="Mute" arguments="mute" activationType="foreground"/></actions>"#
+        ));
+        assert!(build_wait_script(&notification)
+            .contains("'reply:' + ($activated.UserInput['rust-toast.reply']"));
+    }
+
+    #[test]
+    fn test_collection_uses_collection_notifier() {
+        let notification = NotificationBuilder::new()
+            .message("Build passed")
+            .collection("c'i")
+            .build();
+        let script = build_script(&notification, "tag", POWERSHELL_APP_ID);
+        assert!(
+            script.contains("ToastCollection]::new('c''i', 'c''i', 'rust-toast-collection=c''i'")
+        );
+        assert!(script.contains("GetToastNotifierForToastCollectionIdAsync('c''i')"));
+        // 作れなかった場合は通常の ToastNotifier
+        assert!(script.contains("if (-not $notifier) { $notifier = "));
+
+        let plain = build_script(
+            &NotificationBuilder::new().build(),
+            "tag",
+            POWERSHELL_APP_ID,
+        );
+        assert!(!plain.contains("ToastCollection"));
+    }
+
+    #[test]
+    fn test_group_sets_tag_when_waiting() {
+        let notification = NotificationBuilder::new().group("build").build();
+        let script = build_wait_script(&notification);
+        assert!(script.contains("$toast.Tag = 'build'\n$toast.Group = 'rust-toast'"));
+
+        let ungrouped = NotificationBuilder::new().build();
+        assert!(!build_wait_script(&ungrouped).contains("$toast.Tag"));
+    }
+
+    #[test]
+    fn test_build_script_escapes_xml_for_powershell() {
+        let notification = NotificationBuilder::new().message("It's done").build();
+        let script = build_script(&notification, "abc", POWERSHELL_APP_ID);
+        assert!(script.contains("<text>It&apos;s done</text>"));
+        assert!(script.contains("CreateToastNotifier('{1AC14E77"));
+        assert!(script.contains("$toast.Tag = 'abc'"));
+        assert!(script.contains("$toast.Group = 'rust-toast'"));
+        assert!(!script.contains("ExpirationTime"));
+    }
+
+    #[test]
+    fn test_render_matches_delivered_script() {
+        let notification = NotificationBuilder::new()
+            .message("It's done")
+            .group("build")
+            .build();
+        let Invocation::Command { program, args, .. } =
+            WindowsNotifier.render(&notification).unwrap()
+        else {
+            panic!("expected a command");
+        };
+        assert_eq!(program, POWERSHELL);
+        assert_eq!(args[..3], ["-NoProfile", "-NonInteractive", "-Command"]);
+        assert_eq!(args[3], build_delivery(&notification).0);
+        assert!(args[3].contains("$toast.Tag = 'build'"));
+
+        // グループの無い通知も、書き出すたびに同じ Tag になる
+        let notification = NotificationBuilder::new().message("It's done").build();
+        let rendered = WindowsNotifier.render(&notification).unwrap();
+        assert_eq!(rendered, WindowsNotifier.render(&notification).unwrap());
+        let Invocation::Command { args, .. } = rendered else {
+            panic!("expected a command");
+        };
+        assert!(args[3].contains(&format!("$toast.Tag = '{RENDER_ID}'")));
+    }
+
+    #[test]
+    fn test_build_remove_script() {
+        let script = build_remove_script("it's");
+        assert!(script.contains("History.Remove('it''s', 'rust-toast', '{1AC14E77"));
+
+        let script = build_remove_script("abc@MyCompany.MyTool");
+        assert!(script.contains("History.Remove('abc', 'rust-toast', 'MyCompany.MyTool')"));
+    }
+
+    #[test]
+    fn test_build_batch_scripts_wraps_and_splits() {
+        let scripts = ["$toast = 1".to_string(), "$toast = 2".to_string()];
+        let batches = build_batch_scripts(&scripts);
+        assert_eq!(batches.len(), 1);
+        let (script, range) = &batches[0];
+        assert_eq!(range, &(0..2));
+        assert!(script.starts_with("try { & {\n$toast = 1\n}"));
+        assert!(script.contains(&format!("'{BATCH_MARKER}1:ok'")));
+
+        // 上限を超える分は別の起動に分ける
+        let large = "x".repeat(BATCH_SCRIPT_LIMIT / 2);
+        let batches = build_batch_scripts(&[large.clone(), large.clone(), large]);
+        let ranges: Vec<_> = batches.iter().map(|(_, range)| range.clone()).collect();
+        assert_eq!(ranges, [0..1, 1..2, 2..3]);
+    }
+
+    #[test]
+    fn test_batch_result_per_notification() {
+        let stdout = format!(
+            "{BATCH_MARKER}0:ok\n{BATCH_MARKER}1:failed:{}\n{BATCH_MARKER}2:failed:{}\n",
+            STANDARD.encode("boom"),
+            STANDARD.encode(format!("{DISABLED_MARKER}DisabledForUser"))
+        );
+        assert!(batch_result(&stdout, 0).is_ok());
+        assert!(matches!(
+            batch_result(&stdout, 1),
+            Err(NotificationError::SendFailed { reason, .. }) if reason == "boom"
+        ));
+        assert!(matches!(
+            batch_result(&stdout, 2),
+            Err(NotificationError::NotificationsDisabled { .. })
+        ));
+        assert!(batch_result(&stdout, 3).is_err());
+    }
+
+    #[test]
+    fn test_app_name_selects_app_id() {
+        assert_eq!(join_id("abc", POWERSHELL_APP_ID), "abc");
+        assert_eq!(join_id("abc", "MyCompany.MyTool"), "abc@MyCompany.MyTool");
+        assert_eq!(
+            split_id("abc@MyCompany.MyTool"),
+            ("abc", "MyCompany.MyTool")
+        );
+        assert_eq!(split_id("abc"), ("abc", POWERSHELL_APP_ID));
+
+        let notification = NotificationBuilder::new()
+            .app_name("MyCompany.MyTool")
+            .build();
+        assert!(
+            build_wait_script(&notification).contains("CreateToastNotifier('MyCompany.MyTool')")
+        );
+
+        let script = build_register_script(
+            "MyCompany.MyTool",
+            "My Tool",
+            Some(Path::new("C:/tool.png")),
+        );
+        assert!(script.contains(r"HKCU:\Software\Classes\AppUserModelId\MyCompany.MyTool'"));
+        assert!(script.contains("-Name DisplayName -Value 'My Tool'"));
+        assert!(script.contains("-Name IconUri -Value 'C:/tool.png'"));
+    }
+
+    #[test]
+    fn test_build_script_sets_expiration() {
+        let notification = NotificationBuilder::new()
+            .expiration(std::time::Duration::from_secs(90))
+            .build();
+        let script = build_script(&notification, "abc", POWERSHELL_APP_ID);
+        assert!(script.contains("$toast.ExpirationTime = [DateTimeOffset]::Now.AddSeconds(90)"));
+    }
+
+    #[test]
+    fn test_build_script_transient_and_resident() {
+        let transient = NotificationBuilder::new()
+            .timeout(Duration::from_secs(3))
+            .expiration(std::time::Duration::from_secs(90))
+            .transient(true)
+            .build();
+        let script = build_script(&transient, "abc", POWERSHELL_APP_ID);
+        assert!(script.contains("$toast.ExpirationTime = [DateTimeOffset]::Now.AddSeconds(7)"));
+
+        let resident = NotificationBuilder::new()
+            .expiration(std::time::Duration::from_secs(90))
+            .resident(true)
+            .build();
+        let script = build_script(&resident, "abc", POWERSHELL_APP_ID);
+        assert!(!script.contains("ExpirationTime"));
+    }
+
+    #[test]
+    fn test_build_script_private_disables_mirroring() {
+        let notification = NotificationBuilder::new().private(true).build();
+        let script = build_script(&notification, "abc", POWERSHELL_APP_ID);
+        assert!(script.contains("NotificationMirroring]::Disabled"));
+
+        let public = NotificationBuilder::new().private(false).build();
+        let script = build_script(&public, "abc", POWERSHELL_APP_ID);
+        assert!(!script.contains("NotificationMirroring"));
+    }
+
+    #[test]
+    fn test_build_wait_script_subscribes_before_show() {
+        let notification = NotificationBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .build();
+        let script = build_wait_script(&notification);
+
+        let register = script.find("-EventName Activated").unwrap();
+        let show = script.find(".Show($toast)").unwrap();
+        assert!(register < show);
+        assert!(script.contains("Wait-Event -Timeout 35"));
+    }
+
     #[test]
     fn test_backend_name() {
         let notifier = WindowsNotifier;
@@ -118,7 +1518,9 @@ mod tests {
     fn test_windows_notifier_available_on_linux_or_windows() {
         let notifier = WindowsNotifier;
         // Linux（WSL）または Windows では利用可能
-        if cfg!(target_os = "linux") || cfg!(target_os = "windows") {
+        if cfg!(feature = "windows-powershell")
+            && (cfg!(target_os = "linux") || cfg!(target_os = "windows"))
+        {
             assert!(notifier.is_available());
         }
     }
@@ -0,0 +1,162 @@
+//! 標準入力の行を監視して通知する（`journalctl -f | rust-toast pipe --match ERROR`）
+//!
+//! 入力を 1 行ずつ読み、ANSI エスケープを除いた行が `--match` の正規表現に一致したら通知します
+//! （`--match` が無ければ全ての行）。
+//!
+//! | モード | 通知 |
+//! |--------|------|
+//! | `PerLine`（デフォルト） | 一致した行ごとに、その行を本文にして送る |
+//! | `OnEof` | 入力が終わった時に、一致した行をまとめて 1 回だけ送る（末尾を残して切り詰める） |
+//!
+//! `OnEof` が覚えておくのは本文に入る末尾（`STDIN_MAX_CHARS` 文字）の分の行だけなので、
+//! 終わらない入力（`journalctl -f`）でもメモリは増え続けません。
+//!
+//! # 学習ポイント
+//! - `BufRead` を引数に取り、標準入力の代わりにテストでは `Cursor` を渡す
+//! - `read_until()` と `from_utf8_lossy()` で UTF-8 でない入力も読み続ける
+//! - `VecDeque` で末尾の一定量だけを残す
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use regex::Regex;
+
+use crate::error::Result;
+use crate::notifier::NotificationBuilder;
+use crate::text;
+
+/// 通知するタイミング
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// 一致した行ごとに通知する
+    #[default]
+    PerLine,
+    /// 入力の終わりにまとめて通知する
+    OnEof,
+}
+
+/// 入力を最後まで読み、一致した行を `send` で通知する（一致した行の数を返す）
+///
+/// `PerLine` では送信の失敗は警告だけにして読み続けます（`journalctl -f` などを止めないため）。
+pub fn run(
+    mut input: impl BufRead,
+    pattern: Option<&Regex>,
+    mode: Mode,
+    title: Option<&str>,
+    mut send: impl FnMut(NotificationBuilder) -> Result<()>,
+) -> Result<usize> {
+    let builder = |message: String| {
+        let builder = NotificationBuilder::new().message(message);
+        match title {
+            Some(title) => builder.title(title),
+            None => builder,
+        }
+    };
+    let mut matched = MatchedTail::default();
+    let mut count = 0;
+    let mut buf = Vec::new();
+    while input.read_until(b'\n', &mut buf)? > 0 {
+        let line = text::clean_piped_text(&String::from_utf8_lossy(&buf));
+        buf.clear();
+        if line.is_empty() || pattern.is_some_and(|pattern| !pattern.is_match(&line)) {
+            continue;
+        }
+        count += 1;
+        match mode {
+            Mode::PerLine => {
+                if let Err(e) = send(builder(line)) {
+                    tracing::warn!("could not send the notification: {e}");
+                }
+            }
+            Mode::OnEof => matched.push(line),
+        }
+    }
+    if mode == Mode::OnEof && !matched.lines.is_empty() {
+        let joined = Vec::from(matched.lines).join("\n");
+        send(builder(text::clean_piped_text(&joined)))?;
+    }
+    Ok(count)
+}
+
+/// `OnEof` で一致した行の末尾（本文に入る `STDIN_MAX_CHARS` 文字を超えた古い行は捨てる）
+#[derive(Debug, Default)]
+struct MatchedTail {
+    lines: VecDeque<String>,
+    /// `lines` の文字数（行の区切りを含む）
+    chars: usize,
+}
+
+impl MatchedTail {
+    fn push(&mut self, line: String) {
+        self.chars += line.chars().count() + 1;
+        self.lines.push_back(line);
+        // 捨てても上限を超える分が残る間だけ捨てる（切り詰めた印の `…` が付くように）
+        while let Some(front) = self.lines.front() {
+            let len = front.chars().count() + 1;
+            if self.chars - len <= text::STDIN_MAX_CHARS {
+                break;
+            }
+            self.chars -= len;
+            self.lines.pop_front();
+        }
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const LOG: &str = "INFO start\n\x1b[31mERROR disk full\x1b[0m\nINFO ok\nERROR retry\n";
+
+    fn collect(mode: Mode, pattern: Option<&str>) -> (usize, Vec<String>) {
+        let pattern = pattern.map(|p| Regex::new(p).unwrap());
+        let mut sent = Vec::new();
+        let count = run(Cursor::new(LOG), pattern.as_ref(), mode, Some("app"), |b| {
+            let notification = b.build();
+            assert_eq!(notification.title, "app");
+            sent.push(notification.message);
+            Ok(())
+        })
+        .unwrap();
+        (count, sent)
+    }
+
+    #[test]
+    fn test_per_line_sends_each_match() {
+        let (count, sent) = collect(Mode::PerLine, Some("^ERROR"));
+        assert_eq!(count, 2);
+        assert_eq!(sent, ["ERROR disk full", "ERROR retry"]);
+
+        let (count, _) = collect(Mode::PerLine, None);
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn test_on_eof_sends_once() {
+        let (count, sent) = collect(Mode::OnEof, Some("ERROR"));
+        assert_eq!(count, 2);
+        assert_eq!(sent, ["ERROR disk full\nERROR retry"]);
+
+        let (count, sent) = collect(Mode::OnEof, Some("panicked"));
+        assert_eq!((count, sent.len()), (0, 0));
+    }
+
+    #[test]
+    fn test_on_eof_keeps_only_the_tail() {
+        let mut tail = MatchedTail::default();
+        for i in 0..10_000 {
+            tail.push(format!("ERROR {i:05}"));
+        }
+        assert!(tail.lines.len() < 400, "{}", tail.lines.len());
+        assert!(tail.chars > text::STDIN_MAX_CHARS);
+
+        let message = text::clean_piped_text(&Vec::from(tail.lines).join("\n"));
+        assert!(message.starts_with('…'));
+        assert!(message.ends_with("ERROR 09999"));
+    }
+}
@@ -10,6 +10,8 @@
 
 use std::fmt;
 use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
 
 /// 実行環境を表す列挙型
 ///
@@ -19,7 +21,13 @@ use std::fs;
 /// - `Copy`: 暗黙的にコピー可能に（小さな値なので）
 /// - `PartialEq`, `Eq`: `==` で比較可能に
 /// - `Hash`: HashMap のキーとして使用可能に
+/// - `serde` フィーチャーでは `Serialize` / `Deserialize`（`"linux"` などの小文字）
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Platform {
     /// ネイティブ Linux 環境
     Linux,
@@ -115,6 +123,218 @@ fn is_wsl() -> bool {
         .unwrap_or(false) // ファイルが読めない場合は false
 }
 
+/// このマシンのホスト名を取得
+///
+/// # 取得方法
+/// 1. `HOSTNAME` / `COMPUTERNAME`（Windows）環境変数
+/// 2. `/proc/sys/kernel/hostname` → `/etc/hostname`（Linux）
+/// 3. `hostname` コマンド（macOS など）
+///
+/// どれも取得できなければ `None` を返します。
+pub fn hostname() -> Option<String> {
+    let from_env = ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.trim().is_empty());
+    let from_file = || {
+        ["/proc/sys/kernel/hostname", "/etc/hostname"]
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .find(|value| !value.trim().is_empty())
+    };
+    let from_command = || {
+        crate::process::command("hostname")
+            .ok()?
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+    };
+
+    from_env
+        .or_else(from_file)
+        .or_else(from_command)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// SSH 経由のセッションかどうかを判定
+///
+/// sshd がログインシェルに設定する `SSH_CONNECTION` / `SSH_CLIENT` / `SSH_TTY`
+/// のいずれかがあれば、リモートから実行されているとみなします。
+pub fn is_remote_session() -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|key| std::env::var_os(key).is_some_and(|value| !value.is_empty()))
+}
+
+/// 画面がロックされているかどうか
+///
+/// Linux では systemd-logind の `LockedHint`（`loginctl show-session`）を見ます。
+/// 判定できない環境（logind が無い、macOS / Windows）では `false` を返します。
+pub fn is_session_locked() -> bool {
+    if detect_platform() != Platform::Linux {
+        return false;
+    }
+    // XDG_SESSION_ID が無い場合は呼び出し元のセッション（"auto"）を使う
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    command_output(
+        "loginctl",
+        &["show-session", &session, "-p", "LockedHint", "--value"],
+    )
+    .is_some_and(|value| value == "yes")
+}
+
+/// おやすみモード（通知の一時停止、DND）かどうか
+///
+/// 次の順に確認します（判定できなければ `false`）:
+/// - Linux
+///   1. dunst: `dunstctl is-paused` が `true`
+///   2. GNOME: `org.gnome.desktop.notifications show-banners` が `false`
+/// - macOS: `is_macos_focus_active()`
+pub fn is_do_not_disturb() -> bool {
+    match detect_platform() {
+        Platform::Linux => {
+            command_output("dunstctl", &["is-paused"]).is_some_and(|value| value == "true")
+                || command_output(
+                    "gsettings",
+                    &["get", "org.gnome.desktop.notifications", "show-banners"],
+                )
+                .is_some_and(|value| value == "false")
+        }
+        Platform::MacOs => is_macos_focus_active(),
+        _ => false,
+    }
+}
+
+/// macOS の集中モード（Focus、macOS 11 以前はおやすみモード）が有効かどうか
+///
+/// 1. macOS 12 以降: `~/Library/DoNotDisturb/DB/Assertions.json` に有効な集中モードの記録がある
+///    （ターミナルに「フルディスクアクセス」が無いと読めません）
+/// 2. 読めなければ: `defaults -currentHost read com.apple.notificationcenterui doNotDisturb` が `1`
+fn is_macos_focus_active() -> bool {
+    let assertions = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/DoNotDisturb/DB/Assertions.json"));
+    match assertions.and_then(|path| fs::read_to_string(path).ok()) {
+        Some(json) => has_focus_assertions(&json),
+        None => command_output(
+            "defaults",
+            &[
+                "-currentHost",
+                "read",
+                "com.apple.notificationcenterui",
+                "doNotDisturb",
+            ],
+        )
+        .is_some_and(|value| value == "1"),
+    }
+}
+
+/// `Assertions.json` に有効な集中モードの記録（`storeAssertionRecords`）があるか
+fn has_focus_assertions(json: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return false;
+    };
+    value["data"].as_array().is_some_and(|data| {
+        data.iter().any(|entry| {
+            entry["storeAssertionRecords"]
+                .as_array()
+                .is_some_and(|records| !records.is_empty())
+        })
+    })
+}
+
+/// ユーザーが通知を見られない状態（画面ロック中・おやすみモード）かどうか
+pub fn is_user_away() -> bool {
+    is_session_locked() || is_do_not_disturb()
+}
+
+/// コマンドが見つかり成功した場合だけ、標準出力（前後の空白を除く）を返す
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    crate::process::command(program)
+        .ok()?
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// ローカルタイムの UTC からのずれ（分、日本時間なら `540`）
+///
+/// Unix（WSL を含む）は `date +%z`（`TZ` 環境変数も反映されます）、
+/// Windows は PowerShell の `TimeZoneInfo.Local` で取得します。
+/// プロセスで最初に呼んだ時の値を使い回すため、実行中の夏時間の切り替えは反映しません。
+/// 取得できなければ `None` を返します。
+pub fn local_utc_offset() -> Option<i32> {
+    static OFFSET: OnceLock<Option<i32>> = OnceLock::new();
+    *OFFSET.get_or_init(|| match detect_platform() {
+        Platform::Windows => command_output(
+            "powershell.exe",
+            &[
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "[int][TimeZoneInfo]::Local.GetUtcOffset([DateTime]::Now).TotalMinutes",
+            ],
+        )
+        .and_then(|minutes| minutes.parse().ok()),
+        _ => command_output("date", &["+%z"]).and_then(|value| parse_utc_offset(&value)),
+    })
+}
+
+/// `date +%z` の `+0900` / `-0530` を分にする
+fn parse_utc_offset(value: &str) -> Option<i32> {
+    let (sign, digits) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// 実行ユーザーのロケールを取得（`ja_JP` 形式）
+///
+/// POSIX と同じ優先順位（`LC_ALL` → `LC_MESSAGES` → `LANG`）で環境変数を見ます。
+/// 未設定、または `C` / `POSIX`（ロケール無指定）の場合は `None` を返します。
+pub fn locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.trim().is_empty())
+        .map(|value| crate::preset::normalize_locale(&value))
+        .filter(|locale| !locale.is_empty() && locale != "c" && locale != "posix")
+}
+
+/// 実行ファイルを `PATH` から探す
+///
+/// `which` コマンド相当の処理です。見つかった場合は最初にヒットした
+/// フルパスを返します。`PATH` 内の相対ディレクトリ（`.` など）は、
+/// 作業ディレクトリに置かれた偽の実行ファイルを拾わないよう無視します。Windows では `PATHEXT` を考慮せず、
+/// `name` に拡張子を含めて渡してください（例: `powershell.exe`）。
+///
+/// # 例
+/// ```
+/// use rust_toast::platform::find_executable;
+///
+/// if let Some(path) = find_executable("zenity") {
+///     println!("zenity: {}", path.display());
+/// }
+/// ```
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    // std::env::split_paths は OS ごとの区切り文字（: や ;）を吸収してくれる
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
 // ============================================================
 // テスト
 // ============================================================
@@ -165,6 +385,31 @@ mod tests {
         assert!(!Platform::Wsl.is_unix_like());
     }
 
+    #[test]
+    fn test_find_executable_missing() {
+        assert!(find_executable("rust-toast-definitely-missing-binary").is_none());
+    }
+
+    #[test]
+    fn test_parse_utc_offset() {
+        assert_eq!(parse_utc_offset("+0900"), Some(540));
+        assert_eq!(parse_utc_offset("-0530"), Some(-330));
+        assert_eq!(parse_utc_offset("+0000"), Some(0));
+        assert_eq!(parse_utc_offset("JST"), None);
+        assert_eq!(parse_utc_offset("+9"), None);
+    }
+
+    #[test]
+    fn test_has_focus_assertions() {
+        let active = r#"{"data":[{"storeAssertionRecords":[{"assertionDetails":{"assertionDetailsModeIdentifier":"com.apple.focus.work"}}]}]}"#;
+        assert!(has_focus_assertions(active));
+        assert!(!has_focus_assertions(
+            r#"{"data":[{"storeAssertionRecords":[]}]}"#
+        ));
+        assert!(!has_focus_assertions(r#"{"data":[{}]}"#));
+        assert!(!has_focus_assertions("not json"));
+    }
+
     #[test]
     fn test_platform_equality() {
         assert_eq!(Platform::Linux, Platform::Linux);
@@ -172,6 +417,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::clone_on_copy)] // Clone の動作確認のため、あえて clone() を呼ぶ
     fn test_platform_clone_and_copy() {
         let p1 = Platform::MacOs;
         let p2 = p1; // Copy により暗黙コピー
@@ -0,0 +1,135 @@
+//! 翻訳付きプリセット
+//!
+//! よく使う通知の文面を名前付きで登録しておき、ロケールごとの翻訳を持たせます。
+//! 共有スクリプトから `.preset("disk-full")` のように送れば、
+//! 送信時に実行ユーザーの言語（`LANG` など）で文面が選ばれます。
+//!
+//! # 例
+//! ```
+//! use rust_toast::config::{self, Config};
+//! use rust_toast::preset::Preset;
+//!
+//! let mut config = Config::default();
+//! config.presets.insert(
+//!     "disk-full".to_string(),
+//!     Preset::new()
+//!         .translation("en", "Disk full", "Free up some space")
+//!         .translation("ja", "ディスクがいっぱいです", "空き容量を確保してください"),
+//! );
+//! config::set(config);
+//! ```
+//!
+//! # 学習ポイント
+//! - `BTreeMap` によるキー順が安定したマップ
+//! - 段階的なフォールバック（`ja_JP` → `ja` → 既定のロケール）
+
+use std::collections::BTreeMap;
+
+/// どの翻訳も当てはまらない場合に使うロケール
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// 1 つのロケールの文面
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetText {
+    /// タイトル
+    pub title: String,
+    /// メッセージ本文
+    pub message: String,
+}
+
+/// 名前付きの通知の文面（ロケールごとの翻訳を持つ）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preset {
+    /// ロケール（`ja`, `pt_BR` など）→ 文面
+    translations: BTreeMap<String, PresetText>,
+}
+
+impl Preset {
+    /// 翻訳の無いプリセットを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ロケールの翻訳を追加（同じロケールは上書き）
+    pub fn translation(
+        mut self,
+        locale: impl Into<String>,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.translations.insert(
+            normalize_locale(&locale.into()),
+            PresetText {
+                title: title.into(),
+                message: message.into(),
+            },
+        );
+        self
+    }
+
+    /// ロケールに最も近い翻訳を返す
+    ///
+    /// 1. 完全一致（`ja_JP`）
+    /// 2. 言語部分のみ（`ja`）
+    /// 3. `FALLBACK_LOCALE`（`en`）
+    /// 4. 登録されている最初の翻訳
+    pub fn resolve(&self, locale: &str) -> Option<&PresetText> {
+        let locale = normalize_locale(locale);
+        let language = locale.split('_').next().unwrap_or(&locale);
+
+        self.translations
+            .get(&locale)
+            .or_else(|| self.translations.get(language))
+            .or_else(|| self.translations.get(FALLBACK_LOCALE))
+            .or_else(|| self.translations.values().next())
+    }
+}
+
+/// ロケール表記を `ja_JP` 形式に揃える
+///
+/// `ja-JP`（BCP 47）と `ja_JP.UTF-8@euro`（POSIX）のどちらも受け付けます。
+pub fn normalize_locale(locale: &str) -> String {
+    let locale = locale.split(['.', '@']).next().unwrap_or(locale).trim();
+    let mut parts = locale.split(['_', '-']);
+    let language = parts.next().unwrap_or_default().to_lowercase();
+    match parts.next() {
+        Some(region) if !region.is_empty() => format!("{language}_{}", region.to_uppercase()),
+        _ => language,
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk_full() -> Preset {
+        Preset::new()
+            .translation("en", "Disk full", "Free up some space")
+            .translation("ja", "ディスクがいっぱいです", "空き容量を確保してください")
+            .translation("pt-BR", "Disco cheio", "Libere espaço")
+    }
+
+    #[test]
+    fn test_normalize_locale() {
+        assert_eq!(normalize_locale("ja_JP.UTF-8"), "ja_JP");
+        assert_eq!(normalize_locale("pt-br"), "pt_BR");
+        assert_eq!(normalize_locale("de_DE@euro"), "de_DE");
+        assert_eq!(normalize_locale("EN"), "en");
+    }
+
+    #[test]
+    fn test_resolve_falls_back_in_order() {
+        let preset = disk_full();
+        assert_eq!(preset.resolve("pt_BR").unwrap().title, "Disco cheio");
+        assert_eq!(
+            preset.resolve("ja_JP.UTF-8").unwrap().title,
+            "ディスクがいっぱいです"
+        );
+        assert_eq!(preset.resolve("fr_FR").unwrap().title, "Disk full");
+        assert_eq!(Preset::new().resolve("en"), None);
+    }
+}
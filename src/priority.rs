@@ -0,0 +1,234 @@
+//! 数値の優先度（0〜10）による振り分け
+//!
+//! 緊急度（`UrgencyLevel`）は OS に伝える 3 段階ですが、優先度は送信側で
+//! 振り分けを決めるための細かい段階です。`Config::priority_routes` の表で、
+//! 優先度の範囲ごとに「追加で送るバックエンド」と「静かな時間帯に捨てるかどうか」を決めます。
+//!
+//! 優先度を指定しない通知は、緊急度から決まります（`from_urgency()`）。
+//!
+//! | 緊急度 | 優先度 |
+//! |--------|--------|
+//! | low | 2 |
+//! | normal | 5 |
+//! | critical | 9 |
+//!
+//! # 例
+//! ```
+//! use rust_toast::config::{self, Config};
+//! use rust_toast::priority::{PriorityRoute, QuietHours};
+//! use rust_toast::Backend;
+//!
+//! let mut config = Config::default();
+//! // 8 以上はダイアログにも出す
+//! config.priority_routes.push(PriorityRoute::new(8, 10).also(Backend::Dialog));
+//! // 3 以下は 22:00〜7:00 には送らない
+//! config.priority_routes.push(PriorityRoute::new(0, 3).drop_in_quiet_hours());
+//! config.quiet_hours = Some(QuietHours::new(22 * 60, 7 * 60));
+//! config::set(config);
+//! ```
+//!
+//! # 学習ポイント
+//! - 日付をまたぐ時間帯の判定（`start > end` の場合）
+//! - 表（`Vec`）による宣言的な設定
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::notifier::{Backend, UrgencyLevel};
+use crate::platform;
+
+/// 優先度の最大値
+pub const MAX_PRIORITY: u8 = 10;
+
+/// 緊急度に対応する優先度（優先度を指定しない通知に使う）
+pub fn from_urgency(urgency: UrgencyLevel) -> u8 {
+    match urgency {
+        UrgencyLevel::Low => 2,
+        UrgencyLevel::Normal => 5,
+        UrgencyLevel::Critical => 9,
+    }
+}
+
+// ============================================================
+// 静かな時間帯
+// ============================================================
+
+/// 静かな時間帯（毎日の開始〜終了、分単位）
+///
+/// `start > end` の場合は日付をまたぎます（22:00〜7:00 など）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    /// 開始時刻（0:00 からの分）
+    pub start: u16,
+    /// 終了時刻（0:00 からの分、この時刻は含まない）
+    pub end: u16,
+    /// UTC からのずれ（分、日本時間なら `9 * 60`）
+    pub utc_offset: i32,
+}
+
+impl QuietHours {
+    /// 開始・終了時刻（0:00 からの分、ローカルタイム）で作成
+    ///
+    /// ずれは `platform::local_utc_offset()`（取得できなければ UTC）です。
+    /// 別のタイムゾーンで判定する場合は `utc_offset()` で上書きします。
+    pub fn new(start: u16, end: u16) -> Self {
+        Self {
+            start,
+            end,
+            utc_offset: platform::local_utc_offset().unwrap_or(0),
+        }
+    }
+
+    /// UTC からのずれ（分）を設定
+    pub fn utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    /// `now` が静かな時間帯に入っているかどうか
+    pub fn contains(&self, now: SystemTime) -> bool {
+        let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+        let minute = (secs / 60 + i64::from(self.utc_offset)).rem_euclid(24 * 60) as u16;
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+// ============================================================
+// 振り分けの表
+// ============================================================
+
+/// 優先度の範囲ごとの振り分け
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityRoute {
+    /// 範囲の下限（含む）
+    pub min: u8,
+    /// 範囲の上限（含む）
+    pub max: u8,
+    /// 通常のバックエンドに加えて送るバックエンド
+    pub also: Vec<Backend>,
+    /// 静かな時間帯（`Config::quiet_hours`）には送らない
+    pub drop_in_quiet_hours: bool,
+}
+
+impl PriorityRoute {
+    /// `min`〜`max`（両端を含む）の優先度に当てはまる振り分けを作成
+    pub fn new(min: u8, max: u8) -> Self {
+        Self {
+            min,
+            max,
+            also: Vec::new(),
+            drop_in_quiet_hours: false,
+        }
+    }
+
+    /// 追加で送るバックエンドを加える
+    pub fn also(mut self, backend: Backend) -> Self {
+        self.also.push(backend);
+        self
+    }
+
+    /// 静かな時間帯には送らないようにする
+    pub fn drop_in_quiet_hours(mut self) -> Self {
+        self.drop_in_quiet_hours = true;
+        self
+    }
+
+    /// 優先度が範囲に入っているかどうか
+    pub fn applies_to(&self, priority: u8) -> bool {
+        (self.min..=self.max).contains(&priority)
+    }
+}
+
+/// 優先度に対する振り分けの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Route {
+    /// 送らない（静かな時間帯の低い優先度）
+    pub dropped: bool,
+    /// 追加で送るバックエンド（重複なし、表の順）
+    pub also: Vec<Backend>,
+}
+
+/// 優先度と時刻から振り分けを決める
+///
+/// 当てはまる全ての行を合わせます（追加のバックエンドは和、1 行でも捨てるなら捨てる）。
+pub fn route(config: &Config, priority: u8, now: SystemTime) -> Route {
+    let quiet = config.quiet_hours.is_some_and(|hours| hours.contains(now));
+    let mut route = Route::default();
+    for rule in config
+        .priority_routes
+        .iter()
+        .filter(|r| r.applies_to(priority))
+    {
+        route.dropped |= quiet && rule.drop_in_quiet_hours;
+        for backend in &rule.also {
+            if !route.also.contains(backend) {
+                route.also.push(backend.clone());
+            }
+        }
+    }
+    route
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 1970-01-01 の `hour:minute`（UTC）
+    fn at(hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(hour * 3600 + minute * 60)
+    }
+
+    #[test]
+    fn test_quiet_hours_wraps_midnight() {
+        let night = QuietHours::new(22 * 60, 7 * 60).utc_offset(0);
+        assert!(night.contains(at(23, 0)));
+        assert!(night.contains(at(6, 59)));
+        assert!(!night.contains(at(7, 0)));
+        assert!(!night.contains(at(12, 0)));
+
+        let lunch = QuietHours::new(12 * 60, 13 * 60).utc_offset(0);
+        assert!(lunch.contains(at(12, 30)));
+        assert!(!lunch.contains(at(13, 0)));
+
+        // UTC 14:00 は日本時間 23:00
+        assert!(night.utc_offset(9 * 60).contains(at(14, 0)));
+    }
+
+    #[test]
+    fn test_route_merges_matching_rules() {
+        let mut config = Config {
+            quiet_hours: Some(QuietHours::new(22 * 60, 7 * 60).utc_offset(0)),
+            ..Config::default()
+        };
+        config.priority_routes = vec![
+            PriorityRoute::new(8, 10).also(Backend::Dialog),
+            PriorityRoute::new(9, 10)
+                .also(Backend::Console)
+                .also(Backend::Dialog),
+            PriorityRoute::new(0, 3).drop_in_quiet_hours(),
+        ];
+
+        assert_eq!(
+            route(&config, 10, at(12, 0)).also,
+            vec![Backend::Dialog, Backend::Console]
+        );
+        assert_eq!(route(&config, 5, at(23, 0)), Route::default());
+        assert!(route(&config, 2, at(23, 0)).dropped);
+        assert!(!route(&config, 2, at(12, 0)).dropped);
+    }
+
+    #[test]
+    fn test_from_urgency() {
+        assert_eq!(from_urgency(UrgencyLevel::Normal), 5);
+        assert!(from_urgency(UrgencyLevel::Critical) <= MAX_PRIORITY);
+    }
+}
@@ -0,0 +1,434 @@
+//! 外部コマンド実行ユーティリティ
+//!
+//! PowerShell や osascript などの外部コマンドを起動する際の共通処理です。
+//! 実行ファイルのパスを一度だけ解決してキャッシュし、
+//! 2 回目以降の起動で `PATH` を探し直さないようにします。
+//!
+//! ## 実行ポリシー
+//!
+//! 外部コマンドは必ず絶対パスに解決してから起動します（`PATH` 内の相対ディレクトリは使いません）。
+//! 特権を持つスクリプトから使う場合は、`Config::helpers` でさらに制限できます。
+//!
+//! | 設定 | 効果 |
+//! |------|------|
+//! | `HelperRule::path` | `PATH` を探さず、指定したフルパスを使う |
+//! | `HelperRule::sha256` | 実行ファイルのハッシュが一致しなければ起動しない |
+//! | `HelperPolicy::strict` | `allow` に無いプログラムは起動しない |
+//!
+//! # 例
+//! ```
+//! use rust_toast::config::{self, Config};
+//! use rust_toast::process::HelperRule;
+//!
+//! let mut config = Config::default();
+//! config.helpers.strict = true;
+//! config.helpers.allow.insert(
+//!     "powershell.exe".to_string(),
+//!     HelperRule {
+//!         path: Some("/mnt/c/Windows/System32/WindowsPowerShell/v1.0/powershell.exe".into()),
+//!         sha256: None,
+//!     },
+//! );
+//! config::set(config);
+//! ```
+//!
+//! ## 時間切れ
+//!
+//! PowerShell や osascript は、OS 側の問題で終わらなくなることがあります。
+//! `output_within()` は上限（`Config::command_timeout`、通知ごとに
+//! `NotificationBuilder::command_timeout()`）を過ぎたコマンドを強制終了し、
+//! `NotificationError::Timeout` を返します。
+//!
+//! # 学習ポイント
+//! - `OnceLock` + `Mutex<HashMap>` によるプロセス全体のキャッシュ
+//! - `std::process::Command` の生成をヘルパー関数に集約する
+//! - 許可リスト方式（明示されたものだけを許す）のポリシー
+//! - `Child::try_wait()` による、ブロックしない終了の確認
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::error::{NotificationError, Result};
+use crate::platform::find_executable;
+
+/// 外部コマンドの実行ポリシー
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HelperPolicy {
+    /// プログラム名（`powershell.exe` など）→ 許可ルール
+    pub allow: BTreeMap<String, HelperRule>,
+    /// `true` なら `allow` に無いプログラムを起動しない
+    pub strict: bool,
+}
+
+/// 1 つのプログラムの許可ルール
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HelperRule {
+    /// 固定のフルパス（`None` なら `PATH` から探す）
+    pub path: Option<PathBuf>,
+    /// 実行ファイルの SHA-256（16 進数、大文字・小文字は問わない）
+    pub sha256: Option<String>,
+}
+
+/// プログラム名 → 解決済みのフルパス のキャッシュ
+fn resolved_paths() -> &'static Mutex<HashMap<String, PathBuf>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, PathBuf>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// プログラム名を `PATH` からフルパスに解決する（結果はキャッシュされる）
+///
+/// 見つからない場合はキャッシュしません（後からインストールされた場合に備えるため）。
+fn lookup(program: &str) -> Option<PathBuf> {
+    let mut cache = resolved_paths().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(path) = cache.get(program) {
+        return Some(path.clone());
+    }
+
+    let path = find_executable(program)?;
+    cache.insert(program.to_string(), path.clone());
+    Some(path)
+}
+
+/// プログラム名をフルパスに解決する
+///
+/// `PATH` に見つからない場合や、実行ポリシーで許可されない場合は `None` を返します。
+/// 理由が必要な場合は `check()` を使ってください。
+pub fn resolve(program: &str) -> Option<PathBuf> {
+    check(program).ok()
+}
+
+/// 実行ポリシー（`Config::helpers`）に従ってプログラムをフルパスに解決する
+pub fn check(program: &str) -> Result<PathBuf> {
+    check_with(&config::current().helpers, program)
+}
+
+/// ポリシーを引数で受け取る `check()`（テスト用に分離）
+fn check_with(policy: &HelperPolicy, program: &str) -> Result<PathBuf> {
+    let untrusted = |reason: String| NotificationError::UntrustedHelper {
+        program: program.to_string(),
+        reason,
+    };
+
+    let rule = policy.allow.get(program);
+    if rule.is_none() && policy.strict {
+        return Err(untrusted("not in the allowlist".to_string()));
+    }
+
+    let path = match rule.and_then(|rule| rule.path.clone()) {
+        Some(path) if !path.is_absolute() => {
+            return Err(untrusted(format!(
+                "configured path is not absolute: {}",
+                path.display()
+            )));
+        }
+        Some(path) if !path.is_file() => {
+            return Err(untrusted(format!("{} does not exist", path.display())));
+        }
+        Some(path) => path,
+        None => lookup(program).ok_or_else(|| NotificationError::CommandNotFound {
+            program: program.to_string(),
+        })?,
+    };
+
+    verify_hash(rule, &path).map_err(untrusted)?;
+    Ok(path)
+}
+
+/// 許可ルールにハッシュがあれば、ファイルのハッシュと比較する
+fn verify_hash(rule: Option<&HelperRule>, path: &Path) -> std::result::Result<(), String> {
+    let Some(expected) = rule.and_then(|rule| rule.sha256.as_deref()) else {
+        return Ok(());
+    };
+    let actual = sha256_file(path).map_err(|e| e.to_string())?;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!(
+            "sha256 of {} is {actual}, expected {expected}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// フルパスで指定されたプログラム（プラグインなど）を実行ポリシーで確認する
+///
+/// 許可ルールはファイル名で探します。`strict` ならルールが必要で、
+/// ルールに `path` があれば一致しなければならず、`sha256` があれば比較します。
+pub fn check_path(path: &Path) -> Result<()> {
+    check_path_with(&config::current().helpers, path)
+}
+
+/// ポリシーを引数で受け取る `check_path()`（テスト用に分離）
+fn check_path_with(policy: &HelperPolicy, path: &Path) -> Result<()> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let untrusted = |reason: String| NotificationError::UntrustedHelper {
+        program: name.clone(),
+        reason,
+    };
+
+    let rule = policy.allow.get(&name);
+    if rule.is_none() && policy.strict {
+        return Err(untrusted("not in the allowlist".to_string()));
+    }
+    if let Some(expected) = rule.and_then(|rule| rule.path.as_deref()) {
+        if expected != path {
+            return Err(untrusted(format!(
+                "configured path is {}, not {}",
+                expected.display(),
+                path.display()
+            )));
+        }
+    }
+    verify_hash(rule, path).map_err(untrusted)
+}
+
+/// ファイルの SHA-256 を 16 進数（小文字）で返す
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let digest = Sha256::digest(std::fs::read(path)?);
+    Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// 実行ポリシーで確認したフルパスで `Command` を作成する
+///
+/// 見つからない場合や許可されない場合はエラーを返します
+/// （プログラム名のまま起動すると、OS が `PATH` を探し直してしまうため）。
+pub fn command(program: &str) -> Result<Command> {
+    Ok(Command::new(check(program)?))
+}
+
+/// 終了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// `Command::output()` と同じく実行して出力を集めるが、`timeout` を過ぎたら強制終了する
+///
+/// 時間切れの場合は `NotificationError::Timeout` を返します。
+pub fn output_within(command: Command, timeout: Duration) -> Result<Output> {
+    run_within(command, None, timeout)
+}
+
+/// `output_within()` と同じだが、`input` を標準入力に書き込む
+pub fn output_with_input(command: Command, input: &[u8], timeout: Duration) -> Result<Output> {
+    run_within(command, Some(input.to_vec()), timeout)
+}
+
+fn run_within(mut command: Command, input: Option<Vec<u8>>, timeout: Duration) -> Result<Output> {
+    let program = program_name(&command);
+    let _span = tracing::debug_span!("command", %program).entered();
+    let started = Instant::now();
+    let stdin = if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    let mut child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // 書き込みも別スレッドで行う（入力を読まずに出力する子プロセスで止まらないように）
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), input) {
+        thread::spawn(move || {
+            // 子プロセスが入力を読まずに終わった場合の失敗は無視する
+            let _ = pipe.write_all(&input);
+        });
+    }
+    // パイプが一杯になると子プロセスが止まるので、終了を待つ間も読み続ける
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // 終了の失敗（すでに終わっていた）は無視する
+            let _ = child.kill();
+            let _ = child.wait();
+            tracing::debug!(?timeout, "timed out, killed");
+            return Err(NotificationError::Timeout {
+                backend: None,
+                program,
+                elapsed: timeout,
+            });
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    tracing::debug!(%status, elapsed = ?started.elapsed(), "finished");
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// エラーに表示するプログラム名（フルパスのファイル名部分）
+pub(crate) fn program_name(command: &Command) -> String {
+    let program = Path::new(command.get_program());
+    program
+        .file_name()
+        .unwrap_or(program.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// パイプを別スレッドで最後まで読む
+fn read_to_end(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            // 読み込みの失敗は、そこまでの出力を返す
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(path: Option<&Path>, sha256: Option<&str>) -> HelperRule {
+        HelperRule {
+            path: path.map(Path::to_path_buf),
+            sha256: sha256.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_resolve_missing_program() {
+        assert!(resolve("rust-toast-definitely-missing-binary").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_is_cached() {
+        // sh はどの Unix 環境にもある
+        let first = resolve("sh").expect("sh should exist");
+        let second = resolve("sh").unwrap();
+        assert_eq!(first, second);
+        assert!(first.is_absolute());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_within_kills_hung_command() {
+        let mut echo = command("sh").unwrap();
+        echo.args(["-c", "echo out; echo err >&2"]);
+        let output = output_within(echo, Duration::from_secs(10)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            (&output.stdout[..], &output.stderr[..]),
+            (&b"out\n"[..], &b"err\n"[..])
+        );
+
+        let mut hung = command("sh").unwrap();
+        hung.args(["-c", "sleep 30"]);
+        let started = Instant::now();
+        let err = output_within(hung, Duration::from_millis(100)).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(
+            matches!(&err, NotificationError::Timeout { program, .. } if program == "sh"),
+            "{err}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_with_input() {
+        let cat = command("cat").unwrap();
+        let output = output_with_input(cat, b"hello", Duration::from_secs(10)).unwrap();
+        assert_eq!(output.stdout, b"hello");
+    }
+
+    #[test]
+    fn test_check_path_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = dir.path().join("plugin");
+        std::fs::write(&plugin, "abc").unwrap();
+
+        let mut policy = HelperPolicy::default();
+        assert!(check_path_with(&policy, &plugin).is_ok());
+
+        policy.strict = true;
+        assert!(check_path_with(&policy, &plugin).is_err());
+
+        policy.allow.insert("plugin".to_string(), rule(None, None));
+        assert!(check_path_with(&policy, &plugin).is_ok());
+
+        // 別の場所にある同じ名前のファイルは許可しない
+        policy.allow.insert(
+            "plugin".to_string(),
+            rule(Some(&dir.path().join("other/plugin")), None),
+        );
+        assert!(matches!(
+            check_path_with(&policy, &plugin),
+            Err(NotificationError::UntrustedHelper { .. })
+        ));
+    }
+
+    #[test]
+    fn test_command_missing_program_is_error() {
+        assert!(matches!(
+            command("rust-toast-definitely-missing-binary"),
+            Err(NotificationError::CommandNotFound { program })
+                if program == "rust-toast-definitely-missing-binary"
+        ));
+    }
+
+    #[test]
+    fn test_strict_policy_rejects_unlisted_program() {
+        let policy = HelperPolicy {
+            allow: BTreeMap::new(),
+            strict: true,
+        };
+        assert!(matches!(
+            check_with(&policy, "sh"),
+            Err(NotificationError::UntrustedHelper { .. })
+        ));
+    }
+
+    #[test]
+    fn test_configured_path_and_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let helper = dir.path().join("helper");
+        std::fs::write(&helper, "abc").unwrap();
+        // "abc" の SHA-256
+        let hash = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD";
+
+        let mut policy = HelperPolicy::default();
+        policy
+            .allow
+            .insert("helper".to_string(), rule(Some(&helper), Some(hash)));
+        assert_eq!(check_with(&policy, "helper").unwrap(), helper);
+
+        // 中身が変わるとハッシュが一致しない
+        std::fs::write(&helper, "abd").unwrap();
+        assert!(matches!(
+            check_with(&policy, "helper"),
+            Err(NotificationError::UntrustedHelper { .. })
+        ));
+
+        // 相対パスは使わない
+        policy.allow.insert(
+            "helper".to_string(),
+            rule(Some(Path::new("bin/helper")), None),
+        );
+        assert!(check_with(&policy, "helper").is_err());
+    }
+}
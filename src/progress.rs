@@ -0,0 +1,289 @@
+//! 長い処理の進捗通知
+//!
+//! 既存のループのイテレータを `wrap_iter()` で包むだけで、進捗を 1 つの通知に
+//! 表示し続け、終わったら完了の通知に置き換えます。
+//!
+//! ```no_run
+//! use rust_toast::progress;
+//!
+//! let files = vec!["a.txt", "b.txt", "c.txt"];
+//! for file in progress::wrap_iter(files, "Processing files") {
+//!     // file を処理する
+//! #   let _ = file;
+//! }
+//! ```
+//!
+//! | 状況 | 本文 |
+//! |------|------|
+//! | 件数が分かる（`size_hint()` の上限と下限が同じ） | `42% (21/50)` と進捗バー（Linux の `value` ヒント） |
+//! | 件数が分からない | `21 done` |
+//! | 完了 | `Done: 50 items in 12.3s` |
+//!
+//! 通知の更新は `UPDATE_INTERVAL` ごと、かつ割合が変わった時だけに間引きます。
+//! 通知の送信に失敗してもループは止めず、ログ（`tracing` の warn）に出すだけにします。
+//!
+//! 置き換えに対応していないバックエンド（最初の通知のハンドルに ID が無い場合。
+//! osascript、コンソール、フォールバック先など）では、更新のたびに通知が増えないよう
+//! 開始時と完了時の 2 回だけ通知します。
+//!
+//! # 学習ポイント
+//! - `Iterator` を包むアダプタ（`next()` の前後に処理を挟む）
+//! - `size_hint()` による件数の推定
+//! - 依存性の注入（`Clock`）で間引きをテスト可能にする
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{self, Clock};
+use crate::notifier::{NotificationBuilder, NotificationHandle};
+
+/// 進捗の通知を更新する最短の間隔
+pub const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 進捗を通知しながら要素を返すイテレータ
+///
+/// `wrap_iter()` で作成します。最後まで回すと完了の通知を表示します
+/// （途中で drop した場合は進捗の通知が残ります）。
+#[derive(Debug)]
+pub struct Progress<I> {
+    inner: I,
+    /// 通知のタイトル
+    title: String,
+    /// 全ての通知の元にする Builder（バックエンドやアプリ名など）
+    template: NotificationBuilder,
+    /// 全体の件数（分からない場合は `None`）
+    total: Option<u64>,
+    /// 返した要素の数
+    done: u64,
+    throttle: Throttle,
+    started: SystemTime,
+    handle: Option<NotificationHandle>,
+    /// 置き換えができないため、開始と完了だけを通知する
+    summary_only: bool,
+    finished: bool,
+}
+
+/// イテレータを包み、進捗を通知しながら要素を返す
+///
+/// 件数は最初の `size_hint()` から決めます（`Vec` や範囲なら正確です）。
+pub fn wrap_iter<I: IntoIterator>(iter: I, title: impl Into<String>) -> Progress<I::IntoIter> {
+    let inner = iter.into_iter();
+    let total = match inner.size_hint() {
+        (lower, Some(upper)) if lower == upper => Some(lower as u64),
+        _ => None,
+    };
+    let clock = clock::current();
+    Progress {
+        inner,
+        title: title.into(),
+        template: NotificationBuilder::new(),
+        total,
+        done: 0,
+        started: clock.now(),
+        throttle: Throttle::new(clock),
+        handle: None,
+        summary_only: false,
+        finished: false,
+    }
+}
+
+impl<I> Progress<I> {
+    /// 全ての通知の元にする Builder を指定（バックエンド・アプリ名・アイコンなど）
+    ///
+    /// タイトル・本文・置き換え ID は上書きされます。
+    pub fn template(mut self, builder: NotificationBuilder) -> Self {
+        self.template = builder;
+        self
+    }
+
+    /// 表示中の通知を `builder` の内容で置き換える（まだ無ければ新しく表示する）
+    ///
+    /// 最初の通知に ID が無ければ、以降は開始と完了だけを通知するよう切り替えます。
+    fn show(&mut self, builder: NotificationBuilder) {
+        let result = match &mut self.handle {
+            Some(handle) if !self.summary_only => handle.update(builder),
+            Some(_) => builder.send().map(|handle| self.handle = Some(handle)),
+            None => builder.send().map(|handle| {
+                self.summary_only = handle.id().is_none();
+                self.handle = Some(handle);
+            }),
+        };
+        if let Err(err) = result {
+            tracing::warn!("progress not shown: {err}");
+        }
+    }
+
+    /// 完了の通知を表示する
+    fn finish(&mut self) {
+        self.finished = true;
+        let elapsed = self
+            .throttle
+            .clock
+            .now()
+            .duration_since(self.started)
+            .unwrap_or_default();
+        let builder = self
+            .template
+            .clone()
+            .title(self.title.clone())
+            .message(finished_message(self.done, elapsed));
+        self.show(builder);
+    }
+}
+
+impl<I: Iterator> Iterator for Progress<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        match self.inner.next() {
+            Some(item) => {
+                self.done += 1;
+                let percent = self.total.map(|total| percent(self.done, total));
+                if !self.summary_only && self.throttle.should_update(percent) {
+                    let mut builder = self
+                        .template
+                        .clone()
+                        .title(self.title.clone())
+                        .message(progress_message(self.done, self.total));
+                    if let Some(percent) = percent {
+                        // dunst や GNOME などは value ヒントを進捗バーとして表示する
+                        builder = builder.hint("value", percent.to_string());
+                    }
+                    self.show(builder);
+                }
+                Some(item)
+            }
+            None => {
+                if !self.finished {
+                    self.finish();
+                }
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+// ============================================================
+// 更新の間引き
+// ============================================================
+
+/// 通知を更新するかどうかを決める
+#[derive(Debug)]
+struct Throttle {
+    clock: Arc<dyn Clock>,
+    last_update: Option<SystemTime>,
+    last_percent: Option<u32>,
+}
+
+impl Throttle {
+    fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            last_update: None,
+            last_percent: None,
+        }
+    }
+
+    /// 最初の 1 回と、前回から `UPDATE_INTERVAL` 経ち割合が変わった時に `true`
+    ///
+    /// 割合が分からない場合は間隔だけで決めます。
+    fn should_update(&mut self, percent: Option<u32>) -> bool {
+        let now = self.clock.now();
+        let due = self
+            .last_update
+            .is_none_or(|last| now.duration_since(last).unwrap_or_default() >= UPDATE_INTERVAL);
+        let changed = percent.is_none() || percent != self.last_percent;
+        if due && changed {
+            self.last_update = Some(now);
+            self.last_percent = percent;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 完了した割合（0〜100）
+fn percent(done: u64, total: u64) -> u32 {
+    (done.min(total) * 100)
+        .checked_div(total)
+        .map_or(100, |percent| percent as u32)
+}
+
+/// 進捗の本文
+fn progress_message(done: u64, total: Option<u64>) -> String {
+    match total {
+        Some(total) => format!("{}% ({done}/{total})", percent(done, total)),
+        None => format!("{done} done"),
+    }
+}
+
+/// 完了の本文
+fn finished_message(done: u64, elapsed: Duration) -> String {
+    let unit = if done == 1 { "item" } else { "items" };
+    format!("Done: {done} {unit} in {:.1}s", elapsed.as_secs_f64())
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_throttle_waits_for_interval_and_change() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+        let mut throttle = Throttle::new(clock.clone());
+
+        assert!(throttle.should_update(Some(1)));
+        // 間隔が経っていない
+        assert!(!throttle.should_update(Some(2)));
+        clock.advance(UPDATE_INTERVAL);
+        assert!(throttle.should_update(Some(2)));
+        // 間隔は経ったが割合が変わっていない
+        clock.advance(UPDATE_INTERVAL);
+        assert!(!throttle.should_update(Some(2)));
+        // 件数が分からない場合は間隔だけ
+        assert!(throttle.should_update(None));
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(progress_message(21, Some(50)), "42% (21/50)");
+        assert_eq!(progress_message(21, None), "21 done");
+        assert_eq!(
+            finished_message(50, Duration::from_millis(12_345)),
+            "Done: 50 items in 12.3s"
+        );
+        assert_eq!(percent(0, 0), 100);
+    }
+
+    #[test]
+    fn test_summary_only_without_replace_id() {
+        // stdout バックエンドは ID を返さない（置き換えに対応していない）
+        let mut progress = wrap_iter(0..3, "Test")
+            .template(NotificationBuilder::new().backend(crate::notifier::Backend::Stdout));
+        assert_eq!(progress.by_ref().count(), 3);
+
+        assert!(progress.summary_only);
+        let last = progress.handle.as_ref().unwrap().notification();
+        assert!(last.message.starts_with("Done: 3 items"));
+        assert_eq!(last.replace_id, None);
+    }
+
+    #[test]
+    fn test_wrap_iter_uses_exact_size_hint() {
+        let progress = wrap_iter(vec![1, 2, 3], "Test");
+        assert_eq!(progress.total, Some(3));
+        let progress = wrap_iter((0..10).filter(|n| n % 2 == 0), "Test");
+        assert_eq!(progress.total, None);
+    }
+}
@@ -0,0 +1,481 @@
+//! 繰り返しの通知（`rust-toast schedule`）
+//!
+//! 「30 分ごと」「平日の 9:00」のような決まった予定を、`rust-toast schedule add` で
+//! データディレクトリ（`schedules.wal`、`store::schedules`）に登録しておき、
+//! 常駐する `rust-toast schedule run` が時刻ごとに同じ引数の rust-toast を実行します。
+//!
+//! | 指定 | 意味 |
+//! |------|------|
+//! | `--every 30m` | `schedule run` の起動から 30 分ごと |
+//! | `--cron "0 9 * * 1-5"` | cron 形式（分 時 日 月 曜日）、`--utc-offset` の時刻で判定 |
+//!
+//! cron 形式の各フィールドは `*`、数値、範囲（`1-5`）、間隔（`*/15`、`0-30/10`）と、
+//! それらのカンマ区切りに対応します。曜日は 0（または 7）が日曜日です。
+//! 日と曜日の両方を指定した場合は、どちらかに一致すれば実行します（cron と同じ）。
+//!
+//! `schedule run` はログイン時に起動するよう、systemd のユーザーユニットや
+//! タスク スケジューラなどに登録してください。登録の変更は 1 分以内に反映されます。
+//! systemd のユーザーユニットは `rust-toast schedule unit`（`systemd_unit()`）で作れます。
+//! `--hardened` を付けると、常駐するプロセスとクリックで実行するコマンドの権限を絞ります。
+//!
+//! # 学習ポイント
+//! - ビットマスクによる集合の表現（分 = 60 ビット）
+//! - 「次の実行時刻」を求める探索と、一致しない日・時の読み飛ばし
+//! - 純粋な判定（`Runner::tick`）と副作用（プロセスの起動）の分離
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock;
+use crate::error::Result;
+use crate::store::schedules::{self, Schedule};
+use crate::template::civil_date;
+
+/// `schedule run` が登録を読み直す間隔
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 次の実行時刻を探す範囲（これより先に一致しない cron 式は実行しない）
+const SEARCH_DAYS: i64 = 5 * 366;
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+// ============================================================
+// 繰り返しの指定
+// ============================================================
+
+/// 繰り返しの指定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    /// 一定の間隔ごと
+    Every(Duration),
+    /// cron 形式の時刻
+    Cron(Cron),
+}
+
+impl Recurrence {
+    /// `after` より後の次の実行時刻（`None` なら二度と実行しない）
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        match self {
+            Self::Every(interval) => Some(after + *interval),
+            Self::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(interval) => write!(f, "every {}s", interval.as_secs()),
+            Self::Cron(cron) => write!(f, "{cron}"),
+        }
+    }
+}
+
+// ============================================================
+// cron 形式
+// ============================================================
+
+/// cron 形式（分 時 日 月 曜日）の時刻の指定
+///
+/// 各フィールドは一致する値のビットマスクで持ちます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    /// 元の式
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// 日が `*`（曜日だけで判定する）
+    any_day: bool,
+    /// 曜日が `*`（日だけで判定する）
+    any_weekday: bool,
+    /// UTC からのずれ（分、日本時間なら `9 * 60`）
+    utc_offset: i32,
+}
+
+impl Cron {
+    /// UTC からのずれ（分）を設定（デフォルトは UTC）
+    pub fn utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    /// 元の式
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 設定した UTC からのずれ（分）
+    pub fn offset(&self) -> i32 {
+        self.utc_offset
+    }
+
+    /// `after` より後で、最初に一致する時刻（分の始まり）
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let secs = after.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+        let offset = i64::from(self.utc_offset);
+        // ローカル時刻での「1970-01-01 0:00 からの分」
+        let mut minute = secs.div_euclid(60) + 1 + offset;
+        let limit = minute + SEARCH_DAYS * MINUTES_PER_DAY;
+        while minute < limit {
+            let day = minute.div_euclid(MINUTES_PER_DAY);
+            if !self.day_matches(day) {
+                minute = (day + 1) * MINUTES_PER_DAY;
+                continue;
+            }
+            let hour = minute.rem_euclid(MINUTES_PER_DAY) / 60;
+            if !has(self.hours, hour) {
+                minute = (minute.div_euclid(60) + 1) * 60;
+                continue;
+            }
+            if has(self.minutes, minute.rem_euclid(60)) {
+                let utc = u64::try_from((minute - offset) * 60).ok()?;
+                return Some(UNIX_EPOCH + Duration::from_secs(utc));
+            }
+            minute += 1;
+        }
+        None
+    }
+
+    /// 1970-01-01 からの日数 `day` の日付が、月・日・曜日に一致するか
+    fn day_matches(&self, day: i64) -> bool {
+        let Ok(days) = u64::try_from(day) else {
+            return false;
+        };
+        let (_, month, day_of_month) = civil_date(days);
+        // 1970-01-01 は木曜日（4）
+        let weekday = (day + 4).rem_euclid(7);
+        if !has(self.months, month as i64) {
+            return false;
+        }
+        let day_ok = has(self.days, day_of_month as i64);
+        let weekday_ok = has(self.weekdays, weekday);
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday_ok,
+            (false, true) => day_ok,
+            (false, false) => day_ok || weekday_ok,
+        }
+    }
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "invalid cron expression '{s}': expected 5 fields (minute hour day month weekday)"
+            ));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 も日曜日
+        if has(weekdays, 7) {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            source: fields.join(" "),
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+            utc_offset: 0,
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cron \"{}\"", self.source)?;
+        if self.utc_offset != 0 {
+            let sign = if self.utc_offset < 0 { '-' } else { '+' };
+            let minutes = self.utc_offset.unsigned_abs();
+            write!(f, " UTC{sign}{:02}:{:02}", minutes / 60, minutes % 60)?;
+        }
+        Ok(())
+    }
+}
+
+fn has(mask: u64, value: i64) -> bool {
+    (0..64).contains(&value) && mask & (1 << value) != 0
+}
+
+/// cron のフィールド 1 つをビットマスクにする（`min..=max` の範囲）
+fn parse_field(field: &str, min: u32, max: u32) -> std::result::Result<u64, String> {
+    let invalid = || format!("invalid cron field '{field}': expected values in {min}-{max}");
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .ok()
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(invalid)
+    };
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // `5/10` は 5 から最後まで 10 ごと
+                None if part.contains('/') => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+// ============================================================
+// 実行（schedule run）
+// ============================================================
+
+/// 登録された予定ごとの次の実行時刻を覚え、時刻が来たものを選ぶ
+#[derive(Debug, Default)]
+pub struct Runner {
+    /// 予定の ID → 次の実行時刻（`None` は二度と実行しない）
+    next: HashMap<u64, Option<SystemTime>>,
+}
+
+impl Runner {
+    /// 空の状態で作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `now` の時点で実行する予定の ID と、次に確認するまでの時間を返す
+    ///
+    /// 初めて見た予定は `now` から次の時刻を求めます（起動前の分はさかのぼらない）。
+    /// 削除された予定は忘れます。
+    pub fn tick(&mut self, schedules: &[Schedule], now: SystemTime) -> (Vec<u64>, Duration) {
+        self.next
+            .retain(|id, _| schedules.iter().any(|schedule| schedule.id == *id));
+        let mut due = Vec::new();
+        for schedule in schedules {
+            let next = self
+                .next
+                .entry(schedule.id)
+                .or_insert_with(|| schedule.recurrence.next_after(now));
+            if next.is_some_and(|next| next <= now) {
+                due.push(schedule.id);
+                *next = schedule.recurrence.next_after(now);
+            }
+        }
+        let wait = self
+            .next
+            .values()
+            .flatten()
+            .map(|next| next.duration_since(now).unwrap_or_default())
+            .min()
+            .unwrap_or(RELOAD_INTERVAL)
+            .min(RELOAD_INTERVAL);
+        (due, wait)
+    }
+}
+
+/// 登録された予定を実行し続ける（戻らない。エラーは登録を読めなかった場合だけ）
+///
+/// 予定ごとに `rust-toast <登録した引数>` を子プロセスとして起動します（終了は待たない）。
+pub fn run() -> Result<()> {
+    let clock = clock::current();
+    let mut runner = Runner::new();
+    let mut children: Vec<Child> = Vec::new();
+    loop {
+        let list = schedules::list()?;
+        let (due, wait) = runner.tick(&list, clock.now());
+        for schedule in list.iter().filter(|schedule| due.contains(&schedule.id)) {
+            match Command::new(std::env::current_exe()?)
+                .args(&schedule.args)
+                .spawn()
+            {
+                Ok(child) => children.push(child),
+                Err(err) => tracing::warn!("schedule {} failed to start: {err}", schedule.id),
+            }
+        }
+        // 終わった子プロセスを回収する（ゾンビを残さない）
+        children.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        clock.sleep(wait.max(Duration::from_secs(1)));
+    }
+}
+
+// ============================================================
+// systemd のユーザーユニット
+// ============================================================
+
+/// 権限を絞る設定（`systemd_unit()` の `hardened`）
+///
+/// 常駐するプロセスは認証情報（`plugin_env`）を持ち、クリックでコマンドを実行するため、
+/// 特権の獲得と capability を禁止し、作るファイルを本人だけのものにして、
+/// データディレクトリ以外を書き込めなくします。
+const HARDENING: [&str; 8] = [
+    "NoNewPrivileges=yes",
+    "CapabilityBoundingSet=",
+    "AmbientCapabilities=",
+    "UMask=0077",
+    "ProtectSystem=strict",
+    "ProtectHome=read-only",
+    "RestrictSUIDSGID=yes",
+    "LockPersonality=yes",
+];
+
+/// `schedule run` を常駐させる systemd のユーザーユニットを作る
+///
+/// `hardened` なら `HARDENING` と、データディレクトリへの書き込みの許可（`ReadWritePaths=`）を加えます。
+pub fn systemd_unit(exe: &Path, data_dir: &Path, hardened: bool) -> String {
+    let mut service = vec![
+        format!("ExecStart={} schedule run", systemd_quote(exe)),
+        "Restart=on-failure".to_string(),
+    ];
+    if hardened {
+        service.extend(HARDENING.iter().map(|line| line.to_string()));
+        service.push(format!("ReadWritePaths={}", systemd_quote(data_dir)));
+    }
+    format!(
+        "[Unit]\n\
+Description=rust-toast recurring notifications\n\
+PartOf=graphical-session.target\n\
+After=graphical-session.target\n\
+\n\
+[Service]\n\
+{}\n\
+\n\
+[Install]\n\
+WantedBy=graphical-session.target\n",
+        service.join("\n")
+    )
+}
+
+/// パスをユニットファイルの値にする（`%` は指定子なので `%%`、空白や引用符があれば `"` で囲む）
+fn systemd_quote(path: &Path) -> String {
+    let text = path.to_string_lossy().replace('%', "%%");
+    if text.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        text
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01（月曜日）の `hour:minute`（UTC）
+    fn at(hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_704_067_200 + hour * 3600 + minute * 60)
+    }
+
+    #[test]
+    fn test_parse_cron() {
+        let cron: Cron = "*/15 9-17 * * 1-5".parse().unwrap();
+        assert_eq!(cron.minutes, 1 | 1 << 15 | 1 << 30 | 1 << 45);
+        assert!(has(cron.hours, 9) && has(cron.hours, 17) && !has(cron.hours, 18));
+        assert!("0 9 * *".parse::<Cron>().is_err());
+        assert!("60 * * * *".parse::<Cron>().is_err());
+        assert!("*/0 * * * *".parse::<Cron>().is_err());
+        // 7 は日曜日（0）
+        let sunday: Cron = "0 0 * * 7".parse().unwrap();
+        assert_eq!(sunday.weekdays, 1);
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let weekdays: Cron = "0 9 * * 1-5".parse().unwrap();
+        assert_eq!(weekdays.next_after(at(8, 30)), Some(at(9, 0)));
+        // 9:00 ちょうどの次は翌日
+        assert_eq!(weekdays.next_after(at(9, 0)), Some(at(24 + 9, 0)));
+        // 金曜日の 9:00 の次は月曜日
+        assert_eq!(
+            weekdays.next_after(at(4 * 24 + 10, 0)),
+            Some(at(7 * 24 + 9, 0))
+        );
+
+        // UTC+9 の 9:00 は UTC の 0:00
+        let tokyo = weekdays.clone().utc_offset(9 * 60);
+        assert_eq!(
+            tokyo.next_after(at(0, 0) - Duration::from_secs(60)),
+            Some(at(0, 0))
+        );
+        assert_eq!(tokyo.to_string(), "cron \"0 9 * * 1-5\" UTC+09:00");
+
+        // 存在しない日付は実行しない
+        let never: Cron = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at(0, 0)), None);
+    }
+
+    #[test]
+    fn test_runner_fires_due_schedules() {
+        let schedule = |id, recurrence| Schedule {
+            id,
+            recurrence,
+            args: vec!["-m".to_string(), "Stand up".to_string()],
+        };
+        let every = schedule(1, Recurrence::Every(Duration::from_secs(30 * 60)));
+        let cron = schedule(2, Recurrence::Cron("0 9 * * *".parse().unwrap()));
+        let schedules = [every, cron];
+        let mut runner = Runner::new();
+
+        // 初回は次の時刻を覚えるだけ（待ち時間は再読み込みの間隔まで）
+        assert_eq!(
+            runner.tick(&schedules, at(8, 30)),
+            (vec![], RELOAD_INTERVAL)
+        );
+        assert_eq!(runner.tick(&schedules, at(9, 0)).0, [1, 2]);
+        assert_eq!(runner.tick(&schedules, at(9, 10)).0, Vec::<u64>::new());
+        assert_eq!(runner.tick(&schedules, at(9, 30)).0, [1]);
+
+        // 削除された予定は忘れる
+        assert_eq!(runner.tick(&schedules[1..], at(10, 0)).0, Vec::<u64>::new());
+        assert_eq!(runner.next.len(), 1);
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let exe = Path::new("/opt/rust toast/rust-toast");
+        let data_dir = Path::new("/home/me/.local/share/rust-toast");
+
+        let plain = systemd_unit(exe, data_dir, false);
+        assert!(plain.contains("ExecStart=\"/opt/rust toast/rust-toast\" schedule run\n"));
+        assert!(plain.contains("WantedBy=graphical-session.target"));
+        assert!(!plain.contains("NoNewPrivileges"));
+
+        let hardened = systemd_unit(exe, data_dir, true);
+        let lines: Vec<_> = hardened.lines().collect();
+        for directive in [
+            "NoNewPrivileges=yes",
+            "CapabilityBoundingSet=",
+            "AmbientCapabilities=",
+            "UMask=0077",
+            "ProtectSystem=strict",
+            "ProtectHome=read-only",
+            "ReadWritePaths=/home/me/.local/share/rust-toast",
+        ] {
+            assert!(lines.contains(&directive), "missing {directive}");
+        }
+        assert_eq!(systemd_quote(Path::new("/tmp/50%")), "/tmp/50%%");
+    }
+}
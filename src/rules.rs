@@ -0,0 +1,236 @@
+//! 判断ルールのテスト（宣言的なフィクスチャ）
+//!
+//! 通知の例と「期待する結果」を TOML に書いておき、`rust-toast rules test` で
+//! 検証します。ルールをバージョン管理し、CI で壊れていないか確認するためのものです。
+//!
+//! ```toml
+//! [[case]]
+//! name = "disk alerts stay until dismissed"
+//! notification = { message = "Disk full", urgency = "critical" }
+//! expect = { timeout = 0, backend = "linux" }
+//! ```
+//!
+//! - `notification`: `NotificationBuilder::from_json()` と同じキー
+//! - `expect`: `Notification::to_json()` の任意のキーと、
+//!   実際に使われるバックエンド名（`backend`、登録したバックエンドはその名前）、
+//!   表示されるかどうか（`shown`、`Simulation::shown`）
+//!
+//! 結果の判定は `notifier::simulate()` で行うため、何も送信しません。
+//!
+//! # 学習ポイント
+//! - `toml_edit` による TOML の読み込みと `serde_json::Value` への変換
+//! - 期待値と実際の値の差分を集めて報告する
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{self, NotificationBuilder};
+
+/// 1 つのテストケース
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    /// ケースの名前（結果の表示に使う）
+    pub name: String,
+    /// 通知の内容（`NotificationBuilder::from_json()` の形式）
+    pub notification: Value,
+    /// 期待する結果（キー → 値）
+    pub expect: Map<String, Value>,
+}
+
+/// 1 つのケースの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    /// ケースの名前
+    pub name: String,
+    /// 期待と異なった項目（空なら成功）
+    pub failures: Vec<String>,
+}
+
+impl CaseResult {
+    /// 全ての期待を満たしたかどうか
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// フィクスチャファイルを読み込む
+pub fn load(path: &Path) -> Result<Vec<Case>> {
+    let text = std::fs::read_to_string(path)?;
+    parse(&text)
+        .map_err(|reason| NotificationError::Other(format!("{}: {}", path.display(), reason)))
+}
+
+/// フィクスチャの TOML を解析する
+pub fn parse(text: &str) -> std::result::Result<Vec<Case>, String> {
+    let document: toml_edit::DocumentMut = text.parse().map_err(|e| format!("{e}"))?;
+    let cases = match document.get("case").map(item_to_json) {
+        Some(Value::Array(cases)) => cases,
+        Some(_) => return Err("`case` must be an array of tables ([[case]])".to_string()),
+        None => return Err("no [[case]] found".to_string()),
+    };
+
+    cases
+        .into_iter()
+        .enumerate()
+        .map(|(index, case)| {
+            let Value::Object(mut case) = case else {
+                return Err(format!("case #{} is not a table", index + 1));
+            };
+            let name = match case.remove("name") {
+                Some(Value::String(name)) => name,
+                _ => format!("case #{}", index + 1),
+            };
+            let notification = case.remove("notification").unwrap_or(Value::Null);
+            if !notification.is_object() {
+                return Err(format!("{name}: `notification` must be a table"));
+            }
+            let expect = match case.remove("expect") {
+                Some(Value::Object(expect)) if !expect.is_empty() => expect,
+                _ => return Err(format!("{name}: `expect` must be a non-empty table")),
+            };
+            Ok(Case {
+                name,
+                notification,
+                expect,
+            })
+        })
+        .collect()
+}
+
+/// ケースを評価する（送信はしない）
+pub fn run(case: &Case) -> CaseResult {
+    let mut failures = Vec::new();
+    // 送信しないので、on_click_exec も読む
+    match NotificationBuilder::from_json_with_exec(&case.notification.to_string()) {
+        Ok(builder) => {
+            let simulation = notifier::simulate(builder);
+            let mut actual: Map<String, Value> =
+                serde_json::from_str(&simulation.notification.to_json()).unwrap_or_default();
+            // `backend` は強制指定ではなく、実際に使われるバックエンド
+            actual.insert(
+                "backend".to_string(),
+                Value::String(simulation.backend.key().to_string()),
+            );
+            // `shown` は重複・送信数の上限・静かな時間で捨てられないかどうか
+            actual.insert("shown".to_string(), Value::Bool(simulation.shown));
+
+            for (key, expected) in &case.expect {
+                match actual.get(key) {
+                    Some(value) if value == expected => {}
+                    Some(value) => {
+                        failures.push(format!("{key}: expected {expected}, got {value}"))
+                    }
+                    None => failures.push(format!("{key}: unknown field")),
+                }
+            }
+        }
+        Err(err) => failures.push(format!("invalid notification: {err}")),
+    }
+
+    CaseResult {
+        name: case.name.clone(),
+        failures,
+    }
+}
+
+// ============================================================
+// TOML → JSON 変換
+// ============================================================
+
+/// TOML の項目を JSON の値に変換（`NotificationBuilder::from_toml()` でも使う）
+pub(crate) fn item_to_json(item: &toml_edit::Item) -> Value {
+    match item {
+        toml_edit::Item::None => Value::Null,
+        toml_edit::Item::Value(value) => value_to_json(value),
+        toml_edit::Item::Table(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, item)| (key.to_string(), item_to_json(item)))
+                .collect(),
+        ),
+        toml_edit::Item::ArrayOfTables(tables) => Value::Array(
+            tables
+                .iter()
+                .map(|table| item_to_json(&toml_edit::Item::Table(table.clone())))
+                .collect(),
+        ),
+    }
+}
+
+/// TOML の値を JSON の値に変換（日時は文字列にする）
+fn value_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::String(d.value().to_string()),
+        toml_edit::Value::Array(array) => Value::Array(array.iter().map(value_to_json).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_string(), value_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"
+[[case]]
+name = "low urgency is short"
+notification = { message = "Backup done", urgency = "low", backend = "stdout" }
+expect = { timeout = 3000, backend = "stdout", shown = true }
+
+[[case]]
+name = "wrong expectation"
+[case.notification]
+message = "Disk full"
+urgency = "critical"
+backend = "stdout"
+actions = [{ id = "open", label = "Open" }]
+[case.expect]
+timeout = 5000
+colour = "red"
+"#;
+
+    #[test]
+    fn test_parse_fixture() {
+        let cases = parse(FIXTURE).unwrap();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].name, "low urgency is short");
+        assert_eq!(cases[1].notification["actions"][0]["id"], "open");
+        assert_eq!(cases[1].expect["timeout"], 5000);
+    }
+
+    #[test]
+    fn test_run_reports_mismatches() {
+        let cases = parse(FIXTURE).unwrap();
+
+        assert!(run(&cases[0]).passed());
+
+        let failed = run(&cases[1]);
+        assert_eq!(
+            failed.failures,
+            vec!["colour: unknown field", "timeout: expected 5000, got 0"]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("title = 'x'").unwrap_err().contains("no [[case]]"));
+        assert!(parse("[[case]]\nnotification = { message = 'x' }")
+            .unwrap_err()
+            .contains("`expect`"));
+        assert!(parse("[[case]\n").is_err());
+    }
+}
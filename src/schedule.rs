@@ -0,0 +1,260 @@
+//! 指定した時刻に通知する（`--at` / `--after`）
+//!
+//! `--at` は常駐プロセスを使わず、OS のスケジューラに「指定時刻に rust-toast を同じ引数で実行する」
+//! 1 回限りのタスクを登録します。登録したら rust-toast 自身はすぐに終了します。
+//!
+//! | プラットフォーム | 登録先 |
+//! |-----------------|--------|
+//! | Windows / WSL | タスク スケジューラ（`Register-ScheduledTask`、実行後に自動で削除） |
+//! | その他 | 未対応（`UnsupportedPlatform`） |
+//!
+//! | `--at` の値 | 意味 |
+//! |-------------|------|
+//! | `18:30` | 次の 18:30（ローカル時刻、過ぎていれば翌日） |
+//! | `+10m` | 今から 10 分後（`s` / `m` / `h`） |
+//!
+//! `--after 10m` は全てのプラットフォームで使えます。同じ引数の rust-toast を端末から
+//! 切り離した子プロセスとして起動し、子プロセスが `NotificationBuilder::send_after()` で待ってから送ります。
+//! `sleep 600 && rust-toast ...` と違い、端末を閉じても送られます（再起動すると失われます）。
+//!
+//! # 学習ポイント
+//! - `FromStr` による CLI 引数のパース
+//! - 自分自身のコマンドラインを組み立て直して後から再実行する
+
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::error::{NotificationError, Result};
+use crate::notifier;
+use crate::platform::{detect_platform, Platform};
+
+/// 通知する時刻
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    /// 次に来るローカル時刻（時, 分）
+    At { hour: u8, minute: u8 },
+    /// 今からの経過時間
+    In(Duration),
+}
+
+impl FromStr for When {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let s = s.trim();
+        if let Some(relative) = s.strip_prefix('+') {
+            let split = relative
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(relative.len());
+            let (number, unit) = relative.split_at(split);
+            let value: u64 = number
+                .parse()
+                .map_err(|_| format!("invalid time '{s}': expected e.g. +10m"))?;
+            let factor = match unit {
+                "s" => 1,
+                "m" => 60,
+                "h" => 60 * 60,
+                _ => return Err(format!("invalid time unit '{unit}': use s, m or h")),
+            };
+            let secs = value
+                .checked_mul(factor)
+                .ok_or_else(|| format!("invalid time '{s}': too far in the future"))?;
+            return Ok(Self::In(Duration::from_secs(secs)));
+        }
+
+        let invalid = || format!("invalid time '{s}': expected HH:MM or +10m");
+        let (hour, minute) = s.split_once(':').ok_or_else(invalid)?;
+        let hour: u8 = hour.parse().map_err(|_| invalid())?;
+        let minute: u8 = minute.parse().map_err(|_| invalid())?;
+        if hour > 23 || minute > 59 {
+            return Err(invalid());
+        }
+        Ok(Self::At { hour, minute })
+    }
+}
+
+impl fmt::Display for When {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::At { hour, minute } => write!(f, "{hour:02}:{minute:02}"),
+            Self::In(duration) => write!(f, "+{}s", duration.as_secs()),
+        }
+    }
+}
+
+/// `when` に、`args`（プログラム名を除く CLI 引数）で rust-toast を実行するよう登録する
+///
+/// 登録したタスクの名前を返します（取り消す場合に使う）。
+pub fn schedule(when: When, args: &[String]) -> Result<String> {
+    match detect_platform() {
+        Platform::Windows | Platform::Wsl => notifier::schedule_task(when, args),
+        platform => Err(NotificationError::UnsupportedPlatform(format!(
+            "--at is not supported on {platform} yet (only Windows Task Scheduler)"
+        ))),
+    }
+}
+
+/// `--after` で起動した、端末から切り離した子プロセスであることを示す環境変数
+pub const DETACHED_ENV: &str = "RUST_TOAST_DETACHED";
+
+/// 端末から切り離した子プロセスの中で動いているか
+pub fn is_detached() -> bool {
+    std::env::var_os(DETACHED_ENV).is_some()
+}
+
+/// `args`（プログラム名を除く CLI 引数）の rust-toast を、端末から切り離して起動する
+///
+/// 標準入出力は閉じ、Unix では別のプロセスグループにするので、端末を閉じた時の
+/// SIGHUP が届きません。子プロセスの PID（`kill` で取り消せる）を返します。
+pub fn spawn_detached(args: &[String]) -> Result<u32> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .env(DETACHED_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        /// コンソールを引き継がない（`DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP`）
+        const DETACHED: u32 = 0x0000_0008 | 0x0000_0200;
+        command.creation_flags(DETACHED);
+    }
+    Ok(command.spawn()?.id())
+}
+
+/// ファイルのパスを値に取るオプション
+const PATH_OPTIONS: [&str; 4] = ["--icon", "-i", "--image", "--sound"];
+
+/// 予約したタスクに渡す CLI 引数を作る
+///
+/// - `--at` とその値を取り除く（予約したタスクが再び予約しないように）
+/// - `PATH_OPTIONS` の値が `cwd` からの相対パスで、ファイルがあれば絶対パスにする
+///   （タスクは別の作業ディレクトリで動くため）
+/// - 標準入力を読む指定（`--stdin` / `--json`）はエラー（実行時には標準入力が無いため）
+pub fn forwarded_args(args: impl IntoIterator<Item = String>, cwd: &Path) -> Result<Vec<String>> {
+    let absolute = |value: String| {
+        let path = cwd.join(&value);
+        if Path::new(&value).is_relative() && path.exists() {
+            path.to_string_lossy().into_owned()
+        } else {
+            value
+        }
+    };
+    let mut forwarded = Vec::new();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        let (option, inline) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        if matches!(option, "--stdin" | "--json") {
+            return Err(NotificationError::InvalidInput {
+                field: "message".to_string(),
+                reason: format!(
+                    "{option} reads stdin, which a scheduled notification does not have"
+                ),
+            });
+        }
+        if option == "--at" {
+            if inline.is_none() {
+                args.next();
+            }
+        } else if PATH_OPTIONS.contains(&option) {
+            match inline {
+                Some(value) => forwarded.push(format!("{option}={}", absolute(value.to_string()))),
+                None => {
+                    forwarded.push(arg.clone());
+                    if let Some(value) = args.next() {
+                        forwarded.push(absolute(value));
+                    }
+                }
+            }
+        } else {
+            forwarded.push(arg);
+        }
+    }
+    Ok(forwarded)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_when() {
+        assert_eq!(
+            "18:30".parse(),
+            Ok(When::At {
+                hour: 18,
+                minute: 30
+            })
+        );
+        assert_eq!("+10m".parse(), Ok(When::In(Duration::from_secs(600))));
+        assert_eq!("+2h".parse(), Ok(When::In(Duration::from_secs(7200))));
+        assert!("24:00".parse::<When>().is_err());
+        assert!("+10d".parse::<When>().is_err());
+        assert!("+18446744073709551615h".parse::<When>().is_err());
+        assert!("tomorrow".parse::<When>().is_err());
+    }
+
+    #[test]
+    fn test_forwarded_args_drop_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = ["-m", "Stand up", "--at", "18:30", "--at=+5m", "-u", "low"].map(String::from);
+        assert_eq!(
+            forwarded_args(args, dir.path()).unwrap(),
+            vec!["-m", "Stand up", "-u", "low"]
+        );
+    }
+
+    #[test]
+    fn test_forwarded_args_absolute_paths_and_no_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("icon.png"), b"").unwrap();
+        let icon = dir.path().join("icon.png").to_string_lossy().into_owned();
+        let args = [
+            "-m",
+            "-x",
+            "--icon",
+            "icon.png",
+            "--image=icon.png",
+            "--sound",
+            "Ping",
+        ];
+        assert_eq!(
+            forwarded_args(args.map(String::from), dir.path()).unwrap(),
+            vec![
+                "-m".to_string(),
+                "-x".to_string(),
+                "--icon".to_string(),
+                icon.clone(),
+                format!("--image={icon}"),
+                "--sound".to_string(),
+                "Ping".to_string(),
+            ]
+        );
+
+        for args in [&["--stdin"][..], &["--json"], &["--json=a.json"]] {
+            let args = args.iter().map(|arg| arg.to_string());
+            assert!(forwarded_args(args, dir.path()).is_err());
+        }
+
+        // `-m -` は本文の "-" そのもの
+        let args = ["-m", "-"].map(String::from);
+        assert_eq!(forwarded_args(args, dir.path()).unwrap(), ["-m", "-"]);
+    }
+}
@@ -0,0 +1,144 @@
+//! 送信元プロセスの情報
+//!
+//! 「どのスクリプトがこの通知を送ったのか」を後から調べられるように、
+//! 送信元のプロセス名・PID・作業ディレクトリを通知に添付します。
+//!
+//! CLI から送信した場合、rust-toast 自身ではなく、rust-toast を起動した
+//! 親プロセス（シェルスクリプトやビルドツール）の情報を記録します。
+//!
+//! # 学習ポイント
+//! - `/proc/<pid>/` による Linux のプロセス情報の取得
+//! - `std::os::unix::process::parent_id()` による親 PID の取得
+//! - 取得できない情報を `Option` で表す
+
+use std::path::PathBuf;
+
+/// 送信元プロセスの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SenderInfo {
+    /// プロセス ID
+    pub pid: u32,
+    /// プロセス名（取得できない環境では `None`）
+    pub name: Option<String>,
+    /// 作業ディレクトリ（取得できない環境では `None`）
+    pub cwd: Option<PathBuf>,
+}
+
+impl SenderInfo {
+    /// 現在のプロセスの情報（ライブラリとして使う場合）
+    pub fn current() -> Self {
+        let pid = std::process::id();
+        Self {
+            pid,
+            name: std::env::current_exe()
+                .ok()
+                .and_then(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .or_else(|| process_name(pid)),
+            cwd: std::env::current_dir().ok(),
+        }
+    }
+
+    /// 親プロセスの情報（CLI として使う場合）
+    ///
+    /// 親 PID が分からない環境（Windows など）では `current()` と同じです。
+    pub fn parent() -> Self {
+        match parent_pid() {
+            Some(pid) => Self {
+                pid,
+                name: process_name(pid),
+                cwd: process_cwd(pid),
+            },
+            None => Self::current(),
+        }
+    }
+
+    /// JSON 値に変換（`Notification::to_json()` 用）
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "pid": self.pid,
+            "name": self.name,
+            "cwd": self.cwd.as_ref().map(|cwd| cwd.display().to_string()),
+        })
+    }
+}
+
+/// 親プロセスの PID
+fn parent_pid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        Some(std::os::unix::process::parent_id())
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// PID からプロセス名を取得
+///
+/// Linux では `/proc/<pid>/comm`、その他の Unix では `ps` コマンドを使います。
+fn process_name(pid: u32) -> Option<String> {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .ok()
+        .or_else(|| {
+            if !cfg!(unix) {
+                return None;
+            }
+            crate::process::command("ps")
+                .ok()?
+                .args(["-o", "comm=", "-p", &pid.to_string()])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        })?;
+
+    // ps はフルパスを返すことがあるので、ファイル名だけにする
+    let name = name.trim();
+    let name = name.rsplit('/').next().unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// PID から作業ディレクトリを取得（Linux のみ）
+fn process_cwd(pid: u32) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/{pid}/cwd")).ok()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_sender() {
+        let sender = SenderInfo::current();
+        assert_eq!(sender.pid, std::process::id());
+        assert_eq!(sender.cwd, std::env::current_dir().ok());
+        assert!(sender.name.is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parent_sender_on_linux() {
+        let sender = SenderInfo::parent();
+        assert_eq!(sender.pid, std::os::unix::process::parent_id());
+        assert!(sender.name.is_some());
+    }
+
+    #[test]
+    fn test_to_json_value() {
+        let sender = SenderInfo {
+            pid: 42,
+            name: Some("make".to_string()),
+            cwd: Some(PathBuf::from("/src/app")),
+        };
+        let value = sender.to_json_value();
+        assert_eq!(value["pid"], 42);
+        assert_eq!(value["name"], "make");
+        assert_eq!(value["cwd"], "/src/app");
+    }
+}
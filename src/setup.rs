@@ -0,0 +1,797 @@
+//! 初回設定のウィザード（`rust-toast setup`）
+//!
+//! 対話しながら次の順に進め、結果を設定ファイル（`path()`、Linux では `~/.config/rust-toast/config.toml`）に
+//! 書き出します。
+//!
+//! 1. 環境の検出（`doctor` と同じ確認を表示）
+//! 2. 使えるバックエンドごとにテスト通知を送り、表示されたかを尋ねる
+//! 3. 表示されたバックエンドから、デフォルトにするものを選ぶ（空欄なら自動選択）
+//! 4. 送信した通知を履歴に記録するかを尋ねる
+//!
+//! 書き出した設定は CLI の送信時に `load()` で読み込みます。
+//! コマンドライン引数（`--backend` など）の方が優先です。
+//! ウィザードで決めない項目（タイトル・アイコン・通知音・フォールバック・プラグインの環境変数）は
+//! 手で書きます（`rust-toast config init` で雛形を書き出せます）。
+//!
+//! ```toml
+//! # Written by `rust-toast setup`
+//! backend = "windows"
+//! record_history = true
+//! title = "Build bot"
+//! fallback = ["console"]
+//!
+//! [plugins.chat]
+//! CHAT_TOKEN = "..."
+//!
+//! # --profile builds で選ぶ（キーは --json と同じ通知の項目と fallback）
+//! [profile.builds]
+//! backend = "chat"
+//! urgency = "low"
+//! group = "ci"
+//! ```
+//!
+//! # 学習ポイント
+//! - `BufRead` / `Write` を引数に取り、対話処理をテスト可能にする
+//! - 副作用（テスト通知の送信）をクロージャで注入する
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::doctor;
+use crate::error::{NotificationError, Result};
+use crate::notifier::{self, Backend, Icon, NotificationBuilder};
+use crate::rules;
+use crate::store;
+use crate::store::journal;
+use crate::targets::{self, BackendSupport};
+
+/// 設定ファイルの名前
+pub const FILE_NAME: &str = "config.toml";
+
+/// 設定ファイルのパスを指定する環境変数
+pub const CONFIG_ENV: &str = "RUST_TOAST_CONFIG";
+
+/// `rust-toast config init` で書き出す雛形（全てコメント）
+/// 設定ファイルの最上位のキー（`[profile.<名前>]` の中は通知の項目と `fallback`）
+const KEYS: [&str; 8] = [
+    "backend",
+    "record_history",
+    "title",
+    "icon",
+    "sound",
+    "fallback",
+    "plugins",
+    "profile",
+];
+
+pub const TEMPLATE: &str = r#"# rust-toast configuration; command-line options take precedence.
+
+# Backend used when --backend is omitted: linux, windows, macos, dialog, console or a plugin name
+# backend = "linux"
+
+# Record sent notifications in the history (rust-toast history / statusbar)
+# record_history = false
+
+# Title used when --title is omitted; {hostname} is replaced
+# title = "Notification"
+
+# Icon and sound used when --icon / --sound are omitted
+# icon = "dialog-information"
+# sound = "Glass"
+
+# Backends tried in order when sending fails
+# fallback = ["dialog", "console"]
+
+# Environment variables passed to a plugin, e.g. credentials; keep this file private (chmod 600)
+# [plugins.chat]
+# CHAT_TOKEN = "..."
+
+# Defaults selected with --profile builds; keys are the same as in --json, plus fallback
+# [profile.builds]
+# backend = "chat"
+# urgency = "low"
+# app_name = "ci"
+# group = "builds"
+"#;
+
+/// 設定ファイルの内容（ウィザードで決めるものと、手で書くもの）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    /// `--backend` を指定しなかった時に使うバックエンド（`None` = 自動選択）
+    pub backend: Option<Backend>,
+    /// 送信した通知を履歴に記録するかどうか（`Config::record_history`）
+    pub record_history: bool,
+    /// タイトルを指定しなかった時のタイトル（`Config::title_template`）
+    pub title: Option<String>,
+    /// アイコンを指定しなかった時のアイコン（`Config::icon`）
+    pub icon: Option<Icon>,
+    /// 通知音を指定しなかった時の通知音（`Config::sound`）
+    pub sound: Option<String>,
+    /// 送信に失敗した時に試すバックエンド（`Config::fallback_chain`）
+    pub fallback: Option<Vec<Backend>>,
+    /// プラグインの名前ごとに渡す環境変数（`Config::plugin_env`）
+    pub plugin_env: BTreeMap<String, BTreeMap<String, String>>,
+    /// `--profile` で選ぶデフォルト（`[profile.<名前>]`）
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// `[profile.<名前>]` のデフォルト
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// 通知の項目（キーは `NotificationBuilder::from_json()` と同じ。`backend` で送り先も決める）
+    pub defaults: serde_json::Map<String, serde_json::Value>,
+    /// 送信に失敗した時に試すバックエンド（`Config::fallback_chain`）
+    pub fallback: Option<Vec<Backend>>,
+}
+
+impl Profile {
+    /// 通知の項目を設定した Builder（CLI や `--json` の指定を `merge()` で重ねる）
+    ///
+    /// 設定ファイルは利用者自身が書いたものなので、`on_click_exec` も読みます。
+    pub fn builder(&self) -> Result<NotificationBuilder> {
+        NotificationBuilder::from_json_with_exec(
+            &serde_json::Value::Object(self.defaults.clone()).to_string(),
+        )
+    }
+
+    /// グローバル設定（`config::current()`）に反映する
+    pub fn apply_config(&self) {
+        if let Some(fallback) = &self.fallback {
+            let mut config = config::current();
+            config.fallback_chain = fallback.clone();
+            config::set(config);
+        }
+    }
+}
+
+impl Settings {
+    /// 設定ファイルの内容に変換
+    pub fn to_toml(&self) -> String {
+        // バックエンド名は英小文字だけなので、エスケープせずに書ける
+        let mut text = String::from("# Written by `rust-toast setup`\n");
+        if let Some(backend) = &self.backend {
+            text.push_str(&format!("backend = \"{}\"\n", backend.key()));
+        }
+        text.push_str(&format!("record_history = {}\n", self.record_history));
+        let strings = [
+            ("title", self.title.clone()),
+            ("icon", self.icon.as_ref().map(Icon::to_string)),
+            ("sound", self.sound.clone()),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                text.push_str(&format!("{key} = {}\n", quote(&value)));
+            }
+        }
+        if let Some(fallback) = &self.fallback {
+            let names: Vec<String> = fallback
+                .iter()
+                .map(|b| format!("\"{}\"", b.key()))
+                .collect();
+            text.push_str(&format!("fallback = [{}]\n", names.join(", ")));
+        }
+        for (plugin, env) in &self.plugin_env {
+            text.push_str(&format!("\n[plugins.{}]\n", quote(plugin)));
+            for (key, value) in env {
+                text.push_str(&format!("{} = {}\n", quote(key), quote(value)));
+            }
+        }
+        for (name, profile) in &self.profiles {
+            text.push_str(&format!("\n[profile.{}]\n", quote(name)));
+            for (key, value) in &profile.defaults {
+                text.push_str(&format!("{} = {}\n", quote(key), toml_value(value)));
+            }
+            if let Some(fallback) = &profile.fallback {
+                let names: Vec<String> = fallback.iter().map(|b| quote(b.key())).collect();
+                text.push_str(&format!("fallback = [{}]\n", names.join(", ")));
+            }
+        }
+        text
+    }
+
+    /// `--profile` で選んだプロファイル
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            NotificationError::InvalidInput {
+                field: "profile".to_string(),
+                reason: format!("unknown profile '{name}' (known: {})", known.join(", ")),
+            }
+        })
+    }
+
+    /// 設定ファイルの内容から読み込む（知らないキーは無視）
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let document: toml_edit::DocumentMut = text.parse().map_err(|e| invalid(&e))?;
+        let string = |key: &str| -> Result<Option<String>> {
+            match document.get(key) {
+                Some(item) => Ok(Some(item.as_str().ok_or_else(|| invalid(key))?.to_string())),
+                None => Ok(None),
+            }
+        };
+        let backend = string("backend")?.map(|name| name.parse()).transpose()?;
+        let record_history = match document.get("record_history") {
+            Some(item) => item.as_bool().ok_or_else(|| invalid("record_history"))?,
+            None => false,
+        };
+        let fallback = document.get("fallback").map(parse_fallback).transpose()?;
+        let mut plugin_env = BTreeMap::new();
+        if let Some(item) = document.get("plugins") {
+            let plugins = item.as_table_like().ok_or_else(|| invalid("plugins"))?;
+            for (plugin, env) in plugins.iter() {
+                let field = format!("plugins.{plugin}");
+                let env = env.as_table_like().ok_or_else(|| invalid(&field))?;
+                let env = env
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = value.as_str().ok_or_else(|| invalid(&field))?;
+                        Ok((key.to_string(), value.to_string()))
+                    })
+                    .collect::<Result<_>>()?;
+                plugin_env.insert(plugin.to_string(), env);
+            }
+        }
+        let mut profiles = BTreeMap::new();
+        if let Some(item) = document.get("profile") {
+            let tables = item.as_table_like().ok_or_else(|| invalid("profile"))?;
+            for (name, table) in tables.iter() {
+                let field = format!("profile.{name}");
+                let serde_json::Value::Object(mut defaults) = rules::item_to_json(table) else {
+                    return Err(invalid(&field));
+                };
+                let fallback = match defaults.remove("fallback") {
+                    Some(value) => {
+                        Some(parse_fallback_json(&value).ok_or_else(|| invalid(&field))??)
+                    }
+                    None => None,
+                };
+                let profile = Profile { defaults, fallback };
+                // 通知の項目として読めるかをここで確かめる（`config validate` で分かるように）
+                profile
+                    .builder()
+                    .map_err(|e| invalid(format!("{field}: {e}")))?;
+                profiles.insert(name.to_string(), profile);
+            }
+        }
+        Ok(Self {
+            backend,
+            record_history,
+            title: string("title")?,
+            icon: string("icon")?.map(Icon::from),
+            sound: string("sound")?,
+            fallback,
+            plugin_env,
+            profiles,
+        })
+    }
+
+    /// グローバル設定（`config::current()`）に反映する
+    pub fn apply_config(&self) {
+        if *self == Self::default() {
+            return;
+        }
+        let mut config = config::current();
+        if self.record_history {
+            config.record_history = true;
+        }
+        if let Some(title) = &self.title {
+            config.title_template = title.clone();
+        }
+        config.icon = self.icon.clone().or(config.icon);
+        config.sound = self.sound.clone().or(config.sound);
+        if let Some(fallback) = &self.fallback {
+            config.fallback_chain = fallback.clone();
+        }
+        for (plugin, env) in &self.plugin_env {
+            config
+                .plugin_env
+                .entry(plugin.clone())
+                .or_default()
+                .extend(env.clone());
+        }
+        config::set(config);
+    }
+}
+
+/// `fallback = [...]` のバックエンド名の配列を読む
+fn parse_fallback(item: &toml_edit::Item) -> Result<Vec<Backend>> {
+    parse_fallback_json(&rules::item_to_json(item)).ok_or_else(|| invalid("fallback"))?
+}
+
+/// バックエンド名の配列（JSON の値）を読む（配列でなければ `None`）
+fn parse_fallback_json(value: &serde_json::Value) -> Option<Result<Vec<Backend>>> {
+    let names = value.as_array()?;
+    Some(
+        names
+            .iter()
+            .map(|name| match name.as_str() {
+                Some(name) => name.parse(),
+                None => Err(invalid("fallback")),
+            })
+            .collect(),
+    )
+}
+
+/// JSON の値を TOML の値として書く（スカラーの表記は同じ、オブジェクトはインラインテーブル）
+fn toml_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(toml_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        serde_json::Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{} = {}", quote(key), toml_value(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// TOML の文字列（キーにも使える）として書く（JSON の文字列のエスケープは TOML でも有効）
+fn quote(text: &str) -> String {
+    serde_json::Value::String(text.to_string()).to_string()
+}
+
+fn invalid(reason: impl std::fmt::Display) -> NotificationError {
+    NotificationError::InvalidInput {
+        field: FILE_NAME.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// 設定ファイルのパス
+///
+/// 1. `RUST_TOAST_CONFIG`
+/// 2. OS ごとの設定の場所
+///    - Linux / WSL: `$XDG_CONFIG_HOME/rust-toast/config.toml`（未設定なら `~/.config/...`）
+///    - macOS: `~/Library/Application Support/rust-toast/config.toml`
+///    - Windows: `%APPDATA%\rust-toast\config.toml`
+///
+/// 以前の `setup` が書いたデータディレクトリの `config.toml` しか無ければ、そちらを使います。
+pub fn path() -> PathBuf {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    let legacy = store::data_dir().join(FILE_NAME);
+    match config_dir() {
+        Some(dir) if dir.join(FILE_NAME).exists() || !legacy.exists() => dir.join(FILE_NAME),
+        _ => legacy,
+    }
+}
+
+/// OS ごとの設定ディレクトリ（`rust-toast` を含む）
+fn config_dir() -> Option<PathBuf> {
+    let env = |key: &str| std::env::var_os(key).filter(|value| !value.is_empty());
+    let base = if cfg!(target_os = "windows") {
+        env("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|dir| dir.join("rust-toast"))
+}
+
+/// 設定ファイルを読み込む（まだ無ければデフォルト）
+pub fn load() -> Result<Settings> {
+    match std::fs::read_to_string(path()) {
+        Ok(text) => Settings::from_toml(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 設定ファイルを書き出す
+pub fn save(settings: &Settings) -> Result<PathBuf> {
+    write(&settings.to_toml())
+}
+
+/// 設定ファイルの雛形（`TEMPLATE`）を書き出す（既にあれば `force` の時だけ上書き）
+pub fn init(force: bool) -> Result<PathBuf> {
+    if !force && path().exists() {
+        return Err(NotificationError::Other(format!(
+            "{} already exists; use --force to overwrite it",
+            path().display()
+        )));
+    }
+    write(TEMPLATE)
+}
+
+/// 設定ファイルを確かめ、注意すること（他のユーザーが読める認証情報など）を返す
+pub fn validate() -> Result<Vec<String>> {
+    let path = path();
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| NotificationError::Other(format!("cannot read {}: {e}", path.display())))?;
+    let settings = Settings::from_toml(&text)?;
+    // 知らないキーは読み飛ばすため、綴りの誤りはここで知らせる
+    let mut warnings: Vec<String> = unknown_keys(&text)
+        .into_iter()
+        .map(|key| format!("unknown key {key} is ignored"))
+        .collect();
+    #[cfg(unix)]
+    if !settings.plugin_env.is_empty() {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            warnings.push(format!(
+                "{} has plugin credentials but is readable by other users (chmod 600)",
+                path.display()
+            ));
+        }
+    }
+    if let Some(Icon::Path(icon)) = &settings.icon {
+        if !icon.is_file() {
+            warnings.push(format!("icon {} does not exist", icon.display()));
+        }
+    }
+    // 組み込み以外の名前は登録されたバックエンド（プラグイン）でなければ送れない
+    let registered = notifier::registered_backends();
+    let backends = settings
+        .backend
+        .iter()
+        .chain(settings.fallback.iter().flatten());
+    for name in backends.filter_map(Backend::custom_name) {
+        if !registered.iter().any(|registered| registered == name) {
+            warnings.push(format!(
+                "backend {name} is not built in or installed as a plugin"
+            ));
+        }
+    }
+    Ok(warnings)
+}
+
+/// 設定ファイルの知らないキー（`profile.alerts.titel` の形）
+///
+/// `[plugins.<名前>]` の中は環境変数の名前なので確かめません。
+fn unknown_keys(text: &str) -> Vec<String> {
+    let Ok(document) = text.parse::<toml_edit::DocumentMut>() else {
+        return Vec::new();
+    };
+    let mut unknown: Vec<String> = document
+        .iter()
+        .map(|(key, _)| key)
+        .filter(|key| !KEYS.contains(key))
+        .map(String::from)
+        .collect();
+    let profiles = document
+        .get("profile")
+        .and_then(|item| item.as_table_like());
+    for (name, table) in profiles.into_iter().flat_map(|tables| tables.iter()) {
+        let Some(table) = table.as_table_like() else {
+            continue;
+        };
+        unknown.extend(
+            table
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| *key != "fallback" && !notifier::JSON_KEYS.contains(key))
+                .map(|key| format!("profile.{name}.{key}")),
+        );
+    }
+    unknown
+}
+
+/// 設定ファイルに書き込む（認証情報を含みうるので、Unix では本人だけが読めるようにする）
+///
+/// 0600 で新しく作った一時ファイルを置き換えるため、既存のファイルのパーミッションに関わらず、
+/// 他のユーザーが読める状態を経ません。
+fn write(text: &str) -> Result<PathBuf> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        journal::create_private_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("toml.tmp");
+    // 前回の一時ファイルが残っていれば、そのパーミッションを引き継がないよう消しておく
+    let _ = std::fs::remove_file(&tmp);
+    let mut file = journal::private_open_options()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)?;
+    file.write_all(text.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(path)
+}
+
+// ============================================================
+// ウィザード
+// ============================================================
+
+/// 標準入出力で対話し、実際にテスト通知を送る
+pub fn run() -> Result<Settings> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut input = stdin.lock();
+
+    writeln!(stdout, "Detecting the environment...")?;
+    for check in doctor::run(None) {
+        writeln!(stdout, "  {check}")?;
+    }
+
+    run_with(
+        &mut input,
+        &mut stdout,
+        &targets::report(),
+        send_test_notification,
+    )
+}
+
+/// `run()` の本体（入出力・バックエンドの一覧・テスト通知の送信を引数で受け取る）
+pub fn run_with(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    backends: &[BackendSupport],
+    mut send_test: impl FnMut(&Backend) -> Result<()>,
+) -> Result<Settings> {
+    // JSON の出力はテスト通知として確認できないので対象にしない
+    let candidates = backends
+        .iter()
+        .filter(|support| support.available && support.backend != Backend::Stdout);
+
+    let mut working = Vec::new();
+    for support in candidates {
+        let question = format!("Send a test notification with {}?", support.name);
+        if !ask(input, output, &question, true)? {
+            continue;
+        }
+        match send_test(&support.backend) {
+            Ok(()) if ask(input, output, "Did it show up?", true)? => {
+                working.push(support.backend.clone())
+            }
+            Ok(()) => {}
+            Err(e) => writeln!(output, "  failed: {e}")?,
+        }
+    }
+
+    let backend = choose_backend(input, output, &working)?;
+    let record_history = ask(
+        input,
+        output,
+        "Record sent notifications in the history?",
+        false,
+    )?;
+    Ok(Settings {
+        backend,
+        record_history,
+        ..Settings::default()
+    })
+}
+
+/// テスト通知を送る
+fn send_test_notification(backend: &Backend) -> Result<()> {
+    NotificationBuilder::new()
+        .title("rust-toast setup")
+        .message(format!(
+            "Test notification from the {} backend",
+            backend.key()
+        ))
+        .backend(backend.clone())
+        .send()
+        .map(drop)
+}
+
+/// 表示されたバックエンドから、デフォルトにするものを選ぶ
+fn choose_backend(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    working: &[Backend],
+) -> Result<Option<Backend>> {
+    if working.is_empty() {
+        writeln!(output, "No backend confirmed; keeping automatic selection.")?;
+        return Ok(None);
+    }
+    let names: Vec<&str> = working.iter().map(Backend::key).collect();
+    loop {
+        let answer = prompt(
+            input,
+            output,
+            &format!("Default backend ({}) [auto]:", names.join(", ")),
+        )?;
+        match answer.as_deref() {
+            None | Some("" | "auto") => return Ok(None),
+            Some(name) => match working.iter().find(|backend| backend.key() == name) {
+                Some(backend) => return Ok(Some(backend.clone())),
+                None => writeln!(output, "  choose one of: {}", names.join(", "))?,
+            },
+        }
+    }
+}
+
+/// はい / いいえで尋ねる（空欄は `default`、入力が終われば `default`）
+pub fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: bool,
+) -> io::Result<bool> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        let Some(answer) = prompt(input, output, &format!("{question} {hint}"))? else {
+            return Ok(default);
+        };
+        match answer.to_ascii_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => writeln!(output, "  please answer y or n")?,
+        }
+    }
+}
+
+/// 質問を表示して 1 行読む（入力が終わっていれば `None`）
+fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+) -> io::Result<Option<String>> {
+    write!(output, "{question} ")?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        writeln!(output)?;
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::UrgencyLevel;
+    use crate::platform::Platform;
+
+    fn support(backend: Backend, available: bool) -> BackendSupport {
+        BackendSupport {
+            name: crate::notifier::backend_status(backend.clone()).0,
+            backend,
+            compiled: true,
+            available,
+        }
+    }
+
+    #[test]
+    fn test_wizard_tests_backends_and_chooses_default() {
+        let backends = [
+            support(Backend::Native(Platform::Linux), true),
+            support(Backend::Native(Platform::MacOs), false),
+            support(Backend::Dialog, true),
+            support(Backend::Console, true),
+            support(Backend::Stdout, true),
+        ];
+        // linux: 送る → 表示された / dialog: 失敗 / console: 送らない
+        // 不正な入力は聞き直す
+        let mut input = "\nmaybe\ny\n\nn\nwindows\nlinux\nyes\n".as_bytes();
+        let mut output = Vec::new();
+        let mut sent = Vec::new();
+        let settings = run_with(&mut input, &mut output, &backends, |backend| {
+            sent.push(backend.clone());
+            match backend {
+                Backend::Dialog => Err(NotificationError::Other("no zenity".into())),
+                _ => Ok(()),
+            }
+        })
+        .unwrap();
+
+        assert_eq!(sent, [Backend::Native(Platform::Linux), Backend::Dialog]);
+        assert_eq!(
+            settings,
+            Settings {
+                backend: Some(Backend::Native(Platform::Linux)),
+                record_history: true,
+                ..Settings::default()
+            }
+        );
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("failed: no zenity"), "{output}");
+        assert!(output.contains("choose one of: linux"), "{output}");
+    }
+
+    #[test]
+    fn test_choose_custom_backend_by_its_name() {
+        let working = [
+            Backend::Native(Platform::Linux),
+            Backend::Custom("foo".to_string()),
+        ];
+        let mut input = "custom\nfoo\n".as_bytes();
+        let mut output = Vec::new();
+        let chosen = choose_backend(&mut input, &mut output, &working).unwrap();
+        assert_eq!(chosen, Some(Backend::Custom("foo".to_string())));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("(linux, foo)"), "{output}");
+        assert!(output.contains("choose one of: linux, foo"), "{output}");
+    }
+
+    #[test]
+    fn test_settings_toml_round_trip() {
+        let settings = Settings {
+            backend: Some(Backend::Dialog),
+            record_history: true,
+            title: Some("Build \"bot\"".to_string()),
+            icon: Some(Icon::Named("emblem-ok".to_string())),
+            sound: None,
+            fallback: Some(vec![Backend::Console]),
+            plugin_env: BTreeMap::from([(
+                "chat".to_string(),
+                BTreeMap::from([("CHAT_TOKEN".to_string(), "s3cret".to_string())]),
+            )]),
+            profiles: BTreeMap::new(),
+        };
+        let text = settings.to_toml();
+        assert!(text.contains("backend = \"dialog\""), "{text}");
+        assert!(text.contains("[plugins.\"chat\"]"), "{text}");
+        assert_eq!(Settings::from_toml(&text).unwrap(), settings);
+
+        // 雛形は全てコメントなので、デフォルトと同じ
+        assert_eq!(Settings::from_toml(TEMPLATE).unwrap(), Settings::default());
+        assert!(Settings::from_toml("fallback = \"console\"").is_err());
+        assert!(Settings::from_toml("[plugins]\nchat = 1").is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let text = r#"
+            backend = "dialog"
+
+            [profile.builds]
+            backend = "console"
+            urgency = "low"
+            actions = [{ id = "open", label = "Open log" }]
+            fallback = ["stdout"]
+        "#;
+        let settings = Settings::from_toml(text).unwrap();
+        let profile = settings.profile("builds").unwrap();
+        assert_eq!(profile.fallback, Some(vec![Backend::Stdout]));
+        let notification = profile.builder().unwrap().message("done").build();
+        assert_eq!(notification.urgency, UrgencyLevel::Low);
+        assert_eq!(notification.backend_override, Some(Backend::Console));
+        assert_eq!(notification.actions[0].label, "Open log");
+
+        // 書き出しても同じ内容に戻る
+        assert_eq!(Settings::from_toml(&settings.to_toml()).unwrap(), settings);
+
+        assert!(settings.profile("alerts").is_err());
+        assert!(Settings::from_toml("[profile.x]\nurgency = \"loud\"").is_err());
+        assert!(Settings::from_toml("[profile.x]\nfallback = \"console\"").is_err());
+
+        assert_eq!(Settings::from_toml("").unwrap(), Settings::default());
+        // 組み込み以外の名前は登録されたバックエンドとして読む（登録の有無は validate() で警告）
+        assert_eq!(
+            Settings::from_toml("backend = \"fax\"").unwrap().backend,
+            Some(Backend::Custom("fax".to_string()))
+        );
+        assert!(Settings::from_toml("record_history = \"yes\"").is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        assert!(unknown_keys(TEMPLATE).is_empty());
+        let text = r#"
+            titel = "typo"
+            backend = "console"
+
+            [plugins.chat]
+            CHAT_TOKEN = "secret"
+
+            [profile.alerts]
+            urgency = "critical"
+            fallback = ["console"]
+            urgncy = "low"
+        "#;
+        assert_eq!(unknown_keys(text), ["titel", "profile.alerts.urgncy"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! ステータスバー（waybar / i3blocks）向けの出力
+//!
+//! 通知履歴（`store::history`）から未読数と重要な通知の数を数え、
+//! 各ステータスバーが期待する形式で出力します。
+//! 未読は画面ロック中・おやすみモード中に届き、まだ見られていない通知です
+//! （`clear()` で既読になります）。
+//!
+//! | 形式 | 出力 |
+//! |------|------|
+//! | waybar | 1 行の JSON（`text` / `alt` / `tooltip` / `class`） |
+//! | i3blocks | `full_text` / `short_text` / `color` の 3 行 |
+//!
+//! 未読が無い場合は `text` / `full_text` が空になり、どちらのバーでも非表示になります。
+//!
+//! # 学習ポイント
+//! - 同じデータを複数の形式に出力する（`match` による分岐）
+//! - 集計（未読の判定）を履歴モジュールに任せ、ここでは表示だけを扱う
+
+use crate::error::Result;
+use crate::store::history;
+
+/// i3blocks で重要な通知がある時の文字色
+const CRITICAL_COLOR: &str = "#FF5555";
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// waybar の custom モジュール（`"return-type": "json"`）
+    Waybar,
+    /// i3blocks のブロック
+    I3blocks,
+}
+
+/// 未読の通知の数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// 未読の通知の数
+    pub unread: usize,
+    /// そのうち緊急度が重要の数
+    pub critical: usize,
+}
+
+impl Counts {
+    /// 履歴のレコード（古い順）から数える
+    ///
+    /// 未読の判定は `history::unread_records()` に従います。
+    pub fn from_payloads<'a>(payloads: impl IntoIterator<Item = &'a str>) -> Self {
+        let unread = history::unread_records(payloads);
+        Self {
+            unread: unread.len(),
+            critical: unread
+                .iter()
+                .filter(|record| record.get("urgency") == Some(&"critical".into()))
+                .count(),
+        }
+    }
+}
+
+/// 現在の未読数（通知履歴から数える）
+pub fn counts() -> Result<Counts> {
+    let entries = history::entries()?;
+    Ok(Counts::from_payloads(
+        entries.iter().map(|entry| entry.payload.as_str()),
+    ))
+}
+
+/// 未読数を 0 に戻す（全ての通知を既読にする）
+pub fn clear() -> Result<()> {
+    history::mark_all_seen()
+}
+
+/// 未読数を指定した形式で出力する
+pub fn render(format: Format, counts: Counts) -> String {
+    let text = if counts.unread == 0 {
+        String::new()
+    } else {
+        counts.unread.to_string()
+    };
+    let tooltip = format!("{} unread, {} critical", counts.unread, counts.critical);
+
+    match format {
+        Format::Waybar => {
+            let class = if counts.critical > 0 {
+                "critical"
+            } else if counts.unread > 0 {
+                "unread"
+            } else {
+                "none"
+            };
+            serde_json::json!({
+                "text": text,
+                "alt": class,
+                "tooltip": tooltip,
+                "class": class,
+            })
+            .to_string()
+        }
+        Format::I3blocks => {
+            let full_text = if counts.unread == 0 {
+                String::new()
+            } else {
+                tooltip
+            };
+            let color = if counts.critical > 0 {
+                CRITICAL_COLOR
+            } else {
+                ""
+            };
+            format!("{full_text}\n{text}\n{color}")
+        }
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_reset_on_clear() {
+        let payloads = [
+            r#"{"message":"old","urgency":"critical","unseen":true}"#,
+            history::SEEN_EVENT,
+            r#"{"message":"a","urgency":"normal","unseen":true}"#,
+            r#"{"message":"b","urgency":"critical","unseen":true}"#,
+            r#"{"message":"shown","urgency":"critical"}"#,
+            "not json",
+        ];
+        assert_eq!(
+            Counts::from_payloads(payloads),
+            Counts {
+                unread: 2,
+                critical: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_waybar() {
+        let counts = Counts {
+            unread: 3,
+            critical: 1,
+        };
+        assert_eq!(
+            render(Format::Waybar, counts),
+            r#"{"alt":"critical","class":"critical","text":"3","tooltip":"3 unread, 1 critical"}"#
+        );
+        assert!(render(Format::Waybar, Counts::default()).contains(r#""text":"""#));
+    }
+
+    #[test]
+    fn test_render_i3blocks() {
+        let counts = Counts {
+            unread: 2,
+            critical: 0,
+        };
+        assert_eq!(
+            render(Format::I3blocks, counts),
+            "2 unread, 0 critical\n2\n"
+        );
+        assert_eq!(render(Format::I3blocks, Counts::default()), "\n\n");
+    }
+}
@@ -0,0 +1,480 @@
+//! 通知履歴
+//!
+//! `Config::record_history` が有効な場合、送信した通知を `history.wal` に
+//! 1 件ずつ JSON（`Notification::to_json()`）で記録します。
+//! ステータスバー（`statusbar` モジュール）などの、プロセスをまたいだ集計に使います。
+//!
+//! ## 未読（見られていない通知）
+//!
+//! 画面がロックされている間やおやすみモード（DND）の間に届いた通知は、
+//! ユーザーが見ていないため `"unseen": true` を付けて記録します。
+//! これらは `mark_all_seen()`（CLI の `rust-toast unread` など、履歴を見た時）まで
+//! 未読として数えられます。
+//!
+//! ## 送信の結果
+//!
+//! 通知は送信の前に記録し、配送が終わると結果（`{"event":"result","seq":N,...}`）を追記します。
+//! `History::query()` は通知と結果を連番で結び付け、条件（`HistoryFilter`）で絞り込みます。
+//!
+//! ```no_run
+//! use std::time::{Duration, SystemTime};
+//! use rust_toast::store::history::{History, HistoryFilter};
+//!
+//! let history = History::load()?;
+//! let filter = HistoryFilter::new()
+//!     .since(SystemTime::now() - Duration::from_secs(60 * 60))
+//!     .topic("ci/*")
+//!     .failed();
+//! for record in history.query(&filter) {
+//!     println!("{} {}", record.notification.title, record.notification.message);
+//! }
+//! # Ok::<(), rust_toast::NotificationError>(())
+//! ```
+//!
+//! # 学習ポイント
+//! - ジャーナル（`Journal`）の上に用途別の薄い API を載せる
+//! - 追記専用ログに「イベント」を記録して状態を表す（既読 = マーカーの追記）
+//! - 条件を Builder で組み立てるフィルター
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::{Notification, NotificationBuilder, UrgencyLevel};
+use crate::store::{journal_path, Entry, FsyncPolicy, Journal};
+use crate::topic;
+
+/// それまでの通知を全て既読にしたことを表すレコード
+pub const SEEN_EVENT: &str = r#"{"event":"seen"}"#;
+
+/// 見られていない通知に付けるキー
+const UNSEEN_KEY: &str = "unseen";
+
+/// 送信の結果のレコードの `event`
+const RESULT_EVENT: &str = "result";
+
+/// 通知履歴のジャーナルのパス
+pub fn path() -> PathBuf {
+    journal_path("history")
+}
+
+/// 履歴に 1 件追記し、連番を返す
+pub fn append(payload: &str) -> Result<u64> {
+    Journal::open(path(), FsyncPolicy::Always)?.append(payload)
+}
+
+/// 通知（`Notification::to_json()` の JSON）を記録する
+///
+/// `seen` が `false`（ロック中・DND 中に届いた）なら未読の印を付けます。
+pub fn record(notification_json: &str, seen: bool) -> Result<u64> {
+    if seen {
+        return append(notification_json);
+    }
+    let mut record: Map<String, Value> = serde_json::from_str(notification_json)
+        .map_err(|err| NotificationError::Other(format!("invalid history record: {err}")))?;
+    record.insert(UNSEEN_KEY.to_string(), Value::Bool(true));
+    append(&Value::Object(record).to_string())
+}
+
+/// 通知（連番 `seq` で記録したもの）の送信の結果を記録する
+pub fn record_result(seq: u64, delivery: &Delivery) -> Result<u64> {
+    let record = json!({
+        "event": RESULT_EVENT,
+        "seq": seq,
+        "backend": delivery.backend,
+        "id": delivery.id,
+        "error": delivery.error,
+    });
+    append(&record.to_string())
+}
+
+/// 履歴の全レコード（古い順、履歴がまだ無ければ空）
+pub fn entries() -> Result<Vec<Entry>> {
+    let path = path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Journal::open(path, FsyncPolicy::Always)?.entries()
+}
+
+/// 未読の通知（古い順）
+pub fn unread() -> Result<Vec<Map<String, Value>>> {
+    let entries = entries()?;
+    Ok(unread_records(
+        entries.iter().map(|entry| entry.payload.as_str()),
+    ))
+}
+
+/// 未読の通知の数
+///
+/// ステータスバーや、離席から戻った時のまとめ表示に使います。
+pub fn unread_count() -> Result<usize> {
+    Ok(unread()?.len())
+}
+
+/// 全ての通知を既読にする（履歴に `SEEN_EVENT` を追記）
+pub fn mark_all_seen() -> Result<()> {
+    append(SEEN_EVENT)?;
+    Ok(())
+}
+
+/// 履歴のレコード（古い順）から未読の通知を取り出す
+///
+/// `SEEN_EVENT` より前のレコードと、未読の印が無いレコードは除きます。
+pub fn unread_records<'a>(payloads: impl IntoIterator<Item = &'a str>) -> Vec<Map<String, Value>> {
+    let mut unread = Vec::new();
+    for payload in payloads {
+        if payload == SEEN_EVENT {
+            unread.clear();
+            continue;
+        }
+        let Ok(Value::Object(record)) = serde_json::from_str::<Value>(payload) else {
+            continue;
+        };
+        if record.get(UNSEEN_KEY) == Some(&Value::Bool(true)) {
+            unread.push(record);
+        }
+    }
+    unread
+}
+
+// ============================================================
+// 問い合わせ
+// ============================================================
+
+/// 1 つのバックエンドへの送信の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    /// 送信したバックエンドの名前（`Notifier::backend_name()`）
+    pub backend: String,
+    /// バックエンドが付けた通知 ID
+    pub id: Option<String>,
+    /// 失敗した理由（成功なら `None`）
+    pub error: Option<String>,
+}
+
+impl Delivery {
+    /// 成功した送信
+    pub fn sent(backend: impl Into<String>, id: Option<String>) -> Self {
+        Self {
+            backend: backend.into(),
+            id,
+            error: None,
+        }
+    }
+
+    /// 失敗した送信
+    pub fn failed(backend: impl Into<String>, error: &NotificationError) -> Self {
+        Self {
+            backend: backend.into(),
+            id: None,
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// 成功したかどうか
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// 履歴の 1 件（通知と、その送信の結果）
+#[derive(Debug, Clone)]
+pub struct HistoryRecord {
+    /// 履歴の連番
+    pub seq: u64,
+    /// 記録した時刻
+    pub timestamp: SystemTime,
+    /// 通知の内容
+    pub notification: Notification,
+    /// 未読かどうか（記録した時点。既読にしたかは `unread()` で確かめる）
+    pub unseen: bool,
+    /// 送信の結果（送った順、ファンアウトなら複数。結果を記録する前の履歴では空）
+    pub deliveries: Vec<Delivery>,
+}
+
+impl HistoryRecord {
+    /// 送信に失敗したバックエンドがあるか
+    pub fn failed(&self) -> bool {
+        self.deliveries.iter().any(|delivery| !delivery.is_ok())
+    }
+
+    /// 記録した時刻（UNIX エポックからのミリ秒）と結果を加えた JSON
+    pub fn to_json(&self) -> String {
+        let mut record: Value =
+            serde_json::from_str(&self.notification.to_json()).unwrap_or_default();
+        record["seq"] = json!(self.seq);
+        record["timestamp_ms"] = json!(self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64));
+        record["deliveries"] = self
+            .deliveries
+            .iter()
+            .map(|delivery| {
+                json!({ "backend": delivery.backend, "id": delivery.id, "error": delivery.error })
+            })
+            .collect();
+        record.to_string()
+    }
+}
+
+/// 履歴の絞り込みの条件（指定しなかった条件は全てに一致）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// この時刻以降に記録したもの
+    pub since: Option<SystemTime>,
+    /// この時刻より前に記録したもの
+    pub until: Option<SystemTime>,
+    /// トピックのパターン（`ci/*` など、`topic::matches()`）
+    pub topic: Option<String>,
+    /// 緊急度
+    pub urgency: Option<UrgencyLevel>,
+    /// タイトルか本文に含まれる文字列（大文字小文字を区別しない）
+    pub text: Option<String>,
+    /// 送信したバックエンドの名前に含まれる文字列（`linux` は `Linux (D-Bus)` に一致、大文字小文字を区別しない）
+    pub backend: Option<String>,
+    /// 送信に失敗したものだけ
+    pub failed: bool,
+    /// 新しいものからこの件数まで
+    pub limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    /// 全てに一致する条件
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// この時刻以降に記録したもの
+    pub fn since(mut self, since: SystemTime) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// この時刻より前に記録したもの
+    pub fn until(mut self, until: SystemTime) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// トピックがパターンに一致するもの
+    pub fn topic(mut self, pattern: impl Into<String>) -> Self {
+        self.topic = Some(pattern.into());
+        self
+    }
+
+    /// 緊急度が一致するもの
+    pub fn urgency(mut self, urgency: UrgencyLevel) -> Self {
+        self.urgency = Some(urgency);
+        self
+    }
+
+    /// タイトルか本文に文字列を含むもの
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// 名前に `backend` を含むバックエンドに送ったもの
+    pub fn backend(mut self, backend: impl Into<String>) -> Self {
+        self.backend = Some(backend.into());
+        self
+    }
+
+    /// 送信に失敗したものだけ
+    pub fn failed(mut self) -> Self {
+        self.failed = true;
+        self
+    }
+
+    /// 新しいものから `limit` 件まで
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// 1 件が条件に一致するか（`limit` は見ない）
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        let notification = &record.notification;
+        let contains =
+            |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+        self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+            && self.topic.as_deref().is_none_or(|pattern| {
+                notification
+                    .topic
+                    .as_deref()
+                    .is_some_and(|topic| topic::matches(pattern, topic))
+            })
+            && self
+                .urgency
+                .is_none_or(|urgency| notification.urgency == urgency)
+            && self.text.as_deref().is_none_or(|text| {
+                contains(&notification.title, text) || contains(&notification.message, text)
+            })
+            && self.backend.as_deref().is_none_or(|backend| {
+                record
+                    .deliveries
+                    .iter()
+                    .any(|delivery| contains(&delivery.backend, backend))
+            })
+            && (!self.failed || record.failed())
+    }
+}
+
+/// 通知履歴（記録した通知と送信の結果）
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    records: Vec<HistoryRecord>,
+}
+
+impl History {
+    /// データディレクトリの履歴を読み込む（まだ無ければ空）
+    pub fn load() -> Result<Self> {
+        Ok(Self::from_entries(&entries()?))
+    }
+
+    /// レコード（古い順）から作成する
+    ///
+    /// 結果のレコードは連番で通知に結び付け、読めないレコードは読み飛ばします。
+    pub fn from_entries(entries: &[Entry]) -> Self {
+        let mut records = Vec::new();
+        let mut index: HashMap<u64, usize> = HashMap::new();
+        for entry in entries {
+            let Ok(Value::Object(record)) = serde_json::from_str::<Value>(&entry.payload) else {
+                continue;
+            };
+            match record.get("event").and_then(Value::as_str) {
+                Some(RESULT_EVENT) => {
+                    let position = record["seq"].as_u64().and_then(|seq| index.get(&seq));
+                    if let Some(&position) = position {
+                        let text = |key: &str| record.get(key)?.as_str().map(str::to_string);
+                        let delivery = Delivery {
+                            backend: text("backend").unwrap_or_default(),
+                            id: text("id"),
+                            error: text("error"),
+                        };
+                        let history: &mut HistoryRecord = &mut records[position];
+                        history.deliveries.push(delivery);
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    // 履歴は表示するだけでコマンドは実行しないので、on_click_exec も読む
+                    let Ok(notification) = NotificationBuilder::from_json_with_exec(&entry.payload)
+                        .map(|builder| builder.build())
+                    else {
+                        continue;
+                    };
+                    index.insert(entry.seq, records.len());
+                    records.push(HistoryRecord {
+                        seq: entry.seq,
+                        timestamp: UNIX_EPOCH + Duration::from_millis(entry.timestamp_ms),
+                        notification,
+                        unseen: record.get(UNSEEN_KEY) == Some(&Value::Bool(true)),
+                        deliveries: Vec::new(),
+                    });
+                }
+            }
+        }
+        Self { records }
+    }
+
+    /// 全ての通知（古い順）
+    pub fn records(&self) -> &[HistoryRecord] {
+        &self.records
+    }
+
+    /// 条件に一致する通知（古い順、`limit` があれば新しいものからその件数）
+    pub fn query(&self, filter: &HistoryFilter) -> Vec<&HistoryRecord> {
+        let matched: Vec<&HistoryRecord> = self
+            .records
+            .iter()
+            .filter(|record| filter.matches(record))
+            .collect();
+        let skip = filter
+            .limit
+            .map_or(0, |limit| matched.len().saturating_sub(limit));
+        matched.into_iter().skip(skip).collect()
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::journal;
+
+    #[test]
+    fn test_unread_records_since_last_seen() {
+        let payloads = [
+            r#"{"message":"old","unseen":true}"#,
+            SEEN_EVENT,
+            r#"{"message":"a","unseen":true}"#,
+            r#"{"message":"shown"}"#,
+            r#"{"message":"b","unseen":true}"#,
+            "not json",
+        ];
+        let messages: Vec<_> = unread_records(payloads)
+            .iter()
+            .map(|record| record["message"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(messages, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_query_joins_results_and_filters() {
+        let entries = journal::entries_from(&[
+            r#"{"title":"Build","message":"passed","topic":"ci/build"}"#,
+            r#"{"event":"result","seq":1,"backend":"Linux (D-Bus)","id":"7","error":null}"#,
+            r#"{"title":"Deploy","message":"failed","urgency":"critical","topic":"ci/deploy"}"#,
+            r#"{"event":"result","seq":3,"backend":"Linux (D-Bus)","id":null,"error":"D-Bus down"}"#,
+            r#"{"event":"result","seq":3,"backend":"Console","id":null,"error":null}"#,
+            SEEN_EVENT,
+            r#"{"title":"Lunch","message":"Pizza is here"}"#,
+        ]);
+        let history = History::from_entries(&entries);
+        let titles = |filter: HistoryFilter| -> Vec<String> {
+            history
+                .query(&filter)
+                .iter()
+                .map(|record| record.notification.title.clone())
+                .collect()
+        };
+
+        assert_eq!(history.records().len(), 3);
+        assert_eq!(history.records()[1].deliveries.len(), 2);
+        assert_eq!(
+            titles(HistoryFilter::new().topic("ci/*")),
+            ["Build", "Deploy"]
+        );
+        assert_eq!(titles(HistoryFilter::new().failed()), ["Deploy"]);
+        assert_eq!(titles(HistoryFilter::new().backend("console")), ["Deploy"]);
+        assert_eq!(
+            titles(HistoryFilter::new().backend("linux")),
+            ["Build", "Deploy"]
+        );
+        assert_eq!(
+            titles(HistoryFilter::new().urgency(UrgencyLevel::Critical)),
+            ["Deploy"]
+        );
+        assert_eq!(titles(HistoryFilter::new().text("PIZZA")), ["Lunch"]);
+        let since = UNIX_EPOCH + Duration::from_secs(3);
+        assert_eq!(
+            titles(HistoryFilter::new().since(since)),
+            ["Deploy", "Lunch"]
+        );
+        assert_eq!(titles(HistoryFilter::new().limit(1)), ["Lunch"]);
+
+        let json: Value = serde_json::from_str(&history.records()[0].to_json()).unwrap();
+        assert_eq!(json["timestamp_ms"], 1000);
+        assert_eq!(json["deliveries"][0]["id"], "7");
+    }
+}
@@ -0,0 +1,776 @@
+//! 追記専用ログ（Write-Ahead Log）
+//!
+//! キューや履歴などの状態を「追記のみ」のファイルに記録します。
+//! 既存のデータを書き換えないため、書き込み中に電源が落ちても
+//! 壊れるのは最後の 1 レコードだけで、それ以前の状態は必ず復元できます。
+//!
+//! # ファイル形式
+//! ```text
+//! rust-toast-journal 1                              ← ヘッダー（マジック + バージョン）
+//! 1<TAB>1760000000000<TAB>1c291ca3<TAB>{"...": ...}  ← 連番 / 記録時刻(ms) / CRC32 / ペイロード
+//! 2<TAB>1760000000500<TAB>8f3b2a10<TAB>{"...": ...}
+//! ```
+//!
+//! 古いバージョンのファイルは `open()` 時に自動で移行されます（`migrate` モジュール）。
+//!
+//! 同じファイルを複数のプロセス（並行して動く CLI など）が開いても連番が重ならないよう、
+//! 追記と復旧はファイルの排他ロック（`File::lock()`、アドバイザリロック）の中で行い、
+//! 他のプロセスが追記していれば読み直して連番を続けます。
+//!
+//! # 学習ポイント
+//! - `File::sync_all()` による fsync（ディスクへの確実な書き込み）
+//! - CRC32 チェックサムによる破損検出
+//! - 一時ファイル + `rename` によるアトミックなファイル置換
+//! - Unix のパーミッション（`OpenOptionsExt::mode`）で本人以外に読ませない
+//! - `File::lock()` によるプロセス間の排他
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::clock::{self, Clock};
+use crate::error::{NotificationError, Result};
+use crate::store::migrate;
+
+/// ジャーナルファイルの先頭に書くマジック文字列
+pub(super) const MAGIC: &str = "rust-toast-journal";
+
+/// 現在のファイル形式のバージョン
+///
+/// 形式を変えたときはここを上げ、`migrate` モジュールに移行処理を追加します。
+/// リリース前の形式の変更では上げません（移行が必要なファイルが存在しないため）。
+/// - 1: `seq / 記録時刻(ms) / crc32 / payload`
+pub const FORMAT_VERSION: u32 = 1;
+
+// ============================================================
+// fsync ポリシー
+// ============================================================
+
+/// ディスクへの同期（fsync）をどの頻度で行うか
+///
+/// fsync は遅い操作なので、安全性と速度のトレードオフを選べるようにします。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// 追記のたびに fsync（電源断でも確定済みのレコードは失われない）
+    #[default]
+    Always,
+    /// N 件ごとに fsync（最大 N-1 件を失う可能性がある）
+    EveryN(u32),
+    /// fsync しない（OS に任せる）
+    Never,
+}
+
+// ============================================================
+// レコード
+// ============================================================
+
+/// ジャーナルの 1 レコード
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// 連番（1 始まり）
+    pub seq: u64,
+    /// 記録時刻（UNIX エポックからのミリ秒）
+    pub timestamp_ms: u64,
+    /// 記録された内容（改行を含まない 1 行の文字列、通常は JSON）
+    pub payload: String,
+}
+
+/// 整合性チェックの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    /// 正常なレコード数
+    pub valid_records: usize,
+    /// チェックサム不一致などで読めなかったレコード数
+    pub corrupt_records: usize,
+    /// 最終行が書きかけ（改行で終わっていない）かどうか
+    pub torn_tail: bool,
+}
+
+impl CheckReport {
+    /// 破損が無いかどうか
+    pub fn is_healthy(&self) -> bool {
+        self.corrupt_records == 0 && !self.torn_tail
+    }
+}
+
+/// ファイルを読み込んだ結果（内部用）
+struct Scan {
+    entries: Vec<Entry>,
+    report: CheckReport,
+    /// 最後の正常なレコードの直後のバイト位置
+    valid_len: u64,
+    /// 破損レコードの後ろに正常なレコードが続いているか
+    corrupt_in_middle: bool,
+}
+
+// ============================================================
+// Journal 本体
+// ============================================================
+
+/// 追記専用ログ
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    next_seq: u64,
+    policy: FsyncPolicy,
+    /// 最後の fsync 以降に追記した件数
+    unsynced: u32,
+    /// レコードの記録時刻に使う時計
+    clock: Arc<dyn Clock>,
+    /// 最後に読み書きした時点のファイルの長さ（他のプロセスの追記を見つけるため）
+    len: Option<u64>,
+}
+
+impl Journal {
+    /// ジャーナルを開く（存在しなければ作成）
+    ///
+    /// クラッシュで最終行（作成直後のヘッダーを含む）が書きかけになっている場合は、
+    /// その行を切り捨てて復旧します（fsync 前のレコードは確定していないため）。
+    /// 途中のレコードが壊れている場合はエラーを返すので、
+    /// `repair()` で修復してください。
+    /// 古い形式のファイルは、開く前に現在の形式へ移行します。
+    pub fn open(path: impl AsRef<Path>, policy: FsyncPolicy) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            create_private_dir_all(parent).map_err(|e| storage_error(&path, e))?;
+        }
+
+        let has_header = fs::read(&path).is_ok_and(|bytes| bytes.contains(&b'\n'));
+        if has_header {
+            // rename でファイルが置き換わるため、ファイルを開く前に移行する
+            migrate::migrate(&path, false)?;
+        }
+        let file = open_append(&path)?;
+        let mut journal = Self {
+            path,
+            file,
+            next_seq: 1,
+            policy,
+            unsynced: 0,
+            clock: clock::current(),
+            len: None,
+        };
+        journal.locked(|_| Ok(()))?;
+        Ok(journal)
+    }
+
+    /// レコードの記録時刻に使う時計を差し替える
+    ///
+    /// 指定しない場合は、開いた時点の `clock::current()` を使います。
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// レコードを追記し、割り当てた連番を返す
+    ///
+    /// fsync するかどうかは `FsyncPolicy` に従います。
+    pub fn append(&mut self, payload: &str) -> Result<u64> {
+        let seq = self.write_record(payload)?;
+        self.unsynced += 1;
+
+        let should_sync = match self.policy {
+            FsyncPolicy::Always => true,
+            FsyncPolicy::EveryN(n) => self.unsynced >= n.max(1),
+            FsyncPolicy::Never => false,
+        };
+        if should_sync {
+            self.sync()?;
+        }
+        Ok(seq)
+    }
+
+    /// レコードを追記し、ポリシーに関係なく必ず fsync する
+    ///
+    /// 緊急度の高い通知など、絶対に失いたくないレコードに使います。
+    pub fn append_durable(&mut self, payload: &str) -> Result<u64> {
+        let seq = self.write_record(payload)?;
+        self.sync()?;
+        Ok(seq)
+    }
+
+    /// 未同期の書き込みをディスクに反映する
+    pub fn sync(&mut self) -> Result<()> {
+        self.file
+            .sync_data()
+            .map_err(|e| storage_error(&self.path, e))?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// 全てのレコードを読み込む
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        Ok(scan_file(&self.path)?.entries)
+    }
+
+    /// ジャーナルファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 1 レコードを書き込む（fsync はしない）
+    fn write_record(&mut self, payload: &str) -> Result<u64> {
+        // 1 レコード = 1 行なので、改行を含むペイロードは受け付けない
+        if payload.contains('\n') {
+            return Err(NotificationError::Storage {
+                path: self.path.clone(),
+                reason: "journal payload must not contain newlines".to_string(),
+            });
+        }
+
+        self.locked(|journal| {
+            let seq = journal.next_seq;
+            let line = format_record(seq, journal.clock.now_ms(), payload);
+            // 1 回の write_all で書くことで、レコードの途中に他の書き込みが挟まらない
+            journal
+                .file
+                .write_all(line.as_bytes())
+                .map_err(|e| storage_error(&journal.path, e))?;
+            journal.next_seq += 1;
+            journal.len = journal.len.map(|len| len + line.len() as u64);
+            Ok(seq)
+        })
+    }
+
+    /// ファイルを排他ロックし、他のプロセスの変更を取り込んでから `f` を実行する
+    ///
+    /// `repair` や移行でファイルが置き換えられていれば開き直します。
+    /// ロックの解除の失敗は無視します（ファイルを閉じれば解除されるため）。
+    fn locked<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        loop {
+            if is_replaced(&self.path, &self.file) {
+                self.file = open_append(&self.path)?;
+                self.len = None;
+            }
+            self.file.lock().map_err(|e| storage_error(&self.path, e))?;
+            if !is_replaced(&self.path, &self.file) {
+                break;
+            }
+            let _ = self.file.unlock();
+        }
+        let result = self.refresh().and_then(|()| f(self));
+        let _ = self.file.unlock();
+        result
+    }
+
+    /// 最後に読み書きした後にファイルが変わっていれば、読み直して連番と末尾を整える（ロック中に呼ぶ）
+    ///
+    /// 空のファイル・書きかけのヘッダーにはヘッダーを書き、書きかけの末尾は切り捨てます。
+    fn refresh(&mut self) -> Result<()> {
+        let len = self
+            .file
+            .metadata()
+            .map_err(|e| storage_error(&self.path, e))?
+            .len();
+        if self.len == Some(len) {
+            return Ok(());
+        }
+
+        let bytes = fs::read(&self.path).map_err(|e| storage_error(&self.path, e))?;
+        let header = format!("{MAGIC} {FORMAT_VERSION}\n");
+        if !bytes.contains(&b'\n') && header.as_bytes().starts_with(&bytes) {
+            // 作ったばかり（または作成中にクラッシュした）ファイル
+            self.file
+                .set_len(0)
+                .and_then(|()| self.file.write_all(header.as_bytes()))
+                .and_then(|()| self.file.sync_all())
+                .map_err(|e| storage_error(&self.path, e))?;
+            sync_parent_dir(&self.path);
+            self.next_seq = 1;
+            self.len = Some(header.len() as u64);
+            return Ok(());
+        }
+
+        let scan = scan_bytes(&self.path, &bytes)?;
+        if scan.corrupt_in_middle {
+            return Err(NotificationError::Storage {
+                path: self.path.clone(),
+                reason: "journal is corrupted; run `rust-toast store repair`".to_string(),
+            });
+        }
+        let mut len = bytes.len() as u64;
+        if !scan.report.is_healthy() {
+            // 書きかけの末尾だけを切り捨てる
+            self.file
+                .set_len(scan.valid_len)
+                .and_then(|()| self.file.sync_all())
+                .map_err(|e| storage_error(&self.path, e))?;
+            len = scan.valid_len;
+        }
+        self.next_seq = scan.entries.last().map_or(1, |entry| entry.seq + 1);
+        self.len = Some(len);
+        Ok(())
+    }
+}
+
+// ============================================================
+// 整合性チェックと修復
+// ============================================================
+
+/// ジャーナルの整合性をチェックする（ファイルは変更しない）
+pub fn check(path: impl AsRef<Path>) -> Result<CheckReport> {
+    Ok(scan_file(path.as_ref())?.report)
+}
+
+/// 壊れたレコードを取り除いてジャーナルを修復する
+///
+/// 正常なレコードだけを一時ファイルに書き出し、`rename` で置き換えるため、
+/// 修復中にクラッシュしても元のファイルは残ります。
+/// 戻り値は修復前のチェック結果です。
+pub fn repair(path: impl AsRef<Path>) -> Result<CheckReport> {
+    let path = path.as_ref();
+    with_file_lock(path, || {
+        let scan = scan_file(path)?;
+        if scan.report.is_healthy() {
+            return Ok(scan.report);
+        }
+
+        replace_with_entries(path, &scan.entries, "repair.tmp")?;
+        Ok(scan.report)
+    })
+}
+
+/// ジャーナルを排他ロックして `f` を実行する（読み込みから置き換えまでの間に追記されないように）
+pub(super) fn with_file_lock<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let file = File::open(path).map_err(|e| storage_error(path, e))?;
+    file.lock().map_err(|e| storage_error(path, e))?;
+    let result = f();
+    // 解除の失敗は無視する（ファイルを閉じれば解除される）
+    let _ = file.unlock();
+    result
+}
+
+/// ジャーナルをロックして読み、`keep` が返したレコードだけで置き換える（コンパクション）
+///
+/// 連番はそのまま残します。読めないレコードは `keep` に渡さずに捨てます。
+pub(super) fn rewrite(
+    path: &Path,
+    tmp_extension: &str,
+    keep: impl FnOnce(Vec<Entry>) -> Vec<Entry>,
+) -> Result<()> {
+    with_file_lock(path, || {
+        let entries = scan_file(path)?.entries;
+        let count = entries.len();
+        let kept = keep(entries);
+        if kept.len() == count {
+            return Ok(());
+        }
+        replace_with_entries(path, &kept, tmp_extension)
+    })
+}
+
+/// レコード一覧から現在の形式のファイルを作り、アトミックに置き換える
+///
+/// `tmp_extension` は作業用の一時ファイルの拡張子です。
+pub(super) fn replace_with_entries(
+    path: &Path,
+    entries: &[Entry],
+    tmp_extension: &str,
+) -> Result<()> {
+    let tmp_path = path.with_extension(tmp_extension);
+    let mut content = format!("{MAGIC} {FORMAT_VERSION}\n");
+    for entry in entries {
+        content.push_str(&format_record(
+            entry.seq,
+            entry.timestamp_ms,
+            &entry.payload,
+        ));
+    }
+
+    let mut tmp = private_open_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)
+        .map_err(|e| storage_error(&tmp_path, e))?;
+    tmp.write_all(content.as_bytes())
+        .map_err(|e| storage_error(&tmp_path, e))?;
+    tmp.sync_all().map_err(|e| storage_error(&tmp_path, e))?;
+    fs::rename(&tmp_path, path).map_err(|e| storage_error(path, e))?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+// ============================================================
+// 内部ヘルパー
+// ============================================================
+
+/// レコード 1 行分の文字列を作る
+///
+/// チェックサムは連番・時刻・ペイロードの全てを対象にします。
+fn format_record(seq: u64, timestamp_ms: u64, payload: &str) -> String {
+    let checksum = crc32(format!("{seq}\t{timestamp_ms}\t{payload}").as_bytes());
+    format!("{seq}\t{timestamp_ms}\t{checksum:08x}\t{payload}\n")
+}
+
+/// 1 行をレコードとして解析する（不正なら `None`）
+fn parse_record(line: &[u8]) -> Option<Entry> {
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.splitn(4, '\t');
+    let seq: u64 = parts.next()?.parse().ok()?;
+    let timestamp_ms: u64 = parts.next()?.parse().ok()?;
+    let checksum = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let payload = parts.next()?;
+
+    let expected = crc32(format!("{seq}\t{timestamp_ms}\t{payload}").as_bytes());
+    (expected == checksum).then(|| Entry {
+        seq,
+        timestamp_ms,
+        payload: payload.to_string(),
+    })
+}
+
+/// ヘッダー行を読み、形式のバージョンとヘッダーの長さ（改行を除く）を返す
+pub(super) fn read_header(path: &Path, bytes: &[u8]) -> Result<(u32, usize)> {
+    let header_end =
+        bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| NotificationError::Storage {
+                path: path.to_path_buf(),
+                reason: "missing journal header".to_string(),
+            })?;
+    let header = String::from_utf8_lossy(&bytes[..header_end]);
+    let version = header
+        .strip_prefix(MAGIC)
+        .and_then(|rest| rest.trim().parse().ok())
+        .ok_or_else(|| NotificationError::Storage {
+            path: path.to_path_buf(),
+            reason: format!("unsupported journal header: {header}"),
+        })?;
+    Ok((version, header_end))
+}
+
+/// ジャーナルファイルを読み込み、レコードと破損状況を調べる
+fn scan_file(path: &Path) -> Result<Scan> {
+    let bytes = fs::read(path).map_err(|e| storage_error(path, e))?;
+    scan_bytes(path, &bytes)
+}
+
+/// 読み込んだジャーナルのレコードと破損状況を調べる
+fn scan_bytes(path: &Path, bytes: &[u8]) -> Result<Scan> {
+    let (version, header_end) = read_header(path, bytes)?;
+    if version != FORMAT_VERSION {
+        return Err(NotificationError::Storage {
+            path: path.to_path_buf(),
+            reason: format!(
+                "journal format v{version} is not v{FORMAT_VERSION}; run `rust-toast store migrate`"
+            ),
+        });
+    }
+
+    let mut scan = Scan {
+        entries: Vec::new(),
+        report: CheckReport::default(),
+        valid_len: header_end as u64 + 1,
+        corrupt_in_middle: false,
+    };
+
+    let mut offset = header_end + 1;
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+        let Some(len) = rest.iter().position(|&b| b == b'\n') else {
+            // 改行で終わっていない = 書き込み途中でクラッシュした
+            scan.report.torn_tail = true;
+            break;
+        };
+
+        match parse_record(&rest[..len]) {
+            Some(entry) => {
+                if scan.report.corrupt_records > 0 {
+                    scan.corrupt_in_middle = true;
+                } else {
+                    scan.valid_len = (offset + len + 1) as u64;
+                }
+                scan.entries.push(entry);
+                scan.report.valid_records += 1;
+            }
+            None => scan.report.corrupt_records += 1,
+        }
+        offset += len + 1;
+    }
+
+    Ok(scan)
+}
+
+/// ジャーナルを追記用に開く（無ければ本人だけが読み書きできるファイルを作る）
+fn open_append(path: &Path) -> Result<File> {
+    private_open_options()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| storage_error(path, e))
+}
+
+/// パスが開いているファイルとは別のファイル（`rename` で置き換えられた・消された）を指しているか
+///
+/// Windows では開いているファイルを置き換えられないため、常に `false` です。
+fn is_replaced(path: &Path, file: &File) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(path), file.metadata()) {
+            (Ok(current), Ok(open)) => (current.dev(), current.ino()) != (open.dev(), open.ino()),
+            _ => true,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, file);
+        false
+    }
+}
+
+/// 履歴には通知の本文が残るため、新しく作るファイルは本人だけが読み書きできるようにする
+///
+/// Unix では 0600 で作成します（既存のファイルのパーミッションは変えません）。
+pub(crate) fn private_open_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+/// ディレクトリを再帰的に作成する（Unix では新しく作るディレクトリを 0700 にする）
+pub(crate) fn create_private_dir_all(path: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+/// ファイル作成・rename をディスクに確定させるため、親ディレクトリを fsync する
+///
+/// Windows ではディレクトリを開けないため何もしません。
+pub(super) fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// io::Error をパス付きのストレージエラーに変換
+pub(super) fn storage_error(path: &Path, err: std::io::Error) -> NotificationError {
+    NotificationError::Storage {
+        path: path.to_path_buf(),
+        reason: err.to_string(),
+    }
+}
+
+/// CRC32（IEEE 802.3）を計算する
+///
+/// テーブルを使わない素朴な実装です。ジャーナルのレコードは短いので十分な速度です。
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// ペイロードを順に、連番 1 からのレコードにする（記録時刻は連番 × 1 秒、他のモジュールのテスト用）
+#[cfg(test)]
+pub(crate) fn entries_from(payloads: &[&str]) -> Vec<Entry> {
+    (1..)
+        .zip(payloads)
+        .map(|(seq, payload)| Entry {
+            seq,
+            timestamp_ms: seq * 1000,
+            payload: payload.to_string(),
+        })
+        .collect()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_crc32_known_value() {
+        // "123456789" の CRC32 は 0xCBF43926（標準のテストベクタ）
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_new_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join("history.wal");
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        journal.append("secret").unwrap();
+        repair(&path).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(path.parent().unwrap()), 0o700);
+    }
+
+    #[test]
+    fn test_append_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        assert_eq!(journal.append(r#"{"a":1}"#).unwrap(), 1);
+        assert_eq!(journal.append_durable(r#"{"b":2}"#).unwrap(), 2);
+        drop(journal);
+
+        // 開き直すと連番が続きから始まる
+        let mut journal = Journal::open(&path, FsyncPolicy::Never).unwrap();
+        assert_eq!(journal.append("third").unwrap(), 3);
+
+        let payloads: Vec<String> = journal
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.payload)
+            .collect();
+        assert_eq!(payloads, vec![r#"{"a":1}"#, r#"{"b":2}"#, "third"]);
+    }
+
+    #[test]
+    fn test_append_uses_injected_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.wal");
+        let manual = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(10)));
+
+        let mut journal = Journal::open(&path, FsyncPolicy::Never).unwrap();
+        journal.set_clock(manual.clone());
+        journal.append("first").unwrap();
+        manual.advance(Duration::from_millis(250));
+        journal.append("second").unwrap();
+
+        let timestamps: Vec<u64> = journal
+            .entries()
+            .unwrap()
+            .iter()
+            .map(|e| e.timestamp_ms)
+            .collect();
+        assert_eq!(timestamps, vec![10_000, 10_250]);
+    }
+
+    #[test]
+    fn test_torn_tail_is_truncated_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.wal");
+
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        journal.append("kept").unwrap();
+        drop(journal);
+
+        // 書きかけのレコードを再現
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"2\t0\tdeadbeef\thalf-writ").unwrap();
+        assert!(check(&path).unwrap().torn_tail);
+
+        let journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        assert_eq!(journal.entries().unwrap().len(), 1);
+        assert!(check(&path).unwrap().is_healthy());
+    }
+
+    #[test]
+    fn test_torn_header_is_rewritten_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+        fs::write(&path, &MAGIC[..6]).unwrap();
+
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        assert_eq!(journal.append("first").unwrap(), 1);
+        assert!(check(&path).unwrap().is_healthy());
+    }
+
+    #[test]
+    fn test_handles_share_sequence_numbers() {
+        // 別々に開いたハンドル（別のプロセスと同じ）の追記で連番が重ならない
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.wal");
+        let mut first = Journal::open(&path, FsyncPolicy::Never).unwrap();
+        let mut second = Journal::open(&path, FsyncPolicy::Never).unwrap();
+
+        assert_eq!(first.append("a").unwrap(), 1);
+        assert_eq!(second.append("b").unwrap(), 2);
+        assert_eq!(first.append("c").unwrap(), 3);
+
+        // repair で置き換えられたファイルにも追記できる
+        repair(&path).unwrap();
+        fs::write(&path, format!("{}x", fs::read_to_string(&path).unwrap())).unwrap();
+        repair(&path).unwrap();
+        assert_eq!(second.append("d").unwrap(), 4);
+        let seqs: Vec<u64> = first.entries().unwrap().iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_repair_removes_corrupt_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        journal.append("first").unwrap();
+        journal.append("second").unwrap();
+        drop(journal);
+
+        // 1 件目のペイロードを書き換えてチェックサムを不一致にする
+        let content = fs::read_to_string(&path).unwrap().replace("first", "FIRST");
+        fs::write(&path, content).unwrap();
+
+        assert!(Journal::open(&path, FsyncPolicy::Always).is_err());
+        let report = repair(&path).unwrap();
+        assert_eq!(report.corrupt_records, 1);
+        assert_eq!(report.valid_records, 1);
+
+        let journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        assert_eq!(journal.entries().unwrap()[0].payload, "second");
+    }
+
+    #[test]
+    fn test_rewrite_keeps_sequence_for_open_handles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        for payload in ["a", "b", "c"] {
+            journal.append(payload).unwrap();
+        }
+
+        rewrite(&path, "compact.tmp", |entries| {
+            entries.into_iter().filter(|entry| entry.seq == 2).collect()
+        })
+        .unwrap();
+
+        // 開いたままのハンドルも置き換えたファイルに追記し、残ったレコードの後ろから数える
+        assert_eq!(journal.append("d").unwrap(), 3);
+        let payloads: Vec<String> = journal
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.payload)
+            .collect();
+        assert_eq!(payloads, ["b", "d"]);
+    }
+
+    #[test]
+    fn test_payload_with_newline_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut journal = Journal::open(dir.path().join("j.wal"), FsyncPolicy::Never).unwrap();
+        assert!(journal.append("a\nb").is_err());
+    }
+}
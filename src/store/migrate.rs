@@ -0,0 +1,269 @@
+//! ジャーナル形式の移行（マイグレーション）
+//!
+//! ジャーナルの形式を変えても、古いファイルを消さずに使い続けられるように、
+//! 旧形式のファイルを読み込んで現在の形式に書き直します。
+//! `Journal::open()` が自動的に呼ぶほか、`rust-toast store migrate --dry-run`
+//! で移行内容を事前に確認できます。
+//!
+//! 移行前のファイルは、ファイル名の後に `.v<旧バージョン>.bak` を付けて残します
+//! （`queue.wal` なら `queue.wal.v1.bak`）。
+//!
+//! # 形式を変えるときの手順
+//! 1. `journal::FORMAT_VERSION` を上げる
+//! 2. 直前の形式の 1 行を読む関数を `line_parser()` に追加する
+//!
+//! 公開した旧形式はまだ無いため、テストでは架空の v0（`連番<TAB>内容`）で
+//! バックアップ・置き換え・読めない行の数え方を確かめます。
+//!
+//! # 学習ポイント
+//! - バージョン番号による形式の判別
+//! - 「計画（dry run）」と「実行」を同じコードで扱う設計
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::{NotificationError, Result};
+use crate::store::journal::{self, Entry, FORMAT_VERSION};
+
+/// 移行の内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationPlan {
+    /// 対象のジャーナル
+    pub path: PathBuf,
+    /// 移行前のバージョン
+    pub from_version: u32,
+    /// 移行後のバージョン
+    pub to_version: u32,
+    /// 移行されるレコード数
+    pub records: usize,
+    /// 壊れていたため移行できないレコード数
+    pub dropped_records: usize,
+    /// 移行前のファイルのバックアップ先
+    pub backup_path: PathBuf,
+}
+
+/// 移行が必要かどうかを調べ、必要なら移行内容を返す（ファイルは変更しない）
+///
+/// 既に現在の形式なら `None` を返します。
+pub fn plan(path: impl AsRef<Path>) -> Result<Option<MigrationPlan>> {
+    Ok(load(path.as_ref())?.map(|(plan, _)| plan))
+}
+
+/// ジャーナルを現在の形式に移行する
+///
+/// `dry_run` が `true` の場合は何も書き込まず、移行内容だけを返します。
+/// 既に現在の形式なら何もせず `None` を返します。
+pub fn migrate(path: impl AsRef<Path>, dry_run: bool) -> Result<Option<MigrationPlan>> {
+    let path = path.as_ref();
+    if dry_run {
+        return plan(path);
+    }
+    journal::with_file_lock(path, || {
+        let Some((plan, entries)) = load(path)? else {
+            return Ok(None);
+        };
+        // 失敗しても元に戻せるよう、置き換える前にバックアップを取る
+        fs::copy(path, &plan.backup_path)
+            .map_err(|e| journal::storage_error(&plan.backup_path, e))?;
+        journal::replace_with_entries(path, &entries, "migrate.tmp")?;
+        Ok(Some(plan))
+    })
+}
+
+/// ファイルを読み込み、旧形式なら移行内容とレコードを返す
+fn load(path: &Path) -> Result<Option<(MigrationPlan, Vec<Entry>)>> {
+    let bytes = fs::read(path).map_err(|e| journal::storage_error(path, e))?;
+    let (version, header_end) = journal::read_header(path, &bytes)?;
+
+    if version == FORMAT_VERSION {
+        return Ok(None);
+    }
+    if version > FORMAT_VERSION {
+        return Err(NotificationError::Storage {
+            path: path.to_path_buf(),
+            reason: format!(
+                "journal format v{version} is newer than supported v{FORMAT_VERSION}; upgrade rust-toast"
+            ),
+        });
+    }
+
+    let body = String::from_utf8_lossy(&bytes[header_end + 1..]);
+    let (entries, dropped_records) = parse_legacy(path, version, &body)?;
+
+    let plan = MigrationPlan {
+        path: path.to_path_buf(),
+        from_version: version,
+        to_version: FORMAT_VERSION,
+        records: entries.len(),
+        dropped_records,
+        backup_path: backup_path(path, version),
+    };
+    Ok(Some((plan, entries)))
+}
+
+/// 移行前のファイルのバックアップ先（`queue.wal` → `queue.wal.v1.bak`）
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".v{version}.bak"));
+    PathBuf::from(name)
+}
+
+/// 旧形式の本文をレコード一覧に変換する
+///
+/// 戻り値は（正常なレコード, 読めなかった行の数）です。
+/// 改行で終わる行だけを `Entry` にし、書きかけの行と読めない行は数えて捨てます。
+fn parse_legacy(path: &Path, version: u32, body: &str) -> Result<(Vec<Entry>, usize)> {
+    let Some(parse_line) = line_parser(version) else {
+        return Err(NotificationError::Storage {
+            path: path.to_path_buf(),
+            reason: format!("no migration from journal format v{version}"),
+        });
+    };
+
+    // 記録時刻の無い形式のため、ファイルの更新時刻で代用する
+    let fallback_ms = modified_ms(path);
+
+    let mut entries = Vec::new();
+    let mut dropped = 0;
+    for line in body.split_inclusive('\n') {
+        match line
+            .strip_suffix('\n')
+            .and_then(|line| parse_line(line, fallback_ms))
+        {
+            Some(entry) => entries.push(entry),
+            None => dropped += 1,
+        }
+    }
+    Ok((entries, dropped))
+}
+
+/// 旧形式の 1 行を読む関数（移行できない形式は `None`）
+///
+/// 形式を変えたら、直前の形式をここに追加します。
+fn line_parser(version: u32) -> Option<fn(&str, u64) -> Option<Entry>> {
+    match version {
+        #[cfg(test)]
+        0 => Some(tests::parse_v0),
+        _ => None,
+    }
+}
+
+/// ファイルの更新時刻（UNIX エポックからのミリ秒、取得できなければ 0）
+fn modified_ms(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::journal::{FsyncPolicy, Journal};
+
+    /// 架空の v0 形式（`連番<TAB>内容`、記録時刻もチェックサムも無い）の 1 行を読む
+    pub(super) fn parse_v0(line: &str, timestamp_ms: u64) -> Option<Entry> {
+        let (seq, payload) = line.split_once('\t')?;
+        Some(Entry {
+            seq: seq.parse().ok()?,
+            timestamp_ms,
+            payload: payload.to_string(),
+        })
+    }
+
+    /// v0 形式のジャーナルを作る
+    fn write_v0(path: &Path, lines: &str) {
+        fs::write(path, format!("rust-toast-journal 0\n{lines}")).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_does_not_modify_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+        write_v0(&path, "1\ta\n2\tb\n");
+        let before = fs::read(&path).unwrap();
+
+        let plan = migrate(&path, true).unwrap().expect("v0 needs migration");
+        assert_eq!((plan.from_version, plan.to_version), (0, FORMAT_VERSION));
+        assert_eq!((plan.records, plan.dropped_records), (2, 0));
+        assert_eq!(plan.backup_path, dir.path().join("queue.wal.v0.bak"));
+        assert_eq!(fs::read(&path).unwrap(), before);
+        assert!(!plan.backup_path.exists());
+    }
+
+    #[test]
+    fn test_migrate_backs_up_and_drops_unreadable_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+        // 2 行目は連番が読めず、3 行目は書きかけ
+        write_v0(&path, "1\tok\nx\tbroken\n3\thalf");
+        let before = fs::read(&path).unwrap();
+
+        let done = migrate(&path, false).unwrap().unwrap();
+        assert_eq!((done.records, done.dropped_records), (1, 2));
+        assert_eq!(fs::read(&done.backup_path).unwrap(), before);
+        assert!(plan(&path).unwrap().is_none());
+
+        let entries = Journal::open(&path, FsyncPolicy::Always)
+            .unwrap()
+            .entries()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].seq, entries[0].payload.as_str()), (1, "ok"));
+        assert!(entries[0].timestamp_ms > 0);
+    }
+
+    #[test]
+    fn test_open_migrates_automatically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.wal");
+        write_v0(&path, "1\tfirst\n2\tsecond\n");
+
+        let mut journal = Journal::open(&path, FsyncPolicy::Always).unwrap();
+        assert_eq!(journal.append("third").unwrap(), 3);
+        let payloads: Vec<String> = journal
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.payload)
+            .collect();
+        assert_eq!(payloads, ["first", "second", "third"]);
+        assert!(dir.path().join("history.wal.v0.bak").exists());
+    }
+
+    #[test]
+    fn test_current_version_needs_no_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+        Journal::open(&path, FsyncPolicy::Always)
+            .unwrap()
+            .append("a")
+            .unwrap();
+        let before = fs::read(&path).unwrap();
+
+        assert!(plan(&path).unwrap().is_none());
+        assert!(migrate(&path, false).unwrap().is_none());
+        assert_eq!(fs::read(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn test_unknown_versions_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.wal");
+        fs::write(&path, "rust-toast-journal 99\n1\tx\n").unwrap();
+        assert!(plan(&path).is_err());
+
+        // 読めない形式は、開かずにエラーにする（ファイルはそのまま）
+        assert!(Journal::open(&path, FsyncPolicy::Always).is_err());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "rust-toast-journal 99\n1\tx\n"
+        );
+    }
+}
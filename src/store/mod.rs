@@ -0,0 +1,92 @@
+//! 永続化ストアモジュール
+//!
+//! 送信待ちキューや通知履歴など、プロセスをまたいで残す状態を
+//! データディレクトリ内のジャーナル（追記専用ログ）に保存します。
+//!
+//! ```text
+//! <data_dir>/
+//! ├── queue.wal     # 送信待ちキュー
+//! ├── history.wal   # 通知履歴
+//! └── schedules.wal # 繰り返しの通知の登録
+//! ```
+//!
+//! # 学習ポイント
+//! - 環境変数と OS ごとの慣習に従ったデータディレクトリの決定
+//! - サブモジュールによる責務の分割
+
+use std::path::PathBuf;
+
+pub mod history;
+pub mod journal;
+pub mod migrate;
+pub mod queue;
+pub mod schedules;
+
+pub use journal::{check, repair, CheckReport, Entry, FsyncPolicy, Journal};
+pub use migrate::MigrationPlan;
+
+/// データディレクトリを上書きする環境変数
+pub const DATA_DIR_ENV: &str = "RUST_TOAST_DATA_DIR";
+
+/// ストアが管理するジャーナルの名前
+pub const JOURNALS: [&str; 3] = ["queue", "history", "schedules"];
+
+/// データディレクトリのパスを返す
+///
+/// 優先順位:
+/// 1. `RUST_TOAST_DATA_DIR`
+/// 2. OS ごとの標準の場所
+///    - Linux / WSL: `$XDG_DATA_HOME/rust-toast`（未設定なら `~/.local/share/rust-toast`）
+///    - macOS: `~/Library/Application Support/rust-toast`
+///    - Windows: `%LOCALAPPDATA%\rust-toast`
+/// 3. どれも決まらなければカレントディレクトリの `.rust-toast`
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = non_empty_env(DATA_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    platform_data_dir()
+        .map(|dir| dir.join("rust-toast"))
+        .unwrap_or_else(|| PathBuf::from(".rust-toast"))
+}
+
+/// 名前付きジャーナルのパスを返す（例: `queue` → `<data_dir>/queue.wal`）
+pub fn journal_path(name: &str) -> PathBuf {
+    data_dir().join(format!("{name}.wal"))
+}
+
+/// OS ごとのデータディレクトリの親
+fn platform_data_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        return non_empty_env("LOCALAPPDATA").map(PathBuf::from);
+    }
+
+    let home = non_empty_env("HOME").map(PathBuf::from);
+    if cfg!(target_os = "macos") {
+        return home.map(|home| home.join("Library").join("Application Support"));
+    }
+
+    non_empty_env("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| home.join(".local").join("share")))
+}
+
+/// 空でない環境変数の値を取得
+fn non_empty_env(key: &str) -> Option<std::ffi::OsString> {
+    std::env::var_os(key).filter(|value| !value.is_empty())
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_path_has_wal_extension() {
+        let path = journal_path("queue");
+        assert_eq!(path.file_name().unwrap(), "queue.wal");
+        assert_eq!(path.parent().unwrap(), data_dir());
+    }
+}
@@ -0,0 +1,174 @@
+//! 送信待ちキュー（スプール）
+//!
+//! `Config::spool` が有効で、通知を表示できるバックエンドが無かった場合
+//! （SSH 先で D-Bus が無い、通知デーモンが落ちているなど）、通知を捨てずに
+//! `queue.wal` に JSON（`Notification::to_json()`）で記録します。
+//! 記録した通知は、次に送信できた時か `notifier::flush_spool()` で古い順に送り直します。
+//!
+//! 送り直した通知は、履歴と同じく削除せずにマーカー（`{"event":"sent","seq":N}`）を追記して表します。
+//! 送れなかった回はマーカー `failed` で数え、読めない通知と `MAX_ATTEMPTS` 回失敗した通知は
+//! `poisoned` にして以後は送りません（1 つの通知でキュー全体が止まらないように）。
+//! 送り直しの後は `compact()` で、送った通知と諦めた通知をファイルから取り除きます。
+//!
+//! # 学習ポイント
+//! - 追記専用ログでの「処理済み」の表し方（レコードごとのマーカー）
+//! - 追記専用ログのコンパクション（残すレコードだけで置き換える）
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::store::journal;
+use crate::store::{journal_path, Entry, FsyncPolicy, Journal};
+
+/// 送り直しに失敗できる回数（これに達した通知は諦める）
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// まだ送っていない通知
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spooled {
+    /// 通知のレコード
+    pub entry: Entry,
+    /// これまでに送り直しに失敗した回数
+    pub failures: u32,
+}
+
+/// キューのジャーナルのパス
+pub fn path() -> PathBuf {
+    journal_path("queue")
+}
+
+/// 通知（`Notification::to_json()` の JSON）をキューに追加し、連番を返す
+pub fn push(notification_json: &str) -> Result<u64> {
+    Journal::open(path(), FsyncPolicy::Always)?.append(notification_json)
+}
+
+/// まだ送っていない通知（古い順、キューがまだ無ければ空）
+pub fn pending() -> Result<Vec<Spooled>> {
+    let path = path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(pending_entries(
+        Journal::open(path, FsyncPolicy::Always)?.entries()?,
+    ))
+}
+
+/// 通知を送ったことを記録する
+pub fn mark_sent(seq: u64) -> Result<()> {
+    append_marker(json!({ "event": "sent", "seq": seq }))
+}
+
+/// 送り直しに失敗したことを記録する
+pub fn mark_failed(seq: u64, error: &str) -> Result<()> {
+    append_marker(json!({ "event": "failed", "seq": seq, "error": error }))
+}
+
+/// 通知を諦めたこと（読めない・失敗し続けた）を記録する
+pub fn mark_poisoned(seq: u64, error: &str) -> Result<()> {
+    append_marker(json!({ "event": "poisoned", "seq": seq, "error": error }))
+}
+
+fn append_marker(marker: Value) -> Result<()> {
+    Journal::open(path(), FsyncPolicy::Always)?.append(&marker.to_string())?;
+    Ok(())
+}
+
+/// 送った通知と諦めた通知（とそのマーカー）をキューから取り除く
+///
+/// 残すのはまだ送っていない通知と、その失敗の記録だけです。
+/// ファイルはロックしたまま読み直して置き換えるので、並行して追加された通知も残ります。
+pub fn compact() -> Result<()> {
+    let path = path();
+    if !path.exists() {
+        return Ok(());
+    }
+    journal::rewrite(&path, "compact.tmp", compacted)
+}
+
+/// レコード（古い順）のうち、まだ送っていない通知とその失敗の記録だけを残す
+pub fn compacted(entries: Vec<Entry>) -> Vec<Entry> {
+    let pending: HashSet<u64> = pending_entries(entries.clone())
+        .iter()
+        .map(|spooled| spooled.entry.seq)
+        .collect();
+    entries
+        .into_iter()
+        .filter(|entry| {
+            pending.contains(&entry.seq)
+                || marker(entry)
+                    .is_some_and(|(event, seq)| event == "failed" && pending.contains(&seq))
+        })
+        .collect()
+}
+
+/// レコード（古い順）から、送った・諦めた印の無い通知を失敗した回数と共に取り出す
+pub fn pending_entries(entries: Vec<Entry>) -> Vec<Spooled> {
+    let mut done = HashSet::new();
+    let mut failures: HashMap<u64, u32> = HashMap::new();
+    for (event, seq) in entries.iter().filter_map(marker) {
+        match event.as_str() {
+            "failed" => *failures.entry(seq).or_default() += 1,
+            _ => {
+                done.insert(seq);
+            }
+        }
+    }
+    entries
+        .into_iter()
+        .filter(|entry| !done.contains(&entry.seq))
+        .filter(|entry| {
+            serde_json::from_str::<Value>(&entry.payload)
+                .is_ok_and(|record| record.get("event").is_none())
+        })
+        .map(|entry| Spooled {
+            failures: failures.get(&entry.seq).copied().unwrap_or(0),
+            entry,
+        })
+        .collect()
+}
+
+/// マーカーのレコードなら（種類、対象の連番）
+fn marker(entry: &Entry) -> Option<(String, u64)> {
+    let record = serde_json::from_str::<Value>(&entry.payload).ok()?;
+    Some((
+        record["event"].as_str()?.to_string(),
+        record["seq"].as_u64()?,
+    ))
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_entries_skip_sent() {
+        let entries = journal::entries_from(&[
+            r#"{"message":"a"}"#,
+            r#"{"message":"b"}"#,
+            r#"{"event":"sent","seq":1}"#,
+            "not json",
+            r#"{"message":"c"}"#,
+            r#"{"event":"failed","seq":2,"error":"down"}"#,
+            r#"{"message":"d"}"#,
+            r#"{"event":"failed","seq":2,"error":"down"}"#,
+            r#"{"event":"poisoned","seq":7,"error":"bad"}"#,
+        ]);
+
+        let pending: Vec<(u64, u32)> = pending_entries(entries.clone())
+            .iter()
+            .map(|spooled| (spooled.entry.seq, spooled.failures))
+            .collect();
+        assert_eq!(pending, [(2, 2), (5, 0)]);
+
+        // コンパクションで残るのは送っていない通知と、その失敗の記録だけ
+        let kept: Vec<u64> = compacted(entries).iter().map(|entry| entry.seq).collect();
+        assert_eq!(kept, [2, 5, 6, 8]);
+    }
+}
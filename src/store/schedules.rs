@@ -0,0 +1,144 @@
+//! 繰り返しの通知の登録
+//!
+//! `rust-toast schedule add` の登録を `schedules.wal` に記録します。
+//! 追加と削除をイベントとして追記し、読む時に先頭から再生して現在の登録を求めます
+//! （予定の ID は追加したレコードの連番）。
+//!
+//! ```text
+//! {"event":"add","every":1800,"args":["-m","Stand up"]}
+//! {"event":"add","cron":"0 9 * * 1-5","utc_offset":540,"args":["-m","Standup"]}
+//! {"event":"remove","id":1}
+//! ```
+//!
+//! # 学習ポイント
+//! - イベントの再生（リプレイ）による状態の復元
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::recurring::{Cron, Recurrence};
+use crate::store::{journal_path, Entry, FsyncPolicy, Journal};
+
+/// 登録された繰り返しの通知
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// 予定の ID（`schedule remove` に渡す）
+    pub id: u64,
+    /// 繰り返しの指定
+    pub recurrence: Recurrence,
+    /// 実行する rust-toast の引数（プログラム名を除く）
+    pub args: Vec<String>,
+}
+
+/// 登録のジャーナルのパス
+pub fn path() -> PathBuf {
+    journal_path("schedules")
+}
+
+/// 予定を登録し、ID を返す
+pub fn add(recurrence: &Recurrence, args: &[String]) -> Result<u64> {
+    let mut record = json!({ "event": "add", "args": args });
+    match recurrence {
+        Recurrence::Every(interval) => record["every"] = json!(interval.as_secs()),
+        Recurrence::Cron(cron) => {
+            record["cron"] = json!(cron.source());
+            record["utc_offset"] = json!(cron.offset());
+        }
+    }
+    Journal::open(path(), FsyncPolicy::Always)?.append(&record.to_string())
+}
+
+/// 予定を削除する（登録されていれば `true`）
+pub fn remove(id: u64) -> Result<bool> {
+    if !list()?.iter().any(|schedule| schedule.id == id) {
+        return Ok(false);
+    }
+    let record = json!({ "event": "remove", "id": id });
+    Journal::open(path(), FsyncPolicy::Always)?.append(&record.to_string())?;
+    Ok(true)
+}
+
+/// 登録されている予定（登録順、まだ無ければ空）
+pub fn list() -> Result<Vec<Schedule>> {
+    let path = path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(replay(
+        &Journal::open(path, FsyncPolicy::Always)?.entries()?,
+    ))
+}
+
+/// レコード（古い順）を再生して、残っている予定を求める
+///
+/// 読めないレコードは読み飛ばします。
+pub fn replay(entries: &[Entry]) -> Vec<Schedule> {
+    let mut schedules: Vec<Schedule> = Vec::new();
+    for entry in entries {
+        let Ok(record) = serde_json::from_str::<Value>(&entry.payload) else {
+            continue;
+        };
+        match record["event"].as_str() {
+            Some("add") => schedules.extend(parse_schedule(entry.seq, &record)),
+            Some("remove") => {
+                let id = record["id"].as_u64();
+                schedules.retain(|schedule| Some(schedule.id) != id);
+            }
+            _ => {}
+        }
+    }
+    schedules
+}
+
+fn parse_schedule(id: u64, record: &Value) -> Option<Schedule> {
+    let recurrence = if let Some(secs) = record["every"].as_u64() {
+        Recurrence::Every(Duration::from_secs(secs))
+    } else {
+        let cron: Cron = record["cron"].as_str()?.parse().ok()?;
+        let offset = record["utc_offset"].as_i64().unwrap_or(0);
+        Recurrence::Cron(cron.utc_offset(i32::try_from(offset).ok()?))
+    };
+    let args = record["args"]
+        .as_array()?
+        .iter()
+        .map(|arg| arg.as_str().map(str::to_string))
+        .collect::<Option<Vec<_>>>()?;
+    Some(Schedule {
+        id,
+        recurrence,
+        args,
+    })
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::journal;
+
+    #[test]
+    fn test_replay_adds_and_removes() {
+        let entries = journal::entries_from(&[
+            r#"{"event":"add","every":1800,"args":["-m","Stand up"]}"#,
+            r#"{"event":"add","cron":"0 9 * * 1-5","utc_offset":540,"args":["-m","Standup"]}"#,
+            "not json",
+            r#"{"event":"add","cron":"bad","args":[]}"#,
+            r#"{"event":"remove","id":1}"#,
+        ]);
+
+        let schedules = replay(&entries);
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].id, 2);
+        assert_eq!(schedules[0].args, ["-m", "Standup"]);
+        assert_eq!(
+            schedules[0].recurrence.to_string(),
+            "cron \"0 9 * * 1-5\" UTC+09:00"
+        );
+    }
+}
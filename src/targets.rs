@@ -0,0 +1,154 @@
+//! ビルド対象とバックエンドの対応状況
+//!
+//! `rust-toast targets` で、このバイナリがどのターゲット向けにビルドされ、
+//! どのバックエンドが組み込まれているかを表示します。
+//! WSL や Apple Silicon 向けにクロスコンパイルした時の確認用です。
+//!
+//! | バックエンド | 組み込まれるターゲット | フィーチャー |
+//! |--------------|------------------------|--------------|
+//! | linux | Linux（D-Bus のコードは Linux 向けのみ、他はスタブ） | `linux-dbus` |
+//! | wsl / windows | Windows と Linux（WSL から `powershell.exe` を呼ぶ） | `windows-powershell` |
+//! | macos | macOS | `macos-osascript` |
+//! | dialog / console / stdout | 全て | なし |
+//!
+//! フィーチャーはデフォルトで全て有効です。ライブラリとして組み込む時に
+//! `default-features = false` で必要なものだけを選ぶと、使わないバックエンドの依存
+//! （notify-rust / zbus など）をビルドしません。
+//!
+//! どのバックエンドも全てのターゲットでコンパイルでき、組み込まれていないものは
+//! `is_available()` が `false` のスタブになります。
+//!
+//! # 学習ポイント
+//! - `build.rs` で決めた値を `env!` でコンパイル時に埋め込む
+//! - `cfg!` マクロ（`bool` を返す）によるターゲットの判定
+
+use std::fmt;
+
+use crate::notifier::{self, Backend};
+use crate::platform::Platform;
+
+/// ビルド対象のターゲットトリプル（`build.rs` が設定する）
+pub const TARGET: &str = env!("RUST_TOAST_TARGET");
+
+/// 表示するバックエンド（CLI の `--backend` の順）
+const BACKENDS: [Backend; 7] = [
+    Backend::Native(Platform::Linux),
+    Backend::Native(Platform::Wsl),
+    Backend::Native(Platform::Windows),
+    Backend::Native(Platform::MacOs),
+    Backend::Dialog,
+    Backend::Console,
+    Backend::Stdout,
+];
+
+/// 1 つのバックエンドの対応状況
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendSupport {
+    /// バックエンド
+    pub backend: Backend,
+    /// 表示名（`Notifier::backend_name()`）
+    pub name: &'static str,
+    /// このターゲット向けに組み込まれているか（スタブでないか）
+    pub compiled: bool,
+    /// 実行中の環境で使えるか（`Notifier::is_available()`）
+    pub available: bool,
+}
+
+impl fmt::Display for BackendSupport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {:<9} {:<11} {}",
+            self.backend.key(),
+            if self.compiled { "compiled" } else { "stub" },
+            if self.available {
+                "available"
+            } else {
+                "unavailable"
+            },
+            self.name
+        )
+    }
+}
+
+/// バックエンドがこのターゲット向けに組み込まれているか（ターゲットとフィーチャーの両方）
+pub fn is_compiled(backend: &Backend) -> bool {
+    match backend {
+        Backend::Native(Platform::Linux) => {
+            cfg!(all(target_os = "linux", feature = "linux-dbus"))
+        }
+        Backend::Native(Platform::Wsl | Platform::Windows) => cfg!(all(
+            any(target_os = "windows", target_os = "linux"),
+            feature = "windows-powershell"
+        )),
+        Backend::Native(Platform::MacOs) => {
+            cfg!(all(target_os = "macos", feature = "macos-osascript"))
+        }
+        Backend::Native(Platform::Unknown)
+        | Backend::Dialog
+        | Backend::Console
+        | Backend::Stdout
+        | Backend::Custom(_) => true,
+    }
+}
+
+/// バックエンドを組み込むフィーチャー（フィーチャーに関係なく組み込まれるものは `None`）
+pub fn feature(backend: &Backend) -> Option<&'static str> {
+    match backend {
+        Backend::Native(Platform::Linux) => Some("linux-dbus"),
+        Backend::Native(Platform::Wsl | Platform::Windows) => Some("windows-powershell"),
+        Backend::Native(Platform::MacOs) => Some("macos-osascript"),
+        Backend::Native(Platform::Unknown)
+        | Backend::Dialog
+        | Backend::Console
+        | Backend::Stdout
+        | Backend::Custom(_) => None,
+    }
+}
+
+/// 1 つのバックエンドの対応状況
+pub fn support(backend: Backend) -> BackendSupport {
+    let (name, available) = notifier::backend_status(backend.clone());
+    BackendSupport {
+        compiled: is_compiled(&backend),
+        backend,
+        name,
+        available,
+    }
+}
+
+/// 全ての組み込みのバックエンドの対応状況
+pub fn report() -> Vec<BackendSupport> {
+    BACKENDS.into_iter().map(support).collect()
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_matches_target() {
+        assert!(!TARGET.is_empty());
+        let report = report();
+        assert_eq!(report.len(), BACKENDS.len());
+
+        let linux = &report[0];
+        assert_eq!(
+            linux.compiled,
+            cfg!(all(target_os = "linux", feature = "linux-dbus"))
+        );
+        // スタブは利用可能と報告しない
+        for support in &report {
+            if !support.compiled {
+                assert!(!support.available, "{support}");
+            }
+        }
+        assert!(report
+            .iter()
+            .any(|s| s.backend == Backend::Stdout && s.compiled));
+    }
+}
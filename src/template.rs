@@ -0,0 +1,189 @@
+//! プレースホルダー付きの文面（テンプレート）
+//!
+//! シェルスクリプトから `sed` で文面を組み立てなくて済むように、
+//! タイトルと本文の `{名前}` を置き換えます。
+//!
+//! | プレースホルダー | 値 |
+//! |------------------|----|
+//! | `{hostname}` | このマシンのホスト名 |
+//! | `{date}` | 今日の日付（`YYYY-MM-DD`、UTC） |
+//! | `{exit_code}` | 変数で渡した終了コード |
+//! | `{env:VAR}` | 環境変数 `VAR`（未設定なら空） |
+//! | `{名前}` | 変数で渡した値 |
+//!
+//! 変数で渡した値は組み込みの値より優先します（ローカルの日付を使う場合は `date` を渡す）。
+//! `{{` と `}}` は `{` と `}` になります。知らない名前はエラーです。
+//!
+//! # 例
+//! ```
+//! use std::collections::BTreeMap;
+//! use rust_toast::template::NotificationTemplate;
+//! use rust_toast::NotificationBuilder;
+//!
+//! let template = NotificationTemplate::new("{job} exited with {exit_code}").title("{job}");
+//! let vars = BTreeMap::from([
+//!     ("job".to_string(), "backup".to_string()),
+//!     ("exit_code".to_string(), "1".to_string()),
+//! ]);
+//! let notification = NotificationBuilder::from_template(&template, &vars)?.build();
+//! assert_eq!(notification.title, "backup");
+//! assert_eq!(notification.message, "backup exited with 1");
+//! # Ok::<(), rust_toast::NotificationError>(())
+//! ```
+//!
+//! # 学習ポイント
+//! - `str::find()` による 1 パスの置換（置き換えた値は再び展開しない）
+//! - UNIX 時間から暦の日付への変換（外部クレートを使わない）
+
+use std::collections::BTreeMap;
+
+use crate::clock;
+use crate::config::UNKNOWN_HOSTNAME;
+use crate::error::{NotificationError, Result};
+use crate::platform;
+
+/// タイトルと本文のテンプレート
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationTemplate {
+    /// タイトル（`None` なら `Config::title_template` に任せる）
+    pub title: Option<String>,
+    /// 本文
+    pub message: String,
+}
+
+impl NotificationTemplate {
+    /// 本文のテンプレートから作成
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            title: None,
+            message: message.into(),
+        }
+    }
+
+    /// タイトルのテンプレートを設定
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+/// `text` のプレースホルダーを置き換える
+pub fn render(text: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        // `{{` / `}}` はそのままの括弧
+        if after.starts_with(&rest[start..=start]) {
+            rendered.push_str(&rest[start..=start]);
+            rest = &after[1..];
+            continue;
+        }
+        if rest[start..].starts_with('}') {
+            return Err(invalid(format!("unmatched '}}' in \"{text}\"")));
+        }
+        let end = after
+            .find('}')
+            .ok_or_else(|| invalid(format!("unclosed '{{' in \"{text}\"")))?;
+        rendered.push_str(&value(&after[..end], vars)?);
+        rest = &after[end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// プレースホルダー 1 つの値
+fn value(name: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    if let Some(value) = vars.get(name) {
+        return Ok(value.clone());
+    }
+    if let Some(var) = name.strip_prefix("env:") {
+        return Ok(std::env::var(var).unwrap_or_default());
+    }
+    match name {
+        "hostname" => Ok(platform::hostname().unwrap_or_else(|| UNKNOWN_HOSTNAME.to_string())),
+        "date" => {
+            let now = clock::current().now();
+            let secs = now
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            Ok(format_date(secs))
+        }
+        _ => Err(invalid(format!("unknown placeholder {{{name}}}"))),
+    }
+}
+
+/// UNIX 時間（秒）の UTC の日付を `YYYY-MM-DD` にする
+fn format_date(secs: u64) -> String {
+    let (year, month, day) = civil_date(secs / 86_400);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// 1970-01-01 からの日数を（年, 月, 日）にする
+///
+/// 0000-03-01 からの日数に直し、400 年周期で年・月・日を求めます（Howard Hinnant の方法）。
+/// 3 月始まりにすると、うるう日が年の最後に来るので計算が簡単になります。
+pub(crate) fn civil_date(days_since_epoch: u64) -> (u64, u64, u64) {
+    let days = days_since_epoch + 719_468; // 0000-03-01 からの日数
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // 3 月 = 0
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+fn invalid(reason: String) -> NotificationError {
+    NotificationError::InvalidInput {
+        field: "template".to_string(),
+        reason,
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_placeholders() {
+        let vars = BTreeMap::from([
+            ("exit_code".to_string(), "2".to_string()),
+            // 置き換えた値は再び展開しない
+            ("job".to_string(), "{exit_code}".to_string()),
+        ]);
+        assert_eq!(
+            render("{job} failed ({exit_code}) {{raw}}", &vars).unwrap(),
+            "{exit_code} failed (2) {raw}"
+        );
+        assert_eq!(
+            render("{env:RUST_TOAST_TEMPLATE_UNSET}|", &vars).unwrap(),
+            "|"
+        );
+        assert!(!render("{hostname}", &vars).unwrap().is_empty());
+
+        for bad in ["{missing}", "{unclosed", "stray }"] {
+            assert!(render(bad, &vars).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+        assert_eq!(format_date(1_735_689_600), "2025-01-01");
+    }
+}
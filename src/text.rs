@@ -0,0 +1,111 @@
+//! 端末向けの出力を通知の本文にする
+//!
+//! コマンドの出力やログには色・カーソル移動の ANSI エスケープや、進捗表示の `\r` が混ざります。
+//! `--stdin`・`exec`・`watch`・`pipe` はどれもここで整えてから通知にします。
+//!
+//! | 関数 | 内容 |
+//! |------|------|
+//! | `strip_ansi()` | ANSI エスケープシーケンスだけを取り除く |
+//! | `clean_piped_text()` | それに加えて `\r` の書き換え・前後の空白・長さ（`STDIN_MAX_CHARS`）を整える |
+//!
+//! # 学習ポイント
+//! - `Peekable` で 1 文字先を見ながら読む、小さな状態機械
+
+/// 標準入力から読んだ本文の最大文字数（超えた分は先頭から捨てる）
+pub const STDIN_MAX_CHARS: usize = 4000;
+
+/// パイプで受け取った文字列を通知の本文にする
+///
+/// - ANSI エスケープシーケンス（色・カーソル移動）を取り除く
+/// - 行の中の `\r`（進捗表示の書き換え）は、端末と同じく最後に書かれた部分だけを残す
+/// - 前後の空白を取り除き、`STDIN_MAX_CHARS` を超えたら末尾（最新の出力）を残す
+pub fn clean_piped_text(text: &str) -> String {
+    let plain = strip_ansi(text);
+    let lines: Vec<&str> = plain
+        .lines()
+        .map(|line| {
+            line.rsplit('\r')
+                .find(|part| !part.is_empty())
+                .unwrap_or("")
+        })
+        .collect();
+    let joined = lines.join("\n");
+    let trimmed = joined.trim();
+    let len = trimmed.chars().count();
+    if len <= STDIN_MAX_CHARS {
+        return trimmed.to_string();
+    }
+    let tail: String = trimmed.chars().skip(len - (STDIN_MAX_CHARS - 1)).collect();
+    format!("…{}", tail.trim_start())
+}
+
+/// ANSI エスケープシーケンスを取り除く
+///
+/// CSI（`ESC [ ... 終端文字`）、OSC（`ESC ] ... BEL` または `ESC \`）、その他の 2 文字の
+/// シーケンス（`ESC x`）に対応します。
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: パラメーターと中間のバイトの後、0x40〜0x7e の終端文字で終わる
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: BEL か ST（ESC \）で終わる
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    plain
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[1;31merror\x1b[0m: x"), "error: x");
+        assert_eq!(strip_ansi("\x1b]0;title\x1b\\done\x1b7"), "done");
+    }
+
+    #[test]
+    fn test_clean_piped_text() {
+        assert_eq!(
+            clean_piped_text("\x1b[1;32m   Compiling\x1b[0m app\n\x1b]0;title\x07Finished\n\n"),
+            "Compiling app\nFinished"
+        );
+        assert_eq!(
+            clean_piped_text("Downloading 10%\rDownloading 100%\r\ndone"),
+            "Downloading 100%\ndone"
+        );
+
+        let long = format!("head {}", "x".repeat(STDIN_MAX_CHARS));
+        let cleaned = clean_piped_text(&long);
+        assert_eq!(cleaned.chars().count(), STDIN_MAX_CHARS);
+        assert!(cleaned.starts_with('…') && cleaned.ends_with('x'));
+    }
+}
@@ -0,0 +1,200 @@
+//! 全体の送信数の制限
+//!
+//! ループの誤りなどで数百件の通知がデスクトップを埋め尽くさないように、
+//! `Config::rate_limit` で「`per` の間に `max` 件まで」の上限を全ての通知に掛けます
+//! （トピックごとの上限 `TopicRule::rate_limit` とは別に数えます）。
+//! 上限を超えた通知の扱いは `Overflow` で選びます。
+//! トピックごとの上限と同じく、数えるのはプロセスの中だけです（別のプロセスの通知は数えません）。
+//!
+//! | `Overflow` | 上限を超えた通知 |
+//! |------------|------------------|
+//! | `Drop` | 表示せずに捨てる（ログに出す） |
+//! | `Queue` | 空きが出るまで送信を待つ（`send()` が戻らない） |
+//! | `Summarize` | 捨てて数え、次に送れた時に「N 件を省略」の通知を 1 つ表示する |
+//!
+//! # 例
+//! ```
+//! use std::time::Duration;
+//! use rust_toast::config::{self, Config};
+//! use rust_toast::throttle::{GlobalRateLimit, Overflow};
+//!
+//! let mut config = Config::default();
+//! config.rate_limit = Some(GlobalRateLimit::new(5, Duration::from_secs(10), Overflow::Summarize));
+//! config::set(config);
+//! ```
+//!
+//! # 学習ポイント
+//! - 既存の部品（`topic::RateLimiter`）の再利用
+//! - 列挙型で方針（ポリシー）を選ばせる
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::clock::{Clock, CurrentClock};
+use crate::topic::{RateLimit, RateLimiter};
+
+/// 上限を超えた通知の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// 表示せずに捨てる
+    #[default]
+    Drop,
+    /// 空きが出るまで待ってから送る
+    Queue,
+    /// 捨てて数え、次に送れた時にまとめて知らせる
+    Summarize,
+}
+
+impl Overflow {
+    /// 表示用の名前
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Drop => "drop",
+            Self::Queue => "queue",
+            Self::Summarize => "summarize",
+        }
+    }
+}
+
+/// 全ての通知に掛ける送信数の上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalRateLimit {
+    /// 上限
+    pub limit: RateLimit,
+    /// 上限を超えた通知の扱い
+    pub overflow: Overflow,
+}
+
+impl GlobalRateLimit {
+    /// `per` あたり `max` 件までの上限を作成
+    pub fn new(max: u32, per: Duration, overflow: Overflow) -> Self {
+        Self {
+            limit: RateLimit::new(max, per),
+            overflow,
+        }
+    }
+}
+
+/// 送信してよいかの判定結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// 送信する
+    Send,
+    /// 送信し、その前に省略した件数を知らせる（`Overflow::Summarize`）
+    SendWithSummary(u32),
+    /// 表示しない
+    Dropped,
+}
+
+/// 全体の送信数を数え、`GlobalRateLimit` に従って判定する
+#[derive(Debug)]
+pub struct Throttle {
+    clock: Arc<dyn Clock>,
+    limiter: RateLimiter,
+    /// `Summarize` で省略した件数（次に送れた時に 0 に戻す）
+    suppressed: AtomicU32,
+}
+
+/// `RateLimiter` のキー（全体で 1 つ）
+const KEY: &str = "*";
+
+impl Throttle {
+    /// 時計を指定して作成
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            limiter: RateLimiter::new(Arc::clone(&clock)),
+            clock,
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// 通知 1 件を送信してよいか判定する（`Queue` なら空きが出るまで待つ）
+    pub fn admit(&self, rule: GlobalRateLimit) -> Admission {
+        loop {
+            if self.limiter.try_acquire(KEY, rule.limit) {
+                return match self.suppressed.swap(0, Ordering::Relaxed) {
+                    0 => Admission::Send,
+                    count => Admission::SendWithSummary(count),
+                };
+            }
+            match rule.overflow {
+                Overflow::Drop => return Admission::Dropped,
+                Overflow::Summarize => {
+                    self.suppressed.fetch_add(1, Ordering::Relaxed);
+                    return Admission::Dropped;
+                }
+                Overflow::Queue => {
+                    // 上限 0 でも待ち続けて回り続けないように、最低でも少し待つ
+                    let wait = self.limiter.time_until_available(KEY, rule.limit);
+                    self.clock.sleep(wait.max(Duration::from_millis(10)));
+                }
+            }
+        }
+    }
+
+    /// 次に送信できるまでの時間（今すぐ送れるなら 0。数えない）
+    pub fn time_until_available(&self, rule: GlobalRateLimit) -> Duration {
+        self.limiter.time_until_available(KEY, rule.limit)
+    }
+
+    /// `Summarize` で省略し、まだ知らせていない件数
+    pub fn suppressed(&self) -> u32 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+}
+
+/// プロセス全体で共有する `Throttle`（時計は呼ばれるたびに `clock::current()` を読む）
+pub(crate) fn global() -> &'static Throttle {
+    static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+    THROTTLE.get_or_init(|| Throttle::new(Arc::new(CurrentClock)))
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    fn throttle() -> (Arc<ManualClock>, Throttle) {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+        (clock.clone(), Throttle::new(clock))
+    }
+
+    #[test]
+    fn test_drop_and_summarize() {
+        let (clock, throttle) = throttle();
+        let rule = GlobalRateLimit::new(2, Duration::from_secs(60), Overflow::Drop);
+        assert_eq!(throttle.admit(rule), Admission::Send);
+        assert_eq!(throttle.admit(rule), Admission::Send);
+        assert_eq!(throttle.admit(rule), Admission::Dropped);
+
+        let rule = GlobalRateLimit {
+            overflow: Overflow::Summarize,
+            ..rule
+        };
+        assert_eq!(throttle.admit(rule), Admission::Dropped);
+        assert_eq!(throttle.admit(rule), Admission::Dropped);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(throttle.admit(rule), Admission::SendWithSummary(2));
+        assert_eq!(throttle.admit(rule), Admission::Send);
+    }
+
+    #[test]
+    fn test_queue_waits_for_a_slot() {
+        let (clock, throttle) = throttle();
+        let rule = GlobalRateLimit::new(1, Duration::from_secs(10), Overflow::Queue);
+        assert_eq!(throttle.admit(rule), Admission::Send);
+        let started = clock.now();
+        // ManualClock の sleep() は時計を進める
+        assert_eq!(throttle.admit(rule), Admission::Send);
+        assert_eq!(
+            clock.now().duration_since(started).unwrap(),
+            Duration::from_secs(10)
+        );
+    }
+}
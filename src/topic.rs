@@ -0,0 +1,256 @@
+//! トピック（名前空間）ごとのルール
+//!
+//! 通知に `ci/build` や `home/door` のようなトピックを付けると、
+//! `Config::topics` のルールでトピックごとのデフォルト（バックエンド・緊急度）と
+//! 送信数の上限を決められます。多くのスクリプトが 1 つの環境を共有しても、
+//! 一部の送信元が通知を溢れさせて他を埋もれさせることを防ぎます。
+//!
+//! | パターン | 一致するトピック |
+//! |----------|------------------|
+//! | `ci/build` | `ci/build` のみ |
+//! | `ci/*` | `ci/build`, `ci/deploy/prod` など `ci/` 以下の全て |
+//! | `*` | 全てのトピック |
+//!
+//! 複数のパターンに一致する場合は、完全一致 → より長いパターンの順に優先します。
+//!
+//! 送信数はプロセスの中だけで数えます（ファイルには残しません）。
+//! 上限が効くのは同じプロセスから送る通知だけで、通知のたびに起動する CLI 同士では数えません。
+//!
+//! # 例
+//! ```
+//! use std::time::Duration;
+//! use rust_toast::config::{self, Config};
+//! use rust_toast::topic::{RateLimit, TopicRule};
+//! use rust_toast::{Backend, UrgencyLevel};
+//!
+//! let mut config = Config::default();
+//! config.topics.insert(
+//!     "ci/*".to_string(),
+//!     TopicRule {
+//!         backend: Some(Backend::Console),
+//!         urgency: Some(UrgencyLevel::Low),
+//!         rate_limit: Some(RateLimit::new(10, Duration::from_secs(60))),
+//!     },
+//! );
+//! config::set(config);
+//! ```
+//!
+//! # 学習ポイント
+//! - `VecDeque` によるスライディングウィンドウ
+//! - 依存性の注入（`Clock`）で時間に依存する処理をテスト可能にする
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{Clock, CurrentClock};
+use crate::config::Config;
+use crate::error::{NotificationError, Result};
+use crate::notifier::{Backend, UrgencyLevel};
+
+/// トピックのルール
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicRule {
+    /// バックエンドの指定が無い通知に使うバックエンド
+    pub backend: Option<Backend>,
+    /// 緊急度の指定が無い通知に使う緊急度
+    pub urgency: Option<UrgencyLevel>,
+    /// 送信数の上限（パターンに一致するトピック全体で共有）
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// 一定時間あたりの送信数の上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// `per` の間に送信できる数
+    pub max: u32,
+    /// 数える期間
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// `per` あたり `max` 件までの上限を作成
+    pub fn new(max: u32, per: Duration) -> Self {
+        Self { max, per }
+    }
+}
+
+/// トピックがパターンに一致するかどうか
+pub fn matches(pattern: &str, topic: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some("") => true,
+        Some(prefix) if prefix.ends_with('/') => topic.starts_with(prefix),
+        _ => pattern == topic,
+    }
+}
+
+/// トピックに最も近いルールを (パターン, ルール) で返す
+pub fn find<'a>(
+    rules: &'a BTreeMap<String, TopicRule>,
+    topic: &str,
+) -> Option<(&'a str, &'a TopicRule)> {
+    rules
+        .iter()
+        .filter(|(pattern, _)| matches(pattern, topic))
+        .max_by_key(|(pattern, _)| (pattern.as_str() == topic, pattern.len()))
+        .map(|(pattern, rule)| (pattern.as_str(), rule))
+}
+
+// ============================================================
+// 送信数の制限
+// ============================================================
+
+/// パターンごとの送信時刻を記録し、上限を超えた送信を拒否する
+#[derive(Debug)]
+pub struct RateLimiter {
+    clock: Arc<dyn Clock>,
+    /// パターン → 期間内に送信した時刻（古い順）
+    sent: Mutex<HashMap<String, VecDeque<SystemTime>>>,
+}
+
+impl RateLimiter {
+    /// 時計を指定して作成
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 送信してよければ記録して `true`、上限に達していれば `false`
+    pub fn try_acquire(&self, key: &str, limit: RateLimit) -> bool {
+        let now = self.clock.now();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let window = sent.entry(key.to_string()).or_default();
+        prune(window, now, limit);
+
+        if window.len() >= limit.max as usize {
+            return false;
+        }
+        window.push_back(now);
+        true
+    }
+
+    /// 次に送信できるまでの時間（今すぐ送れるなら 0）
+    pub fn time_until_available(&self, key: &str, limit: RateLimit) -> Duration {
+        // 上限が 0 なら空くことはないので、期間の長さだけ待たせる
+        if limit.max == 0 {
+            return limit.per;
+        }
+        let now = self.clock.now();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(window) = sent.get_mut(key) else {
+            return Duration::ZERO;
+        };
+        prune(window, now, limit);
+
+        if window.len() < limit.max as usize {
+            return Duration::ZERO;
+        }
+        window.front().map_or(Duration::ZERO, |&oldest| {
+            (oldest + limit.per).duration_since(now).unwrap_or_default()
+        })
+    }
+}
+
+/// 期間外になった記録を捨てる
+fn prune(window: &mut VecDeque<SystemTime>, now: SystemTime, limit: RateLimit) {
+    while window
+        .front()
+        .is_some_and(|&at| now.duration_since(at).unwrap_or_default() >= limit.per)
+    {
+        window.pop_front();
+    }
+}
+
+/// プロセス全体で共有する `RateLimiter`（時計は呼ばれるたびに `clock::current()` を読む）
+fn global_limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(Arc::new(CurrentClock)))
+}
+
+/// トピックの送信数の上限に達しているかどうか（数えない。シミュレーション用）
+pub(crate) fn rate_limit_reached(pattern: &str, limit: RateLimit) -> bool {
+    !global_limiter()
+        .time_until_available(pattern, limit)
+        .is_zero()
+}
+
+/// トピックの送信数の上限を確認する（送信前に呼ぶ）
+///
+/// トピックが無い場合や、ルールに上限が無い場合は常に `Ok` です。
+pub(crate) fn check_rate_limit(config: &Config, topic: Option<&str>) -> Result<()> {
+    let Some(topic) = topic else {
+        return Ok(());
+    };
+    let Some((pattern, rule)) = find(&config.topics, topic) else {
+        return Ok(());
+    };
+    match rule.rate_limit {
+        Some(limit) if !global_limiter().try_acquire(pattern, limit) => {
+            Err(NotificationError::RateLimited {
+                topic: topic.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("ci/build", "ci/build"));
+        assert!(!matches("ci/build", "ci/build/1"));
+        assert!(matches("ci/*", "ci/deploy/prod"));
+        assert!(!matches("ci/*", "ci"));
+        assert!(!matches("ci/*", "cid/x"));
+        assert!(matches("*", "home/door"));
+    }
+
+    #[test]
+    fn test_find_prefers_most_specific() {
+        let rules: BTreeMap<String, TopicRule> = ["*", "ci/*", "ci/deploy/*", "ci/deploy/prod"]
+            .into_iter()
+            .map(|pattern| (pattern.to_string(), TopicRule::default()))
+            .collect();
+
+        assert_eq!(find(&rules, "ci/deploy/prod").unwrap().0, "ci/deploy/prod");
+        assert_eq!(find(&rules, "ci/deploy/dev").unwrap().0, "ci/deploy/*");
+        assert_eq!(find(&rules, "ci/build").unwrap().0, "ci/*");
+        assert_eq!(find(&rules, "home/door").unwrap().0, "*");
+        assert_eq!(find(&BTreeMap::new(), "home/door"), None);
+    }
+
+    #[test]
+    fn test_rate_limiter_sliding_window() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+        let limiter = RateLimiter::new(clock.clone());
+        let limit = RateLimit::new(2, Duration::from_secs(60));
+
+        assert!(limiter.try_acquire("ci/*", limit));
+        clock.advance(Duration::from_secs(30));
+        assert!(limiter.try_acquire("ci/*", limit));
+        assert!(!limiter.try_acquire("ci/*", limit));
+        // 他のパターンには影響しない
+        assert!(limiter.try_acquire("home/*", limit));
+
+        // 最初の送信から 60 秒経つと 1 件分空く
+        assert_eq!(
+            limiter.time_until_available("ci/*", limit),
+            Duration::from_secs(30)
+        );
+        assert_eq!(limiter.time_until_available("new/*", limit), Duration::ZERO);
+        clock.advance(Duration::from_secs(30));
+        assert!(limiter.try_acquire("ci/*", limit));
+        assert!(!limiter.try_acquire("ci/*", limit));
+    }
+}
@@ -0,0 +1,195 @@
+//! 分散トレースとの関連付け（トレース ID / スパン ID）
+//!
+//! 障害の通知から「どのリクエストで起きたのか」を監視基盤（Jaeger, Tempo など）で
+//! 辿れるように、通知を送った処理のトレース ID とスパン ID を添付します。
+//! 添付した値は `to_json()` の `"trace_context"` として、履歴や stdout バックエンドにも残ります。
+//!
+//! 値の受け渡しには W3C Trace Context の `traceparent` 形式を使います。
+//!
+//! ```text
+//! 00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01
+//! ^^ ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ ^^^^^^^^^^^^^^^^ ^^
+//! 版 トレース ID（16 バイト）          スパン ID（8 バイト） フラグ
+//! ```
+//!
+//! CLI は `--traceparent`、指定が無ければ環境変数 `TRACEPARENT` を使います。
+//!
+//! # 例
+//! ```
+//! use rust_toast::trace::TraceContext;
+//! use rust_toast::NotificationBuilder;
+//!
+//! let context: TraceContext = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+//!     .parse()
+//!     .unwrap();
+//! let notification = NotificationBuilder::new()
+//!     .message("Payment failed")
+//!     .trace_context(context)
+//!     .build();
+//! assert!(notification.to_json().contains("4bf92f3577b34da6a3ce929d0e0e4736"));
+//! ```
+//!
+//! # 学習ポイント
+//! - `u128::from_str_radix` による 16 進数のパース
+//! - `{:032x}` のようなゼロ埋めの書式指定
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::NotificationError;
+
+/// `--traceparent` が無い場合に CLI が読む環境変数
+pub const TRACEPARENT_ENV: &str = "TRACEPARENT";
+
+/// 通知を送った処理のトレース ID とスパン ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct TraceContext {
+    /// トレース ID（0 は無効）
+    pub trace_id: u128,
+    /// スパン ID（0 は無効）
+    pub span_id: u64,
+}
+
+impl TraceContext {
+    /// トレース ID とスパン ID から作成（OpenTelemetry などの数値の ID をそのまま渡せる）
+    pub fn new(trace_id: u128, span_id: u64) -> Self {
+        Self { trace_id, span_id }
+    }
+
+    /// トレース ID（32 桁の小文字 16 進数）
+    pub fn trace_id_hex(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    /// スパン ID（16 桁の小文字 16 進数）
+    pub fn span_id_hex(&self) -> String {
+        format!("{:016x}", self.span_id)
+    }
+
+    /// `to_json()` の `"trace_context"` の値
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "trace_id": self.trace_id_hex(),
+            "span_id": self.span_id_hex(),
+        })
+    }
+
+    /// 16 進数のトレース ID とスパン ID から作成（`to_json_value()` の読み込み用）
+    pub fn from_hex(trace_id: &str, span_id: &str) -> Result<Self, NotificationError> {
+        let trace_id = parse_hex(trace_id, 32).ok_or_else(|| invalid("trace id"))?;
+        let span_id = parse_hex(span_id, 16).ok_or_else(|| invalid("span id"))?;
+        if trace_id == 0 || span_id == 0 {
+            return Err(invalid("all-zero id"));
+        }
+        Ok(Self::new(trace_id, span_id as u64))
+    }
+
+    /// 環境変数 `TRACEPARENT` の値（無い・読めない場合は `None`）
+    pub fn from_env() -> Option<Self> {
+        std::env::var(TRACEPARENT_ENV).ok()?.parse().ok()
+    }
+}
+
+/// `traceparent` 形式（版 `00`、サンプリングのフラグ付き）
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+}
+
+/// `traceparent` 形式からの変換
+///
+/// 版 `ff` は無効、未知の版は後ろに続く項目を無視して読みます（仕様どおり）。
+impl FromStr for TraceContext {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self, NotificationError> {
+        let mut parts = s.trim().split('-');
+        let (Some(version), Some(trace_id), Some(span_id), Some(flags)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid("expected version-traceid-spanid-flags"));
+        };
+        if parse_hex(version, 2).is_none_or(|v| v == 0xff) || parse_hex(flags, 2).is_none() {
+            return Err(invalid("bad version or flags"));
+        }
+        if version == "00" && parts.next().is_some() {
+            return Err(invalid("unexpected fields after flags"));
+        }
+        Self::from_hex(trace_id, span_id)
+    }
+}
+
+impl From<TraceContext> for String {
+    fn from(context: TraceContext) -> Self {
+        context.to_string()
+    }
+}
+
+impl TryFrom<String> for TraceContext {
+    type Error = NotificationError;
+
+    fn try_from(s: String) -> Result<Self, NotificationError> {
+        s.parse()
+    }
+}
+
+/// ちょうど `digits` 桁の小文字 16 進数を読む（`from_str_radix` は `+` や大文字も受け付けるため）
+fn parse_hex(s: &str, digits: usize) -> Option<u128> {
+    let valid = s.len() == digits && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+    valid.then(|| u128::from_str_radix(s, 16).ok()).flatten()
+}
+
+fn invalid(reason: &str) -> NotificationError {
+    NotificationError::InvalidInput {
+        field: "traceparent".to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let context: TraceContext = TRACEPARENT.parse().unwrap();
+        assert_eq!(context.trace_id, 0x4bf92f3577b34da6a3ce929d0e0e4736);
+        assert_eq!(context.span_id_hex(), "00f067aa0ba902b7");
+        assert_eq!(context.to_string(), TRACEPARENT);
+        assert_eq!(
+            TraceContext::new(1, 2).to_string(),
+            "00-00000000000000000000000000000001-0000000000000002-01"
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_traceparent() {
+        for bad in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert!(bad.parse::<TraceContext>().is_err(), "{bad}");
+        }
+        // 未知の版は後ろの項目を無視する
+        assert!(format!("01{}-extra", &TRACEPARENT[2..])
+            .parse::<TraceContext>()
+            .is_ok());
+    }
+}
@@ -0,0 +1,412 @@
+//! ファイルの変更を監視して通知する（`rust-toast watch`）
+//!
+//! `notify` クレート（Linux: inotify / macOS: FSEvents / Windows: ReadDirectoryChangesW）で
+//! ファイルやディレクトリの変更を受け取り、`DEBOUNCE` の間に届いた変更をまとめて通知します。
+//!
+//! | 指定 | 通知 |
+//! |------|------|
+//! | パターンなし | 変更されたファイルの一覧（`MAX_LINES` 件まで） |
+//! | `--pattern` | 追記された行のうち、正規表現に一致したもの（ファイルごとに 1 通知） |
+//!
+//! ファイルを監視する場合は、エディタの保存やログのローテーション（置き換え）でも追えるよう、
+//! 親ディレクトリを監視してそのファイルの変更だけを拾います。
+//! 追記の読み取りはファイルごとに読み終えた位置を覚え、ファイルが短くなったら先頭から読み直します。
+//! 監視を始める前の内容は通知しません。1 回に読むのは追記の末尾 `MAX_READ_BYTES` までで、
+//! NUL を含むファイル（ビルドの成果物などのバイナリ）は読み飛ばします。
+//!
+//! # 学習ポイント
+//! - `mpsc::Receiver::recv_timeout()` による、短い間に続いたイベントのまとめ
+//! - `Seek` で前回の続きから読む（`tail -f` と同じ考え方）
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecursiveMode, Watcher};
+use regex::Regex;
+
+use crate::error::{NotificationError, Result};
+use crate::notifier::NotificationBuilder;
+use crate::text;
+
+/// 最初の変更からこの時間だけ待ち、その間の変更を 1 回にまとめる
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 1 つの通知に含める行（ファイル名・一致した行）の上限
+pub const MAX_LINES: usize = 10;
+
+/// 1 つのファイルの変更で読む追記の上限（バイト、超えたら古い側を読み飛ばす）
+pub const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+/// ファイルやディレクトリの監視
+pub struct Watch {
+    /// 監視する場所
+    root: PathBuf,
+    /// 追記された行の絞り込み（`None` なら変更されたファイルを通知）
+    pattern: Option<Regex>,
+    /// 通知のタイトル（`None` ならファイル名・ディレクトリ名から作る）
+    title: Option<String>,
+    /// 追記された行の読み取り位置
+    tail: Tail,
+}
+
+impl Watch {
+    /// 監視を準備する（パターンがあれば、既存のファイルの末尾を読み取り位置にする）
+    pub fn new(root: impl Into<PathBuf>, pattern: Option<Regex>, title: Option<String>) -> Self {
+        // イベントのパスは絶対パスで届くので、読み取り位置の記録と揃える
+        let root = root.into();
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+        let mut tail = Tail::default();
+        if pattern.is_some() {
+            tail.seed(&root);
+        }
+        Self {
+            root,
+            pattern,
+            title,
+            tail,
+        }
+    }
+
+    /// 変更を監視し、まとめた変更ごとに `send` を呼ぶ（監視できなくなるまで戻らない）
+    ///
+    /// 送信の失敗は警告だけにして、監視を続けます。
+    pub fn run(mut self, mut send: impl FnMut(NotificationBuilder) -> Result<()>) -> Result<()> {
+        let watch_error = |e: notify::Error| {
+            NotificationError::Other(format!("cannot watch {}: {e}", self.root.display()))
+        };
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(watch_error)?;
+        let single_file = self.root.is_file();
+        let (target, mode) = match self.root.parent() {
+            Some(parent) if single_file => (
+                if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                },
+                RecursiveMode::NonRecursive,
+            ),
+            _ => (self.root.as_path(), RecursiveMode::Recursive),
+        };
+        watcher.watch(target, mode).map_err(watch_error)?;
+
+        // 監視対象のファイル名（単一のファイルの場合は、親ディレクトリの他の変更を無視する）
+        let file_name = single_file.then(|| self.root.file_name().map(ToOwned::to_owned));
+        let relevant = |event: &notify::Event| -> Vec<PathBuf> {
+            // 自分で読んだ時のイベント（Access）で通知が繰り返されないよう無視する
+            if matches!(event.kind, EventKind::Access(_)) {
+                return Vec::new();
+            }
+            event
+                .paths
+                .iter()
+                .filter(|path| match &file_name {
+                    Some(name) => path.file_name() == name.as_deref(),
+                    None => true,
+                })
+                .cloned()
+                .collect()
+        };
+
+        let mut changed = BTreeSet::new();
+        let mut deadline: Option<Instant> = None;
+        loop {
+            let event = match deadline {
+                None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+                Some(deadline) => {
+                    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+            };
+            match event {
+                Ok(Ok(event)) => {
+                    let paths = relevant(&event);
+                    // 消えた・名前が変わった（ローテーション）ファイルは、次に現れたら先頭から読む
+                    if matches!(
+                        event.kind,
+                        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+                    ) {
+                        paths.iter().for_each(|path| self.tail.forget(path));
+                    }
+                    if !paths.is_empty() {
+                        changed.extend(paths);
+                        deadline.get_or_insert_with(|| Instant::now() + DEBOUNCE);
+                    }
+                }
+                Ok(Err(e)) => tracing::warn!("watch error: {e}"),
+                // 最初の変更から DEBOUNCE が過ぎたら、それまでの変更をまとめて通知する
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    for builder in self.handle(&std::mem::take(&mut changed)) {
+                        if let Err(e) = send(builder) {
+                            tracing::warn!("could not send the notification: {e}");
+                        }
+                    }
+                    deadline = None;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    /// まとめた変更から送る通知を作る
+    pub fn handle(&mut self, changed: &BTreeSet<PathBuf>) -> Vec<NotificationBuilder> {
+        let Some(pattern) = &self.pattern else {
+            return vec![self.changed_files(changed)];
+        };
+        let mut builders = Vec::new();
+        for path in changed {
+            if !path.is_file() {
+                self.tail.forget(path);
+                continue;
+            }
+            let lines = match self.tail.read_new_lines(path) {
+                Ok(lines) => lines,
+                Err(e) => {
+                    tracing::warn!("cannot read {}: {e}", path.display());
+                    continue;
+                }
+            };
+            let matched: Vec<String> = lines
+                .iter()
+                .map(|line| text::strip_ansi(line))
+                .filter(|line| pattern.is_match(line))
+                .collect();
+            if matched.is_empty() {
+                continue;
+            }
+            let title = self.title.clone().unwrap_or_else(|| display_name(path));
+            builders.push(
+                NotificationBuilder::new()
+                    .title(title)
+                    .message(summarize(&matched)),
+            );
+        }
+        builders
+    }
+
+    /// 変更されたファイルの一覧の通知
+    fn changed_files(&self, changed: &BTreeSet<PathBuf>) -> NotificationBuilder {
+        let title = self
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("Changed: {}", display_name(&self.root)));
+        let names: Vec<String> = changed
+            .iter()
+            .map(|path| {
+                let relative = path.strip_prefix(&self.root).unwrap_or(path);
+                let name = if relative.as_os_str().is_empty() {
+                    path
+                } else {
+                    relative
+                };
+                let removed = if path.exists() { "" } else { " (removed)" };
+                format!("{}{removed}", name.display())
+            })
+            .collect();
+        NotificationBuilder::new()
+            .title(title)
+            .message(summarize(&names))
+    }
+}
+
+/// 行を `MAX_LINES` 行までにまとめる（多ければ最新の行を残し、先頭に省略した数を書く）
+fn summarize(lines: &[String]) -> String {
+    if lines.len() <= MAX_LINES {
+        return lines.join("\n");
+    }
+    let skipped = lines.len() - MAX_LINES;
+    format!("… {skipped} more\n{}", lines[skipped..].join("\n"))
+}
+
+/// 通知に表示する名前（ファイル名、無ければパス全体）
+fn display_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
+
+/// 追記された行を読むための、ファイルごとの読み終えた位置
+#[derive(Debug, Default)]
+struct Tail {
+    offsets: HashMap<PathBuf, u64>,
+}
+
+impl Tail {
+    /// 既存のファイル（ディレクトリなら配下の全て）の末尾を読み取り位置にする
+    ///
+    /// シンボリックリンクのディレクトリはたどりません（親へのリンクで再帰が終わらなくなるため）。
+    fn seed(&mut self, path: &Path) {
+        if path.is_file() {
+            if let Ok(metadata) = path.metadata() {
+                self.offsets.insert(path.to_path_buf(), metadata.len());
+            }
+        } else if let Ok(entries) = path.read_dir() {
+            for entry in entries.flatten() {
+                // file_type() はリンク先を見ないので、リンクのディレクトリは is_dir() にならない
+                let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+                let path = entry.path();
+                if is_dir || path.is_file() {
+                    self.seed(&path);
+                }
+            }
+        }
+    }
+
+    /// 消えたファイルの読み取り位置を忘れる（同じ名前で作り直されたら先頭から読む）
+    fn forget(&mut self, path: &Path) {
+        self.offsets.remove(path);
+    }
+
+    /// 前回の続きから、改行で終わった行だけを読む
+    ///
+    /// 書きかけの行は次回に回し、ファイルが前回より短くなっていたら先頭から読み直します。
+    /// 追記が `MAX_READ_BYTES` より多ければ末尾だけを読み（途中から始まる最初の行は捨てる）、
+    /// NUL を含む（テキストでない）場合は何も返さずに末尾まで読んだことにします。
+    fn read_new_lines(&mut self, path: &Path) -> io::Result<Vec<String>> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let offset = self.offsets.get(path).copied().unwrap_or(0);
+        let offset = if len < offset { 0 } else { offset };
+        let start = offset.max(len.saturating_sub(MAX_READ_BYTES));
+        file.seek(SeekFrom::Start(start))?;
+        let mut buf = Vec::new();
+        file.take(len - start).read_to_end(&mut buf)?;
+        if buf.contains(&0) {
+            self.offsets.insert(path.to_path_buf(), len);
+            return Ok(Vec::new());
+        }
+
+        let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let first = if start > offset {
+            buf[..complete]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(0, |i| i + 1)
+        } else {
+            0
+        };
+        self.offsets
+            .insert(path.to_path_buf(), start + complete as u64);
+        Ok(String::from_utf8_lossy(&buf[first..complete])
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect())
+    }
+}
+
+// ============================================================
+// テスト
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_pattern_matches_appended_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        append(&log, "ERROR before watching\n");
+
+        let pattern = Regex::new("ERROR").unwrap();
+        let mut watch = Watch::new(dir.path(), Some(pattern), None);
+        let changed = BTreeSet::from([log.clone()]);
+
+        // 書きかけの行は、改行が来るまで待つ
+        append(
+            &log,
+            "INFO started\nERROR disk \x1b[31mfull\x1b[0m\nERROR half",
+        );
+        let builders = watch.handle(&changed);
+        assert_eq!(builders.len(), 1);
+        let notification = builders[0].clone().build();
+        assert_eq!(notification.title, "app.log");
+        assert_eq!(notification.message, "ERROR disk full");
+
+        append(&log, " line\n");
+        let notification = watch.handle(&changed)[0].clone().build();
+        assert_eq!(notification.message, "ERROR half line");
+
+        // ローテーションで短くなったら先頭から読む
+        std::fs::write(&log, "ERROR rotated\n").unwrap();
+        let notification = watch.handle(&changed)[0].clone().build();
+        assert_eq!(notification.message, "ERROR rotated");
+
+        append(&log, "INFO fine\n");
+        assert!(watch.handle(&changed).is_empty());
+    }
+
+    #[test]
+    fn test_skips_binary_and_reads_only_the_latest_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let pattern = Regex::new("ERROR").unwrap();
+        let mut watch = Watch::new(dir.path(), Some(pattern), None);
+
+        let binary = dir.path().join("app.o");
+        std::fs::write(&binary, b"ERROR\0\x7fELF\n").unwrap();
+        assert!(watch.handle(&BTreeSet::from([binary])).is_empty());
+
+        // 上限を超えた追記は古い側を読み飛ばし、途中から始まる行は通知しない
+        let log = dir.path().join("big.log");
+        let filler = "x".repeat(MAX_READ_BYTES as usize);
+        append(&log, &format!("ERROR old\nERROR cut {filler}\nERROR new\n"));
+        let builders = watch.handle(&BTreeSet::from([log]));
+        assert_eq!(builders[0].clone().build().message, "ERROR new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_seed_does_not_follow_directory_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        append(&log, "ERROR before watching\n");
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+
+        let watch = Watch::new(dir.path(), Some(Regex::new("ERROR").unwrap()), None);
+        assert_eq!(watch.tail.offsets.len(), 1);
+    }
+
+    #[test]
+    fn test_changed_files_without_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let built = dir.path().join("target").join("app");
+        std::fs::create_dir_all(built.parent().unwrap()).unwrap();
+        std::fs::write(&built, "bin").unwrap();
+        let gone = dir.path().join("old.o");
+
+        let mut watch = Watch::new(dir.path(), None, None);
+        let builders = watch.handle(&BTreeSet::from([built, gone]));
+        let notification = builders[0].clone().build();
+        assert!(notification.title.starts_with("Changed: "));
+        assert_eq!(
+            notification.message,
+            format!(
+                "old.o (removed)\n{}",
+                Path::new("target").join("app").display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_summarize_keeps_latest_lines() {
+        let lines: Vec<String> = (1..=12).map(|i| i.to_string()).collect();
+        let text = summarize(&lines);
+        assert!(text.starts_with("… 2 more\n3\n"), "{text}");
+        assert!(text.ends_with("\n12"), "{text}");
+    }
+}